@@ -0,0 +1,101 @@
+use cbor_diag::{
+    check_well_formed, parse_bytes, ByteString, DataItem, FloatWidth, IntegerWidth, Simple,
+    TextString,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// A single 1 MiB byte string.
+fn large_bytestring() -> DataItem {
+    DataItem::ByteString(ByteString {
+        data: (0..1 << 20).map(|i| i as u8).collect(),
+        bitwidth: IntegerWidth::Unknown,
+        encoding: None,
+    })
+}
+
+/// A map with `len` small integer to text entries.
+fn map(len: u64) -> DataItem {
+    DataItem::Map {
+        data: (0..len)
+            .map(|i| {
+                (
+                    DataItem::Integer {
+                        value: i,
+                        bitwidth: IntegerWidth::Unknown,
+                    },
+                    DataItem::TextString(TextString {
+                        data: format!("value {}", i),
+                        bitwidth: IntegerWidth::Unknown,
+                    }),
+                )
+            })
+            .collect(),
+        bitwidth: Some(IntegerWidth::Unknown),
+    }
+}
+
+/// 200 levels of arrays, each with a few scalars beside the next level.
+fn deep_nesting() -> DataItem {
+    (0..200).fold(DataItem::Simple(Simple::NULL), |inner, i| DataItem::Array {
+        data: vec![
+            DataItem::Integer {
+                value: i,
+                bitwidth: IntegerWidth::Unknown,
+            },
+            DataItem::Float {
+                value: 1.5,
+                bitwidth: FloatWidth::Unknown,
+            },
+            inner,
+        ],
+        bitwidth: Some(IntegerWidth::Unknown),
+    })
+}
+
+fn messages() -> Vec<(&'static str, DataItem)> {
+    vec![
+        ("large_bytestring", large_bytestring()),
+        ("many_entries", map(10_000)),
+        ("deep_nesting", deep_nesting()),
+    ]
+}
+
+fn encode(c: &mut Criterion) {
+    for (name, item) in messages() {
+        let bytes = item.to_bytes();
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function("to_hex", |b| b.iter(|| item.to_hex()));
+        group.bench_function("to_diag_pretty", |b| b.iter(|| item.to_diag_pretty()));
+        group.bench_function("to_bytes", |b| b.iter(|| item.to_bytes()));
+        group.bench_function("parse_bytes", |b| b.iter(|| parse_bytes(&bytes).unwrap()));
+        group.bench_function("check_well_formed", |b| {
+            b.iter(|| check_well_formed(&bytes).unwrap())
+        });
+        group.finish();
+    }
+}
+
+fn pretty(c: &mut Criterion) {
+    let item = map(100_000);
+    c.bench_function("large_map/to_diag_pretty", |b| {
+        b.iter(|| item.to_diag_pretty())
+    });
+
+    // Filled onto lines rather than one per line
+    let item = DataItem::Array {
+        data: (0..100_000)
+            .map(|value| DataItem::Integer {
+                value,
+                bitwidth: IntegerWidth::Unknown,
+            })
+            .collect(),
+        bitwidth: Some(IntegerWidth::Unknown),
+    };
+    c.bench_function("large_array/to_diag_pretty", |b| {
+        b.iter(|| item.to_diag_pretty())
+    });
+}
+
+criterion_group!(benches, encode, pretty);
+criterion_main!(benches);
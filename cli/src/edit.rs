@@ -0,0 +1,341 @@
+use std::convert::TryFrom;
+
+use cbor_diag::{DataItem, IntegerWidth, TextString};
+
+use crate::error::{Error, Result};
+
+/// A step along a path into an item, tags are looked through so they don't need steps of their
+/// own.
+#[derive(Debug)]
+enum Step {
+    /// `.N`, an array index or an integer map key
+    Number(i128),
+    /// `.name` for a text key, or `.` followed by any key in diagnostic notation
+    Key(DataItem),
+    /// `[N]`, an array index
+    Index(usize),
+    /// `[-]`, the end of an array
+    End,
+}
+
+impl Step {
+    fn index(&self) -> Option<usize> {
+        match *self {
+            Step::Number(number) => usize::try_from(number).ok(),
+            Step::Index(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn matches_key(&self, key: &DataItem) -> bool {
+        match self {
+            Step::Number(number) => key.as_i128() == Some(*number),
+            // Compare the canonical encodings so that widths don't matter
+            Step::Key(step) => step.canonical_key() == key.canonical_key(),
+            _ => false,
+        }
+    }
+
+    /// The map key to add for this step.
+    fn key(&self) -> Option<DataItem> {
+        match self {
+            Step::Number(number) if *number >= 0 => Some(DataItem::Integer {
+                value: u64::try_from(*number).ok()?,
+                bitwidth: IntegerWidth::Unknown,
+            }),
+            Step::Number(number) => Some(DataItem::Negative {
+                value: u64::try_from(-1 - *number).ok()?,
+                bitwidth: IntegerWidth::Unknown,
+            }),
+            Step::Key(key) => Some(key.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// A path to an item, like `.1."kid"` or `.items[-]`.
+struct Path<'a> {
+    text: &'a str,
+    steps: Vec<Step>,
+    /// Where each step ends in `text`
+    ends: Vec<usize>,
+}
+
+impl<'a> Path<'a> {
+    /// Parse the path at the start of `text`, returning it and the text following it.
+    fn parse(text: &'a str) -> Result<(Self, &'a str)> {
+        let invalid = |rest: &str| {
+            Error::other(format!(
+                "invalid path {:?} at offset {}",
+                text,
+                text.len() - rest.len()
+            ))
+        };
+
+        let mut path = Path {
+            text,
+            steps: Vec::new(),
+            ends: Vec::new(),
+        };
+        let mut rest = text;
+        // `.` alone is the whole item
+        if let Some(after) = rest.strip_prefix('.') {
+            if after.is_empty() || after.starts_with('=') {
+                path.text = &text[..1];
+                return Ok((path, after));
+            }
+        }
+        loop {
+            let (step, after) = if let Some(after) = rest.strip_prefix('[') {
+                let (index, after) = after.split_once(']').ok_or_else(|| invalid(rest))?;
+                match index {
+                    "-" => (Step::End, after),
+                    _ => (
+                        Step::Index(index.parse().map_err(|_| invalid(rest))?),
+                        after,
+                    ),
+                }
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let len = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(after.len());
+                let (word, following) = after.split_at(len);
+                let ends_step = following.is_empty() || following.starts_with(&['.', '[', '='][..]);
+                if let Ok(number) = word.parse() {
+                    (Step::Number(number), following)
+                } else if ends_step
+                    && word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                {
+                    let key = DataItem::TextString(TextString {
+                        data: word.to_owned(),
+                        bitwidth: IntegerWidth::Unknown,
+                    });
+                    (Step::Key(key), following)
+                } else {
+                    match cbor_diag::parse_diag_partial(after) {
+                        Ok(Some((key, len))) => (Step::Key(key), &after[len..]),
+                        _ => return Err(invalid(rest)),
+                    }
+                }
+            } else if path.steps.is_empty() {
+                return Err(invalid(rest));
+            } else {
+                return Ok((path, rest));
+            };
+            path.steps.push(step);
+            rest = after;
+            path.ends.push(text.len() - rest.len());
+            path.text = &text[..text.len() - rest.len()];
+        }
+    }
+
+    /// The text of the first `len` steps.
+    fn prefix(&self, len: usize) -> &str {
+        match len {
+            0 => ".",
+            _ => &self.text[..self.ends[len - 1]],
+        }
+    }
+
+    /// The error for the item at this path not existing, where only the first `len` steps could
+    /// be followed, to an item that `reason` describes the problem with.
+    fn missing(&self, len: usize, reason: String) -> Error {
+        Error::other(format!(
+            "no item at {}, found {} but {}",
+            self.text,
+            self.prefix(len),
+            reason
+        ))
+    }
+
+    /// The item containing the last step of this path, failing if it doesn't exist.
+    fn parent<'b>(&self, item: &'b mut DataItem) -> Result<(&'b mut DataItem, &Step)> {
+        let (last, steps) = match self.steps.split_last() {
+            Some(split) => split,
+            None => unreachable!("the root has no parent"),
+        };
+        let mut parent = item;
+        for (depth, step) in steps.iter().enumerate() {
+            parent = match child(parent, step) {
+                Ok(child) => child,
+                Err(parent) => return Err(self.missing(depth, reason(parent, step))),
+            };
+        }
+        Ok((untagged(parent), last))
+    }
+}
+
+fn untagged(item: &mut DataItem) -> &mut DataItem {
+    match item {
+        DataItem::Tag { value, .. } => untagged(value),
+        item => item,
+    }
+}
+
+/// Why a step can't be taken from `item`.
+fn reason(item: &DataItem, step: &Step) -> String {
+    match item {
+        DataItem::Tag { value, .. } => reason(value, step),
+        DataItem::Array { data, .. } => array_reason(data),
+        DataItem::Map { .. } => map_reason(step),
+        _ => "it is not an array or map".to_owned(),
+    }
+}
+
+fn array_reason(data: &[DataItem]) -> String {
+    match data.len() {
+        1 => "it has 1 item".to_owned(),
+        len => format!("it has {} items", len),
+    }
+}
+
+fn map_reason(step: &Step) -> String {
+    match step.key() {
+        Some(key) => format!("it has no key {}", key.to_diag()),
+        None => "it is a map".to_owned(),
+    }
+}
+
+/// The item reached by taking `step` from `item`, or `item` back if there is none.
+fn child<'a>(
+    item: &'a mut DataItem,
+    step: &Step,
+) -> std::result::Result<&'a mut DataItem, &'a DataItem> {
+    let item = untagged(item);
+    let found = match item {
+        DataItem::Array { data, .. } => step.index().filter(|&index| index < data.len()),
+        DataItem::Map { data, .. } => data.iter().position(|(key, _)| step.matches_key(key)),
+        _ => None,
+    };
+    match (item, found) {
+        (DataItem::Array { data, .. }, Some(index)) => Ok(&mut data[index]),
+        (DataItem::Map { data, .. }, Some(index)) => Ok(&mut data[index].1),
+        (item, _) => Err(item),
+    }
+}
+
+/// What to do at a path.
+enum Operation {
+    /// Replace the item, or add it as a new entry to a map or at the end of an array
+    Set(DataItem),
+    /// Add the item as a new entry to a map, or within an array before the index
+    Insert(DataItem),
+    /// Remove the item, or the map entry it's the value of
+    Delete,
+}
+
+/// Apply `operation` at `path` within `item`.
+fn operate(item: &mut DataItem, path: &Path<'_>, operation: Operation) -> Result<()> {
+    if path.steps.is_empty() {
+        return match operation {
+            Operation::Set(value) => {
+                *item = value;
+                Ok(())
+            }
+            _ => Err(Error::other("can't insert or delete the whole item")),
+        };
+    }
+    let depth = path.steps.len() - 1;
+    let (parent, step) = path.parent(item)?;
+    match (parent, operation) {
+        (DataItem::Array { data, .. }, Operation::Set(value)) => match step {
+            Step::End => data.push(value),
+            step => match step.index().and_then(|index| data.get_mut(index)) {
+                Some(item) => *item = value,
+                None => return Err(path.missing(depth, array_reason(data))),
+            },
+        },
+        (DataItem::Array { data, .. }, Operation::Insert(value)) => match step {
+            Step::End => data.push(value),
+            step => match step.index().filter(|&index| index <= data.len()) {
+                Some(index) => data.insert(index, value),
+                None => return Err(path.missing(depth, array_reason(data))),
+            },
+        },
+        (DataItem::Array { data, .. }, Operation::Delete) => {
+            match step.index().filter(|&index| index < data.len()) {
+                Some(index) => drop(data.remove(index)),
+                None => return Err(path.missing(depth, array_reason(data))),
+            }
+        }
+        (DataItem::Map { data, .. }, operation) => {
+            let found = data.iter().position(|(key, _)| step.matches_key(key));
+            match (found, operation) {
+                (Some(index), Operation::Set(value)) => data[index].1 = value,
+                (Some(_), Operation::Insert(_)) => {
+                    return Err(Error::other(format!(
+                        "can't insert at {}, the key already exists",
+                        path.text
+                    )))
+                }
+                (Some(index), Operation::Delete) => drop(data.remove(index)),
+                (None, Operation::Set(value)) | (None, Operation::Insert(value)) => {
+                    match step.key() {
+                        Some(key) => data.push((key, value)),
+                        None => return Err(path.missing(depth, map_reason(step))),
+                    }
+                }
+                (None, Operation::Delete) => return Err(path.missing(depth, map_reason(step))),
+            }
+        }
+        (parent, _) => return Err(path.missing(depth, reason(parent, step))),
+    }
+    Ok(())
+}
+
+/// Parse a `PATH=VALUE` argument, with the value in diagnostic notation.
+fn path_and_value<'a>(
+    arg: &'a str,
+    parse_options: &cbor_diag::ParseOptions,
+) -> Result<(Path<'a>, DataItem)> {
+    let (path, rest) = Path::parse(arg)?;
+    let value = rest
+        .strip_prefix('=')
+        .ok_or_else(|| Error::other(format!("expected PATH=VALUE, found {:?}", arg)))?;
+    let value = cbor_diag::parse_diag_with(value, parse_options)
+        .map_err(|err| Error::other(format!("invalid value for {}: {}", path.text, err)))?;
+    Ok((path, value))
+}
+
+/// Which option of `edit` an operation was given with.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Kind {
+    /// `--set PATH=VALUE`
+    Set,
+    /// `--insert PATH=VALUE`
+    Insert,
+    /// `--delete PATH`
+    Delete,
+}
+
+/// Apply the operations of `edit` to `item`, in the order they were given.
+pub(crate) fn apply(
+    item: &mut DataItem,
+    edit: &crate::Edit,
+    parse_options: &cbor_diag::ParseOptions,
+) -> Result<()> {
+    for (kind, arg) in &edit.operations {
+        match kind {
+            Kind::Set => {
+                let (path, value) = path_and_value(arg, parse_options)?;
+                operate(item, &path, Operation::Set(value))?;
+            }
+            Kind::Insert => {
+                let (path, value) = path_and_value(arg, parse_options)?;
+                operate(item, &path, Operation::Insert(value))?;
+            }
+            Kind::Delete => {
+                let (path, rest) = Path::parse(arg)?;
+                if !rest.is_empty() {
+                    return Err(Error::other(format!(
+                        "invalid path {:?} at offset {}",
+                        arg,
+                        arg.len() - rest.len()
+                    )));
+                }
+                operate(item, &path, Operation::Delete)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::{fmt, io};
+
+/// The kinds of failure that are distinguished by exit status.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Kind {
+    /// Anything not covered by another kind, such as unsupported combinations of options or
+    /// items that can't be encoded in the output format
+    Other,
+    /// The input is not a valid item in the expected format
+    Parse,
+    /// A valid item was followed by more data
+    TrailingData,
+    /// Textual input is not valid UTF-8
+    InvalidUtf8,
+    /// Reading the input or writing the output failed
+    Io,
+    /// The item doesn't match the `--cddl` schema
+    Invalid,
+    /// The output was cut off at `--max-output-bytes`
+    Truncated,
+}
+
+impl Kind {
+    pub(crate) fn exit_code(self) -> i32 {
+        match self {
+            Kind::Other => 1,
+            Kind::Parse => 2,
+            Kind::TrailingData => 3,
+            Kind::InvalidUtf8 => 4,
+            Kind::Io => 5,
+            Kind::Invalid => 6,
+            Kind::Truncated => 7,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Other => "other",
+            Kind::Parse => "parse",
+            Kind::TrailingData => "trailing_data",
+            Kind::InvalidUtf8 => "invalid_utf8",
+            Kind::Io => "io",
+            Kind::Invalid => "invalid",
+            Kind::Truncated => "truncated",
+        }
+    }
+}
+
+/// A failure, with as much detail about where it happened as is known.
+#[derive(Debug)]
+pub(crate) struct Error {
+    pub(crate) kind: Kind,
+    pub(crate) message: String,
+    /// The byte offset in the (decoded, for hex) binary input
+    pub(crate) offset: Option<usize>,
+    /// The line of the input, for `--lines`
+    pub(crate) line: Option<usize>,
+    /// How many items were output before failing, for `--seq`
+    pub(crate) items: Option<usize>,
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn new(kind: Kind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            offset: None,
+            line: None,
+            items: None,
+        }
+    }
+
+    pub(crate) fn other(message: impl Into<String>) -> Self {
+        Self::new(Kind::Other, message)
+    }
+
+    pub(crate) fn at_offset(self, offset: Option<usize>) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        Self {
+            line: Some(line),
+            ..self
+        }
+    }
+
+    pub(crate) fn after_items(self, items: usize) -> Self {
+        Self {
+            items: Some(items),
+            ..self
+        }
+    }
+
+    /// A single line JSON object describing this error.
+    pub(crate) fn to_json(&self) -> String {
+        fn number(value: Option<usize>) -> String {
+            value.map_or_else(|| "null".to_owned(), |value| value.to_string())
+        }
+
+        format!(
+            r#"{{"kind":"{}","code":{},"message":{},"offset":{},"line":{},"items":{}}}"#,
+            self.kind.name(),
+            self.kind.exit_code(),
+            json_string(&self.message),
+            number(self.offset),
+            number(self.line),
+            number(self.items),
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        let truncated = err
+            .get_ref()
+            .is_some_and(|inner| inner.is::<crate::limit::OutputTruncated>());
+        if truncated {
+            Self::new(Kind::Truncated, err.to_string())
+        } else {
+            Self::new(Kind::Io, err.to_string())
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::new(
+            Kind::InvalidUtf8,
+            format!("input is not valid UTF-8: {}", err),
+        )
+        .at_offset(Some(err.valid_up_to()))
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        err.utf8_error().into()
+    }
+}
+
+/// Errors from the library are all problems with the input, apart from failures reading or
+/// writing streams, and encoding failures which are converted explicitly.
+impl From<cbor_diag::Error> for Error {
+    fn from(err: cbor_diag::Error) -> Self {
+        match err {
+            cbor_diag::Error::Io(err) => err.into(),
+            cbor_diag::Error::TrailingData { offset, .. } => {
+                Self::new(Kind::TrailingData, err.to_string()).at_offset(Some(offset))
+            }
+            cbor_diag::Error::LimitExceeded { offset, .. } => {
+                Self::new(Kind::Parse, err.to_string()).at_offset(Some(offset))
+            }
+            err => Self::new(Kind::Parse, err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// The error returned once output has been cut off by [`LimitedOutput`].
+#[derive(Debug)]
+pub(crate) struct OutputTruncated(usize);
+
+impl fmt::Display for OutputTruncated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "output truncated after {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for OutputTruncated {}
+
+/// Passes writes through to `inner` until `limit` bytes have been written, then writes a notice
+/// and fails every write after with an [`OutputTruncated`] error.
+pub(crate) struct LimitedOutput<W> {
+    inner: W,
+    limit: Option<usize>,
+    written: usize,
+    truncated: bool,
+    /// Whether the last byte written ended a line
+    at_line_start: bool,
+}
+
+impl<W: Write> LimitedOutput<W> {
+    pub(crate) fn new(inner: W, limit: Option<usize>) -> Self {
+        Self {
+            inner,
+            limit,
+            written: 0,
+            truncated: false,
+            at_line_start: true,
+        }
+    }
+
+    fn error(&self) -> io::Error {
+        io::Error::other(OutputTruncated(self.written))
+    }
+}
+
+impl<W: Write> Write for LimitedOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return self.inner.write(buf),
+        };
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.truncated {
+            return Err(self.error());
+        }
+        let remaining = limit - self.written;
+        if buf.len() <= remaining {
+            let len = self.inner.write(buf)?;
+            self.written += len;
+            self.at_line_start = buf[..len]
+                .last()
+                .map_or(self.at_line_start, |&b| b == b'\n');
+            return Ok(len);
+        }
+        self.inner.write_all(&buf[..remaining])?;
+        self.written = limit;
+        if remaining > 0 {
+            self.at_line_start = buf[remaining - 1] == b'\n';
+        }
+        if !self.at_line_start {
+            self.inner.write_all(b"\n")?;
+        }
+        self.inner.write_all(b"... output truncated ...\n")?;
+        self.inner.flush()?;
+        self.truncated = true;
+        Err(self.error())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,7 +1,14 @@
-use anyhow::anyhow;
-use std::io::{self, Read, Write};
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read, Write};
 use strum::VariantNames;
 
+mod edit;
+mod error;
+mod limit;
+mod terminal;
+
+use self::error::{Error, Kind, Result};
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, strum::EnumString, strum::EnumVariantNames)]
 #[strum(serialize_all = "snake_case")]
 enum From {
@@ -19,25 +26,617 @@ enum To {
     Bytes,
     Diag,
     Compact,
+    Rust,
+    C,
+    Python,
+    Tree,
+    Dot,
+    Html,
+    Stats,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, strum::EnumString, strum::EnumVariantNames)]
+#[strum(serialize_all = "snake_case")]
+enum BytesEncoding {
+    Base16,
+    Base64,
+    Base64url,
+    Base32,
+    Base32hex,
+    Text,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, strum::EnumString, strum::EnumVariantNames)]
+#[strum(serialize_all = "snake_case")]
+enum Expand {
+    Packed,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, strum::EnumString, strum::EnumVariantNames)]
+#[strum(serialize_all = "snake_case")]
+enum ErrorFormat {
+    Human,
+    Json,
 }
 
 #[derive(Debug, structopt::StructOpt)]
 #[structopt(name = "cbor-diag", setting = structopt::clap::AppSettings::ColoredHelp)]
 /// A utility for converting between binary, diagnostic, hex and annotated hex
 /// formats for CBOR.
+///
+/// Exits with status 1 for general failures, 2 for input that can't be parsed,
+/// 3 for trailing data after an item, 4 for textual input that isn't valid
+/// UTF-8, 5 for I/O errors, and 6 for items that don't match the `--cddl`
+/// schema.
 struct Args {
-    /// What format to attempt to parse the input as
-    #[structopt(long, default_value = "auto", possible_values(From::VARIANTS))]
+    /// What format to attempt to parse the input as, `hex` also accepts C and Rust arrays of
+    /// bytes like `{ 0xa1, 0x01, 0x02 }`
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values(From::VARIANTS),
+        global = true
+    )]
     from: From,
 
     /// What format to output
-    #[structopt(long, default_value = "diag", possible_values(To::VARIANTS))]
+    #[structopt(
+        long,
+        default_value = "diag",
+        possible_values(To::VARIANTS),
+        global = true
+    )]
     to: To,
 
-    /// Parse a series of undelimited CBOR data items in binary format (a.k.a. the `cbor-seq` data
-    /// type).
-    #[structopt(long, conflicts_with("from"))]
+    /// With `--to bytes`, write the binary output even when stdout is a terminal, instead of
+    /// showing it as hex with a warning
+    #[structopt(long)]
+    raw: bool,
+
+    /// Parse a series of undelimited CBOR data items (a.k.a. the `cbor-seq` data type), in binary
+    /// format unless `--from hex` for concatenated hex items or `--from diag` for whitespace or
+    /// comment separated diagnostic notation items.
+    #[structopt(long)]
     seq: bool,
+
+    /// With `--seq`, collect all the items and output them as a single array
+    #[structopt(long, requires("seq"))]
+    seq_as_array: bool,
+
+    /// Treat a break (0xff) following the item in binary or hex input as the end of the data,
+    /// with `--seq` ending the sequence
+    #[structopt(long)]
+    stop_at_break: bool,
+
+    /// Output the elements of an input array as separate items, a CBOR sequence for `--to bytes`
+    #[structopt(long, conflicts_with_all(&["seq", "lenient", "lines", "expect"]))]
+    split_array: bool,
+
+    /// What to write between items when outputting a sequence in a textual format, supports the
+    /// escapes `\n`, `\r`, `\t`, `\0` and `\\` (ignored for `--to bytes`, which always outputs
+    /// the exact concatenated items)
+    #[structopt(long, default_value = "\\n", parse(try_from_str = unescape))]
+    delimiter: String,
+
+    /// For `--to annotated`, limit how many lines of content are shown for each byte string,
+    /// eliding the middle of longer byte strings
+    #[structopt(long)]
+    max_bytes_lines: Option<usize>,
+
+    /// Stop writing output after this many bytes, ending it with `... output truncated ...` and
+    /// exiting with status 7, for safely converting untrusted input that could expand to a huge
+    /// output
+    #[structopt(long)]
+    max_output_bytes: Option<usize>,
+
+    /// For `--to annotated`, `--to diag` and `--to compact`, limit how many chunks are shown for
+    /// each indefinite length string, eliding (for `annotated`) or joining (for `diag` and
+    /// `compact`) the chunks of strings with more
+    #[structopt(long)]
+    max_chunks: Option<usize>,
+
+    /// For `--to hex`, put a space after every this many bytes; for `--to annotated`, after every
+    /// this many bytes of string content on a line
+    #[structopt(long, default_value = "0")]
+    hex_group: usize,
+
+    /// For `--to hex`, use uppercase hex digits
+    #[structopt(long)]
+    hex_upper: bool,
+
+    /// For `--to hex`, text to put before each group of bytes, such as `0x`
+    #[structopt(long, default_value = "")]
+    hex_prefix: String,
+
+    /// For `--to annotated`, how many bytes of string content to show per line
+    #[structopt(long, default_value = "16")]
+    hex_width: usize,
+
+    /// For `--to annotated`, don't show the ASCII rendering of byte string content
+    #[structopt(long)]
+    no_ascii: bool,
+
+    /// For `--to annotated`, don't put a space between the initial byte of each header and its
+    /// argument, e.g. `190018` rather than `19 0018`
+    #[structopt(long)]
+    no_split_headers: bool,
+
+    /// For `--to annotated`, the furthest right column to align comments to, lines with longer
+    /// hex have their comment directly after it
+    #[structopt(long)]
+    max_comment_column: Option<usize>,
+
+    /// For `--to annotated`, only show comments describing the structure of the data, not the
+    /// meaning of tagged values
+    #[structopt(long)]
+    no_semantic_comments: bool,
+
+    /// For `--to annotated`, don't mark items that are encoded wider than needed or with an
+    /// indefinite length
+    #[structopt(long)]
+    no_flag_non_preferred: bool,
+
+    /// For `--to annotated` of a binary input file, read the file a piece at a time instead of
+    /// all at once, for files too large to fit in memory; done automatically for files over 16
+    /// MiB, with a note that warnings were not checked. Text strings that aren't valid UTF-8 are
+    /// always shown as bytes, as with `--allow-invalid-utf8`, and no warnings are reported
+    #[structopt(
+        long,
+        conflicts_with_all(&["seq", "lenient", "lines", "expect", "split-array", "in-place"])
+    )]
+    streaming: bool,
+
+    /// Only check that the binary input is well-formed CBOR, without parsing it into items,
+    /// outputting nothing and exiting with status 2 if it isn't; with `--seq` the input can be a
+    /// sequence of any number of items
+    #[structopt(
+        long,
+        conflicts_with_all(&["lenient", "lines", "expect", "split-array", "in-place", "streaming"])
+    )]
+    check_well_formed: bool,
+
+    /// When parsing binary or hex input, accept text strings containing invalid UTF-8 instead of
+    /// failing, showing their raw bytes
+    #[structopt(long)]
+    allow_invalid_utf8: bool,
+
+    /// When parsing diagnostic notation, keep application-oriented literals with unknown prefixes
+    /// like `foo'...'` instead of failing (they can only be output as diagnostic notation)
+    #[structopt(long)]
+    allow_unknown_app_literals: bool,
+
+    /// Fail if a well-known tag from RFC 8949 has content of the wrong type, such as `0(5)` where
+    /// tag 0 requires a text string
+    #[structopt(long)]
+    strict_tags: bool,
+
+    /// For `--to diag` and `--to compact`, escape all non-ASCII characters in text strings
+    #[structopt(long)]
+    escape_non_ascii: bool,
+
+    /// For `--to diag` and `--to compact`, how to show byte strings that don't have an encoding
+    /// from their literal form in diagnostic input or an enclosing tag (`text` falls back to
+    /// `base16` for content that is not valid UTF-8)
+    #[structopt(
+        long,
+        default_value = "base16",
+        possible_values(BytesEncoding::VARIANTS)
+    )]
+    bytes_encoding: BytesEncoding,
+
+    /// For `--to diag` and `--to compact`, show byte strings that are printable UTF-8 text as
+    /// `'...'`
+    #[structopt(long)]
+    prefer_text_bytes: bool,
+
+    /// For `--to diag` and `--to compact`, follow rational numbers (tag 30) with a comment giving
+    /// their approximate decimal value
+    #[structopt(long)]
+    rational_approximations: bool,
+
+    /// For `--to rust`, `--to c` and `--to python`, how many bytes to put on each line (`0` for a
+    /// single line)
+    #[structopt(long, default_value = "16")]
+    codegen_width: usize,
+
+    /// For `--to rust`, `--to c` and `--to python`, declare a variable with this name holding the
+    /// bytes
+    #[structopt(long)]
+    codegen_name: Option<String>,
+
+    /// For `--to tree` and `--to dot`, how many characters of each value to show before
+    /// truncating it (`0` to show them in full)
+    #[structopt(long, default_value = "40")]
+    tree_width: usize,
+
+    /// For `--to stats`, how many of the largest subtrees to list
+    #[structopt(long, default_value = "10")]
+    stats_top: usize,
+
+    /// For `--to diag` and `--to compact`, show map entries sorted by their deterministic key
+    /// order, so that maps differing only in ordering show the same (the item itself is unchanged)
+    #[structopt(long)]
+    sort_map_keys: bool,
+
+    /// For `--to diag` and `--to compact`, show indefinite length strings with their chunks
+    /// joined (the item itself is unchanged)
+    #[structopt(long)]
+    normalize_indefinite: bool,
+
+    /// For `--to diag` and `--to compact`, output plain diagnostic notation without the `_0` to
+    /// `_3` width and `_` indefinite length indicators, joining the chunks of indefinite length
+    /// strings
+    #[structopt(long)]
+    no_encoding_indicators: bool,
+
+    /// For `--to diag`, don't follow the last item of containers wrapped over multiple lines with
+    /// a comma, for tools that don't accept them
+    #[structopt(long)]
+    no_trailing_commas: bool,
+
+    /// For `--to diag`, the width to fill lines to with the items of long arrays of numbers
+    #[structopt(long, default_value = "100")]
+    max_line_width: usize,
+
+    /// Output the deterministic encoding of the item from RFC 8949 § 4.2.1, with the shortest
+    /// widths, definite lengths and sorted map keys
+    #[structopt(long)]
+    canonical: bool,
+
+    /// For `--to annotated`, `--to diag` and `--to compact`, name integer map keys using the JSON
+    /// object in this file, e.g. `{"1": "alg", "4": "kid"}`
+    #[structopt(long, parse(from_os_str))]
+    key_names: Option<std::path::PathBuf>,
+
+    /// The contents of the `--key-names` file, loaded before running
+    #[structopt(skip)]
+    key_name_map: std::collections::BTreeMap<i128, String>,
+
+    /// Expand encodings that compress the item before outputting it, `packed` replaces packed
+    /// CBOR (tags 113 and 1113) with the unpacked item (can be repeated)
+    #[structopt(long, number_of_values = 1, possible_values(Expand::VARIANTS))]
+    expand: Vec<Expand>,
+
+    /// Redact the value at this path of map keys in each item before outputting it (can be
+    /// repeated), keys are separated by `.`, numbers also match array indexes and `*` matches any
+    /// key or index, e.g. `--redact 'users.*.password'`
+    #[structopt(long, number_of_values = 1, allow_hyphen_values = true)]
+    redact: Vec<String>,
+
+    /// With `--redact`, replace values with an empty byte string instead of a placeholder with
+    /// the same size and structure
+    #[structopt(long)]
+    redact_empty: bool,
+
+    /// Check each item against the first rule of the CDDL schema in this file before outputting
+    /// it, only a basic subset of CDDL is supported (see the `cbor_diag::cddl` docs)
+    #[structopt(long, parse(from_os_str))]
+    cddl: Option<std::path::PathBuf>,
+
+    /// The contents of the `--cddl` file, loaded before running
+    #[structopt(skip)]
+    cddl_schema: Option<String>,
+
+    /// When parsing binary or hex input, output as much as can be decoded from truncated or
+    /// malformed data instead of failing, with warnings describing the problems written to stderr
+    /// (exits with status 2 if there were any)
+    #[structopt(long, conflicts_with("seq"))]
+    lenient: bool,
+
+    /// Don't write warnings about the input to stderr, such as well-known tags with content of the
+    /// wrong type or duplicate map keys
+    #[structopt(long)]
+    quiet: bool,
+
+    /// Fail with status 2 instead of outputting the item if parsing the input gave any warnings
+    #[structopt(long)]
+    deny_warnings: bool,
+
+    /// Parse each non-empty line of the input as a separate hex or diagnostic notation item,
+    /// outputting each as soon as it is read; lines that fail to parse are reported on stderr and
+    /// skipped (exits with the status for the first of these at the end of the input)
+    #[structopt(long, conflicts_with_all(&["seq", "lenient"]))]
+    lines: bool,
+
+    /// How to write errors to stderr, `json` writes a single line object with the `kind` of error,
+    /// its exit `code`, a `message`, and the byte `offset` in the input, `line` for `--lines` and
+    /// count of `items` output for `--seq` when known
+    #[structopt(long, default_value = "human", possible_values(ErrorFormat::VARIANTS))]
+    error_format: ErrorFormat,
+
+    /// Instead of outputting the input, compare it to the item in this file, printing a diff of
+    /// their diagnostic notation and exiting with status 1 if they differ
+    #[structopt(long, conflicts_with_all(&["seq", "lenient", "lines"]), parse(from_os_str))]
+    expect: Option<std::path::PathBuf>,
+
+    /// What format to attempt to parse the `--expect` file as
+    #[structopt(long, default_value = "auto", possible_values(From::VARIANTS))]
+    expect_from: From,
+
+    /// For `--expect`, also require integer, length and tag widths to match, i.e. the items
+    /// must have the same binary encoding
+    #[structopt(long, requires("expect"))]
+    strict: bool,
+
+    /// Instead of reading stdin and writing stdout, rewrite each of the files in place with the
+    /// `--to` format (each is written to a temporary file then renamed over the original)
+    #[structopt(
+        long,
+        requires("files"),
+        conflicts_with_all(&["seq", "lenient", "lines", "expect", "split-array"])
+    )]
+    in_place: bool,
+
+    /// With `--in-place`, don't change any files, only list those that would change and exit with
+    /// status 1 if there are any
+    #[structopt(long, requires("in-place"))]
+    check: bool,
+
+    /// Use this as the input instead of reading stdin, e.g. `cbor-diag --data a16568656c6c6f01`
+    #[structopt(long, conflicts_with_all(&["files", "in-place"]))]
+    data: Option<String>,
+
+    /// A file to read the input from instead of stdin, or if it doesn't exist the input itself;
+    /// with `--in-place` any number of files to rewrite, each is processed independently so a
+    /// failure is reported and the rest are still rewritten
+    #[structopt(parse(from_os_str))]
+    files: Vec<std::path::PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, structopt::StructOpt)]
+enum Command {
+    /// Output a random item, the same each time for the same seed and options
+    Generate(Generate),
+    /// Change the items at paths within the input item, then output it; when the input and
+    /// output are both binary or hex, everything that wasn't changed keeps its exact encoding
+    Edit(Edit),
+}
+
+#[derive(Debug, structopt::StructOpt)]
+struct Generate {
+    /// The seed for the random number generator
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+
+    /// How deeply nested containers and tags can be
+    #[structopt(long, default_value = "4")]
+    max_depth: usize,
+
+    /// The maximum number of items in arrays, entries in maps and chunks in indefinite length
+    /// strings
+    #[structopt(long, default_value = "8")]
+    max_len: usize,
+
+    /// The maximum length of strings in bytes
+    #[structopt(long, default_value = "16")]
+    max_string_len: usize,
+
+    /// Only generate items of this major type, 0 to 7 (can be repeated)
+    #[structopt(long, number_of_values = 1)]
+    major_type: Vec<u8>,
+
+    /// Only generate this tag (can be repeated)
+    #[structopt(long, number_of_values = 1)]
+    tag: Vec<u64>,
+
+    /// Sometimes encode integers, lengths and tags wider than needed
+    #[structopt(long)]
+    non_preferred: bool,
+
+    /// Sometimes use indefinite length arrays, maps and strings
+    #[structopt(long)]
+    indefinite: bool,
+
+    /// Randomize the values of the item read from the input, keeping its structure, tags and map
+    /// keys
+    #[structopt(long)]
+    template: bool,
+}
+
+/// The operations of `cbor-diag edit`, applied in the order they are given.
+///
+/// Paths are a sequence of steps from the whole item `.`: `.N` is an array index or integer map
+/// key, `.name` a text map key, `.` followed by any other map key in diagnostic notation, such as
+/// `."a.b"` or `.h'01'`, `[N]` an array index and `[-]` the end of an array. Tags are looked
+/// through, so `.0` is the first item of `18([1, 2])`.
+#[derive(Debug, structopt::StructOpt)]
+struct EditArgs {
+    /// Replace the item at a path with a value in diagnostic notation, `PATH=VALUE`, adding it
+    /// if the last step is a missing map key or `[-]` (can be repeated)
+    #[structopt(long, number_of_values = 1, allow_hyphen_values = true)]
+    set: Vec<String>,
+
+    /// Add a value in diagnostic notation to an array before the index, or to a map with a new
+    /// key, `PATH=VALUE` (can be repeated)
+    #[structopt(long, number_of_values = 1, allow_hyphen_values = true)]
+    insert: Vec<String>,
+
+    /// Remove the item at a path from its array, or its entry from its map (can be repeated)
+    #[structopt(long, number_of_values = 1, allow_hyphen_values = true)]
+    delete: Vec<String>,
+}
+
+/// The `edit` operations in the order they were given on the command line, which structopt only
+/// keeps within each option.
+#[derive(Debug)]
+struct Edit {
+    operations: Vec<(edit::Kind, String)>,
+}
+
+impl structopt::StructOpt for Edit {
+    fn clap<'a, 'b>() -> structopt::clap::App<'a, 'b> {
+        <EditArgs as structopt::StructOpt>::clap()
+    }
+
+    fn from_clap(matches: &structopt::clap::ArgMatches<'_>) -> Self {
+        let EditArgs {
+            set,
+            insert,
+            delete,
+        } = <EditArgs as structopt::StructOpt>::from_clap(matches);
+        let mut operations = Vec::new();
+        for (kind, name, values) in [
+            (edit::Kind::Set, "set", set),
+            (edit::Kind::Insert, "insert", insert),
+            (edit::Kind::Delete, "delete", delete),
+        ] {
+            let indices = matches.indices_of(name).into_iter().flatten();
+            operations.extend(
+                indices
+                    .zip(values)
+                    .map(|(index, value)| (index, kind, value)),
+            );
+        }
+        operations.sort_by_key(|&(index, _, _)| index);
+        Edit {
+            operations: operations
+                .into_iter()
+                .map(|(_, kind, value)| (kind, value))
+                .collect(),
+        }
+    }
+}
+
+impl structopt::StructOptInternal for Edit {
+    fn augment_clap<'a, 'b>(app: structopt::clap::App<'a, 'b>) -> structopt::clap::App<'a, 'b> {
+        <EditArgs as structopt::StructOptInternal>::augment_clap(app)
+    }
+}
+
+impl Generate {
+    fn options(&self) -> Result<cbor_diag::GenerateOptions> {
+        if let Some(major) = self.major_type.iter().find(|&&major| major > 7) {
+            return Err(Error::other(format!("invalid major type {}", major)));
+        }
+        Ok(cbor_diag::GenerateOptions {
+            max_depth: self.max_depth,
+            max_container_len: self.max_len,
+            max_string_len: self.max_string_len,
+            major_types: if self.major_type.is_empty() {
+                (0..=7).collect()
+            } else {
+                self.major_type.clone()
+            },
+            tags: self.tag.iter().copied().map(cbor_diag::Tag).collect(),
+            non_preferred_widths: self.non_preferred,
+            indefinite: self.indefinite,
+        })
+    }
+}
+
+impl Args {
+    fn hex_options(&self) -> cbor_diag::HexOptions {
+        cbor_diag::HexOptions {
+            bytes_per_line: self.hex_width,
+            show_ascii: !self.no_ascii,
+            max_bytestring_lines: self.max_bytes_lines,
+            max_chunks: self.max_chunks,
+            max_comment_column: self.max_comment_column,
+            semantic_comments: !self.no_semantic_comments,
+            flag_non_preferred: !self.no_flag_non_preferred,
+            key_names: self.key_name_map.clone(),
+            group: self.hex_group,
+            split_headers: !self.no_split_headers,
+        }
+    }
+
+    fn bytes_hex_options(&self) -> cbor_diag::BytesHexOptions {
+        cbor_diag::BytesHexOptions {
+            group: self.hex_group,
+            uppercase: self.hex_upper,
+            prefix: self.hex_prefix.clone(),
+        }
+    }
+
+    fn diag_options(&self) -> cbor_diag::DiagFormatOptions {
+        cbor_diag::DiagFormatOptions {
+            escape_non_ascii: self.escape_non_ascii,
+            bytestring_encoding: match self.bytes_encoding {
+                BytesEncoding::Base16 => cbor_diag::Encoding::Base16,
+                BytesEncoding::Base64 => cbor_diag::Encoding::Base64,
+                BytesEncoding::Base64url => cbor_diag::Encoding::Base64Url,
+                BytesEncoding::Base32 => cbor_diag::Encoding::Base32,
+                BytesEncoding::Base32hex => cbor_diag::Encoding::Base32Hex,
+                BytesEncoding::Text => cbor_diag::Encoding::Text,
+            },
+            prefer_text_bytestrings: self.prefer_text_bytes,
+            rational_approximations: self.rational_approximations,
+            key_names: self.key_name_map.clone(),
+            sort_map_keys: self.sort_map_keys,
+            normalize_indefinite: self.normalize_indefinite,
+            max_chunks: self.max_chunks,
+            emit_encoding_indicators: !self.no_encoding_indicators,
+            trailing_commas: !self.no_trailing_commas,
+            max_line_width: self.max_line_width,
+        }
+    }
+
+    fn codegen_options(&self) -> cbor_diag::CodegenOptions {
+        cbor_diag::CodegenOptions {
+            bytes_per_line: self.codegen_width,
+            name: self.codegen_name.clone(),
+        }
+    }
+
+    fn tree_options(&self) -> cbor_diag::TreeOptions {
+        cbor_diag::TreeOptions {
+            max_value_width: self.tree_width,
+        }
+    }
+
+    /// Where to read the input from, when not rewriting files `--in-place`.
+    fn input(&self) -> Result<Input<'_>> {
+        if let Some(data) = &self.data {
+            return Ok(Input::Data(data));
+        }
+        match self.files.as_slice() {
+            [] => Ok(Input::Stdin),
+            [path] if path.exists() => Ok(Input::File(path)),
+            [data] => data.to_str().map(Input::Data).ok_or_else(|| {
+                Error::new(
+                    Kind::InvalidUtf8,
+                    "input argument is not an existing file or valid UTF-8",
+                )
+            }),
+            _ => Err(Error::other(
+                "only one input file can be given without --in-place",
+            )),
+        }
+    }
+
+    fn parse_options(&self) -> cbor_diag::ParseOptions {
+        cbor_diag::ParseOptions {
+            allow_invalid_utf8: self.allow_invalid_utf8,
+            allow_unknown_app_literals: self.allow_unknown_app_literals,
+            strict_tag_content: self.strict_tags,
+            stop_at_stray_break: self.stop_at_break,
+            ..Default::default()
+        }
+    }
+}
+
+fn unescape(s: &str) -> std::result::Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            result.push(match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some(c) => return Err(format!("unknown escape sequence '\\{}'", c)),
+                None => return Err("incomplete escape sequence at end of delimiter".into()),
+            });
+        } else {
+            result.push(c);
+        }
+    }
+    Ok(result)
 }
 
 trait ReadExt: Read {
@@ -52,85 +651,1338 @@ trait ReadExt: Read {
 
 impl<R: Read> ReadExt for R {}
 
-fn output_item(value: cbor_diag::DataItem, to: To, mut output: impl Write) -> anyhow::Result<()> {
-    match to {
+/// Output `value` in the `--to` format, textual formats are followed by a single newline.
+fn output_item(value: &cbor_diag::DataItem, args: &Args, mut output: impl Write) -> Result<()> {
+    write_item(value, args, &mut output)?;
+    if args.to != To::Bytes {
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Passes writes through to `inner`, except for a newline at the very end which is dropped.
+///
+/// A newline is held back until something more is written, so this streams rather than buffering
+/// the whole output.
+struct WithoutFinalNewline<W> {
+    inner: W,
+    /// Whether a newline has been held back
+    pending: bool,
+}
+
+impl<W: Write> WithoutFinalNewline<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: false,
+        }
+    }
+}
+
+impl<W: Write> Write for WithoutFinalNewline<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pending {
+            self.inner.write_all(b"\n")?;
+            self.pending = false;
+        }
+        match buf.split_last() {
+            Some((b'\n', rest)) => {
+                self.inner.write_all(rest)?;
+                self.pending = true;
+            }
+            _ => self.inner.write_all(buf)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Output `value` in the `--to` format without any trailing newline.
+fn write_item(value: &cbor_diag::DataItem, args: &Args, mut output: impl Write) -> Result<()> {
+    let expanded;
+    let value = if args.expand.contains(&Expand::Packed) {
+        expanded = value.expand_packed()?;
+        &expanded
+    } else {
+        value
+    };
+    if let Some(schema) = &args.cddl_schema {
+        validate(value, schema, args)?;
+    }
+    let redacted;
+    let value = if args.redact.is_empty() {
+        value
+    } else {
+        redacted = redact(value, args);
+        &redacted
+    };
+    // Failing to encode is a problem with the item, not the input
+    let encode_error = |err: cbor_diag::Error| Error::other(err.to_string());
+    let canonical;
+    let value = if args.canonical {
+        let options = cbor_diag::ParseOptions {
+            allow_invalid_utf8: true,
+            ..Default::default()
+        };
+        canonical =
+            cbor_diag::parse_bytes_with(value.canonical_key(), &options).map_err(encode_error)?;
+        &canonical
+    } else {
+        value
+    };
+    let to_bytes = || value.try_to_bytes().map_err(encode_error);
+    match args.to {
         To::Annotated => {
-            output.write_all(value.to_hex().as_bytes())?;
+            let mut output = WithoutFinalNewline::new(&mut output);
+            value.write_hex(&args.hex_options(), &mut output)?;
         }
         To::Hex => {
-            output.write_all(hex::encode(value.to_bytes()).as_bytes())?;
+            let hex = value.to_hex_string_with(&args.bytes_hex_options());
+            output.write_all(hex.map_err(encode_error)?.as_bytes())?;
         }
         To::Bytes => {
-            output.write_all(&value.to_bytes())?;
+            output.write_all(&to_bytes()?)?;
         }
         To::Diag => {
-            output.write_all(value.to_diag_pretty().as_bytes())?;
-            output.write_all(b"\n")?;
+            output.write_all(value.to_diag_pretty_with(&args.diag_options()).as_bytes())?;
         }
         To::Compact => {
-            output.write_all(value.to_diag().as_bytes())?;
-            output.write_all(b"\n")?;
+            output.write_all(value.to_diag_with(&args.diag_options()).as_bytes())?;
+        }
+        To::Rust => {
+            let source = value.to_rust_literal(&args.codegen_options());
+            output.write_all(source.map_err(encode_error)?.as_bytes())?;
+        }
+        To::C => {
+            let source = value.to_c_literal(&args.codegen_options());
+            output.write_all(source.map_err(encode_error)?.as_bytes())?;
+        }
+        To::Python => {
+            let source = value.to_python_literal(&args.codegen_options());
+            output.write_all(source.map_err(encode_error)?.as_bytes())?;
+        }
+        To::Tree => {
+            output.write_all(value.to_tree_with(&args.tree_options()).as_bytes())?;
+        }
+        To::Dot => {
+            output.write_all(value.to_dot_with(&args.tree_options()).as_bytes())?;
+        }
+        To::Html => {
+            let options = cbor_diag::HtmlOptions {
+                hex: args.hex_options(),
+                ..Default::default()
+            };
+            let html = value.to_html(&options);
+            output.write_all(html.strip_suffix('\n').unwrap_or(&html).as_bytes())?;
+        }
+        To::Stats => {
+            output.write_all(stats(value, args.stats_top).as_bytes())?;
         }
     };
 
     Ok(())
 }
 
-#[paw::main]
-fn main(args: Args) -> anyhow::Result<()> {
-    let input = std::io::stdin();
-    let mut input = input.lock();
+/// A table of where the bytes of `value` are used, listing the `top` largest subtrees.
+fn stats(value: &cbor_diag::DataItem, top: usize) -> String {
+    const MAJOR_TYPES: [&str; 8] = [
+        "unsigned",
+        "negative",
+        "bytes",
+        "text",
+        "array",
+        "map",
+        "tag",
+        "simple/float",
+    ];
 
-    let output = std::io::stdout();
-    let mut output = output.lock();
+    let report = value.size_report();
+    let total = report.total();
+    let percent = |bytes: usize| 100.0 * bytes as f64 / total.max(1) as f64;
 
-    if args.seq {
-        let mut data = Default::default();
+    let mut lines = vec![
+        format!(
+            "total: {} bytes ({} header, {} payload)",
+            total,
+            report.major_types.values().map(|t| t.header).sum::<usize>(),
+            report
+                .major_types
+                .values()
+                .map(|t| t.payload)
+                .sum::<usize>(),
+        ),
+        String::new(),
+        "largest subtrees:".into(),
+        "   bytes  header  payload        %  path".into(),
+    ];
+    for subtree in report.largest(top) {
+        let path = if subtree.path.is_empty() {
+            "root".into()
+        } else {
+            format!("{:?}", subtree.path)
+        };
+        lines.push(format!(
+            "{:>8} {:>7} {:>8} {:>7.1}%  {}",
+            subtree.total(),
+            subtree.header,
+            subtree.payload,
+            percent(subtree.total()),
+            path
+        ));
+    }
 
-        while input.read_to_vec(&mut data)? {
-            while let Some((value, len)) = cbor_diag::parse_bytes_partial(&data)? {
-                output_item(value, args.to, &mut output)?;
-                if args.to != To::Bytes && args.to != To::Compact {
-                    output.write_all(b"\n")?;
-                }
-                data.drain(..len);
+    lines.push(String::new());
+    lines.push("by major type:".into());
+    lines.push("  type             count  header  payload        %".into());
+    for (&major, totals) in &report.major_types {
+        lines.push(format!(
+            "  {} {:<13} {:>6} {:>7} {:>8} {:>7.1}%",
+            major,
+            MAJOR_TYPES[usize::from(major)],
+            totals.count,
+            totals.header,
+            totals.payload,
+            percent(totals.total()),
+        ));
+    }
+
+    if !report.tags.is_empty() {
+        lines.push(String::new());
+        lines.push("by tag (including tagged items):".into());
+        lines.push("  tag              count   bytes        %".into());
+        for (&tag, totals) in &report.tags {
+            lines.push(format!(
+                "  {:<15} {:>6} {:>7} {:>7.1}%",
+                tag,
+                totals.count,
+                totals.total(),
+                percent(totals.total()),
+            ));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// The item at `path` within `item`, following the same indexing as [`cbor_diag::cddl`]
+/// violations.
+fn item_at<'a>(item: &'a cbor_diag::DataItem, path: &[usize]) -> Option<&'a cbor_diag::DataItem> {
+    use cbor_diag::DataItem;
+
+    match (item, path) {
+        (item, []) => Some(item),
+        (DataItem::Array { data, .. }, [index, rest @ ..]) => item_at(data.get(*index)?, rest),
+        (DataItem::Map { data, .. }, [index, 0, rest @ ..]) => item_at(&data.get(*index)?.0, rest),
+        (DataItem::Map { data, .. }, [index, 1, rest @ ..]) => item_at(&data.get(*index)?.1, rest),
+        (DataItem::Tag { value, .. }, [0, rest @ ..]) => item_at(value, rest),
+        _ => None,
+    }
+}
+
+/// A step into an item along a path, as matched by `--redact`.
+enum Step<'a> {
+    Index(usize),
+    Key(&'a cbor_diag::DataItem),
+}
+
+/// The steps taken to reach the item at `path` within `item`, if it is not a map key.
+fn steps<'a>(mut item: &'a cbor_diag::DataItem, mut path: &[usize]) -> Option<Vec<Step<'a>>> {
+    use cbor_diag::DataItem;
+
+    let mut steps = Vec::new();
+    loop {
+        match (item, path) {
+            (_, []) => return Some(steps),
+            (DataItem::Array { data, .. }, [index, rest @ ..]) => {
+                steps.push(Step::Index(*index));
+                item = data.get(*index)?;
+                path = rest;
             }
+            (DataItem::Map { data, .. }, [index, 1, rest @ ..]) => {
+                let (key, value) = data.get(*index)?;
+                steps.push(Step::Key(key));
+                item = value;
+                path = rest;
+            }
+            // Tags are skipped over, so that keypaths can ignore them
+            (DataItem::Tag { value, .. }, [0, rest @ ..]) => {
+                item = value;
+                path = rest;
+            }
+            _ => return None,
         }
+    }
+}
 
-        if !data.is_empty() {
-            return Err(anyhow!("{} bytes remaining after last item", data.len()));
+fn step_matches(segment: &str, step: &Step<'_>) -> bool {
+    use cbor_diag::DataItem;
+
+    match step {
+        _ if segment == "*" => true,
+        Step::Index(index) => segment.parse() == Ok(*index),
+        Step::Key(DataItem::Integer { value, .. }) => segment.parse() == Ok(i128::from(*value)),
+        Step::Key(DataItem::Negative { value, .. }) => {
+            segment.parse() == Ok(-1 - i128::from(*value))
         }
-    } else {
-        let data = {
-            let mut data = Default::default();
-            input.read_to_end(&mut data)?;
-            data
+        Step::Key(DataItem::TextString(text)) => segment == text.data,
+        Step::Key(_) => false,
+    }
+}
+
+/// Replace the values at the `--redact` keypaths within `value`.
+fn redact(value: &cbor_diag::DataItem, args: &Args) -> cbor_diag::DataItem {
+    use cbor_diag::{ByteString, DataItem, IntegerWidth, Redaction};
+
+    let keypaths: Vec<Vec<&str>> = args
+        .redact
+        .iter()
+        .map(|keypath| keypath.split('.').collect())
+        .collect();
+
+    let mut redacted = value.clone();
+    // Nothing is removed, so the paths within `redacted` are the same as in `value`
+    redacted.redact(|path, item| {
+        let steps = match steps(value, path) {
+            Some(steps) => steps,
+            None => return Redaction::Keep,
         };
+        let matches = keypaths.iter().any(|keypath| {
+            keypath.len() == steps.len()
+                && keypath
+                    .iter()
+                    .zip(&steps)
+                    .all(|(segment, step)| step_matches(segment, step))
+        });
+        if !matches {
+            Redaction::Keep
+        } else if args.redact_empty {
+            Redaction::Replace(DataItem::ByteString(ByteString {
+                data: Vec::new(),
+                bitwidth: IntegerWidth::Unknown,
+                encoding: None,
+            }))
+        } else {
+            Redaction::Replace(item.to_placeholder())
+        }
+    });
+    redacted
+}
+
+/// Check `value` against a `--cddl` schema, describing each violation along with the part of
+/// the item it refers to.
+fn validate(value: &cbor_diag::DataItem, schema: &str, args: &Args) -> Result<()> {
+    const SNIPPET_LEN: usize = 40;
+
+    let violations = match cbor_diag::cddl::validate(value, schema) {
+        Ok(()) => return Ok(()),
+        Err(violations) => violations,
+    };
+
+    let mut message = String::from("item does not match the CDDL schema");
+    for violation in violations {
+        if let cbor_diag::cddl::ViolationKind::InvalidSchema(_) = violation.kind {
+            return Err(Error::other(violation.to_string()));
+        }
+        message.push_str(&format!("\n  {}", violation));
+        if let Some(item) = item_at(value, &violation.path) {
+            let diag = item.to_diag_with(&args.diag_options());
+            match diag.char_indices().nth(SNIPPET_LEN) {
+                Some((end, _)) => message.push_str(&format!(", found {}…", &diag[..end])),
+                None => message.push_str(&format!(", found {}", diag)),
+            }
+        }
+    }
+    Err(Error::new(Kind::Invalid, message))
+}
+
+/// Where parsing `data` as binary fails, from the first problem found when parsing leniently.
+fn bytes_offset(data: &[u8]) -> Option<usize> {
+    Some(cbor_diag::parse_bytes_lenient(data).1.first()?.offset)
+}
+
+/// Describe an error from parsing `data` as binary, detailing any trailing data.
+fn bytes_error(err: cbor_diag::Error, data: &[u8]) -> Error {
+    match err {
+        cbor_diag::Error::TrailingData {
+            offset, remaining, ..
+        } => {
+            let trailing = &data[offset..];
+            let preview = &trailing[..trailing.len().min(8)];
+            let mut message = format!(
+                "parsed 1 item ({} bytes), {} trailing bytes starting with 0x{}{}",
+                offset,
+                remaining,
+                hex::encode(preview),
+                if preview.len() < trailing.len() {
+                    "…"
+                } else {
+                    ""
+                },
+            );
+            if let Ok(Some(_)) = cbor_diag::parse_bytes_partial(trailing) {
+                message.push_str(" (the trailing bytes start another item, use --seq to parse a sequence of items)");
+            }
+            Error::new(Kind::TrailingData, message).at_offset(Some(offset))
+        }
+        err => Error::from(err).at_offset(bytes_offset(data)),
+    }
+}
+
+/// Describe an error from parsing `text` as hex, with the offset in the decoded binary.
+fn hex_error(err: cbor_diag::Error, text: &str) -> Error {
+    let offset = match &err {
+        cbor_diag::Error::TrailingData { .. } => None,
+        _ => cbor_diag::parse_hex_lenient(text)
+            .ok()
+            .and_then(|(_, warnings)| Some(warnings.first()?.offset)),
+    };
+    match Error::from(err) {
+        err if err.offset.is_some() => err,
+        err => err.at_offset(offset),
+    }
+}
 
+/// Remove ANSI escape sequences from `text`, such as the colors in terminal output copied back in as
+/// input. Only CSI sequences (`ESC [ ... final`) are removed, any other escape is left for the
+/// parser to reject.
+fn strip_ansi(text: &str) -> Cow<'_, str> {
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b[") {
+        stripped.push_str(&rest[..start]);
+        let sequence = &rest[start + 2..];
+        // Parameter and intermediate bytes, then a single final byte
+        let params = sequence
+            .find(|c: char| !('\x20'..='\x3f').contains(&c))
+            .unwrap_or(sequence.len());
+        match sequence[params..].chars().next() {
+            Some(c) if ('\x40'..='\x7e').contains(&c) => rest = &sequence[params + 1..],
+            _ => {
+                stripped.push_str(&rest[start..start + 2]);
+                rest = sequence;
+            }
+        }
+    }
+    stripped.push_str(rest);
+    Cow::Owned(stripped)
+}
+
+/// Whether `text` could only have been meant as plain hex: hex digits and whitespace, with an even
+/// number of digits.
+fn looks_like_hex(text: &str) -> bool {
+    let mut digits = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if !c.is_ascii_hexdigit() {
+            return false;
+        }
+        digits += 1;
+    }
+    digits > 0 && digits % 2 == 0
+}
+
+/// Add to `err` from parsing an input argument that no file exists at it, if it looks like it was
+/// meant as a path, such as `dir/item` or `item.cbor`.
+fn missing_file(args: &Args, input: &Input<'_>, err: Error) -> Error {
+    let text = match input {
+        Input::Data(text) if args.data.is_none() => text,
+        _ => return err,
+    };
+    let extension = text.rsplit_once('.').is_some_and(|(name, extension)| {
+        !name.is_empty()
+            && !extension.is_empty()
+            && extension.chars().all(|c| c.is_ascii_alphanumeric())
+            && extension.chars().any(|c| c.is_ascii_alphabetic())
+    });
+    let path = text.contains('/') || text.contains(std::path::MAIN_SEPARATOR) || extension;
+    if !path || text.trim() != *text {
+        return err;
+    }
+    Error {
+        message: format!(
+            "no file exists at {}, and parsing it as data failed: {}",
+            text, err.message
+        ),
+        ..err
+    }
+}
+
+/// Parse `text` as hex or diagnostic notation, ignoring surrounding whitespace, returning the error
+/// from hex if it [looks like hex](looks_like_hex) or else from diagnostic notation if neither
+/// work.
+fn parse_text(
+    text: &str,
+    parse_options: &cbor_diag::ParseOptions,
+) -> cbor_diag::Result<cbor_diag::ParseReport> {
+    let text = strip_ansi(text);
+    let text = text.trim();
+    cbor_diag::parse_hex_report(text, parse_options).or_else(|hex_err| {
+        cbor_diag::parse_diag_report(text, parse_options)
+            .or_else(|err| {
+                // Arrays of hex bytes can look like diagnostic notation, so only try them after it
+                let options = cbor_diag::ParseOptions {
+                    allow_hex_arrays: true,
+                    ..parse_options.clone()
+                };
+                cbor_diag::parse_hex_report(text, &options).map_err(|_| err)
+            })
+            .map_err(|err| if looks_like_hex(text) { hex_err } else { err })
+    })
+}
+
+/// Describe an error from [`parse_text`] for input given without a format, only going into detail
+/// if it looks like hex.
+fn text_error(err: cbor_diag::Error, text: &str) -> Error {
+    let text = strip_ansi(text);
+    if looks_like_hex(&text) {
+        hex_error(err, &text)
+    } else {
+        Error::new(Kind::Parse, "Failed all parsers")
+    }
+}
+
+/// Parse a single item from `data` in the `from` format, along with any warnings.
+fn parse_item(
+    data: Vec<u8>,
+    from: From,
+    parse_options: &cbor_diag::ParseOptions,
+) -> Result<cbor_diag::ParseReport> {
+    Ok(match from {
+        From::Auto => match cbor_diag::parse_bytes_report(&data, parse_options) {
+            Ok(value) => value,
+            Err(err) => match std::str::from_utf8(&data) {
+                Ok(text) => parse_text(text, parse_options).map_err(|err| text_error(err, text))?,
+                // Only binary input can have been meant as binary, so report why that failed
+                Err(_) => return Err(bytes_error(err, &data)),
+            },
+        },
+        From::Hex => {
+            let data = String::from_utf8(data)?;
+            let data = strip_ansi(&data);
+            let options = cbor_diag::ParseOptions {
+                allow_hex_arrays: true,
+                ..parse_options.clone()
+            };
+            cbor_diag::parse_hex_report(&data, &options).map_err(|err| hex_error(err, &data))?
+        }
+        From::Bytes => cbor_diag::parse_bytes_report(&data, parse_options)
+            .map_err(|err| bytes_error(err, &data))?,
+        From::Diag => {
+            let data = String::from_utf8(data)?;
+            cbor_diag::parse_diag_report(strip_ansi(&data), parse_options)?
+        }
+    })
+}
+
+/// Write the warnings from parsing an item to stderr unless `--quiet`, prefixed by the `file` they
+/// are from if given, then return the item or fail if there were any with `--deny-warnings`.
+fn warn(
+    report: cbor_diag::ParseReport,
+    args: &Args,
+    file: Option<&std::path::Path>,
+) -> Result<cbor_diag::DataItem> {
+    let warnings = report.warnings;
+    if !args.quiet {
+        for warning in &warnings {
+            match file {
+                Some(file) => eprintln!("warning: {}: {}", file.display(), warning),
+                None => eprintln!("warning: {}", warning),
+            }
+        }
+    }
+    match warnings.len() {
+        0 => Ok(report.item),
+        _ if !args.deny_warnings => Ok(report.item),
+        1 => Err(Error::new(
+            Kind::Parse,
+            "1 warning denied by --deny-warnings",
+        )),
+        len => Err(Error::new(
+            Kind::Parse,
+            format!("{} warnings denied by --deny-warnings", len),
+        )),
+    }
+}
+
+/// Write a line based diff of `expected` and `actual`, using the longest common subsequence of
+/// lines.
+fn write_diff(
+    expected: &str,
+    actual: &str,
+    names: (&str, &str),
+    mut output: impl Write,
+) -> io::Result<()> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // lengths[i][j] is the length of the longest common subsequence of expected[i..] and
+    // actual[j..]
+    let mut lengths = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    writeln!(output, "--- {}", names.0)?;
+    writeln!(output, "+++ {}", names.1)?;
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(output, " {}", expected[i])?;
+            i += 1;
+            j += 1;
+        } else if j == actual.len()
+            || (i < expected.len() && lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            writeln!(output, "-{}", expected[i])?;
+            i += 1;
+        } else {
+            writeln!(output, "+{}", actual[j])?;
+            j += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Compare `value`, parsed from `input`, to the item in the `--expect` file, returning whether
+/// they match.
+fn verify(
+    value: &cbor_diag::DataItem,
+    input: &Input<'_>,
+    path: &std::path::Path,
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    output: impl Write,
+) -> Result<bool> {
+    let data = std::fs::read(path).map_err(|err| {
+        Error::new(
+            Kind::Io,
+            format!("failed to read {}: {}", path.display(), err),
+        )
+    })?;
+    let expected = parse_item(data, args.expect_from, parse_options)?.item;
+
+    let matches = cbor_diag::roundtrip::semantically_eq(&expected, value)
+        && (!args.strict
+            || match (expected.try_to_bytes(), value.try_to_bytes()) {
+                (Ok(expected), Ok(value)) => expected == value,
+                _ => false,
+            });
+
+    if !matches {
+        let (mut expected, mut value) = (expected, value.clone());
+        if !args.strict {
+            expected.clear_widths();
+            value.clear_widths();
+        }
+        write_diff(
+            &expected.to_diag_pretty_with(&args.diag_options()),
+            &value.to_diag_pretty_with(&args.diag_options()),
+            (&path.display().to_string(), &input.name()),
+            output,
+        )?;
+    }
+
+    Ok(matches)
+}
+
+/// Parse and output each line of the input as a separate item, returning the kind of the first
+/// failure if any lines failed.
+fn lines(
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> Result<Option<Kind>> {
+    if args.from == From::Bytes {
+        return Err(Error::other("--lines is not supported for binary input"));
+    }
+
+    let mut failure = None;
+    let mut line = Vec::new();
+    let mut number = 0;
+    while input.read_until(b'\n', &mut line)? != 0 {
+        number += 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            line.clear();
+            continue;
+        }
+        let data = std::mem::take(&mut line);
         let value = match args.from {
-            From::Auto => cbor_diag::parse_bytes(&data)
+            // Each line is text, so don't attempt to parse it as binary
+            From::Auto => String::from_utf8(data)
+                .map_err(Error::from)
+                .and_then(|text| {
+                    parse_text(&text, parse_options).map_err(|err| {
+                        if looks_like_hex(&text) {
+                            hex_error(err, &text)
+                        } else {
+                            err.into()
+                        }
+                    })
+                }),
+            from => parse_item(data, from, parse_options),
+        }
+        .and_then(|report| warn(report, args, None));
+        match value {
+            Ok(value) => {
+                output_item(&value, args, &mut output)?;
+                output.flush()?;
+            }
+            Err(err) => {
+                output.flush()?;
+                let err = err.at_line(number);
+                report(&err, args.error_format);
+                failure = failure.or(Some(err.kind));
+            }
+        }
+    }
+
+    Ok(failure)
+}
+
+/// Parse the input allowing partial results, for `--from auto` data that parses successfully as
+/// any format is used as-is, otherwise it's parsed leniently as hex if it decodes as hex, falling
+/// back to binary.
+fn parse_lenient(
+    data: Vec<u8>,
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+) -> Result<(Option<cbor_diag::DataItem>, Vec<cbor_diag::ParseWarning>)> {
+    Ok(match args.from {
+        From::Auto => {
+            let value = cbor_diag::parse_bytes_with(&data, parse_options)
                 .ok()
                 .or_else(|| {
-                    String::from_utf8(data).ok().and_then(|data| {
-                        cbor_diag::parse_hex(&data)
-                            .ok()
-                            .or_else(|| cbor_diag::parse_diag(&data).ok())
-                    })
-                })
-                .ok_or_else(|| anyhow!("Failed all parsers"))?,
-            From::Hex => {
-                let data = String::from_utf8(data)?;
-                cbor_diag::parse_hex(data)?
+                    let data = std::str::from_utf8(&data).ok()?;
+                    cbor_diag::parse_hex_with(data, parse_options)
+                        .ok()
+                        .or_else(|| cbor_diag::parse_diag_with(data, parse_options).ok())
+                });
+            if let Some(value) = value {
+                (Some(value), Vec::new())
+            } else if let Some(parsed) = std::str::from_utf8(&data)
+                .ok()
+                .and_then(|data| cbor_diag::parse_hex_lenient(data).ok())
+            {
+                parsed
+            } else {
+                cbor_diag::parse_bytes_lenient(data)
+            }
+        }
+        From::Hex => cbor_diag::parse_hex_lenient(String::from_utf8(data)?)?,
+        From::Bytes => cbor_diag::parse_bytes_lenient(data),
+        From::Diag => {
+            return Err(Error::other(
+                "--lenient is not supported for diagnostic notation",
+            ))
+        }
+    })
+}
+
+/// Output `value` as the item at `index` in a sequence.
+fn output_seq_item(
+    value: &cbor_diag::DataItem,
+    index: usize,
+    args: &Args,
+    mut output: impl Write,
+) -> Result<()> {
+    // The delimiter takes the place of the newline after each item
+    if args.to != To::Bytes && index > 0 {
+        output.write_all(args.delimiter.as_bytes())?;
+    }
+    write_item(value, args, output)
+}
+
+/// End a sequence of `items` items output by `output_seq_item`.
+fn finish_seq(items: usize, args: &Args, mut output: impl Write) -> Result<()> {
+    if args.to != To::Bytes && items > 0 {
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Parse a sequence of items from `input` in the `--from` format, binary items are output as soon
+/// as they are complete while hex and diagnostic notation are read in full first.
+fn seq(
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    mut input: impl Read,
+    mut output: impl Write,
+) -> Result<()> {
+    match args.from {
+        From::Auto | From::Bytes => seq_bytes(args, parse_options, input, output),
+        From::Hex => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            let text = String::from_utf8(data)?;
+            let data = decode_hex(&strip_ansi(&text))?;
+            seq_bytes(args, parse_options, &data[..], output)
+        }
+        From::Diag => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            let text = String::from_utf8(data)?;
+            let text = strip_ansi(&text);
+            let mut items = 0;
+            let mut offset = 0;
+            let mut collected = Vec::new();
+            while let Some((value, len)) =
+                cbor_diag::parse_diag_partial_with(&text[offset..], parse_options)
+                    .map_err(|err| Error::from(err).at_offset(Some(offset)).after_items(items))?
+            {
+                if args.seq_as_array {
+                    collected.push(value);
+                } else {
+                    output_seq_item(&value, items, args, &mut output)
+                        .map_err(|err| err.after_items(items))?;
+                }
+                items += 1;
+                offset += len;
             }
-            From::Bytes => cbor_diag::parse_bytes(data)?,
-            From::Diag => {
-                let data = String::from_utf8(data)?;
-                cbor_diag::parse_diag(data)?
+            if args.seq_as_array {
+                output_item(&cbor_diag::items_to_array(collected), args, &mut output)
+            } else {
+                finish_seq(items, args, &mut output)
             }
-        };
+        }
+    }
+}
+
+/// Decode hex with `#` comments and whitespace, such as concatenated annotated hex items.
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits: String = text
+        .lines()
+        .flat_map(|line| line.split('#').next().unwrap_or_default().chars())
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    hex::decode(digits).map_err(|err| Error::new(Kind::Parse, format!("invalid hex: {}", err)))
+}
+
+/// Parse a sequence of binary items from `input`, outputting each as soon as it is complete.
+fn seq_bytes(
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    mut input: impl Read,
+    mut output: impl Write,
+) -> Result<()> {
+    let mut data = Default::default();
+    let mut items = 0;
+    let mut offset = 0;
+    let mut collected = Vec::new();
+    let mut ended = false;
+
+    while input.read_to_vec(&mut data)? {
+        if parse_options.stop_at_stray_break && !ended && data.first() == Some(&0xff) {
+            ended = true;
+            offset += 1;
+            data.drain(..1);
+        }
+        if ended {
+            continue;
+        }
+        while let Some((value, len)) = cbor_diag::parse_bytes_partial_with(&data, parse_options)
+            .map_err(|err| {
+                let error_offset = bytes_offset(&data).map(|error_offset| offset + error_offset);
+                Error::from(err).at_offset(error_offset).after_items(items)
+            })?
+        {
+            if args.seq_as_array {
+                collected.push(value);
+            } else {
+                output_seq_item(&value, items, args, &mut output)
+                    .map_err(|err| err.after_items(items))?;
+            }
+            items += 1;
+            offset += len;
+            data.drain(..len);
+            if parse_options.stop_at_stray_break && data.first() == Some(&0xff) {
+                ended = true;
+                offset += 1;
+                data.drain(..1);
+                break;
+            }
+        }
+    }
+
+    if args.seq_as_array {
+        output_item(&cbor_diag::items_to_array(collected), args, &mut output)?;
+    } else {
+        finish_seq(items, args, &mut output)?;
+    }
+
+    if !data.is_empty() {
+        let after = if ended { "the break" } else { "last item" };
+        return Err(Error::new(
+            Kind::Parse,
+            format!("{} bytes remaining after {}", data.len(), after),
+        )
+        .at_offset(Some(offset))
+        .after_items(items));
+    }
+
+    Ok(())
+}
+
+fn generate(
+    generate: &Generate,
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    mut input: impl Read,
+    output: impl Write,
+) -> Result<()> {
+    use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+    let options = generate.options()?;
+    let mut rng = ChaCha8Rng::seed_from_u64(generate.seed);
+    let value = if generate.template {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let template = warn(parse_item(data, args.from, parse_options)?, args, None)?;
+        cbor_diag::generate(&template, &mut rng, &options)
+    } else {
+        cbor_diag::generate_arbitrary(&mut rng, &options)
+    };
+    output_item(&value, args, output)
+}
+
+/// Apply the `edit` operations to the input item and output it, when both the input and output
+/// are binary or hex only re-encoding what changed.
+fn edit_item(
+    options: &Edit,
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    input: &Input<'_>,
+    mut output: impl Write,
+) -> Result<()> {
+    let data = input.read()?;
+    let binary = !matches!(input, Input::Data(_)) && matches!(args.from, From::Auto | From::Bytes);
+    let (mut value, original) = match cbor_diag::parse_bytes_preserving_with(&data, parse_options) {
+        Ok((value, original)) if binary => (value, Some(original)),
+        _ => match input {
+            Input::Data(text) if args.from == From::Auto => {
+                let report =
+                    parse_text(text, parse_options).map_err(|err| text_error(err, text))?;
+                (warn(report, args, None)?, None)
+            }
+            _ => (
+                warn(parse_item(data, args.from, parse_options)?, args, None)?,
+                None,
+            ),
+        },
+    };
+
+    edit::apply(&mut value, options, parse_options)?;
+
+    let reencoded = args.canonical
+        || !args.expand.is_empty()
+        || !args.redact.is_empty()
+        || args.cddl_schema.is_some();
+    match original {
+        Some(original) if !reencoded && matches!(args.to, To::Bytes | To::Hex) => {
+            let bytes = value
+                .try_to_bytes_preserving(&original)
+                .map_err(|err| Error::other(err.to_string()))?;
+            if args.to == To::Bytes {
+                output.write_all(&bytes)?;
+            } else {
+                writeln!(output, "{}", args.bytes_hex_options().encode(&bytes))?;
+            }
+            Ok(())
+        }
+        _ => output_item(&value, args, output),
+    }
+}
+
+/// Write `data` to a temporary file next to `path`, then rename it over `path`, so that the file
+/// is never left partially written.
+fn write_atomically(path: &std::path::Path, data: &[u8]) -> io::Result<()> {
+    let mut temp = std::ffi::OsString::from(".");
+    temp.push(path.file_name().unwrap_or_default());
+    temp.push(".cbor-diag-tmp");
+    let temp = path.with_file_name(temp);
+    let result = std::fs::write(&temp, data)
+        .and_then(|()| std::fs::set_permissions(&temp, std::fs::metadata(path)?.permissions()))
+        .and_then(|()| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Convert the file at `path` to the `--to` format, returning whether that changed it, and
+/// writing the new contents unless only checking.
+fn rewrite_file(
+    path: &std::path::Path,
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+) -> Result<bool> {
+    let in_file = |err: Error| Error {
+        message: format!("{}: {}", path.display(), err.message),
+        ..err
+    };
+    let data = Input::File(path).read()?;
+    let value = parse_item(data.clone(), args.from, parse_options)
+        .and_then(|report| warn(report, args, Some(path)))
+        .map_err(in_file)?;
+    let mut formatted = Vec::new();
+    output_item(&value, args, &mut formatted).map_err(in_file)?;
+    if formatted == data {
+        return Ok(false);
+    }
+    if !args.check {
+        write_atomically(path, &formatted).map_err(|err| {
+            Error::new(
+                Kind::Io,
+                format!("failed to write {}: {}", path.display(), err),
+            )
+        })?;
+    }
+    Ok(true)
+}
+
+/// Rewrite each of the `--in-place` files, reporting failures as they happen and exiting with
+/// the status of the first at the end, or with status 1 if `--check` found any that would change.
+fn in_place(
+    args: &Args,
+    parse_options: &cbor_diag::ParseOptions,
+    mut output: impl Write,
+) -> Result<()> {
+    let mut failure = None;
+    let mut changed = false;
+    for path in &args.files {
+        match rewrite_file(path, args, parse_options) {
+            Ok(true) if args.check => {
+                writeln!(output, "{}", path.display())?;
+                changed = true;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                output.flush()?;
+                report(&err, args.error_format);
+                failure.get_or_insert(err.kind);
+            }
+        }
+    }
+    output.flush()?;
+    if let Some(kind) = failure {
+        std::process::exit(kind.exit_code());
+    }
+    if changed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<()> {
+    let output = std::io::stdout();
+    let mut output = limit::LimitedOutput::new(output.lock(), args.max_output_bytes);
+
+    let parse_options = args.parse_options();
+
+    if args.in_place {
+        return in_place(args, &parse_options, &mut output);
+    }
+
+    let input = args.input()?;
+
+    if let Some(Command::Generate(options)) = &args.command {
+        return generate(options, args, &parse_options, input.open()?, &mut output);
+    }
 
-        output_item(value, args.to, &mut output)?;
+    if let Some(Command::Edit(options)) = &args.command {
+        return edit_item(options, args, &parse_options, &input, &mut output);
     }
 
+    if args.lines {
+        if let Some(kind) = lines(args, &parse_options, input.open()?, &mut output)? {
+            std::process::exit(kind.exit_code());
+        }
+        return Ok(());
+    }
+
+    if args.check_well_formed {
+        return check_well_formed(args, &input);
+    }
+
+    if args.seq {
+        return seq(args, &parse_options, input.open()?, &mut output);
+    }
+
+    if let Some(path) = streaming_file(args, &input)? {
+        let file = std::fs::File::open(path).map_err(|err| read_error(path, err))?;
+        let result =
+            cbor_diag::annotate_stream(io::BufReader::new(file), &mut output, &args.hex_options());
+        match result {
+            Ok(()) => {
+                if !args.streaming && !args.quiet {
+                    eprintln!(
+                        "note: {} was streamed because of its size, so warnings such as duplicate \
+                         map keys were not checked",
+                        path.display()
+                    );
+                }
+                return Ok(());
+            }
+            // Nothing is written for invalid input, so when streaming wasn't asked for the
+            // problem is found again and reported the same as for smaller files
+            Err(err @ cbor_diag::Error::Io(_)) => return Err(err.into()),
+            Err(err) if args.streaming => return Err(err.into()),
+            Err(_) => {}
+        }
+    }
+
+    let data = input.read()?;
+
+    if args.lenient {
+        let (value, warnings) = parse_lenient(data, args, &parse_options)?;
+        if let Some(value) = value {
+            output_item(&value, args, &mut output)?;
+        }
+        if !warnings.is_empty() {
+            output.flush()?;
+            if !args.quiet {
+                for warning in warnings {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+            std::process::exit(Kind::Parse.exit_code());
+        }
+        return Ok(());
+    }
+
+    let report = match &input {
+        // Data on the command line is text, don't try to parse it as binary
+        Input::Data(text) if args.from == From::Auto => {
+            parse_text(text, &parse_options).map_err(|err| text_error(err, text))
+        }
+        _ => parse_item(data, args.from, &parse_options),
+    }
+    .map_err(|err| missing_file(args, &input, err))?;
+    let value = warn(report, args, None)?;
+
+    if let Some(path) = &args.expect {
+        if !verify(&value, &input, path, args, &parse_options, &mut output)? {
+            output.flush()?;
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args.split_array {
+        let mut items = 0;
+        for item in cbor_diag::array_to_items(value)? {
+            output_seq_item(&item, items, args, &mut output)?;
+            items += 1;
+        }
+        return finish_seq(items, args, &mut output);
+    }
+
+    output_item(&value, args, &mut output)?;
+
+    Ok(())
+}
+
+/// Check that the binary `input` is well-formed for `--check-well-formed`.
+fn check_well_formed(args: &Args, input: &Input<'_>) -> Result<()> {
+    if matches!(input, Input::Data(_)) || !matches!(args.from, From::Auto | From::Bytes) {
+        return Err(Error::other("--check-well-formed needs binary input"));
+    }
+    let data = input.read()?;
+    let result = if args.seq {
+        cbor_diag::check_well_formed_seq(&data)
+    } else {
+        cbor_diag::check_well_formed(&data)
+    };
+    result.map(drop).map_err(|err| bytes_error(err, &data))
+}
+
+fn report(err: &Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => eprintln!("Error: {}", err),
+        ErrorFormat::Json => eprintln!("{}", err.to_json()),
+    }
+}
+
+/// Binary input files larger than this are annotated without reading them into memory, see
+/// `--streaming`.
+const STREAMING_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// The file to annotate a piece at a time, with `--streaming` or for large enough binary files
+/// when nothing else needs the whole item in memory.
+fn streaming_file<'a>(args: &Args, input: &Input<'a>) -> Result<Option<&'a std::path::Path>> {
+    let whole_item = args.canonical
+        || args.cddl.is_some()
+        || !args.expand.is_empty()
+        || !args.redact.is_empty()
+        || args.strict_tags
+        || args.deny_warnings
+        || args.stop_at_break
+        || args.expect.is_some()
+        || args.split_array;
+    let binary_file = match *input {
+        Input::File(path) if matches!(args.from, From::Auto | From::Bytes) => Some(path),
+        _ => None,
+    };
+
+    if args.streaming {
+        if args.to != To::Annotated {
+            return Err(Error::other(
+                "--streaming is only supported with --to annotated",
+            ));
+        }
+        if whole_item {
+            return Err(Error::other(
+                "--streaming can't be combined with options that need the whole item",
+            ));
+        }
+        return binary_file
+            .map(Some)
+            .ok_or_else(|| Error::other("--streaming needs a binary input file"));
+    }
+
+    match binary_file {
+        Some(path) if args.to == To::Annotated && !whole_item => {
+            let len = std::fs::metadata(path)
+                .map_err(|err| read_error(path, err))?
+                .len();
+            Ok(Some(path).filter(|_| len > STREAMING_THRESHOLD))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// A source of input data.
+enum Input<'a> {
+    Stdin,
+    File(&'a std::path::Path),
+    /// Data given directly as an argument
+    Data(&'a str),
+}
+
+impl<'a> Input<'a> {
+    /// How to refer to this input in messages.
+    fn name(&self) -> String {
+        match self {
+            Input::Stdin => "stdin".to_owned(),
+            Input::File(path) => path.display().to_string(),
+            Input::Data(_) => "argument".to_owned(),
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn BufRead + 'a>> {
+        Ok(match *self {
+            Input::Stdin => Box::new(io::stdin().lock()),
+            Input::File(path) => Box::new(io::BufReader::new(
+                std::fs::File::open(path).map_err(|err| read_error(path, err))?,
+            )),
+            Input::Data(data) => Box::new(data.as_bytes()),
+        })
+    }
+
+    fn read(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.open()?
+            .read_to_end(&mut data)
+            .map_err(|err| match self {
+                Input::File(path) => read_error(path, err),
+                _ => err.into(),
+            })?;
+        Ok(data)
+    }
+}
+
+fn read_error(path: &std::path::Path, err: io::Error) -> Error {
+    Error::new(
+        Kind::Io,
+        format!("failed to read {}: {}", path.display(), err),
+    )
+}
+
+fn read_file(path: &std::path::Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|err| read_error(path, err))
+}
+
+/// Parse a `--key-names` file, a JSON object from integers to names.
+fn parse_key_names(data: &str) -> Result<std::collections::BTreeMap<i128, String>> {
+    use cbor_diag::{DataItem, TextString};
+
+    let invalid =
+        |message: String| Error::new(Kind::Parse, format!("invalid key names: {}", message));
+
+    // JSON is a subset of diagnostic notation
+    let item = cbor_diag::parse_diag(data.trim());
+    let entries = match &item {
+        Ok(DataItem::Map { data, .. }) => data,
+        _ => return Err(invalid("expected a JSON object".into())),
+    };
+    entries
+        .iter()
+        .map(|entry| match entry {
+            (
+                DataItem::TextString(TextString { data: key, .. }),
+                DataItem::TextString(TextString { data: name, .. }),
+            ) => {
+                let key = key
+                    .parse()
+                    .map_err(|_| invalid(format!("key {:?} is not an integer", key)))?;
+                if name.contains('/') {
+                    return Err(invalid(format!("name {:?} contains '/'", name)));
+                }
+                Ok((key, name.clone()))
+            }
+            _ => Err(invalid("expected string keys and values".into())),
+        })
+        .collect()
+}
+
+/// Read the files given as options, before any input is processed.
+fn load_files(args: &mut Args) -> Result<()> {
+    if let Some(path) = &args.cddl {
+        args.cddl_schema = Some(read_file(path)?);
+    }
+    if let Some(path) = &args.key_names {
+        args.key_name_map = parse_key_names(&read_file(path)?)?;
+    }
+    Ok(())
+}
+
+/// Show binary output as hex instead when it would go to a terminal, where it could leave the
+/// terminal in a broken state, unless `--raw` is given.
+fn protect_terminal(args: &mut Args) {
+    if args.to == To::Bytes
+        && !args.raw
+        && !args.in_place
+        && terminal::is_terminal(terminal::Stream::Stdout)
+    {
+        eprintln!(
+            "warning: not writing binary output to a terminal, showing it as hex instead (use \
+             --raw to write it anyway)"
+        );
+        args.to = To::Hex;
+    }
+}
+
+#[paw::main]
+fn main(mut args: Args) -> io::Result<()> {
+    protect_terminal(&mut args);
+    if let Err(err) = load_files(&mut args).and_then(|()| run(&args)) {
+        io::stdout().flush()?;
+        report(&err, args.error_format);
+        std::process::exit(err.kind.exit_code());
+    }
     Ok(())
 }
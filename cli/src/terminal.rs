@@ -0,0 +1,13 @@
+pub(crate) use atty::Stream;
+
+/// Whether `stream` is connected to an interactive terminal.
+///
+/// The `CBOR_DIAG_TERMINAL` environment variable overrides the detection for all streams when set
+/// to `1` or `0`, so that the behaviour on a terminal can be tested.
+pub(crate) fn is_terminal(stream: Stream) -> bool {
+    match std::env::var_os("CBOR_DIAG_TERMINAL") {
+        Some(value) if value == "1" => true,
+        Some(value) if value == "0" => false,
+        _ => atty::is(stream),
+    }
+}
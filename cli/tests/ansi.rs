@@ -0,0 +1,121 @@
+use assert_cmd::Command;
+
+const FIXTURE: &str = concat!(
+    "a26568656c6c6f65776f726c64a163796f756673686f756c64a165766973",
+    "6974d820781868747470733a2f2f63626f722e6e656d6f3135372e636f6d",
+);
+
+fn run(args: &[&str], input: impl Into<Vec<u8>>) -> std::process::Output {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap()
+}
+
+fn stdout(args: &[&str], input: impl Into<Vec<u8>>) -> String {
+    let output = run(args, input);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Color `text` the way a terminal highlighter might: strings green, numbers bold blue and
+/// brackets bold, each reset afterwards.
+fn colorize(text: &str) -> String {
+    let mut colored = String::new();
+    let mut in_string = false;
+    let mut in_number = false;
+    for c in text.chars() {
+        if in_number && !c.is_ascii_digit() {
+            colored.push_str("\x1b[0m");
+            in_number = false;
+        }
+        match c {
+            '"' if in_string => {
+                colored.push_str("\"\x1b[39m");
+                in_string = false;
+            }
+            '"' => {
+                colored.push_str("\x1b[32m\"");
+                in_string = true;
+            }
+            '0'..='9' if !in_string && !in_number => {
+                colored.push_str("\x1b[1;34m");
+                colored.push(c);
+                in_number = true;
+            }
+            '{' | '}' | '[' | ']' if !in_string => {
+                colored.push_str("\x1b[1m");
+                colored.push(c);
+                colored.push_str("\x1b[22m");
+            }
+            c => colored.push(c),
+        }
+    }
+    if in_number {
+        colored.push_str("\x1b[0m");
+    }
+    colored
+}
+
+#[test]
+fn colored_pretty_diag() {
+    let pretty = stdout(&["--to", "diag"], FIXTURE);
+    let colored = colorize(&pretty);
+    assert!(colored.contains("\x1b[32m\"hello\"\x1b[39m"));
+
+    for from in &["auto", "diag"] {
+        assert_eq!(
+            stdout(&["--from", from, "--to", "hex"], colored.as_str()),
+            format!("{}\n", FIXTURE)
+        );
+    }
+    assert_eq!(
+        stdout(&["--to", "hex", "--data", &colored], ""),
+        format!("{}\n", FIXTURE)
+    );
+}
+
+#[test]
+fn colored_hex() {
+    let colored = format!("\x1b[2m{}\x1b[0m", FIXTURE);
+    for from in &["auto", "hex"] {
+        assert_eq!(
+            stdout(&["--from", from, "--to", "hex"], colored.as_str()),
+            format!("{}\n", FIXTURE)
+        );
+    }
+}
+
+#[test]
+fn colored_sequence() {
+    assert_eq!(
+        stdout(
+            &["--seq", "--from", "diag", "--to", "compact"],
+            "\x1b[34m1\x1b[0m\n\x1b[32m\"a\"\x1b[0m"
+        ),
+        "1\n\"a\"\n"
+    );
+}
+
+#[test]
+fn binary_escape_bytes_kept() {
+    // 0x1b is the header of an 8 byte unsigned integer, the following bytes are data
+    let input = b"\x1b\x00\x00\x00\x00\x00\x5b\x6d\x31".to_vec();
+    assert_eq!(
+        stdout(&["--from", "bytes", "--to", "compact"], input.clone()),
+        "5991729_3\n"
+    );
+    assert_eq!(stdout(&["--to", "compact"], input), "5991729_3\n");
+}
+
+#[test]
+fn other_escapes_rejected() {
+    let output = run(&["--from", "diag"], "\x1b]0;title\x071");
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,90 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn schema_file(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+const SCHEMA: &str = r#"
+    person = {
+        name: tstr,
+        ? age: uint,
+        ? tags: [* tstr],
+    }
+"#;
+
+#[test]
+fn valid() {
+    let path = schema_file("cddl-valid.cddl", SCHEMA);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--to", "compact", "--cddl"])
+        .arg(&path)
+        .write_stdin(r#"{"name": "Alice", "tags": ["a"]}"#)
+        .assert()
+        .success()
+        .stdout("{\"name\":\"Alice\",\"tags\":[\"a\"]}\n");
+}
+
+#[test]
+fn violations() {
+    let path = schema_file("cddl-violations.cddl", SCHEMA);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--cddl"])
+        .arg(&path)
+        .write_stdin(r#"{"age": -1, "tags": ["a very long text string that is cut short", 2]}"#)
+        .assert()
+        .code(6)
+        .stdout("")
+        .stderr(concat!(
+            "Error: item does not match the CDDL schema\n",
+            "  at root: missing `name: tstr`, found {\"age\":-1,\"tags\":[\"a very long text stri…\n",
+            "  at [0, 1]: does not match `uint`, found -1\n",
+            "  at [1, 1, 1]: not allowed by `[* tstr]`, found 2\n",
+        ));
+}
+
+#[test]
+fn json_error() {
+    let path = schema_file("cddl-json.cddl", "root = [uint]");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--error-format", "json", "--cddl"])
+        .arg(&path)
+        .write_stdin("8120")
+        .assert()
+        .code(6)
+        .stderr(concat!(
+            r#"{"kind":"invalid","code":6,"message":"item does not match the CDDL schema\n  at [0]: does not match `uint`, found -1","#,
+            r#""offset":null,"line":null,"items":null}"#,
+            "\n",
+        ));
+}
+
+#[test]
+fn invalid_schema() {
+    let path = schema_file("cddl-invalid.cddl", "root = [uint .size 2]");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--cddl"])
+        .arg(&path)
+        .write_stdin("8101")
+        .assert()
+        .code(1)
+        .stderr(
+            "Error: invalid schema: ranges and control operators are not supported at line 1\n",
+        );
+}
+
+#[test]
+fn missing_schema() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--cddl", "/nonexistent.cddl"])
+        .write_stdin("01")
+        .assert()
+        .code(5);
+}
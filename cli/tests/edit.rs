@@ -0,0 +1,175 @@
+use assert_cmd::Command;
+
+fn edit(args: &[&str], input: &str) -> String {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "edit"])
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn edit_error(args: &[&str], input: &str) -> String {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "edit"])
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+const INPUT: &str = r#"{"a": [1, 2], 4: h'01', "b.c": {-1: null}}"#;
+
+#[test]
+fn maps() {
+    assert_eq!(
+        edit(&["--set", r#".a=h'4711'"#, "--set", ".4=true"], INPUT),
+        "{\"a\":h'4711',4:true,\"b.c\":{-1:null}}\n"
+    );
+    assert_eq!(
+        edit(&["--set", r#"."b.c".-1="x""#, "--set", ".new=1"], INPUT),
+        "{\"a\":[1,2],4:h'01',\"b.c\":{-1:\"x\"},\"new\":1}\n"
+    );
+    assert_eq!(
+        edit(&["--insert", r#"."b.c".h'00'=0"#, "--delete", ".4"], INPUT),
+        "{\"a\":[1,2],\"b.c\":{-1:null,h'00':0}}\n"
+    );
+}
+
+#[test]
+fn arrays() {
+    assert_eq!(
+        edit(&["--set", ".a.0=5", "--set", ".a[1]=[]"], INPUT),
+        "{\"a\":[5,[]],4:h'01',\"b.c\":{-1:null}}\n"
+    );
+    assert_eq!(
+        edit(&["--insert", ".a[0]=0", "--insert", ".a[-]=3"], INPUT),
+        "{\"a\":[0,1,2,3],4:h'01',\"b.c\":{-1:null}}\n"
+    );
+    assert_eq!(
+        edit(&["--set", ".a[-]=3", "--delete", ".a.0"], INPUT),
+        "{\"a\":[2,3],4:h'01',\"b.c\":{-1:null}}\n"
+    );
+    assert_eq!(edit(&["--insert", "[2]=2"], "[0, 1]"), "[0,1,2]\n");
+    assert_eq!(edit(&["--set", ".=1"], "[0, 1]"), "1\n");
+}
+
+#[test]
+fn through_tags() {
+    let input = "18([h'a10126', {4: h'3131'}, h'01', h'0102'])";
+    assert_eq!(
+        edit(&["--set", r#".1.4=h'4711'"#], input),
+        "18([h'a10126',{4:h'4711'},h'01',h'0102'])\n"
+    );
+}
+
+#[test]
+fn missing_paths() {
+    for (args, message) in &[
+        (
+            &["--set", ".a.5=0"][..],
+            "no item at .a.5, found .a but it has 2 items",
+        ),
+        (
+            &["--delete", r#"."b.c".-2"#],
+            r#"no item at ."b.c".-2, found ."b.c" but it has no key -2"#,
+        ),
+        (
+            &["--set", ".x.y=0"],
+            r#"no item at .x.y, found . but it has no key "x""#,
+        ),
+        (
+            &["--insert", ".4[0]=0"],
+            "no item at .4[0], found .4 but it is not an array or map",
+        ),
+        (
+            &["--delete", ".a[-]"],
+            "no item at .a[-], found .a but it has 2 items",
+        ),
+        (
+            &["--insert", ".a=0"],
+            "can't insert at .a, the key already exists",
+        ),
+        (&["--delete", "."], "can't insert or delete the whole item"),
+        (&["--set", "a=0"], r#"invalid path "a=0" at offset 0"#),
+        (
+            &["--set", ".a[x]=0"],
+            r#"invalid path ".a[x]=0" at offset 2"#,
+        ),
+        (&["--set", ".a"], r#"expected PATH=VALUE, found ".a""#),
+    ] {
+        assert_eq!(edit_error(args, INPUT), format!("Error: {}\n", message));
+    }
+}
+
+#[test]
+fn preserves_binary_encoding() {
+    // COSE_Sign1 with a non-preferred length and a signalling NaN that re-encoding would change
+    let input = hex::decode("d28443a10126a2044231313a0000fffff9fc01580101420102").unwrap();
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "hex", "edit", "--set", ".1.4=h'4711'"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "d28443a10126a2044247113a0000fffff9fc01580101420102\n"
+    );
+
+    // Without the original encoding only the widths are kept
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--to",
+            "hex",
+            "--from",
+            "hex",
+            "edit",
+            "--set",
+            ".1.4=h'4711'",
+        ])
+        .write_stdin("d28443a10126a2044231313a0000fffff9fc01580101420102")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "d28443a10126a2044247113a0000fffff9fe01580101420102\n"
+    );
+}
+
+#[test]
+fn argument_order() {
+    assert_eq!(
+        edit(&["--delete", ".0", "--insert", ".0=9"], "[1,2,3]"),
+        "[9,2,3]\n"
+    );
+    assert_eq!(
+        edit(&["--insert", ".0=9", "--delete", ".0"], "[1,2,3]"),
+        "[1,2,3]\n"
+    );
+    assert_eq!(
+        edit(&["--delete", ".a", "--insert", ".a=1"], r#"{"a": 5}"#),
+        "{\"a\":1}\n"
+    );
+    assert_eq!(
+        edit(
+            &["--set", ".0=[]", "--insert", ".0[-]=1", "--delete", ".1", "--set", ".0[-]=2"],
+            "[0, 1]"
+        ),
+        "[[1,2]]\n"
+    );
+}
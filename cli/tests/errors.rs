@@ -0,0 +1,137 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+fn json_error(args: &[&str], input: &[u8], code: i32) -> String {
+    let mut args = args.to_vec();
+    args.extend(&["--error-format", "json"]);
+    let output = cbor_diag()
+        .args(&args)
+        .write_stdin(input)
+        .assert()
+        .code(code)
+        .get_output()
+        .clone();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn parse_error() {
+    cbor_diag()
+        .args(&["--from", "bytes"])
+        .write_stdin(&b"\x82\x01"[..])
+        .assert()
+        .code(2)
+        .stderr("Error: declared length 2 is out of range, only 1 bytes available\n");
+    assert_eq!(
+        json_error(&["--from", "bytes"], b"\x82\x01", 2),
+        concat!(
+            r#"{"kind":"parse","code":2,"#,
+            r#""message":"declared length 2 is out of range, only 1 bytes available","#,
+            r#""offset":2,"line":null,"items":null}"#,
+            "\n",
+        )
+    );
+    assert_eq!(
+        json_error(&["--from", "hex"], b"82 01 1c", 2),
+        concat!(
+            r#"{"kind":"parse","code":2,"#,
+            r#""message":"TODO cbor-diag::Error: Parsing error at offset 2: reserved additional information value 28","#,
+            r#""offset":2,"line":null,"items":null}"#,
+            "\n",
+        )
+    );
+    cbor_diag()
+        .args(&["--from", "diag"])
+        .write_stdin("[1,")
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn trailing_data() {
+    let stderr = json_error(&["--from", "bytes"], b"\x01\x02", 3);
+    assert!(stderr.starts_with(r#"{"kind":"trailing_data","code":3,"message":"parsed 1 item"#));
+    assert!(stderr.ends_with("\"offset\":1,\"line\":null,\"items\":null}\n"));
+}
+
+#[test]
+fn invalid_utf8() {
+    assert_eq!(
+        json_error(&["--from", "diag"], b"\"a\xff\"", 4),
+        concat!(
+            r#"{"kind":"invalid_utf8","code":4,"#,
+            r#""message":"input is not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 2","#,
+            r#""offset":2,"line":null,"items":null}"#,
+            "\n",
+        )
+    );
+}
+
+#[test]
+fn io_error() {
+    let stderr = json_error(&["--expect", "does-not-exist.diag"], b"1", 5);
+    assert!(stderr
+        .starts_with(r#"{"kind":"io","code":5,"message":"failed to read does-not-exist.diag: "#));
+}
+
+#[test]
+fn other_error() {
+    cbor_diag()
+        .args(&["--from", "diag", "--lenient"])
+        .write_stdin("1")
+        .assert()
+        .code(1)
+        .stderr("Error: --lenient is not supported for diagnostic notation\n");
+    cbor_diag()
+        .args(&[
+            "--from",
+            "diag",
+            "--to",
+            "bytes",
+            "--allow-unknown-app-literals",
+        ])
+        .write_stdin("foo'x'")
+        .assert()
+        .code(1)
+        .stderr(
+            "Error: TODO cbor-diag::Error: application literal foo'x' has no binary encoding\n",
+        );
+}
+
+#[test]
+fn seq_items() {
+    assert_eq!(
+        json_error(&["--seq"], b"\x01\x82\x01\x02\x1c", 2),
+        concat!(
+            r#"{"kind":"parse","code":2,"#,
+            r#""message":"TODO cbor-diag::Error: Parsing error at offset 0: reserved additional information value 28","#,
+            r#""offset":4,"line":null,"items":2}"#,
+            "\n",
+        )
+    );
+    assert_eq!(
+        json_error(&["--seq"], b"\x01\x82\x01", 2),
+        concat!(
+            r#"{"kind":"parse","code":2,"#,
+            r#""message":"2 bytes remaining after last item","#,
+            r#""offset":1,"line":null,"items":1}"#,
+            "\n",
+        )
+    );
+}
+
+#[test]
+fn lines() {
+    assert_eq!(
+        json_error(&["--lines", "--from", "diag"], b"1\n[\n2\n", 2),
+        concat!(
+            r#"{"kind":"parse","code":2,"#,
+            r#""message":"TODO cbor-diag::Error: Parsing error (Error((\"[\\n\", Tag)))","#,
+            r#""offset":null,"line":2,"items":null}"#,
+            "\n",
+        )
+    );
+}
@@ -0,0 +1,99 @@
+use assert_cmd::Command;
+
+fn generate(args: &[&str], input: &str) -> String {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("generate")
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn deterministic() {
+    for seed in &["0", "1", "12345"] {
+        let args = [
+            "--seed",
+            seed,
+            "--indefinite",
+            "--non-preferred",
+            "--to",
+            "hex",
+        ];
+        assert_eq!(generate(&args, ""), generate(&args, ""));
+    }
+    assert_ne!(
+        generate(&["--seed", "1", "--to", "hex"], ""),
+        generate(&["--seed", "2", "--to", "hex"], "")
+    );
+}
+
+#[test]
+fn stable() {
+    assert_eq!(
+        generate(
+            &[
+                "--seed",
+                "6",
+                "--max-depth",
+                "2",
+                "--max-len",
+                "3",
+                "--max-string-len",
+                "6"
+            ],
+            ""
+        ),
+        "43350(h'')\n"
+    );
+}
+
+#[test]
+fn output_format() {
+    let diag = generate(&["--seed", "7", "--to", "compact"], "");
+    let hex = generate(&["--seed", "7", "--to", "hex"], "");
+    assert_eq!(
+        cbor_diag::parse_diag(diag.trim()).unwrap().to_bytes(),
+        hex::decode(hex.trim()).unwrap()
+    );
+}
+
+#[test]
+fn major_types() {
+    for seed in 0..10 {
+        let output = generate(
+            &[
+                "--seed",
+                &seed.to_string(),
+                "--major-type",
+                "1",
+                "--to",
+                "compact",
+            ],
+            "",
+        );
+        assert!(output.starts_with('-'), "{}", output);
+    }
+}
+
+#[test]
+fn template() {
+    let output = generate(
+        &["--template", "--seed", "1", "--max-string-len", "8"],
+        r#"{"user": "bob", "age": 42}"#,
+    );
+    assert_eq!(output, "{\"user\": \">F/\u{b4371}X\", \"age\": 11}\n");
+}
+
+#[test]
+fn invalid_major_type() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["generate", "--major-type", "8"])
+        .assert()
+        .code(1)
+        .stderr("Error: invalid major type 8\n");
+}
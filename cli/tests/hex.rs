@@ -0,0 +1,284 @@
+use assert_cmd::Command;
+
+#[test]
+fn hex_width() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--hex-width", "2"])
+        .write_stdin(&b"\x43abc"[..])
+        .assert()
+        .success()
+        .stdout("43      # bytes(3)\n   6162 #   \"ab\"\n   63   #   \"c\"\n");
+}
+
+#[test]
+fn no_ascii() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--no-ascii"])
+        .write_stdin(&b"\x43abc"[..])
+        .assert()
+        .success()
+        .stdout("43        # bytes(3)\n   616263\n");
+}
+
+#[test]
+fn flag_non_preferred() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated"])
+        .write_stdin(&b"\x18\x01"[..])
+        .assert()
+        .success()
+        .stdout("18 01 # unsigned(1) (non-preferred encoding)\n");
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--no-flag-non-preferred"])
+        .write_stdin(&b"\x18\x01"[..])
+        .assert()
+        .success()
+        .stdout("18 01 # unsigned(1)\n");
+}
+
+#[test]
+fn annotated_roundtrip() {
+    let input = b"\xa2\x61a\x43abc\x61b\xa1\x01\x42\x00\xff";
+    let annotated = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated"])
+        .write_stdin(&input[..])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "bytes"])
+        .write_stdin(annotated)
+        .assert()
+        .success()
+        .stdout(&input[..]);
+}
+
+#[test]
+fn expand_packed() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "--expand", "packed"])
+        .write_stdin(r#"113(["hello", [simple(0), 6(-1)]])"#)
+        .assert()
+        .failure()
+        .code(2)
+        .stderr("Error: TODO cbor-diag::Error: packed reference to shared item 17 is out of range, the table has 1 items\n");
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "--expand", "packed"])
+        .write_stdin(r#"113(["hello", [simple(0), simple(0)]])"#)
+        .assert()
+        .success()
+        .stdout("[\"hello\",\"hello\"]\n");
+}
+
+#[test]
+fn sort_map_keys() {
+    for input in &["a2 6162 01 6161 02", "a2 6161 02 6162 01"] {
+        Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(&["--from", "hex", "--to", "compact", "--sort-map-keys"])
+            .write_stdin(*input)
+            .assert()
+            .success()
+            .stdout("{\"a\":2,\"b\":1}\n");
+    }
+
+    // Other outputs keep the original order
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "hex", "--sort-map-keys"])
+        .write_stdin("a2 6162 01 6161 02")
+        .assert()
+        .success()
+        .stdout("a2616201616102\n");
+}
+
+#[test]
+fn normalize_indefinite() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "compact", "--normalize-indefinite"])
+        .write_stdin("7f 6161 6162 ff")
+        .assert()
+        .success()
+        .stdout("\"ab\"\n");
+}
+
+#[test]
+fn no_encoding_indicators() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--from",
+            "hex",
+            "--to",
+            "compact",
+            "--no-encoding-indicators",
+        ])
+        .write_stdin("9f 1801 f93e00 d81841 01 7f 6161 6162 ff ff")
+        .assert()
+        .success()
+        .stdout("[1,1.5,24(h'01'),\"ab\"]\n");
+}
+
+#[test]
+fn c_array_input() {
+    for from in &["hex", "auto"] {
+        Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(&["--from", from, "--to", "compact"])
+            .write_stdin("{ 0xA1, 0x01, 0x2 } // alg")
+            .assert()
+            .success()
+            .stdout("{1:2}\n");
+    }
+
+    // Valid diagnostic notation takes precedence when guessing
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact"])
+        .write_stdin("[12, 34]")
+        .assert()
+        .success()
+        .stdout("[12,34]\n");
+}
+
+#[test]
+fn codegen() {
+    for (to, expected) in &[
+        ("rust", "&[0xa1, 0x01, 0x02] // {1:2}\n"),
+        ("c", "uint8_t msg[] = {0xA1, 0x01, 0x02}; // {1:2}\n"),
+        ("python", "bytes.fromhex(\"a10102\")  # {1:2}\n"),
+    ] {
+        Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(&["--from", "hex", "--to", to])
+            .write_stdin("a10102")
+            .assert()
+            .success()
+            .stdout(*expected);
+    }
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "c", "--codegen-width", "2"])
+        .args(&["--codegen-name", "vector"])
+        .write_stdin("a10102")
+        .assert()
+        .success()
+        .stdout("uint8_t vector[] = {\n    0xA1, 0x01,\n    0x02,\n}; // {1:2}\n");
+}
+
+#[test]
+fn hex_grouping() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "hex", "--hex-group", "2", "--hex-upper"])
+        .write_stdin(&b"\x43abc"[..])
+        .assert()
+        .success()
+        .stdout("4361 6263\n");
+}
+
+#[test]
+fn hex_prefix() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "hex", "--hex-group", "1", "--hex-prefix", "0x"])
+        .write_stdin(&b"\x82\x01\x02"[..])
+        .assert()
+        .success()
+        .stdout("0x82 0x01 0x02\n");
+}
+
+#[test]
+fn crlf_line_endings() {
+    for args in &[
+        &["--to", "compact"][..],
+        &["--from", "hex", "--to", "compact"],
+    ] {
+        Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(*args)
+            .write_stdin("\r\n  82 01\r\n\t02 \t\r\n\r\n")
+            .assert()
+            .success()
+            .stdout("[1,2]\n");
+    }
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "--lines"])
+        .write_stdin("8201\t02\r\na1 01 02\r\n")
+        .assert()
+        .success()
+        .stdout("[1,2]\n{1:2}\n");
+}
+
+#[test]
+fn auto_hex_error() {
+    // Looks like hex, so report why it isn't valid instead of a diagnostic notation error
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact"])
+        .write_stdin("82 01\r\n")
+        .assert()
+        .code(2)
+        .stderr("Error: declared length 2 is out of range, only 1 bytes available\n");
+
+    // Valid diagnostic notation is still accepted when it isn't valid hex
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact"])
+        .write_stdin("1000\r\n")
+        .assert()
+        .success()
+        .stdout("1000\n");
+}
+
+#[test]
+fn html() {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "html", "--hex-width", "2"])
+        .write_stdin(&b"\x43abc"[..])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let html = String::from_utf8(output).unwrap();
+    assert!(html.starts_with("<style>\n"));
+    assert!(html.contains(
+        "<span class=\"cbor-content\">6162</span><span class=\"cbor-comment\"> #   &quot;ab&quot;</span>\n"
+    ));
+    assert!(html.ends_with("</details></pre>\n"));
+}
+
+#[test]
+fn group_and_split_headers() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--to",
+            "annotated",
+            "--hex-group",
+            "2",
+            "--no-split-headers",
+        ])
+        .write_stdin(&b"\x58\x03abc"[..])
+        .assert()
+        .success()
+        .stdout("5803       # bytes(3) (non-preferred encoding)\n   6162 63 #   \"abc\"\n");
+}
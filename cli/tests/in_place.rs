@@ -0,0 +1,107 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn fixture(name: &str, contents: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn canonical_bytes() {
+    // {"b": 1, "a": 2_3}
+    let path = fixture(
+        "in-place-canonical.cbor",
+        b"\xa2\x61b\x01\x61a\x1b\0\0\0\0\0\0\0\x02",
+    );
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--from",
+            "bytes",
+            "--to",
+            "bytes",
+            "--canonical",
+            "--in-place",
+        ])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(std::fs::read(&path).unwrap(), b"\xa2\x61a\x02\x61b\x01");
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--from",
+            "bytes",
+            "--to",
+            "bytes",
+            "--canonical",
+            "--in-place",
+            "--check",
+        ])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn normalize_diag() {
+    let path = fixture("in-place-normalize.diag", br#"{"a":[1,2],  "b" : h'00'}"#);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--to", "diag", "--in-place"])
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"a\": [1, 2], \"b\": h'00'}\n"
+    );
+}
+
+#[test]
+fn check_only_reports() {
+    let formatted = fixture("in-place-check-formatted.diag", b"[1, 2]\n");
+    let unformatted = fixture("in-place-check-unformatted.diag", b"[1,2]");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--to", "diag", "--in-place", "--check"])
+        .arg(&formatted)
+        .arg(&unformatted)
+        .assert()
+        .code(1)
+        .stdout(format!("{}\n", unformatted.display()));
+    assert_eq!(std::fs::read(&unformatted).unwrap(), b"[1,2]");
+}
+
+#[test]
+fn files_are_independent() {
+    let invalid = fixture("in-place-independent-invalid.diag", b"[1,");
+    let valid = fixture("in-place-independent-valid.diag", b"[1,2]");
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--to", "diag", "--in-place"])
+        .arg(&invalid)
+        .arg(&valid)
+        .assert()
+        .code(2)
+        .get_output()
+        .clone();
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with(&format!("Error: {}: ", invalid.display())));
+    assert_eq!(std::fs::read(&invalid).unwrap(), b"[1,");
+    assert_eq!(std::fs::read_to_string(&valid).unwrap(), "[1, 2]\n");
+}
+
+#[test]
+fn requires_files() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--in-place")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,109 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+#[test]
+fn hex_argument() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("a16568656c6c6f01")
+        .assert()
+        .success()
+        .stdout("{\"hello\": 1}\n");
+}
+
+#[test]
+fn diag_argument() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "diag", "--to", "hex", r#"{"hello": 1}"#])
+        .assert()
+        .success()
+        .stdout("a16568656c6c6f01\n");
+}
+
+#[test]
+fn arguments_are_text() {
+    // As bytes this would be the negative integer -18
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "hex", "1"])
+        .assert()
+        .success()
+        .stdout("01\n");
+}
+
+#[test]
+fn data_flag() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact", "--data", "[1, 2]"])
+        .assert()
+        .success()
+        .stdout("[1,2]\n");
+}
+
+#[test]
+fn file_argument() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("input-argument.diag");
+    std::fs::write(&path, "[1,2]").unwrap();
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "hex"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("820102\n");
+}
+
+#[test]
+fn single_input() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["1", "2"])
+        .assert()
+        .code(1)
+        .stderr("Error: only one input file can be given without --in-place\n");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--data", "1", "2"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn missing_file() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("does-not-exist.cbor")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(
+            "Error: no file exists at does-not-exist.cbor, and parsing it as data failed: Failed all parsers\n",
+        );
+
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "missing/item"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: no file exists at missing/item, and parsing it as data failed: TODO cbor-diag::Error: Invalid character 'm' in hex at line 1, column 1\n"
+    );
+
+    // Not path-like, or given with --data, so only the parse error is reported
+    for args in &[&["nope"][..], &["--data", "does-not-exist.cbor"]] {
+        let output = Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(*args)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "Error: Failed all parsers\n"
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use assert_cmd::Command;
+
+#[test]
+fn rejected_by_default() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "bytes"])
+        .write_stdin(&b"\x62\xc3\x28"[..])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn allowed() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "bytes", "--to", "bytes", "--allow-invalid-utf8"])
+        .write_stdin(&b"\x62\xc3\x28"[..])
+        .assert()
+        .success()
+        .stdout(&b"\x62\xc3\x28"[..]);
+}
@@ -0,0 +1,60 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn names_file(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn annotated() {
+    let path = names_file("key-names-annotated.json", r#"{"1": "alg", "-1": "crv"}"#);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "annotated", "--key-names"])
+        .arg(&path)
+        .write_stdin("a2 01 26 20 01")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "a2    # map(2)\n",
+            "   01 #   unsigned(1) (alg)\n",
+            "   26 #   negative(-7)\n",
+            "   20 #   negative(-1) (crv)\n",
+            "   01 #   unsigned(1)\n",
+        ));
+}
+
+#[test]
+fn compact() {
+    let path = names_file("key-names-compact.json", "{\n  \"4\": \"kid\"\n}\n");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "compact", "--key-names"])
+        .arg(&path)
+        .write_stdin("a2 04 41 01 05 00")
+        .assert()
+        .success()
+        .stdout("{/ kid / 4:h'01',5:0}\n");
+}
+
+#[test]
+fn invalid() {
+    for (contents, message) in &[
+        ("[1]", "expected a JSON object"),
+        (r#"{"a": "b"}"#, r#"key "a" is not an integer"#),
+        (r#"{"1": 2}"#, "expected string keys and values"),
+        (r#"{"1": "a/b"}"#, r#"name "a/b" contains '/'"#),
+    ] {
+        let path = names_file("key-names-invalid.json", contents);
+        Command::cargo_bin("cbor-diag")
+            .unwrap()
+            .args(&["--from", "hex", "--key-names"])
+            .arg(&path)
+            .write_stdin("01")
+            .assert()
+            .code(2)
+            .stderr(format!("Error: invalid key names: {}\n", message));
+    }
+}
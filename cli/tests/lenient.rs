@@ -0,0 +1,54 @@
+use assert_cmd::Command;
+
+#[test]
+fn truncated() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "bytes", "--lenient"])
+        .write_stdin(&b"\x85\x01\x02\x03"[..])
+        .assert()
+        .code(2)
+        .stdout("[1, 2, 3]\n")
+        .stderr(concat!(
+            "warning: at offset 4: expected a data item, only 0 bytes present\n",
+            "warning: at offset 4: array declared 5 items, only 3 present\n",
+        ));
+}
+
+#[test]
+fn truncated_hex_auto() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--lenient", "--to", "compact"])
+        .write_stdin("82 01 19 01")
+        .assert()
+        .code(2)
+        .stdout("[1]\n")
+        .stderr(concat!(
+            "warning: at offset 4: expected 2 byte argument, only 1 bytes present\n",
+            "warning: at offset 4: array declared 2 items, only 1 present\n",
+        ));
+}
+
+#[test]
+fn complete() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--lenient"])
+        .write_stdin("[1, 2]")
+        .assert()
+        .success()
+        .stdout("[1, 2]\n")
+        .stderr("");
+}
+
+#[test]
+fn strict_by_default() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "bytes"])
+        .write_stdin(&b"\x85\x01\x02\x03"[..])
+        .assert()
+        .failure()
+        .stdout("");
+}
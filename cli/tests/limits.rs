@@ -0,0 +1,26 @@
+use assert_cmd::Command;
+
+#[test]
+fn max_chunks_diag() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "compact", "--max-chunks", "2"])
+        .write_stdin("5f 41 01 41 02 41 03 ff")
+        .assert()
+        .success()
+        .stdout("(_ h'010203' / 3 chunks, 3 bytes total /)\n");
+}
+
+#[test]
+fn max_chunks_annotated() {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "annotated", "--max-chunks", "2"])
+        .write_stdin("5f 41 01 41 02 41 03 ff")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("... (1 chunks, 1 bytes omitted) ..."));
+    assert!(!stdout.contains("02 #"));
+}
@@ -0,0 +1,68 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+#[test]
+fn hex_lines() {
+    cbor_diag()
+        .args(&["--lines"])
+        .write_stdin("a1616101\n\n820102\r\n63616263\n")
+        .assert()
+        .success()
+        .stdout("{\"a\": 1}\n[1, 2]\n\"abc\"\n");
+}
+
+#[test]
+fn diag_lines() {
+    cbor_diag()
+        .args(&["--lines", "--from", "diag", "--to", "hex"])
+        .write_stdin("{\"a\": 1}\n[1, 2]\n  \n\"abc\"")
+        .assert()
+        .success()
+        .stdout("a1616101\n820102\n63616263\n");
+}
+
+#[test]
+fn auto_detects_each_line() {
+    cbor_diag()
+        .args(&["--lines", "--to", "compact"])
+        .write_stdin("01\n1\n[1, 2]\n")
+        .assert()
+        .success()
+        .stdout("1\n1\n[1,2]\n");
+}
+
+#[test]
+fn annotated() {
+    cbor_diag()
+        .args(&["--lines", "--to", "annotated"])
+        .write_stdin("01\n8101\n")
+        .assert()
+        .success()
+        .stdout("01 # unsigned(1)\n81    # array(1)\n   01 #   unsigned(1)\n");
+}
+
+#[test]
+fn errors_continue() {
+    cbor_diag()
+        .args(&["--lines", "--from", "hex"])
+        .write_stdin("01\nzz\n8201\n02\n")
+        .assert()
+        .code(2)
+        .stdout("1\n2\n")
+        .stderr(concat!(
+            "Error: line 2: TODO cbor-diag::Error: Invalid character 'z' in hex at line 1, column 1\n",
+            "Error: line 3: declared length 2 is out of range, only 1 bytes available\n",
+        ));
+}
+
+#[test]
+fn binary_rejected() {
+    cbor_diag()
+        .args(&["--lines", "--from", "bytes"])
+        .write_stdin("01\n")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,63 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+#[test]
+fn truncated() {
+    cbor_diag()
+        .args(&["--to", "compact", "--max-output-bytes", "5"])
+        .write_stdin(r#""abcdefghij""#)
+        .assert()
+        .code(7)
+        .stdout("\"abcd\n... output truncated ...\n")
+        .stderr("Error: output truncated after 5 bytes\n");
+}
+
+#[test]
+fn truncated_at_line_end() {
+    cbor_diag()
+        .args(&["--to", "diag", "--max-output-bytes", "7"])
+        .write_stdin("[1, 2]\n[3, 4]")
+        .args(&["--lines"])
+        .assert()
+        .code(7)
+        .stdout("[1, 2]\n... output truncated ...\n");
+}
+
+#[test]
+fn within_limit() {
+    cbor_diag()
+        .args(&["--to", "diag", "--max-output-bytes", "7"])
+        .write_stdin("[1, 2]")
+        .assert()
+        .success()
+        .stdout("[1, 2]\n");
+}
+
+#[test]
+fn sequence() {
+    cbor_diag()
+        .args(&["--from", "diag", "--seq", "--max-output-bytes", "4"])
+        .args(&["--error-format", "json"])
+        .write_stdin("1 2 3 4 5")
+        .assert()
+        .code(7)
+        .stdout("1\n2\n... output truncated ...\n")
+        .stderr(
+            r#"{"kind":"truncated","code":7,"message":"output truncated after 4 bytes","offset":null,"line":null,"items":2}
+"#,
+        );
+}
+
+#[test]
+fn truncated_annotated() {
+    cbor_diag()
+        .args(&["--to", "annotated", "--max-output-bytes", "40"])
+        .write_stdin(r#"["hello", "world"]"#)
+        .assert()
+        .code(7)
+        .stdout("82               # array(2)\n   65       \n... output truncated ...\n")
+        .stderr("Error: output truncated after 40 bytes\n");
+}
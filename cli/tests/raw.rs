@@ -0,0 +1,54 @@
+use assert_cmd::Command;
+
+fn cbor_diag(terminal: &str) -> Command {
+    let mut command = Command::cargo_bin("cbor-diag").unwrap();
+    command.env("CBOR_DIAG_TERMINAL", terminal);
+    command
+}
+
+#[test]
+fn bytes_to_terminal() {
+    cbor_diag("1")
+        .args(&["--to", "bytes"])
+        .write_stdin("[1, \"\\u001b[2J\"]")
+        .assert()
+        .success()
+        .stdout("8201641b5b324a\n")
+        .stderr(
+            "warning: not writing binary output to a terminal, showing it as hex instead (use \
+             --raw to write it anyway)\n",
+        );
+}
+
+#[test]
+fn raw_to_terminal() {
+    cbor_diag("1")
+        .args(&["--to", "bytes", "--raw"])
+        .write_stdin("[1, 2]")
+        .assert()
+        .success()
+        .stdout(&b"\x82\x01\x02"[..])
+        .stderr("");
+}
+
+#[test]
+fn bytes_to_pipe() {
+    cbor_diag("0")
+        .args(&["--to", "bytes"])
+        .write_stdin("[1, 2]")
+        .assert()
+        .success()
+        .stdout(&b"\x82\x01\x02"[..])
+        .stderr("");
+}
+
+#[test]
+fn text_to_terminal() {
+    cbor_diag("1")
+        .args(&["--to", "compact"])
+        .write_stdin("[1, 2]")
+        .assert()
+        .success()
+        .stdout("[1,2]\n")
+        .stderr("");
+}
@@ -0,0 +1,74 @@
+use assert_cmd::Command;
+
+fn redact(args: &[&str], input: &str) -> String {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "compact"])
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn by_key() {
+    let input = r#"{"user": "bob", "password": "hunter2", 4: h'0102', -1: 5}"#;
+    assert_eq!(
+        redact(&["--redact", "password", "--redact", "4"], input),
+        "{\"user\":\"bob\",\"password\":\"xxxxxxx\",4:h'0000',-1:5}\n"
+    );
+    assert_eq!(
+        redact(&["--redact", "-1"], input),
+        "{\"user\":\"bob\",\"password\":\"hunter2\",4:h'0102',-1:0}\n"
+    );
+}
+
+#[test]
+fn nested_and_wildcards() {
+    let input = r#"{"users": [{"name": "a", "key": h'01'}, {"name": "b", "key": h'0203'}]}"#;
+    assert_eq!(
+        redact(&["--redact", "users.*.key"], input),
+        "{\"users\":[{\"name\":\"a\",\"key\":h'00'},{\"name\":\"b\",\"key\":h'0000'}]}\n"
+    );
+    assert_eq!(
+        redact(&["--redact", "users.1"], input),
+        "{\"users\":[{\"name\":\"a\",\"key\":h'01'},{\"name\":\"x\",\"key\":h'0000'}]}\n"
+    );
+}
+
+#[test]
+fn through_tags() {
+    // A COSE_Sign1 signature, the tag is skipped over
+    let input = "18([h'a10126', {}, h'01', h'0102030405'])";
+    assert_eq!(
+        redact(&["--redact", "3"], input),
+        "18([h'a10126',{},h'01',h'0000000000'])\n"
+    );
+}
+
+#[test]
+fn empty() {
+    assert_eq!(
+        redact(&["--redact", "a", "--redact-empty"], r#"{"a": [1, 2]}"#),
+        "{\"a\":h''}\n"
+    );
+}
+
+#[test]
+fn keeps_size() {
+    let input =
+        "a2 63 6b6579 58 20 0000000000000000000000000000000000000000000000000000000000000001 01 02";
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "hex", "--redact", "key"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "a2636b6579582000000000000000000000000000000000000000000000000000000000000000000102\n"
+    );
+}
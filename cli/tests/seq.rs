@@ -0,0 +1,310 @@
+use assert_cmd::Command;
+
+const SEQ: &[u8] = b"\xa1\x61\x61\x01\x82\x01\x02\x63abc";
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+#[test]
+fn bytes_round_trip() {
+    cbor_diag()
+        .args(&["--seq", "--to", "bytes"])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout(SEQ);
+}
+
+#[test]
+fn bytes_pipe_to_hex() {
+    let bytes = cbor_diag()
+        .args(&["--seq", "--to", "bytes"])
+        .write_stdin(SEQ)
+        .output()
+        .unwrap()
+        .stdout;
+
+    cbor_diag()
+        .args(&["--seq", "--to", "hex"])
+        .write_stdin(bytes)
+        .assert()
+        .success()
+        .stdout("a1616101\n820102\n63616263\n");
+}
+
+#[test]
+fn bytes_ignores_delimiter() {
+    cbor_diag()
+        .args(&["--seq", "--to", "bytes", "--delimiter", ","])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout(SEQ);
+}
+
+#[test]
+fn compact_delimiter() {
+    cbor_diag()
+        .args(&["--seq", "--to", "compact", "--delimiter", ", "])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout("{\"a\":1}, [1,2], \"abc\"\n");
+}
+
+#[test]
+fn hex_escaped_delimiter() {
+    cbor_diag()
+        .args(&["--seq", "--to", "hex", "--delimiter", "\\0"])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout("a1616101\x00820102\x0063616263\n");
+}
+
+#[test]
+fn diag_single_newline() {
+    cbor_diag()
+        .args(&["--seq", "--to", "diag"])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout("{\"a\": 1}\n[1, 2]\n\"abc\"\n");
+}
+
+#[test]
+fn invalid_delimiter_escape() {
+    cbor_diag()
+        .args(&["--seq", "--delimiter", "\\q"])
+        .write_stdin(SEQ)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn two_items_every_format() {
+    let cases: &[(&str, &[u8])] = &[
+        ("bytes", b"\x82\x01\x02\x61a"),
+        ("hex", b"820102\n6161\n"),
+        ("diag", b"[1, 2]\n\"a\"\n"),
+        ("compact", b"[1,2]\n\"a\"\n"),
+        (
+            "annotated",
+            b"82    # array(2)\n   01 #   unsigned(1)\n   02 #   unsigned(2)\n61    # text(1)\n   61 #   \"a\"\n",
+        ),
+    ];
+    for &(to, expected) in cases {
+        cbor_diag()
+            .args(&["--seq", "--to", to])
+            .write_stdin(&b"\x82\x01\x02\x61a"[..])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn single_item_every_format() {
+    let cases: &[(&str, &[u8])] = &[
+        ("bytes", b"\x82\x01\x02"),
+        ("hex", b"820102\n"),
+        ("diag", b"[1, 2]\n"),
+        ("compact", b"[1,2]\n"),
+        (
+            "annotated",
+            b"82    # array(2)\n   01 #   unsigned(1)\n   02 #   unsigned(2)\n",
+        ),
+    ];
+    for &(to, expected) in cases {
+        cbor_diag()
+            .args(&["--to", to])
+            .write_stdin(&b"\x82\x01\x02"[..])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn seq_as_array() {
+    cbor_diag()
+        .args(&["--seq", "--seq-as-array"])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout("[{\"a\": 1}, [1, 2], \"abc\"]\n");
+
+    cbor_diag()
+        .args(&["--seq", "--seq-as-array", "--to", "hex"])
+        .write_stdin(SEQ)
+        .assert()
+        .success()
+        .stdout("83a161610182010263616263\n");
+
+    cbor_diag()
+        .args(&["--seq", "--seq-as-array", "--to", "compact"])
+        .write_stdin(&b""[..])
+        .assert()
+        .success()
+        .stdout("[]\n");
+}
+
+#[test]
+fn split_array() {
+    cbor_diag()
+        .args(&["--from", "diag", "--to", "bytes", "--split-array"])
+        .write_stdin(r#"[{"a": 1}, [1, 2], "abc"]"#)
+        .assert()
+        .success()
+        .stdout(SEQ);
+
+    cbor_diag()
+        .args(&["--from", "diag", "--to", "compact", "--split-array"])
+        .write_stdin(r#"[_ {"a": 1}, [1, 2]]"#)
+        .assert()
+        .success()
+        .stdout("{\"a\":1}\n[1,2]\n");
+
+    cbor_diag()
+        .args(&["--from", "diag", "--split-array"])
+        .write_stdin("[]")
+        .assert()
+        .success()
+        .stdout("");
+
+    cbor_diag()
+        .args(&["--from", "diag", "--split-array"])
+        .write_stdin("1")
+        .assert()
+        .code(2)
+        .stderr("Error: TODO cbor-diag::Error: expected an array to split into a sequence\n");
+}
+
+#[test]
+fn seq_from_hex() {
+    cbor_diag()
+        .args(&["--seq", "--from", "hex", "--to", "compact"])
+        .write_stdin("a1 61 61 01  # {\"a\": 1}\n820102\n636162 63\n")
+        .assert()
+        .success()
+        .stdout("{\"a\":1}\n[1,2]\n\"abc\"\n");
+
+    cbor_diag()
+        .args(&["--seq", "--from", "hex"])
+        .write_stdin("01 8201")
+        .assert()
+        .code(2)
+        .stdout("1\n")
+        .stderr("Error: 2 bytes remaining after last item\n");
+}
+
+#[test]
+fn seq_from_diag() {
+    cbor_diag()
+        .args(&["--seq", "--from", "diag", "--to", "hex"])
+        .write_stdin("{\"a\": 1}\n[1, 2] / third / \"abc\"\n")
+        .assert()
+        .success()
+        .stdout("a1616101\n820102\n63616263\n");
+
+    cbor_diag()
+        .args(&[
+            "--seq",
+            "--seq-as-array",
+            "--from",
+            "diag",
+            "--to",
+            "compact",
+        ])
+        .write_stdin("1 2\n3")
+        .assert()
+        .success()
+        .stdout("[1,2,3]\n");
+
+    cbor_diag()
+        .args(&[
+            "--seq",
+            "--from",
+            "diag",
+            "--to",
+            "compact",
+            "--error-format",
+            "json",
+        ])
+        .write_stdin("1 [2")
+        .assert()
+        .code(2)
+        .stdout("1")
+        .stderr(concat!(
+            r#"{"kind":"parse","code":2,"message":"TODO cbor-diag::Error: Parsing error (Error((\"[2\", Tag)))","#,
+            r#""offset":2,"line":null,"items":1}"#,
+            "\n"
+        ));
+}
+
+#[test]
+fn stop_at_break() {
+    cbor_diag()
+        .args(&["--seq", "--stop-at-break", "--to", "compact"])
+        .write_stdin(&b"\x01\x9f\x02\xff\xff"[..])
+        .assert()
+        .success()
+        .stdout("1\n[_2]\n");
+
+    cbor_diag()
+        .args(&[
+            "--seq",
+            "--stop-at-break",
+            "--from",
+            "hex",
+            "--to",
+            "compact",
+        ])
+        .write_stdin("ff")
+        .assert()
+        .success()
+        .stdout("");
+
+    cbor_diag()
+        .args(&["--seq", "--stop-at-break", "--to", "compact"])
+        .write_stdin(&b"\x01\xff\x02"[..])
+        .assert()
+        .code(2)
+        .stdout("1\n")
+        .stderr("Error: 1 bytes remaining after the break\n");
+
+    // A break where an item is required is still an error
+    cbor_diag()
+        .args(&["--seq", "--stop-at-break", "--to", "compact"])
+        .write_stdin(&b"\x01\x82\x01\xff"[..])
+        .assert()
+        .code(2)
+        .stdout("1")
+        .stderr("Error: TODO cbor-diag::Error: Parsing error at offset 2: unexpected break\n");
+
+    cbor_diag()
+        .args(&["--seq", "--to", "compact"])
+        .write_stdin(&b"\x01\xff"[..])
+        .assert()
+        .code(2)
+        .stdout("1")
+        .stderr("Error: TODO cbor-diag::Error: Parsing error at offset 0: unexpected break\n");
+}
+
+#[test]
+fn stop_at_break_single_item() {
+    cbor_diag()
+        .args(&["--stop-at-break", "--to", "compact"])
+        .write_stdin(&b"\x82\x01\x02\xff"[..])
+        .assert()
+        .success()
+        .stdout("[1,2]\n");
+
+    cbor_diag()
+        .args(&["--stop-at-break", "--from", "hex", "--to", "compact"])
+        .write_stdin("01ff")
+        .assert()
+        .success()
+        .stdout("1\n");
+}
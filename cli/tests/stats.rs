@@ -0,0 +1,32 @@
+use assert_cmd::Command;
+
+#[test]
+fn stats() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "stats", "--stats-top", "3"])
+        .write_stdin(r#"[1, 32("http://a"), (_ h'0102')]"#)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "total: 18 bytes (8 header, 10 payload)\n",
+            "\n",
+            "largest subtrees:\n",
+            "   bytes  header  payload        %  path\n",
+            "      18       8       10   100.0%  root\n",
+            "      11       3        8    61.1%  [1]\n",
+            "       9       1        8    50.0%  [1, 0]\n",
+            "\n",
+            "by major type:\n",
+            "  type             count  header  payload        %\n",
+            "  0 unsigned           1       1        0     5.6%\n",
+            "  2 bytes              2       3        2    27.8%\n",
+            "  3 text               1       1        8    50.0%\n",
+            "  4 array              1       1        0     5.6%\n",
+            "  6 tag                1       2        0    11.1%\n",
+            "\n",
+            "by tag (including tagged items):\n",
+            "  tag              count   bytes        %\n",
+            "  32                   1      11    61.1%\n",
+        ));
+}
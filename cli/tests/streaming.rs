@@ -0,0 +1,188 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn input_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn annotated(args: &[&str], path: &PathBuf) -> Vec<u8> {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated"])
+        .args(args)
+        .arg(path)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone()
+}
+
+#[test]
+fn same_output() {
+    let path = input_file(
+        "streaming-same-output.cbor",
+        &hex::decode("a2016361626302d8209f4201024103ff").unwrap(),
+    );
+    for args in &[&[][..], &["--hex-width", "1"], &["--max-chunks", "1"]] {
+        let streamed = annotated(&[&["--streaming"], *args].concat(), &path);
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            String::from_utf8(annotated(args, &path)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn large_file() {
+    // Over the size that is streamed automatically
+    let mut data = vec![0x5a, 0x01, 0x10, 0x00, 0x00];
+    data.resize(5 + 0x0110_0000, 0);
+    let path = input_file("streaming-large-file.cbor", &data);
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--max-bytes-lines", "2"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "note: {} was streamed because of its size, so warnings such as duplicate map keys \
+             were not checked\n",
+            path.display()
+        )
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 4);
+    assert!(stdout.starts_with("5a 01100000 "));
+    assert!(stdout.contains("#   ... (17,825,760 bytes omitted) ...\n"));
+
+    // The note is hidden by `--quiet`
+    let output = annotated(&["--max-bytes-lines", "2", "--quiet"], &path);
+    assert_eq!(String::from_utf8(output).unwrap(), stdout);
+
+    // Problems are reported as they are for smaller files
+    data.push(0);
+    let path = input_file("streaming-large-file-trailing.cbor", &data);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--from", "bytes"])
+        .arg(&path)
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr(
+            "Error: parsed 1 item (17825797 bytes), 1 trailing bytes starting with 0x00 (the \
+             trailing bytes start another item, use --seq to parse a sequence of items)\n",
+        );
+}
+
+#[test]
+fn malformed() {
+    let path = input_file("streaming-malformed.cbor", b"\x82\x01");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--streaming"])
+        .arg(&path)
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(
+            "Error: TODO cbor-diag::Error: Parsing error at offset 2: expected a data item, only 0 bytes present\n",
+        );
+}
+
+#[test]
+fn unsupported() {
+    let path = input_file("streaming-unsupported.cbor", b"\x01");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--streaming"])
+        .write_stdin(&b"\x01"[..])
+        .assert()
+        .code(1)
+        .stderr("Error: --streaming needs a binary input file\n");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--streaming", "--from", "hex"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr("Error: --streaming needs a binary input file\n");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "diag", "--streaming"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr("Error: --streaming is only supported with --to annotated\n");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--streaming", "--canonical"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr("Error: --streaming can't be combined with options that need the whole item\n");
+}
+
+/// A large byte string of zeros within `prefix` and `suffix`, over the size that would be
+/// streamed automatically.
+fn large_item(name: &str, prefix: &[u8], suffix: &[u8]) -> PathBuf {
+    let mut data = prefix.to_vec();
+    data.extend_from_slice(&[0x5a, 0x01, 0x10, 0x00, 0x00]);
+    data.resize(data.len() + 0x0110_0000, 0);
+    data.extend_from_slice(suffix);
+    input_file(name, &data)
+}
+
+#[test]
+fn large_file_whole_item() {
+    // {1: h'00...', 1: 0}
+    let path = large_item("streaming-large-file-map.cbor", b"\xa2\x01", b"\x01\x00");
+
+    let expect = input_file("streaming-large-file-expect.diag", b"1");
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "annotated", "--quiet", "--expect"])
+        .arg(&expect)
+        .arg(&path)
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    assert!(output.starts_with(format!("--- {}\n", expect.display()).as_bytes()));
+
+    // Warnings are only found when the whole item is read
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&[
+            "--to",
+            "annotated",
+            "--max-bytes-lines",
+            "2",
+            "--deny-warnings",
+        ])
+        .arg(&path)
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(
+            "warning: at offset 17825799: duplicate map key 1\n\
+             Error: 1 warning denied by --deny-warnings\n",
+        );
+
+    // [h'00...', 1]
+    let path = large_item("streaming-large-file-array.cbor", b"\x82", b"\x01");
+    let output = annotated(
+        &["--quiet", "--split-array", "--max-bytes-lines", "2"],
+        &path,
+    );
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("5a 01100000 "));
+    assert!(output.ends_with("\n01 # unsigned(1)\n"));
+}
@@ -0,0 +1,50 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+fn error(args: &[&str], input: &[u8]) -> String {
+    let output = cbor_diag()
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .code(2)
+        .get_output()
+        .clone();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn accepted_by_default() {
+    cbor_diag()
+        .args(&["--from", "diag", "--to", "compact"])
+        .write_stdin("0(5)")
+        .assert()
+        .success()
+        .stdout("0(5)\n");
+}
+
+#[test]
+fn rejected_diag() {
+    let stderr = error(&["--from", "diag", "--strict-tags"], b"[1, 32(h'00')]");
+    assert!(stderr.contains("at offset 4: tag 32 (URI) expects a text string, found a byte string"));
+}
+
+#[test]
+fn rejected_bytes() {
+    let stderr = error(&["--from", "bytes", "--strict-tags"], b"\x81\xc2\x61\x00");
+    assert!(stderr.contains(
+        "at offset 1: tag 2 (positive bignum) expects a byte string, found a text string"
+    ));
+}
+
+#[test]
+fn valid_content() {
+    cbor_diag()
+        .args(&["--from", "diag", "--to", "compact", "--strict-tags"])
+        .write_stdin(r#"[0("2013-03-21T20:04:00Z"), 1(1.5), 4([-2, 2(h'01')]), 21({1: 2})]"#)
+        .assert()
+        .success()
+        .stdout("[0(\"2013-03-21T20:04:00Z\"),1(1.5),4([-2,2(h'01')]),21({1:2})]\n");
+}
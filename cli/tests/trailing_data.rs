@@ -0,0 +1,29 @@
+use assert_cmd::Command;
+
+fn stderr(input: &[u8], args: &[&str]) -> String {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn trailing_item() {
+    assert!(
+        stderr(b"\x82\x01\x02\x1a\x00\x00\x00\x01", &["--from", "bytes"]).contains(
+            "parsed 1 item (3 bytes), 5 trailing bytes starting with 0x1a00000001 \
+             (the trailing bytes start another item, use --seq to parse a sequence of items)\n"
+        )
+    );
+}
+
+#[test]
+fn trailing_garbage() {
+    assert!(stderr(b"\x01\xff\xfe\xfd\xfc\xfb\xfa\xf9\xf8\xf7", &[])
+        .contains("parsed 1 item (1 bytes), 9 trailing bytes starting with 0xfffefdfcfbfaf9f8…\n"));
+}
@@ -0,0 +1,34 @@
+use assert_cmd::Command;
+
+#[test]
+fn tree() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--to", "tree", "--tree-width", "4"])
+        .write_stdin(r#"[1, "hello"]"#)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "array(2), 8 bytes\n",
+            "├── unsigned 1, 1 byte\n",
+            "└── text(5) \"hel…, 6 bytes\n",
+        ));
+}
+
+#[test]
+fn dot() {
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--to", "dot"])
+        .write_stdin("8101")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "digraph cbor {\n",
+            "    node [shape=box];\n",
+            "    n0 [label=\"array(1)\\n2 bytes\"];\n",
+            "    n1 [label=\"unsigned 1\\n1 byte\"];\n",
+            "    n0 -> n1;\n",
+            "}\n",
+        ));
+}
@@ -0,0 +1,145 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn expect_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn matches() {
+    let path = expect_file("verify-matches.diag", br#"{"a": [1, 2], "b": h'00'}"#);
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--expect")
+        .arg(&path)
+        .write_stdin(&b"\xa2\x61a\x82\x01\x18\x02\x61b\x41\x00"[..])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn mismatch() {
+    let path = expect_file(
+        "verify-mismatch.diag",
+        br#"{"name": "a fairly long text string", "values": [1, 2], "more": h'00'}"#,
+    );
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--from", "hex", "--expect-from", "diag", "--expect"])
+        .arg(&path)
+        .write_stdin(concat!(
+            "a3 646e616d65 78196120666169726c79206c6f6e67207465787420737472696e67",
+            "   6676616c756573 820103 646d6f7265 4100",
+        ))
+        .assert()
+        .code(1)
+        .stdout(format!(
+            concat!(
+                "--- {}\n",
+                "+++ stdin\n",
+                " {{\n",
+                "     \"name\": \"a fairly long text string\",\n",
+                "-    \"values\": [1, 2],\n",
+                "+    \"values\": [1, 3],\n",
+                "     \"more\": h'00',\n",
+                " }}\n",
+            ),
+            path.display()
+        ));
+}
+
+#[test]
+fn strict() {
+    let path = expect_file("verify-strict.hex", b"82 01 1802");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--expect")
+        .arg(&path)
+        .write_stdin("[1, 2]")
+        .assert()
+        .success();
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--strict")
+        .arg("--expect")
+        .arg(&path)
+        .write_stdin("[1, 2]")
+        .assert()
+        .code(1)
+        .stdout(format!(
+            "--- {}\n+++ stdin\n-[1, 2_0]\n+[1, 2]\n",
+            path.display()
+        ));
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--strict")
+        .arg("--expect")
+        .arg(&path)
+        .write_stdin("[1, 2_0]")
+        .assert()
+        .success();
+}
+
+#[test]
+fn widths_ignored_in_diff() {
+    let path = expect_file("verify-widths.diag", b"[1.5_1, (_ h'01'_0), 1]");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--expect")
+        .arg(&path)
+        .write_stdin("[1.5_2, (_ h'01'_1), 2]")
+        .assert()
+        .code(1)
+        .stdout(format!(
+            "--- {}\n+++ stdin\n-[1.5, (_ h'01'), 1]\n+[1.5, (_ h'01'), 2]\n",
+            path.display()
+        ));
+}
+
+#[test]
+fn missing_file() {
+    let output = Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .args(&["--expect", "does-not-exist.diag"])
+        .write_stdin("1")
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("failed to read does-not-exist.diag"));
+}
+
+#[test]
+fn input_name() {
+    let path = expect_file("verify-input-name.diag", b"[1, 2]");
+    let input = expect_file("verify-input-name.hex", b"820103");
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--expect")
+        .arg(&path)
+        .arg(&input)
+        .assert()
+        .code(1)
+        .stdout(format!(
+            "--- {}\n+++ {}\n-[1, 2]\n+[1, 3]\n",
+            path.display(),
+            input.display()
+        ));
+
+    Command::cargo_bin("cbor-diag")
+        .unwrap()
+        .arg("--expect")
+        .arg(&path)
+        .args(&["--data", "[1, 3]"])
+        .assert()
+        .code(1)
+        .stdout(format!(
+            "--- {}\n+++ argument\n-[1, 2]\n+[1, 3]\n",
+            path.display()
+        ));
+}
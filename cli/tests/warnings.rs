@@ -0,0 +1,94 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+fn run(args: &[&str], input: &str) -> (Option<i32>, String, String) {
+    let output = cbor_diag()
+        .args(&["--to", "compact"])
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+const INPUT: &str = "{1: 0(2), 1: 3}";
+
+#[test]
+fn printed() {
+    assert_eq!(
+        run(&[], INPUT),
+        (
+            Some(0),
+            "{1:0(2),1:3}\n".to_owned(),
+            concat!(
+                "warning: at offset 4: tag 0 (date/time) expects a text string, found an unsigned integer\n",
+                "warning: at offset 10: duplicate map key 1\n",
+            )
+            .to_owned()
+        )
+    );
+    assert_eq!(
+        run(&["--from", "hex"], "a201c0020103"),
+        (
+            Some(0),
+            "{1:0(2),1:3}\n".to_owned(),
+            concat!(
+                "warning: at offset 2: tag 0 (date/time) expects a text string, found an unsigned integer\n",
+                "warning: at offset 4: duplicate map key 1\n",
+            )
+            .to_owned()
+        )
+    );
+}
+
+#[test]
+fn quiet() {
+    assert_eq!(
+        run(&["--quiet"], INPUT),
+        (Some(0), "{1:0(2),1:3}\n".to_owned(), String::new())
+    );
+}
+
+#[test]
+fn denied() {
+    let (code, stdout, stderr) = run(&["--deny-warnings"], INPUT);
+    assert_eq!(code, Some(2));
+    assert_eq!(stdout, "");
+    assert!(stderr.ends_with("Error: 2 warnings denied by --deny-warnings\n"));
+    assert_eq!(stderr.lines().count(), 3);
+
+    assert_eq!(
+        run(&["--deny-warnings", "--quiet"], "{1: 2, 1: 3}"),
+        (
+            Some(2),
+            String::new(),
+            "Error: 1 warning denied by --deny-warnings\n".to_owned()
+        )
+    );
+
+    assert_eq!(
+        run(&["--deny-warnings"], "{1: 2, 3: 4}"),
+        (Some(0), "{1:2,3:4}\n".to_owned(), String::new())
+    );
+}
+
+#[test]
+fn lines() {
+    let (code, stdout, stderr) = run(
+        &["--lines", "--deny-warnings"],
+        "{1: 2}\n{1: 2, 1: 3}\n[]\n",
+    );
+    assert_eq!(code, Some(2));
+    assert_eq!(stdout, "{1:2}\n[]\n");
+    assert_eq!(
+        stderr,
+        "warning: at offset 7: duplicate map key 1\nError: line 2: 1 warning denied by --deny-warnings\n"
+    );
+}
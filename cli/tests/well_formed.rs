@@ -0,0 +1,61 @@
+use assert_cmd::Command;
+
+fn cbor_diag() -> Command {
+    Command::cargo_bin("cbor-diag").unwrap()
+}
+
+#[test]
+fn well_formed() {
+    cbor_diag()
+        .arg("--check-well-formed")
+        .write_stdin(&b"\x82\x01\x61a"[..])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+}
+
+#[test]
+fn malformed() {
+    cbor_diag()
+        .arg("--check-well-formed")
+        .write_stdin(&b"\x82\x01"[..])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr("Error: TODO cbor-diag::Error: Parsing error at offset 2: expected a data item, only 0 bytes present\n");
+    cbor_diag()
+        .arg("--check-well-formed")
+        .write_stdin(&b"\x01\x02"[..])
+        .assert()
+        .code(2)
+        .stdout("");
+}
+
+#[test]
+fn sequence() {
+    for input in &[&b""[..], b"\x01\x02", b"\x82\x01\x02\x61a"] {
+        cbor_diag()
+            .args(&["--check-well-formed", "--seq"])
+            .write_stdin(*input)
+            .assert()
+            .success()
+            .stdout("");
+    }
+    cbor_diag()
+        .args(&["--check-well-formed", "--seq"])
+        .write_stdin(&b"\x01\x82\x02"[..])
+        .assert()
+        .code(2)
+        .stdout("");
+}
+
+#[test]
+fn needs_binary_input() {
+    cbor_diag()
+        .args(&["--check-well-formed", "--from", "hex"])
+        .write_stdin("01")
+        .assert()
+        .code(1)
+        .stderr("Error: --check-well-formed needs binary input\n");
+}
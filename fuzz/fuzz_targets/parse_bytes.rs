@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(item) = cbor_diag::parse_bytes(data) {
+        let _ = item.to_hex();
+        let _ = item.to_diag_pretty();
+        cbor_diag::parse_bytes(item.to_bytes()).unwrap();
+    }
+});
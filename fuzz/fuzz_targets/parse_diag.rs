@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(item) = cbor_diag::parse_diag(data) {
+        let _ = item.to_bytes();
+        let _ = item.to_hex();
+        let _ = item.to_diag_pretty();
+    }
+});
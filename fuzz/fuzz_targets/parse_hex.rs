@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(item) = cbor_diag::parse_hex(data) {
+        let _ = item.to_diag_pretty();
+        cbor_diag::parse_hex(item.to_hex()).unwrap();
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use cbor_diag::DataItem;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|item: DataItem| {
+    cbor_diag::roundtrip::all(&item);
+});
@@ -0,0 +1,163 @@
+//! Implementation of [`arbitrary::Arbitrary`] for [`DataItem`], for use in
+//! fuzzing.
+//!
+//! Only data items that can be exactly roundtripped through the binary
+//! encoding are generated: all widths are known and large enough for the
+//! values they apply to, simple values are never in the reserved range and
+//! floats are never NaN (as that would compare unequal to itself).
+
+use std::{cmp, convert::TryFrom};
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use half::f16;
+
+use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString};
+
+/// How deeply nested containers and tags can be.
+const MAX_DEPTH: usize = 8;
+
+/// The maximum number of items in a single container or indefinite string.
+const MAX_ITEMS: u64 = 16;
+
+fn integer_width(u: &mut Unstructured<'_>) -> Result<IntegerWidth> {
+    Ok(*u.choose(&[
+        IntegerWidth::Zero,
+        IntegerWidth::Eight,
+        IntegerWidth::Sixteen,
+        IntegerWidth::ThirtyTwo,
+        IntegerWidth::SixtyFour,
+    ])?)
+}
+
+fn integer_width_max(width: IntegerWidth) -> u64 {
+    match width {
+        IntegerWidth::Unknown => unreachable!(),
+        IntegerWidth::Zero => 23,
+        IntegerWidth::Eight => u8::max_value().into(),
+        IntegerWidth::Sixteen => u16::max_value().into(),
+        IntegerWidth::ThirtyTwo => u32::max_value().into(),
+        IntegerWidth::SixtyFour => u64::max_value(),
+    }
+}
+
+fn integer(u: &mut Unstructured<'_>) -> Result<(u64, IntegerWidth)> {
+    let bitwidth = integer_width(u)?;
+    let value = u.int_in_range(0..=integer_width_max(bitwidth))?;
+    Ok((value, bitwidth))
+}
+
+/// A width suitable for a string or container, and the maximum number of
+/// items to generate for it.
+fn length_width(u: &mut Unstructured<'_>, limit: u64) -> Result<(IntegerWidth, usize)> {
+    let bitwidth = integer_width(u)?;
+    let max = cmp::min(integer_width_max(bitwidth), limit);
+    Ok((bitwidth, usize::try_from(max).unwrap_or(usize::max_value())))
+}
+
+fn bytestring(u: &mut Unstructured<'_>) -> Result<ByteString> {
+    let (bitwidth, max) = length_width(u, u.len() as u64)?;
+    let len = cmp::min(u.int_in_range(0..=max)?, u.len());
+    let data = u.bytes(len)?.to_owned();
+    Ok(ByteString {
+        data,
+        bitwidth,
+        encoding: None,
+    })
+}
+
+fn textstring(u: &mut Unstructured<'_>) -> Result<TextString> {
+    let (bitwidth, max) = length_width(u, u.len() as u64)?;
+    let mut data = String::arbitrary(u)?;
+    let mut len = cmp::min(data.len(), max);
+    while !data.is_char_boundary(len) {
+        len -= 1;
+    }
+    data.truncate(len);
+    Ok(TextString { data, bitwidth })
+}
+
+fn float(u: &mut Unstructured<'_>) -> Result<DataItem> {
+    let bitwidth = *u.choose(&[
+        FloatWidth::Sixteen,
+        FloatWidth::ThirtyTwo,
+        FloatWidth::SixtyFour,
+    ])?;
+    let value = match bitwidth {
+        FloatWidth::Unknown => unreachable!(),
+        FloatWidth::Sixteen => f16::from_bits(u16::arbitrary(u)?).to_f64(),
+        FloatWidth::ThirtyTwo => f32::from_bits(u32::arbitrary(u)?).into(),
+        FloatWidth::SixtyFour => f64::from_bits(u64::arbitrary(u)?),
+    };
+    let value = if value.is_nan() { 0.0 } else { value };
+    Ok(DataItem::Float { value, bitwidth })
+}
+
+fn simple(u: &mut Unstructured<'_>) -> Result<Simple> {
+    let value = u.int_in_range(0..=247)?;
+    Ok(Simple(if value < 24 { value } else { value + 8 }))
+}
+
+fn items<T>(
+    u: &mut Unstructured<'_>,
+    max: usize,
+    mut item: impl FnMut(&mut Unstructured<'_>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=cmp::min(max, MAX_ITEMS as usize))?;
+    (0..len).map(|_| item(u)).collect()
+}
+
+fn definite_length(u: &mut Unstructured<'_>) -> Result<(Option<IntegerWidth>, usize)> {
+    if bool::arbitrary(u)? {
+        let (bitwidth, max) = length_width(u, MAX_ITEMS)?;
+        Ok((Some(bitwidth), max))
+    } else {
+        Ok((None, MAX_ITEMS as usize))
+    }
+}
+
+fn data_item(u: &mut Unstructured<'_>, depth: usize) -> Result<DataItem> {
+    let choices = if depth < MAX_DEPTH { 11 } else { 8 };
+    Ok(match u.choose_index(choices)? {
+        0 => {
+            let (value, bitwidth) = integer(u)?;
+            DataItem::Integer { value, bitwidth }
+        }
+        1 => {
+            let (value, bitwidth) = integer(u)?;
+            DataItem::Negative { value, bitwidth }
+        }
+        2 => DataItem::ByteString(bytestring(u)?),
+        3 => DataItem::IndefiniteByteString(items(u, MAX_ITEMS as usize, bytestring)?),
+        4 => DataItem::TextString(textstring(u)?),
+        5 => DataItem::IndefiniteTextString(items(u, MAX_ITEMS as usize, textstring)?),
+        6 => float(u)?,
+        7 => DataItem::Simple(simple(u)?),
+        8 => {
+            let (bitwidth, max) = definite_length(u)?;
+            let data = items(u, max, |u| data_item(u, depth + 1))?;
+            DataItem::Array { data, bitwidth }
+        }
+        9 => {
+            let (bitwidth, max) = definite_length(u)?;
+            let data = items(u, max, |u| {
+                Ok((data_item(u, depth + 1)?, data_item(u, depth + 1)?))
+            })?;
+            DataItem::Map { data, bitwidth }
+        }
+        10 => {
+            let (tag, bitwidth) = integer(u)?;
+            DataItem::Tag {
+                tag: Tag(tag),
+                bitwidth,
+                value: Box::new(data_item(u, depth + 1)?),
+            }
+        }
+        _ => unreachable!(),
+    })
+}
+
+impl<'a> Arbitrary<'a> for DataItem {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        data_item(u, 0)
+    }
+}
@@ -0,0 +1,66 @@
+//! Base32 and base32hex from RFC 4648, used by the `b32''` and `h32''` byte
+//! string literals of RFC 8610 appendix G.2.
+
+/// The base32 alphabet, RFC 4648 § 6.
+pub(crate) const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The base32hex alphabet, RFC 4648 § 7.
+pub(crate) const BASE32HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// Encode `data` with `alphabet`, without padding.
+pub(crate) fn encode(data: &[u8], alphabet: &[u8; 32]) -> String {
+    let mut output = String::with_capacity((data.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &byte in data {
+        buffer = buffer << 8 | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(char::from(alphabet[usize::from(buffer >> bits & 31)]));
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        output.push(char::from(alphabet[usize::from(buffer << (5 - bits) & 31)]));
+    }
+    output
+}
+
+/// Decode `text` with `alphabet`, ignoring case and whitespace, with optional
+/// padding.
+///
+/// Returns `None` for characters outside the alphabet, incorrect padding, a
+/// length that can't be produced by encoding, or unused bits that are not
+/// zero.
+pub(crate) fn decode(text: &str, alphabet: &[u8; 32]) -> Option<Vec<u8>> {
+    let text: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    let padding = text.iter().rev().take_while(|&&byte| byte == b'=').count();
+    let digits = &text[..text.len() - padding];
+    if padding > 0 && !text.len().is_multiple_of(8) {
+        return None;
+    }
+    if let 1 | 3 | 6 = digits.len() % 8 {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(digits.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &digit in digits {
+        let digit = digit.to_ascii_uppercase();
+        let value = alphabet.iter().position(|&c| c == digit)?;
+        buffer = buffer << 5 | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if buffer != 0 {
+        return None;
+    }
+    Some(data)
+}
@@ -0,0 +1,740 @@
+//! A minimal structural validator for [CDDL][RFC 8610] schemas.
+//!
+//! Only a subset of CDDL is supported:
+//!
+//!  * rules of the form `name = type`, the first rule is the one the data
+//!    item is validated against
+//!  * maps `{ ... }` and arrays `[ ... ]` of group entries, with keys given as
+//!    barewords `name: type`, literals `"name": type` or `1: type`, or types
+//!    `tstr => type`, separated by optional commas
+//!  * the occurrence indicators `?`, `*` and `+` on group entries
+//!  * type choices with `/`, and parenthesized types
+//!  * tags `#6.N(type)` and `#6.N`
+//!  * integer, float and text string literals
+//!  * the prelude types `any`, `uint`, `nint`, `int`, `bstr`, `bytes`,
+//!    `tstr`, `text`, `bool`, `true`, `false`, `nil`, `null`, `undefined`,
+//!    `float`, `float16`, `float32`, `float64`, `number`, `tdate`, `time`,
+//!    `biguint`, `bignint` and `bigint`
+//!
+//! Map keys are matched with cut semantics: once an entry's key matches a
+//! group entry its value must match that entry's type. Arrays are matched
+//! greedily, without backtracking.
+//!
+//! [RFC 8610]: https://www.rfc-editor.org/rfc/rfc8610.html
+
+use std::fmt;
+
+use crate::{DataItem, FloatWidth, Simple, Tag};
+
+/// A way in which a data item does not match a schema, see [`validate`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct CddlViolation {
+    /// The path of the item the violation is for, as used by
+    /// [`Comments`](crate::Comments).
+    pub path: Vec<usize>,
+    /// The part of the schema that was violated, as written in it.
+    pub rule: String,
+    /// What went wrong.
+    pub kind: ViolationKind,
+}
+
+/// The kinds of [`CddlViolation`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// The schema itself is not valid, or uses unsupported features; the
+    /// message describes the problem.
+    InvalidSchema(String),
+    /// The item is not of the type or value the rule requires.
+    Mismatch,
+    /// The array or map is missing an element or entry the rule requires.
+    Missing,
+    /// The array element or map entry (identified by the path of its key) is
+    /// not allowed by the rule.
+    Unexpected,
+}
+
+impl CddlViolation {
+    fn new(path: &[usize], rule: &str, kind: ViolationKind) -> Self {
+        Self {
+            path: path.to_owned(),
+            rule: rule.to_owned(),
+            kind,
+        }
+    }
+
+    fn invalid_schema(message: impl Into<String>) -> Self {
+        Self::new(&[], "", ViolationKind::InvalidSchema(message.into()))
+    }
+}
+
+impl fmt::Display for CddlViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let ViolationKind::InvalidSchema(message) = &self.kind {
+            return write!(f, "invalid schema: {}", message);
+        }
+        if self.path.is_empty() {
+            f.write_str("at root: ")?;
+        } else {
+            write!(f, "at {:?}: ", self.path)?;
+        }
+        match self.kind {
+            ViolationKind::InvalidSchema(_) => unreachable!(),
+            ViolationKind::Mismatch => write!(f, "does not match `{}`", self.rule),
+            ViolationKind::Missing => write!(f, "missing `{}`", self.rule),
+            ViolationKind::Unexpected => write!(f, "not allowed by `{}`", self.rule),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Type {
+    choices: Vec<Type2>,
+    /// The source of this type, for violations
+    text: String,
+}
+
+#[derive(Debug)]
+enum Type2 {
+    Integer(i128),
+    Float(f64),
+    Text(String),
+    Name(String),
+    Map(Vec<Entry>),
+    Array(Vec<Entry>),
+    Tag(u64, Option<Box<Type>>),
+    Parenthesized(Box<Type>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    min: usize,
+    max: Option<usize>,
+    key: Option<Type>,
+    value: Type,
+    /// The source of this entry, for violations
+    text: String,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+type ParseResult<T> = Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error<T>(&self, message: &str) -> ParseResult<T> {
+        let line = self.input[..self.pos].matches('\n').count() + 1;
+        Err(format!("{} at line {}", message, line))
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with(';') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.error(&format!("expected `{}`", token))
+        }
+    }
+
+    /// The source parsed since `start` on a single line, without comments.
+    fn text_since(&self, start: usize) -> String {
+        let mut text = String::new();
+        let mut chars = self.input[start..self.pos].chars();
+        let mut quote = None;
+        let mut space = false;
+        while let Some(c) = chars.next() {
+            if space && !c.is_whitespace() && c != ';' {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                space = false;
+            }
+            match (quote, c) {
+                (None, ';') => {
+                    chars.by_ref().find(|&c| c == '\n');
+                    space = true;
+                }
+                (None, c) if c.is_whitespace() => space = true,
+                (None, '"') | (None, '\'') => {
+                    quote = Some(c);
+                    text.push(c);
+                }
+                (Some(_), '\\') => {
+                    text.push(c);
+                    text.extend(chars.next());
+                }
+                (Some(q), c) => {
+                    if c == q {
+                        quote = None;
+                    }
+                    text.push(c);
+                }
+                (None, c) => text.push(c),
+            }
+        }
+        text
+    }
+
+    fn name(&mut self) -> ParseResult<String> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let is_start = |c: char| c.is_ascii_alphabetic() || "_$@".contains(c);
+        if !rest.starts_with(is_start) {
+            return self.error("expected a rule name");
+        }
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "_$@-.".contains(c)))
+            .unwrap_or(rest.len());
+        // Names can't end with `-` or `.`
+        let name = rest[..len].trim_end_matches(&['-', '.'][..]);
+        self.pos += name.len();
+        Ok(name.to_owned())
+    }
+
+    fn rules(&mut self) -> ParseResult<Vec<(String, Type)>> {
+        let mut rules = Vec::new();
+        self.skip_whitespace();
+        while !self.rest().is_empty() {
+            let name = self.name()?;
+            if self.eat("/=") || self.eat("//=") {
+                return self.error("choice extension is not supported");
+            }
+            self.expect("=")?;
+            rules.push((name, self.ty()?));
+            self.skip_whitespace();
+        }
+        Ok(rules)
+    }
+
+    fn ty(&mut self) -> ParseResult<Type> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut choices = vec![self.type2()?];
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("//") {
+                return self.error("group choices are not supported");
+            }
+            if self.rest().starts_with('.') {
+                return self.error("ranges and control operators are not supported");
+            }
+            if !self.eat("/") {
+                break;
+            }
+            choices.push(self.type2()?);
+        }
+        Ok(Type {
+            choices,
+            text: self.text_since(start),
+        })
+    }
+
+    fn type2(&mut self) -> ParseResult<Type2> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        if self.eat("{") {
+            Ok(Type2::Map(self.group("}")?))
+        } else if self.eat("[") {
+            Ok(Type2::Array(self.group("]")?))
+        } else if self.eat("(") {
+            let ty = self.ty()?;
+            self.expect(")")?;
+            Ok(Type2::Parenthesized(Box::new(ty)))
+        } else if self.eat("#6.") {
+            let tag = self.number()?;
+            let tag = match tag {
+                Type2::Integer(tag) if (0..=i128::from(u64::max_value())).contains(&tag) => {
+                    tag as u64
+                }
+                _ => return self.error("expected a tag number"),
+            };
+            let value = if self.rest().starts_with('(') {
+                self.pos += 1;
+                let ty = self.ty()?;
+                self.expect(")")?;
+                Some(Box::new(ty))
+            } else {
+                None
+            };
+            Ok(Type2::Tag(tag, value))
+        } else if rest.starts_with('#') {
+            self.error("only tags are supported for `#` types")
+        } else if rest.starts_with('"') {
+            self.text()
+        } else if rest.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+            self.number()
+        } else {
+            Ok(Type2::Name(self.name()?))
+        }
+    }
+
+    fn text(&mut self) -> ParseResult<Type2> {
+        let mut text = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += index + 1;
+                    return Ok(Type2::Text(text));
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, c)) => text.push(c),
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+        self.error("unterminated text string")
+    }
+
+    fn number(&mut self) -> ParseResult<Type2> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| {
+                !(c.is_ascii_digit() || c == '.' || c == 'e' || (c == '-' && i == 0))
+                    // `..` is a range operator
+                    || rest[i..].starts_with("..")
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let number = &rest[..len];
+        let parsed = if number.contains(&['.', 'e'][..]) {
+            number.parse().ok().map(Type2::Float)
+        } else {
+            number.parse().ok().map(Type2::Integer)
+        };
+        match parsed {
+            Some(parsed) => {
+                self.pos += len;
+                Ok(parsed)
+            }
+            None => self.error("invalid number"),
+        }
+    }
+
+    fn group(&mut self, close: &str) -> ParseResult<Vec<Entry>> {
+        let mut entries = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Ok(entries);
+            }
+            if self.rest().is_empty() {
+                return self.error(&format!("expected `{}`", close));
+            }
+            entries.push(self.entry()?);
+            self.eat(",");
+        }
+    }
+
+    fn entry(&mut self) -> ParseResult<Entry> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let (min, max) = if self.eat("?") {
+            (0, Some(1))
+        } else if self.eat("*") {
+            (0, None)
+        } else if self.eat("+") {
+            (1, None)
+        } else {
+            (1, Some(1))
+        };
+
+        let ty = self.ty()?;
+        if self.eat("*") {
+            return self.error("occurrence ranges are not supported");
+        }
+        let (key, value) = if self.eat("=>") {
+            (Some(ty), self.ty()?)
+        } else if self.eat(":") {
+            let key = match &ty.choices[..] {
+                // A bareword is a text string key
+                [Type2::Name(name)] => Type {
+                    choices: vec![Type2::Text(name.clone())],
+                    text: ty.text,
+                },
+                [Type2::Text(_)] | [Type2::Integer(_)] | [Type2::Float(_)] => ty,
+                _ => return self.error("expected a bareword or literal before `:`"),
+            };
+            (Some(key), self.ty()?)
+        } else {
+            (None, ty)
+        };
+
+        Ok(Entry {
+            min,
+            max,
+            key,
+            value,
+            text: self.text_since(start),
+        })
+    }
+}
+
+/// Rules may refer to each other recursively, so limit how deep they can go
+/// without the data item getting deeper.
+const MAX_DEPTH: usize = 256;
+
+struct Validator<'a> {
+    rules: &'a [(String, Type)],
+}
+
+impl<'a> Validator<'a> {
+    fn rule(&self, name: &str) -> Option<&'a Type> {
+        self.rules
+            .iter()
+            .find(|(rule, _)| rule == name)
+            .map(|(_, ty)| ty)
+    }
+
+    /// Check that all names used in `ty` are defined.
+    fn check_names(&self, ty: &Type) -> Result<(), String> {
+        ty.choices
+            .iter()
+            .try_for_each(|choice| self.check_type2_names(choice))
+    }
+
+    fn check_type2_names(&self, ty: &Type2) -> Result<(), String> {
+        match ty {
+            Type2::Name(name) => {
+                if self.rule(name).is_some()
+                    || prelude(name, &DataItem::Simple(Simple::NULL)).is_some()
+                {
+                    Ok(())
+                } else {
+                    Err(format!("unknown rule `{}`", name))
+                }
+            }
+            Type2::Map(entries) | Type2::Array(entries) => entries.iter().try_for_each(|entry| {
+                if let Some(key) = &entry.key {
+                    self.check_names(key)?;
+                }
+                self.check_names(&entry.value)
+            }),
+            Type2::Tag(_, Some(ty)) | Type2::Parenthesized(ty) => self.check_names(ty),
+            Type2::Integer(_) | Type2::Float(_) | Type2::Text(_) | Type2::Tag(_, None) => Ok(()),
+        }
+    }
+
+    fn matches(&self, ty: &Type, item: &DataItem, depth: usize) -> bool {
+        self.validate(ty, item, &mut Vec::new(), depth).is_empty()
+    }
+
+    fn validate(
+        &self,
+        ty: &Type,
+        item: &DataItem,
+        path: &mut Vec<usize>,
+        depth: usize,
+    ) -> Vec<CddlViolation> {
+        if depth > MAX_DEPTH {
+            return vec![CddlViolation::invalid_schema(format!(
+                "rules are nested more than {} deep",
+                MAX_DEPTH
+            ))];
+        }
+        if let [choice] = &ty.choices[..] {
+            return self.validate_type2(choice, &ty.text, item, path, depth);
+        }
+        let mut violations = Vec::new();
+        for choice in &ty.choices {
+            let choice_violations = self.validate_type2(choice, &ty.text, item, path, depth);
+            if choice_violations.is_empty() {
+                return Vec::new();
+            }
+            // Problems with the schema are more important than the data
+            violations.extend(
+                choice_violations
+                    .into_iter()
+                    .filter(|v| matches!(v.kind, ViolationKind::InvalidSchema(_))),
+            );
+        }
+        if violations.is_empty() {
+            violations.push(CddlViolation::new(path, &ty.text, ViolationKind::Mismatch));
+        }
+        violations
+    }
+
+    fn validate_type2(
+        &self,
+        ty: &Type2,
+        text: &str,
+        item: &DataItem,
+        path: &mut Vec<usize>,
+        depth: usize,
+    ) -> Vec<CddlViolation> {
+        let matched = match ty {
+            Type2::Integer(expected) => item.as_i128() == Some(*expected),
+            Type2::Float(expected) => {
+                matches!(*item, DataItem::Float { value, .. } if value == *expected)
+            }
+            Type2::Text(expected) => text_string(item).as_ref() == Some(expected),
+            Type2::Name(name) => match self.rule(name) {
+                Some(ty) => return self.validate(ty, item, path, depth + 1),
+                None => prelude(name, item).unwrap_or(false),
+            },
+            Type2::Map(entries) => match item {
+                DataItem::Map { data, .. } => {
+                    return self.validate_map(entries, text, data, path, depth)
+                }
+                _ => false,
+            },
+            Type2::Array(entries) => match item {
+                DataItem::Array { data, .. } => {
+                    return self.validate_array(entries, text, data, path, depth)
+                }
+                _ => false,
+            },
+            Type2::Tag(expected, ty) => match item {
+                DataItem::Tag { tag, value, .. } if tag.0 == *expected => match ty {
+                    Some(ty) => {
+                        path.push(0);
+                        let violations = self.validate(ty, value, path, depth);
+                        path.pop();
+                        return violations;
+                    }
+                    None => true,
+                },
+                _ => false,
+            },
+            Type2::Parenthesized(ty) => return self.validate(ty, item, path, depth),
+        };
+        if matched {
+            Vec::new()
+        } else {
+            vec![CddlViolation::new(path, text, ViolationKind::Mismatch)]
+        }
+    }
+
+    fn validate_map(
+        &self,
+        entries: &[Entry],
+        text: &str,
+        data: &[(DataItem, DataItem)],
+        path: &mut Vec<usize>,
+        depth: usize,
+    ) -> Vec<CddlViolation> {
+        let mut violations = Vec::new();
+        let mut used = vec![false; data.len()];
+        for entry in entries {
+            let key = match &entry.key {
+                Some(key) => key,
+                None => {
+                    violations.push(CddlViolation::invalid_schema(format!(
+                        "map entry `{}` has no key",
+                        entry.text
+                    )));
+                    continue;
+                }
+            };
+            let mut count = 0;
+            for (index, (item_key, value)) in data.iter().enumerate() {
+                if used[index] || entry.max.is_some_and(|max| count >= max) {
+                    continue;
+                }
+                if self.matches(key, item_key, depth) {
+                    used[index] = true;
+                    count += 1;
+                    path.extend_from_slice(&[index, 1]);
+                    violations.extend(self.validate(&entry.value, value, path, depth));
+                    path.truncate(path.len() - 2);
+                }
+            }
+            if count < entry.min {
+                violations.push(CddlViolation::new(
+                    path,
+                    &entry.text,
+                    ViolationKind::Missing,
+                ));
+            }
+        }
+        for (index, used) in used.into_iter().enumerate() {
+            if !used {
+                path.extend_from_slice(&[index, 0]);
+                violations.push(CddlViolation::new(path, text, ViolationKind::Unexpected));
+                path.truncate(path.len() - 2);
+            }
+        }
+        violations
+    }
+
+    fn validate_array(
+        &self,
+        entries: &[Entry],
+        text: &str,
+        data: &[DataItem],
+        path: &mut Vec<usize>,
+        depth: usize,
+    ) -> Vec<CddlViolation> {
+        let mut index = 0;
+        for entry in entries {
+            let mut count = 0;
+            while index < data.len()
+                && entry.max.is_none_or(|max| count < max)
+                && self.matches(&entry.value, &data[index], depth)
+            {
+                index += 1;
+                count += 1;
+            }
+            if count < entry.min {
+                if index < data.len() {
+                    path.push(index);
+                    let violations = self.validate(&entry.value, &data[index], path, depth);
+                    path.pop();
+                    return violations;
+                }
+                return vec![CddlViolation::new(
+                    path,
+                    &entry.text,
+                    ViolationKind::Missing,
+                )];
+            }
+        }
+        (index..data.len())
+            .map(|index| {
+                path.push(index);
+                let violation = CddlViolation::new(path, text, ViolationKind::Unexpected);
+                path.pop();
+                violation
+            })
+            .collect()
+    }
+}
+
+fn text_string(item: &DataItem) -> Option<String> {
+    match item {
+        DataItem::TextString(string) => Some(string.data.clone()),
+        DataItem::IndefiniteTextString(strings) => {
+            Some(strings.iter().map(|s| &s.data[..]).collect())
+        }
+        _ => None,
+    }
+}
+
+fn float(item: &DataItem, width: FloatWidth) -> bool {
+    matches!(*item, DataItem::Float { bitwidth, .. } if bitwidth == width || bitwidth == FloatWidth::Unknown)
+}
+
+fn tagged(item: &DataItem, expected: Tag, value: fn(&DataItem) -> bool) -> bool {
+    matches!(item, DataItem::Tag { tag, value: inner, .. } if *tag == expected && value(inner))
+}
+
+fn bytes(item: &DataItem) -> bool {
+    matches!(
+        item,
+        DataItem::ByteString(_) | DataItem::IndefiniteByteString(_)
+    )
+}
+
+/// Whether `item` matches the prelude type `name`, if there is one.
+fn prelude(name: &str, item: &DataItem) -> Option<bool> {
+    Some(match name {
+        "any" => true,
+        "uint" => matches!(item, DataItem::Integer { .. }),
+        "nint" => matches!(item, DataItem::Negative { .. }),
+        "int" => item.as_i128().is_some(),
+        "bstr" | "bytes" => bytes(item),
+        "tstr" | "text" => text_string(item).is_some(),
+        "bool" => matches!(
+            item,
+            DataItem::Simple(Simple::FALSE) | DataItem::Simple(Simple::TRUE)
+        ),
+        "true" => matches!(item, DataItem::Simple(Simple::TRUE)),
+        "false" => matches!(item, DataItem::Simple(Simple::FALSE)),
+        "nil" | "null" => matches!(item, DataItem::Simple(Simple::NULL)),
+        "undefined" => matches!(item, DataItem::Simple(Simple::UNDEFINED)),
+        "float" => matches!(item, DataItem::Float { .. }),
+        "float16" => float(item, FloatWidth::Sixteen),
+        "float32" => float(item, FloatWidth::ThirtyTwo),
+        "float64" => float(item, FloatWidth::SixtyFour),
+        "number" => item.as_i128().is_some() || matches!(item, DataItem::Float { .. }),
+        "tdate" => tagged(item, Tag::DATETIME, |value| text_string(value).is_some()),
+        "time" => tagged(item, Tag::EPOCH_DATETIME, |value| {
+            value.as_i128().is_some() || matches!(value, DataItem::Float { .. })
+        }),
+        "biguint" => tagged(item, Tag::POSITIVE_BIGNUM, bytes),
+        "bignint" => tagged(item, Tag::NEGATIVE_BIGNUM, bytes),
+        "bigint" => {
+            tagged(item, Tag::POSITIVE_BIGNUM, bytes) || tagged(item, Tag::NEGATIVE_BIGNUM, bytes)
+        }
+        _ => return None,
+    })
+}
+
+/// Validate `item` against the first rule of the CDDL `schema`, returning all
+/// the ways it doesn't match.
+///
+/// If the schema can't be parsed, or uses an unsupported feature, that is
+/// returned as the only violation. See the [module docs](self) for what is
+/// supported.
+///
+/// ```rust
+/// use cbor_diag::cddl::{validate, ViolationKind};
+///
+/// let schema = "person = { name: tstr, ? age: uint }";
+///
+/// let item = cbor_diag::parse_diag(r#"{"name": "Alice", "age": 30}"#).unwrap();
+/// assert_eq!(validate(&item, schema), Ok(()));
+///
+/// let item = cbor_diag::parse_diag(r#"{"name": "Bob", "age": -1}"#).unwrap();
+/// let violations = validate(&item, schema).unwrap_err();
+/// assert_eq!(violations[0].path, [1, 1]);
+/// assert_eq!(violations[0].kind, ViolationKind::Mismatch);
+/// assert_eq!(violations[0].to_string(), "at [1, 1]: does not match `uint`");
+/// ```
+pub fn validate(item: &DataItem, schema: &str) -> Result<(), Vec<CddlViolation>> {
+    let rules = Parser {
+        input: schema,
+        pos: 0,
+    }
+    .rules()
+    .map_err(|message| vec![CddlViolation::invalid_schema(message)])?;
+
+    let validator = Validator { rules: &rules };
+    let root = match rules.first() {
+        Some((_, root)) => root,
+        None => return Err(vec![CddlViolation::invalid_schema("no rules")]),
+    };
+    for (_, ty) in &rules {
+        validator
+            .check_names(ty)
+            .map_err(|message| vec![CddlViolation::invalid_schema(message)])?;
+    }
+
+    let violations = validator.validate(root, item, &mut Vec::new(), 0);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+/// Where a comment is placed relative to the data item it is attached to.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CommentPosition {
+    /// Before the item, `/ comment / 1`
+    Before,
+    /// After the item, `1 / comment /`
+    After,
+}
+
+/// Comments from diagnostic notation, kept separately from the data item they
+/// were attached to, see [`parse_diag_with_comments`](crate::parse_diag_with_comments).
+///
+/// Items are identified by their path of indexes from the root item, which
+/// has the empty path:
+///
+///  * items in an [array](crate::DataItem::Array) and chunks of indefinite
+///    length strings are at their index
+///  * the key and value of entry `i` of a [map](crate::DataItem::Map) are at
+///    `i, 0` and `i, 1`
+///  * the value of a [tag](crate::DataItem::Tag) is at `0`
+///
+/// So in `{"a": [1, 2(h'00')]}` the `h'00'` is at path `[0, 1, 1, 0]`.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Comments {
+    comments: BTreeMap<Vec<usize>, [Vec<String>; 2]>,
+}
+
+impl Comments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+
+    /// The comments at `position` relative to the item at `path`, in order.
+    pub fn get(&self, path: &[usize], position: CommentPosition) -> &[String] {
+        self.comments
+            .get(path)
+            .map_or(&[], |comments| &comments[position as usize])
+    }
+
+    /// Add a comment at `position` relative to the item at `path`, after any
+    /// existing comments there.
+    ///
+    /// The comment must not contain `/`, as it would end the comment early
+    /// when shown.
+    pub fn push(
+        &mut self,
+        path: Vec<usize>,
+        position: CommentPosition,
+        comment: impl Into<String>,
+    ) {
+        self.comments.entry(path).or_default()[position as usize].push(comment.into());
+    }
+
+    /// Whether there are any comments for items within the item at `path`.
+    pub(crate) fn any_within(&self, path: &[usize]) -> bool {
+        self.comments
+            .range(path.to_vec()..)
+            .find(|(key, _)| key.as_slice() != path)
+            .is_some_and(|(key, _)| key.starts_with(path))
+    }
+
+    pub(crate) fn remove(&mut self, path: &[usize]) {
+        self.comments.remove(path);
+    }
+
+    /// All comments, ordered by the path of the item they are attached to.
+    pub fn iter(&self) -> impl Iterator<Item = (&[usize], CommentPosition, &str)> {
+        self.comments.iter().flat_map(|(path, [before, after])| {
+            before
+                .iter()
+                .map(move |comment| (&path[..], CommentPosition::Before, &comment[..]))
+                .chain(
+                    after
+                        .iter()
+                        .map(move |comment| (&path[..], CommentPosition::After, &comment[..])),
+                )
+        })
+    }
+}
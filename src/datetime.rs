@@ -0,0 +1,147 @@
+use std::{convert::TryFrom, fmt::Display};
+
+use chrono::{DateTime, Datelike, FixedOffset, SecondsFormat, TimeZone, Timelike, Utc};
+
+use crate::{DataItem, FloatWidth, IntegerWidth, Result, Tag, TextString};
+
+/// How [`DataItem::from_datetime`] represents a date and time.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DateTimeStyle {
+    /// An RFC 3339 string in a [`Tag::DATETIME`], keeping the offset from
+    /// UTC.
+    Text,
+    /// Seconds since the epoch in a [`Tag::EPOCH_DATETIME`], as an integer
+    /// for whole seconds and a float otherwise.
+    Epoch,
+    /// Seconds since the epoch in a [`Tag::EPOCH_DATETIME`], always as a
+    /// float.
+    EpochFloat,
+}
+
+fn is_leap_second(datetime: &impl Timelike) -> bool {
+    datetime.nanosecond() >= 1_000_000_000
+}
+
+impl DataItem {
+    /// Create a tagged date and time in the given `style`.
+    ///
+    /// Fails for leap seconds, which [RFC 8949 § 3.4.1][RFC 3.4.1] doesn't
+    /// allow, and for [`DateTimeStyle::Text`] with years outside `0000` to
+    /// `9999`, which can't be written in RFC 3339.
+    ///
+    /// ```rust
+    /// use cbor_diag::{DataItem, DateTimeStyle};
+    /// use chrono::DateTime;
+    ///
+    /// let datetime = DateTime::parse_from_rfc3339("2013-03-21T20:04:00.5Z").unwrap();
+    /// let item = DataItem::from_datetime(&datetime, DateTimeStyle::Text).unwrap();
+    /// assert_eq!(item.to_diag(), r#"0("2013-03-21T20:04:00.500Z")"#);
+    /// let item = DataItem::from_datetime(&datetime, DateTimeStyle::Epoch).unwrap();
+    /// assert_eq!(item.to_diag(), "1(1363896240.5)");
+    /// assert_eq!(item.to_datetime(), Some(datetime));
+    /// ```
+    ///
+    /// [RFC 3.4.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-3.4.1
+    pub fn from_datetime<Tz: TimeZone>(
+        datetime: &DateTime<Tz>,
+        style: DateTimeStyle,
+    ) -> Result<DataItem>
+    where
+        Tz::Offset: Display,
+    {
+        if is_leap_second(datetime) {
+            return Err("leap seconds cannot be represented".into());
+        }
+        let (tag, value) = match style {
+            DateTimeStyle::Text => {
+                if !(0..=9999).contains(&datetime.year()) {
+                    return Err(format!(
+                        "year {} cannot be written as an RFC 3339 date",
+                        datetime.year()
+                    )
+                    .into());
+                }
+                let text = datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+                (
+                    Tag::DATETIME,
+                    DataItem::TextString(TextString {
+                        data: text,
+                        bitwidth: IntegerWidth::Unknown,
+                    }),
+                )
+            }
+            DateTimeStyle::Epoch | DateTimeStyle::EpochFloat => {
+                let seconds = datetime.timestamp();
+                let nanoseconds = datetime.timestamp_subsec_nanos();
+                let value = if style == DateTimeStyle::Epoch && nanoseconds == 0 {
+                    if seconds >= 0 {
+                        DataItem::Integer {
+                            value: seconds as u64,
+                            bitwidth: IntegerWidth::Unknown,
+                        }
+                    } else {
+                        DataItem::Negative {
+                            value: (-1 - seconds) as u64,
+                            bitwidth: IntegerWidth::Unknown,
+                        }
+                    }
+                } else {
+                    DataItem::Float {
+                        value: seconds as f64 + f64::from(nanoseconds) / 1e9,
+                        bitwidth: FloatWidth::Unknown,
+                    }
+                };
+                (Tag::EPOCH_DATETIME, value)
+            }
+        };
+        Ok(DataItem::Tag {
+            tag,
+            bitwidth: IntegerWidth::Unknown,
+            value: Box::new(value),
+        })
+    }
+
+    /// The date and time of a [`Tag::DATETIME`] or [`Tag::EPOCH_DATETIME`]
+    /// item, or [`None`] if this is anything else or the value is invalid.
+    ///
+    /// Epoch values may be negative or have fractional seconds (rounded to
+    /// the nearest nanosecond), but must be within the range supported by
+    /// [`chrono`]. Epoch values are returned in UTC, strings keep their
+    /// offset. Strings with leap seconds are rejected, as in [RFC 8949 §
+    /// 3.4.1][RFC 3.4.1].
+    ///
+    /// [RFC 3.4.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-3.4.1
+    pub fn to_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        let (tag, value) = match self {
+            DataItem::Tag { tag, value, .. } => (*tag, &**value),
+            _ => return None,
+        };
+        let (seconds, nanoseconds) = match (tag, value) {
+            (Tag::DATETIME, DataItem::TextString(TextString { data, .. })) => {
+                let datetime = DateTime::parse_from_rfc3339(data).ok()?;
+                return Some(datetime).filter(|datetime| !is_leap_second(datetime));
+            }
+            (Tag::EPOCH_DATETIME, DataItem::Integer { value, .. }) => {
+                (i64::try_from(*value).ok()?, 0)
+            }
+            (Tag::EPOCH_DATETIME, DataItem::Negative { value, .. }) => {
+                (i64::try_from(-1 - i128::from(*value)).ok()?, 0)
+            }
+            (Tag::EPOCH_DATETIME, DataItem::Float { value, .. }) => {
+                let seconds = value.floor();
+                if !(i64::MIN as f64..i64::MAX as f64).contains(&seconds) {
+                    return None;
+                }
+                let nanoseconds = ((value - seconds) * 1e9).round() as u32;
+                if nanoseconds >= 1_000_000_000 {
+                    (seconds as i64 + 1, 0)
+                } else {
+                    (seconds as i64, nanoseconds)
+                }
+            }
+            _ => return None,
+        };
+        let datetime = Utc.timestamp_opt(seconds, nanoseconds).single()?;
+        Some(datetime.with_timezone(&FixedOffset::east_opt(0)?))
+    }
+}
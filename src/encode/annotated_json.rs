@@ -0,0 +1,206 @@
+use std::convert::TryFrom;
+
+use serde_json::{json, Map, Number, Value};
+
+use super::hex::{lines, Line};
+use crate::{
+    parse::parse_bytes_with_spans_with, DataItem, HexOptions, ParseOptions, Result, Simple,
+    SpanTree,
+};
+
+/// Options controlling the annotated JSON output, see
+/// [`DataItem::to_annotated_json_with`].
+#[derive(Debug, Clone, Default)]
+pub struct AnnotatedJsonOptions {
+    /// Options for the annotated hex the comments are taken from.
+    ///
+    /// Only the options affecting comments apply, the offsets and headers
+    /// are always those of the binary encoding.
+    pub hex: HexOptions,
+
+    /// Whether to give the `value` of negative integers below `i64::MIN`,
+    /// down to `-2^64`, as a JSON number rounded to the nearest `f64`.
+    ///
+    /// These can't be represented exactly as JSON numbers by most readers, so
+    /// if `false` (the default) they are given as a string of their exact
+    /// decimal value instead.
+    pub lossy_large_negatives: bool,
+}
+
+fn kind(item: &DataItem) -> &'static str {
+    match item {
+        DataItem::Integer { .. } => "unsigned",
+        DataItem::Negative { .. } => "negative",
+        DataItem::ByteString(_) | DataItem::IndefiniteByteString(_) => "bytes",
+        DataItem::TextString(_)
+        | DataItem::IndefiniteTextString(_)
+        | DataItem::InvalidTextString(_)
+        | DataItem::InvalidIndefiniteTextString(_) => "text",
+        DataItem::Array { .. } => "array",
+        DataItem::Map { .. } => "map",
+        DataItem::Tag { .. } => "tag",
+        DataItem::Float { .. } => "float",
+        DataItem::Simple(_) => "simple",
+        DataItem::AppLiteral { .. } => "application literal",
+    }
+}
+
+/// The JSON form of a scalar item's value, `None` for containers.
+fn value(item: &DataItem, options: &AnnotatedJsonOptions) -> Option<Value> {
+    Some(match *item {
+        DataItem::Integer { value, .. } => value.into(),
+        DataItem::Negative { value, .. } => {
+            let value = -1 - i128::from(value);
+            match i64::try_from(value) {
+                Ok(value) => value.into(),
+                Err(_) if options.lossy_large_negatives => {
+                    Number::from_f64(value as f64).expect("finite").into()
+                }
+                Err(_) => value.to_string().into(),
+            }
+        }
+        DataItem::ByteString(ref bytestring) => hex::encode(&bytestring.data).into(),
+        DataItem::InvalidTextString(ref bytestring) => hex::encode(&bytestring.data).into(),
+        DataItem::TextString(ref textstring) => textstring.data.clone().into(),
+        DataItem::Float { value, .. } => match Number::from_f64(value) {
+            Some(number) => number.into(),
+            None if value.is_nan() => "NaN".into(),
+            None if value > 0.0 => "Infinity".into(),
+            None => "-Infinity".into(),
+        },
+        DataItem::Simple(Simple::FALSE) => false.into(),
+        DataItem::Simple(Simple::TRUE) => true.into(),
+        DataItem::Simple(Simple::NULL) => Value::Null,
+        DataItem::Simple(Simple(value)) => value.into(),
+        _ => return None,
+    })
+}
+
+/// The data items directly within `item`, in encoded order.
+fn children(item: &DataItem) -> Vec<&DataItem> {
+    match item {
+        DataItem::Array { data, .. } => data.iter().collect(),
+        DataItem::Map { data, .. } => data.iter().flat_map(|(k, v)| vec![k, v]).collect(),
+        DataItem::Tag { value, .. } => vec![value],
+        _ => Vec::new(),
+    }
+}
+
+fn node(
+    item: &DataItem,
+    spans: &SpanTree,
+    line: &Line,
+    bytes: &[u8],
+    options: &AnnotatedJsonOptions,
+) -> Value {
+    let span = spans.span();
+
+    let mut comment = line.comment.clone();
+    if let DataItem::Tag { .. } = item {
+        // Lines after the value are the semantic comments on the tag
+        for extra in line.sublines.iter().skip(1) {
+            comment.push_str("; ");
+            comment.push_str(&extra.comment);
+        }
+    }
+
+    let mut object = Map::new();
+    object.insert("offset".into(), span.header.start.into());
+    object.insert("length".into(), span.range().len().into());
+    object.insert(
+        "header_hex".into(),
+        hex::encode(&bytes[span.header.clone()]).into(),
+    );
+    object.insert("kind".into(), kind(item).into());
+    object.insert("comment".into(), comment.into());
+    if let Some(value) = value(item, options) {
+        object.insert("value".into(), value);
+    }
+    object.insert(
+        "children".into(),
+        match item {
+            // The chunks of indefinite strings are not separate data items,
+            // so are described directly from their lines
+            DataItem::IndefiniteByteString(_)
+            | DataItem::IndefiniteTextString(_)
+            | DataItem::InvalidIndefiniteTextString(_) => spans
+                .children()
+                .iter()
+                .zip(&line.sublines)
+                .map(|(spans, line)| chunk(item, spans, line, bytes))
+                .collect(),
+            _ => children(item)
+                .into_iter()
+                .zip(spans.children())
+                .zip(&line.sublines)
+                .map(|((item, spans), line)| node(item, spans, line, bytes, options))
+                .collect(),
+        },
+    );
+    object.into()
+}
+
+/// A definite length chunk of an indefinite length string.
+fn chunk(string: &DataItem, spans: &SpanTree, line: &Line, bytes: &[u8]) -> Value {
+    let span = spans.span();
+    let content = &bytes[span.content.clone()];
+    let value = match string {
+        DataItem::IndefiniteTextString(_) => String::from_utf8_lossy(content).into_owned(),
+        _ => hex::encode(content),
+    };
+    json!({
+        "offset": span.header.start,
+        "length": span.range().len(),
+        "header_hex": hex::encode(&bytes[span.header.clone()]),
+        "kind": kind(string),
+        "comment": line.comment,
+        "value": value,
+        "children": [],
+    })
+}
+
+impl DataItem {
+    /// The same information as [`DataItem::to_hex`], as a JSON tree for use
+    /// by other tools.
+    ///
+    /// Each item is an object with:
+    ///
+    ///  * `offset` and `length`: the byte range of the whole item in its
+    ///    binary encoding
+    ///  * `header_hex`: the initial byte and argument of the item
+    ///  * `kind`: the major type, e.g. `"unsigned"`, `"bytes"` or `"map"`
+    ///  * `comment`: the comment from the annotated hex
+    ///  * `value`: for scalars only, the value as a JSON number, string (hex
+    ///    encoded for byte strings), boolean or null; negative integers below
+    ///    `i64::MIN` are given as a string of their decimal value, see
+    ///    [`AnnotatedJsonOptions::lossy_large_negatives`]
+    ///  * `children`: the items within the item, with the keys and values of
+    ///    maps interleaved
+    ///
+    /// Requires the `serde` feature, and fails if the item cannot be encoded
+    /// to binary.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag("[1]").unwrap();
+    /// let json = item.to_annotated_json().unwrap();
+    /// assert_eq!(json["comment"], "array(1)");
+    /// assert_eq!(json["children"][0]["offset"], 1);
+    /// assert_eq!(json["children"][0]["value"], 1);
+    /// ```
+    pub fn to_annotated_json(&self) -> Result<Value> {
+        self.to_annotated_json_with(&AnnotatedJsonOptions::default())
+    }
+
+    /// Like [`DataItem::to_annotated_json`], but with configurable comments
+    /// and values.
+    pub fn to_annotated_json_with(&self, options: &AnnotatedJsonOptions) -> Result<Value> {
+        let bytes = self.try_to_bytes()?;
+        let parse_options = ParseOptions {
+            allow_invalid_utf8: true,
+            ..ParseOptions::default()
+        };
+        let (item, spans) = parse_bytes_with_spans_with(&bytes, &parse_options)?;
+        let lines = lines(&item, &options.hex);
+        Ok(node(&item, &spans, &lines, &bytes, options))
+    }
+}
@@ -1,8 +1,333 @@
+use std::fmt;
+
 use half::f16;
 
-use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString};
+use crate::{
+    ByteString, DataItem, FloatWidth, IntegerWidth, OriginalEncoding, Result, Simple, SpanTree,
+    Tag, TextString,
+};
+
+/// Options controlling how data items are encoded to binary, see
+/// [`DataItem::try_to_bytes_with`].
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// Whether to use a wider encoding for integers, lengths and tags that
+    /// don't fit in their declared [`IntegerWidth`] (default `false`).
+    ///
+    /// If `false` these cause an error from [`DataItem::try_to_bytes_with`],
+    /// see [`DataItem::validate_encoding`]. [`DataItem::to_bytes_with`]
+    /// always widens them.
+    pub widen_on_overflow: bool,
+
+    /// Whether to encode all arrays and maps using the indefinite length
+    /// form, regardless of their declared form (default `false`).
+    pub force_indefinite_containers: bool,
+
+    /// If set, encode all byte and text strings as indefinite length strings
+    /// made of chunks of at most this many bytes (default [`None`]).
+    ///
+    /// Text strings are only split on UTF-8 character boundaries, so a chunk
+    /// may be longer than this if it holds a single wider character. Empty
+    /// strings are encoded with no chunks.
+    pub chunk_strings: Option<usize>,
+}
+
+/// A problem preventing a data item being encoded as declared, see
+/// [`DataItem::validate_encoding`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EncodingError {
+    /// The path to the item with the problem, as used by
+    /// [`Comments`](crate::Comments).
+    pub path: Vec<usize>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "at path {:?}: {}", self.path, self.message)
+        }
+    }
+}
+
+fn fits(value: u64, bitwidth: IntegerWidth) -> bool {
+    match bitwidth {
+        IntegerWidth::Unknown | IntegerWidth::SixtyFour => true,
+        IntegerWidth::Zero => value < 24,
+        IntegerWidth::Eight => value <= u64::from(u8::max_value()),
+        IntegerWidth::Sixteen => value <= u64::from(u16::max_value()),
+        IntegerWidth::ThirtyTwo => value <= u64::from(u32::max_value()),
+    }
+}
+
+fn width_name(bitwidth: IntegerWidth) -> &'static str {
+    match bitwidth {
+        IntegerWidth::Unknown | IntegerWidth::SixtyFour => "64 bits",
+        IntegerWidth::Zero => "the initial byte",
+        IntegerWidth::Eight => "8 bits",
+        IntegerWidth::Sixteen => "16 bits",
+        IntegerWidth::ThirtyTwo => "32 bits",
+    }
+}
+
+struct Validator {
+    path: Vec<usize>,
+    errors: Vec<EncodingError>,
+}
+
+impl Validator {
+    fn error(&mut self, message: String) {
+        self.errors.push(EncodingError {
+            path: self.path.clone(),
+            message,
+        });
+    }
+
+    fn width(&mut self, what: &str, shown: impl fmt::Display, value: u64, bitwidth: IntegerWidth) {
+        if !fits(value, bitwidth) {
+            self.error(format!(
+                "{} {} does not fit in {}",
+                what,
+                shown,
+                width_name(bitwidth)
+            ));
+        }
+    }
+
+    fn length(&mut self, len: usize, bitwidth: IntegerWidth) {
+        self.width("length", len, len as u64, bitwidth);
+    }
+
+    fn child(&mut self, index: usize, item: &DataItem) {
+        self.path.push(index);
+        self.item(item);
+        self.path.pop();
+    }
+
+    fn chunks<T>(&mut self, strings: &[T], length: impl Fn(&T) -> (usize, IntegerWidth)) {
+        for (index, string) in strings.iter().enumerate() {
+            let (len, bitwidth) = length(string);
+            self.path.push(index);
+            self.length(len, bitwidth);
+            self.path.pop();
+        }
+    }
 
-fn item_to_bytes(bytes: &mut Vec<u8>, value: &DataItem) {
+    fn item(&mut self, item: &DataItem) {
+        match item {
+            DataItem::Integer { value, bitwidth } => {
+                self.width("unsigned integer", value, *value, *bitwidth);
+            }
+            DataItem::Negative { value, bitwidth } => {
+                let shown = -1 - i128::from(*value);
+                self.width("negative integer", shown, *value, *bitwidth);
+            }
+            DataItem::ByteString(ByteString { data, bitwidth, .. })
+            | DataItem::InvalidTextString(ByteString { data, bitwidth, .. }) => {
+                self.length(data.len(), *bitwidth);
+            }
+            DataItem::TextString(TextString { data, bitwidth }) => {
+                self.length(data.len(), *bitwidth);
+            }
+            DataItem::IndefiniteByteString(strings)
+            | DataItem::InvalidIndefiniteTextString(strings) => {
+                self.chunks(strings, |string| (string.data.len(), string.bitwidth));
+            }
+            DataItem::IndefiniteTextString(strings) => {
+                self.chunks(strings, |string| (string.data.len(), string.bitwidth));
+            }
+            DataItem::Array { data, bitwidth } => {
+                if let Some(bitwidth) = bitwidth {
+                    self.length(data.len(), *bitwidth);
+                }
+                for (index, item) in data.iter().enumerate() {
+                    self.child(index, item);
+                }
+            }
+            DataItem::Map { data, bitwidth } => {
+                if let Some(bitwidth) = bitwidth {
+                    self.length(data.len(), *bitwidth);
+                }
+                for (index, (key, value)) in data.iter().enumerate() {
+                    self.path.push(index);
+                    self.child(0, key);
+                    self.child(1, value);
+                    self.path.pop();
+                }
+            }
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                value,
+            } => {
+                self.width("tag", tag.0, tag.0, *bitwidth);
+                self.child(0, value);
+            }
+            DataItem::Float { .. } => {}
+            DataItem::Simple(simple @ Simple(value)) => {
+                if simple.is_reserved() {
+                    self.error(format!(
+                        "simple({}) is reserved and cannot be encoded",
+                        value
+                    ));
+                }
+            }
+            DataItem::AppLiteral { prefix, content } => {
+                self.error(format!(
+                    "application literal {}'{}' has no binary encoding",
+                    prefix, content
+                ));
+            }
+        }
+    }
+}
+
+/// Split `data` into chunks of at most `size` bytes, only splitting where
+/// `boundary` allows.
+fn chunks<'a, T: ?Sized>(
+    data: &'a T,
+    size: usize,
+    len: impl Fn(&T) -> usize,
+    boundary: impl Fn(&T, usize) -> bool,
+    split: impl Fn(&'a T, usize) -> (&'a T, &'a T),
+) -> Vec<&'a T> {
+    let mut rest = data;
+    let mut chunks = Vec::new();
+    while len(rest) > 0 {
+        let mut end = size.max(1).min(len(rest));
+        while !boundary(rest, end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // A single character wider than the chunk size
+            end = (1..=len(rest)).find(|&i| boundary(rest, i)).unwrap();
+        }
+        let (chunk, remaining) = split(rest, end);
+        chunks.push(chunk);
+        rest = remaining;
+    }
+    chunks
+}
+
+fn byte_chunks(strings: &[ByteString], size: usize) -> Vec<ByteString> {
+    strings
+        .iter()
+        .flat_map(|string| {
+            chunks(
+                &string.data[..],
+                size,
+                <[u8]>::len,
+                |_, _| true,
+                <[u8]>::split_at,
+            )
+            .into_iter()
+            .map(move |data| ByteString {
+                data: data.to_owned(),
+                bitwidth: IntegerWidth::Unknown,
+                encoding: string.encoding,
+            })
+        })
+        .collect()
+}
+
+fn text_chunks(strings: &[TextString], size: usize) -> Vec<TextString> {
+    strings
+        .iter()
+        .flat_map(|string| {
+            chunks(
+                &string.data[..],
+                size,
+                str::len,
+                str::is_char_boundary,
+                str::split_at,
+            )
+            .into_iter()
+            .map(|data| TextString {
+                data: data.to_owned(),
+                bitwidth: IntegerWidth::Unknown,
+            })
+        })
+        .collect()
+}
+
+/// Convert `item` to the forms requested by `options` before encoding.
+fn reshape(item: &DataItem, options: &EncodeOptions) -> DataItem {
+    let chunk_strings = options.chunk_strings;
+    match item {
+        DataItem::ByteString(string) => match chunk_strings {
+            Some(size) => {
+                DataItem::IndefiniteByteString(byte_chunks(std::slice::from_ref(string), size))
+            }
+            None => item.clone(),
+        },
+        DataItem::IndefiniteByteString(strings) => match chunk_strings {
+            Some(size) => DataItem::IndefiniteByteString(byte_chunks(strings, size)),
+            None => item.clone(),
+        },
+        DataItem::TextString(string) => match chunk_strings {
+            Some(size) => {
+                DataItem::IndefiniteTextString(text_chunks(std::slice::from_ref(string), size))
+            }
+            None => item.clone(),
+        },
+        DataItem::IndefiniteTextString(strings) => match chunk_strings {
+            Some(size) => DataItem::IndefiniteTextString(text_chunks(strings, size)),
+            None => item.clone(),
+        },
+        DataItem::InvalidTextString(string) => match chunk_strings {
+            Some(size) => DataItem::InvalidIndefiniteTextString(byte_chunks(
+                std::slice::from_ref(string),
+                size,
+            )),
+            None => item.clone(),
+        },
+        DataItem::InvalidIndefiniteTextString(strings) => match chunk_strings {
+            Some(size) => DataItem::InvalidIndefiniteTextString(byte_chunks(strings, size)),
+            None => item.clone(),
+        },
+        DataItem::Array { data, bitwidth } => DataItem::Array {
+            data: data.iter().map(|item| reshape(item, options)).collect(),
+            bitwidth: if options.force_indefinite_containers {
+                None
+            } else {
+                *bitwidth
+            },
+        },
+        DataItem::Map { data, bitwidth } => DataItem::Map {
+            data: data
+                .iter()
+                .map(|(key, value)| (reshape(key, options), reshape(value, options)))
+                .collect(),
+            bitwidth: if options.force_indefinite_containers {
+                None
+            } else {
+                *bitwidth
+            },
+        },
+        DataItem::Tag {
+            tag,
+            bitwidth,
+            value,
+        } => DataItem::Tag {
+            tag: *tag,
+            bitwidth: *bitwidth,
+            value: Box::new(reshape(value, options)),
+        },
+        DataItem::Integer { .. }
+        | DataItem::Negative { .. }
+        | DataItem::Float { .. }
+        | DataItem::Simple(_)
+        | DataItem::AppLiteral { .. } => item.clone(),
+    }
+}
+
+/// Encode `value`, if `strict` failing on reserved simple values and
+/// application-oriented literals, otherwise encoding them as in
+/// [`DataItem::to_bytes`].
+fn item_to_bytes(bytes: &mut Vec<u8>, value: &DataItem, strict: bool) -> Result<()> {
     match *value {
         DataItem::Integer { value, bitwidth } => positive_to_bytes(bytes, value, bitwidth),
         DataItem::Negative { value, bitwidth } => negative_to_bytes(bytes, value, bitwidth),
@@ -14,39 +339,59 @@ fn item_to_bytes(bytes: &mut Vec<u8>, value: &DataItem) {
         DataItem::IndefiniteTextString(ref textstrings) => {
             indefinite_string_to_bytes(bytes, 0x03, textstrings, definite_textstring_to_bytes)
         }
-        DataItem::Array { ref data, bitwidth } => array_to_bytes(bytes, data, bitwidth),
-        DataItem::Map { ref data, bitwidth } => map_to_bytes(bytes, data, bitwidth),
+        DataItem::InvalidTextString(ref textstring) => {
+            invalid_textstring_to_bytes(bytes, textstring)
+        }
+        DataItem::InvalidIndefiniteTextString(ref textstrings) => {
+            indefinite_string_to_bytes(bytes, 0x03, textstrings, invalid_textstring_to_bytes)
+        }
+        DataItem::Array { ref data, bitwidth } => {
+            return array_to_bytes(bytes, data, bitwidth, strict)
+        }
+        DataItem::Map { ref data, bitwidth } => return map_to_bytes(bytes, data, bitwidth, strict),
         DataItem::Tag {
             tag,
             bitwidth,
             ref value,
-        } => tagged_to_bytes(bytes, tag, bitwidth, &*value),
+        } => return tagged_to_bytes(bytes, tag, bitwidth, &*value, strict),
         DataItem::Float { value, bitwidth } => float_to_bytes(bytes, value, bitwidth),
-        DataItem::Simple(simple) => simple_to_bytes(bytes, simple),
+        DataItem::Simple(simple) => return simple_to_bytes(bytes, simple, strict),
+        DataItem::AppLiteral {
+            ref prefix,
+            ref content,
+        } if strict => {
+            return Err(format!(
+                "application literal {}'{}' has no binary encoding",
+                prefix, content
+            )
+            .into());
+        }
+        DataItem::AppLiteral {
+            ref prefix,
+            ref content,
+        } => {
+            // The representation of unknown application-extensions, tag 999
+            // with an array of the prefix and content
+            integer_to_bytes(bytes, 999, IntegerWidth::Unknown, 6);
+            bytes.push(0x82);
+            for text in &[prefix, content] {
+                integer_to_bytes(bytes, text.len() as u64, IntegerWidth::Unknown, 3);
+                bytes.extend_from_slice(text.as_bytes());
+            }
+        }
     }
+    Ok(())
 }
 
-fn integer_to_bytes(bytes: &mut Vec<u8>, value: u64, bitwidth: IntegerWidth, major: u8) {
-    const U8_MAX: u64 = u8::max_value() as u64;
-    const U16_MAX: u64 = u16::max_value() as u64;
-    const U32_MAX: u64 = u32::max_value() as u64;
-    const U64_MAX: u64 = u64::max_value();
-
+pub(super) fn integer_to_bytes(bytes: &mut Vec<u8>, value: u64, bitwidth: IntegerWidth, major: u8) {
     match bitwidth {
+        // Only reachable when widening, the fallible encoders otherwise
+        // validate the item first
+        bitwidth if !fits(value, bitwidth) => {
+            integer_to_bytes(bytes, value, IntegerWidth::Unknown, major);
+        }
         IntegerWidth::Unknown => {
-            integer_to_bytes(
-                bytes,
-                value,
-                #[allow(clippy::match_overlapping_arm)]
-                match value {
-                    0..=23 => IntegerWidth::Zero,
-                    0..=U8_MAX => IntegerWidth::Eight,
-                    0..=U16_MAX => IntegerWidth::Sixteen,
-                    0..=U32_MAX => IntegerWidth::ThirtyTwo,
-                    0..=U64_MAX => IntegerWidth::SixtyFour,
-                },
-                major,
-            );
+            integer_to_bytes(bytes, value, IntegerWidth::minimal_for(value), major);
         }
         IntegerWidth::Zero => {
             bytes.push(major << 5 | (value as u8));
@@ -70,6 +415,88 @@ fn integer_to_bytes(bytes: &mut Vec<u8>, value: u64, bitwidth: IntegerWidth, maj
     }
 }
 
+/// The width [`integer_to_bytes`] encodes `value` with for `bitwidth`, the
+/// shortest if it is unknown or too narrow to hold the value.
+pub(crate) fn encoded_width(value: u64, bitwidth: IntegerWidth) -> IntegerWidth {
+    match bitwidth {
+        IntegerWidth::Unknown => IntegerWidth::minimal_for(value),
+        bitwidth if !fits(value, bitwidth) => IntegerWidth::minimal_for(value),
+        bitwidth => bitwidth,
+    }
+}
+
+/// The size of the initial byte and argument written by [`integer_to_bytes`].
+pub(crate) fn header_len(value: u64, bitwidth: IntegerWidth) -> usize {
+    1 + encoded_width(value, bitwidth).byte_len()
+}
+
+/// The size of a definite length string with `len` bytes of data.
+fn string_len(len: usize, bitwidth: IntegerWidth) -> usize {
+    header_len(len as u64, bitwidth) + len
+}
+
+/// The size of the header of a container with `len` items, including the
+/// break for indefinite length ones.
+fn container_header_len(len: usize, bitwidth: Option<IntegerWidth>) -> usize {
+    match bitwidth {
+        Some(bitwidth) => header_len(len as u64, bitwidth),
+        None => 2,
+    }
+}
+
+/// The number of bytes [`item_to_bytes`] would write for `value`.
+fn item_len(value: &DataItem) -> Result<usize> {
+    Ok(match *value {
+        DataItem::Integer { value, bitwidth } | DataItem::Negative { value, bitwidth } => {
+            header_len(value, bitwidth)
+        }
+        DataItem::ByteString(ref bytestring) | DataItem::InvalidTextString(ref bytestring) => {
+            string_len(bytestring.data.len(), bytestring.bitwidth)
+        }
+        DataItem::TextString(ref textstring) => {
+            string_len(textstring.data.len(), textstring.bitwidth)
+        }
+        DataItem::IndefiniteByteString(ref bytestrings)
+        | DataItem::InvalidIndefiniteTextString(ref bytestrings) => {
+            container_header_len(0, None)
+                + bytestrings
+                    .iter()
+                    .map(|bytestring| string_len(bytestring.data.len(), bytestring.bitwidth))
+                    .sum::<usize>()
+        }
+        DataItem::IndefiniteTextString(ref textstrings) => {
+            container_header_len(0, None)
+                + textstrings
+                    .iter()
+                    .map(|textstring| string_len(textstring.data.len(), textstring.bitwidth))
+                    .sum::<usize>()
+        }
+        DataItem::Array { ref data, bitwidth } => {
+            container_header_len(data.len(), bitwidth)
+                + data.iter().map(item_len).sum::<Result<usize>>()?
+        }
+        DataItem::Map { ref data, bitwidth } => {
+            container_header_len(data.len(), bitwidth)
+                + data
+                    .iter()
+                    .map(|(key, value)| Ok(item_len(key)? + item_len(value)?))
+                    .sum::<Result<usize>>()?
+        }
+        DataItem::Tag {
+            tag,
+            bitwidth,
+            ref value,
+        } => header_len(tag.0, bitwidth) + item_len(value)?,
+        DataItem::Float { bitwidth, .. } => 1 + bitwidth.byte_len(),
+        // These may fail to encode, so share the checks
+        DataItem::Simple(_) | DataItem::AppLiteral { .. } => {
+            let mut bytes = Vec::new();
+            item_to_bytes(&mut bytes, value, true)?;
+            bytes.len()
+        }
+    })
+}
+
 fn positive_to_bytes(bytes: &mut Vec<u8>, value: u64, bitwidth: IntegerWidth) {
     integer_to_bytes(bytes, value, bitwidth, 0);
 }
@@ -78,7 +505,10 @@ fn negative_to_bytes(bytes: &mut Vec<u8>, value: u64, bitwidth: IntegerWidth) {
     integer_to_bytes(bytes, value, bitwidth, 1);
 }
 
-fn definite_bytestring_to_bytes(bytes: &mut Vec<u8>, ByteString { data, bitwidth }: &ByteString) {
+fn definite_bytestring_to_bytes(
+    bytes: &mut Vec<u8>,
+    ByteString { data, bitwidth, .. }: &ByteString,
+) {
     integer_to_bytes(bytes, data.len() as u64, *bitwidth, 2);
     bytes.extend_from_slice(data);
 }
@@ -88,6 +518,14 @@ fn definite_textstring_to_bytes(bytes: &mut Vec<u8>, TextString { data, bitwidth
     bytes.extend_from_slice(data.as_bytes());
 }
 
+fn invalid_textstring_to_bytes(
+    bytes: &mut Vec<u8>,
+    ByteString { data, bitwidth, .. }: &ByteString,
+) {
+    integer_to_bytes(bytes, data.len() as u64, *bitwidth, 3);
+    bytes.extend_from_slice(data);
+}
+
 fn indefinite_string_to_bytes<T>(
     bytes: &mut Vec<u8>,
     major: u8,
@@ -101,47 +539,65 @@ fn indefinite_string_to_bytes<T>(
     bytes.push(0xff);
 }
 
-fn array_to_bytes(bytes: &mut Vec<u8>, array: &[DataItem], bitwidth: Option<IntegerWidth>) {
+fn array_to_bytes(
+    bytes: &mut Vec<u8>,
+    array: &[DataItem],
+    bitwidth: Option<IntegerWidth>,
+    strict: bool,
+) -> Result<()> {
     if let Some(bitwidth) = bitwidth {
         integer_to_bytes(bytes, array.len() as u64, bitwidth, 4);
     } else {
         bytes.push(4 << 5 | 0x1f);
     }
 
-    array.iter().for_each(|item| item_to_bytes(bytes, item));
+    for item in array {
+        item_to_bytes(bytes, item, strict)?;
+    }
 
     if bitwidth.is_none() {
         bytes.push(0xff);
     }
+
+    Ok(())
 }
 
 fn map_to_bytes(
     bytes: &mut Vec<u8>,
     values: &[(DataItem, DataItem)],
     bitwidth: Option<IntegerWidth>,
-) {
+    strict: bool,
+) -> Result<()> {
     if let Some(bitwidth) = bitwidth {
         integer_to_bytes(bytes, values.len() as u64, bitwidth, 5);
     } else {
         bytes.push(5 << 5 | 0x1f);
     }
 
-    values.iter().for_each(|(item1, item2)| {
-        item_to_bytes(bytes, item1);
-        item_to_bytes(bytes, item2);
-    });
+    for (item1, item2) in values {
+        item_to_bytes(bytes, item1, strict)?;
+        item_to_bytes(bytes, item2, strict)?;
+    }
 
     if bitwidth.is_none() {
         bytes.push(0xff);
     }
+
+    Ok(())
 }
 
-fn tagged_to_bytes(bytes: &mut Vec<u8>, tag: Tag, bitwidth: IntegerWidth, value: &DataItem) {
+fn tagged_to_bytes(
+    bytes: &mut Vec<u8>,
+    tag: Tag,
+    bitwidth: IntegerWidth,
+    value: &DataItem,
+    strict: bool,
+) -> Result<()> {
     integer_to_bytes(bytes, tag.0, bitwidth, 6);
-    item_to_bytes(bytes, value);
+    item_to_bytes(bytes, value, strict)
 }
 
-fn float_to_bytes(bytes: &mut Vec<u8>, value: f64, mut bitwidth: FloatWidth) {
+pub(super) fn float_to_bytes(bytes: &mut Vec<u8>, value: f64, mut bitwidth: FloatWidth) {
     if bitwidth == FloatWidth::Unknown {
         bitwidth = FloatWidth::SixtyFour;
     }
@@ -163,14 +619,411 @@ fn float_to_bytes(bytes: &mut Vec<u8>, value: f64, mut bitwidth: FloatWidth) {
     }
 }
 
-fn simple_to_bytes(bytes: &mut Vec<u8>, Simple(value): Simple) {
-    integer_to_bytes(bytes, value.into(), IntegerWidth::Unknown, 7);
+fn simple_to_bytes(bytes: &mut Vec<u8>, simple: Simple, strict: bool) -> Result<()> {
+    let Simple(value) = simple;
+    // Reserved values are not well-formed in either form, the one byte form
+    // would be a float or break so use the two byte form
+    let bitwidth = match simple.is_reserved() {
+        true if strict => {
+            return Err(format!("simple({}) is reserved and cannot be encoded", value).into());
+        }
+        true => IntegerWidth::Eight,
+        false => IntegerWidth::Unknown,
+    };
+    integer_to_bytes(bytes, value.into(), bitwidth, 7);
+    Ok(())
+}
+
+/// Whether a scalar `item` is the same as `original`, comparing floats by
+/// their bits so changes between `0.0` and `-0.0` or NaN payloads are seen.
+fn unchanged(item: &DataItem, original: &DataItem) -> bool {
+    match (item, original) {
+        (
+            DataItem::Float { value, bitwidth },
+            DataItem::Float {
+                value: original_value,
+                bitwidth: original_bitwidth,
+            },
+        ) => value.to_bits() == original_value.to_bits() && bitwidth == original_bitwidth,
+        _ => item == original,
+    }
+}
+
+/// The original encoding of an item being re-encoded by
+/// [`DataItem::to_bytes_preserving`], for the parts of it that are still
+/// there.
+#[derive(Copy, Clone)]
+struct Preserving<'a> {
+    source: &'a [u8],
+    item: &'a DataItem,
+    spans: &'a SpanTree,
+}
+
+impl<'a> Preserving<'a> {
+    /// The original encoding of the `index`th item directly within this
+    /// one, with the keys and values of a map interleaved.
+    fn child(self, index: usize, item: Option<&'a DataItem>) -> Option<Self> {
+        Some(Preserving {
+            source: self.source,
+            item: item?,
+            spans: self.spans.children().get(index)?,
+        })
+    }
+
+    fn header(self) -> &'a [u8] {
+        &self.source[self.spans.span().header.clone()]
+    }
+}
+
+/// Like [`item_to_bytes`], but copying the bytes of scalars that are the same
+/// as in the original encoding, and the headers of arrays, maps and tags
+/// that are the same kind with the same length or tag number, recursing into
+/// their items.
+fn preserving_to_bytes(
+    bytes: &mut Vec<u8>,
+    item: &DataItem,
+    original: Option<Preserving<'_>>,
+    strict: bool,
+) -> Result<()> {
+    let original = match original {
+        Some(original) => original,
+        None => return item_to_bytes(bytes, item, strict),
+    };
+    match (item, original.item) {
+        (
+            DataItem::Array { data, bitwidth },
+            DataItem::Array {
+                data: original_data,
+                bitwidth: original_bitwidth,
+            },
+        ) if bitwidth == original_bitwidth => {
+            container_header(
+                bytes,
+                original,
+                data.len(),
+                original_data.len(),
+                *bitwidth,
+                4,
+            );
+            for (index, item) in data.iter().enumerate() {
+                let child = original.child(index, original_data.get(index));
+                preserving_to_bytes(bytes, item, child, strict)?;
+            }
+            if bitwidth.is_none() {
+                bytes.push(0xff);
+            }
+        }
+        (
+            DataItem::Map { data, bitwidth },
+            DataItem::Map {
+                data: original_data,
+                bitwidth: original_bitwidth,
+            },
+        ) if bitwidth == original_bitwidth => {
+            container_header(
+                bytes,
+                original,
+                data.len(),
+                original_data.len(),
+                *bitwidth,
+                5,
+            );
+            for (index, (key, value)) in data.iter().enumerate() {
+                // Entries are matched by key, so removing or reordering them
+                // keeps the rest
+                let position = match original_data.get(index) {
+                    Some((original_key, _)) if original_key == key => Some(index),
+                    _ => original_data
+                        .iter()
+                        .position(|(original_key, _)| original_key == key),
+                };
+                let entry = position.map(|position| (position, &original_data[position]));
+                let child =
+                    entry.and_then(|(position, (key, _))| original.child(position * 2, Some(key)));
+                preserving_to_bytes(bytes, key, child, strict)?;
+                let child = entry.and_then(|(position, (_, value))| {
+                    original.child(position * 2 + 1, Some(value))
+                });
+                preserving_to_bytes(bytes, value, child, strict)?;
+            }
+            if bitwidth.is_none() {
+                bytes.push(0xff);
+            }
+        }
+        (
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                value,
+            },
+            DataItem::Tag {
+                tag: original_tag,
+                bitwidth: original_bitwidth,
+                value: original_value,
+            },
+        ) if tag == original_tag && bitwidth == original_bitwidth => {
+            bytes.extend_from_slice(original.header());
+            preserving_to_bytes(
+                bytes,
+                value,
+                original.child(0, Some(original_value)),
+                strict,
+            )?;
+        }
+        (item, original_item) if unchanged(item, original_item) => {
+            bytes.extend_from_slice(&original.source[original.spans.span().range()]);
+        }
+        (item, _) => item_to_bytes(bytes, item, strict)?,
+    }
+    Ok(())
+}
+
+/// The header of an array or map being re-encoded by [`preserving_to_bytes`],
+/// copied unless its length has changed.
+fn container_header(
+    bytes: &mut Vec<u8>,
+    original: Preserving<'_>,
+    len: usize,
+    original_len: usize,
+    bitwidth: Option<IntegerWidth>,
+    major: u8,
+) {
+    match bitwidth {
+        Some(bitwidth) if len != original_len => {
+            integer_to_bytes(bytes, len as u64, bitwidth, major)
+        }
+        _ => bytes.extend_from_slice(original.header()),
+    }
 }
 
 impl DataItem {
+    /// Encode this data item to CBOR binary format.
+    ///
+    /// This never fails, items that can't be encoded as declared (see
+    /// [`DataItem::validate_encoding`]) are encoded as closely as possible:
+    /// integers, lengths and tags that don't fit their width are widened,
+    /// reserved simple values use the two byte form even though it isn't
+    /// well-formed, and application-oriented literals become tag 999 with an
+    /// array of their prefix and content. Use [`DataItem::try_to_bytes`] to
+    /// get an error instead.
     pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&EncodeOptions::default())
+    }
+
+    /// Encode this data item to CBOR binary format, failing if it cannot be
+    /// encoded as declared, see [`DataItem::validate_encoding`].
+    pub fn try_to_bytes(&self) -> Result<Vec<u8>> {
+        self.try_to_bytes_with(&EncodeOptions::default())
+    }
+
+    /// Like [`DataItem::to_bytes`], but with configurable encoding.
+    ///
+    /// Integers, lengths and tags are always widened if they don't fit their
+    /// width, whatever [`EncodeOptions::widen_on_overflow`] is set to.
+    pub fn to_bytes_with(&self, options: &EncodeOptions) -> Vec<u8> {
+        match self.encode(options, false) {
+            Ok(bytes) => bytes,
+            Err(_) => unreachable!("only strict encoding fails"),
+        }
+    }
+
+    /// Like [`DataItem::try_to_bytes`], but with configurable encoding.
+    ///
+    /// The item is validated as declared, before any conversion to indefinite
+    /// length forms requested by `options`.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, EncodeOptions};
+    ///
+    /// let item = parse_diag(r#"["hello"]"#).unwrap();
+    /// let options = EncodeOptions {
+    ///     force_indefinite_containers: true,
+    ///     chunk_strings: Some(3),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     hex::encode(item.try_to_bytes_with(&options).unwrap()),
+    ///     "9f7f6368656c626c6fffff",
+    /// );
+    /// ```
+    pub fn try_to_bytes_with(&self, options: &EncodeOptions) -> Result<Vec<u8>> {
+        if !options.widen_on_overflow {
+            if let Err(errors) = self.validate_encoding() {
+                return Err(errors[0].to_string().into());
+            }
+        }
+        self.encode(options, true)
+    }
+
+    fn encode(&self, options: &EncodeOptions, strict: bool) -> Result<Vec<u8>> {
         let mut bytes = Vec::with_capacity(128);
-        item_to_bytes(&mut bytes, self);
-        bytes
+        if options.force_indefinite_containers || options.chunk_strings.is_some() {
+            item_to_bytes(&mut bytes, &reshape(self, options), strict)?;
+        } else {
+            item_to_bytes(&mut bytes, self, strict)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Encode this data item to CBOR binary format, reusing the bytes it was
+    /// parsed from with [`parse_bytes_preserving`](crate::parse_bytes_preserving)
+    /// for everything that hasn't changed since.
+    ///
+    /// Scalars equal to the original item at the same position are copied
+    /// exactly, including encodings that re-encoding would not reproduce,
+    /// like NaN payloads or non-preferred simple values. Arrays, maps and tags
+    /// that are still the same kind (and definite or indefinite length) keep
+    /// their original header, only re-encoding the length if it changed, and
+    /// have their items compared with the original ones at the same position,
+    /// or for maps with the same key. Anything else is encoded as by
+    /// [`DataItem::to_bytes`].
+    ///
+    /// Like [`DataItem::to_bytes`] this never fails, see
+    /// [`DataItem::try_to_bytes_preserving`] to get an error instead.
+    pub fn to_bytes_preserving(&self, original: &OriginalEncoding) -> Vec<u8> {
+        match self.encode_preserving(original, false) {
+            Ok(bytes) => bytes,
+            Err(_) => unreachable!("only strict encoding fails"),
+        }
+    }
+
+    /// Like [`DataItem::to_bytes_preserving`], but failing if the item cannot
+    /// be encoded as declared, see [`DataItem::validate_encoding`].
+    pub fn try_to_bytes_preserving(&self, original: &OriginalEncoding) -> Result<Vec<u8>> {
+        if let Err(errors) = self.validate_encoding() {
+            return Err(errors[0].to_string().into());
+        }
+        self.encode_preserving(original, true)
+    }
+
+    fn encode_preserving(&self, original: &OriginalEncoding, strict: bool) -> Result<Vec<u8>> {
+        let original = Preserving {
+            source: &original.bytes,
+            item: &original.item,
+            spans: &original.spans,
+        };
+        let mut bytes = Vec::with_capacity(original.source.len());
+        preserving_to_bytes(&mut bytes, self, Some(original), strict)?;
+        Ok(bytes)
+    }
+
+    /// The length of the binary encoding of this item, as from
+    /// [`DataItem::try_to_bytes`], calculated without encoding it.
+    ///
+    /// Fails if the item has no binary encoding, but unlike
+    /// [`DataItem::try_to_bytes`] does not check the declared widths, items
+    /// that don't fit in theirs are measured as widened.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"[_ 1000, "hello"]"#).unwrap();
+    /// assert_eq!(item.encoded_len().unwrap(), 11);
+    /// assert_eq!(item.encoded_len().unwrap(), item.to_bytes().len());
+    /// ```
+    pub fn encoded_len(&self) -> Result<usize> {
+        item_len(self)
+    }
+
+    /// Convert all indefinite length arrays, maps and strings within this
+    /// item to their definite length forms, concatenating the chunks of
+    /// strings.
+    ///
+    /// The converted lengths have an [`IntegerWidth::Unknown`] width, all
+    /// other encoding details are kept.
+    ///
+    /// ```rust
+    /// use cbor_diag::parse_diag;
+    ///
+    /// let item = parse_diag(r#"[_ (_ "he", "llo"), {_ 1: (_ h'01', h'02')}]"#).unwrap();
+    /// assert_eq!(item.to_definite().to_diag(), r#"["hello",{1:h'0102'}]"#);
+    /// ```
+    pub fn to_definite(&self) -> DataItem {
+        match self {
+            DataItem::IndefiniteByteString(strings) => DataItem::ByteString(ByteString {
+                data: strings
+                    .iter()
+                    .flat_map(|s| s.data.iter().copied())
+                    .collect(),
+                bitwidth: IntegerWidth::Unknown,
+                encoding: strings.first().and_then(|s| s.encoding),
+            }),
+            DataItem::IndefiniteTextString(strings) => DataItem::TextString(TextString {
+                data: strings.iter().map(|s| &s.data[..]).collect(),
+                bitwidth: IntegerWidth::Unknown,
+            }),
+            DataItem::InvalidIndefiniteTextString(strings) => {
+                let data: Vec<u8> = strings
+                    .iter()
+                    .flat_map(|s| s.data.iter().copied())
+                    .collect();
+                match String::from_utf8(data) {
+                    Ok(data) => DataItem::TextString(TextString {
+                        data,
+                        bitwidth: IntegerWidth::Unknown,
+                    }),
+                    Err(err) => DataItem::InvalidTextString(ByteString {
+                        data: err.into_bytes(),
+                        bitwidth: IntegerWidth::Unknown,
+                        encoding: None,
+                    }),
+                }
+            }
+            DataItem::Array { data, bitwidth } => DataItem::Array {
+                data: data.iter().map(DataItem::to_definite).collect(),
+                bitwidth: Some(bitwidth.unwrap_or(IntegerWidth::Unknown)),
+            },
+            DataItem::Map { data, bitwidth } => DataItem::Map {
+                data: data
+                    .iter()
+                    .map(|(key, value)| (key.to_definite(), value.to_definite()))
+                    .collect(),
+                bitwidth: Some(bitwidth.unwrap_or(IntegerWidth::Unknown)),
+            },
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                value,
+            } => DataItem::Tag {
+                tag: *tag,
+                bitwidth: *bitwidth,
+                value: Box::new(value.to_definite()),
+            },
+            DataItem::Integer { .. }
+            | DataItem::Negative { .. }
+            | DataItem::ByteString(_)
+            | DataItem::TextString(_)
+            | DataItem::InvalidTextString(_)
+            | DataItem::Float { .. }
+            | DataItem::Simple(_)
+            | DataItem::AppLiteral { .. } => self.clone(),
+        }
+    }
+
+    /// Check that this data item can be encoded to binary as declared,
+    /// returning all the problems found if not.
+    ///
+    /// This checks that integers, lengths and tags fit in their declared
+    /// [`IntegerWidth`] (with [`IntegerWidth::Zero`] only allowing values up
+    /// to 23), and that there are no reserved simple values or
+    /// [application-oriented literals](DataItem::AppLiteral), which have no
+    /// encoding.
+    ///
+    /// ```rust
+    /// use cbor_diag::{DataItem, IntegerWidth};
+    ///
+    /// let item = DataItem::Integer { value: 300, bitwidth: IntegerWidth::Eight };
+    /// let errors = item.validate_encoding().unwrap_err();
+    /// assert_eq!(errors[0].to_string(), "unsigned integer 300 does not fit in 8 bits");
+    /// ```
+    pub fn validate_encoding(&self) -> std::result::Result<(), Vec<EncodingError>> {
+        let mut validator = Validator {
+            path: Vec::new(),
+            errors: Vec::new(),
+        };
+        validator.item(self);
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
     }
 }
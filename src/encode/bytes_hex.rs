@@ -0,0 +1,82 @@
+use crate::{DataItem, Result};
+
+/// Options controlling the plain hex output, see
+/// [`DataItem::to_hex_string_with`].
+///
+/// This is the encoded bytes alone, for the annotated output see
+/// [`HexOptions`](crate::HexOptions).
+#[derive(Debug, Clone, Default)]
+pub struct BytesHexOptions {
+    /// How many bytes to put in each space separated group (default `0`, a
+    /// single unbroken group).
+    pub group: usize,
+
+    /// Whether to use uppercase hex digits.
+    pub uppercase: bool,
+
+    /// Text to put before each group, such as `0x`.
+    ///
+    /// Output with a prefix can only be parsed again by
+    /// [`parse_hex_with`](crate::parse_hex_with) when using `0x` with a
+    /// `group` of `1`, and
+    /// [`ParseOptions::allow_hex_arrays`](crate::ParseOptions::allow_hex_arrays)
+    /// set. Without a prefix, the output is always accepted by
+    /// [`parse_hex`](crate::parse_hex).
+    pub prefix: String,
+}
+
+impl BytesHexOptions {
+    /// Format already encoded `bytes` as hex, as for
+    /// [`DataItem::to_hex_string_with`].
+    ///
+    /// ```rust
+    /// use cbor_diag::BytesHexOptions;
+    ///
+    /// let options = BytesHexOptions {
+    ///     group: 1,
+    ///     prefix: "0x".into(),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(options.encode(b"\xa1\x01"), "0xa1 0x01");
+    /// ```
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let group = if self.group == 0 {
+            bytes.len().max(1)
+        } else {
+            self.group
+        };
+        let mut output = String::with_capacity(bytes.len() * 3);
+        for (index, chunk) in bytes.chunks(group).enumerate() {
+            if index > 0 {
+                output.push(' ');
+            }
+            output.push_str(&self.prefix);
+            if self.uppercase {
+                output.push_str(&hex::encode_upper(chunk));
+            } else {
+                output.push_str(&hex::encode(chunk));
+            }
+        }
+        output
+    }
+}
+
+impl DataItem {
+    /// Encode this data item to CBOR binary format, then output it as hex
+    /// formatted according to `options`.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, BytesHexOptions};
+    ///
+    /// let item = parse_diag(r#"{"a": 1}"#).unwrap();
+    /// let options = BytesHexOptions {
+    ///     group: 2,
+    ///     uppercase: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(item.to_hex_string_with(&options).unwrap(), "A161 6101");
+    /// ```
+    pub fn to_hex_string_with(&self, options: &BytesHexOptions) -> Result<String> {
+        Ok(options.encode(&self.try_to_bytes()?))
+    }
+}
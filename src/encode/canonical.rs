@@ -0,0 +1,367 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use super::{
+    bytes::{float_to_bytes, integer_to_bytes},
+    numbers::{extract_integer, integer_item},
+};
+use crate::{DataItem, FloatWidth, IntegerWidth, Simple, Tag};
+
+/// A [`DataItem`] compared by its deterministic encoding, for use as a key in
+/// a [`HashMap`](std::collections::HashMap) or
+/// [`BTreeMap`](std::collections::BTreeMap).
+///
+/// Items that differ only in how they are encoded are equal, see
+/// [`DataItem::canonical_key`] for the details. Items are ordered by the
+/// bytewise lexicographic order of their deterministic encodings, as used for
+/// sorting map keys in [RFC 8949 § 4.2.1][RFC 4.2.1].
+///
+/// [RFC 4.2.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.2.1
+#[derive(Debug, Clone)]
+pub struct CanonicalDataItem {
+    item: DataItem,
+    key: Vec<u8>,
+}
+
+impl CanonicalDataItem {
+    pub fn new(item: DataItem) -> Self {
+        let key = item.canonical_key();
+        Self { item, key }
+    }
+
+    /// The original item, with its encoding details intact.
+    pub fn item(&self) -> &DataItem {
+        &self.item
+    }
+
+    pub fn into_item(self) -> DataItem {
+        self.item
+    }
+
+    /// The deterministic encoding of the item, see
+    /// [`DataItem::canonical_key`].
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+impl From<DataItem> for CanonicalDataItem {
+    fn from(item: DataItem) -> Self {
+        Self::new(item)
+    }
+}
+
+impl PartialEq for CanonicalDataItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for CanonicalDataItem {}
+
+impl Hash for CanonicalDataItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+impl PartialOrd for CanonicalDataItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CanonicalDataItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+fn string_to_bytes<'a>(
+    bytes: &mut Vec<u8>,
+    major: u8,
+    chunks: impl Iterator<Item = &'a [u8]> + Clone,
+) {
+    let len = chunks.clone().map(|chunk| chunk.len()).sum::<usize>();
+    integer_to_bytes(bytes, len as u64, IntegerWidth::Unknown, major);
+    for chunk in chunks {
+        bytes.extend_from_slice(chunk);
+    }
+}
+
+/// The narrowest width that can hold `value` exactly, along with the value to
+/// encode at that width.
+///
+/// Unless `preserve_nan_payload` is set all NaNs are replaced with the
+/// canonical quiet NaN, which fits in 16 bits.
+pub(super) fn minimal_float_width(value: f64, preserve_nan_payload: bool) -> (f64, FloatWidth) {
+    if value.is_nan() && !preserve_nan_payload {
+        (f64::NAN, FloatWidth::Sixteen)
+    } else {
+        (value, FloatWidth::minimal_for(value))
+    }
+}
+
+/// Call `f` on `item` and then every item within it.
+fn for_each_mut(item: &mut DataItem, f: &mut impl FnMut(&mut DataItem)) {
+    f(item);
+    match item {
+        DataItem::Array { data, .. } => {
+            for item in data {
+                for_each_mut(item, f);
+            }
+        }
+        DataItem::Map { data, .. } => {
+            for (key, value) in data {
+                for_each_mut(key, f);
+                for_each_mut(value, f);
+            }
+        }
+        DataItem::Tag { value, .. } => for_each_mut(value, f),
+        DataItem::Integer { .. }
+        | DataItem::Negative { .. }
+        | DataItem::ByteString(_)
+        | DataItem::IndefiniteByteString(_)
+        | DataItem::TextString(_)
+        | DataItem::IndefiniteTextString(_)
+        | DataItem::InvalidTextString(_)
+        | DataItem::InvalidIndefiniteTextString(_)
+        | DataItem::Float { .. }
+        | DataItem::Simple(_)
+        | DataItem::AppLiteral { .. } => {}
+    }
+}
+
+fn minimal_float_widths(item: &mut DataItem, preserve_nan_payload: bool) {
+    for_each_mut(item, &mut |item| {
+        if let DataItem::Float { value, bitwidth } = item {
+            let (minimal, width) = minimal_float_width(*value, preserve_nan_payload);
+            *value = minimal;
+            *bitwidth = width;
+        }
+    });
+}
+
+/// Set the widths of all integers, lengths and tags within `item` with
+/// `width`, called with the value being encoded.
+fn set_integer_widths(item: &mut DataItem, width: fn(u64) -> IntegerWidth) {
+    for_each_mut(item, &mut |item| match item {
+        DataItem::Integer { value, bitwidth } | DataItem::Negative { value, bitwidth } => {
+            *bitwidth = width(*value);
+        }
+        DataItem::ByteString(string) | DataItem::InvalidTextString(string) => {
+            string.bitwidth = width(string.data.len() as u64);
+        }
+        DataItem::IndefiniteByteString(strings)
+        | DataItem::InvalidIndefiniteTextString(strings) => {
+            for string in strings {
+                string.bitwidth = width(string.data.len() as u64);
+            }
+        }
+        DataItem::TextString(string) => {
+            string.bitwidth = width(string.data.len() as u64);
+        }
+        DataItem::IndefiniteTextString(strings) => {
+            for string in strings {
+                string.bitwidth = width(string.data.len() as u64);
+            }
+        }
+        DataItem::Array { data, bitwidth } => {
+            if let Some(bitwidth) = bitwidth {
+                *bitwidth = width(data.len() as u64);
+            }
+        }
+        DataItem::Map { data, bitwidth } => {
+            if let Some(bitwidth) = bitwidth {
+                *bitwidth = width(data.len() as u64);
+            }
+        }
+        DataItem::Tag { tag, bitwidth, .. } => {
+            *bitwidth = width(tag.0);
+        }
+        DataItem::Float { .. } | DataItem::Simple(_) | DataItem::AppLiteral { .. } => {}
+    });
+}
+
+fn item_to_bytes(bytes: &mut Vec<u8>, item: &DataItem) {
+    match item {
+        DataItem::Integer { value, .. } => {
+            integer_to_bytes(bytes, *value, IntegerWidth::Unknown, 0);
+        }
+        DataItem::Negative { value, .. } => {
+            integer_to_bytes(bytes, *value, IntegerWidth::Unknown, 1);
+        }
+        DataItem::ByteString(string) => {
+            string_to_bytes(bytes, 2, std::iter::once(&string.data[..]));
+        }
+        DataItem::IndefiniteByteString(strings) => {
+            string_to_bytes(bytes, 2, strings.iter().map(|s| &s.data[..]));
+        }
+        DataItem::TextString(string) => {
+            string_to_bytes(bytes, 3, std::iter::once(string.data.as_bytes()));
+        }
+        DataItem::IndefiniteTextString(strings) => {
+            string_to_bytes(bytes, 3, strings.iter().map(|s| s.data.as_bytes()));
+        }
+        DataItem::InvalidTextString(string) => {
+            string_to_bytes(bytes, 3, std::iter::once(&string.data[..]));
+        }
+        DataItem::InvalidIndefiniteTextString(strings) => {
+            string_to_bytes(bytes, 3, strings.iter().map(|s| &s.data[..]));
+        }
+        DataItem::Array { data, .. } => {
+            integer_to_bytes(bytes, data.len() as u64, IntegerWidth::Unknown, 4);
+            for item in data {
+                item_to_bytes(bytes, item);
+            }
+        }
+        DataItem::Map { data, .. } => {
+            let mut entries: Vec<(Vec<u8>, &DataItem)> = data
+                .iter()
+                .map(|(key, value)| (key.canonical_key(), value))
+                .collect();
+            entries.sort_by(|(l, _), (r, _)| l.cmp(r));
+            integer_to_bytes(bytes, data.len() as u64, IntegerWidth::Unknown, 5);
+            for (key, value) in entries {
+                bytes.extend_from_slice(&key);
+                item_to_bytes(bytes, value);
+            }
+        }
+        DataItem::Tag { tag, value, .. } => {
+            let integer = match *tag {
+                Tag::POSITIVE_BIGNUM | Tag::NEGATIVE_BIGNUM => extract_integer(item),
+                _ => None,
+            };
+            match &integer.map(|integer| integer_item(&integer)) {
+                Some(DataItem::Tag { tag, value, .. }) => {
+                    integer_to_bytes(bytes, tag.0, IntegerWidth::Unknown, 6);
+                    // Already minimal, so must not be converted again
+                    if let DataItem::ByteString(string) = &**value {
+                        string_to_bytes(bytes, 2, std::iter::once(&string.data[..]));
+                    }
+                }
+                Some(plain) => item_to_bytes(bytes, plain),
+                None => {
+                    integer_to_bytes(bytes, tag.0, IntegerWidth::Unknown, 6);
+                    item_to_bytes(bytes, value);
+                }
+            }
+        }
+        DataItem::Float { value, .. } => {
+            let (value, bitwidth) = minimal_float_width(*value, false);
+            float_to_bytes(bytes, value, bitwidth);
+        }
+        DataItem::Simple(simple @ Simple(value)) => {
+            // Reserved values are not well-formed, but still give them a
+            // distinct key instead of failing
+            let bitwidth = if simple.is_reserved() {
+                IntegerWidth::Eight
+            } else {
+                IntegerWidth::Unknown
+            };
+            integer_to_bytes(bytes, (*value).into(), bitwidth, 7);
+        }
+        DataItem::AppLiteral { prefix, content } => {
+            // These have no encoding at all, use the reserved additional
+            // information value 28 to keep them distinct from any real item
+            bytes.push(0x1c);
+            string_to_bytes(bytes, 3, std::iter::once(prefix.as_bytes()));
+            string_to_bytes(bytes, 3, std::iter::once(content.as_bytes()));
+        }
+    }
+}
+
+impl DataItem {
+    /// The deterministic encoding of this item, so that items that differ
+    /// only in how they are encoded have the same key.
+    ///
+    /// This follows the core deterministic encoding requirements of [RFC 8949
+    /// § 4.2.1][RFC 4.2.1]:
+    ///
+    ///  * integers, lengths and tags use their shortest encoding
+    ///  * indefinite length items are converted to definite length, with the
+    ///    chunks of strings concatenated
+    ///  * map entries are sorted by the bytewise lexicographic order of their
+    ///    encoded keys
+    ///  * floats use the shortest width that preserves their value, and all
+    ///    NaNs are normalized to `f97e00`
+    ///
+    /// Along with the preferred serialization of bignums from [RFC 8949 §
+    /// 3.4.3][RFC 3.4.3], those that fit in 64 bits become plain integers and
+    /// the rest lose any leading zero bytes.
+    ///
+    /// As in the deterministic encoding `-0.0` and `0.0` are distinct, as are
+    /// integers and floats with the same value. Unlike [`DataItem::to_bytes`]
+    /// this never fails, reserved simple values and text strings with invalid
+    /// UTF-8 are encoded as-is.
+    ///
+    /// [RFC 4.2.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.2.1
+    /// [RFC 3.4.3]: https://www.rfc-editor.org/rfc/rfc8949.html#section-3.4.3
+    pub fn canonical_key(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(128);
+        item_to_bytes(&mut bytes, self);
+        bytes
+    }
+
+    /// Set the width of every float within this item to the narrowest one
+    /// that holds its value exactly, the preferred serialization from [RFC
+    /// 8949 § 4.1][RFC 4.1].
+    ///
+    /// Infinities and subnormal values are narrowed like any other value,
+    /// while all NaNs are replaced with the canonical quiet NaN `f97e00`, see
+    /// [`DataItem::minimal_float_widths_preserving_nan`] to keep their
+    /// payloads instead. This is the form floats take in
+    /// [`DataItem::canonical_key`].
+    ///
+    /// ```rust
+    /// let mut item = cbor_diag::parse_diag("[1.5, 100000.0, 0.1, Infinity]").unwrap();
+    /// item.minimal_float_widths();
+    /// assert_eq!(item.to_diag(), "[1.5_1,100000.0_2,0.1_3,Infinity_1]");
+    /// ```
+    ///
+    /// [RFC 4.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.1
+    pub fn minimal_float_widths(&mut self) {
+        minimal_float_widths(self, false);
+    }
+
+    /// Like [`DataItem::minimal_float_widths`], but NaNs keep their sign and
+    /// payload, only being narrowed if that is lossless.
+    pub fn minimal_float_widths_preserving_nan(&mut self) {
+        minimal_float_widths(self, true);
+    }
+
+    /// Set the width of every integer, tag and definite length within this
+    /// item to its shortest encoding, the preferred serialization from [RFC
+    /// 8949 § 4.1][RFC 4.1].
+    ///
+    /// Indefinite length items stay indefinite, and floats are unchanged, see
+    /// [`DataItem::minimal_float_widths`] for those.
+    ///
+    /// ```rust
+    /// // [1, 'a']
+    /// let mut item = cbor_diag::parse_bytes(b"\x98\x02\x1b\0\0\0\0\0\0\0\x01\x59\0\x01a").unwrap();
+    /// item.minimal_integer_widths();
+    /// assert_eq!(item.to_bytes(), b"\x82\x01\x41a");
+    /// ```
+    ///
+    /// [RFC 4.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.1
+    pub fn minimal_integer_widths(&mut self) {
+        set_integer_widths(self, IntegerWidth::minimal_for);
+    }
+
+    /// Reset the width of every integer, tag, definite length and float
+    /// within this item to unknown, so that the encoder picks its default for
+    /// them.
+    ///
+    /// Indefinite length items stay indefinite.
+    pub fn clear_widths(&mut self) {
+        set_integer_widths(self, |_| IntegerWidth::Unknown);
+        for_each_mut(self, &mut |item| {
+            if let DataItem::Float { bitwidth, .. } = item {
+                *bitwidth = FloatWidth::Unknown;
+            }
+        });
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{DataItem, Result};
+
+/// Options controlling the source code output, see
+/// [`DataItem::to_rust_literal`], [`DataItem::to_c_literal`] and
+/// [`DataItem::to_python_literal`].
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// How many bytes to put on each line (default 16).
+    ///
+    /// Items longer than this are split over multiple indented lines. A value
+    /// of `0` puts all the bytes on a single line.
+    pub bytes_per_line: usize,
+
+    /// The name of a variable to declare holding the bytes.
+    ///
+    /// If [`None`] (the default) the Rust and Python output is just an
+    /// expression, while C always needs a name so uses `msg`.
+    pub name: Option<String>,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            bytes_per_line: 16,
+            name: None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Language {
+    Rust,
+    C,
+    Python,
+}
+
+fn to_source(item: &DataItem, options: &CodegenOptions, language: Language) -> Result<String> {
+    let bytes = item.try_to_bytes()?;
+    let bytes_per_line = match options.bytes_per_line {
+        0 => bytes.len().max(1),
+        n => n,
+    };
+    let lines: Vec<String> = bytes
+        .chunks(bytes_per_line)
+        .map(|chunk| match language {
+            Language::Rust => chunk
+                .iter()
+                .map(|byte| format!("0x{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Language::C => chunk
+                .iter()
+                .map(|byte| format!("0x{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Language::Python => format!("\"{}\"", hex::encode(chunk)),
+        })
+        .collect();
+
+    let (open, close, separator, comment) = match language {
+        Language::Rust => ("&[", "]", ",", " //"),
+        Language::C => ("{", "}", ",", " //"),
+        Language::Python => ("bytes.fromhex(", ")", "", "  #"),
+    };
+    let (declaration, terminator) = match (language, &options.name) {
+        (Language::Rust, Some(name)) => (format!("const {}: &[u8] = ", name), ";"),
+        (Language::C, name) => (
+            format!("uint8_t {}[] = ", name.as_deref().unwrap_or("msg")),
+            ";",
+        ),
+        (Language::Python, Some(name)) => (format!("{} = ", name), ""),
+        (_, None) => (String::new(), ""),
+    };
+
+    let mut output = declaration;
+    output.push_str(open);
+    if lines.len() == 1 {
+        output.push_str(&lines[0]);
+    } else {
+        output.push('\n');
+        for line in &lines {
+            output.push_str(&format!("    {}{}\n", line, separator));
+        }
+    }
+    output.push_str(close);
+    output.push_str(terminator);
+    output.push_str(&format!("{} {}", comment, item.to_diag()));
+    Ok(output)
+}
+
+impl DataItem {
+    /// Encode this data item as a Rust byte slice expression, followed by a
+    /// comment with its compact diagnostic notation.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, CodegenOptions};
+    ///
+    /// let item = parse_diag("{1: 2}").unwrap();
+    /// assert_eq!(
+    ///     item.to_rust_literal(&CodegenOptions::default()).unwrap(),
+    ///     "&[0xa1, 0x01, 0x02] // {1:2}",
+    /// );
+    /// ```
+    pub fn to_rust_literal(&self, options: &CodegenOptions) -> Result<String> {
+        to_source(self, options, Language::Rust)
+    }
+
+    /// Encode this data item as a C array initializer, followed by a comment
+    /// with its compact diagnostic notation.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, CodegenOptions};
+    ///
+    /// let item = parse_diag("{1: 2}").unwrap();
+    /// assert_eq!(
+    ///     item.to_c_literal(&CodegenOptions::default()).unwrap(),
+    ///     "uint8_t msg[] = {0xA1, 0x01, 0x02}; // {1:2}",
+    /// );
+    /// ```
+    pub fn to_c_literal(&self, options: &CodegenOptions) -> Result<String> {
+        to_source(self, options, Language::C)
+    }
+
+    /// Encode this data item as a Python `bytes` expression, followed by a
+    /// comment with its compact diagnostic notation.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, CodegenOptions};
+    ///
+    /// let item = parse_diag("{1: 2}").unwrap();
+    /// assert_eq!(
+    ///     item.to_python_literal(&CodegenOptions::default()).unwrap(),
+    ///     r#"bytes.fromhex("a10102")  # {1:2}"#,
+    /// );
+    /// ```
+    pub fn to_python_literal(&self, options: &CodegenOptions) -> Result<String> {
+        to_source(self, options, Language::Python)
+    }
+}
@@ -1,8 +1,117 @@
+use std::{cmp, collections::BTreeMap, fmt};
+
 use base64::{self, display::Base64Display};
 use half::f16;
+use separator::Separatable;
+
+use super::numbers::{approximate, extract_rational, key_name};
+use crate::{
+    base32, ByteString, CommentPosition, Comments, DataItem, Encoding, FloatWidth, IntegerWidth,
+    Simple, Tag, TextString,
+};
+
+/// Options controlling the diagnostic notation output, see
+/// [`DataItem::to_diag_with`].
+#[derive(Debug, Clone)]
+pub struct DiagFormatOptions {
+    /// Whether to escape all non-ASCII characters in text strings as
+    /// `\u{...}` (default `false`).
+    ///
+    /// Bidirectional control and other invisible formatting characters are
+    /// always escaped, as they can make the output misleading.
+    pub escape_non_ascii: bool,
+
+    /// How to show byte strings (default [`Encoding::Base16`]).
+    ///
+    /// This applies to byte strings without their own
+    /// [`ByteString::encoding`] that are not within one of the tags 21 to 23
+    /// giving an expected encoding.
+    pub bytestring_encoding: Encoding,
+
+    /// Whether to show byte strings that look like text as `'...'` (default
+    /// `false`).
+    ///
+    /// This applies to byte strings that would otherwise be shown using
+    /// [`DiagFormatOptions::bytestring_encoding`], with content that is valid
+    /// UTF-8 consisting only of printable characters.
+    pub prefer_text_bytestrings: bool,
+
+    /// Whether to follow rational numbers (tag 30) with a comment giving
+    /// their approximate decimal value, e.g. `30([1, 3]) / ≈ 0.3333 /`
+    /// (default `false`).
+    pub rational_approximations: bool,
+
+    /// Names for integer map keys, shown as a comment before the key, e.g.
+    /// `{/ alg / 1: -7}` (default empty).
+    ///
+    /// These apply to the keys of all maps. Names must not contain `/`, as
+    /// it would end the comment early.
+    pub key_names: BTreeMap<i128, String>,
+
+    /// Whether to show map entries sorted by the deterministic order of their
+    /// keys from [`DataItem::canonical_key`] (default `false`).
+    ///
+    /// This only affects how maps are shown, so that the same map encoded
+    /// with different orderings shows identically, e.g. for diffing.
+    pub sort_map_keys: bool,
+
+    /// Whether to show indefinite length strings as a single string with
+    /// their chunks joined (default `false`).
+    ///
+    /// Like [`DiagFormatOptions::sort_map_keys`] this only affects how the
+    /// item is shown, the output can't be parsed back to the same encoding.
+    pub normalize_indefinite: bool,
+
+    /// The most chunks of an indefinite length string to show individually.
+    ///
+    /// Strings with more chunks than this are shown as a single chunk with
+    /// their content joined, followed by a comment giving how many chunks
+    /// there were, e.g. `(_ h'...' / 4,096 chunks, 8,388,608 bytes total /)`.
+    /// If [`None`] (the default) every chunk is shown.
+    pub max_chunks: Option<usize>,
+
+    /// Whether to show encoding indicators (default `true`).
+    ///
+    /// These are the `_0` to `_3` suffixes giving the width of integers,
+    /// floats, tags and lengths, and the `_` marking indefinite length items,
+    /// which are extensions to the diagnostic notation of [RFC 8949 §
+    /// 8][RFC 8]. Without them the output is plain diagnostic notation, with
+    /// indefinite length strings shown with their chunks joined.
+    ///
+    /// [RFC 8]: https://www.rfc-editor.org/rfc/rfc8949.html#section-8
+    pub emit_encoding_indicators: bool,
 
-use super::Encoding;
-use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString};
+    /// Whether to follow the last item of a container wrapped over multiple
+    /// lines by [`DataItem::to_diag_pretty`] with a comma (default `true`).
+    ///
+    /// [`parse_diag`](crate::parse_diag) accepts a trailing comma in any
+    /// container, but some other tools don't.
+    pub trailing_commas: bool,
+
+    /// The width to fill lines to with the items of long arrays of numbers
+    /// or simple values in [`DataItem::to_diag_pretty`] (default `100`).
+    ///
+    /// These are packed as many to a line as fit, rather than one per line.
+    pub max_line_width: usize,
+}
+
+impl Default for DiagFormatOptions {
+    fn default() -> Self {
+        Self {
+            escape_non_ascii: false,
+            bytestring_encoding: Encoding::Base16,
+            prefer_text_bytestrings: false,
+            rational_approximations: false,
+            key_names: BTreeMap::new(),
+            sort_map_keys: false,
+            normalize_indefinite: false,
+            max_chunks: None,
+            emit_encoding_indicators: true,
+            trailing_commas: true,
+            max_line_width: 100,
+        }
+    }
+}
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub(crate) enum Layout {
@@ -13,33 +122,117 @@ pub(crate) enum Layout {
 pub(crate) struct Context<'a> {
     output: &'a mut String,
     layout: Layout,
-    encoding: Encoding,
+    options: &'a DiagFormatOptions,
+    /// The expected encoding from an enclosing tag
+    encoding: Option<Encoding>,
     indent: usize,
+    comments: Option<&'a Comments>,
+    /// The path of the current item, as used by [`Comments`]
+    path: Vec<usize>,
+    /// The width of the tags directly enclosing the current item, which are
+    /// output on the same line as it
+    tag_prefix: usize,
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(text) => {
+            !text.is_empty()
+                && text
+                    .chars()
+                    .all(|c| !c.is_control() && !is_invisible_format(c))
+        }
+        Err(_) => false,
+    }
+}
+
+/// Invisible characters that affect how surrounding text is displayed, these
+/// are always escaped.
+fn is_invisible_format(c: char) -> bool {
+    matches!(
+        c,
+        '\u{ad}'
+            | '\u{61c}'
+            | '\u{180e}'
+            | '\u{200b}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{206f}'
+            | '\u{feff}'
+            | '\u{fff9}'..='\u{fffb}'
+    )
 }
 
 trait LengthEstimate {
     /// Can shortcircuit and return `max` if it is more than that
-    fn estimate(&self, max: usize) -> usize;
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize;
+}
+
+/// The number of decimal digits in `value`.
+fn digits(value: u64) -> usize {
+    let mut digits = 1;
+    let mut value = value / 10;
+    while value > 0 {
+        digits += 1;
+        value /= 10;
+    }
+    digits
 }
 
-fn is_trivial(value: &impl LengthEstimate) -> bool {
+/// The kind of items that can be filled onto lines in an array when all of
+/// its items are the same kind, see [`DiagFormatOptions::max_line_width`].
+fn fill_kind(item: &DataItem) -> Option<u8> {
+    match item {
+        DataItem::Integer { .. } | DataItem::Negative { .. } => Some(0),
+        DataItem::Float { .. } => Some(1),
+        DataItem::Simple(_) => Some(2),
+        _ => None,
+    }
+}
+
+/// The encoding that tags 21 to 23 expect all byte strings within their
+/// content to be shown with, RFC 8949 § 3.4.5.2.
+fn expected_encoding(tag: Tag) -> Option<Encoding> {
+    match tag {
+        Tag::ENCODED_BASE64URL => Some(Encoding::Base64Url),
+        Tag::ENCODED_BASE64 => Some(Encoding::Base64),
+        Tag::ENCODED_BASE16 => Some(Encoding::Base16),
+        _ => None,
+    }
+}
+
+/// Options to estimate widths with for items within a tag expecting
+/// `encoding`, which applies in place of the configured encoding.
+fn expecting(options: &DiagFormatOptions, encoding: Encoding) -> DiagFormatOptions {
+    DiagFormatOptions {
+        bytestring_encoding: encoding,
+        prefer_text_bytestrings: false,
+        ..options.clone()
+    }
+}
+
+fn is_trivial(prefix: usize, value: &impl LengthEstimate, options: &DiagFormatOptions) -> bool {
     const MAX: usize = 60;
-    value.estimate(MAX) < MAX
+    prefix + value.estimate(MAX.saturating_sub(prefix), options) < MAX
 }
 
 impl LengthEstimate for DataItem {
-    fn estimate(&self, max: usize) -> usize {
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
         match self {
-            DataItem::Integer { value, .. } => value.to_string().len() + 2,
-            DataItem::Negative { value, .. } => value.to_string().len() + 3,
+            DataItem::Integer { value, .. } => digits(*value) + 2,
+            DataItem::Negative { value, .. } => digits(*value) + 3,
             DataItem::Float { value, .. } => value.to_string().len() + 3,
-            DataItem::Simple(value) => value.estimate(max),
-            DataItem::ByteString(value) => value.estimate(max),
-            DataItem::TextString(value) => value.estimate(max),
+            DataItem::Simple(value) => value.estimate(max, options),
+            DataItem::AppLiteral { prefix, content } => prefix.len() + content.len() + 2,
+            DataItem::ByteString(value) => value.estimate(max, options),
+            DataItem::InvalidTextString(value) => value.data.len() * 2 + 3,
+            DataItem::TextString(value) => value.estimate(max, options),
             DataItem::Array { data, .. } => {
                 let mut len = 4;
                 for item in data {
-                    len += item.estimate(max - len) + 2;
+                    len += item.estimate(max.saturating_sub(len), options) + 2;
                     if len >= max {
                         return len;
                     }
@@ -49,7 +242,7 @@ impl LengthEstimate for DataItem {
             DataItem::Map { data, .. } => {
                 let mut len = 4;
                 for entry in data {
-                    len += entry.estimate(max - len) + 2;
+                    len += entry.estimate(max.saturating_sub(len), options) + 2;
                     if len >= max {
                         return len;
                     }
@@ -59,7 +252,17 @@ impl LengthEstimate for DataItem {
             DataItem::IndefiniteByteString(strings) => {
                 let mut len = 4;
                 for string in strings {
-                    len += string.estimate(max - len) + 2;
+                    len += string.estimate(max.saturating_sub(len), options) + 2;
+                    if len >= max {
+                        return len;
+                    }
+                }
+                len
+            }
+            DataItem::InvalidIndefiniteTextString(strings) => {
+                let mut len = 4;
+                for string in strings {
+                    len += string.estimate(max.saturating_sub(len), options) + 2;
                     if len >= max {
                         return len;
                     }
@@ -69,90 +272,189 @@ impl LengthEstimate for DataItem {
             DataItem::IndefiniteTextString(strings) => {
                 let mut len = 4;
                 for string in strings {
-                    len += string.estimate(max - len) + 2;
+                    len += string.estimate(max.saturating_sub(len), options) + 2;
                     if len >= max {
                         return len;
                     }
                 }
                 len
             }
-            DataItem::Tag { tag, value, .. } => (tag, value).estimate(max),
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                value,
+            } => {
+                let suffix = match bitwidth {
+                    IntegerWidth::Unknown | IntegerWidth::Zero => 0,
+                    _ => 2,
+                };
+                let len = match expected_encoding(*tag) {
+                    Some(encoding) => (tag, value).estimate(max, &expecting(options, encoding)),
+                    None => (tag, value).estimate(max, options),
+                };
+                len + suffix
+            }
         }
     }
 }
 
 impl<T: LengthEstimate + ?Sized> LengthEstimate for &T {
-    fn estimate(&self, max: usize) -> usize {
-        (&**self).estimate(max)
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
+        (&**self).estimate(max, options)
     }
 }
 
 impl<T: LengthEstimate + ?Sized> LengthEstimate for Box<T> {
-    fn estimate(&self, max: usize) -> usize {
-        (&**self).estimate(max)
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
+        (&**self).estimate(max, options)
     }
 }
 
 impl<T: LengthEstimate, U: LengthEstimate> LengthEstimate for (T, U) {
-    fn estimate(&self, max: usize) -> usize {
-        let mut len = self.0.estimate(max);
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
+        let mut len = self.0.estimate(max, options);
         if len < max {
-            len += self.1.estimate(max - len);
+            len += self.1.estimate(max.saturating_sub(len), options);
         }
         len
     }
 }
 
 impl LengthEstimate for ByteString {
-    fn estimate(&self, _: usize) -> usize {
-        self.data.len() * 2 + 4
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
+        let encoding = self.encoding.unwrap_or_else(|| {
+            if options.prefer_text_bytestrings && looks_like_text(&self.data) {
+                Encoding::Text
+            } else {
+                options.bytestring_encoding
+            }
+        });
+        match (encoding, std::str::from_utf8(&self.data)) {
+            (Encoding::Base64, _) | (Encoding::Base64Url, _) => {
+                // Unpadded, with `b64''` around it
+                self.data.len() / 3 * 4 + [0, 2, 3][self.data.len() % 3] + 6
+            }
+            (Encoding::Base32, _) | (Encoding::Base32Hex, _) => {
+                // Unpadded, with `b32''` around it
+                (self.data.len() * 8).div_ceil(5) + 6
+            }
+            (Encoding::Text, Ok(text)) => quoted_width('\'', text, max, options),
+            _ => self.data.len() * 2 + 3,
+        }
     }
 }
 
 impl LengthEstimate for TextString {
-    fn estimate(&self, _: usize) -> usize {
-        self.data.len() + 2
+    fn estimate(&self, max: usize, options: &DiagFormatOptions) -> usize {
+        quoted_width('"', &self.data, max, options)
+    }
+}
+
+/// The number of characters `text` is shown as when quoted with `quote`,
+/// including any escapes, see [`Context::quoted_to_diag`].
+fn quoted_width(quote: char, text: &str, max: usize, options: &DiagFormatOptions) -> usize {
+    let mut width = 2;
+    for c in text.chars() {
+        width += match c {
+            c if c == quote || c == '\\' => 2,
+            c if c.is_control() => 6,
+            c if is_invisible_format(c) || (options.escape_non_ascii && !c.is_ascii()) => {
+                format!("{:x}", c as u32).len() + 4
+            }
+            _ => 1,
+        };
+        if width >= max {
+            break;
+        }
     }
+    width
 }
 
 impl LengthEstimate for Tag {
-    fn estimate(&self, _: usize) -> usize {
-        self.0.to_string().len() + 2
+    fn estimate(&self, _: usize, _: &DiagFormatOptions) -> usize {
+        digits(self.0) + 2
     }
 }
 
 impl LengthEstimate for Simple {
-    fn estimate(&self, _: usize) -> usize {
-        self.0.to_string().len() + 8
+    fn estimate(&self, _: usize, _: &DiagFormatOptions) -> usize {
+        digits(self.0.into()) + 8
     }
 }
 
 impl<'a> Context<'a> {
-    pub(crate) fn new(output: &'a mut String, layout: Layout) -> Self {
+    pub(crate) fn new(
+        output: &'a mut String,
+        layout: Layout,
+        options: &'a DiagFormatOptions,
+    ) -> Self {
         Self {
             output,
             layout,
-            encoding: Encoding::Base16,
+            options,
+            encoding: None,
             indent: 0,
+            comments: None,
+            path: Vec::new(),
+            tag_prefix: 0,
         }
     }
 
-    pub(crate) fn with_encoding(&mut self, encoding: Encoding) -> Context<'_> {
-        Context {
-            output: self.output,
-            layout: self.layout,
-            encoding,
-            indent: self.indent,
+    /// Show an item expecting byte strings within it to use `encoding`.
+    fn with_encoding(&mut self, encoding: Encoding, item_to_diag: impl FnOnce(&mut Self)) {
+        let outer = self.encoding.replace(encoding);
+        item_to_diag(self);
+        self.encoding = outer;
+    }
+
+    /// Whether the current item can be shown on a single line, along with the
+    /// tags around it, items containing comments never are.
+    fn is_trivial(&self, tag_prefix: usize, value: &DataItem) -> bool {
+        let trivial = match self.encoding {
+            Some(encoding) => is_trivial(tag_prefix, value, &expecting(self.options, encoding)),
+            None => is_trivial(tag_prefix, value, self.options),
+        };
+        trivial && !self.comments.is_some_and(|c| c.any_within(&self.path))
+    }
+
+    /// Show an item with any comments for its path around it.
+    fn with_comments(&mut self, item_to_diag: impl FnOnce(&mut Self)) {
+        let comments = match self.comments {
+            Some(comments) => comments,
+            None => return item_to_diag(self),
+        };
+        for comment in comments.get(&self.path, CommentPosition::Before) {
+            self.output.push_str(&format!("/ {} / ", comment));
+        }
+        item_to_diag(self);
+        for comment in comments.get(&self.path, CommentPosition::After) {
+            self.output.push_str(&format!(" / {} /", comment));
         }
     }
 
+    /// Show the item at `index` within the current item.
+    fn child_to_diag(&mut self, index: usize, item_to_diag: impl FnOnce(&mut Self)) {
+        self.path.push(index);
+        self.with_comments(item_to_diag);
+        self.path.pop();
+    }
+
     fn pretty(&self) -> bool {
         self.layout == Layout::Pretty
     }
 
     fn indent(&mut self) {
-        for _ in 0..self.indent {
-            self.output.push(' ');
+        self.indent_at(self.output.len());
+    }
+
+    /// Insert the current indentation at byte offset `at` of the output.
+    fn indent_at(&mut self, at: usize) {
+        const SPACES: &str = "                                                                ";
+        let mut indent = self.indent;
+        while indent > 0 {
+            let chunk = cmp::min(indent, SPACES.len());
+            self.output.insert_str(at, &SPACES[..chunk]);
+            indent -= chunk;
         }
     }
 
@@ -160,39 +462,80 @@ impl<'a> Context<'a> {
         self.output.push('\n');
     }
 
+    /// The width of the current line, including any trailing comma.
+    fn line_width(&self) -> usize {
+        let line = self.output.rsplit('\n').next().unwrap_or_default();
+        line.chars().count() + 1
+    }
+
+    /// The encoding indicator to show for an argument encoded with
+    /// `bitwidth`, if any.
+    fn encoding_indicator(&self, bitwidth: IntegerWidth) -> Option<u8> {
+        if !self.options.emit_encoding_indicators {
+            return None;
+        }
+        bitwidth.indicator()
+    }
+
+    /// Write `value` directly to the output, without allocating a string
+    /// for it.
+    fn display(&mut self, value: impl fmt::Display) {
+        use std::fmt::Write;
+        write!(self.output, "{}", value).expect("writing to a String cannot fail");
+    }
+
+    /// Write `value` in decimal, without going through the formatting
+    /// machinery as this is done for every integer.
+    fn decimal(&mut self, mut value: u64) {
+        let mut digits = [0; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        self.output
+            .push_str(std::str::from_utf8(&digits[start..]).expect("digits are ASCII"));
+    }
+
+    /// Write an encoding indicator, such as `_1`.
+    fn indicator(&mut self, indicator: u8) {
+        self.output.push('_');
+        self.output.push(char::from(b'0' + indicator));
+    }
+
     fn integer_to_diag(&mut self, value: u64, bitwidth: IntegerWidth) {
-        if bitwidth == IntegerWidth::Unknown || bitwidth == IntegerWidth::Zero {
-            self.output.push_str(&value.to_string());
-        } else {
-            let encoding = match bitwidth {
-                IntegerWidth::Eight => 0,
-                IntegerWidth::Sixteen => 1,
-                IntegerWidth::ThirtyTwo => 2,
-                IntegerWidth::SixtyFour => 3,
-                _ => unreachable!(),
-            };
-            self.output.push_str(&format!("{}_{}", value, encoding));
+        self.decimal(value);
+        if let Some(indicator) = self.encoding_indicator(bitwidth) {
+            self.indicator(indicator);
         }
     }
 
     fn negative_to_diag(&mut self, value: u64, bitwidth: IntegerWidth) {
-        let value = -1i128 - i128::from(value);
-        if bitwidth == IntegerWidth::Unknown || bitwidth == IntegerWidth::Zero {
-            self.output.push_str(&value.to_string());
-        } else {
-            let encoding = match bitwidth {
-                IntegerWidth::Eight => 0,
-                IntegerWidth::Sixteen => 1,
-                IntegerWidth::ThirtyTwo => 2,
-                IntegerWidth::SixtyFour => 3,
-                _ => unreachable!(),
-            };
-            self.output.push_str(&format!("{}_{}", value, encoding));
+        match value.checked_add(1) {
+            Some(magnitude) => {
+                self.output.push('-');
+                self.decimal(magnitude);
+            }
+            None => self.display(-1i128 - i128::from(value)),
+        }
+        if let Some(indicator) = self.encoding_indicator(bitwidth) {
+            self.indicator(indicator);
         }
     }
 
     fn definite_bytestring_to_diag(&mut self, bytestring: &ByteString) {
-        match self.encoding {
+        let encoding = bytestring.encoding.or(self.encoding).unwrap_or_else(|| {
+            if self.options.prefer_text_bytestrings && looks_like_text(&bytestring.data) {
+                Encoding::Text
+            } else {
+                self.options.bytestring_encoding
+            }
+        });
+        match encoding {
             Encoding::Base64Url => {
                 self.output.push_str(&format!(
                     "b64'{}'",
@@ -205,6 +548,24 @@ impl<'a> Context<'a> {
                     Base64Display::with_config(&bytestring.data, base64::STANDARD_NO_PAD)
                 ));
             }
+            Encoding::Base32 => {
+                self.output.push_str(&format!(
+                    "b32'{}'",
+                    base32::encode(&bytestring.data, base32::BASE32)
+                ));
+            }
+            Encoding::Base32Hex => {
+                self.output.push_str(&format!(
+                    "h32'{}'",
+                    base32::encode(&bytestring.data, base32::BASE32HEX)
+                ));
+            }
+            Encoding::Text => match std::str::from_utf8(&bytestring.data) {
+                Ok(text) => self.quoted_to_diag('\'', text),
+                Err(_) => self
+                    .output
+                    .push_str(&format!("h'{}'", hex::encode(&bytestring.data))),
+            },
             Encoding::Base16 => {
                 self.output
                     .push_str(&format!("h'{}'", hex::encode(&bytestring.data)));
@@ -213,32 +574,85 @@ impl<'a> Context<'a> {
     }
 
     fn definite_textstring_to_diag(&mut self, textstring: &TextString) {
-        self.output.push('"');
-        for c in textstring.data.chars() {
-            if c == '\"' || c == '\\' {
-                for c in c.escape_default() {
+        self.quoted_to_diag('"', &textstring.data);
+    }
+
+    fn quoted_to_diag(&mut self, quote: char, text: &str) {
+        self.output.push(quote);
+        // Copy runs of characters that don't need escaping in one go
+        let mut rest = text;
+        while let Some(index) = rest.find(|c: char| self.needs_escape(quote, c)) {
+            self.output.push_str(&rest[..index]);
+            let c = rest[index..].chars().next().expect("found a character");
+            rest = &rest[index + c.len_utf8()..];
+            match c {
+                c if c == quote || c == '\\' => {
+                    self.output.push('\\');
                     self.output.push(c);
                 }
-            } else {
-                self.output.push(c);
+                // Keep the output printable, including the C1 controls which
+                // some terminals treat like escape sequences
+                c if c.is_control() => {
+                    self.display(format_args!("\\u{:04x}", c as u32));
+                }
+                // Invisible formatting, or non-ASCII with `escape_non_ascii`
+                c => self.display(format_args!("\\u{{{:x}}}", c as u32)),
             }
         }
-        self.output.push('"');
+        self.output.push_str(rest);
+        self.output.push(quote);
+    }
+
+    /// Whether `c` within a string quoted by `quote` is shown escaped.
+    fn needs_escape(&self, quote: char, c: char) -> bool {
+        if c.is_ascii() {
+            c == quote || c == '\\' || c.is_ascii_control()
+        } else {
+            c.is_control() || is_invisible_format(c) || self.options.escape_non_ascii
+        }
+    }
+
+    /// There's no way to represent invalid UTF-8 in a text string, so these
+    /// are shown as byte strings instead.
+    fn invalid_textstring_to_diag(&mut self, bytestring: &ByteString) {
+        self.output
+            .push_str(&format!("h'{}'", hex::encode(&bytestring.data)));
     }
 
+    /// Show a container, `items` are paired with their index within it for
+    /// the paths of any comments.
+    ///
+    /// If `fill` the items of a non-trivial container are packed onto lines up
+    /// to [`DiagFormatOptions::max_line_width`] instead of one per line.
+    #[allow(clippy::too_many_arguments)]
     fn container_to_diag<T>(
         &mut self,
         begin: char,
-        items: impl IntoIterator<Item = T>,
+        items: impl IntoIterator<Item = (usize, T)>,
         end: char,
-        definite: bool,
+        bitwidth: Option<IntegerWidth>,
         trivial: bool,
+        fill: bool,
         item_to_diag: fn(&mut Self, T),
     ) {
+        let fill = fill && !trivial && self.pretty();
         self.output.push(begin);
-        if !definite {
-            self.output.push('_');
-            if trivial && self.pretty() {
+        let indicator = match bitwidth {
+            None if self.options.emit_encoding_indicators => {
+                self.output.push('_');
+                if trivial && self.pretty() {
+                    self.output.push(' ');
+                }
+                None
+            }
+            None => None,
+            Some(bitwidth) => self.encoding_indicator(bitwidth),
+        };
+        if let Some(indicator) = indicator {
+            // Always followed by whitespace to distinguish it from an
+            // indefinite length container starting with an integer
+            self.indicator(indicator);
+            if trivial || !self.pretty() {
                 self.output.push(' ');
             }
         }
@@ -246,29 +660,47 @@ impl<'a> Context<'a> {
             self.indent += 4;
         }
         let mut items = items.into_iter();
-        if let Some(item) = items.next() {
+        if let Some((index, item)) = items.next() {
             if self.pretty() && !trivial {
                 self.line();
                 self.indent();
             }
-            item_to_diag(self, item);
+            self.child_to_diag(index, |this| item_to_diag(this, item));
         }
-        for item in items {
+        // The width of the current line when filling, kept track of as the
+        // items are added rather than measured each time
+        let mut width = if fill { self.line_width() } else { 0 };
+        for (index, item) in items {
             self.output.push(',');
             if self.pretty() {
-                if trivial {
+                if trivial || fill {
                     self.output.push(' ');
                 } else {
                     self.line();
                     self.indent();
                 }
             }
-            item_to_diag(self, item);
+            let start = self.output.len();
+            self.child_to_diag(index, |this| item_to_diag(this, item));
+            if fill {
+                // Filled items are all scalars, so never span lines
+                let len = self.output[start..].chars().count();
+                width += 2 + len;
+                if width > self.options.max_line_width {
+                    // Move the item onto the next line, in place of the space
+                    // before it
+                    self.output.replace_range(start - 1..start, "\n");
+                    self.indent_at(start);
+                    width = self.indent + len + 1;
+                }
+            }
         }
         if !trivial {
             self.indent -= 4;
             if self.pretty() {
-                self.output.push(',');
+                if self.options.trailing_commas {
+                    self.output.push(',');
+                }
                 self.line();
                 self.indent();
             }
@@ -282,55 +714,150 @@ impl<'a> Context<'a> {
         trivial: bool,
         definite_string_to_diag: fn(&mut Self, &T),
     ) {
-        self.container_to_diag('(', strings, ')', false, trivial, definite_string_to_diag);
+        self.container_to_diag(
+            '(',
+            strings.iter().enumerate(),
+            ')',
+            None,
+            trivial,
+            false,
+            definite_string_to_diag,
+        );
     }
 
-    fn array_to_diag(&mut self, array: &[DataItem], definite: bool, trivial: bool) {
-        self.container_to_diag('[', array, ']', definite, trivial, Self::item_to_diag);
+    /// The number of chunks of an indefinite length string, if more than
+    /// [`DiagFormatOptions::max_chunks`].
+    fn excess_chunks(&self, value: &DataItem) -> Option<usize> {
+        let chunks = match value {
+            DataItem::IndefiniteByteString(strings) => strings.len(),
+            DataItem::IndefiniteTextString(strings) => strings.len(),
+            DataItem::InvalidIndefiniteTextString(strings) => strings.len(),
+            _ => return None,
+        };
+        self.options
+            .max_chunks
+            .filter(|&max| chunks > max)
+            .map(|_| chunks)
     }
 
-    fn map_to_diag(&mut self, values: &[(DataItem, DataItem)], definite: bool, trivial: bool) {
-        self.container_to_diag('{', values, '}', definite, trivial, |this, (key, value)| {
-            this.item_to_diag(key);
-            this.output.push(':');
-            if this.pretty() {
-                this.output.push(' ');
-            }
-            this.item_to_diag(value);
-        });
+    fn coalesced_string_to_diag(&mut self, value: &DataItem) {
+        let chunks = self.excess_chunks(value).unwrap_or_default();
+        let joined = value.to_definite();
+        let len = match &joined {
+            DataItem::ByteString(ByteString { data, .. })
+            | DataItem::InvalidTextString(ByteString { data, .. }) => data.len(),
+            DataItem::TextString(TextString { data, .. }) => data.len(),
+            _ => 0,
+        };
+        self.output.push_str("(_ ");
+        self.item_to_diag(&joined);
+        self.output.push_str(&format!(
+            " / {} chunks, {} bytes total /)",
+            chunks.separated_string(),
+            len.separated_string()
+        ));
     }
 
-    pub fn tagged_to_diag(&mut self, tag: Tag, bitwidth: IntegerWidth, value: &DataItem) {
-        if bitwidth == IntegerWidth::Unknown || bitwidth == IntegerWidth::Zero {
-            self.output.push_str(&tag.0.to_string());
-        } else {
-            let encoding = match bitwidth {
-                IntegerWidth::Eight => 0,
-                IntegerWidth::Sixteen => 1,
-                IntegerWidth::ThirtyTwo => 2,
-                IntegerWidth::SixtyFour => 3,
-                _ => unreachable!(),
-            };
-            self.output.push_str(&format!("{}_{}", tag.0, encoding));
+    fn array_to_diag(&mut self, array: &[DataItem], bitwidth: Option<IntegerWidth>, trivial: bool) {
+        // Long arrays of numbers are filled onto lines, as one per line makes
+        // them hard to take in
+        let fill = !self.comments.is_some_and(|c| c.any_within(&self.path))
+            && array.first().is_some_and(|first| {
+                let kind = fill_kind(first);
+                kind.is_some() && array.iter().all(|item| fill_kind(item) == kind)
+            });
+        self.container_to_diag(
+            '[',
+            array.iter().enumerate(),
+            ']',
+            bitwidth,
+            trivial,
+            fill,
+            Self::item_to_diag,
+        );
+    }
+
+    fn map_to_diag(
+        &mut self,
+        values: &[(DataItem, DataItem)],
+        bitwidth: Option<IntegerWidth>,
+        trivial: bool,
+    ) {
+        let mut entries: Vec<_> = values.iter().enumerate().collect();
+        if self.options.sort_map_keys {
+            entries.sort_by_cached_key(|(_, (key, _))| key.canonical_key());
         }
-        self.output.push('(');
+        self.container_to_diag(
+            '{',
+            entries,
+            '}',
+            bitwidth,
+            trivial,
+            false,
+            |this, (key, value)| {
+                if let Some(name) = key_name(&this.options.key_names, key) {
+                    this.output.push_str(&format!("/ {} / ", name));
+                }
+                let start = this.output.len();
+                this.child_to_diag(0, |this| this.item_to_diag(key));
+                this.output.push(':');
+                // A key wrapped over multiple lines is followed by the value
+                // on its own line, indented, to keep them apart
+                let wrapped = this.output[start..].contains('\n');
+                if wrapped {
+                    this.indent += 4;
+                    this.line();
+                    this.indent();
+                } else if this.pretty() {
+                    this.output.push(' ');
+                }
+                this.child_to_diag(1, |this| this.item_to_diag(value));
+                if wrapped {
+                    this.indent -= 4;
+                }
+            },
+        );
+    }
 
-        match tag {
-            Tag::ENCODED_BASE64URL => {
-                self.with_encoding(Encoding::Base64Url).item_to_diag(value);
-            }
-            Tag::ENCODED_BASE64 => {
-                self.with_encoding(Encoding::Base64).item_to_diag(value);
-            }
-            Tag::ENCODED_BASE16 => {
-                self.with_encoding(Encoding::Base16).item_to_diag(value);
-            }
-            _ => {
-                self.item_to_diag(value);
-            }
+    pub fn tagged_to_diag(
+        &mut self,
+        tag_prefix: usize,
+        tag: Tag,
+        bitwidth: IntegerWidth,
+        value: &DataItem,
+    ) {
+        let start = self.output.len();
+        self.decimal(tag.0);
+        if let Some(indicator) = self.encoding_indicator(bitwidth) {
+            self.indicator(indicator);
         }
+        self.output.push('(');
+        // Include the closing paren, any tags nested directly within this
+        // will be closed on the same line too
+        self.tag_prefix = tag_prefix + self.output.len() - start + 1;
+
+        self.child_to_diag(0, |this| match expected_encoding(tag) {
+            Some(encoding) => this.with_encoding(encoding, |this| this.item_to_diag(value)),
+            None => this.item_to_diag(value),
+        });
+        self.tag_prefix = 0;
 
         self.output.push(')');
+
+        if tag == Tag::RATIONAL && self.options.rational_approximations {
+            let approximation = extract_rational(value)
+                .ok()
+                .and_then(|(numerator, denominator)| approximate(&numerator, &denominator));
+            if let Some(approximation) = approximation {
+                let symbol = if self.options.escape_non_ascii {
+                    '~'
+                } else {
+                    '≈'
+                };
+                self.output
+                    .push_str(&format!(" / {} {} /", symbol, approximation));
+            }
+        }
     }
 
     fn float_to_diag(&mut self, value: f64, bitwidth: FloatWidth) {
@@ -342,22 +869,22 @@ impl<'a> Context<'a> {
             }
             self.output.push_str("Infinity");
         } else {
-            let value = match bitwidth {
-                FloatWidth::Unknown | FloatWidth::SixtyFour => value.to_string(),
-                FloatWidth::Sixteen => f16::from_f64(value).to_string(),
-                FloatWidth::ThirtyTwo => (value as f32).to_string(),
-            };
-            self.output.push_str(&value);
-            if !value.contains('.') && !value.contains('e') {
+            let start = self.output.len();
+            match bitwidth {
+                FloatWidth::Unknown | FloatWidth::SixtyFour => self.display(value),
+                FloatWidth::Sixteen => self.display(f16::from_f64(value)),
+                FloatWidth::ThirtyTwo => self.display(value as f32),
+            }
+            if !self.output[start..].contains(['.', 'e']) {
                 self.output.push_str(".0");
             }
         }
-        self.output.push_str(match bitwidth {
-            FloatWidth::Unknown => "",
-            FloatWidth::Sixteen => "_1",
-            FloatWidth::ThirtyTwo => "_2",
-            FloatWidth::SixtyFour => "_3",
-        });
+        if !self.options.emit_encoding_indicators {
+            return;
+        }
+        if let Some(indicator) = bitwidth.indicator() {
+            self.indicator(indicator);
+        }
     }
 
     fn simple_to_diag(&mut self, simple: Simple) {
@@ -371,6 +898,7 @@ impl<'a> Context<'a> {
     }
 
     fn item_to_diag(&mut self, value: &DataItem) {
+        let tag_prefix = std::mem::take(&mut self.tag_prefix);
         match *value {
             DataItem::Integer { value, bitwidth } => {
                 self.integer_to_diag(value, bitwidth);
@@ -381,41 +909,73 @@ impl<'a> Context<'a> {
             DataItem::ByteString(ref bytestring) => {
                 self.definite_bytestring_to_diag(bytestring);
             }
+            DataItem::IndefiniteByteString(_)
+            | DataItem::IndefiniteTextString(_)
+            | DataItem::InvalidIndefiniteTextString(_)
+                if self.options.normalize_indefinite || !self.options.emit_encoding_indicators =>
+            {
+                self.item_to_diag(&value.to_definite());
+            }
+            DataItem::IndefiniteByteString(_)
+            | DataItem::IndefiniteTextString(_)
+            | DataItem::InvalidIndefiniteTextString(_)
+                if self.excess_chunks(value).is_some() =>
+            {
+                self.coalesced_string_to_diag(value);
+            }
+            DataItem::IndefiniteByteString(ref bytestrings) if bytestrings.is_empty() => {
+                // `(_ )` is ambiguous, RFC 8949 § 8.1 reserves `''_` and `""_` for
+                // zero chunk strings
+                self.output.push_str("''_");
+            }
             DataItem::IndefiniteByteString(ref bytestrings) => {
                 self.indefinite_string_to_diag(
                     bytestrings,
-                    is_trivial(value),
+                    self.is_trivial(tag_prefix, value),
                     Self::definite_bytestring_to_diag,
                 );
             }
             DataItem::TextString(ref textstring) => {
                 self.definite_textstring_to_diag(textstring);
             }
+            DataItem::IndefiniteTextString(ref textstrings) if textstrings.is_empty() => {
+                self.output.push_str("\"\"_");
+            }
             DataItem::IndefiniteTextString(ref textstrings) => {
                 self.indefinite_string_to_diag(
                     textstrings,
-                    is_trivial(value),
+                    self.is_trivial(tag_prefix, value),
                     Self::definite_textstring_to_diag,
                 );
             }
+            DataItem::InvalidTextString(ref bytestring) => {
+                self.invalid_textstring_to_diag(bytestring);
+            }
+            DataItem::InvalidIndefiniteTextString(ref bytestrings) => {
+                self.indefinite_string_to_diag(
+                    bytestrings,
+                    self.is_trivial(tag_prefix, value),
+                    Self::invalid_textstring_to_diag,
+                );
+            }
             DataItem::Array {
                 ref data,
                 ref bitwidth,
             } => {
-                self.array_to_diag(data, bitwidth.is_some(), is_trivial(value));
+                self.array_to_diag(data, *bitwidth, self.is_trivial(tag_prefix, value));
             }
             DataItem::Map {
                 ref data,
                 ref bitwidth,
             } => {
-                self.map_to_diag(data, bitwidth.is_some(), is_trivial(value));
+                self.map_to_diag(data, *bitwidth, self.is_trivial(tag_prefix, value));
             }
             DataItem::Tag {
                 tag,
                 bitwidth,
                 ref value,
             } => {
-                self.tagged_to_diag(tag, bitwidth, &*value);
+                self.tagged_to_diag(tag_prefix, tag, bitwidth, &*value);
             }
             DataItem::Float { value, bitwidth } => {
                 self.float_to_diag(value, bitwidth);
@@ -423,20 +983,79 @@ impl<'a> Context<'a> {
             DataItem::Simple(simple) => {
                 self.simple_to_diag(simple);
             }
+            DataItem::AppLiteral {
+                ref prefix,
+                ref content,
+            } => {
+                self.output.push_str(prefix);
+                self.quoted_to_diag('\'', content);
+            }
         }
     }
 }
 
 impl DataItem {
+    /// Format as compact diagnostic notation on a single line.
+    ///
+    /// The output is plain text with no styling, and can be parsed back with
+    /// [`parse_diag`](crate::parse_diag).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_hex("a1 61 61 82 01 02").unwrap();
+    /// assert_eq!(item.to_diag(), r#"{"a":[1,2]}"#);
+    /// ```
     pub fn to_diag(&self) -> String {
+        self.to_diag_with(&DiagFormatOptions::default())
+    }
+
+    /// Like [`DataItem::to_diag`], but with configurable output.
+    pub fn to_diag_with(&self, options: &DiagFormatOptions) -> String {
         let mut s = String::with_capacity(128);
-        Context::new(&mut s, Layout::Compact).item_to_diag(self);
+        Context::new(&mut s, Layout::Compact, options).item_to_diag(self);
         s
     }
 
+    /// Format as diagnostic notation, with containers that don't fit on one
+    /// line wrapped and indented.
+    ///
+    /// Like [`DataItem::to_diag`], the output is plain text that can be parsed
+    /// back with [`parse_diag`](crate::parse_diag).
     pub fn to_diag_pretty(&self) -> String {
+        self.to_diag_pretty_with(&DiagFormatOptions::default())
+    }
+
+    /// Like [`DataItem::to_diag_pretty`], but with configurable output.
+    pub fn to_diag_pretty_with(&self, options: &DiagFormatOptions) -> String {
+        let mut s = String::with_capacity(128);
+        Context::new(&mut s, Layout::Pretty, options).item_to_diag(self);
+        s
+    }
+
+    /// Like [`DataItem::to_diag_pretty`], but showing `comments` attached to
+    /// this item and the items within it as `/ comment /`.
+    ///
+    /// See [`parse_diag_with_comments`](crate::parse_diag_with_comments) for
+    /// an example.
+    pub fn to_diag_pretty_with_comments(&self, comments: &Comments) -> String {
         let mut s = String::with_capacity(128);
-        Context::new(&mut s, Layout::Pretty).item_to_diag(self);
+        let options = DiagFormatOptions::default();
+        let mut ctx = Context::new(&mut s, Layout::Pretty, &options);
+        ctx.comments = Some(comments);
+        ctx.with_comments(|ctx| ctx.item_to_diag(self));
         s
     }
 }
+
+/// Formats as compact diagnostic notation, or pretty with the alternate flag
+/// (`{:#}`).
+impl fmt::Display for DataItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str(&self.to_diag_pretty())
+        } else {
+            f.write_str(&self.to_diag())
+        }
+    }
+}
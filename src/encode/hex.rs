@@ -1,29 +1,211 @@
 use std::{
-    ascii, cmp, i64, iter,
+    ascii, cmp,
+    collections::BTreeMap,
+    convert::TryFrom,
+    i64,
+    io::{self, Write},
+    iter,
     net::{Ipv4Addr, Ipv6Addr},
+    str,
 };
 
-use super::Encoding;
+use super::{
+    bytes::{encoded_width, integer_to_bytes},
+    canonical::minimal_float_width,
+    numbers::{
+        approximate, extract_integer, extract_negative_bignum, extract_positive_bignum,
+        extract_rational, key_name,
+    },
+    Encoding,
+};
 use base64::{self, display::Base64Display};
-use chrono::{DateTime, NaiveDateTime};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
 use half::f16;
-use num_bigint::{BigInt, BigUint, Sign};
+use num_bigint::BigInt;
 use num_rational::{BigRational, Ratio};
 use num_traits::pow::pow;
 use separator::Separatable;
 use url::Url;
 use uuid::Uuid;
 
-use crate::{parse_bytes, ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString};
+use crate::{
+    base32, parse::parse_full_date, parse_bytes, ByteString, CommentPosition, Comments, DataItem,
+    FloatWidth, IntegerWidth, Simple, Tag, TextString,
+};
 
-struct Line {
-    hex: String,
-    comment: String,
-    sublines: Vec<Line>,
+/// Options controlling the annotated hex output, see [`DataItem::to_hex_with`].
+#[derive(Debug, Clone)]
+pub struct HexOptions {
+    /// How many bytes of byte or text string content to show per line
+    /// (default 16).
+    ///
+    /// Text strings are shown on a single line if they are at most half as
+    /// long again as this, and lines may be extended to include the whole of
+    /// a character straddling the boundary. A value of `0` is treated as `1`.
+    pub bytes_per_line: usize,
+
+    /// Whether to show the content of byte strings as escaped ASCII in the
+    /// comment column (default `true`).
+    ///
+    /// Turning this off can make binary-heavy payloads easier to read, the
+    /// comments for byte strings with a suggested encoding are always shown.
+    pub show_ascii: bool,
+
+    /// The maximum number of lines to show for the content of a byte string.
+    ///
+    /// If a byte string would need more lines than this, only the lines at
+    /// the start and end are shown (about half of the limit each) with a
+    /// marker line noting how many bytes were omitted in between. If [`None`]
+    /// (the default) the full content is always shown.
+    pub max_bytestring_lines: Option<usize>,
+
+    /// The maximum number of chunks to show for an indefinite length string.
+    ///
+    /// If a string has more chunks than this, only the chunks at the start
+    /// and end are shown (about half of the limit each) with a marker line
+    /// noting how many chunks and bytes were omitted in between. If [`None`]
+    /// (the default) every chunk is shown.
+    pub max_chunks: Option<usize>,
+
+    /// The maximum column the comments are aligned to.
+    ///
+    /// Comments for all lines of the output normally start in a single column
+    /// just past the widest hex. If that is further right than this, the
+    /// comments are aligned here instead, and any line with hex extending past
+    /// it has its comment directly after the hex. If [`None`] (the default)
+    /// there is no limit.
+    pub max_comment_column: Option<usize>,
+
+    /// Whether to add comments describing the meaning of tagged values, such
+    /// as the decoded value of bignums or whether a regular expression is
+    /// valid (default `true`).
+    ///
+    /// Some of these require extra work such as parsing dates or nested CBOR
+    /// data, turning them off leaves only the structural comments.
+    pub semantic_comments: bool,
+
+    /// Whether to mark items that don't use the preferred serialization from
+    /// [RFC 8949 § 4.1][RFC 4.1] with `(non-preferred encoding)` (default
+    /// `false`).
+    ///
+    /// This covers integers, tags, lengths and floats that are encoded wider
+    /// than needed, and indefinite length items.
+    ///
+    /// [RFC 4.1]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.1
+    pub flag_non_preferred: bool,
+
+    /// Names for integer map keys, shown after the key's comment, e.g.
+    /// `01 # unsigned(1) (alg)` (default empty).
+    ///
+    /// These apply to the keys of all maps, for protocols such as COSE or
+    /// CWT that use small integers in place of text keys.
+    pub key_names: BTreeMap<i128, String>,
+
+    /// How many bytes of string content to put in each space separated group
+    /// on a line, e.g. `6162 6364` for `2` (default `0`, a single unbroken
+    /// group).
+    pub group: usize,
+
+    /// Whether to separate the initial byte of each header from its argument
+    /// with a space, e.g. `19 0018` rather than `190018` (default `true`).
+    pub split_headers: bool,
+}
+
+impl Default for HexOptions {
+    fn default() -> Self {
+        Self {
+            bytes_per_line: 16,
+            show_ascii: true,
+            max_bytestring_lines: None,
+            max_chunks: None,
+            max_comment_column: None,
+            semantic_comments: true,
+            flag_non_preferred: false,
+            key_names: BTreeMap::new(),
+            group: 0,
+            split_headers: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(super) struct Context<'a> {
+    pub(super) options: &'a HexOptions,
+    encoding: Option<Encoding>,
+    comments: Option<&'a Comments>,
+    /// The path of the current item as used by [`Comments`], only tracked
+    /// when there are comments
+    path: &'a [usize],
+}
+
+impl<'a> Context<'a> {
+    pub(super) fn new(options: &'a HexOptions) -> Self {
+        Self {
+            options,
+            encoding: None,
+            comments: None,
+            path: &[],
+        }
+    }
+
+    pub(super) fn with_encoding(self, encoding: Option<Encoding>) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// The path of the item at `indexes` within the current item.
+    fn child_path(self, indexes: &[usize]) -> Vec<usize> {
+        if self.comments.is_some() {
+            [self.path, indexes].concat()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn at<'b>(self, path: &'b [usize]) -> Context<'b>
+    where
+        'a: 'b,
+    {
+        Context {
+            options: self.options,
+            encoding: self.encoding,
+            comments: self.comments,
+            path,
+        }
+    }
+
+    /// Add any comments for the current item to its line.
+    fn with_comments(self, mut line: Line) -> Line {
+        if let Some(comments) = self.comments {
+            let before = comments.get(self.path, CommentPosition::Before);
+            let after = comments.get(self.path, CommentPosition::After);
+            for comment in before.iter().chain(after) {
+                line.comment.push_str(&format!(" / {} /", comment));
+            }
+        }
+        line
+    }
+
+    pub(super) fn bytes_per_line(self) -> usize {
+        cmp::max(1, self.options.bytes_per_line)
+    }
+
+    /// Mark `line` if its header is not the preferred serialization.
+    fn flag_non_preferred(self, mut line: Line, preferred: bool) -> Line {
+        if self.options.flag_non_preferred && !preferred {
+            line.comment.push_str(" (non-preferred encoding)");
+        }
+        line
+    }
+}
+
+pub(super) struct Line {
+    pub(super) hex: String,
+    pub(super) comment: String,
+    pub(super) sublines: Vec<Line>,
 }
 
 impl Line {
-    fn new(hex: impl Into<String>, comment: impl Into<String>) -> Line {
+    pub(super) fn new(hex: impl Into<String>, comment: impl Into<String>) -> Line {
         Line {
             hex: hex.into(),
             comment: comment.into(),
@@ -31,64 +213,133 @@ impl Line {
         }
     }
 
-    fn from_value(encoding: Option<Encoding>, value: &DataItem) -> Line {
-        match *value {
-            DataItem::Integer { value, bitwidth } => integer_to_hex(value, bitwidth),
-            DataItem::Negative { value, bitwidth } => negative_to_hex(value, bitwidth),
-            DataItem::ByteString(ref bytestring) => {
-                definite_bytestring_to_hex(encoding, bytestring)
-            }
-            DataItem::IndefiniteByteString(ref bytestrings) => {
-                indefinite_string_to_hex(0x02, "bytes", bytestrings, |bytestring| {
-                    definite_bytestring_to_hex(encoding, bytestring)
-                })
-            }
-            DataItem::TextString(ref textstring) => definite_textstring_to_hex(textstring),
-            DataItem::IndefiniteTextString(ref textstrings) => {
-                indefinite_string_to_hex(0x03, "text", textstrings, definite_textstring_to_hex)
+    pub(super) fn from_value(ctx: Context<'_>, value: &DataItem) -> Line {
+        let line = match *value {
+            DataItem::Integer { value, bitwidth } => integer_to_hex(ctx, value, bitwidth),
+            DataItem::Negative { value, bitwidth } => negative_to_hex(ctx, value, bitwidth),
+            DataItem::ByteString(ref bytestring) => definite_bytestring_to_hex(ctx, bytestring),
+            DataItem::IndefiniteByteString(ref bytestrings) => indefinite_string_to_hex(
+                ctx,
+                0x02,
+                "bytes",
+                bytestrings,
+                |bytestring| bytestring.data.len(),
+                |bytestring| definite_bytestring_to_hex(ctx, bytestring),
+            ),
+            DataItem::TextString(ref textstring) => definite_textstring_to_hex(ctx, textstring),
+            DataItem::IndefiniteTextString(ref textstrings) => indefinite_string_to_hex(
+                ctx,
+                0x03,
+                "text",
+                textstrings,
+                |textstring| textstring.data.len(),
+                |textstring| definite_textstring_to_hex(ctx, textstring),
+            ),
+            DataItem::InvalidTextString(ref bytestring) => {
+                invalid_textstring_to_hex(ctx, bytestring)
             }
-            DataItem::Array { ref data, bitwidth } => array_to_hex(encoding, data, bitwidth),
-            DataItem::Map { ref data, bitwidth } => map_to_hex(encoding, data, bitwidth),
+            DataItem::InvalidIndefiniteTextString(ref bytestrings) => indefinite_string_to_hex(
+                ctx,
+                0x03,
+                "text",
+                bytestrings,
+                |bytestring| bytestring.data.len(),
+                |bytestring| invalid_textstring_to_hex(ctx, bytestring),
+            ),
+            DataItem::Array { ref data, bitwidth } => array_to_hex(ctx, data, bitwidth),
+            DataItem::Map { ref data, bitwidth } => map_to_hex(ctx, data, bitwidth),
             DataItem::Tag {
                 tag,
                 bitwidth,
                 ref value,
-            } => tagged_to_hex(encoding, tag, bitwidth, &*value),
-            DataItem::Float { value, bitwidth } => float_to_hex(value, bitwidth),
-            DataItem::Simple(simple) => simple_to_hex(simple),
+            } => tagged_to_hex(ctx, tag, bitwidth, &*value),
+            DataItem::Float { value, bitwidth } => float_to_hex(ctx, value, bitwidth),
+            DataItem::Simple(simple) => simple_to_hex(ctx, simple),
+            DataItem::AppLiteral {
+                ref prefix,
+                ref content,
+            } => {
+                let mut literal = format!("{}'", prefix);
+                for c in content.chars() {
+                    if c == '\'' || c == '\\' || c.is_control() {
+                        literal.extend(c.escape_default());
+                    } else {
+                        literal.push(c);
+                    }
+                }
+                literal.push('\'');
+                Line::new(
+                    "",
+                    format!("application literal {}, no binary encoding", literal),
+                )
+            }
+        };
+        ctx.with_comments(line)
+    }
+
+    fn merge(self, ctx: Context<'_>) -> String {
+        let mut output = Vec::with_capacity(128);
+        self.write(ctx, &mut output)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(output).expect("all lines are valid UTF-8")
+    }
+
+    fn write(self, ctx: Context<'_>, output: &mut impl Write) -> io::Result<()> {
+        let mut column = self.hex_width();
+        if let Some(max) = ctx.options.max_comment_column {
+            column = cmp::min(column, max);
         }
+        self.do_merge(column, 0, output)
     }
 
-    fn merge(self) -> String {
-        let hex_width = self.hex_width();
-        let mut output = String::with_capacity(128);
-        self.do_merge(hex_width as isize, 0, &mut output);
-        output
+    /// Write this line and its sublines, aligning the comments to start after
+    /// `column`, lines with hex extending past that have their comment
+    /// directly after the hex instead.
+    fn do_merge(
+        self,
+        column: usize,
+        indent_level: usize,
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        self.write_line(column, indent_level, output)?;
+
+        for line in self.sublines {
+            line.do_merge(column, indent_level + 1, output)?;
+        }
+
+        Ok(())
     }
 
-    fn do_merge(self, hex_width: isize, indent_level: usize, output: &mut String) {
-        let (hex_indent, width) = if hex_width < 0 {
-            (indent_level * 3 - hex_width.abs() as usize, 0)
+    /// Write just this line, without its sublines, as [`Line::do_merge`]
+    /// does.
+    pub(super) fn write_line(
+        &self,
+        column: usize,
+        indent_level: usize,
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        let hex_indent = if self.hex.is_empty() {
+            0
         } else {
-            (indent_level * 3, hex_width as usize)
+            indent_level * 3
         };
 
-        output.push_str(&format!(
-            "{blank:hex_indent$}{hex:width$} # {blank:comment_indent$}{comment}\n",
-            blank = "",
-            hex_indent = hex_indent,
-            comment_indent = indent_level * 2,
-            hex = self.hex,
-            width = width,
-            comment = self.comment
-        ));
-
-        for line in self.sublines {
-            line.do_merge(hex_width - 3, indent_level + 1, output);
+        // Written piece by piece rather than through `core::fmt`, which pads
+        // one character at a time and dominates the time taken for large
+        // items
+        write_spaces(output, hex_indent)?;
+        output.write_all(self.hex.as_bytes())?;
+        if !self.comment.is_empty() {
+            let padding = column.saturating_sub(hex_indent + self.hex.len());
+            write_spaces(output, padding)?;
+            output.write_all(b" # ")?;
+            write_spaces(output, indent_level * 2)?;
+            output.write_all(self.comment.as_bytes())?;
         }
+        output.write_all(b"\n")
     }
 
-    fn hex_width(&self) -> usize {
+    pub(super) fn hex_width(&self) -> usize {
         cmp::max(
             self.hex.len(),
             self.sublines
@@ -107,100 +358,105 @@ impl Line {
     }
 }
 
-fn integer_to_hex(value: u64, mut bitwidth: IntegerWidth) -> Line {
-    if bitwidth == IntegerWidth::Unknown {
-        bitwidth = if value < 24 {
-            IntegerWidth::Zero
-        } else if value < u64::from(u8::max_value()) {
-            IntegerWidth::Eight
-        } else if value < u64::from(u16::max_value()) {
-            IntegerWidth::Sixteen
-        } else if value < u64::from(u32::max_value()) {
-            IntegerWidth::ThirtyTwo
-        } else {
-            IntegerWidth::SixtyFour
-        };
+fn write_spaces(output: &mut impl Write, mut count: usize) -> io::Result<()> {
+    const SPACES: [u8; 64] = [b' '; 64];
+    while count > 0 {
+        let chunk = cmp::min(count, SPACES.len());
+        output.write_all(&SPACES[..chunk])?;
+        count -= chunk;
     }
+    Ok(())
+}
 
-    let hex = match bitwidth {
-        IntegerWidth::Unknown => unreachable!(),
-        IntegerWidth::Zero => format!("{:02x}", value),
-        IntegerWidth::Eight => format!("18 {:02x}", value),
-        IntegerWidth::Sixteen => format!("19 {:04x}", value),
-        IntegerWidth::ThirtyTwo => format!("1a {:08x}", value),
-        IntegerWidth::SixtyFour => format!("1b {:016x}", value),
+/// Append the lowercase hex encoding of `data` to `output`.
+fn push_hex(output: &mut String, data: &[u8]) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    output.reserve(data.len() * 2);
+    for &byte in data {
+        output.push(char::from(DIGITS[usize::from(byte >> 4)]));
+        output.push(char::from(DIGITS[usize::from(byte & 0x0f)]));
+    }
+}
+
+/// The hex of one line's worth of string content, in groups as set by
+/// [`HexOptions::group`].
+fn content_hex(ctx: Context<'_>, datum: &[u8]) -> String {
+    let group = match ctx.options.group {
+        0 => datum.len().max(1),
+        group => group,
     };
+    let mut hex = String::with_capacity(datum.len() * 2 + datum.len() / group);
+    for (index, chunk) in datum.chunks(group).enumerate() {
+        if index > 0 {
+            hex.push(' ');
+        }
+        push_hex(&mut hex, chunk);
+    }
+    hex
+}
+
+/// The hex of an encoded initial byte and its argument, separated by a space
+/// unless [`HexOptions::split_headers`] is off.
+fn header_hex(ctx: Context<'_>, header: &[u8]) -> String {
+    let mut hex = String::with_capacity(header.len() * 2 + 1);
+    push_hex(&mut hex, &header[..1]);
+    if header.len() > 1 {
+        if ctx.options.split_headers {
+            hex.push(' ');
+        }
+        push_hex(&mut hex, &header[1..]);
+    }
+    hex
+}
+
+/// The hex of the initial byte and argument encoding `value` with major type
+/// `major`.
+fn argument_hex(ctx: Context<'_>, major: u8, value: u64, bitwidth: IntegerWidth) -> String {
+    let mut header = Vec::with_capacity(9);
+    integer_to_bytes(&mut header, value, bitwidth, major);
+    header_hex(ctx, &header)
+}
+
+fn integer_to_hex(ctx: Context<'_>, value: u64, bitwidth: IntegerWidth) -> Line {
+    let bitwidth = encoded_width(value, bitwidth);
+
+    let hex = argument_hex(ctx, 0, value, bitwidth);
 
     let comment = format!("unsigned({})", value.separated_string());
 
-    Line::new(hex, comment)
+    ctx.flag_non_preferred(
+        Line::new(hex, comment),
+        bitwidth == IntegerWidth::minimal_for(value),
+    )
 }
 
-fn negative_to_hex(value: u64, mut bitwidth: IntegerWidth) -> Line {
-    if bitwidth == IntegerWidth::Unknown {
-        bitwidth = if value < 24 {
-            IntegerWidth::Zero
-        } else if value < u64::from(u8::max_value()) {
-            IntegerWidth::Eight
-        } else if value < u64::from(u16::max_value()) {
-            IntegerWidth::Sixteen
-        } else if value < u64::from(u32::max_value()) {
-            IntegerWidth::ThirtyTwo
-        } else {
-            IntegerWidth::SixtyFour
-        };
-    }
+fn negative_to_hex(ctx: Context<'_>, value: u64, bitwidth: IntegerWidth) -> Line {
+    let bitwidth = encoded_width(value, bitwidth);
 
-    let hex = match bitwidth {
-        IntegerWidth::Unknown => unreachable!(),
-        IntegerWidth::Zero => format!("{:02x}", value + 0x20),
-        IntegerWidth::Eight => format!("38 {:02x}", value),
-        IntegerWidth::Sixteen => format!("39 {:04x}", value),
-        IntegerWidth::ThirtyTwo => format!("3a {:08x}", value),
-        IntegerWidth::SixtyFour => format!("3b {:016x}", value),
-    };
+    let hex = argument_hex(ctx, 1, value, bitwidth);
 
     let comment = format!("negative({})", (-1 - i128::from(value)).separated_string());
 
-    Line::new(hex, comment)
+    ctx.flag_non_preferred(
+        Line::new(hex, comment),
+        bitwidth == IntegerWidth::minimal_for(value),
+    )
 }
 
-fn length_to_hex(
+pub(super) fn length_to_hex(
+    ctx: Context<'_>,
     length: Option<usize>,
-    mut bitwidth: Option<IntegerWidth>,
+    bitwidth: Option<IntegerWidth>,
     major: u8,
     kind: &str,
 ) -> Line {
     // TODO: Rearrange the data to remove the unwraps.
 
-    if bitwidth == Some(IntegerWidth::Unknown) {
-        bitwidth = if length.unwrap() < 24 {
-            Some(IntegerWidth::Zero)
-        } else if length.unwrap() < usize::from(u8::max_value()) {
-            Some(IntegerWidth::Eight)
-        } else if length.unwrap() < usize::from(u16::max_value()) {
-            Some(IntegerWidth::Sixteen)
-        } else if length.unwrap() < u32::max_value() as usize {
-            Some(IntegerWidth::ThirtyTwo)
-        } else {
-            Some(IntegerWidth::SixtyFour)
-        };
-    }
+    let bitwidth = bitwidth.map(|bitwidth| encoded_width(length.unwrap() as u64, bitwidth));
 
     let hex = match bitwidth {
-        Some(IntegerWidth::Unknown) => unreachable!(),
-        Some(IntegerWidth::Zero) => format!("{:02x}", (length.unwrap() as u8) + (major << 5)),
-        Some(IntegerWidth::Eight) => format!("{:02x} {:02x}", (major << 5) | 0x18, length.unwrap()),
-        Some(IntegerWidth::Sixteen) => {
-            format!("{:02x} {:04x}", (major << 5) | 0x19, length.unwrap())
-        }
-        Some(IntegerWidth::ThirtyTwo) => {
-            format!("{:02x} {:08x}", (major << 5) | 0x1a, length.unwrap())
-        }
-        Some(IntegerWidth::SixtyFour) => {
-            format!("{:02x} {:016x}", (major << 5) | 0x1b, length.unwrap())
-        }
-        None => format!("{:02x}", (major << 5) | 0x1F),
+        Some(bitwidth) => argument_hex(ctx, major, length.unwrap() as u64, bitwidth),
+        None => header_hex(ctx, &[(major << 5) | 0x1F]),
     };
 
     let comment = format!(
@@ -213,83 +469,164 @@ fn length_to_hex(
         },
     );
 
-    Line::new(hex, comment)
+    let preferred =
+        bitwidth.is_some() && bitwidth == length.map(|l| IntegerWidth::minimal_for(l as u64));
+    ctx.flag_non_preferred(Line::new(hex, comment), preferred)
 }
 
-fn bytes_to_hex(encoding: Option<Encoding>, data: &[u8]) -> impl Iterator<Item = Line> + '_ {
-    data.chunks(16).map(move |datum| {
-        let hex = hex::encode(datum);
-        let comment = match encoding {
-            Some(Encoding::Base64Url) => format!(
-                "b64'{}'",
-                Base64Display::with_config(&data, base64::URL_SAFE_NO_PAD)
-            ),
-            Some(Encoding::Base64) => format!(
-                "b64'{}'",
-                Base64Display::with_config(&data, base64::STANDARD_NO_PAD)
-            ),
-            Some(Encoding::Base16) => format!("h'{}'", hex),
-            None => {
-                let text: String = datum
-                    .iter()
-                    .cloned()
-                    .flat_map(ascii::escape_default)
-                    .map(char::from)
-                    .collect();
-                format!("\"{}\"", text)
+/// The line showing `datum`, one line's worth of the content of a byte
+/// string.
+pub(super) fn bytes_line(ctx: Context<'_>, datum: &[u8]) -> Line {
+    let hex = content_hex(ctx, datum);
+    let comment = match ctx.encoding {
+        Some(Encoding::Base64Url) => format!(
+            "b64'{}'",
+            Base64Display::with_config(datum, base64::URL_SAFE_NO_PAD)
+        ),
+        Some(Encoding::Base64) => format!(
+            "b64'{}'",
+            Base64Display::with_config(datum, base64::STANDARD_NO_PAD)
+        ),
+        Some(Encoding::Base32) => format!("b32'{}'", base32::encode(datum, base32::BASE32)),
+        Some(Encoding::Base32Hex) => {
+            format!("h32'{}'", base32::encode(datum, base32::BASE32HEX))
+        }
+        Some(Encoding::Base16) => {
+            let mut comment = String::with_capacity(datum.len() * 2 + 3);
+            comment.push_str("h'");
+            push_hex(&mut comment, datum);
+            comment.push('\'');
+            comment
+        }
+        Some(Encoding::Text) | None if !ctx.options.show_ascii => String::new(),
+        Some(Encoding::Text) | None => {
+            let mut comment = String::with_capacity(datum.len() + 2);
+            comment.push('"');
+            for &byte in datum {
+                match byte {
+                    b'"' | b'\'' | b'\\' => {
+                        comment.extend(ascii::escape_default(byte).map(char::from))
+                    }
+                    b' '..=b'~' => comment.push(char::from(byte)),
+                    _ => comment.extend(ascii::escape_default(byte).map(char::from)),
+                }
             }
-        };
-        Line::new(hex, comment)
-    })
+            comment.push('"');
+            comment
+        }
+    };
+    Line::new(hex, comment)
 }
 
-fn definite_bytestring_to_hex(encoding: Option<Encoding>, bytestring: &ByteString) -> Line {
-    let ByteString { ref data, bitwidth } = *bytestring;
+/// The lines left out of the content of a byte string `len` bytes long, as
+/// the index of the first line omitted, the index of the first line shown
+/// after them and the number of bytes omitted.
+pub(super) fn omitted_lines(ctx: Context<'_>, len: usize) -> Option<(usize, usize, usize)> {
+    let bytes_per_line = ctx.bytes_per_line();
+    let lines = len.div_ceil(bytes_per_line);
+    match ctx.options.max_bytestring_lines {
+        Some(max) if lines > max => {
+            let head = max - max / 2;
+            let tail_start = lines - max / 2;
+            let omitted = cmp::min(tail_start * bytes_per_line, len) - head * bytes_per_line;
+            Some((head, tail_start, omitted))
+        }
+        _ => None,
+    }
+}
 
-    let mut line = length_to_hex(Some(data.len()), Some(bitwidth), 2, "bytes");
+/// The line standing in for `omitted` bytes of a byte string.
+pub(super) fn omitted_bytes_line(omitted: usize) -> Line {
+    Line::new(
+        "",
+        format!("... ({} bytes omitted) ...", omitted.separated_string()),
+    )
+}
+
+fn bytes_to_hex(ctx: Context<'_>, data: &[u8]) -> Vec<Line> {
+    let chunks = data.chunks(ctx.bytes_per_line());
+    match omitted_lines(ctx, data.len()) {
+        Some((head, tail_start, omitted)) => {
+            let mut lines = Vec::with_capacity(head + chunks.len() - tail_start + 1);
+            lines.extend(
+                chunks
+                    .clone()
+                    .take(head)
+                    .map(|datum| bytes_line(ctx, datum)),
+            );
+            lines.push(omitted_bytes_line(omitted));
+            lines.extend(chunks.skip(tail_start).map(|datum| bytes_line(ctx, datum)));
+            lines
+        }
+        None => chunks.map(|datum| bytes_line(ctx, datum)).collect(),
+    }
+}
+
+fn definite_bytestring_to_hex(ctx: Context<'_>, bytestring: &ByteString) -> Line {
+    let ByteString {
+        ref data, bitwidth, ..
+    } = *bytestring;
+
+    let mut line = length_to_hex(ctx, Some(data.len()), Some(bitwidth), 2, "bytes");
 
     if data.is_empty() {
         line.sublines.push(Line::new("", "\"\""));
     } else {
-        line.sublines.extend(bytes_to_hex(encoding, data))
+        line.sublines.extend(bytes_to_hex(ctx, data))
     }
 
     line
 }
 
-fn definite_textstring_to_hex(textstring: &TextString) -> Line {
+/// The line showing `datum`, one line's worth of the content of a text
+/// string.
+pub(super) fn text_line(ctx: Context<'_>, datum: &str) -> Line {
+    let hex = content_hex(ctx, datum.as_bytes());
+    let mut comment = String::with_capacity(datum.len() + 2);
+    comment.push('"');
+    for c in datum.chars() {
+        if c == '\"' || c == '\\' || c.is_control() {
+            for c in c.escape_default() {
+                comment.push(c);
+            }
+        } else {
+            comment.push(c);
+        }
+    }
+    comment.push('"');
+    Line::new(hex, comment)
+}
+
+/// Whether the content of a text string `len` bytes long is shown on a single
+/// line, rather than split at about every [`HexOptions::bytes_per_line`]
+/// bytes.
+pub(super) fn is_single_text_line(ctx: Context<'_>, len: usize) -> bool {
+    let bytes_per_line = ctx.bytes_per_line();
+    len <= bytes_per_line + bytes_per_line / 2
+}
+
+fn definite_textstring_to_hex(ctx: Context<'_>, textstring: &TextString) -> Line {
     let TextString { ref data, bitwidth } = *textstring;
 
-    let mut line = length_to_hex(Some(data.len()), Some(bitwidth), 3, "text");
+    let mut line = length_to_hex(ctx, Some(data.len()), Some(bitwidth), 3, "text");
 
     if data.is_empty() {
         line.sublines.push(Line::new("", "\"\""));
     } else {
-        let mut push_line = |datum: &str| {
-            let hex = hex::encode(datum);
-            let mut comment = String::with_capacity(datum.len());
-            comment.push('"');
-            for c in datum.chars() {
-                if c == '\"' || c == '\\' || c.is_control() {
-                    for c in c.escape_default() {
-                        comment.push(c);
-                    }
-                } else {
-                    comment.push(c);
-                }
-            }
-            comment.push('"');
-            line.sublines.push(Line::new(hex, comment));
-        };
+        let mut push_line = |datum: &str| line.sublines.push(text_line(ctx, datum));
 
-        if data.len() <= 24 {
+        let bytes_per_line = ctx.bytes_per_line();
+        if is_single_text_line(ctx, data.len()) {
             push_line(&data);
         } else {
             let mut data = data.as_str();
             while !data.is_empty() {
-                let mut split = 16;
+                // Extend the line to include the whole of any character
+                // straddling the line boundary so every comment shows
+                // complete characters.
+                let mut split = cmp::min(bytes_per_line, data.len());
                 while !data.is_char_boundary(split) {
-                    split -= 1;
+                    split += 1;
                 }
                 let (datum, new_data) = data.split_at(split);
                 data = new_data;
@@ -301,30 +638,79 @@ fn definite_textstring_to_hex(textstring: &TextString) -> Line {
     line
 }
 
+fn invalid_textstring_to_hex(ctx: Context<'_>, bytestring: &ByteString) -> Line {
+    let ByteString {
+        ref data, bitwidth, ..
+    } = *bytestring;
+
+    // Chunks of an indefinite length string may still be valid by themselves
+    if let Ok(data) = str::from_utf8(data) {
+        let data = data.to_owned();
+        return definite_textstring_to_hex(ctx, &TextString { data, bitwidth });
+    }
+
+    let mut line = length_to_hex(ctx, Some(data.len()), Some(bitwidth), 3, "text");
+    line.comment.push_str(" (invalid UTF-8)");
+    line.sublines
+        .extend(bytes_to_hex(ctx.with_encoding(None), data));
+
+    line
+}
+
 fn indefinite_string_to_hex<T>(
+    ctx: Context<'_>,
     major: u8,
     name: &str,
     strings: &[T],
+    chunk_len: impl Fn(&T) -> usize,
     definite_string_to_hex: impl Fn(&T) -> Line,
 ) -> Line {
-    let mut line = length_to_hex(None, None, major, name);
+    let mut line = length_to_hex(ctx, None, None, major, name);
 
-    line.sublines
-        .extend(strings.iter().map(definite_string_to_hex));
+    let chunk_to_line = |(index, string): (usize, &T)| {
+        let path = ctx.child_path(&[index]);
+        ctx.at(&path).with_comments(definite_string_to_hex(string))
+    };
+    match ctx.options.max_chunks {
+        Some(max) if strings.len() > max => {
+            let head = max - max / 2;
+            let tail_start = strings.len() - max / 2;
+            let omitted: usize = strings[head..tail_start].iter().map(chunk_len).sum();
+            line.sublines
+                .extend(strings.iter().enumerate().take(head).map(chunk_to_line));
+            line.sublines.push(Line::new(
+                "",
+                format!(
+                    "... ({} chunks, {} bytes omitted) ...",
+                    (tail_start - head).separated_string(),
+                    omitted.separated_string()
+                ),
+            ));
+            line.sublines.extend(
+                strings
+                    .iter()
+                    .enumerate()
+                    .skip(tail_start)
+                    .map(chunk_to_line),
+            );
+        }
+        _ => line
+            .sublines
+            .extend(strings.iter().enumerate().map(chunk_to_line)),
+    }
     line.sublines.push(Line::new("ff", "break"));
 
     line
 }
 
-fn array_to_hex(
-    encoding: Option<Encoding>,
-    array: &[DataItem],
-    bitwidth: Option<IntegerWidth>,
-) -> Line {
-    let mut line = length_to_hex(Some(array.len()), bitwidth, 4, "array");
+fn array_to_hex(ctx: Context<'_>, array: &[DataItem], bitwidth: Option<IntegerWidth>) -> Line {
+    let mut line = length_to_hex(ctx, Some(array.len()), bitwidth, 4, "array");
 
     line.sublines
-        .extend(array.iter().map(|value| Line::from_value(encoding, value)));
+        .extend(array.iter().enumerate().map(|(index, value)| {
+            let path = ctx.child_path(&[index]);
+            Line::from_value(ctx.at(&path), value)
+        }));
 
     if bitwidth.is_none() {
         line.sublines.push(Line::new("ff", "break"));
@@ -334,18 +720,22 @@ fn array_to_hex(
 }
 
 fn map_to_hex(
-    encoding: Option<Encoding>,
+    ctx: Context<'_>,
     values: &[(DataItem, DataItem)],
     bitwidth: Option<IntegerWidth>,
 ) -> Line {
-    let mut line = length_to_hex(Some(values.len()), bitwidth, 5, "map");
+    let mut line = length_to_hex(ctx, Some(values.len()), bitwidth, 5, "map");
 
-    line.sublines.extend(
-        values
-            .iter()
-            .flat_map(|(v1, v2)| iter::once(v1).chain(iter::once(v2)))
-            .map(|value| Line::from_value(encoding, value)),
-    );
+    for (index, (key, value)) in values.iter().enumerate() {
+        let path = ctx.child_path(&[index, 0]);
+        let mut key_line = Line::from_value(ctx.at(&path), key);
+        if let Some(name) = key_name(&ctx.options.key_names, key) {
+            key_line.comment.push_str(&format!(" ({})", name));
+        }
+        line.sublines.push(key_line);
+        let path = ctx.child_path(&[index, 1]);
+        line.sublines.push(Line::from_value(ctx.at(&path), value));
+    }
 
     if bitwidth.is_none() {
         line.sublines.push(Line::new("ff", "break"));
@@ -354,36 +744,9 @@ fn map_to_hex(
     line
 }
 
-fn tagged_to_hex(
-    encoding: Option<Encoding>,
-    tag: Tag,
-    mut bitwidth: IntegerWidth,
-    value: &DataItem,
-) -> Line {
-    if bitwidth == IntegerWidth::Unknown {
-        bitwidth = if tag.0 < 24 {
-            IntegerWidth::Zero
-        } else if tag.0 < u64::from(u8::max_value()) {
-            IntegerWidth::Eight
-        } else if tag.0 < u64::from(u16::max_value()) {
-            IntegerWidth::Sixteen
-        } else if tag.0 < u64::from(u32::max_value()) {
-            IntegerWidth::ThirtyTwo
-        } else {
-            IntegerWidth::SixtyFour
-        };
-    }
-
-    let hex = match bitwidth {
-        IntegerWidth::Unknown => unreachable!(),
-        IntegerWidth::Zero => format!("{:02x}", 0xc0 | tag.0),
-        IntegerWidth::Eight => format!("d8 {:02x}", tag.0),
-        IntegerWidth::Sixteen => format!("d9 {:04x}", tag.0),
-        IntegerWidth::ThirtyTwo => format!("da {:08x}", tag.0),
-        IntegerWidth::SixtyFour => format!("db {:016x}", tag.0),
-    };
-
-    let extra = match tag {
+/// A short description of the meaning of `tag`, if it is a known one.
+pub(super) fn tag_name(tag: Tag) -> Option<&'static str> {
+    match tag {
         Tag::DATETIME => Some("standard datetime string"),
         Tag::EPOCH_DATETIME => Some("epoch datetime value"),
         Tag::POSITIVE_BIGNUM => Some("positive bignum"),
@@ -394,54 +757,111 @@ fn tagged_to_hex(
         Tag::ENCODED_BASE64 => Some("suggested base64 encoding"),
         Tag::ENCODED_BASE16 => Some("suggested base16 encoding"),
         Tag::ENCODED_CBOR => Some("encoded cbor data item"),
+        Tag::RATIONAL => Some("rational"),
         Tag::URI => Some("uri"),
         Tag::BASE64URL => Some("base64url encoded text"),
         Tag::BASE64 => Some("base64 encoded text"),
-        Tag::REGEX => Some("regex"),
-        Tag::MIME => Some("mime message"),
+        Tag::REGEX => Some("regular expression"),
+        Tag::MIME => Some("MIME message"),
         Tag::UUID => Some("uuid"),
+        Tag::EPOCH_DATE => Some("days since epoch"),
+        Tag::DATE => Some("full-date string"),
         Tag::NETWORK_ADDRESS => Some("network address"),
         Tag::SELF_DESCRIBE_CBOR => Some("self describe cbor"),
         _ => None,
+    }
+}
+
+/// Whether the content of `tag` gets an extra line explaining its meaning.
+pub(super) fn has_semantic_comment(ctx: Context<'_>, tag: Tag) -> bool {
+    ctx.options.semantic_comments
+        && matches!(
+            tag,
+            Tag::DATETIME
+                | Tag::EPOCH_DATETIME
+                | Tag::POSITIVE_BIGNUM
+                | Tag::NEGATIVE_BIGNUM
+                | Tag::DECIMAL_FRACTION
+                | Tag::BIGFLOAT
+                | Tag::RATIONAL
+                | Tag::URI
+                | Tag::REGEX
+                | Tag::MIME
+                | Tag::BASE64URL
+                | Tag::BASE64
+                | Tag::ENCODED_CBOR
+                | Tag::NETWORK_ADDRESS
+                | Tag::UUID
+                | Tag::EPOCH_DATE
+                | Tag::DATE
+        )
+}
+
+/// The line for the header of `tag`, without its content.
+pub(super) fn tag_line(ctx: Context<'_>, tag: Tag, bitwidth: IntegerWidth) -> Line {
+    let bitwidth = encoded_width(tag.0, bitwidth);
+
+    let hex = argument_hex(ctx, 6, tag.0, bitwidth);
+
+    let comment = if let Some(extra) = tag_name(tag) {
+        format!("{}, tag({})", extra, tag.0)
+    } else {
+        format!("tag({})", tag.0)
     };
 
+    ctx.flag_non_preferred(
+        Line::new(hex, comment),
+        bitwidth == IntegerWidth::minimal_for(tag.0),
+    )
+}
+
+/// The context for the content of `tag`, which suggests an encoding for any
+/// byte strings within it.
+pub(super) fn tag_content(ctx: Context<'_>, tag: Tag) -> Context<'_> {
+    let encoding = match tag {
+        Tag::ENCODED_BASE64URL => Some(Encoding::Base64Url),
+        Tag::ENCODED_BASE64 => Some(Encoding::Base64),
+        Tag::ENCODED_BASE16 => Some(Encoding::Base16),
+        Tag::NETWORK_ADDRESS => Some(Encoding::Base16),
+        Tag::UUID => Some(Encoding::Base16),
+        _ => ctx.encoding,
+    };
+    ctx.with_encoding(encoding)
+}
+
+fn tagged_to_hex(ctx: Context<'_>, tag: Tag, bitwidth: IntegerWidth, value: &DataItem) -> Line {
     let extra_line = match tag {
+        _ if !ctx.options.semantic_comments => None,
         Tag::DATETIME => Some(datetime_epoch(value)),
         Tag::EPOCH_DATETIME => Some(epoch_datetime(value)),
         Tag::POSITIVE_BIGNUM => Some(positive_bignum(value)),
         Tag::NEGATIVE_BIGNUM => Some(negative_bignum(value)),
         Tag::DECIMAL_FRACTION => Some(decimal_fraction(value)),
         Tag::BIGFLOAT => Some(bigfloat(value)),
+        Tag::RATIONAL => Some(rational(value)),
         Tag::URI => Some(uri(value)),
-        Tag::BASE64URL => Some(base64url(value)),
-        Tag::BASE64 => Some(base64(value)),
-        Tag::ENCODED_CBOR => Some(encoded_cbor(value)),
+        Tag::REGEX => regex(value),
+        Tag::MIME => mime(value),
+        Tag::BASE64URL => Some(base64url(ctx, value)),
+        Tag::BASE64 => Some(base64(ctx, value)),
+        Tag::ENCODED_CBOR => Some(encoded_cbor(ctx, value)),
         Tag::NETWORK_ADDRESS => Some(network_address(value)),
         Tag::UUID => Some(uuid(value)),
+        Tag::EPOCH_DATE => Some(epoch_date(value)),
+        Tag::DATE => Some(full_date(value)),
         _ => None,
     };
 
-    let encoding = match tag {
-        Tag::ENCODED_BASE64URL => Some(Encoding::Base64Url),
-        Tag::ENCODED_BASE64 => Some(Encoding::Base64),
-        Tag::ENCODED_BASE16 => Some(Encoding::Base16),
-        Tag::NETWORK_ADDRESS => Some(Encoding::Base16),
-        Tag::UUID => Some(Encoding::Base16),
-        _ => encoding,
-    };
-
-    let comment = if let Some(extra) = extra {
-        format!("{}, tag({})", extra, tag.0)
-    } else {
-        format!("tag({})", tag.0)
-    };
+    let line = tag_line(ctx, tag, bitwidth);
 
     Line {
-        hex,
-        comment,
-        sublines: iter::once(Line::from_value(encoding, value))
-            .chain(extra_line)
-            .collect(),
+        sublines: iter::once(Line::from_value(
+            tag_content(ctx.at(&ctx.child_path(&[0])), tag),
+            value,
+        ))
+        .chain(extra_line)
+        .collect(),
+        ..line
     }
 }
 
@@ -497,10 +917,13 @@ fn epoch_datetime(value: &DataItem) -> Line {
         | DataItem::IndefiniteByteString(..)
         | DataItem::TextString(..)
         | DataItem::IndefiniteTextString(..)
+        | DataItem::InvalidTextString(..)
+        | DataItem::InvalidIndefiniteTextString(..)
         | DataItem::Array { .. }
         | DataItem::Map { .. }
         | DataItem::Tag { .. }
-        | DataItem::Simple(..) => {
+        | DataItem::Simple(..)
+        | DataItem::AppLiteral { .. } => {
             return Line::new("", "invalid type for epoch datetime");
         }
     };
@@ -512,11 +935,40 @@ fn epoch_datetime(value: &DataItem) -> Line {
     }
 }
 
-fn extract_positive_bignum(value: &DataItem) -> Option<BigUint> {
-    if let DataItem::ByteString(ByteString { data, .. }) = value {
-        Some(BigUint::from_bytes_be(data))
+fn epoch_date(value: &DataItem) -> Line {
+    // The days from 0001-01-01 to 1970-01-01
+    const EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+    let days = match *value {
+        DataItem::Integer { value, .. } => i64::try_from(value).ok(),
+        DataItem::Negative { value, .. } => i64::try_from(value).ok().map(|value| -1 - value),
+        _ => return Line::new("", "invalid type for days since epoch"),
+    };
+
+    let date = days
+        .and_then(|days| days.checked_add(EPOCH_DAYS_FROM_CE))
+        .and_then(|days| i32::try_from(days).ok())
+        .and_then(NaiveDate::from_num_days_from_ce_opt);
+
+    if let Some(date) = date {
+        Line::new("", format!("date({})", date.format("%F")))
     } else {
-        None
+        Line::new("", "(date out of range)")
+    }
+}
+
+fn full_date(value: &DataItem) -> Line {
+    if let DataItem::TextString(TextString { data, .. }) = value {
+        Line::new(
+            "",
+            if parse_full_date(data).is_some() {
+                "valid full-date"
+            } else {
+                "invalid full-date (expected YYYY-MM-DD)"
+            },
+        )
+    } else {
+        Line::new("", "invalid type for full-date")
     }
 }
 
@@ -526,14 +978,6 @@ fn positive_bignum(value: &DataItem) -> Line {
         .unwrap_or_else(|| Line::new("", "invalid type for bignum"))
 }
 
-fn extract_negative_bignum(value: &DataItem) -> Option<BigInt> {
-    if let DataItem::ByteString(ByteString { data, .. }) = value {
-        Some(BigInt::from(-1) - BigInt::from_bytes_be(Sign::Plus, data))
-    } else {
-        None
-    }
-}
-
 fn negative_bignum(value: &DataItem) -> Line {
     extract_negative_bignum(value)
         .map(|num| Line::new("", format!("bignum({})", num)))
@@ -563,27 +1007,7 @@ fn extract_fraction(value: &DataItem, base: usize) -> Result<BigRational, &'stat
                 }
                 _ => return Err("invalid type"),
             };
-            let mantissa = match data[1] {
-                DataItem::Integer { value, .. } => BigInt::from(value),
-                DataItem::Negative { value, .. } => BigInt::from(-1) - BigInt::from(value),
-                DataItem::Tag {
-                    tag: Tag::POSITIVE_BIGNUM,
-                    ref value,
-                    ..
-                } => match extract_positive_bignum(&*value) {
-                    Some(value) => BigInt::from_biguint(Sign::Plus, value),
-                    _ => return Err("invalid type"),
-                },
-                DataItem::Tag {
-                    tag: Tag::NEGATIVE_BIGNUM,
-                    ref value,
-                    ..
-                } => match extract_negative_bignum(&*value) {
-                    Some(value) => value,
-                    _ => return Err("invalid type"),
-                },
-                _ => return Err("invalid type"),
-            };
+            let mantissa = extract_integer(&data[1]).ok_or("invalid type")?;
             let multiplier = if positive_exponent {
                 Ratio::from_integer(pow(BigInt::from(base), exponent))
             } else {
@@ -609,6 +1033,22 @@ fn bigfloat(value: &DataItem) -> Line {
         .unwrap_or_else(|err| Line::new("", format!("{} for bigfloat", err)))
 }
 
+fn rational(value: &DataItem) -> Line {
+    match extract_rational(value) {
+        Ok((numerator, denominator)) => match approximate(&numerator, &denominator) {
+            Some(approximation) => Line::new(
+                "",
+                format!(
+                    "rational({}/{} ≈ {})",
+                    numerator, denominator, approximation
+                ),
+            ),
+            None => Line::new("", format!("rational({}/{})", numerator, denominator)),
+        },
+        Err(err) => Line::new("", format!("{} for rational", err)),
+    }
+}
+
 fn uri(value: &DataItem) -> Line {
     if let DataItem::TextString(TextString { data, .. }) = value {
         Line::new(
@@ -624,7 +1064,114 @@ fn uri(value: &DataItem) -> Line {
     }
 }
 
+fn regex(value: &DataItem) -> Option<Line> {
+    if let DataItem::TextString(TextString { data, .. }) = value {
+        check_regex(data)
+            .err()
+            .map(|err| Line::new("", format!("(invalid: {})", err)))
+    } else {
+        Some(Line::new("", "invalid type for regular expression"))
+    }
+}
+
+/// A lightweight check of the syntax of a PCRE-like regular expression,
+/// catching unbalanced groups and classes and misplaced quantifiers.
+fn check_regex(pattern: &str) -> Result<(), &'static str> {
+    let mut chars = pattern.chars().peekable();
+    let mut depth = 0usize;
+    // Whether there is an atom before the current position that a quantifier
+    // could apply to
+    let mut repeatable = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next().ok_or("trailing backslash")?;
+                repeatable = true;
+            }
+            '(' => {
+                depth += 1;
+                // Group modifiers such as `(?:` and `(?<name>`
+                if chars.peek() == Some(&'?') {
+                    chars.next();
+                }
+                repeatable = false;
+            }
+            ')' => {
+                depth = depth.checked_sub(1).ok_or("unmatched ')'")?;
+                repeatable = true;
+            }
+            '[' => {
+                chars.next_if_eq(&'^');
+                // A leading `]` is part of the class
+                chars.next_if_eq(&']');
+                loop {
+                    match chars.next().ok_or("unterminated character class")? {
+                        '\\' => {
+                            chars.next().ok_or("trailing backslash")?;
+                        }
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+                repeatable = true;
+            }
+            '|' => repeatable = false,
+            '*' | '+' | '?' => {
+                if !repeatable {
+                    return Err("nothing to repeat");
+                }
+                // Lazy and possessive modifiers
+                if chars.peek() == Some(&'?') || chars.peek() == Some(&'+') {
+                    chars.next();
+                }
+                repeatable = false;
+            }
+            '^' | '$' => repeatable = false,
+            _ => repeatable = true,
+        }
+    }
+    if depth > 0 {
+        return Err("unmatched '('");
+    }
+    Ok(())
+}
+
+fn mime(value: &DataItem) -> Option<Line> {
+    if let DataItem::TextString(TextString { data, .. }) = value {
+        content_type(data)
+            .map(|content_type| Line::new("", format!("content-type({})", content_type)))
+    } else {
+        Some(Line::new("", "invalid type for MIME message"))
+    }
+}
+
+/// Find the value of the `Content-Type` header of a MIME message, unfolding
+/// any continuation lines.
+fn content_type(message: &str) -> Option<String> {
+    let mut lines = message
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .take_while(|line| !line.is_empty())
+        .peekable();
+    while let Some(line) = lines.next() {
+        let (name, value) = match line.find(':') {
+            Some(index) => (&line[..index], &line[index + 1..]),
+            None => continue,
+        };
+        if name.trim().eq_ignore_ascii_case("content-type") {
+            let mut value = value.trim().to_owned();
+            while let Some(next) = lines.next_if(|line| line.starts_with(&[' ', '\t'][..])) {
+                value.push(' ');
+                value.push_str(next.trim());
+            }
+            return Some(value).filter(|value| !value.is_empty());
+        }
+    }
+    None
+}
+
 fn base64_base(
+    ctx: Context<'_>,
     value: &DataItem,
     config: base64::Config,
 ) -> Result<impl Iterator<Item = Line>, String> {
@@ -632,8 +1179,9 @@ fn base64_base(
         base64::decode_config(data, config)
             .map(|data| {
                 let mut line = Line::new("", "");
-                line.sublines.extend(bytes_to_hex(None, &data));
-                let merged = line.merge();
+                line.sublines
+                    .extend(bytes_to_hex(ctx.with_encoding(None), &data));
+                let merged = line.merge(ctx);
                 merged
                     .lines()
                     .skip(1)
@@ -647,8 +1195,8 @@ fn base64_base(
     }
 }
 
-fn base64url(value: &DataItem) -> Line {
-    base64_base(value, base64::URL_SAFE_NO_PAD)
+fn base64url(ctx: Context<'_>, value: &DataItem) -> Line {
+    base64_base(ctx, value, base64::URL_SAFE_NO_PAD)
         .map(|lines| {
             let mut line = Line::new("", "base64url decoded");
             line.sublines.extend(lines);
@@ -657,8 +1205,8 @@ fn base64url(value: &DataItem) -> Line {
         .unwrap_or_else(|err| Line::new("", format!("{} for base64url", err)))
 }
 
-fn base64(value: &DataItem) -> Line {
-    base64_base(value, base64::STANDARD_NO_PAD)
+fn base64(ctx: Context<'_>, value: &DataItem) -> Line {
+    base64_base(ctx, value, base64::STANDARD_NO_PAD)
         .map(|lines| {
             let mut line = Line::new("", "base64 decoded");
             line.sublines.extend(lines);
@@ -667,13 +1215,17 @@ fn base64(value: &DataItem) -> Line {
         .unwrap_or_else(|err| Line::new("", format!("{} for base64", err)))
 }
 
-fn encoded_cbor(value: &DataItem) -> Line {
+fn encoded_cbor(ctx: Context<'_>, value: &DataItem) -> Line {
     if let DataItem::ByteString(ByteString { data, .. }) = value {
         match parse_bytes(data) {
             Ok(value) => {
                 let mut line = Line::new("", "encoded cbor data item");
-                line.sublines
-                    .extend(value.to_hex().lines().map(|line| Line::new("", line)));
+                line.sublines.extend(
+                    value
+                        .to_hex_with(ctx.options)
+                        .lines()
+                        .map(|line| Line::new("", line)),
+                );
                 line
             }
             Err(err) => {
@@ -753,16 +1305,31 @@ fn network_address(value: &DataItem) -> Line {
     }
 }
 
-fn float_to_hex(value: f64, mut bitwidth: FloatWidth) -> Line {
-    if bitwidth == FloatWidth::Unknown {
-        bitwidth = FloatWidth::SixtyFour;
-    }
+fn float_to_hex(ctx: Context<'_>, value: f64, bitwidth: FloatWidth) -> Line {
+    // An unknown width wasn't chosen by the sender, so isn't worth flagging
+    let preferred =
+        bitwidth == FloatWidth::Unknown || bitwidth == minimal_float_width(value, true).1;
+    let bitwidth = if bitwidth == FloatWidth::Unknown {
+        FloatWidth::SixtyFour
+    } else {
+        bitwidth
+    };
 
     let hex = match bitwidth {
         FloatWidth::Unknown => unreachable!(),
-        FloatWidth::Sixteen => format!("f9 {:04x}", f16::from_f64(value).to_bits()),
-        FloatWidth::ThirtyTwo => format!("fa {:08x}", (value as f32).to_bits()),
-        FloatWidth::SixtyFour => format!("fb {:016x}", value.to_bits()),
+        FloatWidth::Sixteen => {
+            let [a, b] = f16::from_f64(value).to_bits().to_be_bytes();
+            header_hex(ctx, &[0xf9, a, b])
+        }
+        FloatWidth::ThirtyTwo => {
+            let [a, b, c, d] = (value as f32).to_bits().to_be_bytes();
+            header_hex(ctx, &[0xfa, a, b, c, d])
+        }
+        FloatWidth::SixtyFour => {
+            let mut header = vec![0xfb];
+            header.extend_from_slice(&value.to_bits().to_be_bytes());
+            header_hex(ctx, &header)
+        }
     };
 
     let comment = format!(
@@ -780,16 +1347,16 @@ fn float_to_hex(value: f64, mut bitwidth: FloatWidth) -> Line {
         }
     );
 
-    Line::new(hex, comment)
+    ctx.flag_non_preferred(Line::new(hex, comment), preferred)
 }
 
-fn simple_to_hex(simple: Simple) -> Line {
+fn simple_to_hex(ctx: Context<'_>, simple: Simple) -> Line {
     let Simple(value) = simple;
 
     let hex = if value < 24 {
-        format!("{:02x}", 0b1110_0000 | value)
+        header_hex(ctx, &[0b1110_0000 | value])
     } else {
-        format!("f8 {:02x}", value)
+        header_hex(ctx, &[0xf8, value])
     };
 
     let extra = match simple {
@@ -797,7 +1364,7 @@ fn simple_to_hex(simple: Simple) -> Line {
         Simple::TRUE => "true, ",
         Simple::NULL => "null, ",
         Simple::UNDEFINED => "undefined, ",
-        Simple(24..=32) => "reserved, ",
+        _ if simple.is_reserved() => "reserved, ",
         _ => "unassigned, ",
     };
 
@@ -806,8 +1373,40 @@ fn simple_to_hex(simple: Simple) -> Line {
     Line::new(hex, comment)
 }
 
+/// The annotated hex lines for `item`, before they are merged into text.
+pub(super) fn lines(item: &DataItem, options: &HexOptions) -> Line {
+    Line::from_value(Context::new(options), item)
+}
+
 impl DataItem {
     pub fn to_hex(&self) -> String {
-        Line::from_value(None, self).merge()
+        self.to_hex_with(&HexOptions::default())
+    }
+
+    /// Like [`DataItem::to_hex`], but with configurable output.
+    pub fn to_hex_with(&self, options: &HexOptions) -> String {
+        let ctx = Context::new(options);
+        Line::from_value(ctx, self).merge(ctx)
+    }
+
+    /// Like [`DataItem::to_hex_with`], but adding `comments` attached to this
+    /// item and the items within it to the end of their lines as
+    /// `/ comment /`.
+    pub fn to_hex_with_comments(&self, options: &HexOptions, comments: &Comments) -> String {
+        let ctx = Context {
+            comments: Some(comments),
+            ..Context::new(options)
+        };
+        Line::from_value(ctx, self).merge(ctx)
+    }
+
+    /// Write the annotated hex output directly to `output` instead of
+    /// collecting it into a [`String`] first.
+    ///
+    /// The output is identical to [`DataItem::to_hex_with`], but can avoid
+    /// repeatedly reallocating a large buffer when dumping big data items.
+    pub fn write_hex(&self, options: &HexOptions, mut output: impl Write) -> io::Result<()> {
+        let ctx = Context::new(options);
+        Line::from_value(ctx, self).write(ctx, &mut output)
     }
 }
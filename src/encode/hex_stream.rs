@@ -0,0 +1,343 @@
+use std::{
+    cmp,
+    io::{self, Read, Seek, SeekFrom, Write},
+    str,
+};
+
+use super::{
+    hex::{
+        bytes_line, has_semantic_comment, is_single_text_line, length_to_hex, omitted_bytes_line,
+        omitted_lines, tag_content, tag_line, text_line, Context, Line,
+    },
+    numbers::key_name,
+};
+use crate::{
+    parse::{Header, StreamReader},
+    parse_bytes_with, DataItem, Event, HexOptions, ParseOptions, Result,
+};
+
+/// Where the lines go: either just measured to find the width of the hex, or
+/// written out aligned to that.
+enum Output<W> {
+    Measure { width: usize },
+    Write { column: usize, output: W },
+}
+
+struct Annotator<'a, R, W> {
+    reader: StreamReader<R>,
+    options: &'a HexOptions,
+    output: Output<W>,
+}
+
+impl<R: Read + Seek, W: Write> Annotator<'_, R, W> {
+    fn emit(&mut self, line: &Line, indent_level: usize) -> Result<()> {
+        match &mut self.output {
+            Output::Measure { width } => {
+                if !line.hex.is_empty() {
+                    *width = cmp::max(*width, indent_level * 3 + line.hex.len());
+                }
+            }
+            Output::Write { column, output } => line.write_line(*column, indent_level, output)?,
+        }
+        for subline in &line.sublines {
+            self.emit(subline, indent_level + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Annotate the single data item in the input, which must not be followed
+    /// by anything else.
+    fn document(&mut self) -> Result<()> {
+        self.item(Context::new(self.options), 0, false)?;
+        let offset = self.reader.offset();
+        if self.reader.peek()?.is_some() {
+            let remaining = self.reader.count_remaining()?;
+            return Err(format!(
+                "parsed 1 item ({} bytes), {} trailing bytes",
+                offset, remaining
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Annotate the next data item, with the names of integer keys added if
+    /// it is the key of a map entry.
+    fn item(&mut self, ctx: Context<'_>, indent_level: usize, key: bool) -> Result<()> {
+        let depth = self.reader.depth();
+        let (start, header) = self.reader.expect_next()?;
+        let event = match header {
+            Header::Event(event) => event,
+            Header::String {
+                major,
+                len,
+                bitwidth,
+            } => {
+                let kind = if major == 2 { "bytes" } else { "text" };
+                let line = length_to_hex(ctx, Some(len as usize), Some(bitwidth), major, kind);
+                return self.string(ctx, line, major, indent_level);
+            }
+        };
+
+        let value = match event {
+            Event::Integer { value, bitwidth } => DataItem::Integer { value, bitwidth },
+            Event::Negative { value, bitwidth } => DataItem::Negative { value, bitwidth },
+            Event::Float { value, bitwidth } => DataItem::Float { value, bitwidth },
+            Event::Simple(simple) => DataItem::Simple(simple),
+            Event::BeginByteString | Event::BeginTextString if ctx.options.max_chunks.is_some() => {
+                // Leaving out chunks needs to know how many there are
+                return self.whole(ctx, start, depth, indent_level);
+            }
+            Event::BeginByteString | Event::BeginTextString => {
+                let (major, kind) = if event == Event::BeginByteString {
+                    (2, "bytes")
+                } else {
+                    (3, "text")
+                };
+                self.emit(&length_to_hex(ctx, None, None, major, kind), indent_level)?;
+                while !self.at_break(indent_level + 1)? {
+                    let line = match self.reader.expect_next()? {
+                        (_, Header::String { len, bitwidth, .. }) => {
+                            length_to_hex(ctx, Some(len as usize), Some(bitwidth), major, kind)
+                        }
+                        (_, Header::Event(_)) => unreachable!("the reader checks chunk types"),
+                    };
+                    self.string(ctx, line, major, indent_level + 1)?;
+                }
+                return Ok(());
+            }
+            Event::BeginArray { len, bitwidth } => {
+                let len = len.map(|len| len as usize);
+                self.emit(&length_to_hex(ctx, len, bitwidth, 4, "array"), indent_level)?;
+                match len {
+                    Some(len) => {
+                        for _ in 0..len {
+                            self.item(ctx, indent_level + 1, false)?;
+                        }
+                    }
+                    None => {
+                        while !self.at_break(indent_level + 1)? {
+                            self.item(ctx, indent_level + 1, false)?;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            Event::BeginMap { len, bitwidth } => {
+                let len = len.map(|len| len as usize);
+                self.emit(&length_to_hex(ctx, len, bitwidth, 5, "map"), indent_level)?;
+                match len {
+                    Some(len) => {
+                        for _ in 0..len {
+                            self.item(ctx, indent_level + 1, true)?;
+                            self.item(ctx, indent_level + 1, false)?;
+                        }
+                    }
+                    None => {
+                        while !self.at_break(indent_level + 1)? {
+                            self.item(ctx, indent_level + 1, true)?;
+                            self.item(ctx, indent_level + 1, false)?;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            Event::Tag { tag, .. } if has_semantic_comment(ctx, tag) => {
+                // The comment is worked out from the whole content
+                return self.whole(ctx, start, depth, indent_level);
+            }
+            Event::Tag { tag, bitwidth } => {
+                self.emit(&tag_line(ctx, tag, bitwidth), indent_level)?;
+                return self.item(tag_content(ctx, tag), indent_level + 1, false);
+            }
+            Event::ByteString { .. } | Event::TextString { .. } | Event::EndIndefinite => {
+                unreachable!("strings are read as headers, breaks are checked for first")
+            }
+        };
+
+        let mut line = Line::from_value(ctx, &value);
+        if key {
+            if let Some(name) = key_name(&ctx.options.key_names, &value) {
+                line.comment.push_str(&format!(" ({})", name));
+            }
+        }
+        self.emit(&line, indent_level)
+    }
+
+    /// Whether the next event is the break ending the indefinite length item
+    /// being read, annotating it if so.
+    fn at_break(&mut self, indent_level: usize) -> Result<bool> {
+        if self.reader.peek()? != Some(0xff) {
+            return Ok(false);
+        }
+        self.reader.expect_next()?;
+        self.emit(&Line::new("ff", "break"), indent_level)?;
+        Ok(true)
+    }
+
+    /// Annotate the item started at `start` all at once, reading the whole
+    /// item into memory.
+    fn whole(
+        &mut self,
+        ctx: Context<'_>,
+        start: usize,
+        depth: usize,
+        indent_level: usize,
+    ) -> Result<()> {
+        let end = self.reader.finish(depth)?;
+        let data = self.reader.raw(start..end)?;
+        let options = ParseOptions {
+            allow_invalid_utf8: true,
+            max_chunks: usize::MAX,
+            max_items: usize::MAX,
+            ..ParseOptions::default()
+        };
+        let value = parse_bytes_with(data, &options)?;
+        self.emit(&Line::from_value(ctx, &value), indent_level)
+    }
+
+    /// Annotate a definite length string with its header `line`, reading the
+    /// content a line at a time.
+    fn string(
+        &mut self,
+        ctx: Context<'_>,
+        mut line: Line,
+        major: u8,
+        indent_level: usize,
+    ) -> Result<()> {
+        let len = self.reader.content_remaining();
+        if len == 0 {
+            line.sublines.push(Line::new("", "\"\""));
+            return self.emit(&line, indent_level);
+        }
+        if major == 2 {
+            self.emit(&line, indent_level)?;
+            self.bytes(ctx, len as usize, indent_level + 1)
+        } else if self.reader.content_is_utf8()? {
+            self.emit(&line, indent_level)?;
+            self.text(ctx, len as usize, indent_level + 1)
+        } else {
+            line.comment.push_str(" (invalid UTF-8)");
+            self.emit(&line, indent_level)?;
+            self.bytes(ctx.with_encoding(None), len as usize, indent_level + 1)
+        }
+    }
+
+    fn bytes(&mut self, ctx: Context<'_>, len: usize, indent_level: usize) -> Result<()> {
+        let bytes_per_line = ctx.bytes_per_line();
+        let omitted = omitted_lines(ctx, len);
+        let mut index = 0;
+        while self.reader.content_remaining() > 0 {
+            if let Some((head, tail_start, omitted)) = omitted {
+                if index == head {
+                    self.emit(&omitted_bytes_line(omitted), indent_level)?;
+                    self.reader.skip_content(omitted as u64)?;
+                    index = tail_start;
+                    continue;
+                }
+            }
+            let data = self.reader.content(bytes_per_line)?;
+            let read = data.len();
+            let line = bytes_line(ctx, data);
+            self.reader.skip_content(read as u64)?;
+            self.emit(&line, indent_level)?;
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, ctx: Context<'_>, len: usize, indent_level: usize) -> Result<()> {
+        if is_single_text_line(ctx, len) {
+            return self.text_line(ctx, len, indent_level);
+        }
+        let bytes_per_line = ctx.bytes_per_line();
+        while self.reader.content_remaining() > 0 {
+            // Extend the line to include the whole of any character straddling
+            // the line boundary, as the lines of a string in memory are
+            let data = self.reader.content(bytes_per_line + 3)?;
+            let mut split = cmp::min(bytes_per_line, data.len());
+            while data.get(split).is_some_and(|&byte| byte & 0xc0 == 0x80) {
+                split += 1;
+            }
+            self.text_line(ctx, split, indent_level)?;
+        }
+        Ok(())
+    }
+
+    /// Annotate the next `len` bytes of text, which are known to be valid
+    /// UTF-8.
+    fn text_line(&mut self, ctx: Context<'_>, len: usize, indent_level: usize) -> Result<()> {
+        let data = self.reader.content(len)?;
+        let line = text_line(ctx, str::from_utf8(data).expect("checked to be valid"));
+        self.reader.skip_content(len as u64)?;
+        self.emit(&line, indent_level)
+    }
+}
+
+/// Write the annotated hex of the single data item in `input` to `output`,
+/// reading the input a piece at a time rather than all at once.
+///
+/// The output is the same as [`DataItem::to_hex_with`], but only a small part
+/// of the input is held in memory at a time, so this can be used on inputs
+/// too large to parse into a [`DataItem`]. The exception is tags with
+/// [`HexOptions::semantic_comments`] explaining them, and indefinite length
+/// strings when [`HexOptions::max_chunks`] is set, which are read whole.
+///
+/// The input is read twice, once to find the width of the hex for aligning
+/// the comments and to check that it is well-formed, then again from the same
+/// starting position to write the output, so nothing is written for invalid
+/// input. Text strings that are not valid UTF-8 are shown as bytes, as with
+/// [`ParseOptions::allow_invalid_utf8`].
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed data item, or if reading
+/// it or writing the output fails, which is returned as [`Error::Io`].
+///
+/// [`Error::Io`]: crate::Error::Io
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::Cursor;
+///
+/// let input = b"\x82\x01\x63abc";
+/// let mut output = Vec::new();
+/// cbor_diag::annotate_stream(Cursor::new(input), &mut output, &Default::default()).unwrap();
+///
+/// let item = cbor_diag::parse_bytes(input).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), item.to_hex());
+/// ```
+pub fn annotate_stream(
+    mut input: impl Read + Seek,
+    output: impl Write,
+    options: &HexOptions,
+) -> Result<()> {
+    let start = input.stream_position()?;
+    let mut annotator = Annotator {
+        reader: StreamReader::new(&mut input),
+        options,
+        output: Output::<io::Sink>::Measure { width: 0 },
+    };
+    annotator.document()?;
+    let mut column = match annotator.output {
+        Output::Measure { width } => width,
+        Output::Write { .. } => unreachable!(),
+    };
+    if let Some(max) = options.max_comment_column {
+        column = cmp::min(column, max);
+    }
+
+    input.seek(SeekFrom::Start(start))?;
+    let mut annotator = Annotator {
+        reader: StreamReader::new(&mut input),
+        options,
+        output: Output::Write { column, output },
+    };
+    annotator.document()?;
+    match annotator.output {
+        Output::Write { mut output, .. } => output.flush()?,
+        Output::Measure { .. } => unreachable!(),
+    }
+    Ok(())
+}
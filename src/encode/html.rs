@@ -0,0 +1,230 @@
+use std::cmp;
+
+use super::hex::{lines, Line};
+use crate::{DataItem, HexOptions};
+
+/// Options controlling the HTML output, see [`DataItem::to_html`].
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// Options for the annotated hex view being marked up.
+    ///
+    /// Every byte is shown so that the offsets stay correct, so
+    /// [`HexOptions::max_bytestring_lines`] and [`HexOptions::max_chunks`] are
+    /// ignored.
+    pub hex: HexOptions,
+
+    /// Whether to include a `<style>` element coloring the roles, scoped to
+    /// the `cbor-diag` class of the `<pre>` (default `true`).
+    ///
+    /// Turn this off to style the classes from a stylesheet of your own.
+    pub style: bool,
+
+    /// The prefix for the `id` of the anchor at the start of each line, which
+    /// is followed by the byte offset in hex (default `"cbor-"`).
+    ///
+    /// Use a different prefix for each dump embedded in a single page to keep
+    /// the ids unique.
+    pub id_prefix: String,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            hex: HexOptions::default(),
+            style: true,
+            id_prefix: "cbor-".into(),
+        }
+    }
+}
+
+const STYLE: &str = "\
+<style>
+.cbor-diag summary { display: block; cursor: pointer; list-style: none; }
+.cbor-diag summary::-webkit-details-marker { display: none; }
+.cbor-diag details:not([open]) > summary::after { content: \"  \\2026\"; }
+.cbor-diag .cbor-offset { color: #999; text-decoration: none; }
+.cbor-diag .cbor-comment, .cbor-diag .cbor-break { color: #6a737d; }
+.cbor-diag .cbor-unsigned, .cbor-diag .cbor-negative, .cbor-diag .cbor-float { color: #005cc5; }
+.cbor-diag .cbor-bytes, .cbor-diag .cbor-text { color: #22863a; }
+.cbor-diag .cbor-content { color: #032f62; }
+.cbor-diag .cbor-array, .cbor-diag .cbor-map { color: #6f42c1; }
+.cbor-diag .cbor-tag { color: #d73a49; }
+.cbor-diag .cbor-simple { color: #e36209; }
+.cbor-diag .cbor-note { color: #6a737d; font-style: italic; }
+</style>
+";
+
+/// The width of the offsets at the start of each line.
+const OFFSET_WIDTH: usize = 8;
+
+fn escape(text: &str, output: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c),
+        }
+    }
+}
+
+fn push_spaces(output: &mut String, count: usize) {
+    output.push_str(&" ".repeat(count));
+}
+
+/// The initial byte of a line's hex, if it has any.
+fn initial_byte(hex: &str) -> Option<u8> {
+    hex.get(..2)
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+}
+
+/// The role of a line shown by its CSS class, from the initial byte of the
+/// item it starts or whether it is part of a string's content.
+fn role(line: &Line, content: bool) -> &'static str {
+    let initial = match initial_byte(&line.hex) {
+        Some(_) if content => return "content",
+        Some(initial) => initial,
+        None => return "note",
+    };
+    match (initial >> 5, initial & 0x1f) {
+        (0, _) => "unsigned",
+        (1, _) => "negative",
+        (2, _) => "bytes",
+        (3, _) => "text",
+        (4, _) => "array",
+        (5, _) => "map",
+        (6, _) => "tag",
+        (7, 25..=27) => "float",
+        (7, 31) => "break",
+        (7, _) => "simple",
+        _ => unreachable!(),
+    }
+}
+
+/// Whether the sublines of `line` are the content of a definite length string.
+fn has_content(line: &Line) -> bool {
+    match initial_byte(&line.hex) {
+        Some(initial) => matches!(initial >> 5, 2 | 3) && initial & 0x1f != 31,
+        None => false,
+    }
+}
+
+struct Writer<'a> {
+    options: &'a HtmlOptions,
+    column: usize,
+    offset: usize,
+    output: String,
+}
+
+impl Writer<'_> {
+    /// Write `line` and its sublines, laid out the same as the annotated hex
+    /// with the offset of each line before it.
+    fn line(&mut self, line: &Line, content: bool, indent_level: usize) {
+        if line.sublines.is_empty() {
+            self.text(line, content, indent_level);
+        } else {
+            self.output.push_str("<details open><summary>");
+            self.text(line, content, indent_level);
+            self.output.push_str("</summary>");
+            let content = has_content(line);
+            for subline in &line.sublines {
+                self.line(subline, content, indent_level + 1);
+            }
+            self.output.push_str("</details>");
+        }
+    }
+
+    fn text(&mut self, line: &Line, content: bool, indent_level: usize) {
+        if line.hex.is_empty() {
+            push_spaces(&mut self.output, OFFSET_WIDTH);
+        } else {
+            let id = format!("{}{:08x}", self.options.id_prefix, self.offset);
+            self.output.push_str("<a class=\"cbor-offset\" id=\"");
+            escape(&id, &mut self.output);
+            self.output.push_str("\" href=\"#");
+            escape(&id, &mut self.output);
+            self.output.push_str(&format!("\">{:08x}</a>", self.offset));
+            self.offset += line.hex.bytes().filter(u8::is_ascii_hexdigit).count() / 2;
+        }
+        self.output.push(' ');
+
+        let hex_indent = if line.hex.is_empty() {
+            0
+        } else {
+            indent_level * 3
+        };
+        push_spaces(&mut self.output, hex_indent);
+        if !line.hex.is_empty() {
+            self.output.push_str("<span class=\"cbor-");
+            self.output.push_str(role(line, content));
+            self.output.push_str("\">");
+            escape(&line.hex, &mut self.output);
+            self.output.push_str("</span>");
+        }
+        if !line.comment.is_empty() {
+            let padding = self.column.saturating_sub(hex_indent + line.hex.len());
+            push_spaces(&mut self.output, padding);
+            self.output.push_str(if line.hex.is_empty() {
+                "<span class=\"cbor-comment cbor-note\"> # "
+            } else {
+                "<span class=\"cbor-comment\"> # "
+            });
+            push_spaces(&mut self.output, indent_level * 2);
+            escape(&line.comment, &mut self.output);
+            self.output.push_str("</span>");
+        }
+        self.output.push('\n');
+    }
+}
+
+impl DataItem {
+    /// An HTML fragment showing the annotated hex of [`DataItem::to_hex_with`]
+    /// for embedding in a page.
+    ///
+    /// The output is a `<pre class="cbor-diag">` element, preceded by a
+    /// `<style>` element unless [`HtmlOptions::style`] is off. The hex of each
+    /// line has a class naming its role, such as `cbor-array`, `cbor-text` or
+    /// `cbor-content` for the content of strings, and the comments have the
+    /// class `cbor-comment`. Each line starts with its byte offset as a link
+    /// to itself, and items containing others are wrapped in `<details>` so
+    /// they can be collapsed.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"["<b>"]"#).unwrap();
+    /// let options = cbor_diag::HtmlOptions {
+    ///     style: false,
+    ///     ..Default::default()
+    /// };
+    /// assert!(item
+    ///     .to_html(&options)
+    ///     .contains(r#"<span class="cbor-comment"> #     &quot;&lt;b&gt;&quot;</span>"#));
+    /// ```
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        let hex = HexOptions {
+            max_bytestring_lines: None,
+            max_chunks: None,
+            ..options.hex.clone()
+        };
+        let line = lines(self, &hex);
+
+        let mut column = line.hex_width();
+        if let Some(max) = hex.max_comment_column {
+            column = cmp::min(column, max);
+        }
+        let mut writer = Writer {
+            options,
+            column,
+            offset: 0,
+            output: String::new(),
+        };
+        if options.style {
+            writer.output.push_str(STYLE);
+        }
+        writer.output.push_str("<pre class=\"cbor-diag\">");
+        writer.line(&line, false, 0);
+        writer.output.push_str("</pre>\n");
+        writer.output
+    }
+}
@@ -1,11 +1,53 @@
+#[cfg(feature = "serde")]
+mod annotated_json;
 mod bytes;
+mod bytes_hex;
+mod canonical;
+mod codegen;
 mod diag;
 mod hex;
+mod hex_stream;
+mod html;
+mod numbers;
+mod tree;
 
-#[derive(Copy, Clone)]
+pub(crate) use self::bytes::header_len;
+
+#[cfg(feature = "serde")]
+pub use self::annotated_json::AnnotatedJsonOptions;
+
+pub use self::{
+    bytes::{EncodeOptions, EncodingError},
+    bytes_hex::BytesHexOptions,
+    canonical::CanonicalDataItem,
+    codegen::CodegenOptions,
+    diag::DiagFormatOptions,
+    hex::HexOptions,
+    hex_stream::annotate_stream,
+    html::HtmlOptions,
+    tree::TreeOptions,
+};
+
+/// A form used to show the content of byte strings in diagnostic notation.
+///
+/// See [RFC 8949 § 8][RFC 8] and [RFC 8610 appendix G][RFC G].
+///
+/// [RFC 8]: https://www.rfc-editor.org/rfc/rfc8949.html#section-8
+/// [RFC G]: https://www.rfc-editor.org/rfc/rfc8610.html#appendix-G
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 #[allow(clippy::enum_variant_names)]
-pub(crate) enum Encoding {
+pub enum Encoding {
+    /// Hex encoded, `h'68656c6c6f'`
     Base16,
+    /// Base64 encoded with the standard alphabet, `b64'aGVsbG8'`
     Base64,
+    /// Base64 encoded with the URL and filename safe alphabet, `b64'aGVsbG8'`
     Base64Url,
+    /// Base32 encoded, `b32'NBSWY3DP'`
+    Base32,
+    /// Base32 encoded with the extended hex alphabet, `h32'D1IMOR3F'`
+    Base32Hex,
+    /// As text in a single-quoted string, `'hello'`, falling back to
+    /// [`Encoding::Base16`] for content that is not valid UTF-8
+    Text,
 }
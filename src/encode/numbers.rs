@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+
+use crate::{ByteString, DataItem, IntegerWidth, Tag};
+
+pub(super) fn extract_positive_bignum(value: &DataItem) -> Option<BigUint> {
+    if let DataItem::ByteString(ByteString { data, .. }) = value {
+        Some(BigUint::from_bytes_be(data))
+    } else {
+        None
+    }
+}
+
+pub(super) fn extract_negative_bignum(value: &DataItem) -> Option<BigInt> {
+    if let DataItem::ByteString(ByteString { data, .. }) = value {
+        Some(BigInt::from(-1) - BigInt::from_bytes_be(Sign::Plus, data))
+    } else {
+        None
+    }
+}
+
+/// The name for an integer map key from a user supplied dictionary.
+pub(super) fn key_name<'a>(
+    key_names: &'a BTreeMap<i128, String>,
+    key: &DataItem,
+) -> Option<&'a str> {
+    key_names.get(&key.as_i128()?).map(String::as_str)
+}
+
+/// Extract the value of an integer or bignum.
+pub(super) fn extract_integer(value: &DataItem) -> Option<BigInt> {
+    match *value {
+        DataItem::Integer { value, .. } => Some(BigInt::from(value)),
+        DataItem::Negative { value, .. } => Some(BigInt::from(-1) - BigInt::from(value)),
+        DataItem::Tag {
+            tag: Tag::POSITIVE_BIGNUM,
+            ref value,
+            ..
+        } => extract_positive_bignum(value).map(|value| BigInt::from_biguint(Sign::Plus, value)),
+        DataItem::Tag {
+            tag: Tag::NEGATIVE_BIGNUM,
+            ref value,
+            ..
+        } => extract_negative_bignum(value),
+        _ => None,
+    }
+}
+
+/// The item with the preferred serialization of `value`, a plain integer if
+/// it fits otherwise a bignum with no leading zero bytes.
+pub(super) fn integer_item(value: &BigInt) -> DataItem {
+    let (tag, magnitude) = if value.sign() == Sign::Minus {
+        let magnitude = BigInt::from(-1) - value;
+        if let Some(value) = magnitude.to_u64() {
+            return DataItem::Negative {
+                value,
+                bitwidth: IntegerWidth::Unknown,
+            };
+        }
+        (Tag::NEGATIVE_BIGNUM, magnitude)
+    } else {
+        if let Some(value) = value.to_u64() {
+            return DataItem::Integer {
+                value,
+                bitwidth: IntegerWidth::Unknown,
+            };
+        }
+        (Tag::POSITIVE_BIGNUM, value.clone())
+    };
+    DataItem::Tag {
+        tag,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(DataItem::ByteString(ByteString {
+            data: magnitude.to_bytes_be().1,
+            bitwidth: IntegerWidth::Unknown,
+            encoding: None,
+        })),
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl DataItem {
+    /// Create an integer with the value of `value`, using a plain integer
+    /// when it fits in 64 bits and a bignum (tag 2 or 3) with no leading zero
+    /// bytes otherwise, the preferred serialization from [RFC 8949 §
+    /// 3.4.3][RFC 3.4.3].
+    ///
+    /// ```rust
+    /// use cbor_diag::DataItem;
+    /// use num_bigint::BigInt;
+    ///
+    /// let value = BigInt::from(u64::MAX) + 1;
+    /// let item = DataItem::from_bigint(&value);
+    /// assert_eq!(item.to_diag(), "2(h'010000000000000000')");
+    /// assert_eq!(item.to_bigint(), Some(value));
+    /// ```
+    ///
+    /// [RFC 3.4.3]: https://www.rfc-editor.org/rfc/rfc8949.html#section-3.4.3
+    pub fn from_bigint(value: &BigInt) -> DataItem {
+        integer_item(value)
+    }
+
+    /// The value of an integer, negative integer or bignum (tag 2 or 3 of a
+    /// byte string), or [`None`] if this is anything else.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        extract_integer(self)
+    }
+}
+
+/// Extract the numerator and denominator of the value of a rational number
+/// (tag 30), as written, without reducing them.
+pub(super) fn extract_rational(value: &DataItem) -> Result<(BigInt, BigInt), &'static str> {
+    let data = match value {
+        DataItem::Array { data, .. } if data.len() == 2 => data,
+        _ => return Err("invalid type"),
+    };
+    let numerator = extract_integer(&data[0]).ok_or("invalid type")?;
+    let denominator = extract_integer(&data[1]).ok_or("invalid type")?;
+    if denominator <= BigInt::zero() {
+        return Err("non-positive denominator");
+    }
+    Ok((numerator, denominator))
+}
+
+/// A short decimal approximation of a fraction, if it is in range of `f64`.
+pub(super) fn approximate(numerator: &BigInt, denominator: &BigInt) -> Option<String> {
+    let value = BigRational::new(numerator.clone(), denominator.clone()).to_f64()?;
+    if !value.is_finite() {
+        return None;
+    }
+    let formatted = format!("{:.4}", value);
+    Some(
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned(),
+    )
+}
@@ -0,0 +1,252 @@
+use super::{bytes::header_len, hex::tag_name};
+use crate::{ByteString, DataItem, IntegerWidth, TextString};
+
+/// Options controlling the tree output, see [`DataItem::to_tree_with`] and
+/// [`DataItem::to_dot_with`].
+#[derive(Debug, Clone)]
+pub struct TreeOptions {
+    /// How many characters of a scalar value to show before truncating it
+    /// with `…` (default 40, `0` to never truncate).
+    pub max_value_width: usize,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            max_value_width: 40,
+        }
+    }
+}
+
+struct Node {
+    /// Whether this is the key or value of a map entry
+    role: Option<&'static str>,
+    label: String,
+    /// The encoded size, if the item has a binary encoding
+    size: Option<usize>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn description(&self) -> String {
+        let role = self.role.map(|role| format!("{}: ", role));
+        let size = match self.size {
+            Some(1) => ", 1 byte".into(),
+            Some(size) => format!(", {} bytes", size),
+            None => ", no binary encoding".into(),
+        };
+        format!("{}{}{}", role.unwrap_or_default(), self.label, size)
+    }
+}
+
+fn truncate(value: String, options: &TreeOptions) -> String {
+    match value.char_indices().nth(options.max_value_width) {
+        Some((index, _)) if options.max_value_width > 0 => format!("{}…", &value[..index]),
+        _ => value,
+    }
+}
+
+fn scalar(label: &str, item: &DataItem, options: &TreeOptions) -> Node {
+    Node {
+        role: None,
+        label: format!("{} {}", label, truncate(item.to_diag(), options)),
+        size: item.encoded_len().ok(),
+        children: Vec::new(),
+    }
+}
+
+/// A node for a container with header `label` and `children`.
+fn container(
+    label: String,
+    length: u64,
+    bitwidth: Option<IntegerWidth>,
+    children: Vec<Node>,
+) -> Node {
+    let header = match bitwidth {
+        Some(bitwidth) => header_len(length, bitwidth),
+        // The initial byte and the break
+        None => 2,
+    };
+    let size = children
+        .iter()
+        .try_fold(header, |size, child| Some(size + child.size?));
+    Node {
+        role: None,
+        label,
+        size,
+        children,
+    }
+}
+
+fn length_label(name: &str, length: usize, bitwidth: Option<IntegerWidth>) -> String {
+    match bitwidth {
+        Some(_) => format!("{}({})", name, length),
+        None => format!("{}(*)", name),
+    }
+}
+
+fn to_node(item: &DataItem, options: &TreeOptions) -> Node {
+    match item {
+        DataItem::Integer { .. } => scalar("unsigned", item, options),
+        DataItem::Negative { .. } => scalar("negative", item, options),
+        DataItem::ByteString(ByteString { data, .. })
+        | DataItem::InvalidTextString(ByteString { data, .. }) => {
+            let name = match item {
+                DataItem::ByteString(_) => "bytes",
+                _ => "text",
+            };
+            scalar(&format!("{}({})", name, data.len()), item, options)
+        }
+        DataItem::TextString(TextString { data, .. }) => {
+            scalar(&format!("text({})", data.len()), item, options)
+        }
+        DataItem::IndefiniteByteString(strings) => container(
+            "bytes(*)".into(),
+            0,
+            None,
+            strings
+                .iter()
+                .map(|string| to_node(&DataItem::ByteString(string.clone()), options))
+                .collect(),
+        ),
+        DataItem::IndefiniteTextString(strings) => container(
+            "text(*)".into(),
+            0,
+            None,
+            strings
+                .iter()
+                .map(|string| to_node(&DataItem::TextString(string.clone()), options))
+                .collect(),
+        ),
+        DataItem::InvalidIndefiniteTextString(strings) => container(
+            "text(*)".into(),
+            0,
+            None,
+            strings
+                .iter()
+                .map(|string| to_node(&DataItem::InvalidTextString(string.clone()), options))
+                .collect(),
+        ),
+        DataItem::Array { data, bitwidth } => container(
+            length_label("array", data.len(), *bitwidth),
+            data.len() as u64,
+            *bitwidth,
+            data.iter().map(|item| to_node(item, options)).collect(),
+        ),
+        DataItem::Map { data, bitwidth } => container(
+            length_label("map", data.len(), *bitwidth),
+            data.len() as u64,
+            *bitwidth,
+            data.iter()
+                .flat_map(|(key, value)| {
+                    let key = Node {
+                        role: Some("key"),
+                        ..to_node(key, options)
+                    };
+                    let value = Node {
+                        role: Some("value"),
+                        ..to_node(value, options)
+                    };
+                    vec![key, value]
+                })
+                .collect(),
+        ),
+        DataItem::Tag {
+            tag,
+            bitwidth,
+            value,
+        } => {
+            let label = match tag_name(*tag) {
+                Some(name) => format!("tag({}) {}", tag.0, name),
+                None => format!("tag({})", tag.0),
+            };
+            container(label, tag.0, Some(*bitwidth), vec![to_node(value, options)])
+        }
+        DataItem::Float { .. } => scalar("float", item, options),
+        DataItem::Simple(_) => scalar("simple", item, options),
+        DataItem::AppLiteral { .. } => scalar("application literal", item, options),
+    }
+}
+
+fn write_tree(node: &Node, prefix: &str, output: &mut String) {
+    for (index, child) in node.children.iter().enumerate() {
+        let last = index + 1 == node.children.len();
+        let (connector, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        output.push('\n');
+        output.push_str(prefix);
+        output.push_str(connector);
+        output.push_str(&child.description());
+        write_tree(child, &format!("{}{}", prefix, indent), output);
+    }
+}
+
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn write_dot(node: &Node, id: &mut usize, output: &mut String) -> usize {
+    let this = *id;
+    *id += 1;
+    let label = match node.size {
+        Some(1) => format!("{}\\n1 byte", escape_dot(&node.label)),
+        Some(size) => format!("{}\\n{} bytes", escape_dot(&node.label), size),
+        None => escape_dot(&node.label),
+    };
+    output.push_str(&format!("    n{} [label=\"{}\"];\n", this, label));
+    for child in &node.children {
+        let child_id = write_dot(child, id, output);
+        match child.role {
+            Some(role) => output.push_str(&format!(
+                "    n{} -> n{} [label=\"{}\"];\n",
+                this, child_id, role
+            )),
+            None => output.push_str(&format!("    n{} -> n{};\n", this, child_id)),
+        }
+    }
+    this
+}
+
+impl DataItem {
+    /// An ASCII tree of the item, showing the type, value or length, and
+    /// encoded size of each item within it.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"[1, {"a": h'00'}]"#).unwrap();
+    /// assert_eq!(item.to_tree(), indoc::indoc!(r#"
+    ///     array(2), 7 bytes
+    ///     ├── unsigned 1, 1 byte
+    ///     └── map(1), 5 bytes
+    ///         ├── key: text(1) "a", 2 bytes
+    ///         └── value: bytes(1) h'00', 2 bytes"#
+    /// ));
+    /// ```
+    pub fn to_tree(&self) -> String {
+        self.to_tree_with(&TreeOptions::default())
+    }
+
+    /// Like [`DataItem::to_tree`], but with configurable output.
+    pub fn to_tree_with(&self, options: &TreeOptions) -> String {
+        let node = to_node(self, options);
+        let mut output = node.description();
+        write_tree(&node, "", &mut output);
+        output
+    }
+
+    /// A [Graphviz](https://graphviz.org) graph of the item, with the same
+    /// information as [`DataItem::to_tree`].
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&TreeOptions::default())
+    }
+
+    /// Like [`DataItem::to_dot`], but with configurable output.
+    pub fn to_dot_with(&self, options: &TreeOptions) -> String {
+        let mut output = String::from("digraph cbor {\n    node [shape=box];\n");
+        write_dot(&to_node(self, options), &mut 0, &mut output);
+        output.push('}');
+        output
+    }
+}
@@ -1,8 +1,54 @@
-use std::{borrow::Cow, fmt};
+use std::{borrow::Cow, fmt, io};
+
+use crate::DataItem;
 
 #[derive(Debug)]
 pub enum Error {
     Todo(Cow<'static, str>),
+
+    /// A complete data item was parsed, but it was followed by more data.
+    TrailingData {
+        /// The data item that was parsed.
+        parsed: DataItem,
+        /// The offset of the first byte after the data item, i.e. its
+        /// encoded length.
+        offset: usize,
+        /// How many bytes follow the data item.
+        remaining: usize,
+    },
+
+    /// A string, array or map in binary input declared a length larger than
+    /// could be present, so was rejected before allocating space for it.
+    ///
+    /// See [`ParseOptions::max_allocation`](crate::ParseOptions::max_allocation).
+    LengthOutOfRange {
+        /// The declared length, in bytes for strings or items for arrays and
+        /// maps.
+        declared: u64,
+        /// The most that could be present, the smaller of the remaining
+        /// input length and the configured maximum allocation.
+        available: usize,
+    },
+
+    /// An indefinite length string in binary input had more chunks, or an
+    /// array or map more items, than allowed.
+    ///
+    /// See [`ParseOptions::max_chunks`](crate::ParseOptions::max_chunks) and
+    /// [`ParseOptions::max_items`](crate::ParseOptions::max_items).
+    LimitExceeded {
+        /// The offset of the first chunk or item over the limit.
+        offset: usize,
+        /// The limit that was exceeded.
+        limit: usize,
+        /// What was counted, `"chunks"`, `"items"` or `"entries"`.
+        counted: &'static str,
+    },
+
+    /// Reading the input or writing the output failed, when working with
+    /// streams rather than data in memory.
+    ///
+    /// See [`annotate_stream`](crate::annotate_stream).
+    Io(io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -25,12 +71,43 @@ impl From<hex::FromHexError> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
 impl std::error::Error for Error {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Todo(s) => write!(f, "TODO cbor-diag::Error: {}", s),
+            Error::TrailingData {
+                offset, remaining, ..
+            } => write!(
+                f,
+                "parsed 1 item ({} bytes), {} trailing bytes",
+                offset, remaining
+            ),
+            Error::LengthOutOfRange {
+                declared,
+                available,
+            } => write!(
+                f,
+                "declared length {} is out of range, only {} bytes available",
+                declared, available
+            ),
+            Error::LimitExceeded {
+                offset,
+                limit,
+                counted,
+            } => write!(
+                f,
+                "more than {} {} at offset {}, exceeding the limit",
+                limit, counted, offset
+            ),
+            Error::Io(err) => write!(f, "{}", err),
         }
     }
 }
@@ -0,0 +1,436 @@
+//! Generation of random data items, for use as fuzzing seeds or example data.
+//!
+//! The items generated depend only on the values returned by the random
+//! number generator passed in, so using a seeded generator with a stable
+//! algorithm (such as [`rand_chacha`](https://docs.rs/rand_chacha)) always
+//! generates the same items for the same seed.
+
+use half::f16;
+use rand_core::RngCore;
+
+use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString};
+
+/// Options controlling [`generate`] and [`generate_arbitrary`].
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// How deeply nested containers and tags can be (default 4).
+    pub max_depth: usize,
+
+    /// The maximum number of items in an array, entries in a map or chunks in
+    /// an indefinite length string (default 8).
+    pub max_container_len: usize,
+
+    /// The maximum length of strings in bytes (default 16).
+    pub max_string_len: usize,
+
+    /// Which major types (0 to 7) to generate items of (default all).
+    ///
+    /// Only used by [`generate_arbitrary`], at the maximum depth only the
+    /// scalar types are used, falling back to unsigned integers if none of
+    /// them are included.
+    pub major_types: Vec<u8>,
+
+    /// Which tags to generate, any tag number if empty (the default).
+    ///
+    /// Only used by [`generate_arbitrary`].
+    pub tags: Vec<Tag>,
+
+    /// Whether to sometimes encode integers, lengths and tags with a wider
+    /// width than needed (default `false`).
+    pub non_preferred_widths: bool,
+
+    /// Whether to sometimes use indefinite length arrays, maps and strings
+    /// (default `false`).
+    pub indefinite: bool,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_container_len: 8,
+            max_string_len: 16,
+            major_types: (0..=7).collect(),
+            tags: Vec::new(),
+            non_preferred_widths: false,
+            indefinite: false,
+        }
+    }
+}
+
+const WIDTHS: [IntegerWidth; 5] = [
+    IntegerWidth::Zero,
+    IntegerWidth::Eight,
+    IntegerWidth::Sixteen,
+    IntegerWidth::ThirtyTwo,
+    IntegerWidth::SixtyFour,
+];
+
+fn integer_width_max(width: IntegerWidth) -> u64 {
+    match width {
+        IntegerWidth::Zero => 23,
+        IntegerWidth::Eight => u8::max_value().into(),
+        IntegerWidth::Sixteen => u16::max_value().into(),
+        IntegerWidth::ThirtyTwo => u32::max_value().into(),
+        IntegerWidth::Unknown | IntegerWidth::SixtyFour => u64::max_value(),
+    }
+}
+
+struct Generator<'a, R: ?Sized> {
+    rng: &'a mut R,
+    options: &'a GenerateOptions,
+}
+
+impl<R: RngCore + ?Sized> Generator<'_, R> {
+    /// A random value in `0..=max`.
+    fn up_to(&mut self, max: u64) -> u64 {
+        match max.checked_add(1) {
+            Some(n) => self.rng.next_u64() % n,
+            None => self.rng.next_u64(),
+        }
+    }
+
+    fn one_in(&mut self, n: u64) -> bool {
+        self.up_to(n - 1) == 0
+    }
+
+    fn choose<T: Copy>(&mut self, choices: &[T]) -> T {
+        choices[self.up_to(choices.len() as u64 - 1) as usize]
+    }
+
+    /// The width to encode `value` with.
+    fn width(&mut self, value: u64) -> IntegerWidth {
+        if self.options.non_preferred_widths && self.one_in(2) {
+            let wider: Vec<IntegerWidth> = WIDTHS
+                .iter()
+                .copied()
+                .filter(|&width| integer_width_max(width) >= value)
+                .collect();
+            self.choose(&wider)
+        } else {
+            IntegerWidth::Unknown
+        }
+    }
+
+    /// A random integer, biased towards the smaller widths.
+    fn integer(&mut self) -> (u64, IntegerWidth) {
+        let width = self.choose(&WIDTHS);
+        let value = self.up_to(integer_width_max(width));
+        (value, self.width(value))
+    }
+
+    /// A random integer with the same width as `bitwidth`, if it is known.
+    fn integer_like(&mut self, value: u64, bitwidth: IntegerWidth) -> u64 {
+        match bitwidth {
+            IntegerWidth::Unknown => {
+                self.up_to(integer_width_max(IntegerWidth::minimal_for(value)))
+            }
+            bitwidth => self.up_to(integer_width_max(bitwidth)),
+        }
+    }
+
+    fn len(&mut self, max: usize) -> usize {
+        self.up_to(max as u64) as usize
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = self.len(self.options.max_string_len);
+        (0..len).map(|_| self.rng.next_u32() as u8).collect()
+    }
+
+    /// Random text, mostly ASCII with some wider characters.
+    fn text(&mut self) -> String {
+        let mut text = String::new();
+        loop {
+            let c = if self.one_in(4) {
+                std::char::from_u32(self.up_to(0x10_ffff) as u32)
+            } else {
+                Some((b' ' + self.up_to(94) as u8) as char)
+            };
+            match c {
+                Some(c) if text.len() + c.len_utf8() <= self.options.max_string_len => {
+                    text.push(c);
+                }
+                Some(_) => break,
+                None => continue,
+            }
+            if self.one_in(self.options.max_string_len as u64 + 1) {
+                break;
+            }
+        }
+        text
+    }
+
+    fn bytestring(&mut self) -> ByteString {
+        let data = self.bytes();
+        ByteString {
+            bitwidth: self.width(data.len() as u64),
+            data,
+            encoding: None,
+        }
+    }
+
+    fn textstring(&mut self) -> TextString {
+        let data = self.text();
+        TextString {
+            bitwidth: self.width(data.len() as u64),
+            data,
+        }
+    }
+
+    /// The width of a container with `len` items, [`None`] for indefinite.
+    fn container_width(&mut self, len: usize) -> Option<IntegerWidth> {
+        if self.options.indefinite && self.one_in(3) {
+            None
+        } else {
+            Some(self.width(len as u64))
+        }
+    }
+
+    fn float(&mut self, bitwidth: FloatWidth) -> f64 {
+        let value = match bitwidth {
+            FloatWidth::Sixteen => f16::from_bits(self.rng.next_u32() as u16).to_f64(),
+            FloatWidth::ThirtyTwo => f32::from_bits(self.rng.next_u32()).into(),
+            FloatWidth::Unknown | FloatWidth::SixtyFour => f64::from_bits(self.rng.next_u64()),
+        };
+        // NaN doesn't compare equal to itself, which makes items awkward to
+        // use in tests
+        if value.is_nan() {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    fn tag(&mut self) -> Tag {
+        if self.options.tags.is_empty() {
+            Tag(self.integer().0)
+        } else {
+            let tags = self.options.tags.clone();
+            self.choose(&tags)
+        }
+    }
+
+    fn arbitrary(&mut self, depth: usize) -> DataItem {
+        let scalar = |major: &&u8| ![4, 5, 6].contains(*major);
+        let majors: Vec<u8> = self
+            .options
+            .major_types
+            .iter()
+            .filter(|major| depth < self.options.max_depth || scalar(major))
+            .copied()
+            .collect();
+        let major = if majors.is_empty() {
+            0
+        } else {
+            self.choose(&majors)
+        };
+
+        match major {
+            0 => {
+                let (value, bitwidth) = self.integer();
+                DataItem::Integer { value, bitwidth }
+            }
+            1 => {
+                let (value, bitwidth) = self.integer();
+                DataItem::Negative { value, bitwidth }
+            }
+            2 if self.options.indefinite && self.one_in(4) => {
+                let len = self.len(self.options.max_container_len);
+                DataItem::IndefiniteByteString((0..len).map(|_| self.bytestring()).collect())
+            }
+            2 => DataItem::ByteString(self.bytestring()),
+            3 if self.options.indefinite && self.one_in(4) => {
+                let len = self.len(self.options.max_container_len);
+                DataItem::IndefiniteTextString((0..len).map(|_| self.textstring()).collect())
+            }
+            3 => DataItem::TextString(self.textstring()),
+            4 => {
+                let len = self.len(self.options.max_container_len);
+                DataItem::Array {
+                    data: (0..len).map(|_| self.arbitrary(depth + 1)).collect(),
+                    bitwidth: self.container_width(len),
+                }
+            }
+            5 => {
+                let len = self.len(self.options.max_container_len);
+                DataItem::Map {
+                    data: (0..len)
+                        .map(|_| (self.arbitrary(depth + 1), self.arbitrary(depth + 1)))
+                        .collect(),
+                    bitwidth: self.container_width(len),
+                }
+            }
+            6 => {
+                let tag = self.tag();
+                DataItem::Tag {
+                    tag,
+                    bitwidth: self.width(tag.0),
+                    value: Box::new(self.arbitrary(depth + 1)),
+                }
+            }
+            _ if self.one_in(2) => {
+                let bitwidth = self.choose(&[
+                    FloatWidth::Sixteen,
+                    FloatWidth::ThirtyTwo,
+                    FloatWidth::SixtyFour,
+                ]);
+                DataItem::Float {
+                    value: self.float(bitwidth),
+                    bitwidth,
+                }
+            }
+            _ => {
+                // Skip the reserved simple values
+                let value = self.up_to(247) as u8;
+                DataItem::Simple(Simple(if value < 24 { value } else { value + 8 }))
+            }
+        }
+    }
+
+    fn like(&mut self, template: &DataItem) -> DataItem {
+        match *template {
+            DataItem::Integer { value, bitwidth } => DataItem::Integer {
+                value: self.integer_like(value, bitwidth),
+                bitwidth,
+            },
+            DataItem::Negative { value, bitwidth } => DataItem::Negative {
+                value: self.integer_like(value, bitwidth),
+                bitwidth,
+            },
+            DataItem::ByteString(ref bytestring) => {
+                DataItem::ByteString(self.bytes_like(bytestring))
+            }
+            DataItem::IndefiniteByteString(ref bytestrings) => DataItem::IndefiniteByteString(
+                bytestrings
+                    .iter()
+                    .map(|bytestring| self.bytes_like(bytestring))
+                    .collect(),
+            ),
+            DataItem::TextString(ref textstring) => {
+                DataItem::TextString(self.text_like(textstring.bitwidth))
+            }
+            DataItem::InvalidTextString(ref bytestring) => {
+                DataItem::TextString(self.text_like(bytestring.bitwidth))
+            }
+            DataItem::IndefiniteTextString(ref textstrings) => DataItem::IndefiniteTextString(
+                textstrings
+                    .iter()
+                    .map(|textstring| self.text_like(textstring.bitwidth))
+                    .collect(),
+            ),
+            DataItem::InvalidIndefiniteTextString(ref bytestrings) => {
+                DataItem::IndefiniteTextString(
+                    bytestrings
+                        .iter()
+                        .map(|bytestring| self.text_like(bytestring.bitwidth))
+                        .collect(),
+                )
+            }
+            DataItem::Array { ref data, bitwidth } => DataItem::Array {
+                data: data.iter().map(|item| self.like(item)).collect(),
+                bitwidth,
+            },
+            DataItem::Map { ref data, bitwidth } => DataItem::Map {
+                data: data
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.like(value)))
+                    .collect(),
+                bitwidth,
+            },
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                ref value,
+            } => DataItem::Tag {
+                tag,
+                bitwidth,
+                value: Box::new(self.like(value)),
+            },
+            DataItem::Float { bitwidth, .. } => DataItem::Float {
+                value: self.float(bitwidth),
+                bitwidth,
+            },
+            DataItem::Simple(Simple::FALSE) | DataItem::Simple(Simple::TRUE) => {
+                DataItem::Simple(if self.one_in(2) {
+                    Simple::TRUE
+                } else {
+                    Simple::FALSE
+                })
+            }
+            DataItem::Simple(_) | DataItem::AppLiteral { .. } => template.clone(),
+        }
+    }
+
+    /// Random bytes, keeping the length width if the new length fits.
+    fn bytes_like(&mut self, bytestring: &ByteString) -> ByteString {
+        let data = self.bytes();
+        ByteString {
+            bitwidth: self.fitting(data.len(), bytestring.bitwidth),
+            data,
+            encoding: bytestring.encoding,
+        }
+    }
+
+    fn text_like(&mut self, bitwidth: IntegerWidth) -> TextString {
+        let data = self.text();
+        TextString {
+            bitwidth: self.fitting(data.len(), bitwidth),
+            data,
+        }
+    }
+
+    fn fitting(&self, len: usize, bitwidth: IntegerWidth) -> IntegerWidth {
+        if len as u64 <= integer_width_max(bitwidth) {
+            bitwidth
+        } else {
+            IntegerWidth::Unknown
+        }
+    }
+}
+
+/// Generate an item like `template`, with the same structure, tags and map
+/// keys, but random values.
+///
+/// Numbers and strings are replaced with random values of the same type
+/// (keeping declared widths, strings are limited to
+/// [`GenerateOptions::max_string_len`]) and booleans randomly flipped, other
+/// simple values are kept.
+///
+/// ```rust
+/// use cbor_diag::{generate, parse_diag, GenerateOptions};
+/// use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+///
+/// let template = parse_diag(r#"{"id": 1, "name": "a", "ok": true}"#).unwrap();
+/// let mut rng = ChaCha8Rng::seed_from_u64(1);
+/// let item = generate(&template, &mut rng, &GenerateOptions::default());
+/// assert!(item.to_diag().starts_with(r#"{"id":"#));
+/// ```
+pub fn generate(
+    template: &DataItem,
+    rng: &mut (impl RngCore + ?Sized),
+    options: &GenerateOptions,
+) -> DataItem {
+    Generator { rng, options }.like(template)
+}
+
+/// Generate a completely random item, as configured by `options`.
+///
+/// ```rust
+/// use cbor_diag::{generate_arbitrary, GenerateOptions};
+/// use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+///
+/// let options = GenerateOptions {
+///     major_types: vec![0, 4],
+///     ..GenerateOptions::default()
+/// };
+/// let item = generate_arbitrary(&mut ChaCha8Rng::seed_from_u64(7), &options);
+/// let again = generate_arbitrary(&mut ChaCha8Rng::seed_from_u64(7), &options);
+/// assert_eq!(item, again);
+/// ```
+pub fn generate_arbitrary(
+    rng: &mut (impl RngCore + ?Sized),
+    options: &GenerateOptions,
+) -> DataItem {
+    Generator { rng, options }.arbitrary(0)
+}
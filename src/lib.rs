@@ -10,13 +10,55 @@
 
 #![warn(rust_2018_idioms)]
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod base32;
+pub mod cddl;
+mod comments;
+#[cfg(feature = "datetime")]
+mod datetime;
 mod encode;
 mod error;
+#[cfg(feature = "generate")]
+mod generate;
+mod map;
+mod packed;
 mod parse;
+mod redact;
+pub mod roundtrip;
+mod seq;
+mod size;
+mod spans;
 mod syntax;
 
 pub use self::{
+    comments::{CommentPosition, Comments},
+    encode::{
+        annotate_stream, BytesHexOptions, CanonicalDataItem, CodegenOptions, DiagFormatOptions,
+        EncodeOptions, Encoding, EncodingError, HexOptions, HtmlOptions, TreeOptions,
+    },
     error::{Error, Result},
-    parse::{parse_bytes, parse_bytes_partial, parse_diag, parse_hex},
+    map::{CborMap, MapEntry},
+    packed::PackedOptions,
+    parse::{
+        check_well_formed, check_well_formed_seq, parse_bytes, parse_bytes_lenient,
+        parse_bytes_partial, parse_bytes_partial_with, parse_bytes_preserving,
+        parse_bytes_preserving_with, parse_bytes_report, parse_bytes_with, parse_bytes_with_spans,
+        parse_diag, parse_diag_partial, parse_diag_partial_with, parse_diag_report,
+        parse_diag_with, parse_diag_with_comments, parse_diag_with_warnings, parse_hex,
+        parse_hex_lenient, parse_hex_report, parse_hex_with, Event, ParseOptions, ParseReport,
+        ParseWarning, Reader, WarningKind, WellFormedness,
+    },
+    redact::Redaction,
+    seq::{array_to_items, items_to_array},
+    size::{SizeReport, SizeTotals, SubtreeSize},
+    spans::{OriginalEncoding, Span, SpanTree},
     syntax::{ByteString, DataItem, FloatWidth, IntegerWidth, Simple, Tag, TextString},
 };
+
+#[cfg(feature = "datetime")]
+pub use self::datetime::DateTimeStyle;
+#[cfg(feature = "serde")]
+pub use self::encode::AnnotatedJsonOptions;
+#[cfg(feature = "generate")]
+pub use self::generate::{generate, generate_arbitrary, GenerateOptions};
@@ -0,0 +1,139 @@
+use crate::DataItem;
+
+/// A view of the entries of a [`DataItem::Map`] for editing them by key, see
+/// [`DataItem::as_map_mut`].
+///
+/// Keys are compared semantically, ignoring differences in how they are
+/// encoded, as for [`CanonicalDataItem`](crate::CanonicalDataItem). Entries
+/// keep their order, with new ones added at the end. If the map already
+/// contains duplicate keys only the first of them is used.
+///
+/// ```rust
+/// use cbor_diag::parse_diag;
+///
+/// // A COSE protected header, with the algorithm and key id
+/// let mut header = parse_diag("{1: -7, 4: h'6b6964'}").unwrap();
+/// let mut map = header.as_map_mut().unwrap();
+/// map.insert(parse_diag("1").unwrap(), parse_diag("-35").unwrap());
+/// map.insert(parse_diag("3").unwrap(), parse_diag("60").unwrap());
+/// map.remove(&parse_diag("4").unwrap());
+/// assert_eq!(header.to_diag(), "{1:-35,3:60}");
+/// ```
+#[derive(Debug)]
+pub struct CborMap<'a> {
+    entries: &'a mut Vec<(DataItem, DataItem)>,
+}
+
+/// A single entry in a [`CborMap`], which may not exist yet, see
+/// [`CborMap::entry`].
+#[derive(Debug)]
+pub struct MapEntry<'a> {
+    entries: &'a mut Vec<(DataItem, DataItem)>,
+    key: DataItem,
+    index: Option<usize>,
+}
+
+impl<'a> CborMap<'a> {
+    fn position(&self, key: &DataItem) -> Option<usize> {
+        let key = key.canonical_key();
+        self.entries
+            .iter()
+            .position(|(existing, _)| existing.canonical_key() == key)
+    }
+
+    /// The entries of the map, in order.
+    pub fn entries(&self) -> &[(DataItem, DataItem)] {
+        self.entries
+    }
+
+    pub fn contains_key(&self, key: &DataItem) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &DataItem) -> Option<&DataItem> {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &DataItem) -> Option<&mut DataItem> {
+        self.position(key)
+            .map(move |index| &mut self.entries[index].1)
+    }
+
+    /// Set the value for `key`, returning the previous value if there was
+    /// one.
+    ///
+    /// An existing entry keeps its position and the encoding of its key, only
+    /// the value is replaced.
+    pub fn insert(&mut self, key: DataItem, value: DataItem) -> Option<DataItem> {
+        match self.position(&key) {
+            Some(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove the entry for `key`, returning its key and value.
+    pub fn remove(&mut self, key: &DataItem) -> Option<(DataItem, DataItem)> {
+        self.position(key).map(|index| self.entries.remove(index))
+    }
+
+    /// The entry for `key`, for modifying or inserting its value in place.
+    ///
+    /// ```rust
+    /// use cbor_diag::parse_diag;
+    ///
+    /// let mut item = parse_diag(r#"{"count": 1}"#).unwrap();
+    /// let mut map = item.as_map_mut().unwrap();
+    /// for key in ["count", "other"] {
+    ///     let key = parse_diag(format!("{:?}", key)).unwrap();
+    ///     map.entry(key).or_insert(parse_diag("0").unwrap());
+    /// }
+    /// assert_eq!(item.to_diag(), r#"{"count":1,"other":0}"#);
+    /// ```
+    pub fn entry(&mut self, key: DataItem) -> MapEntry<'_> {
+        let index = self.position(&key);
+        MapEntry {
+            entries: self.entries,
+            key,
+            index,
+        }
+    }
+}
+
+impl<'a> MapEntry<'a> {
+    /// Whether the entry is already in the map.
+    pub fn exists(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// The value of the entry, inserting `value` at the end of the map if it
+    /// doesn't exist.
+    pub fn or_insert(self, value: DataItem) -> &'a mut DataItem {
+        self.or_insert_with(|| value)
+    }
+
+    /// Like [`MapEntry::or_insert`], only creating the value if needed.
+    pub fn or_insert_with(self, value: impl FnOnce() -> DataItem) -> &'a mut DataItem {
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                self.entries.push((self.key, value()));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].1
+    }
+}
+
+impl DataItem {
+    /// A view of this item's entries for editing them by key, if it is a
+    /// map, see [`CborMap`].
+    pub fn as_map_mut(&mut self) -> Option<CborMap<'_>> {
+        match self {
+            DataItem::Map { data, .. } => Some(CborMap { entries: data }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::convert::TryFrom;
+
+use crate::{DataItem, Result, Simple, Tag};
+
+/// Options controlling [`DataItem::expand_packed_with`].
+#[derive(Debug, Clone)]
+pub struct PackedOptions {
+    /// How deeply table setups and references to shared items may be nested
+    /// within each other (default 32).
+    ///
+    /// Going deeper than this is an error, which also stops a shared item
+    /// that refers back to itself from expanding forever.
+    pub max_depth: usize,
+}
+
+impl Default for PackedOptions {
+    fn default() -> Self {
+        Self { max_depth: 32 }
+    }
+}
+
+/// A reference to an entry in one of the packing tables.
+enum Reference {
+    Shared(u64),
+}
+
+/// The packing tables in scope for an item.
+struct Tables<'a> {
+    shared: Vec<&'a DataItem>,
+}
+
+/// The parts of a table setup tag.
+struct Setup<'a> {
+    shared: &'a [DataItem],
+    rump: &'a DataItem,
+}
+
+/// Split the content of a table setup into its parts, if `tag` is one.
+fn table_setup(tag: Tag, value: &DataItem) -> Option<Result<Setup<'_>>> {
+    let data = match (tag, value) {
+        (Tag::PACKED_TABLE_SETUP, DataItem::Array { data, .. })
+        | (Tag::PACKED_SPLIT_TABLE_SETUP, DataItem::Array { data, .. }) => data,
+        (Tag::PACKED_TABLE_SETUP, _) | (Tag::PACKED_SPLIT_TABLE_SETUP, _) => {
+            return Some(Err(format!(
+                "packed table setup tag({}) must contain an array",
+                tag.0
+            )
+            .into()));
+        }
+        _ => return None,
+    };
+
+    Some(match (tag, &data[..]) {
+        // The same table is used for both shared and argument items
+        (Tag::PACKED_TABLE_SETUP, [shared @ .., rump]) => Ok(Setup { shared, rump }),
+        (
+            Tag::PACKED_SPLIT_TABLE_SETUP,
+            [DataItem::Array { data: shared, .. }, DataItem::Array { .. }, rump],
+        ) => Ok(Setup { shared, rump }),
+        (Tag::PACKED_SPLIT_TABLE_SETUP, _) => Err(format!(
+            "packed table setup tag({}) must contain a shared table, argument table and rump",
+            tag.0
+        )
+        .into()),
+        _ => Err(format!("packed table setup tag({}) is missing its rump", tag.0).into()),
+    })
+}
+
+/// The reference `item` makes, if it is one.
+fn reference(item: &DataItem) -> Option<Reference> {
+    match *item {
+        DataItem::Simple(Simple(value)) if value < 16 => Some(Reference::Shared(value.into())),
+        DataItem::Tag {
+            tag: Tag::PACKED_REFERENCE,
+            ref value,
+            ..
+        } => match **value {
+            DataItem::Integer { value, .. } => Some(Reference::Shared(
+                value.saturating_mul(2).saturating_add(16),
+            )),
+            DataItem::Negative { value, .. } => Some(Reference::Shared(
+                value.saturating_mul(2).saturating_add(17),
+            )),
+            // Argument references are not yet supported, they're left as-is
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn expand(
+    item: &DataItem,
+    tables: Option<&Tables<'_>>,
+    depth: usize,
+    options: &PackedOptions,
+) -> Result<DataItem> {
+    if depth > options.max_depth {
+        return Err(format!(
+            "packed references are nested more than {} deep",
+            options.max_depth
+        )
+        .into());
+    }
+
+    // References are only meaningful within a table setup
+    if let Some(tables) = tables {
+        if let Some(Reference::Shared(index)) = reference(item) {
+            let shared = usize::try_from(index)
+                .ok()
+                .and_then(|index| tables.shared.get(index))
+                .ok_or_else(|| {
+                    format!(
+                        "packed reference to shared item {} is out of range, the table has {} items",
+                        index,
+                        tables.shared.len()
+                    )
+                })?;
+            return expand(shared, Some(tables), depth + 1, options);
+        }
+    }
+
+    Ok(match item {
+        DataItem::Array { data, bitwidth } => DataItem::Array {
+            data: data
+                .iter()
+                .map(|item| expand(item, tables, depth, options))
+                .collect::<Result<_>>()?,
+            bitwidth: *bitwidth,
+        },
+        DataItem::Map { data, bitwidth } => DataItem::Map {
+            data: data
+                .iter()
+                .map(|(key, value)| {
+                    Ok((
+                        expand(key, tables, depth, options)?,
+                        expand(value, tables, depth, options)?,
+                    ))
+                })
+                .collect::<Result<_>>()?,
+            bitwidth: *bitwidth,
+        },
+        DataItem::Tag {
+            tag,
+            bitwidth,
+            value,
+        } => match table_setup(*tag, value) {
+            Some(setup) => {
+                let setup = setup?;
+                // Tables set up within the scope of others are prepended to them
+                let mut shared: Vec<&DataItem> = setup.shared.iter().collect();
+                shared.extend(tables.iter().flat_map(|tables| &tables.shared));
+                let tables = Tables { shared };
+                expand(setup.rump, Some(&tables), depth + 1, options)?
+            }
+            None => DataItem::Tag {
+                tag: *tag,
+                bitwidth: *bitwidth,
+                value: Box::new(expand(value, tables, depth, options)?),
+            },
+        },
+        item => item.clone(),
+    })
+}
+
+impl DataItem {
+    /// Expand any [packed CBOR][Packed] within this item, replacing table
+    /// setups with their rump and references with the table entries they
+    /// refer to.
+    ///
+    /// Only shared item references are supported, argument references (for
+    /// prefix and suffix composition) are left unexpanded.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"113(["hello", [simple(0), simple(0)]])"#).unwrap();
+    /// assert_eq!(item.expand_packed().unwrap().to_diag(), r#"["hello","hello"]"#);
+    /// ```
+    ///
+    /// [Packed]: https://datatracker.ietf.org/doc/html/draft-ietf-cbor-packed
+    pub fn expand_packed(&self) -> Result<DataItem> {
+        self.expand_packed_with(&PackedOptions::default())
+    }
+
+    /// Like [`DataItem::expand_packed`], but with configurable limits.
+    pub fn expand_packed_with(&self, options: &PackedOptions) -> Result<DataItem> {
+        expand(self, None, 0, options)
+    }
+}
@@ -1,287 +1,678 @@
-#![allow(clippy::useless_let_if_seq)]
-use std::{convert::TryFrom, str};
-
-use half::f16;
-use nom::{
-    bits::{bits, bytes},
-    branch::alt,
-    bytes::streaming::take as take_bytes,
-    combinator::{map, map_res, verify},
-    error::{make_error, ErrorKind},
-    multi::{count, many_till},
-    number::streaming::{be_f32, be_f64, be_u16},
-    sequence::{pair, preceded},
-    Err, IResult,
+use std::{convert::TryFrom, fmt};
+
+use super::{
+    reader::{Event, Malformed, Reader},
+    ParseOptions, ParseReport,
+};
+use crate::{
+    spans::{OriginalEncoding, Span, SpanTree},
+    ByteString, DataItem, Error, IntegerWidth, Result, Tag, TextString,
 };
 
-use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Result, Simple, Tag, TextString};
-
-pub fn take_bits<I, O>(count: usize) -> impl Fn((I, usize)) -> IResult<(I, usize), O>
-where
-    I: nom::Slice<std::ops::RangeFrom<usize>> + nom::InputIter<Item = u8> + nom::InputLength,
-    O: From<u8>
-        + core::ops::AddAssign
-        + core::ops::Shl<usize, Output = O>
-        + core::ops::Shr<usize, Output = O>,
-{
-    nom::bits::streaming::take(count)
+/// A problem found while parsing with [`parse_bytes_lenient`],
+/// [`parse_bytes_report`] or
+/// [`parse_diag_with_warnings`](crate::parse_diag_with_warnings).
+///
+/// From [`parse_bytes_lenient`] the data item returned will be missing some
+/// content, or have altered content, from this point on.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseWarning {
+    /// What kind of problem this is.
+    pub kind: WarningKind,
+    /// The offset in the input at which the problem was found.
+    pub offset: usize,
+    /// The path of the item the problem is with, as used by
+    /// [`Comments`](crate::Comments).
+    ///
+    /// Problems with malformed input from [`parse_bytes_lenient`] aren't
+    /// tied to a complete item so are given the root item's empty path.
+    pub path: Vec<usize>,
+    /// A description of the problem.
+    pub message: String,
 }
 
-pub fn tag_bits<I, O>(pattern: O, count: usize) -> impl Fn((I, usize)) -> IResult<(I, usize), O>
-where
-    I: nom::Slice<std::ops::RangeFrom<usize>>
-        + nom::InputIter<Item = u8>
-        + nom::InputLength
-        + Clone,
-    O: From<u8>
-        + core::ops::AddAssign
-        + core::ops::Shl<usize, Output = O>
-        + core::ops::Shr<usize, Output = O>
-        + PartialEq,
-{
-    nom::bits::streaming::tag(pattern, count)
+/// The kinds of [`ParseWarning`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// The input is not well-formed or ended early, from
+    /// [`parse_bytes_lenient`].
+    Malformed,
+    /// A text string contains invalid UTF-8, from [`parse_bytes_lenient`].
+    InvalidUtf8,
+    /// The data item is followed by more data, from [`parse_bytes_lenient`].
+    TrailingData,
+    /// An indefinite length string has more chunks, or a container more
+    /// items, than allowed, from [`parse_bytes_lenient`].
+    LimitExceeded,
+    /// A float literal in diagnostic notation changes value when narrowed to
+    /// the width of its encoding indicator, see
+    /// [`ParseOptions::allow_lossy_float_narrowing`].
+    LossyFloat,
+    /// A well-known tag has content of the wrong type, see
+    /// [`ParseOptions::strict_tag_content`].
+    TagContent,
+    /// A map has a key equal to an earlier key in it, from the
+    /// `parse_*_report` functions.
+    DuplicateKey,
 }
 
-fn integer(input: (&[u8], usize)) -> IResult<(&[u8], usize), (u64, IntegerWidth)> {
-    alt((
-        pair(verify(take_bits(5), |&v| v < 24), |i| {
-            Ok((i, IntegerWidth::Zero))
-        }),
-        pair(preceded(tag_bits(24, 5), take_bits(8)), |i| {
-            Ok((i, IntegerWidth::Eight))
-        }),
-        pair(preceded(tag_bits(25, 5), take_bits(16)), |i| {
-            Ok((i, IntegerWidth::Sixteen))
-        }),
-        pair(preceded(tag_bits(26, 5), take_bits(32)), |i| {
-            Ok((i, IntegerWidth::ThirtyTwo))
-        }),
-        pair(preceded(tag_bits(27, 5), take_bits(64)), |i| {
-            Ok((i, IntegerWidth::SixtyFour))
-        }),
-    ))(input)
+impl ParseWarning {
+    /// A warning for a problem not tied to a particular item.
+    pub(crate) fn new(kind: WarningKind, offset: usize, message: String) -> Self {
+        Self {
+            kind,
+            offset,
+            path: Vec::new(),
+            message,
+        }
+    }
 }
 
-fn positive(input: &[u8]) -> IResult<&[u8], DataItem> {
-    bits(preceded(
-        tag_bits(0, 3),
-        map(integer, |(value, bitwidth)| DataItem::Integer {
-            value,
-            bitwidth,
-        }),
-    ))(input)
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at offset {}: {}", self.offset, self.message)
+    }
 }
 
-fn negative(input: &[u8]) -> IResult<&[u8], DataItem> {
-    bits(preceded(
-        tag_bits(1, 3),
-        map(integer, |(value, bitwidth)| DataItem::Negative {
-            value,
-            bitwidth,
-        }),
-    ))(input)
+/// Why the decoder stopped before reaching the end of a data item.
+enum Stop {
+    /// The input ended part way through
+    Incomplete(ParseWarning),
+    /// The input is not well-formed CBOR
+    Invalid(ParseWarning),
+    /// A declared length was larger than allowed, `incomplete` is set if it
+    /// could still be satisfied by more input
+    LengthOutOfRange {
+        declared: u64,
+        available: usize,
+        incomplete: bool,
+    },
+    /// An indefinite length string had more chunks, or a container more
+    /// items, than allowed
+    LimitExceeded {
+        offset: usize,
+        limit: usize,
+        counted: &'static str,
+    },
 }
 
-fn definite_bytestring(input: &[u8]) -> IResult<&[u8], ByteString> {
-    let (input, (length, bitwidth)) = bits(preceded(tag_bits(2, 3), integer))(input)?;
-    let length = usize::try_from(length)
-        .map_err(|_| Err::Error(make_error(input, ErrorKind::LengthValue)))?;
-    let (input, data) = take_bytes(length)(input)?;
-    let data = data.to_owned();
-    Ok((input, ByteString { data, bitwidth }))
-}
+/// `Ok(None)` is only returned in lenient mode, when no item could be decoded
+/// before stopping.
+type Parsed<T> = std::result::Result<Option<T>, Stop>;
 
-fn indefinite_bytestring(input: &[u8]) -> IResult<&[u8], DataItem> {
-    preceded(
-        bits(pair(tag_bits(2, 3), tag_bits(31, 5))),
-        map(many_till(definite_bytestring, stop_code), |(strings, _)| {
-            DataItem::IndefiniteByteString(strings)
-        }),
-    )(input)
+/// Unwrap an `Ok(Some(_))`, returning early for `Ok(None)` or `Err(_)`.
+macro_rules! next {
+    ($e:expr) => {
+        match $e? {
+            Some(value) => value,
+            None => return Ok(None),
+        }
+    };
 }
 
-fn bytestring(input: &[u8]) -> IResult<&[u8], DataItem> {
-    alt((
-        map(definite_bytestring, DataItem::ByteString),
-        indefinite_bytestring,
-    ))(input)
+/// A definite length array or map being decoded, to describe where the input
+/// ended within it.
+struct Container {
+    kind: &'static str,
+    /// What the length counts, `"items"` or `"entries"`
+    counted: &'static str,
+    start: usize,
+    declared: u64,
+    /// How many items or entries have been decoded so far
+    decoded: usize,
 }
 
-fn definite_textstring(input: &[u8]) -> IResult<&[u8], TextString> {
-    let (input, (length, bitwidth)) = bits(preceded(tag_bits(3, 3), integer))(input)?;
-    let length = usize::try_from(length)
-        .map_err(|_| Err::Error(make_error(input, ErrorKind::LengthValue)))?;
-    let (input, data) = map_res(take_bytes(length), str::from_utf8)(input)?;
-    let data = data.to_owned();
-    Ok((input, TextString { data, bitwidth }))
+struct Decoder<'a> {
+    reader: Reader<'a>,
+    /// Whether to record problems as warnings and stop decoding, returning
+    /// what was decoded so far, instead of failing
+    lenient: bool,
+    /// Set once a problem has been recorded in lenient mode, every open item
+    /// is then closed off as-is
+    stopped: bool,
+    warnings: Vec<ParseWarning>,
+    /// See [`ParseOptions::max_allocation`]
+    max_allocation: Option<usize>,
+    /// See [`ParseOptions::max_chunks`]
+    max_chunks: usize,
+    /// See [`ParseOptions::max_items`]
+    max_items: usize,
+    /// When recording spans, the completed children of each open item, with
+    /// the root item recorded in the first
+    spans: Option<Vec<Vec<SpanTree>>>,
+    /// The definite length containers being decoded, innermost last
+    containers: Vec<Container>,
 }
 
-fn indefinite_textstring(input: &[u8]) -> IResult<&[u8], DataItem> {
-    preceded(
-        bits(pair(tag_bits(3, 3), tag_bits(31, 5))),
-        map(many_till(definite_textstring, stop_code), |(strings, _)| {
-            DataItem::IndefiniteTextString(strings)
-        }),
-    )(input)
-}
+impl<'a> Decoder<'a> {
+    fn new(input: &'a [u8], lenient: bool) -> Self {
+        let defaults = ParseOptions::default();
+        Self {
+            reader: Reader::new(input),
+            lenient,
+            stopped: false,
+            warnings: Vec::new(),
+            max_allocation: None,
+            max_chunks: defaults.max_chunks,
+            max_items: defaults.max_items,
+            spans: None,
+            containers: Vec::new(),
+        }
+    }
 
-fn textstring(input: &[u8]) -> IResult<&[u8], DataItem> {
-    alt((
-        map(definite_textstring, DataItem::TextString),
-        indefinite_textstring,
-    ))(input)
-}
+    fn with_options(input: &'a [u8], options: &ParseOptions) -> Self {
+        Self {
+            max_allocation: options.max_allocation,
+            max_chunks: options.max_chunks,
+            max_items: options.max_items,
+            ..Self::new(input, false)
+        }
+    }
 
-fn definite_array(input: &[u8]) -> IResult<&[u8], DataItem> {
-    let (input, (length, bitwidth)) = bits(preceded(tag_bits(4, 3), integer))(input)?;
-    let (input, data) = count(data_item, length as usize)(input)?;
-    Ok((
-        input,
-        DataItem::Array {
-            data,
-            bitwidth: Some(bitwidth),
-        },
-    ))
-}
+    fn open_span(&mut self) {
+        if let Some(spans) = &mut self.spans {
+            spans.push(Vec::new());
+        }
+    }
+
+    /// Record the span of the item started by the last [`Decoder::open_span`]
+    /// at `start`, with its header ending at `header_end`.
+    fn close_span(&mut self, start: usize, header_end: usize, is_map: bool) {
+        let end = self.reader.offset();
+        if let Some(spans) = &mut self.spans {
+            let children = spans.pop().expect("opened span");
+            let span = Span {
+                header: start..header_end,
+                content: header_end..end,
+            };
+            spans
+                .last_mut()
+                .expect("root span")
+                .push(SpanTree::new(span, is_map, children));
+        }
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        self.reader.remaining()
+    }
+
+    fn stop(
+        &mut self,
+        kind: fn(ParseWarning) -> Stop,
+        offset: usize,
+        message: String,
+    ) -> std::result::Result<(), Stop> {
+        let warning = ParseWarning::new(WarningKind::Malformed, offset, message);
+        if self.lenient {
+            self.warnings.push(warning);
+            self.stopped = true;
+            Ok(())
+        } else {
+            Err(kind(warning))
+        }
+    }
 
-fn indefinite_array(input: &[u8]) -> IResult<&[u8], DataItem> {
-    preceded(
-        bits(pair(tag_bits(4, 3), tag_bits(31, 5))),
-        map(many_till(data_item, stop_code), |(data, _)| {
-            DataItem::Array {
-                data,
-                bitwidth: None,
+    /// Fail because the input ended, or in lenient mode record it and stop.
+    ///
+    /// When failing the message describes each definite length container the
+    /// input ended in, innermost first, lenient mode records a problem for
+    /// each as it is closed instead.
+    fn incomplete(&mut self, mut message: String) -> std::result::Result<(), Stop> {
+        if !self.lenient {
+            for container in self.containers.iter().rev() {
+                message.push_str(&format!(
+                    ", in {} at offset {} declared {} {}, input ended after {}",
+                    container.kind,
+                    container.start,
+                    container.declared,
+                    container.counted,
+                    container.decoded
+                ));
             }
-        }),
-    )(input)
-}
+        }
+        // The reader has moved to the end of the input
+        self.stop(Stop::Incomplete, self.reader.offset(), message)
+    }
 
-fn array(input: &[u8]) -> IResult<&[u8], DataItem> {
-    alt((definite_array, indefinite_array))(input)
-}
+    /// Fail because of invalid input, or in lenient mode record it and stop.
+    fn invalid(&mut self, offset: usize, message: String) -> std::result::Result<(), Stop> {
+        self.stop(Stop::Invalid, offset, message)
+    }
 
-fn definite_map(input: &[u8]) -> IResult<&[u8], DataItem> {
-    let (input, (length, bitwidth)) = bits(preceded(tag_bits(5, 3), integer))(input)?;
-    let (input, data) = count(pair(data_item, data_item), length as usize)(input)?;
-    Ok((
-        input,
-        DataItem::Map {
-            data,
-            bitwidth: Some(bitwidth),
-        },
-    ))
-}
+    /// Fail because a string has more chunks or a container more items than
+    /// allowed, with `offset` the first over the limit, or in lenient mode
+    /// record it and stop.
+    fn limit_exceeded(
+        &mut self,
+        offset: usize,
+        limit: usize,
+        counted: &'static str,
+    ) -> std::result::Result<(), Stop> {
+        if self.lenient {
+            self.warnings.push(ParseWarning::new(
+                WarningKind::LimitExceeded,
+                offset,
+                format!("more than {} {}, exceeding the limit", limit, counted),
+            ));
+            self.stopped = true;
+            Ok(())
+        } else {
+            Err(Stop::LimitExceeded {
+                offset,
+                limit,
+                counted,
+            })
+        }
+    }
 
-fn indefinite_map(input: &[u8]) -> IResult<&[u8], DataItem> {
-    preceded(
-        bits(pair(tag_bits(5, 3), tag_bits(31, 5))),
-        map(
-            many_till(pair(data_item, data_item), stop_code),
-            |(data, _)| DataItem::Map {
-                data,
-                bitwidth: None,
-            },
-        ),
-    )(input)
-}
+    /// Record a problem for an item closed early because of an earlier
+    /// problem in lenient mode, at the same offset as that problem.
+    fn unclosed(&mut self, message: String) {
+        let offset = self
+            .warnings
+            .last()
+            .map_or(self.reader.offset(), |w| w.offset);
+        self.warnings
+            .push(ParseWarning::new(WarningKind::Malformed, offset, message));
+    }
 
-fn data_map(input: &[u8]) -> IResult<&[u8], DataItem> {
-    alt((definite_map, indefinite_map))(input)
-}
+    /// Read the next event, recording any problem with it.
+    ///
+    /// In lenient mode a string cut short by the end of the input is still
+    /// returned with whatever content was present.
+    fn event(&mut self) -> Parsed<Event<'a>> {
+        match self.reader.read() {
+            Ok(Some((_, event))) => Ok(Some(event)),
+            Ok(None) => {
+                self.incomplete("expected a data item, only 0 bytes present".into())?;
+                Ok(None)
+            }
+            Err(Malformed::Incomplete {
+                message,
+                truncated: Some((declared, event)),
+            }) => {
+                if let Event::ByteString { data, .. } | Event::TextString { data, .. } = event {
+                    self.check_length(declared, data.len())?;
+                }
+                self.incomplete(message)?;
+                Ok(Some(event))
+            }
+            Err(Malformed::Incomplete { message, .. }) => {
+                self.incomplete(message)?;
+                Ok(None)
+            }
+            Err(Malformed::Invalid { offset, message }) => {
+                self.invalid(offset, message)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Check a declared length against the `remaining` input and the maximum
+    /// allocation before using it, in lenient mode problems are instead
+    /// found while reading the content.
+    fn check_length(&self, length: u64, remaining: usize) -> std::result::Result<(), Stop> {
+        if self.lenient {
+            return Ok(());
+        }
+        let max = self.max_allocation.unwrap_or(usize::max_value());
+        match usize::try_from(length) {
+            Ok(length) if length <= remaining && length <= max => Ok(()),
+            result => Err(Stop::LengthOutOfRange {
+                declared: length,
+                available: remaining.min(max),
+                // Only limited by the input so far, more may follow in a stream
+                incomplete: matches!(result, Ok(length) if length <= max),
+            }),
+        }
+    }
+
+    /// Copy out the content of a string read in full.
+    fn bytes(&self, data: &[u8]) -> std::result::Result<Vec<u8>, Stop> {
+        self.check_length(data.len() as u64, data.len() + self.remaining().len())?;
+        Ok(data.to_owned())
+    }
 
-fn tag_bitsged(input: &[u8]) -> IResult<&[u8], DataItem> {
-    let (input, (tag, bitwidth)) = bits(preceded(tag_bits(6, 3), integer))(input)?;
-    let (input, value) = data_item(input)?;
-    let value = Box::new(value);
-    Ok((
-        input,
-        DataItem::Tag {
-            tag: Tag(tag),
+    fn bytestring(
+        &mut self,
+        data: &[u8],
+        bitwidth: IntegerWidth,
+    ) -> std::result::Result<ByteString, Stop> {
+        Ok(ByteString {
+            data: self.bytes(data)?,
             bitwidth,
-            value,
-        },
-    ))
-}
+            encoding: None,
+        })
+    }
 
-fn float(input: &[u8]) -> IResult<&[u8], DataItem> {
-    bits(preceded(
-        tag_bits(7, 3),
-        map(
-            alt((
-                preceded(
-                    tag_bits(25, 5),
-                    bytes::<
-                        _,
-                        _,
-                        (&[u8], nom::error::ErrorKind),
-                        ((&[u8], usize), nom::error::ErrorKind),
-                        _,
-                    >(map(be_u16, |u| {
-                        (f16::from_bits(u).to_f64(), FloatWidth::Sixteen)
-                    })),
-                ),
-                preceded(
-                    tag_bits(26, 5),
-                    bytes::<
-                        _,
-                        _,
-                        (&[u8], nom::error::ErrorKind),
-                        ((&[u8], usize), nom::error::ErrorKind),
-                        _,
-                    >(map(be_f32, |f| (f64::from(f), FloatWidth::ThirtyTwo))),
-                ),
-                preceded(
-                    tag_bits(27, 5),
-                    bytes::<
-                        _,
-                        _,
-                        (&[u8], nom::error::ErrorKind),
-                        ((&[u8], usize), nom::error::ErrorKind),
-                        _,
-                    >(map(be_f64, |f| (f, FloatWidth::SixtyFour))),
-                ),
-            )),
-            |(value, bitwidth)| DataItem::Float { value, bitwidth },
-        ),
-    ))(input)
-}
+    /// Text strings with invalid UTF-8 are always parsed to their raw bytes,
+    /// it is up to the caller to reject them based on the `ParseOptions`.
+    fn textstring(
+        &mut self,
+        start: usize,
+        data: &[u8],
+        bitwidth: IntegerWidth,
+    ) -> std::result::Result<std::result::Result<TextString, ByteString>, Stop> {
+        Ok(match String::from_utf8(self.bytes(data)?) {
+            Ok(data) => Ok(TextString { data, bitwidth }),
+            Err(err) => {
+                if self.lenient {
+                    self.warnings.push(ParseWarning::new(
+                        WarningKind::InvalidUtf8,
+                        start,
+                        "text string contains invalid UTF-8".into(),
+                    ));
+                }
+                Err(ByteString {
+                    data: err.into_bytes(),
+                    bitwidth,
+                    encoding: None,
+                })
+            }
+        })
+    }
 
-fn simple(input: &[u8]) -> IResult<&[u8], DataItem> {
-    bits(preceded(
-        tag_bits(7, 3),
-        map(
-            alt((
-                verify(take_bits(5), |&v| v < 24),
-                preceded(tag_bits(24, 5), take_bits(8)),
-            )),
-            |value| DataItem::Simple(Simple(value)),
-        ),
-    ))(input)
-}
+    /// Parse the chunks of an indefinite length string up to and including
+    /// the break, passing the start offset and content of each chunk to
+    /// `chunk`.
+    fn indefinite_string<T>(
+        &mut self,
+        kind: &str,
+        mut chunk: impl FnMut(&mut Self, usize, &'a [u8], IntegerWidth) -> std::result::Result<T, Stop>,
+    ) -> Parsed<Vec<T>> {
+        let mut chunks = Vec::new();
+        loop {
+            let start = self.reader.offset();
+            match self.remaining().first() {
+                None => {
+                    self.event()?;
+                    return Ok(Some(chunks));
+                }
+                Some(&initial) if initial != 0xff && chunks.len() == self.max_chunks => {
+                    self.limit_exceeded(start, self.max_chunks, "chunks")?;
+                    break;
+                }
+                Some(_) => {}
+            }
+            match self.event()? {
+                Some(Event::EndIndefinite) => return Ok(Some(chunks)),
+                Some(Event::ByteString { data, bitwidth })
+                | Some(Event::TextString { data, bitwidth }) => {
+                    let header_end = self.reader.offset() - data.len();
+                    self.open_span();
+                    let value = chunk(self, start, data, bitwidth);
+                    self.close_span(start, header_end, false);
+                    chunks.push(value?);
+                    if self.stopped {
+                        break;
+                    }
+                }
+                Some(_) => unreachable!("the reader only returns chunks or a break"),
+                None => break,
+            }
+        }
+        self.unclosed(format!(
+            "indefinite length {} not terminated, only {} chunks present",
+            kind,
+            chunks.len()
+        ));
+        Ok(Some(chunks))
+    }
 
-fn stop_code(input: &[u8]) -> IResult<&[u8], DataItem> {
-    bits(preceded(
-        tag_bits(7, 3),
-        map(tag_bits(31, 5), |value| DataItem::Simple(Simple(value))),
-    ))(input)
-}
+    fn indefinite_textstring(&mut self) -> Parsed<DataItem> {
+        let strings = next!(self.indefinite_string("text string", Self::textstring));
+        Ok(Some(if strings.iter().all(|string| string.is_ok()) {
+            DataItem::IndefiniteTextString(strings.into_iter().flatten().collect())
+        } else {
+            DataItem::InvalidIndefiniteTextString(
+                strings
+                    .into_iter()
+                    .map(|string| match string {
+                        Ok(TextString { data, bitwidth }) => ByteString {
+                            data: data.into_bytes(),
+                            bitwidth,
+                            encoding: None,
+                        },
+                        Err(bytes) => bytes,
+                    })
+                    .collect(),
+            )
+        }))
+    }
+
+    /// Check for the break ending an indefinite length container, recording
+    /// a problem if the input ends first.
+    fn at_break(&mut self, kind: &str, len: usize) -> std::result::Result<bool, Stop> {
+        if self.stopped {
+            self.unclosed(format!(
+                "indefinite length {} not terminated, only {} present",
+                kind, len
+            ));
+            return Ok(true);
+        }
+        match self.remaining().first() {
+            // The reader reports the missing break, or reads the break
+            None | Some(0xff) => {
+                self.event()?;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    /// Capacity for a container declaring `length` items, each item needs at
+    /// least one byte so don't trust the length further than that.
+    fn capacity(&self, length: u64) -> usize {
+        usize::try_from(length)
+            .unwrap_or(usize::max_value())
+            .min(self.remaining().len())
+            .min(self.max_allocation.unwrap_or(usize::max_value()))
+            .min(self.max_items)
+    }
+
+    /// Start decoding the content of a definite length container starting at
+    /// `start`.
+    fn open_container(
+        &mut self,
+        kind: &'static str,
+        counted: &'static str,
+        start: usize,
+        declared: u64,
+    ) {
+        self.containers.push(Container {
+            kind,
+            counted,
+            start,
+            declared,
+            decoded: 0,
+        });
+    }
 
-fn data_item(input: &[u8]) -> IResult<&[u8], DataItem> {
-    alt((
-        positive,
-        negative,
-        bytestring,
-        textstring,
-        array,
-        data_map,
-        tag_bitsged,
-        float,
-        simple,
-    ))(input)
+    /// Record that the innermost container has `decoded` items or entries.
+    fn decoded(&mut self, decoded: usize) {
+        if let Some(container) = self.containers.last_mut() {
+            container.decoded = decoded;
+        }
+    }
+
+    fn array(
+        &mut self,
+        start: usize,
+        length: Option<u64>,
+        bitwidth: Option<IntegerWidth>,
+    ) -> Parsed<DataItem> {
+        let length = match length {
+            Some(length) => length,
+            None => {
+                let mut data = Vec::new();
+                while !self.at_break("array", data.len())? {
+                    if data.len() == self.max_items {
+                        self.limit_exceeded(self.reader.offset(), self.max_items, "items")?;
+                        continue;
+                    }
+                    match self.item()? {
+                        Some(item) => data.push(item),
+                        None => continue,
+                    }
+                }
+                return Ok(Some(DataItem::Array {
+                    data,
+                    bitwidth: None,
+                }));
+            }
+        };
+
+        self.check_length(length, self.remaining().len())?;
+        let mut data = Vec::with_capacity(self.capacity(length));
+        self.open_container("array", "items", start, length);
+        while (data.len() as u64) < length && !self.stopped {
+            if data.len() == self.max_items {
+                self.limit_exceeded(self.reader.offset(), self.max_items, "items")?;
+                continue;
+            }
+            if let Some(item) = self.item()? {
+                data.push(item);
+                self.decoded(data.len());
+            }
+        }
+        self.containers.pop();
+        if (data.len() as u64) < length {
+            self.unclosed(format!(
+                "array declared {} items, only {} present",
+                length,
+                data.len()
+            ));
+        }
+        Ok(Some(DataItem::Array { data, bitwidth }))
+    }
+
+    fn entry(&mut self) -> Parsed<(DataItem, DataItem)> {
+        let key = next!(self.item());
+        if self.stopped {
+            return Ok(None);
+        }
+        let value = next!(self.item());
+        Ok(Some((key, value)))
+    }
+
+    fn map(
+        &mut self,
+        start: usize,
+        length: Option<u64>,
+        bitwidth: Option<IntegerWidth>,
+    ) -> Parsed<DataItem> {
+        let length = match length {
+            Some(length) => length,
+            None => {
+                let mut data = Vec::new();
+                while !self.at_break("map", data.len())? {
+                    if data.len() == self.max_items {
+                        self.limit_exceeded(self.reader.offset(), self.max_items, "entries")?;
+                        continue;
+                    }
+                    match self.entry()? {
+                        Some(entry) => data.push(entry),
+                        None => continue,
+                    }
+                }
+                return Ok(Some(DataItem::Map {
+                    data,
+                    bitwidth: None,
+                }));
+            }
+        };
+
+        self.check_length(length, self.remaining().len())?;
+        let mut data = Vec::with_capacity(self.capacity(length));
+        self.open_container("map", "entries", start, length);
+        while (data.len() as u64) < length && !self.stopped {
+            if data.len() == self.max_items {
+                self.limit_exceeded(self.reader.offset(), self.max_items, "entries")?;
+                continue;
+            }
+            if let Some(entry) = self.entry()? {
+                data.push(entry);
+                self.decoded(data.len());
+            }
+        }
+        self.containers.pop();
+        if (data.len() as u64) < length {
+            self.unclosed(format!(
+                "map declared {} entries, only {} present",
+                length,
+                data.len()
+            ));
+        }
+        Ok(Some(DataItem::Map { data, bitwidth }))
+    }
+
+    fn tagged(&mut self, tag: Tag, bitwidth: IntegerWidth) -> Parsed<DataItem> {
+        match self.item()? {
+            Some(value) => Ok(Some(DataItem::Tag {
+                tag,
+                bitwidth,
+                value: Box::new(value),
+            })),
+            None => {
+                self.unclosed(format!("tag {} missing its value", tag.0));
+                Ok(None)
+            }
+        }
+    }
+
+    fn item(&mut self) -> Parsed<DataItem> {
+        let start = self.reader.offset();
+        self.open_span();
+        let mut header_end = start;
+        let item = self.item_contents(start, &mut header_end);
+        if let Ok(Some(item)) = &item {
+            let is_map = matches!(item, DataItem::Map { .. });
+            self.close_span(start, header_end, is_map);
+        }
+        item
+    }
+
+    /// Parse an item starting at `start`, setting `header_end` once the
+    /// header has been parsed.
+    fn item_contents(&mut self, start: usize, header_end: &mut usize) -> Parsed<DataItem> {
+        let event = next!(self.event());
+        *header_end = self.reader.offset();
+
+        Ok(Some(match event {
+            Event::Integer { value, bitwidth } => DataItem::Integer { value, bitwidth },
+            Event::Negative { value, bitwidth } => DataItem::Negative { value, bitwidth },
+            Event::ByteString { data, bitwidth } => {
+                *header_end -= data.len();
+                DataItem::ByteString(self.bytestring(data, bitwidth)?)
+            }
+            Event::TextString { data, bitwidth } => {
+                *header_end -= data.len();
+                match self.textstring(start, data, bitwidth)? {
+                    Ok(string) => DataItem::TextString(string),
+                    Err(bytes) => DataItem::InvalidTextString(bytes),
+                }
+            }
+            Event::BeginByteString => DataItem::IndefiniteByteString(next!(self
+                .indefinite_string("byte string", |this, _, data, bitwidth| this
+                    .bytestring(data, bitwidth)))),
+            Event::BeginTextString => next!(self.indefinite_textstring()),
+            Event::BeginArray { len, bitwidth } => next!(self.array(start, len, bitwidth)),
+            Event::BeginMap { len, bitwidth } => next!(self.map(start, len, bitwidth)),
+            Event::Tag { tag, bitwidth } => next!(self.tagged(tag, bitwidth)),
+            Event::Float { value, bitwidth } => DataItem::Float { value, bitwidth },
+            Event::Simple(simple) => DataItem::Simple(simple),
+            Event::EndIndefinite => unreachable!("breaks are only read by the containers"),
+        }))
+    }
 }
 
 /// Parse a string containing a binary encoded CBOR data item.
 ///
+/// If the data item is followed by more data this fails with
+/// [`Error::TrailingData`], which includes the parsed item, use
+/// [`parse_bytes_partial`] to allow trailing data.
+///
 /// # Examples
 ///
 /// ```rust
@@ -302,14 +693,165 @@ fn data_item(input: &[u8]) -> IResult<&[u8], DataItem> {
 ///     });
 /// ```
 pub fn parse_bytes(bytes: impl AsRef<[u8]>) -> Result<DataItem> {
-    let (remaining, parsed) =
-        data_item(bytes.as_ref()).map_err(|e| format!("Parsing error ({:?})", e))?;
+    parse_bytes_with(bytes, &ParseOptions::default())
+}
+
+/// Like [`parse_bytes`], but with configurable parsing.
+pub fn parse_bytes_with(bytes: impl AsRef<[u8]>, options: &ParseOptions) -> Result<DataItem> {
+    if options.strict_tag_content {
+        return parse_bytes_report(bytes, options).map(|report| report.item);
+    }
+    decode(&mut Decoder::with_options(bytes.as_ref(), options), options)
+}
+
+/// Decode a single item using all the input.
+fn decode(decoder: &mut Decoder<'_>, options: &ParseOptions) -> Result<DataItem> {
+    let parsed = match decoder.item() {
+        Ok(Some(item)) => item,
+        Ok(None) => unreachable!("only returned in lenient mode"),
+        Err(Stop::Incomplete(warning)) | Err(Stop::Invalid(warning)) => {
+            return Err(format!("Parsing error {}", warning).into());
+        }
+        Err(Stop::LengthOutOfRange {
+            declared,
+            available,
+            ..
+        }) => {
+            return Err(Error::LengthOutOfRange {
+                declared,
+                available,
+            })
+        }
+        Err(Stop::LimitExceeded {
+            offset,
+            limit,
+            counted,
+        }) => {
+            return Err(Error::LimitExceeded {
+                offset,
+                limit,
+                counted,
+            })
+        }
+    };
+    options.check(&parsed)?;
+    let mut offset = decoder.reader.offset();
+    let mut remaining = decoder.remaining();
+    if options.stop_at_stray_break && remaining.first() == Some(&0xff) {
+        offset += 1;
+        remaining = &remaining[1..];
+    }
     if !remaining.is_empty() {
-        return Err(format!("Remaining bytes ({})", hex::encode(remaining)).into());
+        return Err(Error::TrailingData {
+            parsed,
+            offset,
+            remaining: remaining.len(),
+        });
     }
     Ok(parsed)
 }
 
+/// Like [`parse_bytes`], but also returning the byte ranges each part of the
+/// data item was parsed from.
+///
+/// # Examples
+///
+/// ```rust
+/// let (item, spans) = cbor_diag::parse_bytes_with_spans(b"\xa1\x01\x82\x02\x03").unwrap();
+///
+/// assert_eq!(item.to_diag(), "{1:[2,3]}");
+/// assert_eq!(spans.span().range(), 0..5);
+/// // The value of the first entry in the map
+/// assert_eq!(spans.get(&[0, 1]).unwrap().content, 3..5);
+/// assert_eq!(spans.item_at(4).unwrap().0, [0, 1, 1]);
+/// ```
+pub fn parse_bytes_with_spans(bytes: impl AsRef<[u8]>) -> Result<(DataItem, SpanTree)> {
+    parse_bytes_with_spans_with(bytes.as_ref(), &ParseOptions::default())
+}
+
+/// Like [`parse_bytes`], but also keeping the original encoding of the data
+/// item, so after changing it [`DataItem::to_bytes_preserving`] can re-encode
+/// only what changed, keeping the exact bytes of everything else.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_bytes_preserving, DataItem, IntegerWidth};
+///
+/// // [1_0, a signalling NaN]
+/// let bytes = b"\x82\x18\x01\xf9\xfc\x01";
+/// let (mut item, original) = parse_bytes_preserving(bytes).unwrap();
+/// assert_eq!(item.to_bytes_preserving(&original), bytes);
+///
+/// if let DataItem::Array { data, .. } = &mut item {
+///     data[0] = DataItem::Integer { value: 2, bitwidth: IntegerWidth::Zero };
+/// }
+/// assert_eq!(item.to_bytes_preserving(&original), b"\x82\x02\xf9\xfc\x01");
+/// // Re-encoding the whole item quiets the NaN
+/// assert_eq!(item.to_bytes(), b"\x82\x02\xf9\xfe\x01");
+/// ```
+pub fn parse_bytes_preserving(bytes: impl AsRef<[u8]>) -> Result<(DataItem, OriginalEncoding)> {
+    parse_bytes_preserving_with(bytes, &ParseOptions::default())
+}
+
+/// Like [`parse_bytes_preserving`], but with configurable parsing.
+pub fn parse_bytes_preserving_with(
+    bytes: impl AsRef<[u8]>,
+    options: &ParseOptions,
+) -> Result<(DataItem, OriginalEncoding)> {
+    let bytes = bytes.as_ref();
+    let (item, spans) = parse_bytes_with_spans_with(bytes, options)?;
+    let original = OriginalEncoding {
+        bytes: bytes.to_owned(),
+        item: item.clone(),
+        spans,
+    };
+    Ok((item, original))
+}
+
+/// Like [`parse_bytes_with_spans`], but with configurable parsing.
+pub(crate) fn parse_bytes_with_spans_with(
+    bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<(DataItem, SpanTree)> {
+    let mut decoder = Decoder::with_options(bytes, options);
+    decoder.spans = Some(vec![Vec::new()]);
+    let item = decode(&mut decoder, options)?;
+    let spans = decoder
+        .spans
+        .and_then(|mut spans| spans.pop()?.pop())
+        .expect("root span");
+    Ok((item, spans))
+}
+
+/// Like [`parse_bytes_with`], but also returns the problems found in the data
+/// item that didn't stop parsing: well-known tags with content of the wrong
+/// type (see [`ParseOptions::strict_tag_content`]) and duplicate map keys.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_bytes_report, ParseOptions};
+///
+/// // [1, 0(5)]
+/// let report = parse_bytes_report(b"\x82\x01\xc0\x05", &ParseOptions::default()).unwrap();
+///
+/// assert_eq!(report.item.to_diag(), "[1,0(5)]");
+/// assert_eq!(report.warnings[0].offset, 2);
+/// assert_eq!(
+///     report.warnings[0].message,
+///     "tag 0 (date/time) expects a text string, found an unsigned integer",
+/// );
+/// ```
+pub fn parse_bytes_report(bytes: impl AsRef<[u8]>, options: &ParseOptions) -> Result<ParseReport> {
+    let (item, spans) = parse_bytes_with_spans_with(bytes.as_ref(), options)?;
+    let offset = |path: &[usize]| spans.get(path).map_or(0, |span| span.header.start);
+    let mut warnings = options.check_tag_content(&item, offset)?;
+    warnings.extend(super::duplicate_keys(&item, offset));
+    warnings.sort_by_key(|warning| warning.offset);
+    Ok(ParseReport { item, warnings })
+}
+
 /// Parse a string containing a binary encoded CBOR data item, optionally followed by more data.
 ///
 /// Returns one of:
@@ -348,9 +890,109 @@ pub fn parse_bytes(bytes: impl AsRef<[u8]>) -> Result<DataItem> {
 ///     )));
 /// ```
 pub fn parse_bytes_partial(bytes: impl AsRef<[u8]>) -> Result<Option<(DataItem, usize)>> {
-    match data_item(bytes.as_ref()) {
-        Ok((remaining, item)) => Ok(Some((item, bytes.as_ref().len() - remaining.len()))),
-        Err(nom::Err::Incomplete(_)) => Ok(None),
-        Err(nom::Err::Failure(_)) | Err(nom::Err::Error(_)) => Err("Parser error".into()),
+    parse_bytes_partial_with(bytes, &ParseOptions::default())
+}
+
+/// Like [`parse_bytes_partial`], but with configurable parsing.
+pub fn parse_bytes_partial_with(
+    bytes: impl AsRef<[u8]>,
+    options: &ParseOptions,
+) -> Result<Option<(DataItem, usize)>> {
+    let mut decoder = Decoder::with_options(bytes.as_ref(), options);
+    if options.strict_tag_content {
+        decoder.spans = Some(vec![Vec::new()]);
+    }
+    match decoder.item() {
+        Ok(Some(item)) => {
+            options.check(&item)?;
+            if let Some(spans) = decoder
+                .spans
+                .take()
+                .and_then(|mut spans| spans.pop()?.pop())
+            {
+                options.check_tag_content(&item, |path| {
+                    spans.get(path).map_or(0, |span| span.header.start)
+                })?;
+            }
+            Ok(Some((item, decoder.reader.offset())))
+        }
+        Ok(None) => unreachable!("only returned in lenient mode"),
+        Err(Stop::Incomplete(_))
+        | Err(Stop::LengthOutOfRange {
+            incomplete: true, ..
+        }) => Ok(None),
+        Err(Stop::Invalid(warning)) => Err(format!("Parsing error {}", warning).into()),
+        Err(Stop::LengthOutOfRange {
+            declared,
+            available,
+            ..
+        }) => Err(Error::LengthOutOfRange {
+            declared,
+            available,
+        }),
+        Err(Stop::LimitExceeded {
+            offset,
+            limit,
+            counted,
+        }) => Err(Error::LimitExceeded {
+            offset,
+            limit,
+            counted,
+        }),
+    }
+}
+
+/// Parse a string containing a binary encoded CBOR data item, recovering as
+/// much as possible from truncated or malformed input.
+///
+/// Instead of failing, parsing stops at the first problem found and any open
+/// arrays, maps, strings and tags are closed off at that point. Returns the
+/// data item decoded so far (if any of it could be) along with warnings
+/// describing each problem and unclosed item. Text strings containing invalid
+/// UTF-8 are accepted as with [`ParseOptions::allow_invalid_utf8`], and any
+/// data following the item is also reported as a warning.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{DataItem, IntegerWidth};
+///
+/// let (item, warnings) = cbor_diag::parse_bytes_lenient(b"\x83\x01\x02");
+///
+/// assert_eq!(
+///     item,
+///     Some(DataItem::Array {
+///         data: vec![
+///             DataItem::Integer { value: 1, bitwidth: IntegerWidth::Zero },
+///             DataItem::Integer { value: 2, bitwidth: IntegerWidth::Zero },
+///         ],
+///         bitwidth: Some(IntegerWidth::Zero),
+///     }));
+///
+/// assert_eq!(
+///     warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+///     [
+///         "at offset 3: expected a data item, only 0 bytes present",
+///         "at offset 3: array declared 3 items, only 2 present",
+///     ]);
+/// ```
+pub fn parse_bytes_lenient(bytes: impl AsRef<[u8]>) -> (Option<DataItem>, Vec<ParseWarning>) {
+    let mut decoder = Decoder::new(bytes.as_ref(), true);
+    let item = match decoder.item() {
+        Ok(item) => item,
+        Err(_) => unreachable!("lenient parsing never fails"),
+    };
+    if !decoder.stopped && !decoder.remaining().is_empty() {
+        let offset = decoder.reader.offset();
+        let message = format!(
+            "{} bytes of trailing data after the data item",
+            decoder.remaining().len()
+        );
+        decoder.warnings.push(ParseWarning::new(
+            WarningKind::TrailingData,
+            offset,
+            message,
+        ));
     }
+    (item, decoder.warnings)
 }
@@ -0,0 +1,250 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::{CommentPosition, Comments};
+
+#[derive(Copy, Clone)]
+enum Frame {
+    /// An array, or the chunks of an indefinite length string
+    Items(usize),
+    /// A map, at entry `.0`, in its value if `.1`
+    Map(usize, bool),
+    Tag,
+}
+
+fn path(frames: &[Frame]) -> Vec<usize> {
+    let mut path = Vec::with_capacity(frames.len());
+    for frame in frames {
+        match *frame {
+            Frame::Items(index) => path.push(index),
+            Frame::Map(index, value) => path.extend(&[index, value as usize]),
+            Frame::Tag => path.push(0),
+        }
+    }
+    path
+}
+
+fn skip_quoted(chars: &mut Peekable<Chars<'_>>, quote: char) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => break,
+            _ => {}
+        }
+    }
+}
+
+fn flush(
+    comments: &mut Comments,
+    pending: &mut Vec<String>,
+    path: Vec<usize>,
+    position: CommentPosition,
+) {
+    for comment in pending.drain(..) {
+        comments.push(path.clone(), position, comment);
+    }
+}
+
+/// Find the comments in diagnostic notation that has already been parsed
+/// successfully, attaching each to the closest item.
+///
+/// A comment is attached after the preceding item if it directly follows
+/// the item, or follows its comma with nothing else on the rest of the line,
+/// otherwise it is attached before the following item. Comments with no item
+/// to attach to in an empty container are attached after the container.
+pub(super) fn collect_comments(text: &str) -> Comments {
+    let mut comments = Comments::new();
+    let mut frames = Vec::new();
+    // Whether the item in the current position has started
+    let mut seen = false;
+    // The path of the item before a comma, until the end of the line, with
+    // the comments since the comma that will be attached after it if nothing
+    // else follows on the line
+    let mut previous = None;
+    let mut pending = Vec::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '/').collect();
+                let comment = comment.trim().to_owned();
+                if previous.is_some() {
+                    pending.push(comment);
+                } else if seen {
+                    comments.push(path(&frames), CommentPosition::After, comment);
+                } else {
+                    comments.push(path(&frames), CommentPosition::Before, comment);
+                }
+                continue;
+            }
+            '\n' | ',' | ']' | '}' | ')' => {
+                if let Some(previous) = previous.take() {
+                    flush(
+                        &mut comments,
+                        &mut pending,
+                        previous,
+                        CommentPosition::After,
+                    );
+                }
+            }
+            c if c.is_whitespace() => continue,
+            ':' => {}
+            _ => {
+                // The start of an item
+                if previous.take().is_some() {
+                    flush(
+                        &mut comments,
+                        &mut pending,
+                        path(&frames),
+                        CommentPosition::Before,
+                    );
+                }
+            }
+        }
+
+        match c {
+            ',' => {
+                previous = Some(path(&frames));
+                match frames.last_mut() {
+                    Some(Frame::Items(index)) => *index += 1,
+                    Some(Frame::Map(index, value)) => {
+                        *index += 1;
+                        *value = false;
+                    }
+                    _ => {}
+                }
+                seen = false;
+            }
+            ':' => {
+                if let Some(Frame::Map(_, value)) = frames.last_mut() {
+                    *value = true;
+                }
+                seen = false;
+            }
+            '[' | '{' | '(' => {
+                let frame = match c {
+                    '[' => Frame::Items(0),
+                    '{' => Frame::Map(0, false),
+                    _ if chars.peek() == Some(&'_') => Frame::Items(0),
+                    _ => Frame::Tag,
+                };
+                // Indefinite length marker and any encoding indicator
+                if chars.next_if_eq(&'_').is_some() {
+                    while chars.next_if(char::is_ascii_digit).is_some() {}
+                }
+                frames.push(frame);
+                seen = false;
+            }
+            ']' | '}' | ')' => {
+                let current = path(&frames);
+                frames.pop();
+                if !seen {
+                    // Move any comments from the empty position to after the
+                    // container
+                    let container = path(&frames);
+                    for comment in comments.get(&current, CommentPosition::Before).to_vec() {
+                        comments.push(container.clone(), CommentPosition::After, comment);
+                    }
+                    comments.remove(&current);
+                }
+                seen = true;
+            }
+            '"' | '\'' => {
+                skip_quoted(&mut chars, c);
+                seen = true;
+            }
+            '\n' => {}
+            _ => seen = true,
+        }
+    }
+
+    if let Some(previous) = previous {
+        flush(
+            &mut comments,
+            &mut pending,
+            previous,
+            CommentPosition::After,
+        );
+    }
+
+    comments
+}
+
+/// Find the offset at which each item starts in diagnostic notation that has
+/// already been parsed successfully, along with its path.
+pub(super) fn item_offsets(text: &str) -> Vec<(Vec<usize>, usize)> {
+    let mut offsets = Vec::new();
+    let mut frames = Vec::new();
+    // Whether the item in the current position has started
+    let mut seen = false;
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '/' => {
+                chars.by_ref().find(|&(_, c)| c == '/');
+                continue;
+            }
+            '"' | '\'' => {
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            c if c.is_whitespace() => continue,
+            _ => {}
+        }
+
+        match c {
+            ',' => {
+                match frames.last_mut() {
+                    Some(Frame::Items(index)) => *index += 1,
+                    Some(Frame::Map(index, value)) => {
+                        *index += 1;
+                        *value = false;
+                    }
+                    _ => {}
+                }
+                seen = false;
+            }
+            ':' => {
+                if let Some(Frame::Map(_, value)) = frames.last_mut() {
+                    *value = true;
+                }
+                seen = false;
+            }
+            ']' | '}' | ')' => {
+                frames.pop();
+                seen = true;
+            }
+            _ => {
+                if !seen {
+                    offsets.push((path(&frames), offset));
+                    seen = true;
+                }
+                if let '[' | '{' | '(' = c {
+                    let frame = match c {
+                        '[' => Frame::Items(0),
+                        '{' => Frame::Map(0, false),
+                        _ if chars.peek().map(|&(_, c)| c) == Some('_') => Frame::Items(0),
+                        _ => Frame::Tag,
+                    };
+                    if chars.next_if(|&(_, c)| c == '_').is_some() {
+                        while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                    }
+                    frames.push(frame);
+                    seen = false;
+                }
+            }
+        }
+    }
+
+    offsets
+}
@@ -1,23 +1,36 @@
 #![allow(clippy::needless_pass_by_value, clippy::useless_let_if_seq)]
 
+use std::convert::TryFrom;
 use std::f64;
 use std::str::FromStr;
 
+use half::f16;
 use nom::{
     branch::alt,
-    bytes::complete::{escaped_transform, tag},
-    character::complete::{char, digit1, hex_digit0, none_of},
-    combinator::{map, map_res, opt, recognize, value, verify},
-    error::context,
-    multi::separated_list,
-    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{char, digit1, hex_digit0, multispace1, none_of, one_of},
+    combinator::{map, map_res, not, opt, peek, recognize, value, verify},
+    error::{context, ErrorKind},
+    multi::{many0, separated_list},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
-use crate::{ByteString, DataItem, FloatWidth, IntegerWidth, Result, Simple, Tag, TextString};
+use super::{ParseOptions, ParseReport, ParseWarning, WarningKind};
+use crate::{
+    base32, ByteString, Comments, DataItem, Encoding, Error, FloatWidth, IntegerWidth, Result,
+    Simple, Tag, TextString,
+};
 
+/// Recognizes whitespace and `/ comments /`.
 fn ws<O: Default>(input: &str) -> IResult<&str, O> {
-    map(nom::character::complete::multispace0, |_| O::default())(input)
+    map(
+        many0(alt((
+            multispace1,
+            recognize(delimited(char('/'), take_while(|c| c != '/'), char('/'))),
+        ))),
+        |_| O::default(),
+    )(input)
 }
 
 fn wrapws<'a, T>(
@@ -28,54 +41,57 @@ fn wrapws<'a, T>(
 
 #[allow(clippy::needless_lifetimes)]
 fn opt_comma_tag<'a>(t: &'a str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
-    alt((tag(t), map(tuple((tag(","), ws, tag(t))), |(_, (), f)| f)))
-}
-
-/// Recognizes zero or more base64url characters: 0-9, A-Z, a-z, -, _
-fn base64url_digit0<T>(input: T) -> IResult<T, T>
-where
-    T: nom::InputTakeAtPosition,
-    <T as nom::InputTakeAtPosition>::Item: nom::AsChar + Copy,
-{
-    use nom::AsChar;
-    input.split_at_position(|item| {
-        !(item.is_alphanum() || item.as_char() == '-' || item.as_char() == '_')
-    })
+    preceded(
+        ws::<()>,
+        alt((tag(t), map(tuple((tag(","), ws, tag(t))), |(_, (), f)| f))),
+    )
 }
 
-/// Recognizes zero or more base64url characters: 0-9, A-Z, a-z, +, /
-fn base64_digit0<T>(input: T) -> IResult<T, T>
-where
-    T: nom::InputTakeAtPosition,
-    <T as nom::InputTakeAtPosition>::Item: nom::AsChar + Copy,
-{
-    use nom::AsChar;
-    input.split_at_position(|item| {
-        !(item.is_alphanum() || item.as_char() == '+' || item.as_char() == '/')
-    })
+/// An encoding indicator, `_0` to `_3`, giving the width of the argument of
+/// the item it follows. Anything else directly after the underscore is an
+/// error rather than being left as remaining text.
+fn encoding(input: &str) -> IResult<&str, u64> {
+    let (rest, _) = char('_')(input)?;
+    let (rest, digits) = take_while(|c: char| c.is_ascii_alphanumeric())(rest)?;
+    match digits.as_bytes() {
+        [digit @ b'0'..=b'3'] => Ok((rest, u64::from(digit - b'0'))),
+        _ => Err(nom::Err::Failure((input, ErrorKind::Digit))),
+    }
 }
 
-fn encoding(input: &str) -> IResult<&str, u64> {
-    preceded(tag("_"), verify(map_res(digit1, u64::from_str), |&e| e < 4))(input)
+/// Fails on an encoding indicator after an item parsed by `parser` that
+/// can't have one, or already had one.
+fn no_indicator<'a, O>(
+    parser: impl Fn(&'a str) -> IResult<&'a str, O>,
+) -> impl Fn(&'a str) -> IResult<&'a str, O> {
+    move |input| {
+        let (rest, output) = parser(input)?;
+        if let Ok((indicator, ())) = ws::<()>(rest) {
+            if indicator.starts_with('_') {
+                return Err(nom::Err::Failure((indicator, ErrorKind::Char)));
+            }
+        }
+        Ok((rest, output))
+    }
+}
+
+fn integer_width(input: &str) -> IResult<&str, IntegerWidth> {
+    map(opt(encoding), |encoding| match encoding {
+        Some(0) => IntegerWidth::Eight,
+        Some(1) => IntegerWidth::Sixteen,
+        Some(2) => IntegerWidth::ThirtyTwo,
+        Some(3) => IntegerWidth::SixtyFour,
+        None => IntegerWidth::Unknown,
+        Some(_) => unreachable!(),
+    })(input)
 }
 
 fn integer(input: &str) -> IResult<&str, (u64, IntegerWidth)> {
-    let (input, value) = map_res(digit1, u64::from_str)(input)?;
-    let (input, encoding) = opt(encoding)(input)?;
-    Ok((
-        input,
-        (
-            value,
-            match encoding {
-                Some(0) => IntegerWidth::Eight,
-                Some(1) => IntegerWidth::Sixteen,
-                Some(2) => IntegerWidth::ThirtyTwo,
-                Some(3) => IntegerWidth::SixtyFour,
-                None => IntegerWidth::Unknown,
-                Some(_) => unreachable!(),
-            },
-        ),
-    ))
+    let (rest, digits) = digit1(input)?;
+    let value =
+        u64::from_str(digits).map_err(|_| nom::Err::Failure((input, ErrorKind::TooLarge)))?;
+    let (rest, bitwidth) = integer_width(rest)?;
+    Ok((rest, (value, bitwidth)))
 }
 
 fn positive(input: &str) -> IResult<&str, DataItem> {
@@ -90,109 +106,322 @@ fn positive(input: &str) -> IResult<&str, DataItem> {
 }
 
 fn negative(input: &str) -> IResult<&str, DataItem> {
-    preceded(
-        tag("-"),
-        map(
-            verify(integer, |&(value, _)| value > 0),
-            |(value, bitwidth)| DataItem::Negative {
-                value: value - 1,
-                bitwidth: if bitwidth == IntegerWidth::Unknown && value <= 24 {
+    let start = input;
+    let (input, _) = tag("-")(input)?;
+    let (rest, digits) = digit1(input)?;
+    let (rest, bitwidth) = integer_width(rest)?;
+    // `-0` is the integer 0, the float is only `-0.0`
+    if digits.bytes().all(|digit| digit == b'0') {
+        return Ok((
+            rest,
+            DataItem::Integer {
+                value: 0,
+                bitwidth: if bitwidth == IntegerWidth::Unknown {
                     IntegerWidth::Zero
                 } else {
                     bitwidth
                 },
             },
+        ));
+    }
+    // Down to -2^64, which is one past the range of a `u64`
+    let value = u128::from_str(digits)
+        .ok()
+        .and_then(|value| u64::try_from(value - 1).ok())
+        .ok_or(nom::Err::Failure((start, ErrorKind::TooLarge)))?;
+    Ok((
+        rest,
+        DataItem::Negative {
+            value,
+            bitwidth: if bitwidth == IntegerWidth::Unknown && value <= 23 {
+                IntegerWidth::Zero
+            } else {
+                bitwidth
+            },
+        },
+    ))
+}
+
+/// Parses exactly four hex digits, as used in `\uXXXX` escapes.
+fn hex4(input: &str) -> Option<u32> {
+    let digits = input.get(..4)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Parses the rest of a `\u` escape, either JSON style `XXXX` (with UTF-16
+/// surrogate pairs written as two escapes) or Rust style `{X...}`.
+fn unicode_escape(input: &str) -> Option<(char, &str)> {
+    if input.starts_with('{') {
+        let end = input.find('}')?;
+        let digits = &input[1..end];
+        if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        return Some((std::char::from_u32(value)?, &input[end + 1..]));
+    }
+
+    let high = hex4(input)?;
+    let rest = &input[4..];
+    match high {
+        0xd800..=0xdbff => {
+            if !rest.starts_with("\\u") {
+                return None;
+            }
+            let low = hex4(&rest[2..])?;
+            if !(0xdc00..=0xdfff).contains(&low) {
+                return None;
+            }
+            let value = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+            Some((std::char::from_u32(value)?, &rest[6..]))
+        }
+        _ => Some((std::char::from_u32(high)?, rest)),
+    }
+}
+
+/// Parses an escape sequence following a `\`, returning the escaped
+/// character and the remaining input.
+fn escape(input: &str) -> Option<(char, &str)> {
+    let mut chars = input.chars();
+    let c = match chars.next()? {
+        c @ '"' | c @ '\'' | c @ '\\' | c @ '/' => c,
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => return unicode_escape(chars.as_str()),
+        _ => return None,
+    };
+    Some((c, chars.as_str()))
+}
+
+/// Parses the content of a string literal up to and including the closing
+/// `quote`, supporting the JSON escapes plus `\u{...}`.
+///
+/// An invalid escape is a failure pointing at its `\`, rather than
+/// backtracking to try other parsers.
+fn quoted(quote: char) -> impl Fn(&str) -> IResult<&str, String> {
+    move |input: &str| {
+        let mut output = String::new();
+        let mut rest = input;
+        loop {
+            match rest.chars().next() {
+                None => return Err(nom::Err::Error((input, ErrorKind::Eof))),
+                Some(c) if c == quote => return Ok((&rest[c.len_utf8()..], output)),
+                Some('\\') => {
+                    let (c, after) =
+                        escape(&rest[1..]).ok_or(nom::Err::Failure((rest, ErrorKind::Escaped)))?;
+                    output.push(c);
+                    rest = after;
+                }
+                Some(c) => {
+                    output.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+    }
+}
+
+/// A `b64'...'` literal, in either the base64 or base64url alphabet with or
+/// without padding, RFC 8610 appendix G.3.
+///
+/// The two alphabets only differ in the characters for 62 and 63, so content
+/// mixing them is still unambiguous and is accepted too. The literal is
+/// recorded as [`Encoding::Base64`] if it uses `+` or `/` and none of `-` or
+/// `_`, otherwise as [`Encoding::Base64Url`].
+fn base64_literal(input: &str) -> IResult<&str, (Vec<u8>, Encoding)> {
+    let (rest, content) = delimited(tag("b64'"), take_while(|c| c != '\''), tag("'"))(input)?;
+    let invalid = || nom::Err::Failure((input, ErrorKind::AlphaNumeric));
+
+    let digits = content.trim_end_matches('=');
+    let padding = content.len() - digits.len();
+    if padding > 2 || (padding > 0 && content.len() % 4 != 0) {
+        return Err(invalid());
+    }
+
+    let url = digits.contains(['-', '_']);
+    let standard = digits.contains(['+', '/']);
+    let digits: String = digits
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    let data = base64::decode_config(&digits, base64::STANDARD_NO_PAD).map_err(|_| invalid())?;
+    let encoding = if standard && !url {
+        Encoding::Base64
+    } else {
+        Encoding::Base64Url
+    };
+    Ok((rest, (data, encoding)))
+}
+
+/// A `b32'...'` or `h32'...'` literal, in the base32 or base32hex alphabet
+/// respectively, ignoring case and whitespace and with or without padding,
+/// RFC 8610 appendix G.2.
+fn base32_literal(input: &str) -> IResult<&str, (Vec<u8>, Encoding)> {
+    let (rest, (prefix, content)) = pair(
+        alt((tag("b32"), tag("h32"))),
+        delimited(tag("'"), take_while(|c| c != '\''), tag("'")),
+    )(input)?;
+    let (alphabet, encoding) = match prefix {
+        "b32" => (base32::BASE32, Encoding::Base32),
+        _ => (base32::BASE32HEX, Encoding::Base32Hex),
+    };
+    let data = base32::decode(content, alphabet)
+        .ok_or(nom::Err::Failure((input, ErrorKind::AlphaNumeric)))?;
+    Ok((rest, (data, encoding)))
+}
+
+fn bytestring_literal(input: &str) -> IResult<&str, (Vec<u8>, Encoding)> {
+    alt((
+        base32_literal,
+        map_res(
+            preceded(tag("h"), delimited(tag("'"), hex_digit0, tag("'"))),
+            |s: &str| hex::decode(s).map(|data| (data, Encoding::Base16)),
         ),
-    )(input)
+        base64_literal,
+        map(preceded(tag("'"), quoted('\'')), |data| {
+            (data.into_bytes(), Encoding::Text)
+        }),
+    ))(input)
 }
 
+/// Adjacent literals are concatenated into a single byte string, RFC 8610
+/// appendix G.4.
+///
+/// An encoding indicator for the length follows the last of them.
 fn definite_bytestring(input: &str) -> IResult<&str, ByteString> {
-    wrapws(map(
-        alt((
-            map_res(
-                preceded(tag("h"), delimited(tag("'"), hex_digit0, tag("'"))),
-                hex::decode,
-            ),
-            map_res(
-                preceded(tag("b64"), delimited(tag("'"), base64url_digit0, tag("'"))),
-                |s: &str| base64::decode_config(s, base64::URL_SAFE_NO_PAD),
-            ),
-            map_res(
-                preceded(tag("b64"), delimited(tag("'"), base64_digit0, tag("'"))),
-                |s: &str| base64::decode_config(s, base64::STANDARD_NO_PAD),
-            ),
-        )),
-        |data| ByteString {
-            data,
-            bitwidth: IntegerWidth::Unknown,
-        },
-    ))(input)
+    wrapws(|input| {
+        let (indicator, ((mut data, encoding), rest)) = pair(
+            bytestring_literal,
+            many0(preceded(ws::<()>, bytestring_literal)),
+        )(input)?;
+        let (remaining, bitwidth) = integer_width(indicator)?;
+        if remaining != indicator && preceded(ws::<()>, bytestring_literal)(remaining).is_ok() {
+            return Err(nom::Err::Failure((indicator, ErrorKind::Char)));
+        }
+        // Only keep the literal form if it was consistent
+        let mut encoding = Some(encoding);
+        for (more, more_encoding) in rest {
+            data.extend(more);
+            if encoding != Some(more_encoding) {
+                encoding = None;
+            }
+        }
+        Ok((
+            remaining,
+            ByteString {
+                data,
+                bitwidth,
+                encoding,
+            },
+        ))
+    })(input)
 }
 
 fn indefinite_bytestring(input: &str) -> IResult<&str, DataItem> {
-    map(
-        delimited(
-            tag("(_"),
-            separated_list(tag(","), definite_bytestring),
-            opt_comma_tag(")"),
+    alt((
+        map(wrapws(terminated(tag("''_"), not(digit1))), |_| {
+            DataItem::IndefiniteByteString(vec![])
+        }),
+        map(
+            delimited(
+                tag("(_"),
+                separated_list(tag(","), definite_bytestring),
+                opt_comma_tag(")"),
+            ),
+            DataItem::IndefiniteByteString,
         ),
-        DataItem::IndefiniteByteString,
-    )(input)
+    ))(input)
 }
 
 fn bytestring(input: &str) -> IResult<&str, DataItem> {
     alt((
-        map(definite_bytestring, DataItem::ByteString),
         indefinite_bytestring,
+        map(definite_bytestring, DataItem::ByteString),
     ))(input)
 }
 
 fn definite_textstring(input: &str) -> IResult<&str, TextString> {
     wrapws(map(
-        delimited(
-            tag("\""),
-            opt(escaped_transform(
-                none_of("\\\""),
-                '\\',
-                alt((tag("\\"), tag("\""))),
-            )),
-            tag("\""),
-        ),
-        |data| TextString {
-            data: data.unwrap_or_default(),
-            bitwidth: IntegerWidth::Unknown,
-        },
+        pair(preceded(tag("\""), quoted('"')), integer_width),
+        |(data, bitwidth)| TextString { data, bitwidth },
     ))(input)
 }
 
 fn indefinite_textstring(input: &str) -> IResult<&str, DataItem> {
-    map(
-        delimited(
-            tag("(_"),
-            separated_list(tag(","), definite_textstring),
-            opt_comma_tag(")"),
+    alt((
+        map(wrapws(terminated(tag("\"\"_"), not(digit1))), |_| {
+            DataItem::IndefiniteTextString(vec![])
+        }),
+        map(
+            delimited(
+                tag("(_"),
+                separated_list(tag(","), definite_textstring),
+                opt_comma_tag(")"),
+            ),
+            DataItem::IndefiniteTextString,
         ),
-        DataItem::IndefiniteTextString,
-    )(input)
+    ))(input)
 }
 
 fn textstring(input: &str) -> IResult<&str, DataItem> {
     alt((
-        map(definite_textstring, DataItem::TextString),
         indefinite_textstring,
+        map(definite_textstring, DataItem::TextString),
+    ))(input)
+}
+
+/// An encoding indicator for the length of a definite length container, `_0`
+/// to `_3`. This must be followed by whitespace to distinguish it from an
+/// indefinite length container starting with an integer, like `[_1]`.
+fn container_width(input: &str) -> IResult<&str, IntegerWidth> {
+    alt((
+        invalid_container_width,
+        map(
+            terminated(preceded(char('_'), one_of("0123")), peek(multispace1)),
+            |c| match c {
+                '0' => IntegerWidth::Eight,
+                '1' => IntegerWidth::Sixteen,
+                '2' => IntegerWidth::ThirtyTwo,
+                _ => IntegerWidth::SixtyFour,
+            },
+        ),
+        value(IntegerWidth::Unknown, tag("")),
     ))(input)
 }
 
+/// Any other number after the underscore followed by whitespace, that can't
+/// be the first item of an indefinite length container as it isn't followed
+/// by a separator, like `[_7 1]`.
+fn invalid_container_width(input: &str) -> IResult<&str, IntegerWidth> {
+    let (_, (_, digits, _, _, _)) =
+        tuple((char('_'), digit1, multispace1, ws::<()>, none_of(",:]}")))(input)?;
+    if let "0" | "1" | "2" | "3" = digits {
+        return Err(nom::Err::Error((input, ErrorKind::Digit)));
+    }
+    Err(nom::Err::Failure((input, ErrorKind::Digit)))
+}
+
 fn definite_array(input: &str) -> IResult<&str, DataItem> {
     map(
-        delimited(
-            wrapws(tag("[")),
+        tuple((
+            preceded(ws::<()>, tag("[")),
+            container_width,
             separated_list(tag(","), data_item),
             opt_comma_tag("]"),
-        ),
-        |data| DataItem::Array {
+        )),
+        |(_, bitwidth, data, _)| DataItem::Array {
             data,
-            bitwidth: Some(IntegerWidth::Unknown),
+            bitwidth: Some(bitwidth),
         },
     )(input)
 }
@@ -217,14 +446,15 @@ fn array(input: &str) -> IResult<&str, DataItem> {
 
 fn definite_map(input: &str) -> IResult<&str, DataItem> {
     map(
-        delimited(
-            wrapws(tag("{")),
+        tuple((
+            preceded(ws::<()>, tag("{")),
+            container_width,
             separated_list(tag(","), separated_pair(data_item, tag(":"), data_item)),
             opt_comma_tag("}"),
-        ),
-        |data| DataItem::Map {
+        )),
+        |(_, bitwidth, data, _)| DataItem::Map {
             data,
-            bitwidth: Some(IntegerWidth::Unknown),
+            bitwidth: Some(bitwidth),
         },
     )(input)
 }
@@ -247,6 +477,56 @@ fn data_map(input: &str) -> IResult<&str, DataItem> {
     alt((definite_map, indefinite_map))(input)
 }
 
+/// The application-oriented literals lowered while parsing, by prefix.
+///
+/// Any other prefix is kept as a [`DataItem::AppLiteral`] to be handled as
+/// configured in [`ParseOptions`]. The `h`, `b64`, `b32` and `h32` prefixes
+/// are byte string literals and never parsed as application-oriented
+/// literals.
+const APP_LITERALS: &[(&str, AppLiteralLowering)] = &[("d", date)];
+
+type AppLiteralLowering = fn(String) -> Option<DataItem>;
+
+/// A `d'YYYY-MM-DD'` literal for a tag 1004 date, from the EDN application
+/// extensions draft.
+fn date(data: String) -> Option<DataItem> {
+    super::parse_full_date(&data)?;
+    Some(DataItem::Tag {
+        tag: Tag::DATE,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(DataItem::TextString(TextString {
+            data,
+            bitwidth: IntegerWidth::Unknown,
+        })),
+    })
+}
+
+fn app_literal(input: &str) -> IResult<&str, DataItem> {
+    let (rest, (prefix, content)) = pair(
+        verify(
+            take_while1(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit()),
+            |prefix: &str| prefix.starts_with(|c: char| c.is_ascii_lowercase()),
+        ),
+        preceded(char('\''), quoted('\'')),
+    )(input)?;
+    if let "h" | "b64" | "b32" | "h32" = prefix {
+        return Err(nom::Err::Error((input, ErrorKind::Verify)));
+    }
+    match APP_LITERALS.iter().find(|(known, _)| *known == prefix) {
+        Some((_, lower)) => match lower(content) {
+            Some(item) => Ok((rest, item)),
+            None => Err(nom::Err::Error((input, ErrorKind::Verify))),
+        },
+        None => Ok((
+            rest,
+            DataItem::AppLiteral {
+                prefix: prefix.to_owned(),
+                content,
+            },
+        )),
+    }
+}
+
 fn tagged(input: &str) -> IResult<&str, DataItem> {
     let (input, (tag_, bitwidth)) = integer(input)?;
     let (input, value) = delimited(tag("("), data_item, tag(")"))(input)?;
@@ -286,23 +566,106 @@ fn float_value(input: &str) -> IResult<&str, f64> {
 }
 
 fn float(input: &str) -> IResult<&str, DataItem> {
-    let (input, value) = float_value(input)?;
-    let (input, encoding) = opt(verify(encoding, |&e| e > 0))(input)?;
+    let start = input;
+    let (input, text) = recognize(float_value)(input)?;
+    let (_, value) = float_value(text)?;
+    let indicator = input;
+    let (input, encoding) = opt(encoding)(input)?;
+    let bitwidth = match encoding {
+        Some(0) => return Err(nom::Err::Failure((indicator, ErrorKind::OneOf))),
+        Some(1) => FloatWidth::Sixteen,
+        Some(2) => FloatWidth::ThirtyTwo,
+        Some(3) => FloatWidth::SixtyFour,
+        Some(_) => unreachable!(),
+        None => FloatWidth::Unknown,
+    };
+    // Round to the nearest value representable in the encoded width, going
+    // directly from the text to avoid double rounding through an `f64`.
+    let narrow = || f32::from_str(text).unwrap_or(value as f32);
+    let narrowed = match bitwidth {
+        FloatWidth::Sixteen => f16::from_f32(narrow()).to_f64(),
+        FloatWidth::ThirtyTwo => f64::from(narrow()),
+        FloatWidth::SixtyFour | FloatWidth::Unknown => value,
+    };
+    // Rounding is expected, but values outside the range of the width would
+    // silently change to infinity or zero
+    if (value.is_finite() && narrowed.is_infinite()) || (value != 0.0 && narrowed == 0.0) {
+        return Err(nom::Err::Failure((start, ErrorKind::Float)));
+    }
     Ok((
         input,
         DataItem::Float {
-            value,
-            bitwidth: match encoding {
-                Some(1) => FloatWidth::Sixteen,
-                Some(2) => FloatWidth::ThirtyTwo,
-                Some(3) => FloatWidth::SixtyFour,
-                Some(_) => unreachable!(),
-                None => FloatWidth::Unknown,
-            },
+            value: narrowed,
+            bitwidth,
         },
     ))
 }
 
+/// Check whether a float literal with a 16 or 32-bit encoding indicator
+/// changes value when narrowed, returning a description of the change.
+///
+/// Literals that are the shortest text identifying the narrowed value (as
+/// produced by [`DataItem::to_diag`]) count as exact, e.g. `0.1_2` is fine
+/// but `0.1_1` is not.
+fn lossy_float(word: &str) -> Option<String> {
+    let (indicator, text) = recognize(float_value)(word).ok()?;
+    let value = f64::from_str(text).ok().filter(|value| value.is_finite())?;
+    let narrow = f32::from_str(text).ok()?;
+    let (bits, narrowed, shortest) = match indicator {
+        "_1" => {
+            let narrow = f16::from_f32(narrow);
+            (16, narrow.to_f64(), narrow.to_string())
+        }
+        "_2" => (32, f64::from(narrow), narrow.to_string()),
+        _ => return None,
+    };
+    if f64::from_str(&shortest) == Ok(value) {
+        return None;
+    }
+    Some(format!(
+        "{} is not exactly representable as a {}-bit float; nearest is {}",
+        text, bits, narrowed
+    ))
+}
+
+/// Find the float literals in diagnostic notation that has already been
+/// parsed successfully which change value when narrowed to the width of
+/// their encoding indicator.
+fn lossy_floats(text: &str) -> Vec<ParseWarning> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || "+-._".contains(c);
+    let mut warnings = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '/' => {
+                chars.by_ref().find(|&(_, c)| c == '/');
+            }
+            '"' | '\'' => {
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            c if is_word(c) => {
+                let mut end = offset + 1;
+                while let Some((index, _)) = chars.next_if(|&(_, c)| is_word(c)) {
+                    end = index + 1;
+                }
+                if let Some(message) = lossy_float(&text[offset..end]) {
+                    warnings.push(ParseWarning::new(WarningKind::LossyFloat, offset, message));
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
 fn simple(input: &str) -> IResult<&str, DataItem> {
     map(
         alt((
@@ -310,12 +673,15 @@ fn simple(input: &str) -> IResult<&str, DataItem> {
             value(Simple::TRUE, tag("true")),
             value(Simple::NULL, tag("null")),
             value(Simple::UNDEFINED, tag("undefined")),
-            map(
-                preceded(
-                    tag("simple"),
-                    map_res(delimited(tag("("), digit1, tag(")")), u8::from_str),
+            preceded(
+                tag("simple"),
+                verify(
+                    map(
+                        map_res(delimited(tag("("), digit1, tag(")")), u8::from_str),
+                        Simple,
+                    ),
+                    |simple| !simple.is_reserved(),
                 ),
-                Simple,
             ),
         )),
         DataItem::Simple,
@@ -325,9 +691,10 @@ fn simple(input: &str) -> IResult<&str, DataItem> {
 fn data_item(input: &str) -> IResult<&str, DataItem> {
     context(
         "data item",
-        wrapws(alt((
+        wrapws(no_indicator(alt((
             context("float", float),
             context("tagged", tagged),
+            context("app literal", app_literal),
             context("positive", positive),
             context("negative", negative),
             context("bytestring", bytestring),
@@ -335,7 +702,7 @@ fn data_item(input: &str) -> IResult<&str, DataItem> {
             context("array", array),
             context("map", data_map),
             context("simple", simple),
-        ))),
+        )))),
     )(input)
 }
 
@@ -374,10 +741,238 @@ fn data_item(input: &str) -> IResult<&str, DataItem> {
 ///     });
 /// ```
 pub fn parse_diag(text: impl AsRef<str>) -> Result<DataItem> {
-    let (remaining, parsed) =
-        data_item(text.as_ref()).map_err(|e| format!("Parsing error ({:?})", e))?;
-    if !remaining.is_empty() {
-        return Err(format!("Remaining text ({:?})", remaining).into());
+    parse_diag_with(text, &ParseOptions::default())
+}
+
+/// Like [`parse_diag`], but with configurable parsing.
+///
+/// Only the options for application-oriented literals,
+/// [`ParseOptions::allow_lossy_float_narrowing`] and
+/// [`ParseOptions::strict_tag_content`] apply to diagnostic notation. Any
+/// warnings are discarded, use [`parse_diag_report`] to see them.
+pub fn parse_diag_with(text: impl AsRef<str>, options: &ParseOptions) -> Result<DataItem> {
+    parse_diag_with_warnings(text, options).map(|(item, _)| item)
+}
+
+/// Like [`parse_diag_with`], but also returns warnings about float literals
+/// that changed value when narrowed to the width of their encoding
+/// indicator, if [`ParseOptions::allow_lossy_float_narrowing`] is set, and
+/// about well-known tags with content of the wrong type.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_diag_with_warnings, ParseOptions};
+///
+/// let options = ParseOptions {
+///     allow_lossy_float_narrowing: true,
+///     ..ParseOptions::default()
+/// };
+/// let (item, warnings) = parse_diag_with_warnings("[0.1_1]", &options).unwrap();
+///
+/// assert_eq!(item.to_diag(), "[0.099975586_1]");
+/// assert_eq!(warnings[0].offset, 1);
+/// assert_eq!(
+///     warnings[0].message,
+///     "0.1 is not exactly representable as a 16-bit float; nearest is 0.0999755859375",
+/// );
+/// ```
+pub fn parse_diag_with_warnings(
+    text: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<(DataItem, Vec<ParseWarning>)> {
+    let text = text.as_ref();
+    let (parsed, len, warnings) = parse_prefix(text, options)?;
+    if len < text.len() {
+        return Err(format!("Remaining text ({:?})", &text[len..]).into());
+    }
+    Ok((parsed, warnings))
+}
+
+/// Like [`parse_diag_with`], but also returns the problems found in the data
+/// item that didn't stop parsing: lossy float literals (see
+/// [`ParseOptions::allow_lossy_float_narrowing`]), well-known tags with
+/// content of the wrong type (see [`ParseOptions::strict_tag_content`]) and
+/// duplicate map keys.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_diag_report, ParseOptions};
+///
+/// let report = parse_diag_report("[1, 0(5)]", &ParseOptions::default()).unwrap();
+///
+/// assert_eq!(report.warnings[0].offset, 4);
+/// assert_eq!(
+///     report.warnings[0].message,
+///     "tag 0 (date/time) expects a text string, found an unsigned integer",
+/// );
+///
+/// let options = ParseOptions {
+///     strict_tag_content: true,
+///     ..ParseOptions::default()
+/// };
+/// assert!(parse_diag_report("[1, 0(5)]", &options).is_err());
+/// ```
+pub fn parse_diag_report(text: impl AsRef<str>, options: &ParseOptions) -> Result<ParseReport> {
+    let text = text.as_ref();
+    let (item, mut warnings) = parse_diag_with_warnings(text, options)?;
+    let offsets = super::comments::item_offsets(text);
+    warnings.extend(super::duplicate_keys(&item, |path| {
+        offsets
+            .iter()
+            .find(|(item, _)| item == path)
+            .map_or(0, |(_, offset)| *offset)
+    }));
+    warnings.sort_by_key(|warning| warning.offset);
+    Ok(ParseReport { item, warnings })
+}
+
+/// Parse the data item at the start of `text`, returning it along with the
+/// length of it and any whitespace and comments following it.
+fn parse_prefix(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<(DataItem, usize, Vec<ParseWarning>)> {
+    let (remaining, mut parsed) = data_item(text).map_err(|e| match e {
+        nom::Err::Failure((rest, ErrorKind::Escaped)) => format!(
+            "Parsing error at offset {}: invalid escape sequence",
+            text.len() - rest.len()
+        ),
+        nom::Err::Failure((rest, ErrorKind::TooLarge)) => format!(
+            "Parsing error at offset {}: integer out of range, larger values need a bignum tag",
+            text.len() - rest.len()
+        ),
+        nom::Err::Failure((rest, ErrorKind::AlphaNumeric)) => {
+            let prefix = rest.split('\'').next().unwrap_or_default();
+            let name = match prefix {
+                "b32" => "base32",
+                "h32" => "base32hex",
+                _ => "base64",
+            };
+            format!(
+                "Parsing error at offset {}: invalid {} in {}'' literal",
+                text.len() - rest.len(),
+                name,
+                prefix
+            )
+        }
+        nom::Err::Failure((rest, ErrorKind::Digit)) => format!(
+            "Parsing error at offset {}: encoding indicator must be _0 to _3",
+            text.len() - rest.len()
+        ),
+        nom::Err::Failure((rest, ErrorKind::OneOf)) => format!(
+            "Parsing error at offset {}: encoding indicator on a float must be _1 to _3",
+            text.len() - rest.len()
+        ),
+        nom::Err::Failure((rest, ErrorKind::Char)) => format!(
+            "Parsing error at offset {}: encoding indicator not allowed on this item",
+            text.len() - rest.len()
+        ),
+        nom::Err::Failure((rest, ErrorKind::Float)) => format!(
+            "Parsing error at offset {}: float out of range for its encoding indicator",
+            text.len() - rest.len()
+        ),
+        e => format!("Parsing error ({:?})", e),
+    })?;
+    let len = text.len() - remaining.len();
+    let mut warnings = lossy_floats(&text[..len]);
+    if let Some(warning) = warnings.first() {
+        if !options.allow_lossy_float_narrowing {
+            return Err(format!("Parsing error {}", warning).into());
+        }
+        let offsets = super::comments::item_offsets(&text[..len]);
+        for warning in &mut warnings {
+            if let Some((path, _)) = offsets.iter().find(|(_, offset)| *offset == warning.offset) {
+                warning.path = path.clone();
+            }
+        }
+    }
+    options.lower_app_literals(&mut parsed)?;
+    warnings.extend(options.check_tag_content(&parsed, |path| {
+        super::comments::item_offsets(&text[..len])
+            .into_iter()
+            .find(|(item, _)| item == path)
+            .map_or(0, |(_, offset)| offset)
+    })?);
+    warnings.sort_by_key(|warning| warning.offset);
+    Ok((parsed, len, warnings))
+}
+
+/// Parse the first of a sequence of whitespace or comment separated data
+/// items in diagnostic notation, returning it along with the length of text
+/// used by it and any following whitespace and comments, or [`None`] if there
+/// are no more items.
+///
+/// Unlike [`parse_bytes_partial`](crate::parse_bytes_partial) an item cut off
+/// by the end of the text is an error, as diagnostic notation can't be read
+/// incrementally.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::parse_diag_partial;
+///
+/// let text = "[1, 2] / second / {3: 4}\n";
+/// let (first, len) = parse_diag_partial(text).unwrap().unwrap();
+/// assert_eq!(first.to_diag(), "[1,2]");
+/// let (second, rest) = parse_diag_partial(&text[len..]).unwrap().unwrap();
+/// assert_eq!(second.to_diag(), "{3:4}");
+/// assert_eq!(parse_diag_partial(&text[len + rest..]).unwrap(), None);
+/// ```
+pub fn parse_diag_partial(text: impl AsRef<str>) -> Result<Option<(DataItem, usize)>> {
+    parse_diag_partial_with(text, &ParseOptions::default())
+}
+
+/// Like [`parse_diag_partial`], but with configurable parsing.
+pub fn parse_diag_partial_with(
+    text: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<Option<(DataItem, usize)>> {
+    let text = text.as_ref();
+    match ws::<()>(text) {
+        Ok(("", ())) => Ok(None),
+        _ => {
+            let (item, len, _) = parse_prefix(text, options)?;
+            Ok(Some((item, len)))
+        }
+    }
+}
+
+/// Like [`parse_diag`], but also returns the `/ comments /` from the text,
+/// attached to the closest item.
+///
+/// These can be shown again with
+/// [`DataItem::to_diag_pretty_with_comments`], so that diagnostic notation
+/// maintained by hand keeps its comments when it is reformatted.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_diag_with_comments, CommentPosition};
+///
+/// let (item, comments) = parse_diag_with_comments(r#"
+///     / a greeting / ["hello", / count / 5]
+/// "#).unwrap();
+///
+/// assert_eq!(comments.get(&[], CommentPosition::Before), ["a greeting"]);
+/// assert_eq!(comments.get(&[1], CommentPosition::Before), ["count"]);
+/// assert_eq!(
+///     item.to_diag_pretty_with_comments(&comments),
+///     "/ a greeting / [\n    \"hello\",\n    / count / 5,\n]",
+/// );
+/// ```
+pub fn parse_diag_with_comments(text: impl AsRef<str>) -> Result<(DataItem, Comments)> {
+    let text = text.as_ref();
+    let item = parse_diag(text)?;
+    Ok((item, super::comments::collect_comments(text)))
+}
+
+/// Parses diagnostic notation, see [`parse_diag`].
+impl FromStr for DataItem {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_diag(s)
     }
-    Ok(parsed)
 }
@@ -1,24 +1,353 @@
 use crate::{DataItem, Result};
 
 mod binary;
+mod comments;
 mod diag;
+mod reader;
+mod stream;
+mod tags;
+mod well_formed;
 
-pub use self::binary::{parse_bytes, parse_bytes_partial};
-pub use self::diag::parse_diag;
+#[cfg(feature = "serde")]
+pub(crate) use self::binary::parse_bytes_with_spans_with;
+pub use self::binary::{
+    parse_bytes, parse_bytes_lenient, parse_bytes_partial, parse_bytes_partial_with,
+    parse_bytes_preserving, parse_bytes_preserving_with, parse_bytes_report, parse_bytes_with,
+    parse_bytes_with_spans, ParseWarning, WarningKind,
+};
+pub use self::diag::{
+    parse_diag, parse_diag_partial, parse_diag_partial_with, parse_diag_report, parse_diag_with,
+    parse_diag_with_comments, parse_diag_with_warnings,
+};
+pub(crate) use self::reader::Header;
+pub use self::reader::{Event, Reader};
+pub(crate) use self::stream::StreamReader;
+pub use self::well_formed::{check_well_formed, check_well_formed_seq, WellFormedness};
 
-fn remove_comments(hex: impl AsRef<str>) -> String {
-    hex.as_ref()
-        .lines()
-        .map(|line| line.split('#').next().unwrap().replace(" ", ""))
+/// Options controlling how data is parsed, see [`parse_bytes_with`] and
+/// [`parse_diag_with`].
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Whether to accept text strings containing invalid UTF-8.
+    ///
+    /// If `true` these are parsed to [`DataItem::InvalidTextString`] or
+    /// [`DataItem::InvalidIndefiniteTextString`] keeping the raw bytes, so
+    /// malformed data can still be inspected. If `false` (the default) they
+    /// cause an error.
+    pub allow_invalid_utf8: bool,
+
+    /// A function to lower application-oriented literals in diagnostic
+    /// notation that aren't supported by this crate.
+    ///
+    /// This is called with the prefix and content of literals like
+    /// `foo'content'` (with any escapes in the content processed), returning
+    /// `None` if it doesn't support them either.
+    pub app_literal_handler: Option<fn(&str, &str) -> Option<DataItem>>,
+
+    /// Whether to keep unsupported application-oriented literals in
+    /// diagnostic notation as [`DataItem::AppLiteral`].
+    ///
+    /// If `false` (the default) any literals not lowered by this crate or the
+    /// [`ParseOptions::app_literal_handler`] cause an error.
+    pub allow_unknown_app_literals: bool,
+
+    /// The largest number of bytes to accept for a string, or to allocate
+    /// up-front for an array or map, based on the length declared in binary
+    /// input.
+    ///
+    /// Declared lengths are always checked against the remaining input (each
+    /// array or map item needs at least one byte), failing with
+    /// [`Error::LengthOutOfRange`](crate::Error::LengthOutOfRange) if they
+    /// exceed it, so if [`None`] (the default) this is effectively the input
+    /// length. Set it to reject large strings or containers earlier, such as
+    /// when parsing a stream with [`parse_bytes_partial_with`].
+    pub max_allocation: Option<usize>,
+
+    /// The largest number of chunks to accept in an indefinite length string
+    /// in binary input (default 1,000,000).
+    ///
+    /// Each chunk needs as little as one byte of input but is kept
+    /// separately, so without a limit a small input can take a lot of memory
+    /// to parse and to show. Exceeding it fails with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+    pub max_chunks: usize,
+
+    /// The largest number of items to accept in an array, or entries in a
+    /// map, in binary input (default 1,000,000).
+    ///
+    /// Like [`ParseOptions::max_chunks`] this guards against indefinite
+    /// length containers of many small items, and applies to each container
+    /// separately.
+    pub max_items: usize,
+
+    /// Whether to accept hex formatted as a C or Rust array, such as `{ 0xA1,
+    /// 0x01, 0x02 }` or `[0xa1, 0x01, 0x02]`, in [`parse_hex_with`].
+    ///
+    /// If `true`, bytes may be written with a `0x` or `0X` prefix and one or
+    /// two digits, commas, braces and brackets are ignored, and `//` starts a
+    /// comment as well as `#`. This is off by default as the input can then
+    /// also be valid diagnostic notation, e.g. `[12, 34]` is either the bytes
+    /// `1234` or an array of two integers.
+    pub allow_hex_arrays: bool,
+
+    /// Whether to accept float literals in diagnostic notation that change
+    /// value when narrowed to the width of their `_1` or `_2` encoding
+    /// indicator, such as `0.1_1`.
+    ///
+    /// A literal is exact if it is the shortest text identifying the value at
+    /// its width, as output by [`DataItem::to_diag`]. If `false` (the
+    /// default) any other literal causes an error, if `true` it is rounded to
+    /// the nearest value with a warning returned by
+    /// [`parse_diag_with_warnings`].
+    pub allow_lossy_float_narrowing: bool,
+
+    /// Whether to reject well-known tags from RFC 8949 §3.4 with content of
+    /// the wrong type, such as `0(5)` where tag 0 requires a text string.
+    ///
+    /// If `false` (the default) these are accepted, with a warning returned
+    /// by [`parse_bytes_report`] and [`parse_diag_report`]. If `true` the
+    /// first causes an error giving its offset.
+    pub strict_tag_content: bool,
+
+    /// Whether a break (`0xff`) directly after the data item in binary
+    /// input ends the data, for framed protocols that mark the end of an
+    /// item or sequence with it.
+    ///
+    /// If `true` the break is consumed, anything after it is still trailing
+    /// data. A break where a data item is required, such as within a
+    /// definite length array, is an error either way. This doesn't change
+    /// [`parse_bytes_partial_with`], which always starts at a data item, so
+    /// when reading a sequence check for a break before each call.
+    pub stop_at_stray_break: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            allow_invalid_utf8: false,
+            app_literal_handler: None,
+            allow_unknown_app_literals: false,
+            max_allocation: None,
+            max_chunks: 1_000_000,
+            max_items: 1_000_000,
+            allow_hex_arrays: false,
+            allow_lossy_float_narrowing: false,
+            strict_tag_content: false,
+            stop_at_stray_break: false,
+        }
+    }
+}
+
+/// A parsed data item along with the problems found in it that didn't stop
+/// parsing, see [`parse_bytes_report`], [`parse_hex_report`] and
+/// [`parse_diag_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseReport {
+    /// The parsed data item.
+    pub item: DataItem,
+    /// The problems found, in the order they appear in the input.
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseOptions {
+    /// Validate an item produced by the binary parser against these options.
+    fn check(&self, item: &DataItem) -> Result<()> {
+        match item {
+            DataItem::InvalidTextString(_) | DataItem::InvalidIndefiniteTextString(_)
+                if !self.allow_invalid_utf8 =>
+            {
+                Err("Text string contains invalid UTF-8".into())
+            }
+            DataItem::Array { data, .. } => data.iter().try_for_each(|item| self.check(item)),
+            DataItem::Map { data, .. } => data.iter().try_for_each(|(key, value)| {
+                self.check(key)?;
+                self.check(value)
+            }),
+            DataItem::Tag { value, .. } => self.check(value),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the content of the well-known tags in `item`, returning a
+    /// warning for each problem found with `offset` giving the start of the
+    /// tag at a path, or an error for the first if
+    /// [`ParseOptions::strict_tag_content`] is set.
+    fn check_tag_content(
+        &self,
+        item: &DataItem,
+        offset: impl Fn(&[usize]) -> usize,
+    ) -> Result<Vec<ParseWarning>> {
+        let warnings: Vec<_> = tags::tag_content_problems(item)
+            .into_iter()
+            .map(|(path, message)| ParseWarning {
+                offset: offset(&path),
+                path,
+                ..ParseWarning::new(WarningKind::TagContent, 0, message)
+            })
+            .collect();
+        match warnings.first() {
+            Some(warning) if self.strict_tag_content => {
+                Err(format!("Invalid tag content {}", warning).into())
+            }
+            _ => Ok(warnings),
+        }
+    }
+
+    /// Lower any application-oriented literals left by the diagnostic
+    /// notation parser.
+    fn lower_app_literals(&self, item: &mut DataItem) -> Result<()> {
+        match item {
+            DataItem::AppLiteral { prefix, content } => {
+                match self
+                    .app_literal_handler
+                    .and_then(|lower| lower(prefix, content))
+                {
+                    Some(lowered) => *item = lowered,
+                    None if self.allow_unknown_app_literals => {}
+                    None => {
+                        return Err(format!(
+                            "Unknown application-oriented literal prefix {:?}",
+                            prefix
+                        )
+                        .into())
+                    }
+                }
+                Ok(())
+            }
+            DataItem::Array { data, .. } => data
+                .iter_mut()
+                .try_for_each(|item| self.lower_app_literals(item)),
+            DataItem::Map { data, .. } => data.iter_mut().try_for_each(|(key, value)| {
+                self.lower_app_literals(key)?;
+                self.lower_app_literals(value)
+            }),
+            DataItem::Tag { value, .. } => self.lower_app_literals(value),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Find the map keys in `item` equal to an earlier key in the same map,
+/// returning a warning for each with `offset` giving the start of the item at
+/// a path.
+fn duplicate_keys(item: &DataItem, offset: impl Fn(&[usize]) -> usize) -> Vec<ParseWarning> {
+    fn find(item: &DataItem, path: &mut Vec<usize>, found: &mut Vec<(Vec<usize>, String)>) {
+        fn visit(
+            index: &[usize],
+            item: &DataItem,
+            path: &mut Vec<usize>,
+            found: &mut Vec<(Vec<usize>, String)>,
+        ) {
+            let len = path.len();
+            path.extend(index);
+            find(item, path, found);
+            path.truncate(len);
+        }
+
+        match item {
+            DataItem::Array { data, .. } => {
+                for (index, item) in data.iter().enumerate() {
+                    visit(&[index], item, path, found);
+                }
+            }
+            DataItem::Map { data, .. } => {
+                let mut seen = std::collections::HashSet::new();
+                for (index, (key, value)) in data.iter().enumerate() {
+                    if !seen.insert(key.canonical_key()) {
+                        let message = format!("duplicate map key {}", key.to_diag());
+                        found.push(([&path[..], &[index, 0]].concat(), message));
+                    }
+                    visit(&[index, 0], key, path, found);
+                    visit(&[index, 1], value, path, found);
+                }
+            }
+            DataItem::Tag { value, .. } => visit(&[0], value, path, found),
+            _ => {}
+        }
+    }
+
+    let mut found = Vec::new();
+    find(item, &mut Vec::new(), &mut found);
+    found
+        .into_iter()
+        .map(|(path, message)| ParseWarning {
+            offset: offset(&path),
+            path,
+            ..ParseWarning::new(WarningKind::DuplicateKey, 0, message)
+        })
         .collect()
 }
 
+/// Parse an RFC 3339 `full-date`, strictly `YYYY-MM-DD`.
+pub(crate) fn parse_full_date(data: &str) -> Option<chrono::NaiveDate> {
+    let bytes = data.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes[range].iter().all(u8::is_ascii_digit);
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    if !digits(0..4) || !digits(5..7) || !digits(8..10) {
+        return None;
+    }
+    chrono::NaiveDate::from_ymd_opt(
+        data[0..4].parse().ok()?,
+        data[5..7].parse().ok()?,
+        data[8..10].parse().ok()?,
+    )
+}
+
+/// Strip comments and whitespace from hex, such as the annotated output of
+/// [`DataItem::to_hex`], leaving only the hex digits.
+///
+/// With `arrays` the formatting of C and Rust arrays is also removed, see
+/// [`ParseOptions::allow_hex_arrays`].
+fn remove_comments(hex: impl AsRef<str>, arrays: bool) -> Result<String> {
+    let mut digits = String::new();
+    for (line_index, line) in hex.as_ref().lines().enumerate() {
+        let mut line = line.split('#').next().unwrap();
+        if arrays {
+            line = line.split("//").next().unwrap();
+        }
+        let mut chars = line.chars().enumerate().peekable();
+        while let Some((column, c)) = chars.next() {
+            let error = |message: &str| {
+                Err(format!(
+                    "{} in hex at line {}, column {}",
+                    message,
+                    line_index + 1,
+                    column + 1
+                )
+                .into())
+            };
+            match c {
+                '0' if arrays && matches!(chars.peek(), Some((_, 'x')) | Some((_, 'X'))) => {
+                    chars.next();
+                    let mut byte = String::new();
+                    while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                        byte.push(c);
+                    }
+                    match byte.len() {
+                        1 => digits.push('0'),
+                        2 => {}
+                        _ => return error(&format!("Invalid byte \"0x{}\"", byte)),
+                    }
+                    digits.push_str(&byte);
+                }
+                '0'..='9' | 'a'..='f' | 'A'..='F' => digits.push(c),
+                ' ' | '\t' | '\r' => {}
+                ',' | '{' | '}' | '[' | ']' if arrays => {}
+                c => return error(&format!("Invalid character {:?}", c)),
+            }
+        }
+    }
+    Ok(digits)
+}
+
 /// Parse a string containing a hex encoded CBOR data item.
 ///
 /// The provided string may contain comments, where a comment is started with a
-/// `#` character and proceeds until the end of the line. Any spaces in the
-/// string will also be ignored, but any other non-hex characters will cause an
-/// error.
+/// `#` character and proceeds until the end of the line. Any whitespace in the
+/// string will also be ignored, so the output of [`DataItem::to_hex`] can be
+/// parsed again, but any other non-hex characters will cause an error.
 ///
 /// # Examples
 ///
@@ -41,7 +370,45 @@ fn remove_comments(hex: impl AsRef<str>) -> String {
 ///     });
 /// ```
 pub fn parse_hex(hex: impl AsRef<str>) -> Result<DataItem> {
-    let hex = remove_comments(hex);
+    parse_hex_with(hex, &ParseOptions::default())
+}
+
+/// Like [`parse_hex`], but with configurable parsing.
+pub fn parse_hex_with(hex: impl AsRef<str>, options: &ParseOptions) -> Result<DataItem> {
+    let hex = remove_comments(hex, options.allow_hex_arrays)?;
+    let bytes = hex::decode(hex)?;
+    parse_bytes_with(bytes, options)
+}
+
+/// Like [`parse_bytes_report`], but for a hex encoded data item as accepted by
+/// [`parse_hex_with`].
+///
+/// The offsets of the warnings are in the decoded binary.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{parse_hex_report, ParseOptions, WarningKind};
+///
+/// let report = parse_hex_report("a2 01 02 01 03", &ParseOptions::default()).unwrap();
+///
+/// assert_eq!(report.warnings[0].kind, WarningKind::DuplicateKey);
+/// assert_eq!(report.warnings[0].offset, 3);
+/// assert_eq!(report.warnings[0].path, [1, 0]);
+/// assert_eq!(report.warnings[0].message, "duplicate map key 1");
+/// ```
+pub fn parse_hex_report(hex: impl AsRef<str>, options: &ParseOptions) -> Result<ParseReport> {
+    let hex = remove_comments(hex, options.allow_hex_arrays)?;
+    let bytes = hex::decode(hex)?;
+    parse_bytes_report(bytes, options)
+}
+
+/// Like [`parse_bytes_lenient`], but for a hex encoded data item as accepted by
+/// [`parse_hex`].
+///
+/// Only the CBOR is parsed leniently, invalid hex is still an error.
+pub fn parse_hex_lenient(hex: impl AsRef<str>) -> Result<(Option<DataItem>, Vec<ParseWarning>)> {
+    let hex = remove_comments(hex, false)?;
     let bytes = hex::decode(hex)?;
-    parse_bytes(bytes)
+    Ok(parse_bytes_lenient(bytes))
 }
@@ -0,0 +1,645 @@
+use std::{convert::TryFrom, ops::Range};
+
+use half::f16;
+
+use super::{ParseWarning, WarningKind};
+use crate::{Error, FloatWidth, IntegerWidth, Result, Simple, Tag};
+
+/// A single step through binary encoded CBOR, as read by a [`Reader`].
+///
+/// Each event is one header from the encoding, along with the content of
+/// definite length strings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event<'a> {
+    /// An unsigned integer.
+    Integer { value: u64, bitwidth: IntegerWidth },
+
+    /// A negative integer, `value` is the encoded argument so the integer is
+    /// `-1 - value`.
+    Negative { value: u64, bitwidth: IntegerWidth },
+
+    /// A definite length byte string, or a chunk of an indefinite length byte
+    /// string.
+    ByteString {
+        data: &'a [u8],
+        bitwidth: IntegerWidth,
+    },
+
+    /// A definite length text string, or a chunk of an indefinite length text
+    /// string.
+    ///
+    /// The content is not checked to be valid UTF-8, a chunk may even end
+    /// part way through a character.
+    TextString {
+        data: &'a [u8],
+        bitwidth: IntegerWidth,
+    },
+
+    /// The start of an indefinite length byte string, followed by its chunks
+    /// as [`Event::ByteString`] and then [`Event::EndIndefinite`].
+    BeginByteString,
+
+    /// The start of an indefinite length text string, followed by its chunks
+    /// as [`Event::TextString`] and then [`Event::EndIndefinite`].
+    BeginTextString,
+
+    /// The start of an array, followed by the events of `len` items.
+    ///
+    /// Indefinite length arrays have no `len` or `bitwidth`, and are ended by
+    /// [`Event::EndIndefinite`].
+    BeginArray {
+        len: Option<u64>,
+        bitwidth: Option<IntegerWidth>,
+    },
+
+    /// The start of a map, followed by the events of `len` entries, each a
+    /// key then a value.
+    ///
+    /// Indefinite length maps have no `len` or `bitwidth`, and are ended by
+    /// [`Event::EndIndefinite`].
+    BeginMap {
+        len: Option<u64>,
+        bitwidth: Option<IntegerWidth>,
+    },
+
+    /// A tag, followed by the events of the item it applies to.
+    Tag { tag: Tag, bitwidth: IntegerWidth },
+
+    /// A floating point number.
+    Float { value: f64, bitwidth: FloatWidth },
+
+    /// A simple value, such as `true` or `null`.
+    Simple(Simple),
+
+    /// The break ending an indefinite length string, array or map.
+    EndIndefinite,
+}
+
+/// An item the reader is part way through.
+#[derive(Debug, Copy, Clone)]
+enum Frame {
+    /// A definite length array or map, with the number of items left to
+    /// read, counting the keys and values of maps separately
+    Definite { remaining: u128 },
+    /// An indefinite length array (major type 4) or map (5), with the number
+    /// of items read so far, counting the keys and values of maps separately
+    Indefinite { major: u8, items: u64 },
+    /// An indefinite length byte (major type 2) or text (3) string, with the
+    /// number of chunks read so far
+    Chunked { major: u8, chunks: u64 },
+    /// A tag that is missing its value
+    Tag,
+}
+
+/// Why the reader could not read the next event.
+pub(super) enum Malformed<'a> {
+    /// The input ended part way through a data item, for a string that was
+    /// cut short this has its declared length and an event with the content
+    /// that was present
+    Incomplete {
+        message: String,
+        truncated: Option<(u64, Event<'a>)>,
+    },
+    /// The input is not well-formed CBOR
+    Invalid { offset: usize, message: String },
+}
+
+impl Malformed<'_> {
+    /// The error for this problem, in input ending at `end`.
+    pub(super) fn into_error(self, end: usize) -> Error {
+        let warning = match self {
+            Malformed::Incomplete { message, .. } => {
+                ParseWarning::new(WarningKind::Malformed, end, message)
+            }
+            Malformed::Invalid { offset, message } => {
+                ParseWarning::new(WarningKind::Malformed, offset, message)
+            }
+        };
+        Error::from(format!("Parsing error {}", warning))
+    }
+}
+
+/// An event as read by [`Reader::read_header`], which leaves the content of
+/// definite length strings to be read separately.
+pub(crate) enum Header<'a> {
+    Event(Event<'a>),
+    /// The header of a definite length string, or chunk, of major type
+    /// `major`
+    String {
+        major: u8,
+        len: u64,
+        bitwidth: IntegerWidth,
+    },
+}
+
+pub(super) fn string_kind(major: u8) -> &'static str {
+    if major == 2 {
+        "byte string"
+    } else {
+        "text string"
+    }
+}
+
+fn string_event(major: u8, data: &[u8], bitwidth: IntegerWidth) -> Event<'_> {
+    if major == 2 {
+        Event::ByteString { data, bitwidth }
+    } else {
+        Event::TextString { data, bitwidth }
+    }
+}
+
+/// A pull parser reading binary encoded CBOR as a stream of [`Event`]s,
+/// without building [`DataItem`](crate::DataItem)s.
+///
+/// This iterates over the events of each data item in the input along with
+/// the offset each starts at, so a [CBOR sequence][RFC 8742] can be read by
+/// continuing past the end of the first item. Each event is checked to be
+/// well-formed in its position: any problem, including the input ending part
+/// way through an item, is returned as an error and ends the iteration.
+/// Text strings are not checked to be valid UTF-8.
+///
+/// [`parse_bytes`](crate::parse_bytes) and the other binary parsers are
+/// built on this reader.
+///
+/// [RFC 8742]: https://www.rfc-editor.org/rfc/rfc8742.html
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{Event, IntegerWidth, Reader};
+///
+/// // [1, "a"]
+/// let events: Vec<_> = Reader::new(b"\x82\x01\x61a").collect::<Result<_, _>>().unwrap();
+///
+/// assert_eq!(
+///     events,
+///     [
+///         (0, Event::BeginArray { len: Some(2), bitwidth: Some(IntegerWidth::Zero) }),
+///         (1, Event::Integer { value: 1, bitwidth: IntegerWidth::Zero }),
+///         (2, Event::TextString { data: b"a", bitwidth: IntegerWidth::Zero }),
+///     ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    input: &'a [u8],
+    offset: usize,
+    /// The items the next event is within, innermost last
+    stack: Vec<Frame>,
+    /// Set once a problem has been returned, ending the iteration
+    failed: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Read the events of the data items in `input`.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            offset: 0,
+            stack: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// The offset of the next event in the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The input from the next event on.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.input[self.offset..]
+    }
+
+    /// How many arrays, maps, tags and indefinite length strings the next
+    /// event is within, `0` between top-level data items.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Skip over the next data item, along with everything within it,
+    /// returning the range of input it was encoded in.
+    ///
+    /// Returns `Ok(None)` without reading anything if there is no next item,
+    /// at the end of the input, the break ending an indefinite length item, or
+    /// after a problem was returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cbor_diag::{Event, IntegerWidth, Reader};
+    ///
+    /// // [[1, 2], 3]
+    /// let mut reader = Reader::new(b"\x82\x82\x01\x02\x03");
+    /// reader.next();
+    ///
+    /// assert_eq!(reader.skip_item().unwrap(), Some(1..4));
+    /// assert_eq!(
+    ///     reader.next().unwrap().unwrap(),
+    ///     (4, Event::Integer { value: 3, bitwidth: IntegerWidth::Zero }));
+    /// assert_eq!(reader.skip_item().unwrap(), None);
+    /// ```
+    pub fn skip_item(&mut self) -> Result<Option<Range<usize>>> {
+        let depth = self.depth();
+        if self.failed || self.at_break() || (depth == 0 && self.remaining().is_empty()) {
+            return Ok(None);
+        }
+        let start = self.offset;
+        loop {
+            match self.next() {
+                Some(Ok(_)) if self.depth() <= depth => return Ok(Some(start..self.offset)),
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err),
+                None => unreachable!("reading within an item fails instead of ending"),
+            }
+        }
+    }
+
+    /// A reader of `input` continuing within the items this one is part way
+    /// through.
+    ///
+    /// This lets input that isn't all in memory be read a window at a time,
+    /// starting each window at the next event.
+    pub(super) fn resume(self, input: &[u8]) -> Reader<'_> {
+        Reader {
+            input,
+            offset: 0,
+            stack: self.stack,
+            failed: false,
+        }
+    }
+
+    /// Whether the next event is the break ending an indefinite length item.
+    fn at_break(&self) -> bool {
+        self.offset < self.input.len() && self.is_break(self.offset, self.stack.last().copied())
+    }
+
+    /// Read the next event along with its offset, `Ok(None)` at the end of
+    /// the input between data items.
+    pub(super) fn read(
+        &mut self,
+    ) -> std::result::Result<Option<(usize, Event<'a>)>, Malformed<'a>> {
+        Ok(match self.read_header()? {
+            Some((start, Header::Event(event))) => Some((start, event)),
+            Some((
+                start,
+                Header::String {
+                    major,
+                    len,
+                    bitwidth,
+                },
+            )) => {
+                let event = self.content(major, len, bitwidth)?;
+                self.end_string();
+                Some((start, event))
+            }
+            None => None,
+        })
+    }
+
+    /// Read the next event as [`Reader::read`] does, except that definite
+    /// length strings stop after their header with the reader left at the
+    /// start of their content, which must be passed over before calling
+    /// [`Reader::end_string`].
+    pub(super) fn read_header(
+        &mut self,
+    ) -> std::result::Result<Option<(usize, Header<'a>)>, Malformed<'a>> {
+        let start = self.offset;
+        let top = self.stack.last().copied();
+        let initial = match self.input.get(start) {
+            Some(&initial) => initial,
+            None => {
+                let message = match top {
+                    None => return Ok(None),
+                    Some(Frame::Chunked { major, chunks }) => format!(
+                        "indefinite length {} missing break after {} chunks present",
+                        string_kind(major),
+                        chunks
+                    ),
+                    Some(Frame::Indefinite { major: 4, items }) => format!(
+                        "indefinite length array missing break after {} present",
+                        items
+                    ),
+                    Some(Frame::Indefinite { items, .. }) if items % 2 == 0 => format!(
+                        "indefinite length map missing break after {} present",
+                        items / 2
+                    ),
+                    Some(_) => "expected a data item, only 0 bytes present".into(),
+                };
+                return Err(self.incomplete(message, None));
+            }
+        };
+        self.offset += 1;
+
+        let event = if self.is_break(start, top) {
+            Event::EndIndefinite
+        } else if let Some(Frame::Chunked { major, .. }) = top {
+            let info = initial & 0x1f;
+            if initial >> 5 != major || info == 31 {
+                return Err(Malformed::Invalid {
+                    offset: start,
+                    message: format!(
+                        "expected a definite length {} chunk or break",
+                        string_kind(major)
+                    ),
+                });
+            }
+            let (len, bitwidth) = self.argument(start, info)?;
+            return Ok(Some((
+                start,
+                Header::String {
+                    major,
+                    len,
+                    bitwidth,
+                },
+            )));
+        } else {
+            match self.item(start, initial)? {
+                Header::Event(event) => event,
+                string => return Ok(Some((start, string))),
+            }
+        };
+
+        self.advance(&event);
+        Ok(Some((start, Header::Event(event))))
+    }
+
+    /// Finish a definite length string once its content has been read.
+    pub(super) fn end_string(&mut self) {
+        self.complete();
+    }
+
+    /// Whether the byte at `start`, within `top`, is a break ending it.
+    fn is_break(&self, start: usize, top: Option<Frame>) -> bool {
+        self.input[start] == 0xff
+            && match top {
+                Some(Frame::Chunked { .. }) => true,
+                Some(Frame::Indefinite { major, items }) => major == 4 || items % 2 == 0,
+                _ => false,
+            }
+    }
+
+    /// Record that the input ended, with nothing more to read after this.
+    fn incomplete(
+        &mut self,
+        message: String,
+        truncated: Option<(u64, Event<'a>)>,
+    ) -> Malformed<'a> {
+        self.offset = self.input.len();
+        Malformed::Incomplete { message, truncated }
+    }
+
+    fn take(&mut self, len: usize, what: &str) -> std::result::Result<&'a [u8], Malformed<'a>> {
+        let remaining = self.remaining();
+        if remaining.len() < len {
+            let message = format!("expected {}, only {} bytes present", what, remaining.len());
+            return Err(self.incomplete(message, None));
+        }
+        self.offset += len;
+        Ok(&remaining[..len])
+    }
+
+    /// Read the argument following an initial byte, `start` is the offset of
+    /// the initial byte.
+    fn argument(
+        &mut self,
+        start: usize,
+        info: u8,
+    ) -> std::result::Result<(u64, IntegerWidth), Malformed<'a>> {
+        let bitwidth = match info {
+            0..=23 => return Ok((info.into(), IntegerWidth::Zero)),
+            24 => IntegerWidth::Eight,
+            25 => IntegerWidth::Sixteen,
+            26 => IntegerWidth::ThirtyTwo,
+            27 => IntegerWidth::SixtyFour,
+            _ => {
+                return Err(Malformed::Invalid {
+                    offset: start,
+                    message: format!("reserved additional information value {}", info),
+                })
+            }
+        };
+        let len = bitwidth.byte_len();
+        let bytes = self.take(len, &format!("{} byte argument", len))?;
+        let value = bytes
+            .iter()
+            .fold(0, |value, &byte| value << 8 | u64::from(byte));
+        Ok((value, bitwidth))
+    }
+
+    /// Read the content of a definite length string of major type `major`.
+    fn content(
+        &mut self,
+        major: u8,
+        length: u64,
+        bitwidth: IntegerWidth,
+    ) -> std::result::Result<Event<'a>, Malformed<'a>> {
+        let remaining = self.remaining();
+        match usize::try_from(length) {
+            Ok(length) if length <= remaining.len() => {
+                self.offset += length;
+                Ok(string_event(major, &remaining[..length], bitwidth))
+            }
+            _ => {
+                let message = format!(
+                    "{} declared {} bytes, only {} present",
+                    string_kind(major),
+                    length,
+                    remaining.len()
+                );
+                let truncated = (length, string_event(major, remaining, bitwidth));
+                Err(self.incomplete(message, Some(truncated)))
+            }
+        }
+    }
+
+    /// Read the rest of a data item starting with the `initial` byte at
+    /// `start`, stopping after the header of definite length strings.
+    fn item(
+        &mut self,
+        start: usize,
+        initial: u8,
+    ) -> std::result::Result<Header<'a>, Malformed<'a>> {
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return self.float_or_simple(start, info).map(Header::Event);
+        }
+        if info == 31 {
+            match major {
+                2 => return Ok(Header::Event(Event::BeginByteString)),
+                3 => return Ok(Header::Event(Event::BeginTextString)),
+                4 => {
+                    return Ok(Header::Event(Event::BeginArray {
+                        len: None,
+                        bitwidth: None,
+                    }))
+                }
+                5 => {
+                    return Ok(Header::Event(Event::BeginMap {
+                        len: None,
+                        bitwidth: None,
+                    }))
+                }
+                _ => {}
+            }
+        }
+
+        let (value, bitwidth) = self.argument(start, info)?;
+        Ok(Header::Event(match major {
+            0 => Event::Integer { value, bitwidth },
+            1 => Event::Negative { value, bitwidth },
+            2 | 3 => {
+                return Ok(Header::String {
+                    major,
+                    len: value,
+                    bitwidth,
+                })
+            }
+            4 => Event::BeginArray {
+                len: Some(value),
+                bitwidth: Some(bitwidth),
+            },
+            5 => Event::BeginMap {
+                len: Some(value),
+                bitwidth: Some(bitwidth),
+            },
+            6 => Event::Tag {
+                tag: Tag(value),
+                bitwidth,
+            },
+            _ => unreachable!(),
+        }))
+    }
+
+    fn float_or_simple(
+        &mut self,
+        start: usize,
+        info: u8,
+    ) -> std::result::Result<Event<'a>, Malformed<'a>> {
+        Ok(match info {
+            0..=23 => Event::Simple(Simple(info)),
+            24 => {
+                let value = self.take(1, "1 byte simple value")?[0];
+                if value < 32 {
+                    // Values below 32 must use the single byte encoding, RFC 8949 § 3.3
+                    return Err(Malformed::Invalid {
+                        offset: start,
+                        message: format!("invalid two byte simple value {}", value),
+                    });
+                }
+                Event::Simple(Simple(value))
+            }
+            25 => {
+                let bytes = self.take(2, "2 byte float")?;
+                Event::Float {
+                    value: f16::from_be_bytes([bytes[0], bytes[1]]).to_f64(),
+                    bitwidth: FloatWidth::Sixteen,
+                }
+            }
+            26 => {
+                let bytes = self.take(4, "4 byte float")?;
+                Event::Float {
+                    value: f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).into(),
+                    bitwidth: FloatWidth::ThirtyTwo,
+                }
+            }
+            27 => {
+                let bytes = self.take(8, "8 byte float")?;
+                let mut array = [0; 8];
+                array.copy_from_slice(bytes);
+                Event::Float {
+                    value: f64::from_be_bytes(array),
+                    bitwidth: FloatWidth::SixtyFour,
+                }
+            }
+            31 => {
+                return Err(Malformed::Invalid {
+                    offset: start,
+                    message: "unexpected break".into(),
+                })
+            }
+            _ => {
+                return Err(Malformed::Invalid {
+                    offset: start,
+                    message: format!("reserved additional information value {}", info),
+                })
+            }
+        })
+    }
+
+    /// Update the open items after reading `event`.
+    fn advance(&mut self, event: &Event<'_>) {
+        match *event {
+            Event::BeginByteString => self.stack.push(Frame::Chunked {
+                major: 2,
+                chunks: 0,
+            }),
+            Event::BeginTextString => self.stack.push(Frame::Chunked {
+                major: 3,
+                chunks: 0,
+            }),
+            Event::BeginArray { len: None, .. } => {
+                self.stack.push(Frame::Indefinite { major: 4, items: 0 })
+            }
+            Event::BeginMap { len: None, .. } => {
+                self.stack.push(Frame::Indefinite { major: 5, items: 0 })
+            }
+            Event::BeginArray { len: Some(0), .. } | Event::BeginMap { len: Some(0), .. } => {
+                self.complete()
+            }
+            Event::BeginArray { len: Some(len), .. } => self.stack.push(Frame::Definite {
+                remaining: len.into(),
+            }),
+            Event::BeginMap { len: Some(len), .. } => self.stack.push(Frame::Definite {
+                remaining: u128::from(len) * 2,
+            }),
+            Event::Tag { .. } => self.stack.push(Frame::Tag),
+            Event::EndIndefinite => {
+                self.stack.pop();
+                self.complete();
+            }
+            _ => self.complete(),
+        }
+    }
+
+    /// Count an item as read within the innermost open item, closing any
+    /// items that this completes.
+    fn complete(&mut self) {
+        while let Some(frame) = self.stack.last_mut() {
+            match frame {
+                Frame::Definite { remaining } => {
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        return;
+                    }
+                }
+                Frame::Indefinite { items, .. } => {
+                    *items += 1;
+                    return;
+                }
+                Frame::Chunked { chunks, .. } => {
+                    *chunks += 1;
+                    return;
+                }
+                Frame::Tag => {}
+            }
+            self.stack.pop();
+        }
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<(usize, Event<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.read() {
+            Ok(event) => event.map(Ok),
+            Err(malformed) => {
+                self.failed = true;
+                Some(Err(malformed.into_error(self.input.len())))
+            }
+        }
+    }
+}
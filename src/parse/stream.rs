@@ -0,0 +1,298 @@
+use std::{
+    cmp,
+    io::{self, Read, Seek, SeekFrom},
+    mem,
+    ops::Range,
+    str,
+};
+
+use super::reader::{string_kind, Header, Malformed, Reader};
+use crate::{Error, Result};
+
+/// The longest header, an initial byte followed by an 8 byte argument.
+const MAX_HEADER: usize = 9;
+
+/// How much to read from the input at a time.
+const READ_SIZE: usize = 8 * 1024;
+
+/// The definite length string the reader is within.
+struct StringContent {
+    major: u8,
+    len: u64,
+    /// The offset of the start of the content
+    start: usize,
+    /// How many bytes of the content are left to read
+    remaining: u64,
+}
+
+/// Reads the events of binary encoded CBOR from a seekable stream, holding
+/// only a small window of the input in memory.
+///
+/// Definite length strings are returned as just their header, their content
+/// is then read piece by piece with [`StreamReader::content`] and
+/// [`StreamReader::skip_content`] before the next event.
+pub(crate) struct StreamReader<R> {
+    input: R,
+    /// The input from offset `base` on that has been read so far
+    buffer: Vec<u8>,
+    base: usize,
+    /// The index in `buffer` of the next byte to read
+    position: usize,
+    eof: bool,
+    /// The items being read, held by a reader without any input between
+    /// events
+    state: Reader<'static>,
+    string: Option<StringContent>,
+}
+
+impl<R: Read + Seek> StreamReader<R> {
+    /// Read `input` from its current position, which is taken as offset 0.
+    pub(crate) fn new(input: R) -> Self {
+        Self {
+            input,
+            buffer: Vec::new(),
+            base: 0,
+            position: 0,
+            eof: false,
+            state: Reader::new(&[]),
+            string: None,
+        }
+    }
+
+    /// The offset of the next byte to read.
+    pub(crate) fn offset(&self) -> usize {
+        self.base + self.position
+    }
+
+    /// How many items the next event is within, as [`Reader::depth`].
+    pub(crate) fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    fn buffered(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    /// Read more input so at least `len` bytes are buffered past the current
+    /// position, unless the input ends first.
+    fn fill(&mut self, len: usize) -> Result<()> {
+        if self.buffered() >= len || self.eof {
+            return Ok(());
+        }
+        self.buffer.drain(..self.position);
+        self.base += self.position;
+        self.position = 0;
+
+        let wanted = cmp::max(len - self.buffer.len(), READ_SIZE);
+        let read = (&mut self.input)
+            .take(wanted as u64)
+            .read_to_end(&mut self.buffer)?;
+        self.eof = read < wanted;
+        Ok(())
+    }
+
+    /// Discard what has been buffered and continue reading from `offset`.
+    fn seek(&mut self, offset: usize) -> Result<()> {
+        // Where offset 0 is in the input
+        let origin = self.input.stream_position()? - (self.base + self.buffer.len()) as u64;
+        self.input.seek(SeekFrom::Start(origin + offset as u64))?;
+        self.buffer.clear();
+        self.base = offset;
+        self.position = 0;
+        self.eof = false;
+        Ok(())
+    }
+
+    /// The error for the input ending while reading the current string.
+    fn truncated(&self, present: usize) -> Error {
+        let string = self.string.as_ref().expect("reading a string");
+        Malformed::Incomplete {
+            message: format!(
+                "{} declared {} bytes, only {} present",
+                string_kind(string.major),
+                string.len,
+                self.offset() - string.start + present
+            ),
+            truncated: None,
+        }
+        .into_error(self.offset() + present)
+    }
+
+    /// The next byte of input without reading it, `None` at the end.
+    pub(crate) fn peek(&mut self) -> Result<Option<u8>> {
+        self.fill(1)?;
+        Ok(self.buffer.get(self.position).copied())
+    }
+
+    /// Read the next event along with its offset, `Ok(None)` at the end of
+    /// the input between data items.
+    pub(crate) fn next(&mut self) -> Result<Option<(usize, Header<'_>)>> {
+        assert!(self.string.is_none(), "string content left unread");
+        self.fill(MAX_HEADER)?;
+
+        let window = &self.buffer[self.position..];
+        let mut reader = mem::replace(&mut self.state, Reader::new(&[])).resume(window);
+        let result = reader.read_header();
+        let read = reader.offset();
+        self.state = reader.resume(&[]);
+
+        // At least a header was buffered unless the input ended, so any
+        // incomplete header is at the end of the input
+        let end = self.base + self.buffer.len();
+        let (start, header) = match result {
+            Ok(Some(event)) => event,
+            Ok(None) => return Ok(None),
+            Err(Malformed::Invalid { offset, message }) => {
+                return Err(Malformed::Invalid {
+                    offset: self.base + self.position + offset,
+                    message,
+                }
+                .into_error(end))
+            }
+            Err(malformed) => return Err(malformed.into_error(end)),
+        };
+        let start = self.base + self.position + start;
+        self.position += read;
+
+        if let Header::String { major, len, .. } = header {
+            if len == 0 {
+                self.state.end_string();
+            } else {
+                self.string = Some(StringContent {
+                    major,
+                    len,
+                    start: self.base + self.position,
+                    remaining: len,
+                });
+            }
+        }
+        Ok(Some((start, header)))
+    }
+
+    /// Read the next event, failing at the end of the input.
+    pub(crate) fn expect_next(&mut self) -> Result<(usize, Header<'_>)> {
+        if self.peek()?.is_none() && self.depth() == 0 {
+            let message = "expected a data item, only 0 bytes present".to_owned();
+            return Err(Malformed::Incomplete {
+                message,
+                truncated: None,
+            }
+            .into_error(self.offset()));
+        }
+        Ok(self.next()?.expect("the input continues"))
+    }
+
+    /// How much of the current string's content is left to read.
+    pub(crate) fn content_remaining(&self) -> u64 {
+        self.string.as_ref().map_or(0, |string| string.remaining)
+    }
+
+    /// The next `len` bytes of the current string's content, or all that is
+    /// left of it if that is less.
+    ///
+    /// They are only read once passed over with [`StreamReader::skip_content`].
+    pub(crate) fn content(&mut self, len: usize) -> Result<&[u8]> {
+        let len = cmp::min(len as u64, self.content_remaining()) as usize;
+        self.fill(len)?;
+        if self.buffered() < len {
+            return Err(self.truncated(self.buffered()));
+        }
+        Ok(&self.buffer[self.position..][..len])
+    }
+
+    /// Pass over the next `len` bytes of the current string's content, which
+    /// must not be more than is left of it.
+    pub(crate) fn skip_content(&mut self, len: u64) -> Result<()> {
+        let string = self.string.as_mut().expect("reading a string");
+        string.remaining -= len;
+        let finished = string.remaining == 0;
+
+        let buffered = cmp::min(len, self.buffered() as u64);
+        self.position += buffered as usize;
+        let unbuffered = len - buffered;
+        if unbuffered > 0 {
+            self.base += self.buffer.len();
+            self.buffer.clear();
+            self.position = 0;
+            let skipped = io::copy(&mut (&mut self.input).take(unbuffered), &mut io::sink())?;
+            self.base += skipped as usize;
+            if skipped < unbuffered {
+                self.eof = true;
+                return Err(self.truncated(0));
+            }
+        }
+
+        if finished {
+            self.string = None;
+            self.state.end_string();
+        }
+        Ok(())
+    }
+
+    /// Whether the rest of the current string's content is valid UTF-8,
+    /// without reading it.
+    pub(crate) fn content_is_utf8(&mut self) -> Result<bool> {
+        let start = self.offset();
+        let mut remaining = self.content_remaining();
+        let mut valid = true;
+        while remaining > 0 {
+            let wanted = cmp::min(remaining, READ_SIZE as u64) as usize;
+            self.fill(wanted)?;
+            if self.buffered() < wanted {
+                return Err(self.truncated(self.buffered()));
+            }
+            let window = &self.buffer[self.position..][..wanted];
+            let checked = match str::from_utf8(window) {
+                Ok(_) => wanted,
+                // A character continuing into the next window
+                Err(err) if err.error_len().is_none() && (wanted as u64) < remaining => {
+                    err.valid_up_to()
+                }
+                Err(_) => {
+                    valid = false;
+                    break;
+                }
+            };
+            self.position += checked;
+            remaining -= checked as u64;
+        }
+        self.seek(start)?;
+        Ok(valid)
+    }
+
+    /// Read through the rest of the items the reader is within until only
+    /// `depth` are left open, returning the offset after them.
+    pub(crate) fn finish(&mut self, depth: usize) -> Result<usize> {
+        while self.depth() > depth {
+            if let Some((_, Header::String { len, .. })) = self.next()? {
+                if len > 0 {
+                    self.skip_content(len)?;
+                }
+            }
+        }
+        Ok(self.offset())
+    }
+
+    /// The input in `range`, which must be before the current position,
+    /// without moving the position.
+    pub(crate) fn raw(&mut self, range: Range<usize>) -> Result<Vec<u8>> {
+        let offset = self.offset();
+        let mut data = vec![0; range.len()];
+        self.seek(range.start)?;
+        self.input.read_exact(&mut data)?;
+        self.base += data.len();
+        self.seek(offset)?;
+        Ok(data)
+    }
+
+    /// How many bytes are left in the input, reading through all of them.
+    pub(crate) fn count_remaining(&mut self) -> Result<usize> {
+        let buffered = self.buffered();
+        let rest = io::copy(&mut self.input, &mut io::sink())?;
+        self.base += self.buffer.len() + rest as usize;
+        self.buffer.clear();
+        self.position = 0;
+        self.eof = true;
+        Ok(buffered + rest as usize)
+    }
+}
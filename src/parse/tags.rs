@@ -0,0 +1,149 @@
+use crate::{DataItem, Tag};
+
+fn is_text(item: &DataItem) -> bool {
+    matches!(
+        item,
+        DataItem::TextString(_)
+            | DataItem::IndefiniteTextString(_)
+            | DataItem::InvalidTextString(_)
+            | DataItem::InvalidIndefiniteTextString(_)
+    )
+}
+
+fn is_bytes(item: &DataItem) -> bool {
+    matches!(
+        item,
+        DataItem::ByteString(_) | DataItem::IndefiniteByteString(_)
+    )
+}
+
+fn is_integer(item: &DataItem) -> bool {
+    matches!(item, DataItem::Integer { .. } | DataItem::Negative { .. })
+}
+
+fn is_number(item: &DataItem) -> bool {
+    is_integer(item) || matches!(item, DataItem::Float { .. })
+}
+
+fn is_bignum(item: &DataItem) -> bool {
+    match item {
+        DataItem::Tag { tag, value, .. } => {
+            (*tag == Tag::POSITIVE_BIGNUM || *tag == Tag::NEGATIVE_BIGNUM) && is_bytes(value)
+        }
+        _ => false,
+    }
+}
+
+fn is_fraction(item: &DataItem) -> bool {
+    match item {
+        DataItem::Array { data, .. } => match &data[..] {
+            [exponent, mantissa] => {
+                is_integer(exponent) && (is_integer(mantissa) || is_bignum(mantissa))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The name of a well-known tag, a description of the content it requires
+/// and a check for that content.
+type Requirement = (&'static str, &'static str, fn(&DataItem) -> bool);
+
+fn requirement(tag: Tag) -> Option<Requirement> {
+    Some(match tag {
+        Tag::DATETIME => ("date/time", "a text string", is_text),
+        Tag::EPOCH_DATETIME => ("epoch date/time", "an integer or float", is_number),
+        Tag::POSITIVE_BIGNUM => ("positive bignum", "a byte string", is_bytes),
+        Tag::NEGATIVE_BIGNUM => ("negative bignum", "a byte string", is_bytes),
+        Tag::DECIMAL_FRACTION => (
+            "decimal fraction",
+            "an array of an integer exponent and an integer or bignum mantissa",
+            is_fraction,
+        ),
+        Tag::BIGFLOAT => (
+            "bigfloat",
+            "an array of an integer exponent and an integer or bignum mantissa",
+            is_fraction,
+        ),
+        Tag::ENCODED_CBOR => ("encoded CBOR data item", "a byte string", is_bytes),
+        Tag::URI => ("URI", "a text string", is_text),
+        Tag::BASE64URL => ("base64url", "a text string", is_text),
+        Tag::BASE64 => ("base64", "a text string", is_text),
+        Tag::REGEX => ("regular expression", "a text string", is_text),
+        Tag::MIME => ("MIME message", "a text string", is_text),
+        // The expected conversion tags 21 to 23 apply to any content
+        _ => return None,
+    })
+}
+
+fn describe(item: &DataItem) -> &'static str {
+    match item {
+        DataItem::Integer { .. } => "an unsigned integer",
+        DataItem::Negative { .. } => "a negative integer",
+        DataItem::ByteString(_) | DataItem::IndefiniteByteString(_) => "a byte string",
+        DataItem::TextString(_)
+        | DataItem::IndefiniteTextString(_)
+        | DataItem::InvalidTextString(_)
+        | DataItem::InvalidIndefiniteTextString(_) => "a text string",
+        DataItem::Array { .. } => "an array",
+        DataItem::Map { .. } => "a map",
+        DataItem::Tag { .. } => "a tag",
+        DataItem::Float { .. } => "a float",
+        DataItem::Simple(_) => "a simple value",
+        DataItem::AppLiteral { .. } => "an application-oriented literal",
+    }
+}
+
+fn check(item: &DataItem, path: &mut Vec<usize>, problems: &mut Vec<(Vec<usize>, String)>) {
+    fn visit(
+        index: &[usize],
+        item: &DataItem,
+        path: &mut Vec<usize>,
+        problems: &mut Vec<(Vec<usize>, String)>,
+    ) {
+        let len = path.len();
+        path.extend(index);
+        check(item, path, problems);
+        path.truncate(len);
+    }
+
+    match item {
+        DataItem::Array { data, .. } => {
+            for (index, item) in data.iter().enumerate() {
+                visit(&[index], item, path, problems);
+            }
+        }
+        DataItem::Map { data, .. } => {
+            for (index, (key, value)) in data.iter().enumerate() {
+                visit(&[index, 0], key, path, problems);
+                visit(&[index, 1], value, path, problems);
+            }
+        }
+        DataItem::Tag { tag, value, .. } => {
+            if let Some((name, expected, valid)) = requirement(*tag) {
+                if !valid(value) {
+                    let message = format!(
+                        "tag {} ({}) expects {}, found {}",
+                        tag.0,
+                        name,
+                        expected,
+                        describe(value)
+                    );
+                    problems.push((path.clone(), message));
+                }
+            }
+            visit(&[0], value, path, problems);
+        }
+        _ => {}
+    }
+}
+
+/// Check the content of the well-known tags from RFC 8949 §3.4, returning the
+/// path to each tag with content of the wrong type along with a description
+/// of the problem.
+pub(super) fn tag_content_problems(item: &DataItem) -> Vec<(Vec<usize>, String)> {
+    let mut problems = Vec::new();
+    check(item, &mut Vec::new(), &mut problems);
+    problems
+}
@@ -0,0 +1,95 @@
+use super::reader::{Malformed, Reader};
+use crate::Result;
+
+/// What was found checking binary input with [`check_well_formed`] or
+/// [`check_well_formed_seq`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WellFormedness {
+    /// How many top-level data items the input contains.
+    pub items: usize,
+    /// How many bytes of input they were encoded in.
+    pub len: usize,
+}
+
+/// Check that `bytes` is a single well-formed binary encoded CBOR data item,
+/// without parsing it into a [`DataItem`](crate::DataItem).
+///
+/// This walks the input with a [`Reader`], so is much faster than
+/// [`parse_bytes`](crate::parse_bytes) for when only well-formedness matters:
+/// nothing is allocated for the items read, only a stack of the arrays, maps,
+/// tags and indefinite length strings being read within. As for the reader,
+/// text strings are not checked to be valid UTF-8, and no limits from
+/// [`ParseOptions`](crate::ParseOptions) apply.
+///
+/// # Errors
+///
+/// Fails at the first problem with the input, as `parse_bytes` would,
+/// including if the data item is followed by anything else.
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{check_well_formed, WellFormedness};
+///
+/// // [1, "a"]
+/// assert_eq!(
+///     check_well_formed(b"\x82\x01\x61a").unwrap(),
+///     WellFormedness { items: 1, len: 4 });
+///
+/// assert!(check_well_formed(b"\x82\x01").is_err());
+/// ```
+pub fn check_well_formed(bytes: impl AsRef<[u8]>) -> Result<WellFormedness> {
+    let bytes = bytes.as_ref();
+    let mut reader = Reader::new(bytes);
+    let len = match reader.skip_item()? {
+        Some(range) => range.end,
+        None => {
+            let message = "expected a data item, only 0 bytes present".to_owned();
+            return Err(Malformed::Incomplete {
+                message,
+                truncated: None,
+            }
+            .into_error(0));
+        }
+    };
+    if len < bytes.len() {
+        return Err(format!(
+            "parsed 1 item ({} bytes), {} trailing bytes",
+            len,
+            bytes.len() - len
+        )
+        .into());
+    }
+    Ok(WellFormedness { items: 1, len })
+}
+
+/// Like [`check_well_formed`], but for a [CBOR sequence][RFC 8742] of any
+/// number of data items, including none.
+///
+/// [RFC 8742]: https://www.rfc-editor.org/rfc/rfc8742.html
+///
+/// # Examples
+///
+/// ```rust
+/// use cbor_diag::{check_well_formed_seq, WellFormedness};
+///
+/// // 1, "a"
+/// assert_eq!(
+///     check_well_formed_seq(b"\x01\x61a").unwrap(),
+///     WellFormedness { items: 2, len: 3 });
+///
+/// assert_eq!(
+///     check_well_formed_seq(b"").unwrap(),
+///     WellFormedness { items: 0, len: 0 });
+/// ```
+pub fn check_well_formed_seq(bytes: impl AsRef<[u8]>) -> Result<WellFormedness> {
+    let mut reader = Reader::new(bytes.as_ref());
+    let mut items = 0;
+    while reader.skip_item()?.is_some() {
+        items += 1;
+    }
+    Ok(WellFormedness {
+        items,
+        len: reader.offset(),
+    })
+}
@@ -0,0 +1,205 @@
+use std::mem;
+
+use crate::{ByteString, DataItem, IntegerWidth, Simple, TextString};
+
+/// What to do with an item, see [`DataItem::redact`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redaction {
+    /// Leave the item as it is, continuing on to the items within it.
+    Keep,
+
+    /// Remove the item from its array, or its entry from its map.
+    ///
+    /// Items that can't be removed, the root item and the values of tags,
+    /// are replaced with `undefined` instead.
+    Remove,
+
+    /// Replace the item, the replacement is not redacted further.
+    ///
+    /// [`DataItem::to_placeholder`] gives a replacement that keeps the size
+    /// of the encoding the same.
+    Replace(DataItem),
+}
+
+const UNDEFINED: DataItem = DataItem::Simple(Simple::UNDEFINED);
+
+/// Apply `matcher` to `item` at `path` and then the items within it,
+/// returning whether it should be removed.
+fn redact(
+    item: &mut DataItem,
+    path: &mut Vec<usize>,
+    matcher: &dyn Fn(&[usize], &DataItem) -> Redaction,
+) -> bool {
+    match matcher(path, item) {
+        Redaction::Keep => {}
+        Redaction::Remove => return true,
+        Redaction::Replace(replacement) => {
+            *item = replacement;
+            return false;
+        }
+    }
+
+    match item {
+        DataItem::Array { data, .. } => {
+            let mut kept = Vec::with_capacity(data.len());
+            for (index, mut item) in mem::take(data).into_iter().enumerate() {
+                path.push(index);
+                if !redact(&mut item, path, matcher) {
+                    kept.push(item);
+                }
+                path.pop();
+            }
+            *data = kept;
+        }
+        DataItem::Map { data, .. } => {
+            let mut kept = Vec::with_capacity(data.len());
+            for (index, (mut key, mut value)) in mem::take(data).into_iter().enumerate() {
+                path.extend_from_slice(&[index, 0]);
+                let remove = redact(&mut key, path, matcher);
+                path.pop();
+                path.push(1);
+                if !remove && !redact(&mut value, path, matcher) {
+                    kept.push((key, value));
+                }
+                path.truncate(path.len() - 2);
+            }
+            *data = kept;
+        }
+        DataItem::Tag { value, .. } => {
+            path.push(0);
+            if redact(value, path, matcher) {
+                **value = UNDEFINED;
+            }
+            path.pop();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+/// Resolve an unknown width to the one it will be encoded with, so it stays
+/// the same when the value changes.
+fn known_width(value: u64, bitwidth: IntegerWidth) -> IntegerWidth {
+    match bitwidth {
+        IntegerWidth::Unknown => IntegerWidth::minimal_for(value),
+        bitwidth => bitwidth,
+    }
+}
+
+fn zeroed_bytes(bytestring: &ByteString) -> ByteString {
+    ByteString {
+        data: vec![0; bytestring.data.len()],
+        ..bytestring.clone()
+    }
+}
+
+fn placeholder_text(len: usize, bitwidth: IntegerWidth) -> TextString {
+    TextString {
+        data: "x".repeat(len),
+        bitwidth,
+    }
+}
+
+impl DataItem {
+    /// Redact parts of this item, as decided by `matcher` for each item
+    /// within it (but not the chunks of indefinite length strings).
+    ///
+    /// `matcher` is passed the path of the item, as used by
+    /// [`Comments`](crate::Comments), and the item itself. The paths are
+    /// those from before any items are removed.
+    ///
+    /// ```rust
+    /// use cbor_diag::{parse_diag, Redaction};
+    ///
+    /// let mut item = parse_diag(r#"{"user": "bob", "key": h'0102', "tmp": 1}"#).unwrap();
+    /// item.redact(|path, item| match path {
+    ///     [1, 1] => Redaction::Replace(item.to_placeholder()),
+    ///     [2, 0] => Redaction::Remove,
+    ///     _ => Redaction::Keep,
+    /// });
+    /// assert_eq!(item.to_diag(), r#"{"user":"bob","key":h'0000'}"#);
+    /// ```
+    pub fn redact(&mut self, matcher: impl Fn(&[usize], &DataItem) -> Redaction) {
+        if redact(self, &mut Vec::new(), &matcher) {
+            *self = UNDEFINED;
+        }
+    }
+
+    /// An item with the same structure and encoded size as this one, but
+    /// with the content of strings and numbers replaced.
+    ///
+    /// Byte strings are filled with zeros, text strings with `x`, and numbers
+    /// set to zero (or `-1`) encoded with the same width. Within arrays, maps
+    /// and tags only the values are replaced, map keys and tags are kept.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"{1: [h'0102', "abc", 1000, -3.5]}"#).unwrap();
+    /// let placeholder = item.to_placeholder();
+    /// assert_eq!(placeholder.to_diag(), r#"{1:[h'0000',"xxx",0_1,0.0]}"#);
+    /// assert_eq!(placeholder.to_bytes().len(), item.to_bytes().len());
+    /// ```
+    pub fn to_placeholder(&self) -> DataItem {
+        match *self {
+            DataItem::Integer { value, bitwidth } => DataItem::Integer {
+                value: 0,
+                bitwidth: known_width(value, bitwidth),
+            },
+            DataItem::Negative { value, bitwidth } => DataItem::Negative {
+                value: 0,
+                bitwidth: known_width(value, bitwidth),
+            },
+            DataItem::ByteString(ref bytestring) => DataItem::ByteString(zeroed_bytes(bytestring)),
+            DataItem::IndefiniteByteString(ref bytestrings) => {
+                DataItem::IndefiniteByteString(bytestrings.iter().map(zeroed_bytes).collect())
+            }
+            DataItem::TextString(ref textstring) => {
+                DataItem::TextString(placeholder_text(textstring.data.len(), textstring.bitwidth))
+            }
+            DataItem::InvalidTextString(ref bytestring) => {
+                DataItem::TextString(placeholder_text(bytestring.data.len(), bytestring.bitwidth))
+            }
+            DataItem::IndefiniteTextString(ref textstrings) => DataItem::IndefiniteTextString(
+                textstrings
+                    .iter()
+                    .map(|textstring| placeholder_text(textstring.data.len(), textstring.bitwidth))
+                    .collect(),
+            ),
+            DataItem::InvalidIndefiniteTextString(ref bytestrings) => {
+                DataItem::IndefiniteTextString(
+                    bytestrings
+                        .iter()
+                        .map(|bytestring| {
+                            placeholder_text(bytestring.data.len(), bytestring.bitwidth)
+                        })
+                        .collect(),
+                )
+            }
+            DataItem::Array { ref data, bitwidth } => DataItem::Array {
+                data: data.iter().map(DataItem::to_placeholder).collect(),
+                bitwidth,
+            },
+            DataItem::Map { ref data, bitwidth } => DataItem::Map {
+                data: data
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_placeholder()))
+                    .collect(),
+                bitwidth,
+            },
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                ref value,
+            } => DataItem::Tag {
+                tag,
+                bitwidth,
+                value: Box::new(value.to_placeholder()),
+            },
+            DataItem::Float { bitwidth, .. } => DataItem::Float {
+                value: 0.0,
+                bitwidth,
+            },
+            DataItem::Simple(_) | DataItem::AppLiteral { .. } => self.clone(),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Helpers to check that a [`DataItem`] survives being encoded to and then
+//! parsed back from each of the supported formats.
+//!
+//! These panic with a description of the difference if the check fails, so
+//! are intended for use in tests and fuzz targets, e.g. along with the
+//! `arbitrary` feature:
+//!
+//! ```ignore
+//! libfuzzer_sys::fuzz_target!(|item: cbor_diag::DataItem| {
+//!     cbor_diag::roundtrip::all(&item);
+//! });
+//! ```
+//!
+//! The data item must be one that can be exactly represented in the binary
+//! encoding: all widths must be known and large enough for the values they
+//! apply to, and floats must not be NaN.
+
+use crate::{parse_bytes, parse_diag, parse_hex, DataItem};
+
+/// Check that `parse_bytes(item.to_bytes()) == item`.
+pub fn bytes(item: &DataItem) {
+    let encoded = item.to_bytes();
+    let parsed = parse_bytes(&encoded)
+        .unwrap_or_else(|err| panic!("failed to parse {:02x?} from {:?}: {}", encoded, item, err));
+    assert_eq!(parsed, *item, "binary roundtrip changed item");
+}
+
+/// Check that `parse_hex(item.to_hex()) == item`.
+pub fn hex(item: &DataItem) {
+    let encoded = item.to_hex();
+    let parsed = parse_hex(&encoded)
+        .unwrap_or_else(|err| panic!("failed to parse {:?} from {:?}: {}", encoded, item, err));
+    assert_eq!(parsed, *item, "hex roundtrip changed item");
+}
+
+/// Check that `parse_diag(item.to_diag())` and `parse_diag(item.to_diag_pretty())`
+/// are both semantically equal to `item`.
+///
+/// Diagnostic notation cannot represent all the encoding details of a data
+/// item, so this compares using [`semantically_eq`].
+pub fn diag(item: &DataItem) {
+    for encoded in &[item.to_diag(), item.to_diag_pretty()] {
+        let parsed = parse_diag(encoded)
+            .unwrap_or_else(|err| panic!("failed to parse {:?} from {:?}: {}", encoded, item, err));
+        assert!(
+            semantically_eq(&parsed, item),
+            "diag roundtrip changed item\n  parsed: {:?}\n  original: {:?}",
+            parsed,
+            item,
+        );
+    }
+}
+
+/// Run all of [`bytes`], [`hex`] and [`diag`].
+pub fn all(item: &DataItem) {
+    bytes(item);
+    hex(item);
+    diag(item);
+}
+
+/// Compare two data items ignoring encoding details: integer and length
+/// widths, and how strings are chunked in indefinite length strings.
+///
+/// Whether a container is definite or indefinite length, and float widths,
+/// are still compared, floats compare NaN as equal to NaN.
+pub fn semantically_eq(left: &DataItem, right: &DataItem) -> bool {
+    use DataItem::*;
+
+    match (left, right) {
+        (Integer { value: l, .. }, Integer { value: r, .. }) => l == r,
+        (Negative { value: l, .. }, Negative { value: r, .. }) => l == r,
+        (ByteString(l), ByteString(r)) => l.data == r.data,
+        (TextString(l), TextString(r)) => l.data == r.data,
+        (InvalidTextString(l), InvalidTextString(r)) => l.data == r.data,
+        (InvalidIndefiniteTextString(l), InvalidIndefiniteTextString(r)) => l
+            .iter()
+            .flat_map(|s| &s.data)
+            .eq(r.iter().flat_map(|s| &s.data)),
+        (IndefiniteByteString(l), IndefiniteByteString(r)) => l
+            .iter()
+            .flat_map(|s| &s.data)
+            .eq(r.iter().flat_map(|s| &s.data)),
+        (IndefiniteTextString(l), IndefiniteTextString(r)) => l
+            .iter()
+            .flat_map(|s| s.data.chars())
+            .eq(r.iter().flat_map(|s| s.data.chars())),
+        (
+            Array {
+                data: l,
+                bitwidth: lw,
+            },
+            Array {
+                data: r,
+                bitwidth: rw,
+            },
+        ) => {
+            lw.is_some() == rw.is_some()
+                && l.len() == r.len()
+                && l.iter().zip(r).all(|(l, r)| semantically_eq(l, r))
+        }
+        (
+            Map {
+                data: l,
+                bitwidth: lw,
+            },
+            Map {
+                data: r,
+                bitwidth: rw,
+            },
+        ) => {
+            lw.is_some() == rw.is_some()
+                && l.len() == r.len()
+                && l.iter()
+                    .zip(r)
+                    .all(|((lk, lv), (rk, rv))| semantically_eq(lk, rk) && semantically_eq(lv, rv))
+        }
+        (
+            Tag {
+                tag: lt, value: l, ..
+            },
+            Tag {
+                tag: rt, value: r, ..
+            },
+        ) => lt == rt && semantically_eq(l, r),
+        (
+            Float {
+                value: l,
+                bitwidth: lw,
+            },
+            Float {
+                value: r,
+                bitwidth: rw,
+            },
+        ) => lw == rw && (l == r || (l.is_nan() && r.is_nan())),
+        (Simple(l), Simple(r)) => l == r,
+        (
+            AppLiteral {
+                prefix: lp,
+                content: lc,
+            },
+            AppLiteral {
+                prefix: rp,
+                content: rc,
+            },
+        ) => lp == rp && lc == rc,
+        _ => false,
+    }
+}
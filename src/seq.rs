@@ -0,0 +1,48 @@
+use crate::{DataItem, IntegerWidth, Result};
+
+/// Collect the items of a [CBOR sequence][RFC 8742] into a single array, so
+/// that the whole sequence can be shown as one item.
+///
+/// ```rust
+/// use cbor_diag::{items_to_array, parse_bytes_partial};
+///
+/// let mut data = &[0x01, 0x61, 0x61][..];
+/// let mut items = Vec::new();
+/// while let Some((item, len)) = parse_bytes_partial(data).unwrap() {
+///     items.push(item);
+///     data = &data[len..];
+/// }
+/// assert_eq!(items_to_array(items).to_diag(), r#"[1,"a"]"#);
+/// ```
+///
+/// [RFC 8742]: https://www.rfc-editor.org/rfc/rfc8742.html
+pub fn items_to_array(items: impl IntoIterator<Item = DataItem>) -> DataItem {
+    DataItem::Array {
+        data: items.into_iter().collect(),
+        bitwidth: Some(IntegerWidth::Unknown),
+    }
+}
+
+/// Split an array into its items, to be output as a [CBOR sequence][RFC
+/// 8742], the inverse of [`items_to_array`].
+///
+/// Fails if `item` is not an array (of either definite or indefinite
+/// length).
+///
+/// ```rust
+/// use cbor_diag::{array_to_items, parse_diag};
+///
+/// let sequence: Vec<u8> = array_to_items(parse_diag(r#"[1, "a"]"#).unwrap())
+///     .unwrap()
+///     .flat_map(|item| item.to_bytes())
+///     .collect();
+/// assert_eq!(sequence, [0x01, 0x61, 0x61]);
+/// ```
+///
+/// [RFC 8742]: https://www.rfc-editor.org/rfc/rfc8742.html
+pub fn array_to_items(mut item: DataItem) -> Result<std::vec::IntoIter<DataItem>> {
+    match &mut item {
+        DataItem::Array { data, .. } => Ok(std::mem::take(data).into_iter()),
+        _ => Err("expected an array to split into a sequence".into()),
+    }
+}
@@ -0,0 +1,264 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use crate::{encode::header_len, ByteString, DataItem, IntegerWidth, TextString};
+
+/// The encoded size of an item and everything within it, see
+/// [`DataItem::size_report`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SubtreeSize {
+    /// The path of the item, as used by [`Comments`](crate::Comments).
+    pub path: Vec<usize>,
+
+    /// The bytes used by initial bytes, their arguments and breaks. Integers,
+    /// floats and simple values are made entirely of these.
+    pub header: usize,
+
+    /// The bytes used by the data of strings.
+    pub payload: usize,
+}
+
+impl SubtreeSize {
+    /// The encoded size of the whole item.
+    pub fn total(&self) -> usize {
+        self.header + self.payload
+    }
+}
+
+/// The number of items in a category and the bytes they use, see
+/// [`SizeReport`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SizeTotals {
+    pub count: usize,
+    pub header: usize,
+    pub payload: usize,
+}
+
+impl SizeTotals {
+    /// The bytes used, header and payload.
+    pub fn total(&self) -> usize {
+        self.header + self.payload
+    }
+}
+
+/// Where the bytes of an encoded item go, see [`DataItem::size_report`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SizeReport {
+    /// Every item in the encoding, including the chunks of indefinite length
+    /// strings, in the order they are encoded (so the root item is first).
+    pub subtrees: Vec<SubtreeSize>,
+
+    /// The items of each major type (0 to 7), with only the bytes of the
+    /// items themselves rather than those within them, so these add up to
+    /// the total size.
+    pub major_types: BTreeMap<u8, SizeTotals>,
+
+    /// The items with each tag, with the bytes of the whole tagged item
+    /// including the tag itself.
+    pub tags: BTreeMap<u64, SizeTotals>,
+}
+
+impl SizeReport {
+    /// The encoded size of the root item.
+    pub fn total(&self) -> usize {
+        self.subtrees.first().map_or(0, SubtreeSize::total)
+    }
+
+    /// The `n` largest subtrees, largest first, with equal sizes in encoded
+    /// order.
+    pub fn largest(&self, n: usize) -> Vec<&SubtreeSize> {
+        let mut subtrees: Vec<&SubtreeSize> = self.subtrees.iter().collect();
+        subtrees.sort_by_key(|subtree| Reverse(subtree.total()));
+        subtrees.truncate(n);
+        subtrees
+    }
+}
+
+fn major_type(item: &DataItem) -> Option<u8> {
+    Some(match item {
+        DataItem::Integer { .. } => 0,
+        DataItem::Negative { .. } => 1,
+        DataItem::ByteString(_) | DataItem::IndefiniteByteString(_) => 2,
+        DataItem::TextString(_)
+        | DataItem::IndefiniteTextString(_)
+        | DataItem::InvalidTextString(_)
+        | DataItem::InvalidIndefiniteTextString(_) => 3,
+        DataItem::Array { .. } => 4,
+        DataItem::Map { .. } => 5,
+        DataItem::Tag { .. } => 6,
+        DataItem::Float { .. } | DataItem::Simple(_) => 7,
+        DataItem::AppLiteral { .. } => return None,
+    })
+}
+
+fn add(total: &mut (usize, usize), (header, payload): (usize, usize)) {
+    total.0 += header;
+    total.1 += payload;
+}
+
+struct Builder {
+    report: SizeReport,
+    path: Vec<usize>,
+}
+
+impl Builder {
+    /// Count the bytes of an item itself towards its major type.
+    fn count(&mut self, major: Option<u8>, (header, payload): (usize, usize)) {
+        if let Some(major) = major {
+            let totals = self.report.major_types.entry(major).or_default();
+            totals.count += 1;
+            totals.header += header;
+            totals.payload += payload;
+        }
+    }
+
+    /// Add `item` at `index` within the current item, returning its header
+    /// and payload bytes.
+    fn child(&mut self, index: &[usize], item: &DataItem) -> (usize, usize) {
+        self.path.extend_from_slice(index);
+        let size = self.item(item);
+        self.path.truncate(self.path.len() - index.len());
+        size
+    }
+
+    /// Add the chunk at `index` within an indefinite length string.
+    fn chunk(
+        &mut self,
+        index: usize,
+        major: u8,
+        len: usize,
+        bitwidth: IntegerWidth,
+    ) -> (usize, usize) {
+        let size = (header_len(len as u64, bitwidth), len);
+        self.count(Some(major), size);
+        let mut path = self.path.clone();
+        path.push(index);
+        self.report.subtrees.push(SubtreeSize {
+            path,
+            header: size.0,
+            payload: size.1,
+        });
+        size
+    }
+
+    /// Add `item` and everything within it, returning its header and payload
+    /// bytes.
+    fn item(&mut self, item: &DataItem) -> (usize, usize) {
+        let index = self.report.subtrees.len();
+        self.report.subtrees.push(SubtreeSize {
+            path: self.path.clone(),
+            header: 0,
+            payload: 0,
+        });
+
+        let mut inner = (0, 0);
+        let own = match item {
+            DataItem::ByteString(ByteString { data, bitwidth, .. })
+            | DataItem::InvalidTextString(ByteString { data, bitwidth, .. }) => {
+                (header_len(data.len() as u64, *bitwidth), data.len())
+            }
+            DataItem::TextString(TextString { data, bitwidth }) => {
+                (header_len(data.len() as u64, *bitwidth), data.len())
+            }
+            DataItem::IndefiniteByteString(strings) => {
+                for (index, string) in strings.iter().enumerate() {
+                    add(
+                        &mut inner,
+                        self.chunk(index, 2, string.data.len(), string.bitwidth),
+                    );
+                }
+                // The initial byte and the break
+                (2, 0)
+            }
+            DataItem::IndefiniteTextString(strings) => {
+                for (index, string) in strings.iter().enumerate() {
+                    add(
+                        &mut inner,
+                        self.chunk(index, 3, string.data.len(), string.bitwidth),
+                    );
+                }
+                (2, 0)
+            }
+            DataItem::InvalidIndefiniteTextString(strings) => {
+                for (index, string) in strings.iter().enumerate() {
+                    add(
+                        &mut inner,
+                        self.chunk(index, 3, string.data.len(), string.bitwidth),
+                    );
+                }
+                (2, 0)
+            }
+            DataItem::Array { data, bitwidth } => {
+                for (index, item) in data.iter().enumerate() {
+                    add(&mut inner, self.child(&[index], item));
+                }
+                (
+                    bitwidth.map_or(2, |bitwidth| header_len(data.len() as u64, bitwidth)),
+                    0,
+                )
+            }
+            DataItem::Map { data, bitwidth } => {
+                for (index, (key, value)) in data.iter().enumerate() {
+                    add(&mut inner, self.child(&[index, 0], key));
+                    add(&mut inner, self.child(&[index, 1], value));
+                }
+                (
+                    bitwidth.map_or(2, |bitwidth| header_len(data.len() as u64, bitwidth)),
+                    0,
+                )
+            }
+            DataItem::Tag {
+                tag,
+                bitwidth,
+                value,
+            } => {
+                add(&mut inner, self.child(&[0], value));
+                (header_len(tag.0, *bitwidth), 0)
+            }
+            // Items without a binary encoding take no space
+            _ => (item.encoded_len().unwrap_or(0), 0),
+        };
+        self.count(major_type(item), own);
+
+        let mut size = own;
+        add(&mut size, inner);
+        let subtree = &mut self.report.subtrees[index];
+        subtree.header = size.0;
+        subtree.payload = size.1;
+
+        if let DataItem::Tag { tag, .. } = item {
+            let totals = self.report.tags.entry(tag.0).or_default();
+            totals.count += 1;
+            totals.header += size.0;
+            totals.payload += size.1;
+        }
+
+        size
+    }
+}
+
+impl DataItem {
+    /// Break down the encoded size of this item, to find what the bytes are
+    /// used on.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag(r#"{1: "hello", 2: [_ h'0102']}"#).unwrap();
+    /// let report = item.size_report();
+    /// assert_eq!(report.total(), 14);
+    /// let largest = report.largest(2);
+    /// assert_eq!(largest[1].path, [0, 1]);
+    /// assert_eq!((largest[1].header, largest[1].payload), (1, 5));
+    /// assert_eq!(report.major_types[&3].count, 1);
+    /// ```
+    pub fn size_report(&self) -> SizeReport {
+        let mut builder = Builder {
+            report: SizeReport {
+                subtrees: Vec::new(),
+                major_types: BTreeMap::new(),
+                tags: BTreeMap::new(),
+            },
+            path: Vec::new(),
+        };
+        builder.item(self);
+        builder.report
+    }
+}
@@ -0,0 +1,140 @@
+use std::ops::Range;
+
+use crate::DataItem;
+
+/// The byte ranges a data item was parsed from in binary input.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Span {
+    /// The initial byte and argument of the item.
+    ///
+    /// For floats and simple values this is the whole item, and for
+    /// indefinite length items it is only the initial byte.
+    pub header: Range<usize>,
+
+    /// The content following the header: the data of a string, the items
+    /// of an array or map (including the break for indefinite length
+    /// ones), the chunks of an indefinite length string (including the
+    /// break) or the value of a tag. Empty for other items.
+    pub content: Range<usize>,
+}
+
+impl Span {
+    /// The whole range of the item, its header and content.
+    pub fn range(&self) -> Range<usize> {
+        self.header.start..self.content.end
+    }
+}
+
+/// The [`Span`]s of a data item and everything within it, mirroring the item
+/// tree, see [`parse_bytes_with_spans`](crate::parse_bytes_with_spans).
+///
+/// Items within the root item are identified by the same paths as used by
+/// [`Comments`](crate::Comments).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SpanTree {
+    span: Span,
+    /// The keys and values of a map are interleaved, so need translating to
+    /// and from paths
+    is_map: bool,
+    children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    pub(crate) fn new(span: Span, is_map: bool, children: Vec<SpanTree>) -> Self {
+        Self {
+            span,
+            is_map,
+            children,
+        }
+    }
+
+    /// The span of the root item.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// The trees of the items directly within the root item, with the keys
+    /// and values of a map interleaved.
+    pub(crate) fn children(&self) -> &[SpanTree] {
+        &self.children
+    }
+
+    fn child(&self, path: &[usize]) -> Option<(&SpanTree, usize)> {
+        match (self.is_map, path) {
+            (true, [entry, 0, ..]) | (true, [entry, 1, ..]) => {
+                Some((self.children.get(entry * 2 + path[1])?, 2))
+            }
+            (true, _) => None,
+            (false, [index, ..]) => Some((self.children.get(*index)?, 1)),
+            (false, []) => None,
+        }
+    }
+
+    /// The span of the item at `path`, if there is one.
+    pub fn get(&self, path: &[usize]) -> Option<&Span> {
+        if path.is_empty() {
+            return Some(&self.span);
+        }
+        let (child, consumed) = self.child(path)?;
+        child.get(&path[consumed..])
+    }
+
+    /// The path and span of the innermost item covering `offset`, if it is
+    /// within the root item.
+    ///
+    /// ```rust
+    /// let (_, spans) = cbor_diag::parse_bytes_with_spans(b"\x82\x01\x41\x00").unwrap();
+    /// let (path, span) = spans.item_at(3).unwrap();
+    /// assert_eq!(path, [1]);
+    /// assert_eq!((span.header.clone(), span.content.clone()), (2..3, 3..4));
+    /// ```
+    pub fn item_at(&self, offset: usize) -> Option<(Vec<usize>, &Span)> {
+        if !self.span.range().contains(&offset) {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut node = self;
+        while let Some(index) = node
+            .children
+            .iter()
+            .position(|child| child.span.range().contains(&offset))
+        {
+            if node.is_map {
+                path.extend_from_slice(&[index / 2, index % 2]);
+            } else {
+                path.push(index);
+            }
+            node = &node.children[index];
+        }
+        Some((path, &node.span))
+    }
+}
+
+/// The binary encoding a data item was parsed from, see
+/// [`parse_bytes_preserving`](crate::parse_bytes_preserving).
+///
+/// This keeps the item as it was parsed, to find what has changed in it when
+/// re-encoding with [`DataItem::to_bytes_preserving`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct OriginalEncoding {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) item: DataItem,
+    pub(crate) spans: SpanTree,
+}
+
+impl OriginalEncoding {
+    /// The bytes the item was parsed from.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The item as it was parsed.
+    pub fn item(&self) -> &DataItem {
+        &self.item
+    }
+
+    /// The byte ranges each part of the item was parsed from.
+    pub fn spans(&self) -> &SpanTree {
+        &self.spans
+    }
+}
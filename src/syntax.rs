@@ -1,3 +1,7 @@
+use std::convert::TryFrom;
+
+use crate::Encoding;
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// How many additional bytes are used to encode this integer (in bits).
 ///
@@ -36,6 +40,119 @@ pub enum FloatWidth {
     SixtyFour,
 }
 
+impl IntegerWidth {
+    /// The narrowest width that can encode `value` as an argument, the
+    /// preferred encoding of it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cbor_diag::IntegerWidth;
+    ///
+    /// assert_eq!(IntegerWidth::minimal_for(23), IntegerWidth::Zero);
+    /// assert_eq!(IntegerWidth::minimal_for(24), IntegerWidth::Eight);
+    /// assert_eq!(IntegerWidth::minimal_for(65536), IntegerWidth::ThirtyTwo);
+    /// ```
+    pub fn minimal_for(value: u64) -> Self {
+        const U8_MAX: u64 = u8::max_value() as u64;
+        const U16_MAX: u64 = u16::max_value() as u64;
+        const U32_MAX: u64 = u32::max_value() as u64;
+        const U64_MAX: u64 = u64::max_value();
+
+        #[allow(clippy::match_overlapping_arm)]
+        match value {
+            0..=23 => IntegerWidth::Zero,
+            0..=U8_MAX => IntegerWidth::Eight,
+            0..=U16_MAX => IntegerWidth::Sixteen,
+            0..=U32_MAX => IntegerWidth::ThirtyTwo,
+            0..=U64_MAX => IntegerWidth::SixtyFour,
+        }
+    }
+
+    /// How many bytes follow the initial byte for an argument of this width.
+    ///
+    /// [`IntegerWidth::Unknown`] has no length of its own, it is encoded with
+    /// the [minimal width](IntegerWidth::minimal_for) for the value, so this
+    /// returns 0 for it.
+    pub fn byte_len(self) -> usize {
+        match self {
+            IntegerWidth::Unknown | IntegerWidth::Zero => 0,
+            IntegerWidth::Eight => 1,
+            IntegerWidth::Sixteen => 2,
+            IntegerWidth::ThirtyTwo => 4,
+            IntegerWidth::SixtyFour => 8,
+        }
+    }
+
+    /// The digit of the encoding indicator in diagnostic notation for this
+    /// width, such as `1` for `_1`, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cbor_diag::IntegerWidth;
+    ///
+    /// assert_eq!(IntegerWidth::Eight.indicator(), Some(0));
+    /// assert_eq!(IntegerWidth::Zero.indicator(), None);
+    /// ```
+    pub fn indicator(self) -> Option<u8> {
+        match self {
+            IntegerWidth::Unknown | IntegerWidth::Zero => None,
+            IntegerWidth::Eight => Some(0),
+            IntegerWidth::Sixteen => Some(1),
+            IntegerWidth::ThirtyTwo => Some(2),
+            IntegerWidth::SixtyFour => Some(3),
+        }
+    }
+}
+
+impl FloatWidth {
+    /// The narrowest width that can encode `value` exactly, keeping the sign
+    /// and payload of NaNs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cbor_diag::FloatWidth;
+    ///
+    /// assert_eq!(FloatWidth::minimal_for(1.5), FloatWidth::Sixteen);
+    /// assert_eq!(FloatWidth::minimal_for(100000.0), FloatWidth::ThirtyTwo);
+    /// assert_eq!(FloatWidth::minimal_for(0.1), FloatWidth::SixtyFour);
+    /// ```
+    pub fn minimal_for(value: f64) -> Self {
+        if half::f16::from_f64(value).to_f64().to_bits() == value.to_bits() {
+            FloatWidth::Sixteen
+        } else if f64::from(value as f32).to_bits() == value.to_bits() {
+            FloatWidth::ThirtyTwo
+        } else {
+            FloatWidth::SixtyFour
+        }
+    }
+
+    /// How many bytes follow the initial byte for a float of this width.
+    ///
+    /// [`FloatWidth::Unknown`] is encoded with 64 bits, so this returns 8 for
+    /// it.
+    pub fn byte_len(self) -> usize {
+        match self {
+            FloatWidth::Sixteen => 2,
+            FloatWidth::ThirtyTwo => 4,
+            FloatWidth::Unknown | FloatWidth::SixtyFour => 8,
+        }
+    }
+
+    /// The digit of the encoding indicator in diagnostic notation for this
+    /// width, such as `1` for `_1`, if it has one.
+    pub fn indicator(self) -> Option<u8> {
+        match self {
+            FloatWidth::Unknown => None,
+            FloatWidth::Sixteen => Some(1),
+            FloatWidth::ThirtyTwo => Some(2),
+            FloatWidth::SixtyFour => Some(3),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// A semantic tag for a CBOR data item.
 ///
@@ -65,6 +182,14 @@ pub struct ByteString {
     pub data: Vec<u8>,
     /// The bitwidth used for encoding the length
     pub bitwidth: IntegerWidth,
+    /// How to show the data in diagnostic notation, when parsed from
+    /// diagnostic notation this is the literal form used
+    ///
+    /// If [`None`] it is shown as hinted by any enclosing tag, or as
+    /// configured by [`DiagFormatOptions::bytestring_encoding`].
+    ///
+    /// [`DiagFormatOptions::bytestring_encoding`]: crate::DiagFormatOptions::bytestring_encoding
+    pub encoding: Option<Encoding>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -82,7 +207,7 @@ pub struct TextString {
     pub bitwidth: IntegerWidth,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug)]
 /// A CBOR data item.
 ///
 /// See [RFC 7049 § 1.2: Data item][RFC 1.2].
@@ -142,6 +267,21 @@ pub enum DataItem {
     /// [RFC 2.2.2]: https://tools.ietf.org/html/rfc7049#section-2.2.2
     IndefiniteTextString(Vec<TextString>),
 
+    /// A text string containing invalid UTF-8, stored as the raw bytes.
+    ///
+    /// This is only produced when parsing with
+    /// [`ParseOptions::allow_invalid_utf8`](crate::ParseOptions::allow_invalid_utf8)
+    /// set, to allow inspecting malformed data. It is encoded back to exactly
+    /// the same bytes, but cannot be represented in diagnostic notation so is
+    /// shown there as a byte string.
+    InvalidTextString(ByteString),
+
+    /// An indefinite length text string where at least one chunk contains
+    /// invalid UTF-8, stored as the raw bytes of each chunk.
+    ///
+    /// See [`DataItem::InvalidTextString`] for more details.
+    InvalidIndefiniteTextString(Vec<ByteString>),
+
     /// An array of data items.
     ///
     /// See [RFC 7049 § 2.1: Major type 4][RFC 2.1].
@@ -209,6 +349,21 @@ pub enum DataItem {
     ///
     /// See the docs for [`Simple`] for more details.
     Simple(Simple),
+
+    /// An application-oriented literal from diagnostic notation, such as
+    /// `foo'content'`, that was not lowered to a data item while parsing.
+    ///
+    /// These are only produced when parsing with
+    /// [`ParseOptions::allow_unknown_app_literals`](crate::ParseOptions::allow_unknown_app_literals),
+    /// and have no binary encoding; they are shown again as written in
+    /// diagnostic notation.
+    AppLiteral {
+        /// The prefix identifying the application extension.
+        prefix: String,
+
+        /// The content of the literal, with any escapes processed.
+        content: String,
+    },
 }
 
 impl Simple {
@@ -240,6 +395,78 @@ impl Simple {
     /// [RFC 2.3]: https://tools.ietf.org/html/rfc7049#section-2.3
     /// concept.
     pub const UNDEFINED: Simple = Simple(23);
+
+    /// Create a simple value, failing for the values 24 to 31 that are
+    /// reserved because they can't be encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cbor_diag::Simple;
+    ///
+    /// assert_eq!(Simple::new(16).unwrap(), Simple(16));
+    /// assert!(Simple::new(24).is_err());
+    /// ```
+    pub fn new(value: u8) -> crate::Result<Simple> {
+        let simple = Simple(value);
+        if simple.is_reserved() {
+            return Err(format!("simple({}) is reserved", value).into());
+        }
+        Ok(simple)
+    }
+
+    /// Whether this is [`Simple::FALSE`] or [`Simple::TRUE`].
+    pub fn is_bool(self) -> bool {
+        self == Simple::FALSE || self == Simple::TRUE
+    }
+
+    /// Whether this is [`Simple::NULL`].
+    pub fn is_null(self) -> bool {
+        self == Simple::NULL
+    }
+
+    /// Whether this is [`Simple::UNDEFINED`].
+    pub fn is_undefined(self) -> bool {
+        self == Simple::UNDEFINED
+    }
+
+    /// Whether this is one of the values 24 to 31, which are reserved by [RFC
+    /// 8949 § 3.3][RFC 3.3] and not well-formed when encoded.
+    ///
+    /// [RFC 3.3]: https://www.rfc-editor.org/rfc/rfc8949.html#section-3.3
+    pub fn is_reserved(self) -> bool {
+        (24..=31).contains(&self.0)
+    }
+
+    /// Whether this is a value with no assigned meaning yet, 0 to 19 or 32 to
+    /// 255.
+    pub fn is_unassigned(self) -> bool {
+        !(20..=31).contains(&self.0)
+    }
+}
+
+impl From<bool> for Simple {
+    fn from(value: bool) -> Self {
+        if value {
+            Simple::TRUE
+        } else {
+            Simple::FALSE
+        }
+    }
+}
+
+/// Fails with the original value if it isn't [`Simple::FALSE`] or
+/// [`Simple::TRUE`].
+impl TryFrom<Simple> for bool {
+    type Error = Simple;
+
+    fn try_from(simple: Simple) -> Result<Self, Simple> {
+        match simple {
+            Simple::FALSE => Ok(false),
+            Simple::TRUE => Ok(true),
+            _ => Err(simple),
+        }
+    }
 }
 
 impl Tag {
@@ -256,6 +483,9 @@ impl Tag {
     /// [RFC 2.4.1]: https://tools.ietf.org/html/rfc7049#section-2.4.1
     pub const DATETIME: Tag = Tag(0);
 
+    /// An alias of [`Tag::DATETIME`], matching its name in the IANA registry.
+    pub const DATETIME_STRING: Tag = Tag::DATETIME;
+
     /// An "Epoch-based date/time"; must only be applied to an [unsigned
     /// integer](DataItem::Integer), [negative integer](DataItem::Negative) or
     /// [floating point](DataItem::Float) data item.
@@ -327,6 +557,37 @@ impl Tag {
     /// [RFC 2.4.3]: https://tools.ietf.org/html/rfc7049#section-2.4.3
     pub const BIGFLOAT: Tag = Tag(5);
 
+    /// A reference to an item in the shared table of an enclosing [packed
+    /// CBOR table setup](Tag::PACKED_TABLE_SETUP); applied to an [unsigned
+    /// integer](DataItem::Integer) or [negative
+    /// integer](DataItem::Negative) data item.
+    ///
+    /// Defined in [draft-ietf-cbor-packed § 2.2][Packed 2.2].
+    ///
+    /// [Packed 2.2]: https://datatracker.ietf.org/doc/html/draft-ietf-cbor-packed#section-2.2
+    pub const PACKED_REFERENCE: Tag = Tag(6);
+
+    /// A COSE single recipient encrypted data object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_ENCRYPT0: Tag = Tag(16);
+
+    /// A COSE MAC without recipients object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_MAC0: Tag = Tag(17);
+
+    /// A COSE single signer data object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_SIGN1: Tag = Tag(18);
+
     /// Sets the expected encoding of any [byte strings](DataItem::ByteString)
     /// contained in the data item to be "base64url"; can be applied to any sort
     /// of data item.
@@ -379,6 +640,42 @@ impl Tag {
     /// [RFC 2.4.4.1]: https://tools.ietf.org/html/rfc7049#section-2.4.4.1
     pub const ENCODED_CBOR: Tag = Tag(24);
 
+    /// A reference to a previously seen string by its index; must only be
+    /// applied to an [unsigned integer](DataItem::Integer) data item within a
+    /// [string reference namespace](Tag::STRING_REFERENCE_NAMESPACE).
+    ///
+    /// Defined in [non-RFC specification][StringrefSpec].
+    ///
+    /// [StringrefSpec]: http://cbor.schmorp.de/stringref
+    pub const STRING_REFERENCE: Tag = Tag(25);
+
+    /// Marks this item as one that may be referred to by a [shared
+    /// reference](Tag::SHARED_REFERENCE); can be applied to any type of data
+    /// item.
+    ///
+    /// Defined in [non-RFC specification][ValueSharingSpec].
+    ///
+    /// [ValueSharingSpec]: http://cbor.schmorp.de/value-sharing
+    pub const SHAREABLE: Tag = Tag(28);
+
+    /// A reference to a previously seen [shareable](Tag::SHAREABLE) item by
+    /// its index; must only be applied to an [unsigned
+    /// integer](DataItem::Integer) data item.
+    ///
+    /// Defined in [non-RFC specification][ValueSharingSpec].
+    ///
+    /// [ValueSharingSpec]: http://cbor.schmorp.de/value-sharing
+    pub const SHARED_REFERENCE: Tag = Tag(29);
+
+    /// Marks this item as being a rational number; must only be applied to an
+    /// [array](DataItem::Array) of two integers or bignums, the numerator and
+    /// a positive denominator.
+    ///
+    /// Defined in [non-RFC specification][RationalSpec].
+    ///
+    /// [RationalSpec]: http://peteroupc.github.io/CBOR/rational.html
+    pub const RATIONAL: Tag = Tag(30);
+
     /// Marks this item as being a valid URI; must only be applied
     /// to a [text string](DataItem::TextString) (or the [indefinite
     /// variant](DataItem::IndefiniteTextString) of) data item.
@@ -455,6 +752,95 @@ impl Tag {
     /// [UuidSpec]: https://github.com/lucas-clemente/cbor-specs/blob/master/uuid.md
     pub const UUID: Tag = Tag(37);
 
+    /// Marks this item as being a text string with a language tag; must only
+    /// be applied to an [array](DataItem::Array) of a language tag and a text
+    /// string.
+    ///
+    /// Defined in [non-RFC specification][LangtagsSpec].
+    ///
+    /// [LangtagsSpec]: http://peteroupc.github.io/CBOR/langtags.html
+    pub const LANGUAGE_TAGGED_STRING: Tag = Tag(38);
+
+    /// Marks this item as being an IPv4 address, or a prefix or interface
+    /// definition.
+    ///
+    /// Defined in [RFC 9164 § 3][RFC 9164 3].
+    ///
+    /// [RFC 9164 3]: https://www.rfc-editor.org/rfc/rfc9164.html#section-3
+    pub const IPV4: Tag = Tag(52);
+
+    /// Marks this item as being an IPv6 address, or a prefix or interface
+    /// definition.
+    ///
+    /// Defined in [RFC 9164 § 3][RFC 9164 3].
+    ///
+    /// [RFC 9164 3]: https://www.rfc-editor.org/rfc/rfc9164.html#section-3
+    pub const IPV6: Tag = Tag(54);
+
+    /// Marks this item as being a CBOR Web Token.
+    ///
+    /// Defined in [RFC 8392 § 6][RFC 8392 6].
+    ///
+    /// [RFC 8392 6]: https://www.rfc-editor.org/rfc/rfc8392.html#section-6
+    pub const CWT: Tag = Tag(61);
+
+    /// A COSE encrypted data object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_ENCRYPT: Tag = Tag(96);
+
+    /// A COSE MAC object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_MAC: Tag = Tag(97);
+
+    /// A COSE signed data object.
+    ///
+    /// Defined in [RFC 9052 § 2][RFC 9052 2].
+    ///
+    /// [RFC 9052 2]: https://www.rfc-editor.org/rfc/rfc9052.html#section-2
+    pub const COSE_SIGN: Tag = Tag(98);
+
+    /// Sets up a packed CBOR table for use by references within the rump;
+    /// must only be applied to an [array](DataItem::Array) of the table items
+    /// followed by the rump.
+    ///
+    /// Defined in [draft-ietf-cbor-packed § 3.1][Packed 3.1].
+    ///
+    /// [Packed 3.1]: https://datatracker.ietf.org/doc/html/draft-ietf-cbor-packed#section-3.1
+    pub const PACKED_TABLE_SETUP: Tag = Tag(113);
+
+    /// Marks this item as being a date as the number of days since
+    /// 1970-01-01; must only be applied to an [unsigned
+    /// integer](DataItem::Integer) or [negative integer](DataItem::Negative)
+    /// data item.
+    ///
+    /// Defined in [RFC 8943 § 3][RFC 8943 3].
+    ///
+    /// [RFC 8943 3]: https://tools.ietf.org/html/rfc8943#section-3
+    pub const EPOCH_DATE: Tag = Tag(100);
+
+    /// Marks this item as a namespace for [string
+    /// references](Tag::STRING_REFERENCE); can be applied to any type of data
+    /// item.
+    ///
+    /// Defined in [non-RFC specification][StringrefSpec].
+    ///
+    /// [StringrefSpec]: http://cbor.schmorp.de/stringref
+    pub const STRING_REFERENCE_NAMESPACE: Tag = Tag(256);
+
+    /// Marks this item as being a mathematical finite set; must only be
+    /// applied to an [array](DataItem::Array) data item.
+    ///
+    /// Defined in [non-RFC specification][SetSpec].
+    ///
+    /// [SetSpec]: https://github.com/input-output-hk/cbor-sets-spec/blob/master/CBOR_SETS.md
+    pub const SET: Tag = Tag(258);
+
     /// Marks this item as being a Network Address (IPv4 or IPv6 or MAC
     /// Address); must only be applied to a [byte string](DataItem::ByteString)
     /// (or the [indefinite variant](DataItem::IndefiniteByteString) of) data
@@ -467,6 +853,48 @@ impl Tag {
     /// [NetworkAddressSpec]: http://www.employees.org/~ravir/cbor-network.txt
     pub const NETWORK_ADDRESS: Tag = Tag(260);
 
+    /// Marks this item as being an extended time; must only be applied to a
+    /// [map](DataItem::Map), or a number as for [`Tag::EPOCH_DATETIME`].
+    ///
+    /// Defined in [RFC 9581 § 3][RFC 9581 3].
+    ///
+    /// [RFC 9581 3]: https://www.rfc-editor.org/rfc/rfc9581.html#section-3
+    pub const EXTENDED_TIME: Tag = Tag(1001);
+
+    /// Marks this item as being a duration; applied to the same types of data
+    /// items as [`Tag::EXTENDED_TIME`].
+    ///
+    /// Defined in [RFC 9581 § 4][RFC 9581 4].
+    ///
+    /// [RFC 9581 4]: https://www.rfc-editor.org/rfc/rfc9581.html#section-4
+    pub const DURATION: Tag = Tag(1002);
+
+    /// Marks this item as being a period of time; must only be applied to an
+    /// [array](DataItem::Array) of a start, end and duration.
+    ///
+    /// Defined in [RFC 9581 § 5][RFC 9581 5].
+    ///
+    /// [RFC 9581 5]: https://www.rfc-editor.org/rfc/rfc9581.html#section-5
+    pub const PERIOD: Tag = Tag(1003);
+
+    /// Sets up separate packed CBOR shared and argument tables for use by
+    /// references within the rump; must only be applied to an
+    /// [array](DataItem::Array) of the shared table, argument table and rump.
+    ///
+    /// Defined in [draft-ietf-cbor-packed § 3.1][Packed 3.1].
+    ///
+    /// [Packed 3.1]: https://datatracker.ietf.org/doc/html/draft-ietf-cbor-packed#section-3.1
+    pub const PACKED_SPLIT_TABLE_SETUP: Tag = Tag(1113);
+
+    /// Marks this item as being an RFC 3339 `full-date` (`YYYY-MM-DD`); must
+    /// only be applied to a [text string](DataItem::TextString) (or the
+    /// [indefinite variant](DataItem::IndefiniteTextString) of) data item.
+    ///
+    /// Defined in [RFC 8943 § 3][RFC 8943 3].
+    ///
+    /// [RFC 8943 3]: https://tools.ietf.org/html/rfc8943#section-3
+    pub const DATE: Tag = Tag(1004);
+
     /// Marks this item as being CBOR, a no-op; can be applied to any type of
     /// data item.
     ///
@@ -478,4 +906,487 @@ impl Tag {
     /// [RFC 2.4]: https://tools.ietf.org/html/rfc7049#section-2.4
     /// [RFC 2.4.5]: https://tools.ietf.org/html/rfc7049#section-2.4.5
     pub const SELF_DESCRIBE_CBOR: Tag = Tag(55799);
+
+    /// An alias of [`Tag::SELF_DESCRIBE_CBOR`], matching its name in the IANA
+    /// registry.
+    pub const SELF_DESCRIBED: Tag = Tag::SELF_DESCRIBE_CBOR;
+
+    /// Marks the start of a file containing a CBOR sequence, a no-op; must
+    /// only be applied to the [byte string](DataItem::ByteString) `'BOR'`.
+    ///
+    /// Defined in [RFC 9277 § 3][RFC 9277 3].
+    ///
+    /// [RFC 9277 3]: https://www.rfc-editor.org/rfc/rfc9277.html#section-3
+    pub const SELF_DESCRIBE_CBOR_SEQUENCE: Tag = Tag(55800);
+}
+
+impl From<u64> for Tag {
+    fn from(tag: u64) -> Self {
+        Tag(tag)
+    }
+}
+
+impl From<Tag> for u64 {
+    fn from(Tag(tag): Tag) -> Self {
+        tag
+    }
+}
+
+impl PartialEq<u64> for Tag {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<Tag> for u64 {
+    fn eq(&self, other: &Tag) -> bool {
+        *self == other.0
+    }
+}
+
+impl DataItem {
+    /// The value of an [unsigned](DataItem::Integer) or
+    /// [negative](DataItem::Negative) integer, or [`None`] if this is anything
+    /// else.
+    ///
+    /// Every value from `-2^64` to `2^64 - 1` fits in an `i128`, so this
+    /// covers the full range of both, unlike an `i64`.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_bytes(b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap();
+    /// assert_eq!(item.as_i128(), Some(-(1 << 64)));
+    ///
+    /// let item = cbor_diag::parse_bytes(b"\x1b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap();
+    /// assert_eq!(item.as_i128(), Some(u64::MAX.into()));
+    /// ```
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            DataItem::Integer { value, .. } => Some(value.into()),
+            DataItem::Negative { value, .. } => Some(-1 - i128::from(value)),
+            _ => None,
+        }
+    }
+
+    /// Peels off all tags wrapping this item, returning them outermost first
+    /// along with the innermost value.
+    ///
+    /// ```rust
+    /// use cbor_diag::{DataItem, IntegerWidth, Tag};
+    ///
+    /// // 55799(24(h'01'))
+    /// let item = cbor_diag::parse_bytes(b"\xd9\xd9\xf7\xd8\x18\x41\x01").unwrap();
+    /// let (tags, value) = item.tag_chain();
+    /// assert_eq!(
+    ///     tags,
+    ///     [
+    ///         (Tag::SELF_DESCRIBED, IntegerWidth::Sixteen),
+    ///         (Tag::ENCODED_CBOR, IntegerWidth::Eight),
+    ///     ]
+    /// );
+    /// assert_eq!(DataItem::retag(&tags, value.clone()), item);
+    /// ```
+    pub fn tag_chain(&self) -> (Vec<(Tag, IntegerWidth)>, &DataItem) {
+        let mut tags = Vec::new();
+        let mut item = self;
+        while let DataItem::Tag {
+            tag,
+            bitwidth,
+            value,
+        } = item
+        {
+            tags.push((*tag, *bitwidth));
+            item = value;
+        }
+        (tags, item)
+    }
+
+    /// Wraps `value` in `tags`, outermost first, the inverse of
+    /// [`DataItem::tag_chain`].
+    pub fn retag(tags: &[(Tag, IntegerWidth)], value: DataItem) -> DataItem {
+        tags.iter()
+            .rev()
+            .fold(value, |value, &(tag, bitwidth)| DataItem::Tag {
+                tag,
+                bitwidth,
+                value: Box::new(value),
+            })
+    }
+
+    /// Take the content of a byte string, concatenating the chunks of an
+    /// indefinite length one, or return the item unchanged if it is anything
+    /// else.
+    ///
+    /// ```rust
+    /// let item = cbor_diag::parse_diag("(_ h'0102', h'03')").unwrap();
+    /// assert_eq!(item.into_bytes(), Ok(vec![1, 2, 3]));
+    ///
+    /// let item = cbor_diag::parse_diag("1").unwrap();
+    /// assert_eq!(item.clone().into_bytes(), Err(item));
+    /// ```
+    pub fn into_bytes(mut self) -> Result<Vec<u8>, Self> {
+        match &mut self {
+            DataItem::ByteString(bytestring) => Ok(std::mem::take(&mut bytestring.data)),
+            DataItem::IndefiniteByteString(chunks) => Ok(chunks
+                .iter()
+                .flat_map(|chunk| chunk.data.iter().copied())
+                .collect()),
+            _ => Err(self),
+        }
+    }
+
+    /// Take the content of a text string, concatenating the chunks of an
+    /// indefinite length one, or return the item unchanged if it is anything
+    /// else.
+    pub fn into_text(mut self) -> Result<String, Self> {
+        match &mut self {
+            DataItem::TextString(textstring) => Ok(std::mem::take(&mut textstring.data)),
+            DataItem::IndefiniteTextString(chunks) => {
+                Ok(chunks.iter().map(|chunk| chunk.data.as_str()).collect())
+            }
+            _ => Err(self),
+        }
+    }
+
+    /// Take the items of an array, or return the item unchanged if it is
+    /// anything else.
+    pub fn into_array(mut self) -> Result<Vec<DataItem>, Self> {
+        match &mut self {
+            DataItem::Array { data, .. } => Ok(std::mem::take(data)),
+            _ => Err(self),
+        }
+    }
+
+    /// Take the entries of a map, or return the item unchanged if it is
+    /// anything else.
+    pub fn into_map(mut self) -> Result<Vec<(DataItem, DataItem)>, Self> {
+        match &mut self {
+            DataItem::Map { data, .. } => Ok(std::mem::take(data)),
+            _ => Err(self),
+        }
+    }
+
+    /// Take the tag and value of a tagged item, or return the item unchanged
+    /// if it is anything else.
+    ///
+    /// Only the outermost tag is removed, see [`DataItem::tag_chain`] for
+    /// nested tags.
+    pub fn into_tagged(mut self) -> Result<(Tag, DataItem), Self> {
+        match &mut self {
+            DataItem::Tag { tag, value, .. } => Ok((
+                *tag,
+                std::mem::replace(&mut **value, DataItem::Simple(Simple::UNDEFINED)),
+            )),
+            _ => Err(self),
+        }
+    }
+}
+
+/// The state of a container part way through being cloned by
+/// [`DataItem::clone`].
+enum CloneFrame<'a> {
+    Array {
+        items: std::slice::Iter<'a, DataItem>,
+        data: Vec<DataItem>,
+        bitwidth: Option<IntegerWidth>,
+    },
+    Map {
+        entries: std::slice::Iter<'a, (DataItem, DataItem)>,
+        /// The value of the entry whose key is being cloned
+        value: Option<&'a DataItem>,
+        /// The clone of the key of the entry whose value is being cloned
+        key: Option<DataItem>,
+        data: Vec<(DataItem, DataItem)>,
+        bitwidth: Option<IntegerWidth>,
+    },
+    Tag {
+        tag: Tag,
+        bitwidth: IntegerWidth,
+        value: Option<&'a DataItem>,
+        cloned: Option<DataItem>,
+    },
+}
+
+impl<'a> CloneFrame<'a> {
+    /// The next item directly within the container to clone.
+    fn next(&mut self) -> Option<&'a DataItem> {
+        match self {
+            CloneFrame::Array { items, .. } => items.next(),
+            CloneFrame::Map { entries, value, .. } => value.take().or_else(|| {
+                let (key, next_value) = entries.next()?;
+                *value = Some(next_value);
+                Some(key)
+            }),
+            CloneFrame::Tag { value, .. } => value.take(),
+        }
+    }
+
+    /// Add the clone of the item last returned from [`CloneFrame::next`].
+    fn push(&mut self, item: DataItem) {
+        match self {
+            CloneFrame::Array { data, .. } => data.push(item),
+            CloneFrame::Map { key, data, .. } => match key.take() {
+                Some(key) => data.push((key, item)),
+                None => *key = Some(item),
+            },
+            CloneFrame::Tag { cloned, .. } => *cloned = Some(item),
+        }
+    }
+
+    fn finish(self) -> DataItem {
+        match self {
+            CloneFrame::Array { data, bitwidth, .. } => DataItem::Array { data, bitwidth },
+            CloneFrame::Map { data, bitwidth, .. } => DataItem::Map { data, bitwidth },
+            CloneFrame::Tag {
+                tag,
+                bitwidth,
+                cloned,
+                ..
+            } => DataItem::Tag {
+                tag,
+                bitwidth,
+                value: Box::new(cloned.expect("the tagged value is cloned before finishing")),
+            },
+        }
+    }
+}
+
+impl DataItem {
+    /// Whether this item contains other data items.
+    fn is_nested(&self) -> bool {
+        matches!(
+            self,
+            DataItem::Array { .. } | DataItem::Map { .. } | DataItem::Tag { .. }
+        )
+    }
+
+    /// Move the nested items directly within this item into `nested`,
+    /// dropping the rest.
+    fn take_nested(&mut self, nested: &mut Vec<DataItem>) {
+        let mut push = |item: DataItem| {
+            if item.is_nested() {
+                nested.push(item);
+            }
+        };
+        match self {
+            DataItem::Array { data, .. } => data.drain(..).for_each(push),
+            DataItem::Map { data, .. } => data.drain(..).for_each(|(key, value)| {
+                push(key);
+                push(value);
+            }),
+            DataItem::Tag { value, .. } => push(std::mem::replace(
+                &mut **value,
+                DataItem::Simple(Simple::UNDEFINED),
+            )),
+            _ => {}
+        }
+    }
+}
+
+// `Drop`, `Clone` and `PartialEq` are implemented with explicit stacks rather
+// than derived, as the derived recursive implementations overflow the stack on
+// deeply nested items, such as those parsed from a few megabytes of `0x81`.
+
+impl Drop for DataItem {
+    fn drop(&mut self) {
+        let mut nested = Vec::new();
+        self.take_nested(&mut nested);
+        while let Some(mut item) = nested.pop() {
+            item.take_nested(&mut nested);
+        }
+    }
+}
+
+impl Clone for DataItem {
+    fn clone(&self) -> Self {
+        let mut frames = Vec::new();
+        let mut next = self;
+        loop {
+            let mut cloned = match next {
+                DataItem::Array { data, bitwidth } => {
+                    frames.push(CloneFrame::Array {
+                        items: data.iter(),
+                        data: Vec::with_capacity(data.len()),
+                        bitwidth: *bitwidth,
+                    });
+                    None
+                }
+                DataItem::Map { data, bitwidth } => {
+                    frames.push(CloneFrame::Map {
+                        entries: data.iter(),
+                        value: None,
+                        key: None,
+                        data: Vec::with_capacity(data.len()),
+                        bitwidth: *bitwidth,
+                    });
+                    None
+                }
+                DataItem::Tag {
+                    tag,
+                    bitwidth,
+                    value,
+                } => {
+                    frames.push(CloneFrame::Tag {
+                        tag: *tag,
+                        bitwidth: *bitwidth,
+                        value: Some(value),
+                        cloned: None,
+                    });
+                    None
+                }
+                DataItem::Integer { value, bitwidth } => Some(DataItem::Integer {
+                    value: *value,
+                    bitwidth: *bitwidth,
+                }),
+                DataItem::Negative { value, bitwidth } => Some(DataItem::Negative {
+                    value: *value,
+                    bitwidth: *bitwidth,
+                }),
+                DataItem::ByteString(string) => Some(DataItem::ByteString(string.clone())),
+                DataItem::TextString(string) => Some(DataItem::TextString(string.clone())),
+                DataItem::IndefiniteByteString(strings) => {
+                    Some(DataItem::IndefiniteByteString(strings.clone()))
+                }
+                DataItem::IndefiniteTextString(strings) => {
+                    Some(DataItem::IndefiniteTextString(strings.clone()))
+                }
+                DataItem::InvalidTextString(string) => {
+                    Some(DataItem::InvalidTextString(string.clone()))
+                }
+                DataItem::InvalidIndefiniteTextString(strings) => {
+                    Some(DataItem::InvalidIndefiniteTextString(strings.clone()))
+                }
+                DataItem::Float { value, bitwidth } => Some(DataItem::Float {
+                    value: *value,
+                    bitwidth: *bitwidth,
+                }),
+                DataItem::Simple(simple) => Some(DataItem::Simple(*simple)),
+                DataItem::AppLiteral { prefix, content } => Some(DataItem::AppLiteral {
+                    prefix: prefix.clone(),
+                    content: content.clone(),
+                }),
+            };
+
+            // Pass finished items up to their containers until one has more
+            // items to clone
+            loop {
+                let frame = match frames.last_mut() {
+                    Some(frame) => frame,
+                    None => return cloned.expect("the root item is cloned before returning"),
+                };
+                if let Some(item) = cloned.take() {
+                    frame.push(item);
+                }
+                match frame.next() {
+                    Some(item) => {
+                        next = item;
+                        break;
+                    }
+                    None => cloned = frames.pop().map(CloneFrame::finish),
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for DataItem {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pairs = Vec::new();
+        let mut pair = (self, other);
+        loop {
+            let equal = match pair {
+                (
+                    DataItem::Array { data, bitwidth },
+                    DataItem::Array {
+                        data: other_data,
+                        bitwidth: other_bitwidth,
+                    },
+                ) => {
+                    pairs.extend(data.iter().zip(other_data));
+                    data.len() == other_data.len() && bitwidth == other_bitwidth
+                }
+                (
+                    DataItem::Map { data, bitwidth },
+                    DataItem::Map {
+                        data: other_data,
+                        bitwidth: other_bitwidth,
+                    },
+                ) => {
+                    for ((key, value), (other_key, other_value)) in data.iter().zip(other_data) {
+                        pairs.push((key, other_key));
+                        pairs.push((value, other_value));
+                    }
+                    data.len() == other_data.len() && bitwidth == other_bitwidth
+                }
+                (
+                    DataItem::Tag {
+                        tag,
+                        bitwidth,
+                        value,
+                    },
+                    DataItem::Tag {
+                        tag: other_tag,
+                        bitwidth: other_bitwidth,
+                        value: other_value,
+                    },
+                ) => {
+                    pairs.push((value, other_value));
+                    tag == other_tag && bitwidth == other_bitwidth
+                }
+                (
+                    DataItem::Integer { value, bitwidth },
+                    DataItem::Integer {
+                        value: other_value,
+                        bitwidth: other_bitwidth,
+                    },
+                )
+                | (
+                    DataItem::Negative { value, bitwidth },
+                    DataItem::Negative {
+                        value: other_value,
+                        bitwidth: other_bitwidth,
+                    },
+                ) => value == other_value && bitwidth == other_bitwidth,
+                (DataItem::ByteString(string), DataItem::ByteString(other))
+                | (DataItem::InvalidTextString(string), DataItem::InvalidTextString(other)) => {
+                    string == other
+                }
+                (DataItem::TextString(string), DataItem::TextString(other)) => string == other,
+                (
+                    DataItem::IndefiniteByteString(strings),
+                    DataItem::IndefiniteByteString(other),
+                )
+                | (
+                    DataItem::InvalidIndefiniteTextString(strings),
+                    DataItem::InvalidIndefiniteTextString(other),
+                ) => strings == other,
+                (
+                    DataItem::IndefiniteTextString(strings),
+                    DataItem::IndefiniteTextString(other),
+                ) => strings == other,
+                (
+                    DataItem::Float { value, bitwidth },
+                    DataItem::Float {
+                        value: other_value,
+                        bitwidth: other_bitwidth,
+                    },
+                ) => value == other_value && bitwidth == other_bitwidth,
+                (DataItem::Simple(simple), DataItem::Simple(other)) => simple == other,
+                (
+                    DataItem::AppLiteral { prefix, content },
+                    DataItem::AppLiteral {
+                        prefix: other_prefix,
+                        content: other_content,
+                    },
+                ) => prefix == other_prefix && content == other_content,
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+            match pairs.pop() {
+                Some(next) => pair = next,
+                None => return true,
+            }
+        }
+    }
 }
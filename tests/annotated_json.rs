@@ -0,0 +1,207 @@
+#![cfg(feature = "serde")]
+
+use cbor_diag::{parse_diag, parse_hex, AnnotatedJsonOptions, DataItem, HexOptions};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const COSE_SIGN1: &str = indoc!(
+    "
+    d2 84 43 a1 01 26 a1 04 42 31 31 54 54 68 69 73
+    20 69 73 20 74 68 65 20 63 6f 6e 74 65 6e 74 2e
+    58 40 8e b3 3e 4c a3 1d 1c 46 5a b0 5a ac 34 cc
+    6b 23 d5 8f ef 5c 08 31 06 c4 d2 5a 91 ae f0 b0
+    11 7e 2a f9 a2 91 aa 32 e1 4a b8 34 dc 56 ed 2a
+    22 34 44 54 7e 01 f1 1d 3b 09 16 e5 a4 c3 45 ca
+    cb 36
+"
+);
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Check every node has the documented fields with the right types, and that
+/// children are contained within their parent.
+fn check_schema(node: &serde_json::Value, bytes: &[u8]) {
+    let fields = [
+        "offset",
+        "length",
+        "header_hex",
+        "kind",
+        "comment",
+        "value",
+        "children",
+    ];
+    let object = node.as_object().expect("node is an object");
+    for key in object.keys() {
+        assert!(fields.contains(&key.as_str()), "unexpected field {}", key);
+    }
+
+    let offset = node["offset"].as_u64().expect("offset") as usize;
+    let length = node["length"].as_u64().expect("length") as usize;
+    assert!(offset + length <= bytes.len());
+
+    let header = node["header_hex"].as_str().expect("header_hex");
+    assert!(!header.is_empty() && header.len() <= length * 2);
+    assert_eq!(header, to_hex(&bytes[offset..][..header.len() / 2]));
+
+    let kind = node["kind"].as_str().expect("kind");
+    assert!(node["comment"].is_string());
+
+    let children = node["children"].as_array().expect("children");
+    let indefinite_string = ["5f", "7f"].contains(&header);
+    let container = ["array", "map", "tag"].contains(&kind) || indefinite_string;
+    assert_eq!(object.contains_key("value"), !container, "{}", node);
+    for child in children {
+        let child_offset = child["offset"].as_u64().unwrap() as usize;
+        let child_length = child["length"].as_u64().unwrap() as usize;
+        assert!(offset < child_offset && child_offset + child_length <= offset + length);
+        check_schema(child, bytes);
+    }
+}
+
+#[test]
+fn cose_sign1_golden() {
+    let item = parse_hex(COSE_SIGN1).unwrap();
+    let json = item.to_annotated_json().unwrap();
+    check_schema(&json, &item.to_bytes());
+    assert_eq!(
+        format!("{:#}\n", json),
+        include_str!("data/cose_sign1.json")
+    );
+}
+
+#[test]
+fn schema() {
+    let diag = r#"
+        [
+            0, 24, -1, -9223372036854775809, 1.5, NaN, -Infinity,
+            h'', h'0102', "", "hi", false, true, null, undefined, simple(99),
+            (_ h'01', h'0203'), (_ "a", "b"), (_ ), [_ 1], {_ "a": [], 1: {}},
+            32("http://example.com"), 24(h'8101'), 1(1.5)
+        ]
+    "#;
+    let item = parse_diag(diag).unwrap();
+    let json = item.to_annotated_json().unwrap();
+    check_schema(&json, &item.to_bytes());
+    assert_eq!(json["children"].as_array().unwrap().len(), 24);
+}
+
+#[test]
+fn values() {
+    let item = parse_diag(
+        r#"[1, -1, -9223372036854775809, 1.5, Infinity, h'00ff', "a", false, null, undefined]"#,
+    )
+    .unwrap();
+    let json = item.to_annotated_json().unwrap();
+    let values: Vec<String> = json["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|child| child["value"].to_string())
+        .collect();
+    assert_eq!(
+        values,
+        [
+            "1",
+            "-1",
+            r#""-9223372036854775809""#,
+            "1.5",
+            r#""Infinity""#,
+            r#""00ff""#,
+            r#""a""#,
+            "false",
+            "null",
+            "23",
+        ]
+    );
+}
+
+#[test]
+fn maps_and_tags() {
+    let item = parse_diag(r#"{1: 32("http://example.com")}"#).unwrap();
+    let json = item.to_annotated_json().unwrap();
+    let children = json["children"].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0]["comment"], "unsigned(1)");
+    assert_eq!(
+        children[1]["comment"],
+        "uri, tag(32); valid URL (checked against URL Standard, not RFC 3986)"
+    );
+    assert_eq!(children[1]["children"][0]["offset"], 4);
+    assert_eq!(children[1]["children"][0]["header_hex"], "72");
+}
+
+#[test]
+fn indefinite_strings() {
+    let item = parse_diag(r#"(_ "ab", "c")"#).unwrap();
+    let json = item.to_annotated_json().unwrap();
+    assert_eq!(json["comment"], "text(*)");
+    let chunks = json["children"].as_array().unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[1]["offset"], 4);
+    assert_eq!(chunks[1]["length"], 2);
+    assert_eq!(chunks[1]["comment"], "text(1)");
+    assert_eq!(chunks[1]["value"], "c");
+}
+
+#[test]
+fn options() {
+    let item = parse_diag("1_0").unwrap();
+    let options = AnnotatedJsonOptions {
+        hex: HexOptions {
+            flag_non_preferred: true,
+            ..HexOptions::default()
+        },
+        ..AnnotatedJsonOptions::default()
+    };
+    let json = item.to_annotated_json_with(&options).unwrap();
+    assert_eq!(json["header_hex"], "1801");
+    assert_eq!(json["comment"], "unsigned(1) (non-preferred encoding)");
+}
+
+#[test]
+fn large_negatives() {
+    let json = |hex: &str, lossy_large_negatives| {
+        let options = AnnotatedJsonOptions {
+            lossy_large_negatives,
+            ..AnnotatedJsonOptions::default()
+        };
+        parse_hex(hex)
+            .unwrap()
+            .to_annotated_json_with(&options)
+            .unwrap()["value"]
+            .clone()
+    };
+
+    // -2^63 still fits in an i64 so is always exact
+    for &lossy in &[false, true] {
+        assert_eq!(
+            json("3b7fffffffffffffff", lossy),
+            serde_json::json!(i64::MIN)
+        );
+    }
+    assert_eq!(json("3b8000000000000000", false), "-9223372036854775809");
+    assert_eq!(json("3bffffffffffffffff", false), "-18446744073709551616");
+    assert_eq!(
+        json("3b8000000000000000", true),
+        serde_json::json!(-9223372036854775808.0)
+    );
+    assert_eq!(
+        json("3bffffffffffffffff", true),
+        serde_json::json!(-18446744073709551616.0)
+    );
+    assert_eq!(
+        json("1bffffffffffffffff", false),
+        serde_json::json!(u64::MAX)
+    );
+}
+
+#[test]
+fn unencodable() {
+    let item = DataItem::AppLiteral {
+        prefix: "dt".into(),
+        content: "x".into(),
+    };
+    assert!(item.to_annotated_json().is_err());
+}
@@ -0,0 +1,119 @@
+use cbor_diag::{parse_diag, parse_diag_with, DataItem, IntegerWidth, ParseOptions, Tag};
+use pretty_assertions::assert_eq;
+
+fn lower_ip(prefix: &str, content: &str) -> Option<DataItem> {
+    if prefix != "ip" {
+        return None;
+    }
+    let address: std::net::Ipv4Addr = content.parse().ok()?;
+    Some(DataItem::Tag {
+        tag: Tag(52),
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(parse_diag(format!("h'{}'", hex::encode(address.octets()))).ok()?),
+    })
+}
+
+#[test]
+fn unknown_rejected_by_default() {
+    assert!(parse_diag("foo'content'").is_err());
+    assert!(parse_diag("[1, foo'content']").is_err());
+}
+
+#[test]
+fn unknown_preserved() {
+    let options = ParseOptions {
+        allow_unknown_app_literals: true,
+        ..ParseOptions::default()
+    };
+    let item = parse_diag_with(r"[1, foo'it\'s', {x1'': 2}]", &options).unwrap();
+    assert_eq!(
+        item,
+        DataItem::Array {
+            data: vec![
+                DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero,
+                },
+                DataItem::AppLiteral {
+                    prefix: "foo".into(),
+                    content: "it's".into(),
+                },
+                DataItem::Map {
+                    data: vec![(
+                        DataItem::AppLiteral {
+                            prefix: "x1".into(),
+                            content: "".into(),
+                        },
+                        DataItem::Integer {
+                            value: 2,
+                            bitwidth: IntegerWidth::Zero,
+                        },
+                    )],
+                    bitwidth: Some(IntegerWidth::Unknown),
+                },
+            ],
+            bitwidth: Some(IntegerWidth::Unknown),
+        }
+    );
+    assert_eq!(item.to_diag(), r"[1,foo'it\'s',{x1'':2}]");
+    assert!(item.try_to_bytes().is_err());
+}
+
+#[test]
+fn handler() {
+    let options = ParseOptions {
+        app_literal_handler: Some(lower_ip),
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_diag_with("[ip'192.0.2.1']", &options)
+            .unwrap()
+            .to_diag(),
+        "[52(h'c0000201')]"
+    );
+    assert!(parse_diag_with("ip'nope'", &options).is_err());
+    assert!(parse_diag_with("foo'bar'", &options).is_err());
+}
+
+#[test]
+fn handler_with_unknown_preserved() {
+    let options = ParseOptions {
+        app_literal_handler: Some(lower_ip),
+        allow_unknown_app_literals: true,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_diag_with("[ip'192.0.2.1', ip'nope', foo'bar']", &options)
+            .unwrap()
+            .to_diag(),
+        "[52(h'c0000201'),ip'nope',foo'bar']"
+    );
+}
+
+#[test]
+fn builtin_prefixes() {
+    let options = ParseOptions {
+        app_literal_handler: Some(|_, _| Some(DataItem::Simple(cbor_diag::Simple::NULL))),
+        allow_unknown_app_literals: true,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_diag_with("[d'2022-06-01', h'00', b64'AA']", &options)
+            .unwrap()
+            .to_diag(),
+        r#"[1004("2022-06-01"),h'00',b64'AA']"#
+    );
+    assert!(parse_diag_with("d'2022-13-01'", &options).is_err());
+    assert!(parse_diag_with("h'0g'", &options).is_err());
+}
+
+#[test]
+fn invalid_prefixes() {
+    let options = ParseOptions {
+        allow_unknown_app_literals: true,
+        ..ParseOptions::default()
+    };
+    for diag in &["Foo'bar'", "1x'bar'", "foo 'bar'", "foo'bar"] {
+        assert!(parse_diag_with(diag, &options).is_err(), "{}", diag);
+    }
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use cbor_diag::{roundtrip, DataItem};
+
+/// Deterministic pseudo-random input buffers of varying sizes.
+fn inputs() -> impl Iterator<Item = Vec<u8>> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..500).map(move |i| {
+        (0..i * 4)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn roundtrip_arbitrary() {
+    for input in inputs() {
+        let item = DataItem::arbitrary(&mut Unstructured::new(&input)).unwrap();
+        roundtrip::all(&item);
+    }
+}
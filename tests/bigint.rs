@@ -0,0 +1,77 @@
+#![cfg(feature = "bigint")]
+
+use cbor_diag::{parse_diag, CanonicalDataItem, DataItem};
+use num_bigint::BigInt;
+use pretty_assertions::assert_eq;
+
+fn bigint(value: i128) -> BigInt {
+    BigInt::from(value)
+}
+
+#[test]
+fn from_bigint() {
+    let two_64 = 1i128 << 64;
+    let cases = [
+        (bigint(0), "0"),
+        (bigint(-1), "-1"),
+        (bigint(two_64 - 1), "18446744073709551615"),
+        (bigint(two_64), "2(h'010000000000000000')"),
+        (bigint(two_64 + 1), "2(h'010000000000000001')"),
+        (bigint(-two_64 + 1), "-18446744073709551615"),
+        (bigint(-two_64), "-18446744073709551616"),
+        (bigint(-two_64 - 1), "3(h'010000000000000000')"),
+        (bigint(i128::MIN), "3(h'7fffffffffffffffffffffffffffffff')"),
+    ];
+    for (value, diag) in &cases {
+        let item = DataItem::from_bigint(value);
+        assert_eq!(item.to_diag(), *diag);
+        assert_eq!(item.to_bigint().as_ref(), Some(value), "{}", diag);
+    }
+}
+
+#[test]
+fn to_bigint() {
+    let cases = [
+        ("5_3", Some(bigint(5))),
+        ("-5", Some(bigint(-5))),
+        // Zero length and leading zero payloads are valid
+        ("2(h'')", Some(bigint(0))),
+        ("3(h'')", Some(bigint(-1))),
+        ("2(h'000001')", Some(bigint(1))),
+        ("3(h'0001')", Some(bigint(-2))),
+        ("2(1)", None),
+        ("4([-1, 5])", None),
+        ("1.0", None),
+    ];
+    for (diag, expected) in &cases {
+        assert_eq!(parse_diag(diag).unwrap().to_bigint(), *expected, "{}", diag);
+    }
+}
+
+#[test]
+fn deterministic_bignums() {
+    // Bignums that fit in 64 bits are plain integers in the deterministic
+    // encoding, others lose their leading zeros
+    let cases = [
+        ("2(h'')", "00"),
+        ("2(h'0001')", "01"),
+        ("3(h'')", "20"),
+        ("2(h'ffffffffffffffff')", "1bffffffffffffffff"),
+        ("3(h'ffffffffffffffff')", "3bffffffffffffffff"),
+        ("2(h'00010000000000000000')", "c249010000000000000000"),
+        ("3(h'010000000000000000')", "c349010000000000000000"),
+        ("2(1)", "c201"),
+    ];
+    for (diag, key) in &cases {
+        assert_eq!(
+            hex::encode(parse_diag(diag).unwrap().canonical_key()),
+            *key,
+            "{}",
+            diag
+        );
+    }
+    assert_eq!(
+        CanonicalDataItem::new(parse_diag("2(h'01')").unwrap()),
+        CanonicalDataItem::new(parse_diag("1").unwrap())
+    );
+}
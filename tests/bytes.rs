@@ -1,4 +1,4 @@
-use cbor_diag::{ByteString, DataItem, IntegerWidth};
+use cbor_diag::{ByteString, DataItem, Encoding, IntegerWidth};
 use indoc::indoc;
 
 #[macro_use]
@@ -16,6 +16,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::Unknown,
+                encoding: Some(Encoding::Base16),
             }),
             {
                 "h''",
@@ -27,6 +28,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::Unknown,
+                encoding: Some(Encoding::Base16),
             }),
             {
                 "h'68656c6c6f'",
@@ -38,6 +40,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"abcdefghijklmnopqrstuvwxyz"[..].into(),
                 bitwidth: IntegerWidth::Unknown,
+                encoding: Some(Encoding::Base16),
             }),
             {
                 "h'6162636465666768696a6b6c6d6e6f707172737475767778797a'",
@@ -49,6 +52,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
                 bitwidth: IntegerWidth::Unknown,
+                encoding: Some(Encoding::Base16),
             }),
             {
                 "h'000102030405060708090a'",
@@ -62,6 +66,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::Zero,
+                encoding: None,
             }),
             indoc!(r#"
                 40 # bytes(0)
@@ -73,6 +78,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::Zero,
+                encoding: None,
             }),
             indoc!(r#"
                 45            # bytes(5)
@@ -86,6 +92,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::Eight,
+                encoding: None,
             }),
             indoc!(r#"
                 58 00 # bytes(0)
@@ -97,6 +104,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::Eight,
+                encoding: None,
             }),
             indoc!(r#"
                 58 05         # bytes(5)
@@ -108,6 +116,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"abcdefghijklmnopqrstuvwxyz"[..].into(),
                 bitwidth: IntegerWidth::Eight,
+                encoding: None,
             }),
             indoc!(r#"
                 58 1a                               # bytes(26)
@@ -120,6 +129,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
                 bitwidth: IntegerWidth::Eight,
+                encoding: None,
             }),
             indoc!(r#"
                 58 0b                     # bytes(11)
@@ -133,6 +143,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::Sixteen,
+                encoding: None,
             }),
             indoc!(r#"
                 59 0000 # bytes(0)
@@ -144,6 +155,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::Sixteen,
+                encoding: None,
             }),
             indoc!(r#"
                 59 0005       # bytes(5)
@@ -155,6 +167,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"abcdefghijklmnopqrstuvwxyz"[..].into(),
                 bitwidth: IntegerWidth::Sixteen,
+                encoding: None,
             }),
             indoc!(r#"
                 59 001a                             # bytes(26)
@@ -169,6 +182,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::ThirtyTwo,
+                encoding: None,
             }),
             indoc!(r#"
                 5a 00000000 # bytes(0)
@@ -180,6 +194,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::ThirtyTwo,
+                encoding: None,
             }),
             indoc!(r#"
                 5a 00000005   # bytes(5)
@@ -191,6 +206,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"abcdefghijklmnopqrstuvwxyz"[..].into(),
                 bitwidth: IntegerWidth::ThirtyTwo,
+                encoding: None,
             }),
             indoc!(r#"
                 5a 0000001a                         # bytes(26)
@@ -205,6 +221,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: vec![],
                 bitwidth: IntegerWidth::SixtyFour,
+                encoding: None,
             }),
             indoc!(r#"
                 5b 0000000000000000 # bytes(0)
@@ -216,6 +233,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"hello"[..].into(),
                 bitwidth: IntegerWidth::SixtyFour,
+                encoding: None,
             }),
             indoc!(r#"
                 5b 0000000000000005 # bytes(5)
@@ -227,6 +245,7 @@ testcases! {
             DataItem::ByteString(ByteString {
                 data: b"abcdefghijklmnopqrstuvwxyz"[..].into(),
                 bitwidth: IntegerWidth::SixtyFour,
+                encoding: None,
             }),
             indoc!(r#"
                 5b 000000000000001a                 # bytes(26)
@@ -238,11 +257,11 @@ testcases! {
 
     mod indefinite {
         mod diag {
-            empty(value2diag) {
+            empty(diag2value, value2diag) {
                 DataItem::IndefiniteByteString(vec![]),
                 {
-                    "(_)",
-                    "(_ )",
+                    "''_",
+                    "''_",
                 }
             }
 
@@ -251,6 +270,7 @@ testcases! {
                     ByteString {
                         data: vec![],
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -264,10 +284,12 @@ testcases! {
                     ByteString {
                         data: vec![],
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: vec![],
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -281,6 +303,7 @@ testcases! {
                     ByteString {
                         data: b"hello"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -294,10 +317,12 @@ testcases! {
                     ByteString {
                         data: b"hello"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: b"world"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -311,18 +336,22 @@ testcases! {
                     ByteString {
                         data: b"abc"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: b""[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: b"defghijklmnopqrstuv"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: b"wxyz"[..].into(),
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -343,10 +372,12 @@ testcases! {
                     ByteString {
                         data: vec![0, 1, 2, 3, 4],
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                     ByteString {
                         data: vec![5, 6, 7, 8, 9, 10],
                         bitwidth: IntegerWidth::Unknown,
+                        encoding: Some(Encoding::Base16),
                     },
                 ]),
                 {
@@ -370,6 +401,7 @@ testcases! {
                     ByteString {
                         data: "".into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
@@ -385,10 +417,12 @@ testcases! {
                     ByteString {
                         data: "".into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                     ByteString {
                         data: "".into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
@@ -406,10 +440,12 @@ testcases! {
                     ByteString {
                         data: b"hello"[..].into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                     ByteString {
                         data: b"world"[..].into(),
                         bitwidth: IntegerWidth::Sixteen,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
@@ -427,18 +463,22 @@ testcases! {
                     ByteString {
                         data: b"abc"[..].into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                     ByteString {
                         data: "".into(),
                         bitwidth: IntegerWidth::Sixteen,
+                        encoding: None,
                     },
                     ByteString {
                         data: b"defghijklmnopqrstuv"[..].into(),
                         bitwidth: IntegerWidth::ThirtyTwo,
+                        encoding: None,
                     },
                     ByteString {
                         data: b"wxyz"[..].into(),
                         bitwidth: IntegerWidth::SixtyFour,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
@@ -461,10 +501,12 @@ testcases! {
                     ByteString {
                         data: vec![0, 1, 2, 3, 4],
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                     ByteString {
                         data: vec![5, 6, 7, 8, 9, 10],
                         bitwidth: IntegerWidth::Eight,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
@@ -482,10 +524,12 @@ testcases! {
                     ByteString {
                         data: b"\\"[..].into(),
                         bitwidth: IntegerWidth::Zero,
+                        encoding: None,
                     },
                     ByteString {
                         data: b"\""[..].into(),
                         bitwidth: IntegerWidth::Eight,
+                        encoding: None,
                     },
                 ]),
                 indoc!(r#"
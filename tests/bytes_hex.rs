@@ -0,0 +1,70 @@
+use cbor_diag::{parse_diag, parse_hex, parse_hex_with, BytesHexOptions, DataItem, ParseOptions};
+use pretty_assertions::assert_eq;
+
+fn item() -> DataItem {
+    parse_diag(r#"{"hello": [1, 2, 3]}"#).unwrap()
+}
+
+#[test]
+fn default_is_plain() {
+    let hex = item()
+        .to_hex_string_with(&BytesHexOptions::default())
+        .unwrap();
+    assert_eq!(hex, "a16568656c6c6f83010203");
+    assert_eq!(parse_hex(&hex).unwrap().to_bytes(), item().to_bytes());
+}
+
+#[test]
+fn groups_and_case() {
+    let options = BytesHexOptions {
+        group: 4,
+        uppercase: true,
+        ..BytesHexOptions::default()
+    };
+    let hex = item().to_hex_string_with(&options).unwrap();
+    assert_eq!(hex, "A1656865 6C6C6F83 010203");
+    assert_eq!(parse_hex(&hex).unwrap().to_bytes(), item().to_bytes());
+}
+
+#[test]
+fn prefix() {
+    let options = BytesHexOptions {
+        group: 1,
+        prefix: "0x".into(),
+        ..BytesHexOptions::default()
+    };
+    let hex = parse_diag("[1, 2]")
+        .unwrap()
+        .to_hex_string_with(&options)
+        .unwrap();
+    assert_eq!(hex, "0x82 0x01 0x02");
+    let arrays = ParseOptions {
+        allow_hex_arrays: true,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_hex_with(&hex, &arrays).unwrap().to_bytes(),
+        [0x82, 1, 2]
+    );
+
+    // Without grouping the prefix is only at the start
+    let options = BytesHexOptions {
+        prefix: "0x".into(),
+        ..BytesHexOptions::default()
+    };
+    assert_eq!(
+        parse_diag("[1, 2]")
+            .unwrap()
+            .to_hex_string_with(&options)
+            .unwrap(),
+        "0x820102"
+    );
+}
+
+#[test]
+fn unencodable() {
+    let item = DataItem::Simple(cbor_diag::Simple(24));
+    assert!(item
+        .to_hex_string_with(&BytesHexOptions::default())
+        .is_err());
+}
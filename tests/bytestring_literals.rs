@@ -0,0 +1,194 @@
+use cbor_diag::{parse_diag, ByteString, DataItem, Encoding, IntegerWidth};
+use pretty_assertions::assert_eq;
+
+fn bytes(data: &[u8], encoding: impl Into<Option<Encoding>>) -> ByteString {
+    ByteString {
+        data: data.into(),
+        bitwidth: IntegerWidth::Unknown,
+        encoding: encoding.into(),
+    }
+}
+
+#[test]
+fn quoted() {
+    assert_eq!(
+        parse_diag("'hello'").unwrap(),
+        DataItem::ByteString(bytes(b"hello", Encoding::Text))
+    );
+    assert_eq!(
+        parse_diag("''").unwrap(),
+        DataItem::ByteString(bytes(b"", Encoding::Text))
+    );
+    assert_eq!(
+        parse_diag(r"'it\'s \\o/'").unwrap(),
+        DataItem::ByteString(bytes(br"it's \o/", Encoding::Text))
+    );
+}
+
+#[test]
+fn concatenated() {
+    let item = parse_diag("'Hello ' h'776f726c64' b64'IQ'").unwrap();
+    assert_eq!(item, DataItem::ByteString(bytes(b"Hello world!", None)));
+    assert_eq!(item.to_bytes(), b"\x4cHello world!");
+    assert_eq!(
+        parse_diag("[h'01'h'02', h'03']").unwrap().to_bytes(),
+        b"\x82\x42\x01\x02\x41\x03"
+    );
+    assert_eq!(parse_diag("'a' 'b'").unwrap().to_diag(), "'ab'");
+}
+
+#[test]
+fn indefinite_mixed_chunks() {
+    let item = parse_diag("(_ h'0102', b64'AwQF', 'hello')").unwrap();
+    assert_eq!(
+        item,
+        DataItem::IndefiniteByteString(vec![
+            bytes(&[1, 2], Encoding::Base16),
+            bytes(&[3, 4, 5], Encoding::Base64Url),
+            bytes(b"hello", Encoding::Text),
+        ])
+    );
+    assert_eq!(
+        item.to_bytes(),
+        b"\x5f\x42\x01\x02\x43\x03\x04\x05\x45hello\xff"
+    );
+    assert_eq!(item.to_diag(), "(_h'0102',b64'AwQF','hello')");
+}
+
+#[test]
+fn indefinite_concatenated_chunk() {
+    assert_eq!(
+        parse_diag("(_ h'01' h'02', 'a' 'b')").unwrap().to_bytes(),
+        b"\x5f\x42\x01\x02\x42ab\xff"
+    );
+}
+
+#[test]
+fn base64_padding() {
+    let hello = DataItem::ByteString(bytes(b"hello", Encoding::Base64Url));
+    assert_eq!(parse_diag("b64'aGVsbG8='").unwrap(), hello);
+    assert_eq!(parse_diag("b64'aGVsbG8'").unwrap(), hello);
+    assert_eq!(
+        parse_diag("b64'aGk='").unwrap(),
+        DataItem::ByteString(bytes(b"hi", Encoding::Base64Url))
+    );
+    assert_eq!(
+        parse_diag("b64'aA=='").unwrap(),
+        DataItem::ByteString(bytes(b"h", Encoding::Base64Url))
+    );
+    assert_eq!(
+        parse_diag("b64'+/+/'").unwrap(),
+        DataItem::ByteString(bytes(b"\xfb\xff\xbf", Encoding::Base64))
+    );
+    assert_eq!(
+        parse_diag("b64'+w=='").unwrap(),
+        DataItem::ByteString(bytes(b"\xfb", Encoding::Base64))
+    );
+}
+
+#[test]
+fn base64_alphabets() {
+    assert_eq!(
+        parse_diag("b64'-_-_'").unwrap(),
+        DataItem::ByteString(bytes(b"\xfb\xff\xbf", Encoding::Base64Url))
+    );
+    // Mixing the alphabets is unambiguous, and treated as base64url
+    assert_eq!(
+        parse_diag("b64'-/+_'").unwrap(),
+        DataItem::ByteString(bytes(b"\xfb\xff\xbf", Encoding::Base64Url))
+    );
+
+    // The alphabet is kept when shown again, without the padding
+    assert_eq!(parse_diag("b64'+w=='").unwrap().to_diag(), "b64'+w'");
+    assert_eq!(parse_diag("b64'-w=='").unwrap().to_diag(), "b64'-w'");
+    assert_eq!(parse_diag("b64'aGk='").unwrap().to_diag(), "b64'aGk'");
+}
+
+#[test]
+fn invalid_base64() {
+    for diag in &[
+        "b64'a'",
+        "b64'aGk=='",
+        "b64'aA='",
+        "b64'aGVsbG8=='",
+        "b64'aGk=a'",
+        "b64'aG.k'",
+        "[1, b64'===='",
+    ] {
+        let message = parse_diag(diag).unwrap_err().to_string();
+        assert!(
+            message.contains("invalid base64 in b64'' literal"),
+            "{}: {}",
+            diag,
+            message
+        );
+    }
+    assert!(parse_diag("[1, b64'a']")
+        .unwrap_err()
+        .to_string()
+        .ends_with("Parsing error at offset 4: invalid base64 in b64'' literal"));
+}
+
+/// The test vectors from RFC 4648 § 10.
+const BASE32_VECTORS: &[(&[u8], &str, &str)] = &[
+    (b"", "", ""),
+    (b"f", "MY======", "CO======"),
+    (b"fo", "MZXQ====", "CPNG===="),
+    (b"foo", "MZXW6===", "CPNMU==="),
+    (b"foob", "MZXW6YQ=", "CPNMUOG="),
+    (b"fooba", "MZXW6YTB", "CPNMUOJ1"),
+    (b"foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+];
+
+#[test]
+fn base32() {
+    for &(data, base32, base32hex) in BASE32_VECTORS {
+        let item = DataItem::ByteString(bytes(data, Encoding::Base32));
+        assert_eq!(parse_diag(format!("b32'{}'", base32)).unwrap(), item);
+        let unpadded = base32.trim_end_matches('=');
+        assert_eq!(parse_diag(format!("b32'{}'", unpadded)).unwrap(), item);
+        let lower = unpadded.to_lowercase();
+        assert_eq!(parse_diag(format!("b32'{}'", lower)).unwrap(), item);
+        assert_eq!(item.to_diag(), format!("b32'{}'", unpadded));
+
+        let item = DataItem::ByteString(bytes(data, Encoding::Base32Hex));
+        assert_eq!(parse_diag(format!("h32'{}'", base32hex)).unwrap(), item);
+        let unpadded = base32hex.trim_end_matches('=');
+        assert_eq!(parse_diag(format!("h32'{}'", unpadded)).unwrap(), item);
+        let lower = unpadded.to_lowercase();
+        assert_eq!(parse_diag(format!("h32'{}'", lower)).unwrap(), item);
+        assert_eq!(item.to_diag(), format!("h32'{}'", unpadded));
+    }
+}
+
+#[test]
+fn base32_whitespace() {
+    assert_eq!(
+        parse_diag("b32'MZXW 6YTB\n  OI======'").unwrap(),
+        DataItem::ByteString(bytes(b"foobar", Encoding::Base32))
+    );
+    assert_eq!(
+        parse_diag("[h32' CPNMUOJ1 ', b32'']").unwrap().to_diag(),
+        "[h32'CPNMUOJ1',b32'']"
+    );
+    assert_eq!(
+        parse_diag("'foo' b32'MJQXE'").unwrap().to_diag(),
+        "h'666f6f626172'"
+    );
+}
+
+#[test]
+fn invalid_base32() {
+    for (diag, error) in &[
+        ("b32'M'", "invalid base32 in b32'' literal"),
+        ("b32'MZX'", "invalid base32 in b32'' literal"),
+        ("b32'MY====='", "invalid base32 in b32'' literal"),
+        ("b32'MY=='", "invalid base32 in b32'' literal"),
+        ("b32'MZ'", "invalid base32 in b32'' literal"),
+        ("b32'MY01'", "invalid base32 in b32'' literal"),
+        ("h32'CPNW'", "invalid base32hex in h32'' literal"),
+    ] {
+        let message = parse_diag(diag).unwrap_err().to_string();
+        assert!(message.contains(error), "{}: {}", diag, message);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::{BTreeSet, HashSet};
+
+use cbor_diag::{parse_bytes, parse_diag, parse_hex, CanonicalDataItem, DataItem};
+use pretty_assertions::assert_eq;
+
+fn key(hex: &str) -> String {
+    hex::encode(parse_hex(hex).unwrap().canonical_key())
+}
+
+#[test]
+fn integers() {
+    assert_eq!(key("1b 0000000000000001"), "01");
+    assert_eq!(key("39 00ff"), "38ff");
+    assert_eq!(key("d9 0020 60"), "d82060");
+}
+
+#[test]
+fn strings() {
+    assert_eq!(key("5f 41 01 42 0203 ff"), "43010203");
+    assert_eq!(key("7f 61 61 60 62 6263 ff"), "63616263");
+    assert_eq!(key("5f ff"), "40");
+}
+
+#[test]
+fn containers() {
+    assert_eq!(key("9f 18 01 ff"), "8101");
+    assert_eq!(key("bf 62 6262 01 61 61 02 ff"), "a261610262626201");
+    // Keys are sorted by their encoded form, so shorter keys come first
+    assert_eq!(key("a3 18 64 00 20 01 0a 02"), "a30a021864002001");
+}
+
+#[test]
+fn floats() {
+    assert_eq!(key("fb 3ff0000000000000"), "f93c00");
+    assert_eq!(key("fb 3ff0000000000001"), "fb3ff0000000000001");
+    assert_eq!(key("fb 3fb999999999999a"), "fb3fb999999999999a");
+    assert_eq!(key("fb 4090000000000000"), "f96400");
+    assert_eq!(key("fb 41e0000000000000"), "fa4f000000");
+    assert_eq!(key("fb 7ff8000000000001"), "f97e00");
+    assert_eq!(key("fa ffc00000"), "f97e00");
+    assert_eq!(key("fb fff0000000000000"), "f9fc00");
+    assert_eq!(key("fb 8000000000000000"), "f98000");
+    assert_eq!(key("fa 00000000"), "f90000");
+}
+
+#[test]
+fn hash_set() {
+    let items = [
+        parse_diag("{1: [1, 2.5], \"a\": h'00'}").unwrap(),
+        parse_hex("a2 61 61 41 00 01 82 01 f9 4100").unwrap(),
+        parse_hex("bf 01 9f 1b 0000000000000001 fb 4004000000000000 ff 7f 61 61 ff 5f 41 00 ff ff")
+            .unwrap(),
+        parse_bytes(b"\xa2\x18\x01\x82\x01\xfa\x40\x20\x00\x00\x61a\x41\x00").unwrap(),
+    ];
+    let set: HashSet<CanonicalDataItem> =
+        items.iter().cloned().map(CanonicalDataItem::from).collect();
+    assert_eq!(set.len(), 1);
+    assert_eq!(
+        hex::encode(set.iter().next().unwrap().key()),
+        "a2018201f9410061614100"
+    );
+}
+
+#[test]
+fn zero_signs_distinct() {
+    let set: HashSet<CanonicalDataItem> = ["0.0", "-0.0", "0.0_3", "0", "NaN", "NaN_1"]
+        .iter()
+        .map(|diag| CanonicalDataItem::new(parse_diag(diag).unwrap()))
+        .collect();
+    assert_eq!(set.len(), 4);
+}
+
+#[test]
+fn ordering() {
+    let set: BTreeSet<CanonicalDataItem> = ["\"b\"", "[]", "100", "-1", "10", "\"a\""]
+        .iter()
+        .map(|diag| CanonicalDataItem::new(parse_diag(diag).unwrap()))
+        .collect();
+    let ordered: Vec<String> = set.iter().map(|item| item.item().to_diag()).collect();
+    assert_eq!(ordered, ["10", "100", "-1", "\"a\"", "\"b\"", "[]"]);
+}
+
+#[test]
+fn item_preserved() {
+    let item = parse_hex("9f 18 01 ff").unwrap();
+    let canonical = CanonicalDataItem::new(item.clone());
+    assert_eq!(*canonical.item(), item);
+    assert_eq!(canonical.into_item(), item);
+    let _ = DataItem::Simple(cbor_diag::Simple(24)).canonical_key();
+}
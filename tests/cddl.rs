@@ -0,0 +1,177 @@
+use cbor_diag::{
+    cddl::{validate, CddlViolation, ViolationKind},
+    parse_diag,
+};
+use pretty_assertions::assert_eq;
+
+fn check(schema: &str, diag: &str) -> Vec<String> {
+    match validate(&parse_diag(diag).unwrap(), schema) {
+        Ok(()) => Vec::new(),
+        Err(violations) => violations.iter().map(CddlViolation::to_string).collect(),
+    }
+}
+
+const PERSON: &str = r#"
+    person = {
+        name: tstr,
+        ? age: uint,         ; optional
+        * tstr => any,
+    }
+"#;
+
+#[test]
+fn primitives() {
+    let cases = [
+        ("uint", "1", true),
+        ("uint", "-1", false),
+        ("nint", "-1", true),
+        ("int", "-1", true),
+        ("int", "1.0", false),
+        ("bstr", "h'00'", true),
+        ("bstr", "(_ h'00')", true),
+        ("tstr", r#""a""#, true),
+        ("tstr", "h'00'", false),
+        ("bool", "true", true),
+        ("bool", "null", false),
+        ("nil", "null", true),
+        ("float", "1.5", true),
+        ("float16", "1.5_1", true),
+        ("float16", "1.5_2", false),
+        ("any", "[1, {2: 3}]", true),
+        ("number", "1.5", true),
+        ("tdate", r#"0("2013-03-21T20:04:00Z")"#, true),
+        ("biguint", "2(h'01')", true),
+    ];
+    for &(ty, diag, valid) in &cases {
+        let schema = format!("root = {}", ty);
+        assert_eq!(check(&schema, diag).is_empty(), valid, "{} {}", ty, diag);
+    }
+}
+
+#[test]
+fn literals_and_choices() {
+    assert_eq!(check("root = 1 / \"a\" / 1.5", "1"), Vec::<String>::new());
+    assert_eq!(
+        check("root = 1 / \"a\" / 1.5", r#""a""#),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        check("root = -2 / (tstr / nil)", "null"),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        check("root = 1 / \"a\" / 1.5", "2"),
+        ["at root: does not match `1 / \"a\" / 1.5`"]
+    );
+}
+
+#[test]
+fn maps() {
+    assert!(check(PERSON, r#"{"name": "Alice"}"#).is_empty());
+    assert!(check(PERSON, r#"{"name": "Alice", "age": 30, "x": [1]}"#).is_empty());
+    assert_eq!(
+        check(PERSON, r#"{"age": -1, 1: 2}"#),
+        [
+            "at root: missing `name: tstr`",
+            "at [0, 1]: does not match `uint`",
+            "at [1, 0]: not allowed by `{ name: tstr, ? age: uint, * tstr => any, }`",
+        ]
+    );
+}
+
+#[test]
+fn literal_keys() {
+    let schema = r#"root = { 1: int, "b": bstr, ? -1: tstr }"#;
+    assert!(check(schema, r#"{1: 2, "b": h'', -1: "x"}"#).is_empty());
+    assert_eq!(
+        check(schema, r#"{1: 2, "b": h'', -1: 1, -1: "y"}"#),
+        [
+            "at [2, 1]: does not match `tstr`",
+            "at [3, 0]: not allowed by `{ 1: int, \"b\": bstr, ? -1: tstr }`",
+        ]
+    );
+}
+
+#[test]
+fn arrays() {
+    let schema = "root = [uint, * tstr, ? bool, + bstr]";
+    assert!(check(schema, r#"[1, h'']"#).is_empty());
+    assert!(check(schema, r#"[1, "a", "b", true, h'', h'']"#).is_empty());
+    assert_eq!(
+        check(schema, r#"[1, "a", 2]"#),
+        ["at [2]: does not match `bstr`"]
+    );
+    assert_eq!(check(schema, r#"[1, "a"]"#), ["at root: missing `+ bstr`"]);
+    assert_eq!(
+        check(schema, r#"[1, h'', 3, 4]"#),
+        [
+            "at [2]: not allowed by `[uint, * tstr, ? bool, + bstr]`",
+            "at [3]: not allowed by `[uint, * tstr, ? bool, + bstr]`",
+        ]
+    );
+    assert_eq!(
+        check(schema, "{}"),
+        ["at root: does not match `[uint, * tstr, ? bool, + bstr]`"]
+    );
+}
+
+#[test]
+fn tags() {
+    let schema = "root = #6.32(tstr) / #6.24";
+    assert!(check(schema, r#"32("http://example.com")"#).is_empty());
+    assert!(check(schema, "24(h'01')").is_empty());
+    assert_eq!(
+        check("root = [#6.32(tstr)]", "[32(1)]"),
+        ["at [0, 0]: does not match `tstr`"]
+    );
+    assert_eq!(
+        check("root = #6.32(tstr)", "33(\"a\")"),
+        ["at root: does not match `#6.32(tstr)`"]
+    );
+}
+
+#[test]
+fn named_rules() {
+    let schema = r#"
+        message = [* header]
+        header = { id: uint, ? children: [* header] }
+    "#;
+    assert!(check(schema, r#"[{"id": 1, "children": [{"id": 2}]}]"#).is_empty());
+    assert_eq!(
+        check(schema, r#"[{"id": 1, "children": [{"id": "2"}]}]"#),
+        ["at [0]: not allowed by `[* header]`"]
+    );
+    assert_eq!(
+        check("root = [header]\nheader = { id: uint }", r#"[{"id": "2"}]"#),
+        ["at [0, 0, 1]: does not match `uint`"]
+    );
+}
+
+#[test]
+fn invalid_schemas() {
+    let violations = validate(&parse_diag("1").unwrap(), "root = foo").unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].path, Vec::<usize>::new());
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::InvalidSchema("unknown rule `foo`".into())
+    );
+
+    assert_eq!(
+        check("root = uint .size 2", "1"),
+        ["invalid schema: ranges and control operators are not supported at line 1"]
+    );
+    assert_eq!(
+        check("root = [\n 1*2 uint]", "1"),
+        ["invalid schema: occurrence ranges are not supported at line 2"]
+    );
+    assert_eq!(
+        check("root = [uint", "1"),
+        ["invalid schema: expected `]` at line 1"]
+    );
+    assert_eq!(check("", "1"), ["invalid schema: no rules"]);
+    assert_eq!(
+        check("root = loop\nloop = root", "1"),
+        ["invalid schema: rules are nested more than 256 deep"]
+    );
+}
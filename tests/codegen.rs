@@ -0,0 +1,114 @@
+use cbor_diag::{parse_diag, CodegenOptions};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn options(bytes_per_line: usize, name: Option<&str>) -> CodegenOptions {
+    CodegenOptions {
+        bytes_per_line,
+        name: name.map(Into::into),
+    }
+}
+
+#[test]
+fn single_line() {
+    let item = parse_diag(r#"{1: "a"}"#).unwrap();
+    let options = CodegenOptions::default();
+    assert_eq!(
+        item.to_rust_literal(&options).unwrap(),
+        r#"&[0xa1, 0x01, 0x61, 0x61] // {1:"a"}"#
+    );
+    assert_eq!(
+        item.to_c_literal(&options).unwrap(),
+        r#"uint8_t msg[] = {0xA1, 0x01, 0x61, 0x61}; // {1:"a"}"#
+    );
+    assert_eq!(
+        item.to_python_literal(&options).unwrap(),
+        r#"bytes.fromhex("a1016161")  # {1:"a"}"#
+    );
+}
+
+#[test]
+fn named() {
+    let item = parse_diag("[1]").unwrap();
+    let options = options(16, Some("VECTOR"));
+    assert_eq!(
+        item.to_rust_literal(&options).unwrap(),
+        "const VECTOR: &[u8] = &[0x81, 0x01]; // [1]"
+    );
+    assert_eq!(
+        item.to_c_literal(&options).unwrap(),
+        "uint8_t VECTOR[] = {0x81, 0x01}; // [1]"
+    );
+    assert_eq!(
+        item.to_python_literal(&options).unwrap(),
+        r#"VECTOR = bytes.fromhex("8101")  # [1]"#
+    );
+}
+
+#[test]
+fn wrapped() {
+    let item = parse_diag("[1, 2, 3, 4]").unwrap();
+    let options = options(2, Some("v"));
+    assert_eq!(
+        item.to_rust_literal(&options).unwrap(),
+        indoc!(
+            "
+            const v: &[u8] = &[
+                0x84, 0x01,
+                0x02, 0x03,
+                0x04,
+            ]; // [1,2,3,4]"
+        )
+    );
+    assert_eq!(
+        item.to_c_literal(&options).unwrap(),
+        indoc!(
+            "
+            uint8_t v[] = {
+                0x84, 0x01,
+                0x02, 0x03,
+                0x04,
+            }; // [1,2,3,4]"
+        )
+    );
+    assert_eq!(
+        item.to_python_literal(&options).unwrap(),
+        indoc!(
+            r#"
+            v = bytes.fromhex(
+                "8401"
+                "0203"
+                "04"
+            )  # [1,2,3,4]"#
+        )
+    );
+}
+
+#[test]
+fn unwrapped() {
+    let item = parse_diag("h'000102030405060708090a0b0c0d0e0f10'").unwrap();
+    assert_eq!(
+        item.to_python_literal(&options(0, None)).unwrap(),
+        r#"bytes.fromhex("51000102030405060708090a0b0c0d0e0f10")  # h'000102030405060708090a0b0c0d0e0f10'"#
+    );
+    assert!(item
+        .to_rust_literal(&CodegenOptions::default())
+        .unwrap()
+        .starts_with("&[\n"));
+}
+
+#[test]
+fn unencodable() {
+    let item = parse_diag("1_0").unwrap();
+    let item = cbor_diag::DataItem::Array {
+        data: vec![
+            item,
+            cbor_diag::DataItem::Integer {
+                value: 1000,
+                bitwidth: cbor_diag::IntegerWidth::Zero,
+            },
+        ],
+        bitwidth: None,
+    };
+    assert!(item.to_rust_literal(&CodegenOptions::default()).is_err());
+}
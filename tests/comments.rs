@@ -0,0 +1,141 @@
+use cbor_diag::{parse_diag, parse_diag_with_comments, CommentPosition, Comments, HexOptions};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+use CommentPosition::{After, Before};
+
+fn collected(comments: &Comments) -> Vec<(Vec<usize>, CommentPosition, String)> {
+    comments
+        .iter()
+        .map(|(path, position, comment)| (path.to_vec(), position, comment.to_owned()))
+        .collect()
+}
+
+#[test]
+fn positions() {
+    let (_, comments) = parse_diag_with_comments(indoc!(
+        r#"
+        / the root / {
+            / key / "a" / after key /: / value / [
+                1, / one /
+                / two / 2 / still two /,
+                24(/ tagged / h'00'),
+                (_ / first chunk / '', 'x' / second chunk /),
+            ],
+            "b": [ / empty / ],
+        } / end /
+        "#
+    ))
+    .unwrap();
+
+    assert_eq!(
+        collected(&comments),
+        vec![
+            (vec![], Before, "the root".into()),
+            (vec![], After, "end".into()),
+            (vec![0, 0], Before, "key".into()),
+            (vec![0, 0], After, "after key".into()),
+            (vec![0, 1], Before, "value".into()),
+            (vec![0, 1, 0], After, "one".into()),
+            (vec![0, 1, 1], Before, "two".into()),
+            (vec![0, 1, 1], After, "still two".into()),
+            (vec![0, 1, 2, 0], Before, "tagged".into()),
+            (vec![0, 1, 3, 0], Before, "first chunk".into()),
+            (vec![0, 1, 3, 1], After, "second chunk".into()),
+            (vec![1, 1], After, "empty".into()),
+        ]
+    );
+}
+
+#[test]
+fn comment_characters_in_strings() {
+    let (_, comments) = parse_diag_with_comments(r#"["a/b", 'c/d', "\"/", / real /1]"#).unwrap();
+    assert_eq!(collected(&comments), vec![(vec![3], Before, "real".into())]);
+}
+
+#[test]
+fn no_comments() {
+    let (item, comments) = parse_diag_with_comments("[1, 2]").unwrap();
+    assert!(comments.is_empty());
+    assert_eq!(item.to_diag_pretty_with_comments(&comments), "[1, 2]");
+}
+
+#[test]
+fn reemit() {
+    let text = indoc!(
+        r#"
+        / config / {
+            "name": "example", / the name /
+            "values": [
+                / first / 1,
+                2,
+                / a much longer item to make this array split over lines /
+                "three",
+            ],
+        }
+        "#
+    );
+    let (item, comments) = parse_diag_with_comments(text).unwrap();
+    let output = item.to_diag_pretty_with_comments(&comments);
+    assert_eq!(
+        output,
+        indoc!(
+            r#"
+            / config / {
+                "name": "example" / the name /,
+                "values": [
+                    / first / 1,
+                    2,
+                    / a much longer item to make this array split over lines / "three",
+                ],
+            }"#
+        )
+    );
+
+    // The output is stable through another round
+    let (reparsed, recomments) = parse_diag_with_comments(&output).unwrap();
+    assert_eq!(reparsed, item);
+    assert_eq!(recomments, comments);
+    assert_eq!(parse_diag(&output).unwrap(), item);
+}
+
+#[test]
+fn hex() {
+    let (item, comments) =
+        parse_diag_with_comments(r#"/ pair / [1 / one /, 24(/ nested / h'00')]"#).unwrap();
+    assert_eq!(
+        item.to_hex_with_comments(&HexOptions::default(), &comments),
+        indoc!(
+            r#"
+            82          # array(2) / pair /
+               01       #   unsigned(1) / one /
+               d8 18    #   encoded cbor data item, tag(24)
+                  41    #     bytes(1) / nested /
+                     00 #       "\x00"
+                        #     encoded cbor data item
+                        #       00 # unsigned(0)
+            "#
+        )
+    );
+}
+
+#[test]
+fn built_comments() {
+    let item = parse_diag("[1, [2]]").unwrap();
+    let mut comments = Comments::new();
+    comments.push(vec![1, 0], Before, "deep");
+    comments.push(vec![1], After, "inner");
+    comments.push(vec![5], After, "missing");
+    assert_eq!(
+        item.to_diag_pretty_with_comments(&comments),
+        indoc!(
+            "
+            [
+                1,
+                [
+                    / deep / 2,
+                ] / inner /,
+            ]"
+        )
+    );
+}
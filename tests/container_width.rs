@@ -0,0 +1,126 @@
+use cbor_diag::{parse_bytes, parse_diag, DataItem, IntegerWidth};
+use pretty_assertions::assert_eq;
+
+fn array(bitwidth: Option<IntegerWidth>) -> DataItem {
+    DataItem::Array {
+        data: vec![
+            DataItem::Integer {
+                value: 1,
+                bitwidth: IntegerWidth::Zero,
+            },
+            DataItem::Integer {
+                value: 2,
+                bitwidth: IntegerWidth::Zero,
+            },
+        ],
+        bitwidth,
+    }
+}
+
+#[test]
+fn parse_array_widths() {
+    for &(text, bitwidth) in &[
+        ("[1, 2]", Some(IntegerWidth::Unknown)),
+        ("[_0 1, 2]", Some(IntegerWidth::Eight)),
+        ("[_1 1, 2]", Some(IntegerWidth::Sixteen)),
+        ("[_2 1, 2]", Some(IntegerWidth::ThirtyTwo)),
+        ("[_3\n    1,\n    2,\n]", Some(IntegerWidth::SixtyFour)),
+        ("[_ 1, 2]", None),
+    ] {
+        assert_eq!(parse_diag(text).unwrap(), array(bitwidth), "{}", text);
+    }
+}
+
+#[test]
+fn indefinite_starting_with_integer() {
+    assert_eq!(
+        parse_diag("[_1,2]").unwrap(),
+        DataItem::Array {
+            data: vec![
+                DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero,
+                },
+                DataItem::Integer {
+                    value: 2,
+                    bitwidth: IntegerWidth::Zero,
+                },
+            ],
+            bitwidth: None,
+        }
+    );
+    assert_eq!(
+        parse_diag("[_1]").unwrap(),
+        DataItem::Array {
+            data: vec![DataItem::Integer {
+                value: 1,
+                bitwidth: IntegerWidth::Zero,
+            }],
+            bitwidth: None,
+        }
+    );
+}
+
+#[test]
+fn parse_map_and_empty() {
+    assert_eq!(
+        parse_diag("{_0 1: 2}").unwrap(),
+        DataItem::Map {
+            data: vec![(
+                DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero,
+                },
+                DataItem::Integer {
+                    value: 2,
+                    bitwidth: IntegerWidth::Zero,
+                },
+            )],
+            bitwidth: Some(IntegerWidth::Eight),
+        }
+    );
+    assert_eq!(
+        parse_diag("[_1 ]").unwrap(),
+        DataItem::Array {
+            data: vec![],
+            bitwidth: Some(IntegerWidth::Sixteen),
+        }
+    );
+}
+
+#[test]
+fn encode() {
+    assert_eq!(array(Some(IntegerWidth::Unknown)).to_diag(), "[1,2]");
+    assert_eq!(array(Some(IntegerWidth::Zero)).to_diag(), "[1,2]");
+    assert_eq!(array(Some(IntegerWidth::Sixteen)).to_diag(), "[_1 1,2]");
+    assert_eq!(
+        array(Some(IntegerWidth::Sixteen)).to_diag_pretty(),
+        "[_1 1, 2]"
+    );
+    assert_eq!(array(None).to_diag(), "[_1,2]");
+}
+
+#[test]
+fn bytes_roundtrip() {
+    for hex in &[
+        "98020102",
+        "9900020102",
+        "9a000000020102",
+        "9b00000000000000020102",
+        "b9000101820203",
+        "9900018100",
+        "990000",
+        "9f990001010102ff",
+    ] {
+        let bytes = hex::decode(hex).unwrap();
+        let item = parse_bytes(&bytes).unwrap();
+        for diag in &[item.to_diag(), item.to_diag_pretty()] {
+            assert_eq!(
+                hex::encode(parse_diag(diag).unwrap().to_bytes()),
+                *hex,
+                "{}",
+                diag
+            );
+        }
+    }
+}
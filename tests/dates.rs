@@ -0,0 +1,100 @@
+use cbor_diag::{parse_diag, parse_hex, DataItem, IntegerWidth, Tag, TextString};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn last_comment(hex: &str) -> String {
+    let annotated = parse_hex(hex).unwrap().to_hex();
+    let last = annotated.lines().last().unwrap();
+    last.trim_start().trim_start_matches("#   ").to_owned()
+}
+
+#[test]
+fn epoch_date() {
+    assert_eq!(
+        parse_hex("d864194c6a").unwrap().to_hex(),
+        indoc!(
+            r#"
+            d8 64      # days since epoch, tag(100)
+               19 4c6a #   unsigned(19,562)
+                       #   date(2023-07-24)
+            "#
+        )
+    );
+}
+
+#[test]
+fn epoch_date_values() {
+    for (hex, expected) in &[
+        ("d86400", "date(1970-01-01)"),
+        ("d86420", "date(1969-12-31)"),
+        ("d864194ac8", "date(2022-06-01)"),
+        ("d8643a0098967f", "date(-25410-12-06)"),
+        ("d8641b7fffffffffffffff", "(date out of range)"),
+        ("d8643b7fffffffffffffff", "(date out of range)"),
+        ("d8641bffffffffffffffff", "(date out of range)"),
+        ("d8643bffffffffffffffff", "(date out of range)"),
+        ("d8646161", "invalid type for days since epoch"),
+    ] {
+        assert_eq!(last_comment(hex), *expected, "{}", hex);
+    }
+}
+
+#[test]
+fn full_date() {
+    assert_eq!(
+        parse_hex("d903ec6a323032322d30362d3031").unwrap().to_hex(),
+        indoc!(
+            r#"
+            d9 03ec                    # full-date string, tag(1004)
+               6a                      #   text(10)
+                  323032322d30362d3031 #     "2022-06-01"
+                                       #   valid full-date
+            "#
+        )
+    );
+}
+
+#[test]
+fn full_date_invalid() {
+    for date in &[
+        "2022-02-30",
+        "2022-6-01",
+        "+2022-06-01",
+        "2022-06-01T00:00:00Z",
+        "",
+    ] {
+        let item = DataItem::Tag {
+            tag: Tag::DATE,
+            bitwidth: IntegerWidth::Unknown,
+            value: Box::new(DataItem::TextString(TextString {
+                data: (*date).into(),
+                bitwidth: IntegerWidth::Unknown,
+            })),
+        };
+        assert_eq!(
+            item.to_hex().lines().last().unwrap().trim_start(),
+            "#   invalid full-date (expected YYYY-MM-DD)",
+            "{}",
+            date
+        );
+    }
+}
+
+#[test]
+fn date_literal() {
+    assert_eq!(
+        parse_diag("[d'2022-06-01', 1]").unwrap(),
+        parse_diag(r#"[1004("2022-06-01"), 1]"#).unwrap()
+    );
+    assert_eq!(
+        parse_diag("d'2022-06-01'").unwrap().to_diag(),
+        r#"1004("2022-06-01")"#
+    );
+}
+
+#[test]
+fn date_literal_invalid() {
+    for diag in &["d'2022-02-30'", "d'20220601'", "d''", "d'2022-06-01"] {
+        assert!(parse_diag(diag).is_err(), "{}", diag);
+    }
+}
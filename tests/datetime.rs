@@ -0,0 +1,100 @@
+#![cfg(feature = "datetime")]
+
+use cbor_diag::{parse_diag, DataItem, DateTimeStyle};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use pretty_assertions::assert_eq;
+
+fn datetime(text: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(text).unwrap()
+}
+
+#[test]
+fn text() {
+    let value = datetime("2013-03-21T20:04:00+01:00");
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Text).unwrap();
+    assert_eq!(item.to_diag(), r#"0("2013-03-21T20:04:00+01:00")"#);
+    assert_eq!(item.to_datetime(), Some(value));
+
+    let value = Utc.ymd(2013, 3, 21).and_hms_micro(20, 4, 0, 250);
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Text).unwrap();
+    assert_eq!(item.to_diag(), r#"0("2013-03-21T20:04:00.000250Z")"#);
+    assert_eq!(item.to_datetime(), Some(value.into()));
+}
+
+#[test]
+fn epoch() {
+    let value = datetime("2013-03-21T20:04:00Z");
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Epoch).unwrap();
+    assert_eq!(item.to_diag(), "1(1363896240)");
+    assert_eq!(item.to_datetime(), Some(value));
+
+    let item = DataItem::from_datetime(&value, DateTimeStyle::EpochFloat).unwrap();
+    assert_eq!(item.to_diag(), "1(1363896240.0)");
+    assert_eq!(item.to_datetime(), Some(value));
+
+    let value = datetime("1969-12-31T23:59:58.75Z");
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Epoch).unwrap();
+    assert_eq!(item.to_diag(), "1(-1.25)");
+    assert_eq!(item.to_datetime(), Some(value));
+
+    let value = datetime("1900-01-01T00:00:00Z");
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Epoch).unwrap();
+    assert_eq!(item.to_diag(), "1(-2208988800)");
+    assert_eq!(item.to_datetime(), Some(value));
+}
+
+#[test]
+fn parsed() {
+    let cases = [
+        ("1(0)", Some("1970-01-01T00:00:00Z")),
+        ("1(-1)", Some("1969-12-31T23:59:59Z")),
+        ("1(1.5)", Some("1970-01-01T00:00:01.5Z")),
+        ("1(-0.5)", Some("1969-12-31T23:59:59.5Z")),
+        ("1(0.9999999999)", Some("1970-01-01T00:00:01Z")),
+        (r#"0("2013-03-21T20:04:00Z")"#, Some("2013-03-21T20:04:00Z")),
+        // Leap seconds aren't allowed
+        (r#"0("2016-12-31T23:59:60Z")"#, None),
+        (r#"0("2013-03-21")"#, None),
+        ("1(18446744073709551615)", None),
+        ("1(-18446744073709551616)", None),
+        ("1(1.0e300)", None),
+        ("1(NaN)", None),
+        ("1(Infinity)", None),
+        // Within an `i64`, but not a year chrono supports
+        ("1(9000000000000000000)", None),
+        (r#"1("0")"#, None),
+        ("0(0)", None),
+        ("2(h'00')", None),
+        ("0", None),
+    ];
+    for &(diag, expected) in &cases {
+        assert_eq!(
+            parse_diag(diag).unwrap().to_datetime(),
+            expected.map(datetime),
+            "{}",
+            diag
+        );
+    }
+}
+
+#[test]
+fn unrepresentable() {
+    let value = Utc.ymd(10000, 1, 1).and_hms(0, 0, 0);
+    let err = DataItem::from_datetime(&value, DateTimeStyle::Text)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.ends_with("year 10000 cannot be written as an RFC 3339 date"),
+        "{}",
+        err
+    );
+    // Epochs have no such limit
+    let item = DataItem::from_datetime(&value, DateTimeStyle::Epoch).unwrap();
+    assert_eq!(item.to_diag(), "1(253402300800)");
+    assert_eq!(item.to_datetime(), Some(value.into()));
+
+    let leap = Utc.ymd(2016, 12, 31).and_hms_milli(23, 59, 59, 1_000);
+    for &style in &[DateTimeStyle::Text, DateTimeStyle::Epoch] {
+        assert!(DataItem::from_datetime(&leap, style).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use cbor_diag::{DataItem, IntegerWidth, Simple, Tag};
+
+const DEPTH: usize = 1_000_000;
+
+/// A chain of alternating arrays, maps and tags `DEPTH` items deep.
+fn chain() -> DataItem {
+    let mut item = DataItem::Simple(Simple::NULL);
+    for i in 0..DEPTH {
+        item = match i % 3 {
+            0 => DataItem::Array {
+                data: vec![item],
+                bitwidth: Some(IntegerWidth::Unknown),
+            },
+            1 => DataItem::Map {
+                data: vec![(DataItem::Simple(Simple::TRUE), item)],
+                bitwidth: None,
+            },
+            _ => DataItem::Tag {
+                tag: Tag(i as u64),
+                bitwidth: IntegerWidth::Unknown,
+                value: Box::new(item),
+            },
+        };
+    }
+    item
+}
+
+/// Run `f` on a thread with a stack far too small to recurse through a
+/// `chain()`.
+fn with_small_stack(f: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn drop() {
+    with_small_stack(|| std::mem::drop(chain()));
+}
+
+#[test]
+fn clone_and_compare() {
+    with_small_stack(|| {
+        let item = chain();
+        let clone = item.clone();
+        assert!(item == clone);
+    });
+}
+
+#[test]
+fn compare_different() {
+    with_small_stack(|| {
+        let item = chain();
+        let mut other = chain();
+        // Change the innermost item
+        let mut innermost = &mut other;
+        loop {
+            innermost = match innermost {
+                DataItem::Array { data, .. } => &mut data[0],
+                DataItem::Map { data, .. } => &mut data[0].1,
+                DataItem::Tag { value, .. } => value,
+                _ => break,
+            };
+        }
+        *innermost = DataItem::Simple(Simple::FALSE);
+        assert!(item != other);
+    });
+}
+
+#[test]
+fn shallow_items() {
+    let item = cbor_diag::parse_diag(r#"[1, {"a": 2(h'00')}, 1.5, [_ ]]"#).unwrap();
+    assert_eq!(item.clone(), item);
+    assert_ne!(
+        item,
+        cbor_diag::parse_diag(r#"[1, {"a": 2(h'01')}, 1.5, [_ ]]"#).unwrap()
+    );
+    assert_ne!(
+        item,
+        cbor_diag::parse_diag(r#"[1, {"a": 2(h'00')}, 1.5, []]"#).unwrap()
+    );
+    assert_ne!(
+        item,
+        cbor_diag::parse_diag(r#"[1, {"a": 2(h'00')}, 1.5]"#).unwrap()
+    );
+}
@@ -0,0 +1,474 @@
+use std::collections::BTreeMap;
+
+use cbor_diag::{
+    parse_diag, ByteString, DataItem, DiagFormatOptions, Encoding, IntegerWidth, Tag, TextString,
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const ESCAPE: DiagFormatOptions = DiagFormatOptions {
+    escape_non_ascii: true,
+    bytestring_encoding: Encoding::Base16,
+    prefer_text_bytestrings: false,
+    rational_approximations: false,
+    key_names: BTreeMap::new(),
+    sort_map_keys: false,
+    normalize_indefinite: false,
+    max_chunks: None,
+    emit_encoding_indicators: true,
+    trailing_commas: true,
+    max_line_width: 100,
+};
+
+fn text(data: &str) -> DataItem {
+    DataItem::TextString(TextString {
+        data: data.into(),
+        bitwidth: IntegerWidth::Unknown,
+    })
+}
+
+#[test]
+fn bidi_always_escaped() {
+    let item = text("user\u{202e}gnp.exe");
+    assert_eq!(item.to_diag(), r#""user\u{202e}gnp.exe""#);
+    assert_eq!(item.to_diag_pretty(), r#""user\u{202e}gnp.exe""#);
+    assert_eq!(item.to_diag_with(&ESCAPE), r#""user\u{202e}gnp.exe""#);
+    assert_eq!(parse_diag(item.to_diag()).unwrap(), item);
+}
+
+#[test]
+fn invisible_always_escaped() {
+    let item = text("a\u{200b}b\u{feff}c\u{ad}d");
+    assert_eq!(item.to_diag(), r#""a\u{200b}b\u{feff}c\u{ad}d""#);
+    assert_eq!(parse_diag(item.to_diag()).unwrap(), item);
+}
+
+#[test]
+fn non_ascii_default() {
+    let item = text("héllo 😀");
+    assert_eq!(item.to_diag(), r#""héllo 😀""#);
+}
+
+#[test]
+fn escape_non_ascii() {
+    let item = DataItem::Array {
+        data: vec![text("héllo 😀"), text("\u{202e}")],
+        bitwidth: Some(IntegerWidth::Unknown),
+    };
+    assert_eq!(
+        item.to_diag_with(&ESCAPE),
+        r#"["h\u{e9}llo \u{1f600}","\u{202e}"]"#
+    );
+    assert_eq!(
+        item.to_diag_pretty_with(&ESCAPE),
+        r#"["h\u{e9}llo \u{1f600}", "\u{202e}"]"#
+    );
+    assert!(item.to_diag_pretty_with(&ESCAPE).is_ascii());
+    assert_eq!(parse_diag(item.to_diag_with(&ESCAPE)).unwrap(), item);
+}
+
+fn bytes(data: &[u8], encoding: Option<Encoding>) -> DataItem {
+    DataItem::ByteString(ByteString {
+        data: data.into(),
+        bitwidth: IntegerWidth::Unknown,
+        encoding,
+    })
+}
+
+fn with_bytestring_encoding(bytestring_encoding: Encoding) -> DiagFormatOptions {
+    DiagFormatOptions {
+        bytestring_encoding,
+        ..DiagFormatOptions::default()
+    }
+}
+
+#[test]
+fn bytestring_encoding() {
+    let item = bytes(b"\xfb\xffhi", None);
+    assert_eq!(item.to_diag(), "h'fbff6869'");
+    for &(encoding, expected) in &[
+        (Encoding::Base16, "h'fbff6869'"),
+        (Encoding::Base64, "b64'+/9oaQ'"),
+        (Encoding::Base64Url, "b64'-_9oaQ'"),
+        (Encoding::Base32, "b32'7P7WQ2I'"),
+        (Encoding::Base32Hex, "h32'VFVMGQ8'"),
+        (Encoding::Text, "h'fbff6869'"),
+    ] {
+        let options = with_bytestring_encoding(encoding);
+        assert_eq!(item.to_diag_with(&options), expected);
+        assert_eq!(item.to_diag_pretty_with(&options), expected);
+        assert_eq!(parse_diag(expected).unwrap().to_bytes(), item.to_bytes());
+    }
+    assert_eq!(
+        bytes(b"it's", None).to_diag_with(&with_bytestring_encoding(Encoding::Text)),
+        r"'it\'s'"
+    );
+}
+
+#[test]
+fn bytestring_encoding_precedence() {
+    let options = with_bytestring_encoding(Encoding::Base64);
+    // The expected encoding from a tag beats the global preference
+    let tagged = DataItem::Tag {
+        tag: Tag::ENCODED_BASE16,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(bytes(b"hi", None)),
+    };
+    assert_eq!(tagged.to_diag_with(&options), "23(h'6869')");
+    // And the item's own encoding beats both
+    let tagged = DataItem::Tag {
+        tag: Tag::ENCODED_BASE16,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(bytes(b"hi", Some(Encoding::Text))),
+    };
+    assert_eq!(tagged.to_diag_with(&options), "23('hi')");
+}
+
+#[test]
+fn literal_form_preserved() {
+    let options = with_bytestring_encoding(Encoding::Base64Url);
+    for diag in &["h'6869'", "b64'aGk'", "'hi'", "22(h'6869')", "21('hi')"] {
+        let item = parse_diag(diag).unwrap();
+        assert_eq!(item.to_diag(), *diag);
+        assert_eq!(item.to_diag_with(&options), *diag);
+    }
+}
+
+#[test]
+fn prefer_text_bytestrings() {
+    let options = DiagFormatOptions {
+        prefer_text_bytestrings: true,
+        ..DiagFormatOptions::default()
+    };
+    for &(data, expected) in &[
+        (&b"hello world"[..], "'hello world'"),
+        (b"it's a \\ test", r"'it\'s a \\ test'"),
+        ("héllo".as_bytes(), "'héllo'"),
+        (b"line\n", "h'6c696e650a'"),
+        (b"\xff\xfe", "h'fffe'"),
+        ("\u{202e}abc".as_bytes(), "h'e280ae616263'"),
+        (b"", "h''"),
+    ] {
+        let item = bytes(data, None);
+        assert_eq!(item.to_diag_with(&options), expected);
+        assert_eq!(parse_diag(expected).unwrap().to_bytes(), item.to_bytes());
+    }
+}
+
+#[test]
+fn prefer_text_bytestrings_tags_win() {
+    let options = DiagFormatOptions {
+        prefer_text_bytestrings: true,
+        bytestring_encoding: Encoding::Base64,
+        ..DiagFormatOptions::default()
+    };
+    let item = DataItem::Array {
+        data: vec![
+            bytes(b"hi", None),
+            DataItem::Tag {
+                tag: Tag::ENCODED_BASE16,
+                bitwidth: IntegerWidth::Unknown,
+                value: Box::new(bytes(b"hi", None)),
+            },
+            DataItem::Tag {
+                tag: Tag::ENCODED_BASE64URL,
+                bitwidth: IntegerWidth::Unknown,
+                value: Box::new(bytes(b"hi", None)),
+            },
+            bytes(b"\x00", None),
+        ],
+        bitwidth: Some(IntegerWidth::Unknown),
+    };
+    assert_eq!(
+        item.to_diag_with(&options),
+        "['hi',23(h'6869'),21(b64'aGk'),b64'AA']"
+    );
+}
+
+#[test]
+fn key_names() {
+    let options = DiagFormatOptions {
+        key_names: vec![(1, "alg".into()), (-1, "crv".into()), (4, "kid".into())]
+            .into_iter()
+            .collect(),
+        ..DiagFormatOptions::default()
+    };
+    let item = parse_diag(r#"{1: -7, -1: 1, 2: {4: h'01'}, "1": 1}"#).unwrap();
+    assert_eq!(
+        item.to_diag_with(&options),
+        r#"{/ alg / 1:-7,/ crv / -1:1,2:{/ kid / 4:h'01'},"1":1}"#
+    );
+    assert_eq!(
+        item.to_diag_pretty_with(&options),
+        r#"{/ alg / 1: -7, / crv / -1: 1, 2: {/ kid / 4: h'01'}, "1": 1}"#
+    );
+    // Values and array items are not named
+    assert_eq!(
+        parse_diag("[1, {0: 1}]").unwrap().to_diag_with(&options),
+        "[1,{0:1}]"
+    );
+    assert_eq!(
+        parse_diag(&item.to_diag_pretty_with(&options)).unwrap(),
+        item
+    );
+}
+
+#[test]
+fn sort_map_keys() {
+    let options = DiagFormatOptions {
+        sort_map_keys: true,
+        ..DiagFormatOptions::default()
+    };
+    let first = parse_diag(r#"{"b": {2: 0, 1: 0}, 10: [{-1: 0, 0: 0}], "a": 1, 1_1: 2}"#).unwrap();
+    let second = parse_diag(r#"{1_1: 2, "a": 1, 10: [{0: 0, -1: 0}], "b": {1: 0, 2: 0}}"#).unwrap();
+    assert_ne!(first.to_diag_pretty(), second.to_diag_pretty());
+    assert_eq!(
+        first.to_diag_pretty_with(&options),
+        second.to_diag_pretty_with(&options)
+    );
+    assert_eq!(
+        first.to_diag_with(&options),
+        r#"{1_1:2,10:[{0:0,-1:0}],"a":1,"b":{1:0,2:0}}"#
+    );
+    // Only the display is sorted
+    assert_eq!(
+        first.to_diag(),
+        r#"{"b":{2:0,1:0},10:[{-1:0,0:0}],"a":1,1_1:2}"#
+    );
+}
+
+#[test]
+fn normalize_indefinite() {
+    let options = DiagFormatOptions {
+        normalize_indefinite: true,
+        ..DiagFormatOptions::default()
+    };
+    let item = parse_diag(r#"[(_ "he", "llo"), (_ h'01', h'02'), ''_, ""_, [_ 1]]"#).unwrap();
+    assert_eq!(
+        item.to_diag_with(&options),
+        r#"["hello",h'0102',h'',"",[_1]]"#
+    );
+    assert_eq!(
+        item.to_diag(),
+        r#"[(_"he","llo"),(_h'01',h'02'),''_,""_,[_1]]"#
+    );
+}
+
+#[test]
+fn no_encoding_indicators() {
+    let options = DiagFormatOptions {
+        emit_encoding_indicators: false,
+        ..DiagFormatOptions::default()
+    };
+    let cases = [
+        ("1_0", "1"),
+        ("-300_3", "-300"),
+        ("1.5_1", "1.5"),
+        ("NaN_2", "NaN"),
+        ("24_1(h'01')", "24(h'01')"),
+        ("[_ 1, [_0 2], {_1 3: 4}]", "[1, [2], {3: 4}]"),
+        ("{_ 1: [_ ]}", "{1: []}"),
+        (r#"(_ "a", "b")"#, r#""ab""#),
+        ("(_ h'01', h'02')", "h'0102'"),
+        (r#"''_"#, "h''"),
+        (r#"""_"#, r#""""#),
+    ];
+    for &(diag, plain) in &cases {
+        let item = parse_diag(diag).unwrap();
+        assert_eq!(item.to_diag_pretty_with(&options), plain, "{}", diag);
+        assert_eq!(
+            item.to_diag_with(&options),
+            plain.replace(", ", ",").replace(": ", ":"),
+            "{}",
+            diag
+        );
+    }
+}
+
+/// Check that `diag` has no commas directly before the end of a container,
+/// outside of strings and comments.
+fn assert_no_trailing_commas(diag: &str) {
+    let mut comma = false;
+    let mut chars = diag.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' => {
+                chars.by_ref().find(|&c| c == '/');
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            ']' | '}' | ')' => assert!(!comma, "trailing comma in {}", diag),
+            _ => {}
+        }
+        comma = c == ',';
+    }
+}
+
+#[test]
+fn trailing_commas() {
+    let options = DiagFormatOptions {
+        trailing_commas: false,
+        ..DiagFormatOptions::default()
+    };
+    let item = parse_diag(
+        r#"{
+            "array": [1, [_ "a long enough value to need wrapping", 12345678901234567890]],
+            "strings": (_ "a long enough value to need wrapping", "across chunks / "),
+            "bytes": (_ h'0123456789abcdef0123456789abcdef', h'0123456789abcdef0123456789abcdef'),
+            {_ "key": "a long enough value to need wrapping, with a comma,"}: 1,
+        }"#,
+    )
+    .unwrap();
+
+    let pretty = item.to_diag_pretty();
+    assert!(pretty.contains(",\n}"), "{}", pretty);
+    assert_eq!(parse_diag(&pretty).unwrap(), item);
+
+    let strict = item.to_diag_pretty_with(&options);
+    assert_no_trailing_commas(&strict);
+    let lines: Vec<&str> = pretty.lines().collect();
+    let expected: Vec<&str> = lines
+        .iter()
+        .zip(lines.iter().skip(1).map(Some).chain(Some(None)))
+        .map(|(line, next)| match next {
+            Some(next) if next.trim_start().starts_with(&[']', '}', ')'][..]) => {
+                line.strip_suffix(',').unwrap_or(line)
+            }
+            _ => line,
+        })
+        .collect();
+    assert_eq!(strict, expected.join("\n"));
+    assert_eq!(parse_diag(&strict).unwrap(), item);
+}
+
+#[test]
+fn trailing_commas_accepted() {
+    let cases = [
+        ("[1, 2,]", "[1,2]"),
+        ("[_ 1, 2 , ]", "[_1,2]"),
+        ("[_1 1,\n]", "[_1 1]"),
+        ("{1: 2,}", "{1:2}"),
+        ("{_ 1: 2, /comment/ }", "{_1:2}"),
+        (r#"(_ "a", "b",)"#, r#"(_"a","b")"#),
+        ("(_ h'01', h'02' ,\n)", "(_h'01',h'02')"),
+    ];
+    for &(diag, compact) in &cases {
+        assert_eq!(parse_diag(diag).unwrap().to_diag(), compact, "{}", diag);
+    }
+}
+
+#[test]
+fn fill_arrays() {
+    let bytes: Vec<String> = (0..100).map(|i| (i * 37 % 256).to_string()).collect();
+    let item = parse_diag(format!("[{}]", bytes.join(", "))).unwrap();
+    let pretty = item.to_diag_pretty();
+    assert_eq!(
+        pretty,
+        indoc!(
+            "
+            [
+                0, 37, 74, 111, 148, 185, 222, 3, 40, 77, 114, 151, 188, 225, 6, 43, 80, 117, 154, 191, 228, 9,
+                46, 83, 120, 157, 194, 231, 12, 49, 86, 123, 160, 197, 234, 15, 52, 89, 126, 163, 200, 237, 18,
+                55, 92, 129, 166, 203, 240, 21, 58, 95, 132, 169, 206, 243, 24, 61, 98, 135, 172, 209, 246, 27,
+                64, 101, 138, 175, 212, 249, 30, 67, 104, 141, 178, 215, 252, 33, 70, 107, 144, 181, 218, 255,
+                36, 73, 110, 147, 184, 221, 2, 39, 76, 113, 150, 187, 224, 5, 42, 79,
+            ]"
+        )
+    );
+    assert!(pretty.lines().all(|line| line.len() <= 100));
+    assert_eq!(parse_diag(&pretty).unwrap(), item);
+
+    let options = DiagFormatOptions {
+        max_line_width: 40,
+        trailing_commas: false,
+        ..DiagFormatOptions::default()
+    };
+    let item = parse_diag("{1: [_ 1.5, -2.5, 100000.0, 7.25, 3.0, -0.0, 0.00001, Infinity, 6.5], 2: [true, false, null, undefined, simple(16), true, false]}").unwrap();
+    let pretty = item.to_diag_pretty_with(&options);
+    assert_eq!(
+        pretty,
+        indoc!(
+            "
+            {
+                1: [_
+                    1.5, -2.5, 100000.0, 7.25, 3.0,
+                    -0.0, 0.00001, Infinity, 6.5
+                ],
+                2: [
+                    true, false, null, undefined,
+                    simple(16), true, false
+                ]
+            }"
+        )
+    );
+    assert_eq!(parse_diag(&pretty).unwrap(), item);
+}
+
+#[test]
+fn fill_only_homogeneous_arrays() {
+    let strings: Vec<String> = (0..24).map(|i| format!("\"{}\"", i)).collect();
+    let mixed: Vec<String> = (0..24)
+        .map(|i| {
+            if i % 2 == 0 {
+                i.to_string()
+            } else {
+                "1.5".into()
+            }
+        })
+        .collect();
+    let map: Vec<String> = (0..24).map(|i| format!("{}: {}", i, i)).collect();
+    for diag in &[
+        format!("[{}]", strings.join(", ")),
+        format!("[{}]", mixed.join(", ")),
+        format!("{{{}}}", map.join(", ")),
+    ] {
+        let item = parse_diag(diag).unwrap();
+        let pretty = item.to_diag_pretty();
+        // One item per line
+        assert_eq!(pretty.lines().count(), 26, "{}", pretty);
+        assert_eq!(parse_diag(&pretty).unwrap(), item);
+    }
+}
+
+#[test]
+fn layout_counts_rendered_width() {
+    // 40 characters, but 120 bytes of UTF-8
+    let japanese =
+        "いろはにほへとちりぬるをわかよたれそつねならむうゐのおくやまけふこえてあさきゆめ";
+    assert_eq!(japanese.chars().count(), 40);
+    let item = parse_diag(format!(r#"["{}"]"#, japanese)).unwrap();
+    assert_eq!(item.to_diag_pretty(), format!(r#"["{}"]"#, japanese));
+    // Unless it's escaped
+    assert_eq!(
+        item.to_diag_pretty_with(&ESCAPE).lines().count(),
+        3,
+        "{}",
+        item.to_diag_pretty_with(&ESCAPE)
+    );
+
+    // 20 bytes of UTF-8, but 120 characters once escaped
+    let item = parse_diag(r#"["\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001\u0001"]"#).unwrap();
+    assert_eq!(item.to_diag_pretty().lines().count(), 3);
+
+    // 30 bytes are 60 characters as hex, but 40 as base64
+    let item = DataItem::Array {
+        data: vec![bytes(&[0xff; 30], None)],
+        bitwidth: Some(IntegerWidth::Unknown),
+    };
+    assert_eq!(item.to_diag_pretty().lines().count(), 3);
+    assert_eq!(
+        item.to_diag_pretty_with(&with_bytestring_encoding(Encoding::Base64)),
+        format!("[b64'{}']", "/".repeat(40)),
+    );
+}
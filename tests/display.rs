@@ -0,0 +1,98 @@
+use cbor_diag::{parse_bytes, parse_hex, roundtrip::semantically_eq, DataItem};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn display() {
+    let item = parse_hex("a2 61 61 82 01 02 61 62 d8 20 60").unwrap();
+    assert_eq!(format!("{}", item), r#"{"a":[1,2],"b":32_0("")}"#);
+    assert_eq!(item.to_string(), item.to_diag());
+    assert_eq!(format!("{:#}", item), item.to_diag_pretty());
+    assert!(!item.to_string().contains('\x1b'));
+}
+
+#[test]
+fn display_pretty() {
+    let item: DataItem =
+        r#"{"key": ["a long enough value to need wrapping", 12345678901234567890]}"#
+            .parse()
+            .unwrap();
+    assert_eq!(
+        format!("{:#}", item),
+        indoc!(
+            r#"
+            {
+                "key": [
+                    "a long enough value to need wrapping",
+                    12345678901234567890,
+                ],
+            }"#
+        )
+    );
+}
+
+#[test]
+fn from_str() {
+    let item: DataItem = "[1, h'02', (_ \"a\", \"b\")]".parse().unwrap();
+    assert_eq!(item.to_bytes(), b"\x83\x01\x41\x02\x7f\x61a\x61b\xff");
+    assert!("[1, ".parse::<DataItem>().is_err());
+}
+
+#[test]
+fn roundtrip() {
+    let item =
+        parse_bytes(b"\xbf\x63key\x9f\xf9\x3c\x00\x20\xc1\x1a\x00\x01\x00\x00\xff\xff").unwrap();
+    let parsed: DataItem = item.to_string().parse().unwrap();
+    assert!(semantically_eq(&item, &parsed));
+    let parsed: DataItem = format!("{:#}", item).parse().unwrap();
+    assert!(semantically_eq(&item, &parsed));
+}
+
+#[test]
+fn plain_strings() {
+    let item: DataItem = r#"{"\u001b[31m": [1, 0("2013-03-21T20:04:00Z"), h'1b'], "b": simple(1)}"#
+        .parse()
+        .unwrap();
+    for diag in &[item.to_diag(), item.to_diag_pretty()] {
+        assert!(!diag.contains('\x1b'), "{}", diag);
+        let parsed: DataItem = diag.parse().unwrap();
+        assert!(semantically_eq(&item, &parsed), "{}", diag);
+    }
+}
+
+#[test]
+fn no_control_characters() {
+    let diag = r#"
+        [
+            {"\u001b[31m": (_ "a\u009b", "\u001b"), 1: 0("2013-03-21T20:04:00Z\u001b")},
+            32("http://\u001b[2J"), 35("\u001b+"), 36("text/plain\u001b"),
+            21([h'1b', {h'1b': (_ h'1b9b', h'')}]), 22(h'1b'), 23('\u001b'),
+            24(h'621b1b'), 55799([_ '\u001b', 1_0, 1.5_1]),
+            [_ d'2022-06-01', simple(1), undefined],
+            foo'\u001b[31m',
+        ]
+    "#;
+    let options = cbor_diag::ParseOptions {
+        allow_unknown_app_literals: true,
+        ..Default::default()
+    };
+    let item = cbor_diag::parse_diag_with(diag, &options).unwrap();
+    let without_app_literal = match &item {
+        DataItem::Array { data, .. } => cbor_diag::items_to_array(data[..data.len() - 1].to_vec()),
+        _ => unreachable!(),
+    };
+    let outputs = vec![
+        item.to_diag(),
+        item.to_diag_pretty(),
+        item.to_hex(),
+        without_app_literal.to_definite().to_diag(),
+        without_app_literal.to_hex(),
+    ];
+    for output in outputs {
+        assert!(
+            !output.chars().any(|c| c.is_control() && c != '\n'),
+            "{}",
+            output
+        );
+    }
+}
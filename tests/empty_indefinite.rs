@@ -0,0 +1,68 @@
+use cbor_diag::{parse_bytes, parse_diag, parse_hex};
+use pretty_assertions::assert_eq;
+
+/// Round-trip the exact encoded bytes through every representation.
+fn roundtrip(bytes: &[u8]) {
+    let item = parse_bytes(bytes).unwrap();
+    assert_eq!(item.to_bytes(), bytes);
+    assert_eq!(parse_hex(item.to_hex()).unwrap().to_bytes(), bytes);
+    assert_eq!(parse_diag(item.to_diag()).unwrap().to_bytes(), bytes);
+    assert_eq!(parse_diag(item.to_diag_pretty()).unwrap().to_bytes(), bytes);
+}
+
+#[test]
+fn definite() {
+    roundtrip(b"\x40");
+    roundtrip(b"\x60");
+    roundtrip(b"\x80");
+    roundtrip(b"\xa0");
+}
+
+#[test]
+fn bytestring() {
+    roundtrip(b"\x5f\xff");
+}
+
+#[test]
+fn textstring() {
+    roundtrip(b"\x7f\xff");
+}
+
+#[test]
+fn array() {
+    roundtrip(b"\x9f\xff");
+}
+
+#[test]
+fn map() {
+    roundtrip(b"\xbf\xff");
+}
+
+#[test]
+fn nested() {
+    roundtrip(b"\x9f\x5f\xff\x7f\xff\x9f\xff\xbf\xff\x80\xa0\xff");
+    roundtrip(b"\xbf\x7f\xff\x9f\xff\x5f\xff\xbf\xff\xff");
+}
+
+#[test]
+fn diag_forms() {
+    for (diag, bytes) in &[
+        ("(_ )", &b"\x5f\xff"[..]),
+        ("(_)", b"\x5f\xff"),
+        ("[_ ]", b"\x9f\xff"),
+        ("[_]", b"\x9f\xff"),
+        ("{_ }", b"\xbf\xff"),
+        ("{_}", b"\xbf\xff"),
+        ("''_", b"\x5f\xff"),
+        (r#"""_"#, b"\x7f\xff"),
+        ("[''_, \"\"_]", b"\x82\x5f\xff\x7f\xff"),
+        (
+            "(_ h'0102', h'030405')",
+            b"\x5f\x42\x01\x02\x43\x03\x04\x05\xff",
+        ),
+        ("[_ [_ ], {_ }]", b"\x9f\x9f\xff\xbf\xff\xff"),
+        (r#"[_ (_), ""_]"#, b"\x9f\x5f\xff\x7f\xff\xff"),
+    ] {
+        assert_eq!(parse_diag(diag).unwrap().to_bytes(), *bytes, "{}", diag);
+    }
+}
@@ -0,0 +1,131 @@
+use cbor_diag::{parse_diag, ByteString, DataItem, Encoding, IntegerWidth, TextString};
+use pretty_assertions::assert_eq;
+
+fn error(diag: &str) -> String {
+    parse_diag(diag).unwrap_err().to_string()
+}
+
+#[test]
+fn legal() {
+    for &(diag, bytes) in &[
+        ("5_0", "1805"),
+        ("5_3", "1b0000000000000005"),
+        ("-1_1", "390000"),
+        ("1.5_1", "f93e00"),
+        ("NaN_3", "fb7ff8000000000000"),
+        ("Infinity_2", "fa7f800000"),
+        ("1_1(2)", "d9000102"),
+        ("[_0 1]", "980101"),
+        ("{_1 1: 2}", "b900010102"),
+        ("[_1]", "9f01ff"),
+        ("{_7: 2}", "bf0702ff"),
+        ("''_", "5fff"),
+        (r#"""_"#, "7fff"),
+    ] {
+        let item = parse_diag(diag).unwrap();
+        assert_eq!(hex::encode(item.to_bytes()), bytes, "{}", diag);
+    }
+}
+
+#[test]
+fn string_lengths() {
+    assert_eq!(
+        parse_diag(r#""x"_1"#).unwrap(),
+        DataItem::TextString(TextString {
+            data: "x".into(),
+            bitwidth: IntegerWidth::Sixteen,
+        })
+    );
+    assert_eq!(
+        parse_diag("h'01' h'02'_0").unwrap(),
+        DataItem::ByteString(ByteString {
+            data: vec![1, 2],
+            bitwidth: IntegerWidth::Eight,
+            encoding: Some(Encoding::Base16),
+        })
+    );
+    for &(diag, bytes) in &[
+        ("''_0", "5800"),
+        (r#"""_2"#, "7a00000000"),
+        ("b64'AA'_1", "59000100"),
+        (r#"(_ "a"_0, "b")"#, "7f7801616162ff"),
+    ] {
+        let item = parse_diag(diag).unwrap();
+        assert_eq!(hex::encode(item.to_bytes()), bytes, "{}", diag);
+    }
+}
+
+#[test]
+fn out_of_range() {
+    for &(diag, offset) in &[
+        ("5_7", 1),
+        ("5_4", 1),
+        ("5_00", 1),
+        ("5_", 1),
+        ("1_000", 1),
+        ("-1_4", 2),
+        ("1.5_4", 3),
+        ("1_9(2)", 1),
+        (r#""x"_9"#, 3),
+        ("h'01'_5", 5),
+        ("[1_7]", 2),
+        ("[_7 1]", 1),
+        ("{_4 1: 2}", 1),
+    ] {
+        assert_eq!(
+            error(diag),
+            format!(
+                "TODO cbor-diag::Error: Parsing error at offset {}: encoding indicator must be _0 to _3",
+                offset
+            ),
+            "{}",
+            diag
+        );
+    }
+}
+
+#[test]
+fn float_eight_bits() {
+    for &(diag, offset) in &[("1.5_0", 3), ("NaN_0", 3), ("[-Infinity_0]", 10)] {
+        assert_eq!(
+            error(diag),
+            format!(
+                "TODO cbor-diag::Error: Parsing error at offset {}: encoding indicator on a float must be _1 to _3",
+                offset
+            ),
+            "{}",
+            diag
+        );
+    }
+}
+
+#[test]
+fn not_allowed() {
+    for &(diag, offset) in &[
+        ("true_1", 4),
+        ("null_0", 4),
+        ("undefined_2", 9),
+        ("simple(5)_1", 9),
+        ("[1]_1", 3),
+        ("{}_0", 2),
+        ("[_ 1]_0", 5),
+        ("1(2)_1", 4),
+        (r#"(_ "a")_1"#, 7),
+        ("(_ h'01')_0", 9),
+        ("dt'x'_1", 5),
+        ("5_1_1", 3),
+        ("5 _1", 2),
+        ("h'01'_0 h'02'", 5),
+        ("[1, true_0]", 8),
+    ] {
+        assert_eq!(
+            error(diag),
+            format!(
+                "TODO cbor-diag::Error: Parsing error at offset {}: encoding indicator not allowed on this item",
+                offset
+            ),
+            "{}",
+            diag
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use cbor_diag::{parse_diag, DataItem, IntegerWidth, TextString};
+use pretty_assertions::assert_eq;
+
+fn text(data: &str) -> DataItem {
+    DataItem::TextString(TextString {
+        data: data.into(),
+        bitwidth: IntegerWidth::Unknown,
+    })
+}
+
+fn error(diag: &str) -> String {
+    format!("{:?}", parse_diag(diag).unwrap_err())
+}
+
+#[test]
+fn json_escapes() {
+    assert_eq!(
+        parse_diag(r#""\"\\\/\b\f\n\r\t""#).unwrap(),
+        text("\"\\/\u{8}\u{c}\n\r\t")
+    );
+    assert_eq!(parse_diag(r#""\u00e9\u20AC""#).unwrap(), text("é€"));
+}
+
+#[test]
+fn surrogate_pair() {
+    assert_eq!(parse_diag(r#""\ud83d\ude00""#).unwrap(), text("😀"));
+}
+
+#[test]
+fn braced() {
+    assert_eq!(parse_diag(r#""\u{1f600}\u{a}""#).unwrap(), text("😀\n"));
+}
+
+#[test]
+fn byte_string() {
+    assert_eq!(parse_diag(r"'\n\u0000'").unwrap().to_bytes(), b"\x42\n\0");
+}
+
+#[test]
+fn encoded() {
+    let item = text("line 1\n\tline 2\r\u{0}\u{7f} 😀 \"\\");
+    assert_eq!(
+        item.to_diag(),
+        r#""line 1\u000a\u0009line 2\u000d\u0000\u007f 😀 \"\\""#
+    );
+    assert_eq!(parse_diag(item.to_diag()).unwrap(), item);
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(
+        error(r#""abc\q""#),
+        r#"Todo("Parsing error at offset 4: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#"["a", "\u12"]"#),
+        r#"Todo("Parsing error at offset 7: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#"{1: "\u{110000}"}"#),
+        r#"Todo("Parsing error at offset 5: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#"'\x00'"#),
+        r#"Todo("Parsing error at offset 1: invalid escape sequence")"#
+    );
+}
+
+#[test]
+fn lone_surrogate() {
+    assert_eq!(
+        error(r#""\ud83d""#),
+        r#"Todo("Parsing error at offset 1: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#""\ud83dA""#),
+        r#"Todo("Parsing error at offset 1: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#""a\ude00""#),
+        r#"Todo("Parsing error at offset 2: invalid escape sequence")"#
+    );
+    assert_eq!(
+        error(r#""\u{d800}""#),
+        r#"Todo("Parsing error at offset 1: invalid escape sequence")"#
+    );
+}
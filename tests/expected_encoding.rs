@@ -0,0 +1,97 @@
+use cbor_diag::{parse_bytes, parse_diag, parse_hex, DataItem};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+// 2x([h'00ff', {1: h'ff'}, (_ h'01', h'02'), [h'fb']]) with the tag given
+fn nested(tag: &str) -> String {
+    format!("{} 84 4200ff a1 01 41ff 5f 4101 4102 ff 81 41fb", tag)
+}
+
+/// Parse `text` through its binary encoding, so byte strings don't keep the
+/// form of their literals.
+fn from_diag(text: &str) -> DataItem {
+    parse_bytes(parse_diag(text).unwrap().to_bytes()).unwrap()
+}
+
+#[test]
+fn diag() {
+    assert_eq!(
+        parse_hex(nested("d5")).unwrap().to_diag(),
+        "21([b64'AP8',{1:b64'_w'},(_b64'AQ',b64'Ag'),[b64'-w']])"
+    );
+    assert_eq!(
+        parse_hex(nested("d6")).unwrap().to_diag(),
+        "22([b64'AP8',{1:b64'/w'},(_b64'AQ',b64'Ag'),[b64'+w']])"
+    );
+    assert_eq!(
+        parse_hex(nested("d7")).unwrap().to_diag(),
+        "23([h'00ff',{1:h'ff'},(_h'01',h'02'),[h'fb']])"
+    );
+}
+
+#[test]
+fn diag_map_keys() {
+    assert_eq!(
+        from_diag("22({h'ff': [h'fb'], 1: {h'00': h'01'}})").to_diag(),
+        "22({b64'/w':[b64'+w'],1:{b64'AA':b64'AQ'}})"
+    );
+}
+
+#[test]
+fn diag_innermost_tag() {
+    assert_eq!(
+        from_diag("21([h'fb', 23([h'fb', 22({1: h'fb'})]), h'fb'])").to_diag(),
+        "21([b64'-w',23([h'fb',22({1:b64'+w'})]),b64'-w'])"
+    );
+}
+
+#[test]
+fn diag_pretty_layout() {
+    // Too wide as hex, but fits on one line in the expected base64
+    let item = from_diag("22([h'000102030405060708090a0b', h'000102030405060708090a0b'])");
+    assert_eq!(
+        item.to_diag_pretty(),
+        "22([b64'AAECAwQFBgcICQoL', b64'AAECAwQFBgcICQoL'])"
+    );
+    let item = from_diag("[22([h'00010203040506070809', h'00010203040506070809'])]");
+    assert_eq!(
+        item.to_diag_pretty(),
+        "[22([b64'AAECAwQFBgcICQ', b64'AAECAwQFBgcICQ'])]"
+    );
+}
+
+#[test]
+fn hex() {
+    assert_eq!(
+        parse_hex(nested("d5")).unwrap().to_hex(),
+        indoc!(
+            "
+            d5             # suggested base64url encoding, tag(21)
+               84          #   array(4)
+                  42       #     bytes(2)
+                     00ff  #       b64'AP8'
+                  a1       #     map(1)
+                     01    #       unsigned(1)
+                     41    #       bytes(1)
+                        ff #         b64'_w'
+                  5f       #     bytes(*)
+                     41    #       bytes(1)
+                        01 #         b64'AQ'
+                     41    #       bytes(1)
+                        02 #         b64'Ag'
+                     ff    #       break
+                  81       #     array(1)
+                     41    #       bytes(1)
+                        fb #         b64'-w'
+            "
+        )
+    );
+    let hex = parse_hex(nested("d6")).unwrap().to_hex();
+    for comment in ["b64'AP8'", "b64'/w'", "b64'AQ'", "b64'Ag'", "b64'+w'"] {
+        assert!(hex.contains(comment), "{}", hex);
+    }
+    let hex = parse_hex(nested("d7")).unwrap().to_hex();
+    for comment in ["h'00ff'", "h'ff'", "h'01'", "h'02'", "h'fb'"] {
+        assert!(hex.contains(comment), "{}", hex);
+    }
+}
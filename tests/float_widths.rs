@@ -0,0 +1,185 @@
+use cbor_diag::{
+    parse_bytes, parse_diag, parse_diag_with, parse_diag_with_warnings, DataItem, FloatWidth,
+    ParseOptions,
+};
+use pretty_assertions::assert_eq;
+
+fn minimal(item: &DataItem) -> String {
+    let mut item = item.clone();
+    item.minimal_float_widths();
+    hex::encode(item.to_bytes())
+}
+
+#[test]
+fn rfc8949_appendix_a() {
+    let examples = [
+        ("0.0", "f90000"),
+        ("-0.0", "f98000"),
+        ("1.0", "f93c00"),
+        ("1.1", "fb3ff199999999999a"),
+        ("1.5", "f93e00"),
+        ("65504.0", "f97bff"),
+        ("100000.0", "fa47c35000"),
+        ("3.4028234663852886e+38", "fa7f7fffff"),
+        ("1.0e+300", "fb7e37e43c8800759c"),
+        ("5.960464477539063e-8", "f90001"),
+        ("0.00006103515625", "f90400"),
+        ("-4.0", "f9c400"),
+        ("-4.1", "fbc010666666666666"),
+        ("Infinity", "f97c00"),
+        ("NaN", "f97e00"),
+        ("-Infinity", "f9fc00"),
+    ];
+
+    for &(diag, expected) in &examples {
+        assert_eq!(minimal(&parse_diag(diag).unwrap()), expected, "{}", diag);
+    }
+}
+
+#[test]
+fn narrows_wide_encodings() {
+    let examples = [
+        ("fb3ff8000000000000", "f93e00"),
+        ("fa3fc00000", "f93e00"),
+        ("fb7ff0000000000000", "f97c00"),
+        ("fbfff0000000000000", "f9fc00"),
+        ("fb3e70000000000000", "f90001"),
+        ("fb3fb999999999999a", "fb3fb999999999999a"),
+    ];
+
+    for &(input, expected) in &examples {
+        let item = parse_bytes(hex::decode(input).unwrap()).unwrap();
+        assert_eq!(minimal(&item), expected, "{}", input);
+    }
+}
+
+#[test]
+fn nan_payloads() {
+    let item = parse_bytes(hex::decode("fb7ff8000000000001").unwrap()).unwrap();
+    assert_eq!(minimal(&item), "f97e00");
+
+    let mut preserved = item.clone();
+    preserved.minimal_float_widths_preserving_nan();
+    assert_eq!(hex::encode(preserved.to_bytes()), "fb7ff8000000000001");
+
+    let mut preserved = parse_bytes(hex::decode("fb7ff8040000000000").unwrap()).unwrap();
+    preserved.minimal_float_widths_preserving_nan();
+    assert_eq!(hex::encode(preserved.to_bytes()), "f97e01");
+}
+
+#[test]
+fn nested() {
+    let mut item = parse_diag("[1.5_3, {0.1_3: 1(100000.0_3)}, [_ 65504.0]]").unwrap();
+    item.minimal_float_widths();
+    assert_eq!(item.to_diag(), "[1.5_1,{0.1_3:1(100000.0_2)},[_65504.0_1]]");
+    match &item {
+        DataItem::Array { data, .. } => match data[0] {
+            DataItem::Float { bitwidth, .. } => assert_eq!(bitwidth, FloatWidth::Sixteen),
+            ref other => panic!("unexpected item {:?}", other),
+        },
+        other => panic!("unexpected item {:?}", other),
+    }
+}
+
+#[test]
+fn canonical_key_matches() {
+    let items = ["[1.5, 0.1, 100000.0, Infinity, NaN]", "{1.0: -0.0}"];
+    for diag in &items {
+        let item = parse_diag(diag).unwrap();
+        let mut narrowed = item.clone();
+        narrowed.minimal_float_widths();
+        assert_eq!(item.canonical_key(), narrowed.to_bytes(), "{}", diag);
+    }
+}
+
+#[test]
+fn every_half_round_trips() {
+    for bits in 0..=u16::max_value() {
+        let value = half::f16::from_bits(bits);
+        if value.is_nan() {
+            continue;
+        }
+        let bytes = [0xf9, (bits >> 8) as u8, bits as u8];
+        let diag = parse_bytes(&bytes[..]).unwrap().to_diag();
+        let item = parse_diag(&diag).unwrap_or_else(|err| panic!("{}: {}", diag, err));
+        assert_eq!(hex::encode(item.to_bytes()), hex::encode(bytes), "{}", diag);
+    }
+}
+
+#[test]
+fn out_of_range_for_width() {
+    for &diag in &["65536.0_1", "-1.0e300_2", "1.0e-8_1", "1.0e-50_2"] {
+        let err = parse_diag(diag).unwrap_err().to_string();
+        assert!(
+            err.ends_with("float out of range for its encoding indicator"),
+            "{}: {}",
+            diag,
+            err
+        );
+    }
+    assert_eq!(
+        hex::encode(
+            parse_diag("[65504.0_1, Infinity_1, -0.0_1]")
+                .unwrap()
+                .to_bytes()
+        ),
+        "83f97bfff97c00f98000"
+    );
+}
+
+#[test]
+fn lossy_narrowing() {
+    let err = parse_diag("[1, 0.1_1]").unwrap_err().to_string();
+    assert!(
+        err.ends_with("Parsing error at offset 4: 0.1 is not exactly representable as a 16-bit float; nearest is 0.0999755859375"),
+        "{}",
+        err
+    );
+    let err = parse_diag("{\"a\": 0.123456789_2}")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.ends_with("0.123456789 is not exactly representable as a 32-bit float; nearest is 0.12345679104328156"),
+        "{}",
+        err
+    );
+
+    // The shortest text for a value at its width is exact, as are literals
+    // without an indicator or wider than the value needs
+    for &diag in &[
+        "0.1_2",
+        "0.099975586_1",
+        "1.0996094_1",
+        "0.50_1",
+        "5.9604645e-8_1",
+        "0.1",
+        "0.1_3",
+    ] {
+        parse_diag(diag).unwrap_or_else(|err| panic!("{}: {}", diag, err));
+    }
+    // Text and comments aren't float literals
+    parse_diag(r#"["0.1_1", '0.1_1', / 0.1_1 / h'01']"#).unwrap();
+}
+
+#[test]
+fn allow_lossy_narrowing() {
+    let options = ParseOptions {
+        allow_lossy_float_narrowing: true,
+        ..ParseOptions::default()
+    };
+    let (item, warnings) = parse_diag_with_warnings("[1.1_1, 0.5_1, -0.1_2]", &options).unwrap();
+    assert_eq!(hex::encode(item.to_bytes()), "83f93c66f93800fabdcccccd");
+    assert_eq!(
+        warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+        [
+            "at offset 1: 1.1 is not exactly representable as a 16-bit float; nearest is 1.099609375",
+        ]
+    );
+    assert_eq!(
+        parse_diag_with("1.1_1", &options).unwrap().to_bytes(),
+        [0xf9, 0x3c, 0x66]
+    );
+
+    // Without the option the first warning is an error
+    assert!(parse_diag_with_warnings("[1.1_1]", &ParseOptions::default()).is_err());
+}
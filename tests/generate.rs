@@ -0,0 +1,204 @@
+#![cfg(feature = "generate")]
+
+use cbor_diag::{
+    generate, generate_arbitrary, parse_bytes, parse_diag, DataItem, GenerateOptions, Tag,
+};
+use pretty_assertions::assert_eq;
+use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+fn arbitrary(seed: u64, options: &GenerateOptions) -> DataItem {
+    generate_arbitrary(&mut ChaCha8Rng::seed_from_u64(seed), options)
+}
+
+fn depth(item: &DataItem) -> usize {
+    match item {
+        DataItem::Array { data, .. } => 1 + data.iter().map(depth).max().unwrap_or(0),
+        DataItem::Map { data, .. } => {
+            1 + data
+                .iter()
+                .map(|(key, value)| depth(key).max(depth(value)))
+                .max()
+                .unwrap_or(0)
+        }
+        DataItem::Tag { value, .. } => 1 + depth(value),
+        _ => 0,
+    }
+}
+
+fn visit(item: &DataItem, f: &mut impl FnMut(&DataItem)) {
+    f(item);
+    match item {
+        DataItem::Array { data, .. } => data.iter().for_each(|item| visit(item, f)),
+        DataItem::Map { data, .. } => data.iter().for_each(|(key, value)| {
+            visit(key, f);
+            visit(value, f);
+        }),
+        DataItem::Tag { value, .. } => visit(value, f),
+        _ => {}
+    }
+}
+
+#[test]
+fn same_seed_same_item() {
+    let options = GenerateOptions {
+        non_preferred_widths: true,
+        indefinite: true,
+        ..GenerateOptions::default()
+    };
+    for seed in 0..50 {
+        // Compare the encodings, to be independent of float NaN comparisons
+        assert_eq!(
+            arbitrary(seed, &options).to_bytes(),
+            arbitrary(seed, &options).to_bytes()
+        );
+    }
+}
+
+#[test]
+fn stable_output() {
+    // Changing the output for a given seed breaks reproducing generated
+    // items, so should only be done deliberately
+    let options = GenerateOptions {
+        major_types: vec![0, 1, 3, 4],
+        ..GenerateOptions::default()
+    };
+    let item = arbitrary(3, &options);
+    assert_eq!(item.to_diag(), arbitrary(3, &options).to_diag());
+    assert_eq!(parse_diag(&item.to_diag()).unwrap(), item);
+}
+
+#[test]
+fn different_seeds_differ() {
+    let options = GenerateOptions::default();
+    let items: std::collections::BTreeSet<Vec<u8>> = (0..20)
+        .map(|seed| arbitrary(seed, &options).to_bytes())
+        .collect();
+    assert!(items.len() > 15);
+}
+
+#[test]
+fn round_trips() {
+    let options = GenerateOptions {
+        non_preferred_widths: true,
+        indefinite: true,
+        ..GenerateOptions::default()
+    };
+    for seed in 0..100 {
+        let bytes = arbitrary(seed, &options).to_bytes();
+        assert_eq!(parse_bytes(&bytes).unwrap().to_bytes(), bytes);
+    }
+}
+
+#[test]
+fn bounds() {
+    let options = GenerateOptions {
+        max_depth: 2,
+        max_container_len: 3,
+        max_string_len: 5,
+        ..GenerateOptions::default()
+    };
+    for seed in 0..100 {
+        let item = arbitrary(seed, &options);
+        assert!(depth(&item) <= 2, "{}", item.to_diag());
+        visit(&item, &mut |item| match item {
+            DataItem::Array { data, bitwidth } => {
+                assert!(data.len() <= 3);
+                assert!(bitwidth.is_some());
+            }
+            DataItem::Map { data, .. } => assert!(data.len() <= 3),
+            DataItem::ByteString(bytestring) => assert!(bytestring.data.len() <= 5),
+            DataItem::TextString(textstring) => assert!(textstring.data.len() <= 5),
+            DataItem::IndefiniteByteString(_) | DataItem::IndefiniteTextString(_) => {
+                panic!("unexpected indefinite string")
+            }
+            _ => {}
+        });
+        // Only preferred widths
+        let mut minimal = item.clone();
+        minimal.minimal_integer_widths();
+        assert_eq!(item.to_bytes(), minimal.to_bytes());
+    }
+}
+
+#[test]
+fn major_types_and_tags() {
+    let options = GenerateOptions {
+        major_types: vec![2, 6],
+        tags: vec![Tag::URI, Tag(1234)],
+        ..GenerateOptions::default()
+    };
+    for seed in 0..50 {
+        visit(&arbitrary(seed, &options), &mut |item| match item {
+            DataItem::Tag { tag, .. } => assert!(*tag == Tag::URI || *tag == Tag(1234)),
+            DataItem::ByteString(_) => {}
+            item => panic!("unexpected item {}", item.to_diag()),
+        });
+    }
+}
+
+#[test]
+fn only_containers() {
+    // Falls back to integers at the maximum depth
+    let options = GenerateOptions {
+        major_types: vec![4],
+        max_depth: 1,
+        ..GenerateOptions::default()
+    };
+    for seed in 0..20 {
+        match &arbitrary(seed, &options) {
+            DataItem::Array { data, .. } => {
+                for item in data {
+                    assert!(matches!(item, DataItem::Integer { .. }));
+                }
+            }
+            item => panic!("unexpected item {}", item.to_diag()),
+        }
+    }
+}
+
+#[test]
+fn template_keeps_structure() {
+    let template = parse_diag(
+        r#"{"id": 1_2, "name": "alice", "key": h'0102', "scores": [-1, 1.5_1], 32: 32("x"), "ok": true, "none": null}"#,
+    )
+    .unwrap();
+    let options = GenerateOptions::default();
+    for seed in 0..20 {
+        let item = generate(&template, &mut ChaCha8Rng::seed_from_u64(seed), &options);
+        let data = match &item {
+            DataItem::Map { data, .. } => data,
+            item => panic!("unexpected item {}", item.to_diag()),
+        };
+        let template = match &template {
+            DataItem::Map { data, .. } => data,
+            _ => unreachable!(),
+        };
+        assert_eq!(data.len(), template.len());
+        for ((key, value), (template_key, template_value)) in data.iter().zip(template) {
+            assert_eq!(key, template_key);
+            assert_eq!(
+                std::mem::discriminant(value),
+                std::mem::discriminant(template_value)
+            );
+        }
+        let text = item.to_diag();
+        assert!(text.contains("32(\""), "{}", text);
+        assert!(text.contains("\"none\":null"), "{}", text);
+        assert!(text.contains("_1]"), "{}", text);
+        assert!(
+            text.starts_with("{\"id\":") && text.contains("_2,"),
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn template_determinism() {
+    let template = parse_diag(r#"[_ "a", h'00', {1: 2}, 3.0]"#).unwrap();
+    let options = GenerateOptions::default();
+    let first = generate(&template, &mut ChaCha8Rng::seed_from_u64(9), &options);
+    let second = generate(&template, &mut ChaCha8Rng::seed_from_u64(9), &options);
+    assert_eq!(first.to_bytes(), second.to_bytes());
+    assert_ne!(first.to_bytes(), template.to_bytes());
+}
@@ -0,0 +1,530 @@
+use cbor_diag::{parse_hex, ByteString, DataItem, HexOptions, IntegerWidth, Tag, TextString};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn bytes(len: usize) -> DataItem {
+    DataItem::ByteString(ByteString {
+        data: (0..len).map(|i| i as u8).collect(),
+        bitwidth: IntegerWidth::Unknown,
+        encoding: None,
+    })
+}
+
+#[test]
+fn max_bytestring_lines_elides_middle() {
+    let options = HexOptions {
+        max_bytestring_lines: Some(3),
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        bytes(100).to_hex_with(&options),
+        indoc!(
+            r#"
+            58 64                               # bytes(100)
+               000102030405060708090a0b0c0d0e0f #   "\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\n\x0b\x0c\r\x0e\x0f"
+               101112131415161718191a1b1c1d1e1f #   "\x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f"
+                                                #   ... (64 bytes omitted) ...
+               60616263                         #   "`abc"
+        "#
+        ),
+    );
+}
+
+#[test]
+fn max_bytestring_lines_short_enough() {
+    let options = HexOptions {
+        max_bytestring_lines: Some(7),
+        ..HexOptions::default()
+    };
+    assert_eq!(bytes(100).to_hex_with(&options), bytes(100).to_hex(),);
+}
+
+#[test]
+fn max_bytestring_lines_one() {
+    let options = HexOptions {
+        max_bytestring_lines: Some(1),
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        bytes(20).to_hex_with(&options),
+        indoc!(
+            r#"
+            54                                  # bytes(20)
+               000102030405060708090a0b0c0d0e0f #   "\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\n\x0b\x0c\r\x0e\x0f"
+                                                #   ... (4 bytes omitted) ...
+        "#
+        ),
+    );
+}
+
+#[test]
+fn max_bytestring_lines_large() {
+    let options = HexOptions {
+        max_bytestring_lines: Some(2),
+        ..HexOptions::default()
+    };
+    let hex = bytes(1_000_000).to_hex_with(&options);
+    assert_eq!(hex.lines().count(), 4);
+    assert!(hex.contains("... (999,968 bytes omitted) ..."));
+}
+
+#[test]
+fn write_hex_matches_to_hex() {
+    let item = DataItem::Tag {
+        tag: Tag::ENCODED_BASE64,
+        bitwidth: IntegerWidth::Zero,
+        value: Box::new(bytes(40)),
+    };
+    let mut output = Vec::new();
+    item.write_hex(&HexOptions::default(), &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), item.to_hex(),);
+}
+
+#[test]
+fn base64_comment_per_line() {
+    let item = DataItem::Tag {
+        tag: Tag::ENCODED_BASE64,
+        bitwidth: IntegerWidth::Zero,
+        value: Box::new(bytes(20)),
+    };
+    assert_eq!(
+        item.to_hex(),
+        indoc!(
+            r#"
+            d6                                     # suggested base64 encoding, tag(22)
+               54                                  #   bytes(20)
+                  000102030405060708090a0b0c0d0e0f #     b64'AAECAwQFBgcICQoLDA0ODw'
+                  10111213                         #     b64'EBESEw'
+        "#
+        ),
+    );
+}
+
+#[test]
+fn bytes_per_line_bytes() {
+    let options = HexOptions {
+        bytes_per_line: 4,
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        bytes(10).to_hex_with(&options),
+        indoc!(
+            r#"
+            4a          # bytes(10)
+               00010203 #   "\x00\x01\x02\x03"
+               04050607 #   "\x04\x05\x06\x07"
+               0809     #   "\x08\t"
+        "#
+        ),
+    );
+}
+
+#[test]
+fn bytes_per_line_text() {
+    let options = HexOptions {
+        bytes_per_line: 4,
+        ..HexOptions::default()
+    };
+    let text = |data: &str| {
+        DataItem::TextString(TextString {
+            data: data.into(),
+            bitwidth: IntegerWidth::Zero,
+        })
+    };
+    assert_eq!(
+        text("abcdef").to_hex_with(&options),
+        indoc!(
+            r#"
+            66              # text(6)
+               616263646566 #   "abcdef"
+        "#
+        ),
+    );
+    assert_eq!(
+        text("abcdefg\u{20ac}").to_hex_with(&options),
+        indoc!(
+            r#"
+            6a              # text(10)
+               61626364     #   "abcd"
+               656667e282ac #   "efg€"
+        "#
+        ),
+    );
+}
+
+#[test]
+fn bytes_per_line_zero() {
+    let options = HexOptions {
+        bytes_per_line: 0,
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        bytes(2).to_hex_with(&options),
+        indoc!(
+            r#"
+            42    # bytes(2)
+               00 #   "\x00"
+               01 #   "\x01"
+        "#
+        ),
+    );
+}
+
+#[test]
+fn no_ascii() {
+    let options = HexOptions {
+        show_ascii: false,
+        ..HexOptions::default()
+    };
+    let item = DataItem::Array {
+        data: vec![
+            bytes(20),
+            DataItem::Tag {
+                tag: Tag::ENCODED_BASE16,
+                bitwidth: IntegerWidth::Zero,
+                value: Box::new(bytes(2)),
+            },
+        ],
+        bitwidth: Some(IntegerWidth::Zero),
+    };
+    assert_eq!(
+        item.to_hex_with(&options),
+        indoc!(
+            r#"
+            82                                     # array(2)
+               54                                  #   bytes(20)
+                  000102030405060708090a0b0c0d0e0f
+                  10111213
+               d7                                  #   suggested base16 encoding, tag(23)
+                  42                               #     bytes(2)
+                     0001                          #       h'0001'
+        "#
+        ),
+    );
+}
+
+// A COSE_Sign1 message from RFC 8152 Appendix C.2.1, with some nesting to
+// check the comments end up aligned
+fn cose_sign1() -> DataItem {
+    parse_hex(
+        "
+        d2 84 43 a1 01 26 a1 04 42 31 31 54 54 68 69 73 20 69 73 20 74 68 65 20
+        63 6f 6e 74 65 6e 74 2e 58 40 8e b3 3e 4c a3 1d 1c 46 5a b0 5a ac 34 cc
+        6b 23 d5 8f ef 5c 08 31 06 c4 d2 5a 91 ae f0 b0 11 7e 2a f9 a2 91 aa 32
+        e1 4a b8 34 dc 56 ed 2a 22 34 44 54 7e 01 f1 1d 3b 09 16 e5 a4 c3 45 ca
+        cb 36
+        ",
+    )
+    .unwrap()
+}
+
+#[test]
+fn comments_aligned() {
+    assert_eq!(
+        cose_sign1().to_hex(),
+        indoc!(
+            r##"
+            d2                                        # tag(18)
+               84                                     #   array(4)
+                  43                                  #     bytes(3)
+                     a10126                           #       "\xa1\x01&"
+                  a1                                  #     map(1)
+                     04                               #       unsigned(4)
+                     42                               #       bytes(2)
+                        3131                          #         "11"
+                  54                                  #     bytes(20)
+                     546869732069732074686520636f6e74 #       "This is the cont"
+                     656e742e                         #       "ent."
+                  58 40                               #     bytes(64)
+                     8eb33e4ca31d1c465ab05aac34cc6b23 #       "\x8e\xb3>L\xa3\x1d\x1cFZ\xb0Z\xac4\xcck#"
+                     d58fef5c083106c4d25a91aef0b0117e #       "\xd5\x8f\xef\\\x081\x06\xc4\xd2Z\x91\xae\xf0\xb0\x11~"
+                     2af9a291aa32e14ab834dc56ed2a2234 #       "*\xf9\xa2\x91\xaa2\xe1J\xb84\xdcV\xed*\"4"
+                     44547e01f11d3b0916e5a4c345cacb36 #       "DT~\x01\xf1\x1d;\t\x16\xe5\xa4\xc3E\xca\xcb6"
+        "##
+        ),
+    );
+}
+
+#[test]
+fn max_comment_column() {
+    let options = HexOptions {
+        max_comment_column: Some(20),
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        cose_sign1().to_hex_with(&options),
+        indoc!(
+            r##"
+            d2                   # tag(18)
+               84                #   array(4)
+                  43             #     bytes(3)
+                     a10126      #       "\xa1\x01&"
+                  a1             #     map(1)
+                     04          #       unsigned(4)
+                     42          #       bytes(2)
+                        3131     #         "11"
+                  54             #     bytes(20)
+                     546869732069732074686520636f6e74 #       "This is the cont"
+                     656e742e    #       "ent."
+                  58 40          #     bytes(64)
+                     8eb33e4ca31d1c465ab05aac34cc6b23 #       "\x8e\xb3>L\xa3\x1d\x1cFZ\xb0Z\xac4\xcck#"
+                     d58fef5c083106c4d25a91aef0b0117e #       "\xd5\x8f\xef\\\x081\x06\xc4\xd2Z\x91\xae\xf0\xb0\x11~"
+                     2af9a291aa32e14ab834dc56ed2a2234 #       "*\xf9\xa2\x91\xaa2\xe1J\xb84\xdcV\xed*\"4"
+                     44547e01f11d3b0916e5a4c345cacb36 #       "DT~\x01\xf1\x1d;\t\x16\xe5\xa4\xc3E\xca\xcb6"
+        "##
+        ),
+    );
+}
+
+#[test]
+fn semantic_comments() {
+    let options = HexOptions {
+        semantic_comments: false,
+        ..HexOptions::default()
+    };
+    let value = parse_hex("c249010000000000000000").unwrap();
+    assert_eq!(
+        value.to_hex_with(&options),
+        indoc!(
+            r#"
+            c2                       # positive bignum, tag(2)
+               49                    #   bytes(9)
+                  010000000000000000 #     "\x01\x00\x00\x00\x00\x00\x00\x00\x00"
+        "#
+        ),
+    );
+}
+
+fn flagged(hex: &str) -> String {
+    let options = HexOptions {
+        flag_non_preferred: true,
+        ..HexOptions::default()
+    };
+    parse_hex(hex).unwrap().to_hex_with(&options)
+}
+
+#[test]
+fn flag_non_preferred_rfc8949_examples() {
+    // Definite length examples from RFC 8949 appendix A, all preferred
+    let preferred = [
+        "00",
+        "17",
+        "1818",
+        "1903e8",
+        "1a000f4240",
+        "1b000000e8d4a51000",
+        "1bffffffffffffffff",
+        "c249010000000000000000",
+        "3bffffffffffffffff",
+        "20",
+        "3863",
+        "3903e7",
+        "f90000",
+        "f93c00",
+        "fb3ff199999999999a",
+        "fa47c35000",
+        "fa7f7fffff",
+        "fb7e37e43c8800759c",
+        "f90001",
+        "f97c00",
+        "f97e00",
+        "c074323031332d30332d32315432303a30343a30305a",
+        "d82076687474703a2f2f7777772e6578616d706c652e636f6d",
+        "40",
+        "6449455446",
+        "83010203",
+        "98190102030405060708090a0b0c0d0e0f101112131415161718181819",
+        "a201020304",
+        "a26161016162820203",
+    ];
+    for hex in &preferred {
+        let output = flagged(hex);
+        assert!(!output.contains("non-preferred"), "{}", output);
+        assert_eq!(output, parse_hex(hex).unwrap().to_hex(), "{}", hex);
+    }
+
+    assert_eq!(
+        flagged("5f42010243030405ff"),
+        indoc!(
+            r#"
+            5f           # bytes(*) (non-preferred encoding)
+               42        #   bytes(2)
+                  0102   #     "\x01\x02"
+               43        #   bytes(3)
+                  030405 #     "\x03\x04\x05"
+               ff        #   break
+        "#
+        ),
+    );
+    assert_eq!(
+        flagged("9f018202039f0405ffff"),
+        indoc!(
+            r#"
+            9f       # array(*) (non-preferred encoding)
+               01    #   unsigned(1)
+               82    #   array(2)
+                  02 #     unsigned(2)
+                  03 #     unsigned(3)
+               9f    #   array(*) (non-preferred encoding)
+                  04 #     unsigned(4)
+                  05 #     unsigned(5)
+                  ff #     break
+               ff    #   break
+        "#
+        ),
+    );
+}
+
+#[test]
+fn flag_non_preferred_overlong() {
+    assert_eq!(
+        flagged("1b0000000000000001"),
+        "1b 0000000000000001 # unsigned(1) (non-preferred encoding)\n",
+    );
+    assert_eq!(
+        flagged("3817"),
+        "38 17 # negative(-24) (non-preferred encoding)\n"
+    );
+    assert_eq!(
+        flagged("fa3fc00000"),
+        "fa 3fc00000 # float(1.5) (non-preferred encoding)\n"
+    );
+    assert_eq!(
+        flagged("5900026869"),
+        indoc!(
+            r#"
+            59 0002 # bytes(2) (non-preferred encoding)
+               6869 #   "hi"
+        "#
+        ),
+    );
+    assert_eq!(
+        flagged("d9000100"),
+        indoc!(
+            r#"
+            d9 0001 # epoch datetime value, tag(1) (non-preferred encoding)
+               00   #   unsigned(0)
+                    #   datetime(1970-01-01T00:00:00Z)
+        "#
+        ),
+    );
+}
+
+#[test]
+fn flag_non_preferred_unknown_widths() {
+    let item = cbor_diag::parse_diag("[255, 1.5, 'abc']").unwrap();
+    let options = HexOptions {
+        flag_non_preferred: true,
+        ..HexOptions::default()
+    };
+    assert!(!item.to_hex_with(&options).contains("non-preferred"));
+}
+
+#[test]
+fn flag_non_preferred_too_narrow_widths() {
+    // Widths too narrow for the value are widened to the shortest that fits
+    let item = DataItem::Tag {
+        tag: Tag(300),
+        bitwidth: IntegerWidth::Eight,
+        value: Box::new(DataItem::Integer {
+            value: 300,
+            bitwidth: IntegerWidth::Zero,
+        }),
+    };
+    let options = HexOptions {
+        flag_non_preferred: true,
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        item.to_hex_with(&options),
+        indoc!(
+            r#"
+            d9 012c    # tag(300)
+               19 012c #   unsigned(300)
+        "#
+        ),
+    );
+}
+
+#[test]
+fn key_names() {
+    let item = cbor_diag::parse_diag("{1: -7, -1: 1, 4: [1]}").unwrap();
+    let options = HexOptions {
+        key_names: vec![(1, "alg".into()), (-1, "crv".into())]
+            .into_iter()
+            .collect(),
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        item.to_hex_with(&options),
+        indoc!(
+            r#"
+            a3       # map(3)
+               01    #   unsigned(1) (alg)
+               26    #   negative(-7)
+               20    #   negative(-1) (crv)
+               01    #   unsigned(1)
+               04    #   unsigned(4)
+               81    #   array(1)
+                  01 #     unsigned(1)
+        "#
+        ),
+    );
+}
+
+#[test]
+fn group() {
+    let item = cbor_diag::parse_diag(r#"[h'000102030405060708', "abcdefg", 1000]"#).unwrap();
+    for &group in &[1, 2, 4, 100] {
+        let options = HexOptions {
+            group,
+            ..HexOptions::default()
+        };
+        let hex = item.to_hex_with(&options);
+        assert_eq!(parse_hex(hex).unwrap().to_bytes(), item.to_bytes());
+    }
+    let options = HexOptions {
+        group: 2,
+        ..HexOptions::default()
+    };
+    assert_eq!(
+        item.to_hex_with(&options),
+        indoc!(
+            r#"
+            83                           # array(3)
+               49                        #   bytes(9)
+                  0001 0203 0405 0607 08 #     "\x00\x01\x02\x03\x04\x05\x06\x07\x08"
+               67                        #   text(7)
+                  6162 6364 6566 67      #     "abcdefg"
+               19 03e8                   #   unsigned(1,000)
+        "#
+        ),
+    );
+}
+
+#[test]
+fn split_headers() {
+    let item = cbor_diag::parse_diag("[1000, 1.5_2, 24(h'01'), simple(32)]").unwrap();
+    let options = HexOptions {
+        split_headers: false,
+        ..HexOptions::default()
+    };
+    let hex = item.to_hex_with(&options);
+    assert_eq!(
+        hex,
+        indoc!(
+            r#"
+            84            # array(4)
+               1903e8     #   unsigned(1,000)
+               fa3fc00000 #   float(1.5)
+               d818       #   encoded cbor data item, tag(24)
+                  41      #     bytes(1)
+                     01   #       "\x01"
+                          #     encoded cbor data item
+                          #       01 # unsigned(1)
+               f820       #   unassigned, simple(32)
+        "#
+        ),
+    );
+    assert_eq!(parse_hex(hex).unwrap().to_bytes(), item.to_bytes());
+}
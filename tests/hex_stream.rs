@@ -0,0 +1,226 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use cbor_diag::{
+    annotate_stream, parse_bytes, parse_bytes_with, parse_diag, parse_hex, Error, HexOptions,
+    ParseOptions,
+};
+use pretty_assertions::assert_eq;
+
+fn stream(bytes: &[u8], options: &HexOptions) -> cbor_diag::Result<String> {
+    let mut output = Vec::new();
+    annotate_stream(Cursor::new(bytes), &mut output, options)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+/// Check streaming `bytes` gives the same output as annotating them in
+/// memory.
+fn check(bytes: &[u8], options: &HexOptions) {
+    let parse_options = ParseOptions {
+        allow_invalid_utf8: true,
+        ..ParseOptions::default()
+    };
+    let item = parse_bytes_with(bytes, &parse_options).unwrap();
+    assert_eq!(stream(bytes, options).unwrap(), item.to_hex_with(options));
+}
+
+fn check_diag(diag: &str, options: &HexOptions) {
+    check(&parse_diag(diag).unwrap().to_bytes(), options);
+}
+
+const ITEMS: &[&str] = &[
+    "0",
+    "-1_1",
+    "1.5_2",
+    "[1, [2, 3], {4: 5}, [_ 6, 7], {_ 8: [_]}]",
+    "{1: -7, 4: h'3131', -1: 'key', 1000: 'name'}",
+    r#"["a", "", h'', (_ h'0102', h''), (_ "a", "bc"), (_ )]"#,
+    r#""The quick brown fox jumps over the lazy dog, then does it again""#,
+    r#""ünïcödé ünïcödé ünïcödé ünïcödé ünïcödé ünïcödé""#,
+    r#""日本語のテキストは一文字が三バイトです。""#,
+    "h'000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021'",
+    "[21(h'0102'), 22(h'0102'), 23([h'0102', 24(h'0102')])]",
+    "[37(h'000102030405060708090a0b0c0d0e0f'), 260(h'7f000001')]",
+    r#"[0("2013-03-21T20:04:00Z"), 1(1363896240), 2(h'0100'), 4([-2, 27315])]"#,
+    r#"[32("http://example.com"), 24(h'82016374776f'), 30([1, 3]), 1004("2020-01-01")]"#,
+    "1234(5678({_ 1: [_ 2, (_ h'03')]}))",
+    "[simple(16), true, null, undefined, 1.0_3, -0.0_1, NaN]",
+];
+
+#[test]
+fn matches_to_hex() {
+    for diag in ITEMS {
+        check_diag(diag, &HexOptions::default());
+    }
+}
+
+#[test]
+fn matches_to_hex_with() {
+    let key_names = [(1, "alg"), (4, "kid"), (-1, "crv")]
+        .iter()
+        .map(|&(key, name)| (key, name.to_owned()))
+        .collect();
+    let options = [
+        HexOptions {
+            bytes_per_line: 4,
+            ..HexOptions::default()
+        },
+        HexOptions {
+            bytes_per_line: 0,
+            show_ascii: false,
+            ..HexOptions::default()
+        },
+        HexOptions {
+            max_bytestring_lines: Some(1),
+            max_chunks: Some(1),
+            ..HexOptions::default()
+        },
+        HexOptions {
+            group: 2,
+            split_headers: false,
+            ..HexOptions::default()
+        },
+        HexOptions {
+            max_comment_column: Some(10),
+            semantic_comments: false,
+            flag_non_preferred: true,
+            key_names,
+            ..HexOptions::default()
+        },
+    ];
+    for options in &options {
+        for diag in ITEMS {
+            check_diag(diag, options);
+        }
+    }
+}
+
+#[test]
+fn non_preferred() {
+    let options = HexOptions {
+        flag_non_preferred: true,
+        ..HexOptions::default()
+    };
+    check(&hex::decode("9900021800d90001590000").unwrap(), &options);
+}
+
+#[test]
+fn invalid_utf8() {
+    check(&hex::decode("62c328").unwrap(), &HexOptions::default());
+    check(
+        &hex::decode("7f62c3a861ff6161ff").unwrap(),
+        &HexOptions::default(),
+    );
+    let text = b"valid text then an invalid byte \xff here";
+    let mut long = vec![0x78, text.len() as u8];
+    long.extend(text);
+    check(&long, &HexOptions::default());
+}
+
+#[test]
+fn large() {
+    // Larger than the pieces the input is read in
+    let data: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+    let text = "ü".repeat(30_000);
+    let item = parse_diag(&format!(r#"[h'{}', "{}"]"#, hex::encode(&data), text)).unwrap();
+    let bytes = item.to_bytes();
+    check(&bytes, &HexOptions::default());
+    check(
+        &bytes,
+        &HexOptions {
+            max_bytestring_lines: Some(4),
+            ..HexOptions::default()
+        },
+    );
+}
+
+#[test]
+fn starts_at_current_position() {
+    let mut input = Cursor::new(hex::decode("ff8201820203").unwrap());
+    input.seek(SeekFrom::Start(1)).unwrap();
+    let mut output = Vec::new();
+    annotate_stream(&mut input, &mut output, &HexOptions::default()).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        parse_hex("8201820203").unwrap().to_hex()
+    );
+}
+
+#[test]
+fn malformed() {
+    for hex in &[
+        "",
+        "82",
+        "8201",
+        "5f41",
+        "7f6161",
+        "9f01",
+        "1c",
+        "5f01ff",
+        "bf01ff",
+        "63616263ff",
+    ] {
+        let bytes = hex::decode(hex).unwrap();
+        assert!(parse_bytes(&bytes).is_err(), "{}", hex);
+        assert!(stream(&bytes, &HexOptions::default()).is_err(), "{}", hex);
+    }
+
+    let err = stream(&hex::decode("9f01").unwrap(), &HexOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "TODO cbor-diag::Error: Parsing error at offset 2: indefinite length array missing break after 1 present"
+    );
+    let err = stream(&hex::decode("5f01ff").unwrap(), &HexOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "TODO cbor-diag::Error: Parsing error at offset 1: expected a definite length byte string chunk or break"
+    );
+}
+
+#[test]
+fn truncated_string() {
+    let err = stream(
+        &hex::decode("5a00010000ffff").unwrap(),
+        &HexOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "TODO cbor-diag::Error: Parsing error at offset 7: byte string declared 65536 bytes, only 2 present"
+    );
+}
+
+#[test]
+fn trailing_data() {
+    let err = stream(
+        &hex::decode("820102030405").unwrap(),
+        &HexOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "TODO cbor-diag::Error: parsed 1 item (3 bytes), 3 trailing bytes"
+    );
+}
+
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("read failed"))
+    }
+}
+
+impl Seek for Failing {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[test]
+fn io_error() {
+    let err = annotate_stream(Failing, io::sink(), &HexOptions::default()).unwrap_err();
+    match err {
+        Error::Io(err) => assert_eq!(err.to_string(), "read failed"),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+}
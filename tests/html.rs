@@ -0,0 +1,109 @@
+use cbor_diag::{parse_diag, HexOptions, HtmlOptions};
+use pretty_assertions::assert_eq;
+
+fn unstyled() -> HtmlOptions {
+    HtmlOptions {
+        style: false,
+        ..Default::default()
+    }
+}
+
+/// The text shown by `html`, with the tags removed and entities decoded.
+fn text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start + rest[start..].find('>').unwrap() + 1..];
+    }
+    text.push_str(rest);
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[test]
+fn nested_golden() {
+    let item = parse_diag(r#"{"a": [1, 1(1500000000)], 2: (_ h'00ff', h'')}"#).unwrap();
+    assert_eq!(item.to_html(&unstyled()), include_str!("data/nested.html"));
+}
+
+#[test]
+fn same_text_as_annotated() {
+    let item = parse_diag(
+        r#"[24(h'820102'), -1, 1.5, null, "a long enough string to wrap over lines", [_ ]]"#,
+    )
+    .unwrap();
+    let html = item.to_html(&unstyled());
+    let text = text(html.strip_suffix('\n').unwrap());
+    let hex = item.to_hex();
+    assert_eq!(text.lines().count(), hex.lines().count());
+    for (line, hex) in text.lines().zip(hex.lines()) {
+        assert_eq!(&line[9..], hex);
+        if !line.starts_with(' ') {
+            assert!(line.len() > 9 && line[..8].chars().all(|c| c.is_ascii_hexdigit()));
+        }
+    }
+}
+
+#[test]
+fn escaped_text() {
+    let item = parse_diag(r#"{"</pre><script>'&": '<img>'}"#).unwrap();
+    let html = item.to_html(&unstyled());
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("<img"));
+    assert_eq!(html.matches("</pre>").count(), 1);
+    assert!(html.contains("&quot;&lt;/pre&gt;&lt;script&gt;&#39;&amp;&quot;"));
+    assert!(text(&html).contains(r#""</pre><script>'&""#));
+
+    let options = HtmlOptions {
+        id_prefix: "\"><b>".into(),
+        ..unstyled()
+    };
+    assert!(item
+        .to_html(&options)
+        .contains(r##"id="&quot;&gt;&lt;b&gt;00000000" href="#&quot;&gt;&lt;b&gt;00000000""##));
+}
+
+#[test]
+fn offsets() {
+    let item = parse_diag(r#"[h'000102030405060708090a0b0c0d0e0f10', 2]"#).unwrap();
+    let html = item.to_html(&unstyled());
+    for offset in &["00000000", "00000001", "00000002", "00000012", "00000013"] {
+        assert!(html.contains(&format!(
+            r##"id="cbor-{0}" href="#cbor-{0}">{0}</a>"##,
+            offset
+        )));
+    }
+    assert!(!html.contains("00000003"));
+}
+
+#[test]
+fn elision_ignored() {
+    let item = parse_diag(&format!("[h'{}', 1]", "00".repeat(256))).unwrap();
+    let options = HtmlOptions {
+        hex: HexOptions {
+            max_bytestring_lines: Some(2),
+            bytes_per_line: 128,
+            ..HexOptions::default()
+        },
+        ..unstyled()
+    };
+    let html = item.to_html(&options);
+    assert!(!html.contains("omitted"));
+    assert!(html.contains(r#"id="cbor-00000104""#));
+}
+
+#[test]
+fn style() {
+    let item = parse_diag("[1]").unwrap();
+    let html = item.to_html(&HtmlOptions::default());
+    assert!(html.starts_with("<style>\n"));
+    assert!(html.contains("</style>\n<pre class=\"cbor-diag\">"));
+    assert!(html.ends_with("</pre>\n"));
+    assert!(item
+        .to_html(&unstyled())
+        .starts_with("<pre class=\"cbor-diag\"><details open><summary>"));
+}
@@ -0,0 +1,127 @@
+use cbor_diag::{parse_bytes, parse_diag, DataItem, EncodeOptions};
+use pretty_assertions::assert_eq;
+
+const INDEFINITE: EncodeOptions = EncodeOptions {
+    widen_on_overflow: false,
+    force_indefinite_containers: true,
+    chunk_strings: None,
+};
+
+fn chunked(size: usize) -> EncodeOptions {
+    EncodeOptions {
+        chunk_strings: Some(size),
+        ..Default::default()
+    }
+}
+
+fn encode(diag: &str, options: &EncodeOptions) -> String {
+    hex::encode(parse_diag(diag).unwrap().to_bytes_with(options))
+}
+
+#[test]
+fn containers() {
+    assert_eq!(
+        encode("[1, [2], {3: 4}]", &INDEFINITE),
+        "9f019f02ffbf0304ffff"
+    );
+    assert_eq!(encode("[]", &INDEFINITE), "9fff");
+    assert_eq!(encode(r#"["ab"]"#, &INDEFINITE), "9f626162ff");
+}
+
+#[test]
+fn bytestrings() {
+    assert_eq!(encode("h'0102030405'", &chunked(2)), "5f4201024203044105ff");
+    assert_eq!(encode("h'0102'", &chunked(2)), "5f420102ff");
+    assert_eq!(encode("h''", &chunked(2)), "5fff");
+    assert_eq!(
+        encode("(_ h'010203', h'04')", &chunked(2)),
+        "5f42010241034104ff"
+    );
+}
+
+#[test]
+fn textstrings() {
+    assert_eq!(encode(r#""hello""#, &chunked(2)), "7f626865626c6c616fff");
+    // "aé€" is 61 c3a9 e282ac, never split within a character
+    assert_eq!(encode(r#""aé€""#, &chunked(2)), "7f616162c3a963e282acff");
+    assert_eq!(encode(r#""aé€""#, &chunked(3)), "7f6361c3a963e282acff");
+    assert_eq!(encode(r#""€""#, &chunked(1)), "7f63e282acff");
+    assert_eq!(encode(r#""""#, &chunked(2)), "7fff");
+}
+
+#[test]
+fn nested() {
+    let options = EncodeOptions {
+        force_indefinite_containers: true,
+        chunk_strings: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        encode(r#"{"ab": 24(h'01')}"#, &options),
+        "bf7f61616162ffd8185f4101ffff"
+    );
+}
+
+#[test]
+fn to_definite() {
+    assert_eq!(
+        parse_diag(r#"[_ (_ "he", "llo"), {_ 1: (_ h'01', h'02')}, (_ )]"#)
+            .unwrap()
+            .to_definite()
+            .to_diag(),
+        r#"["hello",{1:h'0102'},h'']"#
+    );
+    let item = parse_diag(r#"[_1 "a", 1_1]"#).unwrap();
+    assert_eq!(item.to_definite(), item);
+}
+
+#[test]
+fn invalid_text_to_definite() {
+    let options = cbor_diag::ParseOptions {
+        allow_invalid_utf8: true,
+        ..Default::default()
+    };
+    // "é" split across two chunks
+    let item = cbor_diag::parse_bytes_with(hex::decode("7f61c361a9ff").unwrap(), &options).unwrap();
+    assert_eq!(item.to_definite().to_diag(), r#""é""#);
+    let item = cbor_diag::parse_bytes_with(hex::decode("7f61c3ff").unwrap(), &options).unwrap();
+    match &item.to_definite() {
+        DataItem::InvalidTextString(string) => assert_eq!(string.data, vec![0xc3]),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let options = EncodeOptions {
+        force_indefinite_containers: true,
+        chunk_strings: Some(3),
+        ..Default::default()
+    };
+    for diag in &[
+        r#"[1, "hello world", h'00010203040506', {"key": ["aé€ü", 2]}]"#,
+        r#"{1: {2: [3, 4]}, "x": 0("2013-03-21T20:04:00Z")}"#,
+        r#"[(_ "abc", "def"), (_ h'010203', h'04')]"#,
+    ] {
+        let item = parse_diag(diag).unwrap();
+
+        // definite → indefinite → definite
+        let indefinite = parse_bytes(item.to_bytes_with(&options)).unwrap();
+        assert_eq!(
+            indefinite.to_definite().to_bytes(),
+            item.to_definite().to_bytes(),
+            "{}",
+            diag
+        );
+
+        // indefinite → definite → indefinite
+        let bytes = indefinite.to_bytes();
+        let definite = parse_bytes(indefinite.to_definite().to_bytes()).unwrap();
+        assert_eq!(
+            hex::encode(definite.to_bytes_with(&options)),
+            hex::encode(bytes),
+            "{}",
+            diag
+        );
+    }
+}
@@ -375,3 +375,89 @@ testcases! {
         }
     }
 }
+
+#[test]
+fn negative_zero() {
+    // `-0` is just the integer zero, only `-0.0` is the float with a sign
+    assert_eq!(
+        cbor_diag::parse_diag("-0").unwrap(),
+        DataItem::Integer {
+            value: 0,
+            bitwidth: IntegerWidth::Zero,
+        }
+    );
+    assert_eq!(
+        cbor_diag::parse_diag("-0_1").unwrap(),
+        DataItem::Integer {
+            value: 0,
+            bitwidth: IntegerWidth::Sixteen,
+        }
+    );
+    match cbor_diag::parse_diag("-0.0").unwrap() {
+        DataItem::Float { value, .. } => assert!(value == 0.0 && value.is_sign_negative()),
+        item => panic!("unexpected item {:?}", item),
+    }
+}
+
+#[test]
+fn negative_range() {
+    assert_eq!(
+        cbor_diag::parse_diag("-1").unwrap(),
+        DataItem::Negative {
+            value: 0,
+            bitwidth: IntegerWidth::Zero,
+        }
+    );
+    let most_negative = cbor_diag::parse_diag("-18446744073709551616").unwrap();
+    assert_eq!(
+        most_negative,
+        DataItem::Negative {
+            value: u64::MAX,
+            bitwidth: IntegerWidth::Unknown,
+        }
+    );
+    assert_eq!(hex::encode(most_negative.to_bytes()), "3bffffffffffffffff");
+    assert_eq!(most_negative.to_diag(), "-18446744073709551616");
+
+    for &diag in &[
+        "-18446744073709551617",
+        "-99999999999999999999999999999999999999999999",
+        "18446744073709551616",
+    ] {
+        let err = cbor_diag::parse_diag(diag).unwrap_err().to_string();
+        assert!(
+            err.ends_with(
+                "Parsing error at offset 0: integer out of range, larger values need a bignum tag"
+            ),
+            "{}: {}",
+            diag,
+            err
+        );
+    }
+    let err = cbor_diag::parse_diag("[1, -18446744073709551617_3]")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("at offset 4: integer out of range"), "{}", err);
+}
+
+#[test]
+fn extremes() {
+    let item = |hex: &str| cbor_diag::parse_hex(hex).unwrap();
+    assert_eq!(item("3bffffffffffffffff").as_i128(), Some(-(1 << 64)));
+    assert_eq!(item("3b7fffffffffffffff").as_i128(), Some(i64::MIN.into()));
+    assert_eq!(item("20").as_i128(), Some(-1));
+    assert_eq!(item("00").as_i128(), Some(0));
+    assert_eq!(item("1bffffffffffffffff").as_i128(), Some(u64::MAX.into()));
+    assert_eq!(item("f6").as_i128(), None);
+    assert_eq!(item("c249010000000000000000").as_i128(), None);
+
+    // The comment shows the value, not the encoded argument
+    assert_eq!(
+        item("3bffffffffffffffff").to_hex(),
+        "3b ffffffffffffffff # negative(-18,446,744,073,709,551,616)\n"
+    );
+    assert_eq!(
+        item("1bffffffffffffffff").to_hex(),
+        "1b ffffffffffffffff # unsigned(18,446,744,073,709,551,615)\n"
+    );
+}
@@ -0,0 +1,72 @@
+use cbor_diag::{parse_bytes, DataItem, FloatWidth, IntegerWidth};
+use pretty_assertions::assert_eq;
+
+fn minimal(input: &str) -> String {
+    let mut item = parse_bytes(hex::decode(input).unwrap()).unwrap();
+    item.minimal_integer_widths();
+    hex::encode(item.to_bytes())
+}
+
+#[test]
+fn overlong_integers() {
+    assert_eq!(minimal("1b0000000000000001"), "01");
+    assert_eq!(minimal("1a00000018"), "1818");
+    assert_eq!(minimal("19ffff"), "19ffff");
+    assert_eq!(minimal("3b0000000000000000"), "20");
+    assert_eq!(minimal("1b0000000100000000"), "1b0000000100000000");
+}
+
+#[test]
+fn overlong_lengths_and_tags() {
+    assert_eq!(minimal("5900026869"), "426869");
+    assert_eq!(minimal("7a000000026869"), "626869");
+    assert_eq!(minimal("98020102"), "820102");
+    assert_eq!(minimal("b900010102"), "a10102");
+    assert_eq!(minimal("db0000000000000001f5"), "c1f5");
+    assert_eq!(minimal("5f58016859000169ff"), "5f41684169ff");
+    assert_eq!(minimal("9f1801ff"), "9f01ff");
+}
+
+#[test]
+fn leaves_floats() {
+    assert_eq!(minimal("fb3ff8000000000000"), "fb3ff8000000000000");
+}
+
+#[test]
+fn clear_widths() {
+    // [_ 1_3, h'61'_0, 1_0(1.5_1), {_ [_0 ]: (_ "b"_1)}]
+    let mut item = parse_bytes(
+        hex::decode("9f1b0000000000000001580161d801f93e00bf98007f79000162ffffff").unwrap(),
+    )
+    .unwrap();
+    item.clear_widths();
+    assert_eq!(item.to_diag(), r#"[_1,h'61',1(1.5),{_[]:(_"b")}]"#);
+    assert_eq!(
+        hex::encode(item.to_bytes()),
+        "9f014161c1fb3ff8000000000000bf807f6162ffffff"
+    );
+
+    match &item {
+        DataItem::Array { data, bitwidth } => {
+            assert_eq!(*bitwidth, None);
+            assert_eq!(
+                data[0],
+                DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Unknown
+                }
+            );
+        }
+        other => panic!("unexpected item {:?}", other),
+    }
+
+    let mut item = parse_bytes(hex::decode("f93e00").unwrap()).unwrap();
+    item.clear_widths();
+    assert_eq!(
+        item,
+        DataItem::Float {
+            value: 1.5,
+            bitwidth: FloatWidth::Unknown
+        }
+    );
+}
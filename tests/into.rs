@@ -0,0 +1,73 @@
+use cbor_diag::{parse_diag, DataItem, Tag};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn bytes() {
+    assert_eq!(parse_diag("h'0102'").unwrap().into_bytes(), Ok(vec![1, 2]));
+    assert_eq!(
+        parse_diag("(_ h'01', h'', h'0203')").unwrap().into_bytes(),
+        Ok(vec![1, 2, 3])
+    );
+    let item = parse_diag(r#""text""#).unwrap();
+    assert_eq!(item.clone().into_bytes(), Err(item));
+}
+
+#[test]
+fn text() {
+    assert_eq!(
+        parse_diag(r#""hello""#).unwrap().into_text(),
+        Ok("hello".to_owned())
+    );
+    assert_eq!(
+        parse_diag(r#"(_ "hel", "lo")"#).unwrap().into_text(),
+        Ok("hello".to_owned())
+    );
+    let item = parse_diag("h'68656c6c6f'").unwrap();
+    assert_eq!(item.clone().into_text(), Err(item));
+}
+
+#[test]
+fn array() {
+    let items = parse_diag("[_ 1, [2]]").unwrap().into_array().unwrap();
+    assert_eq!(
+        items,
+        [parse_diag("1").unwrap(), parse_diag("[2]").unwrap()]
+    );
+    let item = parse_diag("{1: 2}").unwrap();
+    assert_eq!(item.clone().into_array(), Err(item));
+}
+
+#[test]
+fn map() {
+    let entries = parse_diag(r#"{"a": 1}"#).unwrap().into_map().unwrap();
+    assert_eq!(
+        entries,
+        [(parse_diag(r#""a""#).unwrap(), parse_diag("1").unwrap())]
+    );
+    let item = parse_diag("[1, 2]").unwrap();
+    assert_eq!(item.clone().into_map(), Err(item));
+}
+
+#[test]
+fn tagged() {
+    let (tag, value) = parse_diag("1(2(3))").unwrap().into_tagged().unwrap();
+    assert_eq!(tag, Tag(1));
+    assert_eq!(value, parse_diag("2(3)").unwrap());
+    let item = parse_diag("3").unwrap();
+    assert_eq!(item.clone().into_tagged(), Err(item));
+}
+
+#[test]
+fn continue_matching() {
+    // A mismatch hands the item back to try the next shape
+    let item = parse_diag(r#"32("https://example.com")"#).unwrap();
+    let value = match item.into_bytes() {
+        Ok(_) => unreachable!(),
+        Err(item) => item.into_tagged().unwrap().1,
+    };
+    assert_eq!(value.into_text().unwrap(), "https://example.com");
+    assert!(matches!(
+        parse_diag("[]").unwrap().into_text(),
+        Err(DataItem::Array { .. })
+    ));
+}
@@ -0,0 +1,97 @@
+use cbor_diag::{
+    parse_bytes, parse_bytes_partial_with, parse_bytes_with, parse_hex, parse_hex_with, ByteString,
+    DataItem, IntegerWidth, ParseOptions,
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const LENIENT: ParseOptions = ParseOptions {
+    allow_invalid_utf8: true,
+    app_literal_handler: None,
+    allow_unknown_app_literals: false,
+    max_allocation: None,
+    max_chunks: 1_000_000,
+    max_items: 1_000_000,
+    allow_hex_arrays: false,
+    allow_lossy_float_narrowing: false,
+    strict_tag_content: false,
+    stop_at_stray_break: false,
+};
+
+#[test]
+fn strict_by_default() {
+    assert!(parse_bytes(b"\x62\xc3\x28").is_err());
+    assert!(parse_bytes(b"\x7f\x61a\x62\xc3\x28\xff").is_err());
+    assert!(parse_bytes(b"\x81\xd8\x20\x62\xc3\x28").is_err());
+    assert!(parse_hex("62 c3 28").is_err());
+}
+
+#[test]
+fn definite() {
+    let bytes = b"\x62\xc3\x28";
+    let item = parse_bytes_with(bytes, &LENIENT).unwrap();
+    assert_eq!(
+        item,
+        DataItem::InvalidTextString(ByteString {
+            data: vec![0xc3, 0x28],
+            bitwidth: IntegerWidth::Zero,
+            encoding: None,
+        })
+    );
+    assert_eq!(item.to_bytes(), bytes);
+    assert_eq!(item.to_diag(), "h'c328'");
+    assert_eq!(
+        item.to_hex(),
+        indoc!(
+            r#"
+            62      # text(2) (invalid UTF-8)
+               c328 #   "\xc3("
+        "#
+        )
+    );
+    assert_eq!(parse_hex_with(item.to_hex(), &LENIENT).unwrap(), item);
+}
+
+#[test]
+fn indefinite() {
+    let bytes = b"\x7f\x61a\x62\xc3\x28\xff";
+    let item = parse_bytes_with(bytes, &LENIENT).unwrap();
+    assert_eq!(
+        item,
+        DataItem::InvalidIndefiniteTextString(vec![
+            ByteString {
+                data: vec![b'a'],
+                bitwidth: IntegerWidth::Zero,
+                encoding: None,
+            },
+            ByteString {
+                data: vec![0xc3, 0x28],
+                bitwidth: IntegerWidth::Zero,
+                encoding: None,
+            },
+        ])
+    );
+    assert_eq!(item.to_bytes(), bytes);
+    assert_eq!(item.to_diag(), "(_h'61',h'c328')");
+    assert_eq!(
+        item.to_hex(),
+        indoc!(
+            r#"
+            7f         # text(*)
+               61      #   text(1)
+                  61   #     "a"
+               62      #   text(2) (invalid UTF-8)
+                  c328 #     "\xc3("
+               ff      #   break
+        "#
+        )
+    );
+}
+
+#[test]
+fn nested_partial() {
+    let bytes = b"\x81\xd8\x20\x62\xc3\x28\x00";
+    let (item, len) = parse_bytes_partial_with(bytes, &LENIENT).unwrap().unwrap();
+    assert_eq!(len, 6);
+    assert_eq!(item.to_bytes(), &bytes[..6]);
+}
@@ -0,0 +1,138 @@
+use cbor_diag::{parse_bytes, parse_bytes_partial_with, parse_bytes_with, Error, ParseOptions};
+use pretty_assertions::assert_eq;
+
+fn max_allocation(max: usize) -> ParseOptions {
+    ParseOptions {
+        max_allocation: Some(max),
+        ..Default::default()
+    }
+}
+
+fn out_of_range(result: cbor_diag::Result<impl std::fmt::Debug>) -> (u64, usize) {
+    match result {
+        Err(Error::LengthOutOfRange {
+            declared,
+            available,
+        }) => (declared, available),
+        other => panic!("expected LengthOutOfRange, got {:?}", other),
+    }
+}
+
+#[test]
+fn huge_string() {
+    // An 8 byte length header declaring a 16 EB string followed by 2 bytes
+    let bytes = hex::decode("5bffffffffffffffff0000").unwrap();
+    assert_eq!(out_of_range(parse_bytes(&bytes)), (u64::max_value(), 2));
+    assert_eq!(
+        parse_bytes(&bytes).unwrap_err().to_string(),
+        "declared length 18446744073709551615 is out of range, only 2 bytes available"
+    );
+
+    let bytes = hex::decode("7bffffffffffffffff").unwrap();
+    assert_eq!(out_of_range(parse_bytes(&bytes)), (u64::max_value(), 0));
+}
+
+#[test]
+fn strings() {
+    assert_eq!(
+        out_of_range(parse_bytes(hex::decode("7a000100006162").unwrap())),
+        (65536, 2)
+    );
+    // Chunks of indefinite length strings are checked too
+    assert_eq!(
+        out_of_range(parse_bytes(hex::decode("5f4501ff").unwrap())),
+        (5, 2)
+    );
+}
+
+#[test]
+fn containers() {
+    assert_eq!(
+        out_of_range(parse_bytes(hex::decode("9bffffffffffffffff01").unwrap())),
+        (u64::max_value(), 1)
+    );
+    assert_eq!(
+        out_of_range(parse_bytes(hex::decode("b90100a0").unwrap())),
+        (256, 1)
+    );
+}
+
+#[test]
+fn max_allocation_limit() {
+    let options = max_allocation(4);
+    assert!(parse_bytes_with(hex::decode("4401020304").unwrap(), &options).is_ok());
+    assert_eq!(
+        out_of_range(parse_bytes_with(
+            hex::decode("450102030405").unwrap(),
+            &options
+        )),
+        (5, 4)
+    );
+    assert_eq!(
+        out_of_range(parse_bytes_with(
+            hex::decode("850102030405").unwrap(),
+            &options
+        )),
+        (5, 4)
+    );
+    assert!(parse_bytes_with(hex::decode("8401020304").unwrap(), &options).is_ok());
+}
+
+#[test]
+fn partial() {
+    // More input may still arrive, unless the length is beyond the maximum
+    assert!(
+        parse_bytes_partial_with(hex::decode("4501").unwrap(), &Default::default())
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        parse_bytes_partial_with(hex::decode("8501").unwrap(), &Default::default())
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        out_of_range(parse_bytes_partial_with(
+            hex::decode("5bffffffffffffffff").unwrap(),
+            &max_allocation(1024)
+        )),
+        (u64::max_value(), 0)
+    );
+    assert!(
+        parse_bytes_partial_with(hex::decode("590400").unwrap(), &max_allocation(1024))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn input_ended_in_container() {
+    let error = |hex| {
+        parse_bytes(hex::decode(hex).unwrap())
+            .unwrap_err()
+            .to_string()
+    };
+
+    // Enough input for the declared length to be possible, but the items are
+    // longer than one byte
+    assert_eq!(
+        error("8319010019020019"),
+        "TODO cbor-diag::Error: Parsing error at offset 8: expected 2 byte argument, only 0 bytes present, in array at offset 0 declared 3 items, input ended after 2"
+    );
+    assert_eq!(
+        error("83190100190200"),
+        "TODO cbor-diag::Error: Parsing error at offset 7: expected a data item, only 0 bytes present, in array at offset 0 declared 3 items, input ended after 2"
+    );
+
+    // The innermost container is first, followed by those enclosing it
+    assert_eq!(
+        error("a20102d8208419010019020019"),
+        "TODO cbor-diag::Error: Parsing error at offset 13: expected 2 byte argument, only 0 bytes present, in array at offset 5 declared 4 items, input ended after 2, in map at offset 0 declared 2 entries, input ended after 1"
+    );
+    // Indefinite length containers aren't described, they report a missing
+    // break when the input ends between their items
+    assert_eq!(
+        error("a1019f1901"),
+        "TODO cbor-diag::Error: Parsing error at offset 5: expected 2 byte argument, only 1 bytes present, in map at offset 0 declared 1 entries, input ended after 0"
+    );
+}
@@ -0,0 +1,179 @@
+use cbor_diag::{parse_bytes, parse_bytes_lenient, parse_bytes_partial, parse_hex_lenient};
+use pretty_assertions::assert_eq;
+
+fn lenient(hex: &str) -> (Option<String>, Vec<String>) {
+    let (item, warnings) = parse_hex_lenient(hex).unwrap();
+    (
+        item.map(|item| item.to_diag()),
+        warnings.iter().map(|w| w.to_string()).collect(),
+    )
+}
+
+#[test]
+fn complete() {
+    assert_eq!(lenient("83 01 02 03"), (Some("[1,2,3]".into()), vec![]));
+}
+
+#[test]
+fn empty() {
+    assert_eq!(
+        lenient(""),
+        (
+            None,
+            vec!["at offset 0: expected a data item, only 0 bytes present".into()]
+        )
+    );
+}
+
+#[test]
+fn truncated_array() {
+    assert_eq!(
+        lenient("85 01 02 03"),
+        (
+            Some("[1,2,3]".into()),
+            vec![
+                "at offset 4: expected a data item, only 0 bytes present".into(),
+                "at offset 4: array declared 5 items, only 3 present".into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn truncated_nested() {
+    assert_eq!(
+        lenient("a2 61 61 9f 01 82 02 63 61 62"),
+        (
+            Some(r#"{"a":[_1,[2,"ab"]]}"#.into()),
+            vec![
+                "at offset 10: text string declared 3 bytes, only 2 present".into(),
+                "at offset 10: indefinite length array not terminated, only 2 present".into(),
+                "at offset 10: map declared 2 entries, only 1 present".into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn truncated_map_value() {
+    assert_eq!(
+        lenient("a1 01"),
+        (
+            Some("{}".into()),
+            vec![
+                "at offset 2: expected a data item, only 0 bytes present".into(),
+                "at offset 2: map declared 1 entries, only 0 present".into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn truncated_bytestring() {
+    assert_eq!(
+        lenient("45 010203"),
+        (
+            Some("h'010203'".into()),
+            vec!["at offset 4: byte string declared 5 bytes, only 3 present".into()]
+        )
+    );
+}
+
+#[test]
+fn truncated_tag() {
+    assert_eq!(
+        lenient("82 01 c1"),
+        (
+            Some("[1]".into()),
+            vec![
+                "at offset 3: expected a data item, only 0 bytes present".into(),
+                "at offset 3: tag 1 missing its value".into(),
+                "at offset 3: array declared 2 items, only 1 present".into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn truncated_argument() {
+    assert_eq!(
+        lenient("82 01 19 01"),
+        (
+            Some("[1]".into()),
+            vec![
+                "at offset 4: expected 2 byte argument, only 1 bytes present".into(),
+                "at offset 4: array declared 2 items, only 1 present".into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(
+        lenient("82 01 1c"),
+        (
+            Some("[1]".into()),
+            vec![
+                "at offset 2: reserved additional information value 28".into(),
+                "at offset 2: array declared 2 items, only 1 present".into(),
+            ]
+        )
+    );
+    assert_eq!(
+        lenient("5f 41 00 61 61 ff"),
+        (
+            Some("(_h'00')".into()),
+            vec![
+                "at offset 3: expected a definite length byte string chunk or break".into(),
+                "at offset 3: indefinite length byte string not terminated, only 1 chunks present"
+                    .into(),
+            ]
+        )
+    );
+}
+
+#[test]
+fn invalid_utf8() {
+    assert_eq!(
+        lenient("62 c3 28"),
+        (
+            Some("h'c328'".into()),
+            vec!["at offset 0: text string contains invalid UTF-8".into()]
+        )
+    );
+}
+
+#[test]
+fn trailing() {
+    assert_eq!(
+        lenient("01 02 03"),
+        (
+            Some("1".into()),
+            vec!["at offset 1: 2 bytes of trailing data after the data item".into()]
+        )
+    );
+}
+
+#[test]
+fn strict_errors() {
+    assert_eq!(
+        format!("{:?}", parse_bytes(b"\x85\x01\x02\x03").unwrap_err()),
+        "LengthOutOfRange { declared: 5, available: 3 }",
+    );
+    assert_eq!(
+        format!("{:?}", parse_bytes(b"\x82\x01\x9f").unwrap_err()),
+        "Todo(\"Parsing error at offset 3: indefinite length array missing break after 0 present, in array at offset 0 declared 2 items, input ended after 1\")",
+    );
+    assert!(parse_bytes(b"\xff").is_err());
+    assert!(parse_bytes(b"\x9f\x01").is_err());
+    assert!(parse_bytes_partial(b"\x9f\x01").unwrap().is_none());
+    assert!(parse_bytes_partial(b"\x9f\x1c").is_err());
+}
+
+#[test]
+fn huge_length() {
+    let (item, warnings) = parse_bytes_lenient(b"\x9b\xff\xff\xff\xff\xff\xff\xff\xff\x01");
+    assert_eq!(item.unwrap().to_diag(), "[_3 1]");
+    assert_eq!(warnings.len(), 2);
+}
@@ -0,0 +1,219 @@
+use cbor_diag::{
+    parse_bytes, parse_bytes_lenient, parse_bytes_partial_with, parse_bytes_with, parse_diag,
+    DataItem, DiagFormatOptions, Error, HexOptions, ParseOptions,
+};
+use pretty_assertions::assert_eq;
+
+fn limits(max_chunks: usize, max_items: usize) -> ParseOptions {
+    ParseOptions {
+        max_chunks,
+        max_items,
+        ..Default::default()
+    }
+}
+
+fn exceeded(result: cbor_diag::Result<impl std::fmt::Debug>) -> (usize, usize, &'static str) {
+    match result {
+        Err(Error::LimitExceeded {
+            offset,
+            limit,
+            counted,
+        }) => (offset, limit, counted),
+        other => panic!("expected LimitExceeded, got {:?}", other),
+    }
+}
+
+/// An indefinite length string of `count` chunks of `chunk` bytes each.
+fn chunked(major: u8, count: usize, chunk: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![major << 5 | 31];
+    for _ in 0..count {
+        bytes.push(major << 5 | chunk.len() as u8);
+        bytes.extend_from_slice(chunk);
+    }
+    bytes.push(0xff);
+    bytes
+}
+
+#[test]
+fn default_limits() {
+    let options = ParseOptions::default();
+    assert_eq!(options.max_chunks, 1_000_000);
+    assert_eq!(options.max_items, 1_000_000);
+}
+
+#[test]
+fn million_empty_chunks() {
+    // Found by fuzzing, 2 MB of input parsing to a million chunks shown one
+    // per line
+    let bytes = chunked(2, 1_000_001, b"");
+    assert_eq!(
+        exceeded(parse_bytes(&bytes)),
+        (1_000_001, 1_000_000, "chunks")
+    );
+    assert_eq!(
+        parse_bytes(&bytes).unwrap_err().to_string(),
+        "more than 1000000 chunks at offset 1000001, exceeding the limit"
+    );
+
+    let bytes = chunked(2, 1_000_000, b"");
+    assert!(parse_bytes(&bytes).is_ok());
+}
+
+#[test]
+fn chunks() {
+    let options = limits(3, 10);
+    let bytes = chunked(2, 3, b"\x01");
+    assert!(parse_bytes_with(&bytes, &options).is_ok());
+    let bytes = chunked(2, 4, b"\x01");
+    assert_eq!(
+        exceeded(parse_bytes_with(&bytes, &options)),
+        (7, 3, "chunks")
+    );
+    let bytes = chunked(3, 4, b"a");
+    assert_eq!(
+        exceeded(parse_bytes_with(&bytes, &options)),
+        (7, 3, "chunks")
+    );
+
+    // The break is still accepted after the last chunk allowed
+    let bytes = chunked(3, 3, b"a");
+    assert_eq!(
+        parse_bytes_with(&bytes, &options).unwrap().to_diag(),
+        r#"(_"a","a","a")"#
+    );
+}
+
+#[test]
+fn items() {
+    let options = limits(10, 3);
+    assert!(parse_bytes_with(hex::decode("9f010203ff").unwrap(), &options).is_ok());
+    assert_eq!(
+        exceeded(parse_bytes_with(
+            hex::decode("9f01020304ff").unwrap(),
+            &options
+        )),
+        (4, 3, "items")
+    );
+    assert_eq!(
+        exceeded(parse_bytes_with(
+            hex::decode("8401020304").unwrap(),
+            &options
+        )),
+        (4, 3, "items")
+    );
+    assert_eq!(
+        exceeded(parse_bytes_with(
+            hex::decode("bf0102030405060708ff").unwrap(),
+            &options
+        )),
+        (7, 3, "entries")
+    );
+    assert_eq!(
+        exceeded(parse_bytes_with(
+            hex::decode("a40102030405060708").unwrap(),
+            &options
+        )),
+        (7, 3, "entries")
+    );
+
+    // Each container is limited separately
+    assert!(parse_bytes_with(hex::decode("83830102038301020383010203").unwrap(), &options).is_ok());
+}
+
+#[test]
+fn nested_in_items() {
+    // Found by fuzzing, indefinite arrays of tiny indefinite strings
+    let options = limits(2, 2);
+    let bytes = hex::decode("9f5f4040ff5f404040ffff").unwrap();
+    assert_eq!(
+        exceeded(parse_bytes_with(&bytes, &options)),
+        (8, 2, "chunks")
+    );
+    let bytes = hex::decode("9f5f40ff5f40ff5f40ffff").unwrap();
+    assert_eq!(
+        exceeded(parse_bytes_with(&bytes, &options)),
+        (7, 2, "items")
+    );
+}
+
+#[test]
+fn partial() {
+    let options = limits(3, 3);
+    // A string over the limit fails even before it is complete
+    assert_eq!(
+        exceeded(parse_bytes_partial_with(
+            hex::decode("5f41014101410141").unwrap(),
+            &options
+        )),
+        (7, 3, "chunks")
+    );
+    assert_eq!(
+        parse_bytes_partial_with(hex::decode("5f410141014101").unwrap(), &options).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn lenient() {
+    let bytes = chunked(2, 1_000_001, b"");
+    let (item, warnings) = parse_bytes_lenient(&bytes);
+    match &item {
+        Some(DataItem::IndefiniteByteString(strings)) => assert_eq!(strings.len(), 1_000_000),
+        other => panic!("expected an indefinite byte string, got {:?}", other),
+    }
+    assert_eq!(warnings[0].offset, 1_000_001);
+    assert_eq!(
+        warnings[0].message,
+        "more than 1000000 chunks, exceeding the limit"
+    );
+}
+
+#[test]
+fn coalesced_diag() {
+    let options = DiagFormatOptions {
+        max_chunks: Some(2),
+        ..DiagFormatOptions::default()
+    };
+
+    let item = parse_bytes(chunked(2, 2, b"\x01\x02")).unwrap();
+    assert_eq!(item.to_diag_with(&options), "(_h'0102',h'0102')");
+
+    let item = parse_bytes(chunked(2, 4096, b"\x01\x02")).unwrap();
+    let diag = item.to_diag_with(&options);
+    assert_eq!(
+        diag,
+        format!(
+            "(_ h'{}' / 4,096 chunks, 8,192 bytes total /)",
+            "0102".repeat(4096)
+        )
+    );
+    assert_eq!(item.to_diag_pretty_with(&options), diag);
+    // Only the chunk boundaries are lost
+    assert_eq!(
+        parse_diag(&diag).unwrap().to_definite().to_diag(),
+        item.to_definite().to_diag()
+    );
+
+    let item = parse_bytes(chunked(3, 3, b"ab")).unwrap();
+    assert_eq!(
+        item.to_diag_with(&options),
+        r#"(_ "ababab" / 3 chunks, 6 bytes total /)"#
+    );
+}
+
+#[test]
+fn coalesced_hex() {
+    let options = HexOptions {
+        max_chunks: Some(2),
+        ..HexOptions::default()
+    };
+
+    let item = parse_bytes(chunked(2, 2, b"\x01\x02")).unwrap();
+    assert_eq!(item.to_hex_with(&options), item.to_hex());
+
+    let item = parse_bytes(chunked(2, 4096, b"\x01\x02")).unwrap();
+    let hex = item.to_hex_with(&options);
+    assert_eq!(hex.lines().count(), 7);
+    assert!(hex.contains("... (4,094 chunks, 8,188 bytes omitted) ..."));
+    assert!(hex.lines().last().unwrap().starts_with("   ff "));
+}
@@ -1,4 +1,4 @@
-use cbor_diag::{ByteString, DataItem, IntegerWidth, TextString};
+use cbor_diag::{parse_diag, ByteString, DataItem, Encoding, IntegerWidth, TextString};
 use indoc::indoc;
 
 #[macro_use]
@@ -106,6 +106,7 @@ testcases! {
                                     DataItem::ByteString(ByteString {
                                         data: "\u{1f1f3}".into(),
                                         bitwidth: IntegerWidth::Unknown,
+                                        encoding: Some(Encoding::Base16),
                                     }),
                                 )
                             ],
@@ -269,6 +270,7 @@ testcases! {
                                     DataItem::ByteString(ByteString {
                                         data: "\u{1f1f3}".into(),
                                         bitwidth: IntegerWidth::Zero,
+                                        encoding: None,
                                     }),
                                 )
                             ],
@@ -437,6 +439,7 @@ testcases! {
                                         DataItem::ByteString(ByteString {
                                             data: "\u{1f1f3}".into(),
                                             bitwidth: IntegerWidth::Unknown,
+                                            encoding: Some(Encoding::Base16),
                                         }),
                                     )
                                 ],
@@ -604,6 +607,7 @@ testcases! {
                                         DataItem::ByteString(ByteString {
                                             data: "\u{1f1f3}".into(),
                                             bitwidth: IntegerWidth::Zero,
+                                            encoding: None,
                                         }),
                                     )
                                 ],
@@ -674,3 +678,49 @@ testcases! {
         }
     }
 }
+
+#[test]
+fn large_keys() {
+    let array: Vec<String> = (1..=20).map(|i| i.to_string()).collect();
+    let item = parse_diag(format!(
+        r#"{{[{}]: {{"a value long enough": "to need wrapping onto multiple lines"}}, {{[1, 2]: 3}}: 4}}"#,
+        array.join(", ")
+    ))
+    .unwrap();
+    let pretty = item.to_diag_pretty();
+    assert_eq!(
+        pretty,
+        indoc!(
+            r#"
+            {
+                [
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+                ]:
+                    {
+                        "a value long enough": "to need wrapping onto multiple lines",
+                    },
+                {[1, 2]: 3}: 4,
+            }"#
+        )
+    );
+    assert_eq!(parse_diag(&pretty).unwrap(), item);
+
+    let item = parse_diag(format!(r#"{{{{[{}]: 1}}: 2}}"#, array.join(", "))).unwrap();
+    let pretty = item.to_diag_pretty();
+    assert_eq!(
+        pretty,
+        indoc!(
+            r#"
+            {
+                {
+                    [
+                        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+                    ]:
+                        1,
+                }:
+                    2,
+            }"#
+        )
+    );
+    assert_eq!(parse_diag(&pretty).unwrap(), item);
+}
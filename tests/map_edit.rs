@@ -0,0 +1,72 @@
+use cbor_diag::{parse_diag, parse_hex, DataItem};
+use pretty_assertions::assert_eq;
+
+fn item(diag: &str) -> DataItem {
+    parse_diag(diag).unwrap()
+}
+
+#[test]
+fn cose_header() {
+    // {1: -7, 4: h'3131'}, alg ES256 and kid "11"
+    let mut header = parse_hex("a2 01 26 04 42 3131").unwrap();
+    let mut map = header.as_map_mut().unwrap();
+
+    assert!(map.contains_key(&item("1")));
+    // Keys are compared semantically, ignoring their encoded width
+    assert!(map.contains_key(&item("4_1")));
+    assert!(!map.contains_key(&item("3")));
+    assert!(!map.contains_key(&item("-1")));
+
+    // Replacing keeps the entry in place
+    assert_eq!(map.insert(item("1_0"), item("-35")), Some(item("-7")));
+    assert_eq!(map.insert(item("3"), item("60")), None);
+    let (key, value) = map.remove(&item("4")).unwrap();
+    assert_eq!(
+        (key.to_diag(), value.to_diag()),
+        ("4".into(), "h'3131'".into())
+    );
+    assert_eq!(map.remove(&item("4")), None);
+    assert_eq!(map.get(&item("3")), Some(&item("60")));
+    *map.get_mut(&item("3")).unwrap() = item("61");
+    assert_eq!(map.entries().len(), 2);
+
+    assert_eq!(
+        hex::encode(header.to_bytes()),
+        "a201382203183d",
+        "{}",
+        header.to_diag()
+    );
+}
+
+#[test]
+fn entry() {
+    let mut claims = item(r#"{1: "issuer", 6: 1700000000}"#);
+    let mut map = claims.as_map_mut().unwrap();
+
+    let exists = map.entry(item("1"));
+    assert!(exists.exists());
+    *exists.or_insert(item("null")) = item(r#""other""#);
+
+    let missing = map.entry(item("4"));
+    assert!(!missing.exists());
+    missing.or_insert_with(|| item("1700003600"));
+
+    assert_eq!(claims.to_diag(), r#"{1:"other",6:1700000000,4:1700003600}"#);
+}
+
+#[test]
+fn semantic_keys() {
+    let mut map = item(r#"{"a": 1, (_ "b", "c"): 2, 1.5: 3}"#);
+    let mut map = map.as_map_mut().unwrap();
+    assert_eq!(map.get(&item(r#""bc""#)), Some(&item("2")));
+    assert_eq!(map.get(&item("1.5_1")), Some(&item("3")));
+    // Integers and floats are distinct keys
+    map.insert(item("1.0"), item("4"));
+    map.insert(item("1"), item("5"));
+    assert_eq!(map.entries().len(), 5);
+}
+
+#[test]
+fn not_a_map() {
+    assert!(item("[1, 2]").as_map_mut().is_none());
+}
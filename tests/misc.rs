@@ -60,7 +60,20 @@ testcases! {
         "),
     }
 
-    simple_24 {
+    simple_0 {
+        DataItem::Simple(Simple(0)),
+        {
+            "simple(0)",
+            "simple(0)",
+        },
+        indoc!("
+            e0 # unassigned, simple(0)
+        "),
+    }
+
+    // Reserved simple values can't be encoded, or parsed from either format,
+    // but can still be displayed, see also `simple_reserved_hex` below
+    simple_24(value2diag) {
         DataItem::Simple(Simple(24)),
         {
             "simple(24)",
@@ -71,6 +84,28 @@ testcases! {
         "),
     }
 
+    simple_31(value2diag) {
+        DataItem::Simple(Simple(31)),
+        {
+            "simple(31)",
+            "simple(31)",
+        },
+        indoc!("
+            f8 1f # reserved, simple(31)
+        "),
+    }
+
+    simple_32 {
+        DataItem::Simple(Simple(32)),
+        {
+            "simple(32)",
+            "simple(32)",
+        },
+        indoc!("
+            f8 20 # unassigned, simple(32)
+        "),
+    }
+
     simple_255 {
         DataItem::Simple(Simple(255)),
         {
@@ -82,3 +117,41 @@ testcases! {
         "),
     }
 }
+
+#[test]
+fn simple_reserved_hex() {
+    assert_eq!(
+        DataItem::Simple(Simple(24)).to_hex(),
+        "f8 18 # reserved, simple(24)\n"
+    );
+    assert_eq!(
+        DataItem::Simple(Simple(31)).to_hex(),
+        "f8 1f # reserved, simple(31)\n"
+    );
+}
+
+#[test]
+fn simple_reserved_diag() {
+    assert!(cbor_diag::parse_diag("simple(24)").is_err());
+    assert!(cbor_diag::parse_diag("simple(30)").is_err());
+    assert!(cbor_diag::parse_diag("simple(31)").is_err());
+    assert!(cbor_diag::parse_diag("simple(256)").is_err());
+}
+
+#[test]
+fn simple_reserved_bytes() {
+    assert!(cbor_diag::parse_bytes([0xf8, 0x00]).is_err());
+    assert!(cbor_diag::parse_bytes([0xf8, 0x17]).is_err());
+    assert!(cbor_diag::parse_bytes([0xf8, 0x1e]).is_err());
+    assert!(DataItem::Simple(Simple(30)).try_to_bytes().is_err());
+    assert_eq!(
+        DataItem::Simple(Simple(32)).try_to_bytes().unwrap(),
+        [0xf8, 0x20]
+    );
+}
+
+#[test]
+fn simple_reserved_to_bytes() {
+    assert!(DataItem::Simple(Simple(24)).try_to_bytes().is_err());
+    assert_eq!(DataItem::Simple(Simple(24)).to_bytes(), [0xf8, 0x18]);
+}
@@ -0,0 +1,114 @@
+use cbor_diag::{parse_diag, PackedOptions};
+use pretty_assertions::assert_eq;
+
+fn expand(diag: &str) -> String {
+    parse_diag(diag).unwrap().expand_packed().unwrap().to_diag()
+}
+
+fn expand_err(diag: &str) -> String {
+    parse_diag(diag)
+        .unwrap()
+        .expand_packed()
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn simple_references() {
+    assert_eq!(
+        expand(r#"113(["a", "b", [simple(1), simple(0), {simple(0): simple(1)}]])"#),
+        r#"["b","a",{"a":"b"}]"#
+    );
+}
+
+#[test]
+fn tagged_references() {
+    let table: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+    let diag = format!(
+        "113([{}, [simple(15), 6(0), 6(-1), 6(1)]])",
+        table.join(", ")
+    );
+    assert_eq!(expand(&diag), "[15,16,17,18]");
+}
+
+#[test]
+fn split_tables() {
+    assert_eq!(
+        expand(r#"1113([["a", "b"], [], [simple(1), simple(0)]])"#),
+        r#"["b","a"]"#
+    );
+}
+
+#[test]
+fn references_within_shared_items() {
+    assert_eq!(
+        expand(r#"113(["a", [simple(0), simple(0)], [simple(1), simple(1)]])"#),
+        r#"[["a","a"],["a","a"]]"#
+    );
+}
+
+#[test]
+fn nested_tables() {
+    // The inner table is prepended to the outer one
+    assert_eq!(
+        expand(r#"113(["a", [simple(0), 113(["b", [simple(0), simple(1)]])]])"#),
+        r#"["a",["b","a"]]"#
+    );
+}
+
+#[test]
+fn outside_table_setup() {
+    assert_eq!(expand("[simple(0), 6(0)]"), "[simple(0),6(0)]");
+}
+
+#[test]
+fn argument_references_unchanged() {
+    assert_eq!(
+        expand(r#"113(["a", [simple(0), 6("b")]])"#),
+        r#"["a",6("b")]"#
+    );
+}
+
+#[test]
+fn out_of_range() {
+    assert_eq!(
+        expand_err(r#"113(["a", [simple(0), simple(1)]])"#),
+        "TODO cbor-diag::Error: packed reference to shared item 1 is out of range, the table has 1 items"
+    );
+    assert_eq!(
+        expand_err("113([[6(18446744073709551615)]])"),
+        "TODO cbor-diag::Error: packed reference to shared item 18446744073709551615 is out of range, the table has 0 items"
+    );
+}
+
+#[test]
+fn depth_limit() {
+    assert_eq!(
+        expand_err("113([[simple(0)], simple(0)])"),
+        "TODO cbor-diag::Error: packed references are nested more than 32 deep"
+    );
+
+    let item = parse_diag("113([1, [simple(0)], simple(1)])").unwrap();
+    assert_eq!(item.expand_packed().unwrap().to_diag(), "[1]");
+    let options = PackedOptions { max_depth: 1 };
+    assert_eq!(
+        item.expand_packed_with(&options).unwrap_err().to_string(),
+        "TODO cbor-diag::Error: packed references are nested more than 1 deep"
+    );
+}
+
+#[test]
+fn malformed_setup() {
+    assert_eq!(
+        expand_err("113(1)"),
+        "TODO cbor-diag::Error: packed table setup tag(113) must contain an array"
+    );
+    assert_eq!(
+        expand_err("113([])"),
+        "TODO cbor-diag::Error: packed table setup tag(113) is missing its rump"
+    );
+    assert_eq!(
+        expand_err("1113([[], 1])"),
+        "TODO cbor-diag::Error: packed table setup tag(1113) must contain a shared table, argument table and rump"
+    );
+}
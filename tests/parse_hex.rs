@@ -0,0 +1,129 @@
+use cbor_diag::{parse_bytes, parse_diag, parse_hex, parse_hex_with, HexOptions, ParseOptions};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn annotated_roundtrip() {
+    let bytes = parse_diag(indoc!(
+        r#"
+        {
+            "key": h'000102030405060708090a0b0c0d0e0f1011121314',
+            "nested": {
+                1: [_ h'cafe', 'text #1'],
+                h'ff': 24(h'a10102'),
+            },
+            "chunks": (_ h'00', h'0102'),
+        }
+        "#
+    ))
+    .unwrap()
+    .to_bytes();
+    let item = parse_bytes(bytes).unwrap();
+    for options in &[
+        HexOptions::default(),
+        HexOptions {
+            bytes_per_line: 3,
+            show_ascii: false,
+            ..HexOptions::default()
+        },
+    ] {
+        let annotated = item.to_hex_with(options);
+        assert_eq!(parse_hex(&annotated).unwrap(), item, "{}", annotated);
+    }
+}
+
+#[test]
+fn whitespace() {
+    assert_eq!(
+        parse_hex("\t82 \r\n\n\t01 # one\r\n  02\r\n").unwrap(),
+        parse_hex("820102").unwrap()
+    );
+}
+
+#[test]
+fn invalid_character() {
+    assert_eq!(
+        parse_hex("82\n  01 # fine: x\n  0g\n")
+            .unwrap_err()
+            .to_string(),
+        "TODO cbor-diag::Error: Invalid character 'g' in hex at line 3, column 4"
+    );
+}
+
+const ARRAYS: ParseOptions = ParseOptions {
+    allow_invalid_utf8: false,
+    app_literal_handler: None,
+    allow_unknown_app_literals: false,
+    max_allocation: None,
+    max_chunks: 1_000_000,
+    max_items: 1_000_000,
+    allow_hex_arrays: true,
+    allow_lossy_float_narrowing: false,
+    strict_tag_content: false,
+    stop_at_stray_break: false,
+};
+
+#[test]
+fn c_array() {
+    let header = indoc!(
+        r#"
+        static const uint8_t test_vector[] = {
+        "#
+    );
+    let array = indoc!(
+        r#"
+        {
+            0xA2, 0x01, 0x26, // alg: ES256
+            0x04, 0x42, 0x31, 0x31 // kid: '11'
+        };
+        "#
+    );
+    assert!(parse_hex_with(header, &ARRAYS).is_err());
+    assert_eq!(
+        parse_hex_with(array.trim_end().trim_end_matches(';'), &ARRAYS).unwrap(),
+        parse_hex("a2 01 26 04 42 3131").unwrap()
+    );
+}
+
+#[test]
+fn rust_array() {
+    assert_eq!(
+        parse_hex_with("[0xa1, 0x1, 0X02,]", &ARRAYS).unwrap(),
+        parse_hex("a10102").unwrap()
+    );
+    // Plain hex still works
+    assert_eq!(
+        parse_hex_with("[a1 01, 0x2] # comment", &ARRAYS).unwrap(),
+        parse_hex("a10102").unwrap()
+    );
+}
+
+#[test]
+fn arrays_need_option() {
+    assert_eq!(
+        parse_hex("[0xa1, 0x01, 0x02]").unwrap_err().to_string(),
+        "TODO cbor-diag::Error: Invalid character '[' in hex at line 1, column 1"
+    );
+}
+
+#[test]
+fn invalid_array_bytes() {
+    assert_eq!(
+        parse_hex_with("0xa1, 0x0102", &ARRAYS)
+            .unwrap_err()
+            .to_string(),
+        "TODO cbor-diag::Error: Invalid byte \"0x0102\" in hex at line 1, column 7"
+    );
+    assert_eq!(
+        parse_hex_with("0xa1,\n 0x,", &ARRAYS)
+            .unwrap_err()
+            .to_string(),
+        "TODO cbor-diag::Error: Invalid byte \"0x\" in hex at line 2, column 2"
+    );
+    assert_eq!(
+        parse_hex_with("0xa1; 0x01", &ARRAYS)
+            .unwrap_err()
+            .to_string(),
+        "TODO cbor-diag::Error: Invalid character ';' in hex at line 1, column 5"
+    );
+}
@@ -62,7 +62,11 @@ fn arb_bytestring() -> impl Strategy<Value = ByteString> {
             any::<u8>(),
             0..=cmp::min(bitwidth_max(bitwidth) as usize, 300),
         )
-        .prop_map(move |data| ByteString { data, bitwidth })
+        .prop_map(move |data| ByteString {
+            data,
+            bitwidth,
+            encoding: None,
+        })
     })
 }
 
@@ -171,8 +175,10 @@ fn arb_data_item() -> impl Strategy<Value = DataItem> {
 
 mod proptest {
     use super::arb_data_item;
-    use cbor_diag::{parse_bytes, parse_diag, parse_hex};
-    use proptest::arbitrary::any;
+    use cbor_diag::{
+        parse_bytes, parse_bytes_with, parse_diag, parse_hex, HexOptions, ParseOptions,
+    };
+    use proptest::{arbitrary::any, option};
 
     proptest::proptest! {
         #[test]
@@ -195,10 +201,80 @@ mod proptest {
             let _ = parse_bytes(s);
         }
 
+        #[test]
+        fn well_formed_matches_parse_bytes(ref s in any::<Vec<u8>>()) {
+            let options = ParseOptions {
+                allow_invalid_utf8: true,
+                ..ParseOptions::default()
+            };
+            let parsed = parse_bytes_with(s, &options);
+            assert_eq!(cbor_diag::check_well_formed(s).is_ok(), parsed.is_ok());
+        }
+
+        #[test]
+        fn well_formed_encoding(item in arb_data_item()) {
+            let bytes = item.to_bytes();
+            let expected = cbor_diag::WellFormedness { items: 1, len: bytes.len() };
+            assert_eq!(cbor_diag::check_well_formed(&bytes).unwrap(), expected);
+        }
+
         #[test]
         fn to_hex_and_back(item in arb_data_item()) {
             assert_eq!(item, parse_hex(item.to_hex()).unwrap());
         }
+
+        #[test]
+        fn to_hex_with_and_back(
+            item in arb_data_item(),
+            bytes_per_line in 0..40usize,
+            show_ascii in any::<bool>(),
+            max_comment_column in option::of(0..80usize),
+        ) {
+            let options = HexOptions {
+                bytes_per_line,
+                show_ascii,
+                max_comment_column,
+                ..HexOptions::default()
+            };
+            assert_eq!(item, parse_hex(item.to_hex_with(&options)).unwrap());
+        }
+
+        #[test]
+        fn annotate_stream_matches_to_hex_with(
+            item in arb_data_item(),
+            bytes_per_line in 0..40usize,
+            max_bytestring_lines in option::of(0..4usize),
+            max_chunks in option::of(0..4usize),
+            max_comment_column in option::of(0..80usize),
+        ) {
+            let options = HexOptions {
+                bytes_per_line,
+                max_bytestring_lines,
+                max_chunks,
+                max_comment_column,
+                ..HexOptions::default()
+            };
+            let bytes = item.to_bytes();
+            let mut hex = Vec::new();
+            cbor_diag::annotate_stream(std::io::Cursor::new(&bytes), &mut hex, &options).unwrap();
+            let expected = parse_bytes(&bytes).unwrap().to_hex_with(&options);
+            assert_eq!(String::from_utf8(hex).unwrap(), expected);
+        }
+
+        #[test]
+        fn encoded_len_matches(item in arb_data_item()) {
+            let len = item.to_bytes().len();
+            assert_eq!(item.encoded_len().unwrap(), len);
+            let report = item.size_report();
+            assert_eq!(report.total(), len);
+            let own: usize = report.major_types.values().map(|totals| totals.total()).sum();
+            assert_eq!(own, len);
+        }
+
+        #[test]
+        fn roundtrip_all(item in arb_data_item()) {
+            cbor_diag::roundtrip::all(&item);
+        }
     }
 }
 
@@ -243,7 +319,59 @@ fn datetime_overflow() {
     assert_eq!(item, parse_hex(item.to_hex()).unwrap());
 }
 
+#[test]
+fn too_small_bitwidth() {
+    // These can't be encoded correctly, but shouldn't panic
+    let _ = DataItem::Negative {
+        value: u64::max_value(),
+        bitwidth: IntegerWidth::Zero,
+    }
+    .to_hex();
+    let _ = DataItem::ByteString(ByteString {
+        data: vec![0; 300],
+        bitwidth: IntegerWidth::Zero,
+        encoding: None,
+    })
+    .to_hex();
+}
+
+#[test]
+fn estimate_underflow() {
+    let item = DataItem::Array {
+        data: vec![
+            DataItem::TextString(TextString {
+                data: "x".repeat(50),
+                bitwidth: IntegerWidth::Zero,
+            }),
+            DataItem::Array {
+                data: vec![DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero,
+                }],
+                bitwidth: Some(IntegerWidth::Zero),
+            },
+        ],
+        bitwidth: Some(IntegerWidth::Zero),
+    };
+    cbor_diag::roundtrip::diag(&item);
+}
+
+#[test]
+fn float32_diag_rounding() {
+    let item = DataItem::Float {
+        value: -15_214_846_148_608.0,
+        bitwidth: FloatWidth::ThirtyTwo,
+    };
+    assert_eq!(item, parse_diag(item.to_diag()).unwrap());
+}
+
 #[test]
 fn negative_underflow() {
-    assert!(parse_diag("-0").is_err());
+    assert_eq!(
+        parse_diag("-0").unwrap(),
+        DataItem::Integer {
+            value: 0,
+            bitwidth: IntegerWidth::Zero,
+        }
+    );
 }
@@ -0,0 +1,128 @@
+use cbor_diag::{
+    parse_diag, parse_hex, ByteString, DataItem, DiagFormatOptions, IntegerWidth, Tag,
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn int(value: i64) -> DataItem {
+    if value >= 0 {
+        DataItem::Integer {
+            value: value as u64,
+            bitwidth: IntegerWidth::Unknown,
+        }
+    } else {
+        DataItem::Negative {
+            value: (-1 - value) as u64,
+            bitwidth: IntegerWidth::Unknown,
+        }
+    }
+}
+
+fn rational(data: Vec<DataItem>) -> DataItem {
+    DataItem::Tag {
+        tag: Tag::RATIONAL,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(DataItem::Array {
+            data,
+            bitwidth: Some(IntegerWidth::Unknown),
+        }),
+    }
+}
+
+fn approximations() -> DiagFormatOptions {
+    DiagFormatOptions {
+        rational_approximations: true,
+        ..DiagFormatOptions::default()
+    }
+}
+
+#[test]
+fn approximation_comment() {
+    for (item, expected) in &[
+        (rational(vec![int(1), int(3)]), "30([1,3]) / ≈ 0.3333 /"),
+        (rational(vec![int(-1), int(2)]), "30([-1,2]) / ≈ -0.5 /"),
+        (rational(vec![int(4), int(2)]), "30([4,2]) / ≈ 2 /"),
+        (rational(vec![int(1), int(0)]), "30([1,0])"),
+        (rational(vec![int(1), int(-2)]), "30([1,-2])"),
+        (rational(vec![int(1)]), "30([1])"),
+        (rational(vec![int(1), int(2), int(3)]), "30([1,2,3])"),
+    ] {
+        assert_eq!(item.to_diag_with(&approximations()), *expected);
+        assert_eq!(parse_diag(expected).unwrap().to_diag(), item.to_diag());
+    }
+}
+
+#[test]
+fn approximation_comment_pretty() {
+    let item = DataItem::Array {
+        data: vec![rational(vec![int(2), int(3)])],
+        bitwidth: Some(IntegerWidth::Unknown),
+    };
+    let expected = "[30([2, 3]) / ≈ 0.6667 /]";
+    assert_eq!(item.to_diag_pretty_with(&approximations()), expected);
+    assert_eq!(parse_diag(expected).unwrap().to_diag(), item.to_diag());
+}
+
+#[test]
+fn approximation_comment_ascii() {
+    let options = DiagFormatOptions {
+        escape_non_ascii: true,
+        ..approximations()
+    };
+    let item = rational(vec![int(1), int(3)]);
+    assert_eq!(item.to_diag_with(&options), "30([1,3]) / ~ 0.3333 /");
+}
+
+#[test]
+fn approximation_off_by_default() {
+    let item = rational(vec![int(1), int(3)]);
+    assert_eq!(item.to_diag(), "30([1,3])");
+}
+
+#[test]
+fn bignum_members() {
+    let bignum = DataItem::Tag {
+        tag: Tag::POSITIVE_BIGNUM,
+        bitwidth: IntegerWidth::Unknown,
+        value: Box::new(DataItem::ByteString(ByteString {
+            data: hex::decode("010000000000000000").unwrap(),
+            bitwidth: IntegerWidth::Unknown,
+            encoding: None,
+        })),
+    };
+    let item = rational(vec![bignum, int(3)]);
+    assert_eq!(
+        item.to_diag_with(&approximations()),
+        "30([2(h'010000000000000000'),3]) / ≈ 6148914691236516864 /"
+    );
+    assert_eq!(
+        item.to_hex(),
+        indoc!(
+            r#"
+            d8 1e                          # rational, tag(30)
+               82                          #   array(2)
+                  c2                       #     positive bignum, tag(2)
+                     49                    #       bytes(9)
+                        010000000000000000 #         "\x01\x00\x00\x00\x00\x00\x00\x00\x00"
+                                           #       bignum(18446744073709551616)
+                  03                       #     unsigned(3)
+                                           #   rational(18446744073709551616/3 ≈ 6148914691236516864)
+            "#
+        )
+    );
+}
+
+#[test]
+fn invalid_hex() {
+    for (hex, expected) in &[
+        ("d81e820100", "non-positive denominator for rational"),
+        ("d81e820120", "non-positive denominator for rational"),
+        ("d81e8101", "invalid type for rational"),
+        ("d81e82016161", "invalid type for rational"),
+        ("d81e01", "invalid type for rational"),
+    ] {
+        let annotated = parse_hex(hex).unwrap().to_hex();
+        let last = annotated.lines().last().unwrap();
+        assert_eq!(last.trim_start().trim_start_matches("#   "), *expected);
+    }
+}
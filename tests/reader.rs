@@ -0,0 +1,402 @@
+use cbor_diag::{parse_bytes_lenient, Event, FloatWidth, IntegerWidth, Reader, Simple, Tag};
+use pretty_assertions::assert_eq;
+
+fn events(bytes: &[u8]) -> Vec<(usize, Event<'_>)> {
+    Reader::new(bytes).collect::<Result<_, _>>().unwrap()
+}
+
+/// The events read before the first problem, along with the problem.
+fn failure(bytes: &[u8]) -> (Vec<(usize, Event<'_>)>, String) {
+    let mut reader = Reader::new(bytes);
+    let mut events = Vec::new();
+    loop {
+        match reader.next() {
+            Some(Ok(event)) => events.push(event),
+            Some(Err(err)) => {
+                assert!(reader.next().is_none());
+                return (events, err.to_string());
+            }
+            None => panic!("expected a problem after {:?}", events),
+        }
+    }
+}
+
+#[test]
+fn scalars() {
+    assert_eq!(
+        events(b"\x00\x18\x18\x39\x03\xe7\xf9\x3c\x00\xfa\x47\xc3\x50\x00\xf5\xf8\xff"),
+        [
+            (
+                0,
+                Event::Integer {
+                    value: 0,
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+            (
+                1,
+                Event::Integer {
+                    value: 24,
+                    bitwidth: IntegerWidth::Eight
+                }
+            ),
+            (
+                3,
+                Event::Negative {
+                    value: 999,
+                    bitwidth: IntegerWidth::Sixteen
+                }
+            ),
+            (
+                6,
+                Event::Float {
+                    value: 1.0,
+                    bitwidth: FloatWidth::Sixteen
+                }
+            ),
+            (
+                9,
+                Event::Float {
+                    value: 100_000.0,
+                    bitwidth: FloatWidth::ThirtyTwo
+                }
+            ),
+            (14, Event::Simple(Simple::TRUE)),
+            (15, Event::Simple(Simple(255))),
+        ]
+    );
+}
+
+#[test]
+fn nested() {
+    // {"a": [1, 32("b")], h'01': h''}
+    let bytes = b"\xa2\x61a\x82\x01\xd8\x20\x61b\x41\x01\x40";
+    assert_eq!(
+        events(bytes),
+        [
+            (
+                0,
+                Event::BeginMap {
+                    len: Some(2),
+                    bitwidth: Some(IntegerWidth::Zero)
+                }
+            ),
+            (
+                1,
+                Event::TextString {
+                    data: b"a",
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+            (
+                3,
+                Event::BeginArray {
+                    len: Some(2),
+                    bitwidth: Some(IntegerWidth::Zero)
+                }
+            ),
+            (
+                4,
+                Event::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+            (
+                5,
+                Event::Tag {
+                    tag: Tag::URI,
+                    bitwidth: IntegerWidth::Eight
+                }
+            ),
+            (
+                7,
+                Event::TextString {
+                    data: b"b",
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+            (
+                9,
+                Event::ByteString {
+                    data: b"\x01",
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+            (
+                11,
+                Event::ByteString {
+                    data: b"",
+                    bitwidth: IntegerWidth::Zero
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn indefinite() {
+    // (_ h'01', h''), [_ ], {_ 1: [_ 2]}, (_ "a")
+    let bytes = b"\x5f\x41\x01\x40\xff\x9f\xff\xbf\x01\x9f\x02\xff\xff\x7f\x61a\xff";
+    let zero = IntegerWidth::Zero;
+    assert_eq!(
+        events(bytes),
+        [
+            (0, Event::BeginByteString),
+            (
+                1,
+                Event::ByteString {
+                    data: b"\x01",
+                    bitwidth: zero
+                }
+            ),
+            (
+                3,
+                Event::ByteString {
+                    data: b"",
+                    bitwidth: zero
+                }
+            ),
+            (4, Event::EndIndefinite),
+            (
+                5,
+                Event::BeginArray {
+                    len: None,
+                    bitwidth: None
+                }
+            ),
+            (6, Event::EndIndefinite),
+            (
+                7,
+                Event::BeginMap {
+                    len: None,
+                    bitwidth: None
+                }
+            ),
+            (
+                8,
+                Event::Integer {
+                    value: 1,
+                    bitwidth: zero
+                }
+            ),
+            (
+                9,
+                Event::BeginArray {
+                    len: None,
+                    bitwidth: None
+                }
+            ),
+            (
+                10,
+                Event::Integer {
+                    value: 2,
+                    bitwidth: zero
+                }
+            ),
+            (11, Event::EndIndefinite),
+            (12, Event::EndIndefinite),
+            (13, Event::BeginTextString),
+            (
+                14,
+                Event::TextString {
+                    data: b"a",
+                    bitwidth: zero
+                }
+            ),
+            (16, Event::EndIndefinite),
+        ]
+    );
+}
+
+#[test]
+fn depth() {
+    let mut reader = Reader::new(b"\x82\xc1\x9f\xff\x01\x02");
+    let mut depths = vec![reader.depth()];
+    while let Some(event) = reader.next() {
+        event.unwrap();
+        depths.push(reader.depth());
+    }
+    // The array closes along with the tag around its last item, and the
+    // trailing item is read as the next in a sequence
+    assert_eq!(depths, [0, 1, 2, 3, 1, 0, 0]);
+}
+
+#[test]
+fn invalid_utf8_not_checked() {
+    assert_eq!(
+        events(b"\x62\xc3\x28"),
+        [(
+            0,
+            Event::TextString {
+                data: b"\xc3\x28",
+                bitwidth: IntegerWidth::Zero
+            }
+        )]
+    );
+}
+
+#[test]
+fn truncated() {
+    let cases: &[(&[u8], usize, &str)] = &[
+        (
+            b"\x18",
+            0,
+            "at offset 1: expected 1 byte argument, only 0 bytes present",
+        ),
+        (
+            b"\x83\x01\x02",
+            3,
+            "at offset 3: expected a data item, only 0 bytes present",
+        ),
+        (
+            b"\x44\x01\x02",
+            0,
+            "at offset 3: byte string declared 4 bytes, only 2 present",
+        ),
+        (
+            b"\x5f\x41\x01",
+            2,
+            "at offset 3: indefinite length byte string missing break after 1 chunks present",
+        ),
+        (
+            b"\x9f\x01\x02",
+            3,
+            "at offset 3: indefinite length array missing break after 2 present",
+        ),
+        (
+            b"\xbf\x01\x02",
+            3,
+            "at offset 3: indefinite length map missing break after 1 present",
+        ),
+        (
+            b"\xbf\x01",
+            2,
+            "at offset 2: expected a data item, only 0 bytes present",
+        ),
+        (
+            b"\xc1",
+            1,
+            "at offset 1: expected a data item, only 0 bytes present",
+        ),
+        (
+            b"\xfb\x00",
+            0,
+            "at offset 2: expected 8 byte float, only 1 bytes present",
+        ),
+    ];
+    for &(bytes, count, message) in cases {
+        let (events, err) = failure(bytes);
+        assert_eq!(events.len(), count, "{:02x?}", bytes);
+        assert_eq!(
+            err,
+            format!("TODO cbor-diag::Error: Parsing error {}", message)
+        );
+    }
+}
+
+#[test]
+fn malformed() {
+    let cases: &[(&[u8], usize, &str)] = &[
+        (b"\xff", 0, "at offset 0: unexpected break"),
+        (b"\x82\x01\xff", 2, "at offset 2: unexpected break"),
+        (b"\xbf\x01\xff", 2, "at offset 2: unexpected break"),
+        (
+            b"\x1c",
+            0,
+            "at offset 0: reserved additional information value 28",
+        ),
+        (
+            b"\xdf\x01",
+            0,
+            "at offset 0: reserved additional information value 31",
+        ),
+        (
+            b"\xf8\x10",
+            0,
+            "at offset 0: invalid two byte simple value 16",
+        ),
+        (
+            b"\x5f\x61a\xff",
+            1,
+            "at offset 1: expected a definite length byte string chunk or break",
+        ),
+        (
+            b"\x7f\x7f\xff\xff",
+            1,
+            "at offset 1: expected a definite length text string chunk or break",
+        ),
+    ];
+    for &(bytes, count, message) in cases {
+        let (events, err) = failure(bytes);
+        assert_eq!(events.len(), count, "{:02x?}", bytes);
+        assert_eq!(
+            err,
+            format!("TODO cbor-diag::Error: Parsing error {}", message)
+        );
+    }
+}
+
+#[test]
+fn same_problems_as_lenient() {
+    // The binary parsers report the problems found by the reader
+    for bytes in &[
+        &b"\x83\x01\x02"[..],
+        b"\x5f\x41\x01",
+        b"\xbf\x01\x02",
+        b"\x5f\x61a\xff",
+        b"\xf8\x10",
+    ] {
+        let (_, err) = failure(bytes);
+        let (_, warnings) = parse_bytes_lenient(bytes);
+        assert_eq!(
+            err,
+            format!("TODO cbor-diag::Error: Parsing error {}", warnings[0])
+        );
+    }
+}
+
+#[test]
+fn skip_item() {
+    // [_ [1, {2: 3}], 24(h'00'), "a"], 4
+    let bytes = b"\x9f\x82\x01\xa1\x02\x03\xd8\x18\x41\x00\x61a\xff\x04";
+    let mut reader = Reader::new(bytes);
+    assert_eq!(
+        reader.next().unwrap().unwrap(),
+        (
+            0,
+            Event::BeginArray {
+                len: None,
+                bitwidth: None
+            }
+        )
+    );
+    assert_eq!(reader.skip_item().unwrap(), Some(1..6));
+    assert_eq!(reader.skip_item().unwrap(), Some(6..10));
+    assert_eq!(reader.skip_item().unwrap(), Some(10..12));
+    // Nothing is skipped at the break
+    assert_eq!(reader.skip_item().unwrap(), None);
+    assert_eq!(reader.next().unwrap().unwrap(), (12, Event::EndIndefinite));
+    assert_eq!(reader.skip_item().unwrap(), Some(13..14));
+    assert_eq!(reader.skip_item().unwrap(), None);
+    assert!(reader.next().is_none());
+
+    let mut reader = Reader::new(b"\x82\x01");
+    assert_eq!(
+        reader.skip_item().unwrap_err().to_string(),
+        "TODO cbor-diag::Error: Parsing error at offset 2: expected a data item, only 0 bytes present"
+    );
+    assert_eq!(reader.skip_item().unwrap(), None);
+}
+
+#[test]
+fn sequence() {
+    let mut reader = Reader::new(b"\x01\x81\x02\x03");
+    let mut items = Vec::new();
+    while !reader.remaining().is_empty() {
+        items.push(reader.skip_item().unwrap().unwrap());
+    }
+    assert_eq!(items, [0..1, 1..3, 3..4]);
+    assert_eq!(reader.depth(), 0);
+    assert!(reader.next().is_none());
+}
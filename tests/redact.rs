@@ -0,0 +1,123 @@
+use cbor_diag::{parse_bytes_with, parse_diag, parse_hex, DataItem, ParseOptions, Redaction};
+use pretty_assertions::assert_eq;
+
+fn redact(diag: &str, matcher: impl Fn(&[usize], &DataItem) -> Redaction) -> String {
+    let mut item = parse_diag(diag).unwrap();
+    item.redact(matcher);
+    item.to_diag()
+}
+
+#[test]
+fn keep_everything() {
+    let diag = r#"{1: [2, 3(h'04')]}"#;
+    assert_eq!(
+        redact(diag, |_, _| Redaction::Keep),
+        parse_diag(diag).unwrap().to_diag()
+    );
+}
+
+#[test]
+fn paths_and_order() {
+    let mut item = parse_diag(r#"[{1: 2}, 3(4)]"#).unwrap();
+    let seen = std::cell::RefCell::new(Vec::new());
+    item.redact(|path, item| {
+        seen.borrow_mut().push((path.to_vec(), item.to_diag()));
+        Redaction::Keep
+    });
+    assert_eq!(
+        seen.into_inner(),
+        [
+            (vec![], "[{1:2},3(4)]".to_owned()),
+            (vec![0], "{1:2}".to_owned()),
+            (vec![0, 0, 0], "1".to_owned()),
+            (vec![0, 0, 1], "2".to_owned()),
+            (vec![1], "3(4)".to_owned()),
+            (vec![1, 0], "4".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn remove() {
+    let matcher = |_: &[usize], item: &DataItem| match item.to_diag().as_str() {
+        "\"secret\"" => Redaction::Remove,
+        _ => Redaction::Keep,
+    };
+    assert_eq!(redact(r#"[1, "secret", 2, "secret"]"#, matcher), "[1,2]");
+    assert_eq!(
+        redact(r#"{"secret": 1, 2: "secret", 3: 4}"#, matcher),
+        "{3:4}"
+    );
+    assert_eq!(redact(r#"[32("secret")]"#, matcher), "[32(undefined)]");
+    assert_eq!(redact(r#""secret""#, matcher), "undefined");
+}
+
+#[test]
+fn removing_keeps_original_paths() {
+    assert_eq!(
+        redact("[0, 1, 2, 3]", |path, _| match path {
+            [1] | [2] => Redaction::Remove,
+            _ => Redaction::Keep,
+        }),
+        "[0,3]"
+    );
+}
+
+#[test]
+fn replace() {
+    assert_eq!(
+        redact(r#"{"a": [1, 2]}"#, |path, _| match path {
+            [0, 1] => Redaction::Replace(DataItem::Simple(cbor_diag::Simple::NULL)),
+            // Not reached, the replacement is not redacted further
+            [0, 1, 0] => Redaction::Remove,
+            _ => Redaction::Keep,
+        }),
+        r#"{"a":null}"#
+    );
+}
+
+#[test]
+fn placeholders_keep_layout() {
+    let cases = [
+        "[_ 1_1, -1000, 1.5_1, 2.5_3, true, simple(99)]",
+        r#"{_ "key": h'0102030405', 4: (_ "ab", "cde"), 5: (_ h'01', h'')}"#,
+        r#"24_1(h'a1010202')"#,
+        "1_3",
+    ];
+    for &diag in &cases {
+        let item = parse_diag(diag).unwrap();
+        let placeholder = item.to_placeholder();
+        assert_eq!(
+            placeholder.to_bytes().len(),
+            item.to_bytes().len(),
+            "{}",
+            diag
+        );
+        assert_eq!(
+            placeholder.size_report().subtrees.len(),
+            item.size_report().subtrees.len(),
+            "{}",
+            diag
+        );
+    }
+
+    // Wide string lengths are kept
+    let item = parse_hex("79 0002 6869").unwrap();
+    assert_eq!(hex::encode(item.to_placeholder().to_bytes()), "7900027878");
+
+    let item = parse_diag(r#"{_ "key": h'0102', 4: (_ "ab", "c"), 5: 1.5_1}"#).unwrap();
+    assert_eq!(
+        item.to_placeholder().to_diag(),
+        r#"{_"key":h'0000',4:(_"xx","x"),5:0.0_1}"#
+    );
+}
+
+#[test]
+fn invalid_text_placeholder() {
+    let options = ParseOptions {
+        allow_invalid_utf8: true,
+        ..ParseOptions::default()
+    };
+    let item = parse_bytes_with([0x62, 0xff, 0xfe], &options).unwrap();
+    assert_eq!(item.to_placeholder().to_diag(), r#""xx""#);
+}
@@ -0,0 +1,47 @@
+use cbor_diag::{array_to_items, items_to_array, parse_diag};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn round_trip() {
+    let items = vec![
+        parse_diag("1").unwrap(),
+        parse_diag(r#"[_ "a"]"#).unwrap(),
+        parse_diag("{}").unwrap(),
+    ];
+    let array = items_to_array(items.clone());
+    assert_eq!(array.to_diag(), r#"[1,[_"a"],{}]"#);
+    assert_eq!(array_to_items(array).unwrap().collect::<Vec<_>>(), items);
+}
+
+#[test]
+fn empty() {
+    let array = items_to_array(Vec::new());
+    assert_eq!(array.to_bytes(), [0x80]);
+    assert_eq!(array_to_items(array).unwrap().count(), 0);
+}
+
+#[test]
+fn not_an_array() {
+    assert_eq!(
+        array_to_items(parse_diag("{1: 2}").unwrap())
+            .unwrap_err()
+            .to_string(),
+        "TODO cbor-diag::Error: expected an array to split into a sequence"
+    );
+}
+
+#[test]
+fn diag_partial() {
+    let text = " / leading / 1 [2, 3]{4: h'05'}  ";
+    let mut offset = 0;
+    let mut items = Vec::new();
+    while let Some((item, len)) = cbor_diag::parse_diag_partial(&text[offset..]).unwrap() {
+        items.push(item.to_diag());
+        offset += len;
+    }
+    assert_eq!(items, ["1", "[2,3]", "{4:h'05'}"]);
+    assert_eq!(offset, text.len());
+
+    assert_eq!(cbor_diag::parse_diag_partial("").unwrap(), None);
+    assert!(cbor_diag::parse_diag_partial("[1, ").is_err());
+}
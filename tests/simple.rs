@@ -0,0 +1,52 @@
+use std::convert::TryFrom;
+
+use cbor_diag::Simple;
+
+#[test]
+fn new() {
+    for value in (0..24).chain(32..=255) {
+        assert_eq!(Simple::new(value).unwrap(), Simple(value));
+    }
+    for value in 24..32 {
+        assert!(Simple::new(value)
+            .unwrap_err()
+            .to_string()
+            .ends_with(&format!("simple({}) is reserved", value)));
+    }
+}
+
+#[test]
+fn classification() {
+    let classify = |simple: Simple| {
+        [
+            simple.is_bool(),
+            simple.is_null(),
+            simple.is_undefined(),
+            simple.is_reserved(),
+            simple.is_unassigned(),
+        ]
+    };
+    assert_eq!(classify(Simple(0)), [false, false, false, false, true]);
+    assert_eq!(classify(Simple(19)), [false, false, false, false, true]);
+    assert_eq!(classify(Simple::FALSE), [true, false, false, false, false]);
+    assert_eq!(classify(Simple::TRUE), [true, false, false, false, false]);
+    assert_eq!(classify(Simple::NULL), [false, true, false, false, false]);
+    assert_eq!(
+        classify(Simple::UNDEFINED),
+        [false, false, true, false, false]
+    );
+    assert_eq!(classify(Simple(24)), [false, false, false, true, false]);
+    assert_eq!(classify(Simple(31)), [false, false, false, true, false]);
+    assert_eq!(classify(Simple(32)), [false, false, false, false, true]);
+    assert_eq!(classify(Simple(255)), [false, false, false, false, true]);
+}
+
+#[test]
+fn bool() {
+    assert_eq!(Simple::from(true), Simple::TRUE);
+    assert_eq!(Simple::from(false), Simple::FALSE);
+    assert_eq!(bool::try_from(Simple::TRUE), Ok(true));
+    assert_eq!(bool::try_from(Simple::FALSE), Ok(false));
+    assert_eq!(bool::try_from(Simple::NULL), Err(Simple::NULL));
+    assert_eq!(bool::try_from(Simple(32)), Err(Simple(32)));
+}
@@ -0,0 +1,123 @@
+use cbor_diag::{parse_diag, parse_hex, DataItem, SizeTotals, SubtreeSize};
+use pretty_assertions::assert_eq;
+
+fn subtree(path: &[usize], header: usize, payload: usize) -> SubtreeSize {
+    SubtreeSize {
+        path: path.to_vec(),
+        header,
+        payload,
+    }
+}
+
+#[test]
+fn subtrees() {
+    let item = parse_diag(r#"{"a": [1000, h'0102']}"#).unwrap();
+    let report = item.size_report();
+    assert_eq!(
+        report.subtrees,
+        [
+            subtree(&[], 7, 3),
+            subtree(&[0, 0], 1, 1),
+            subtree(&[0, 1], 5, 2),
+            subtree(&[0, 1, 0], 3, 0),
+            subtree(&[0, 1, 1], 1, 2),
+        ]
+    );
+    assert_eq!(report.total(), 10);
+    assert_eq!(report.total(), item.to_bytes().len());
+}
+
+#[test]
+fn indefinite_overhead() {
+    // The initial bytes and breaks of indefinite items, and the headers of
+    // chunks, are all header bytes
+    let item = parse_diag(r#"[_ (_ "ab", "c"), (_ )]"#).unwrap();
+    let report = item.size_report();
+    assert_eq!(
+        report.subtrees,
+        [
+            subtree(&[], 8, 3),
+            subtree(&[0], 4, 3),
+            subtree(&[0, 0], 1, 2),
+            subtree(&[0, 1], 1, 1),
+            subtree(&[1], 2, 0),
+        ]
+    );
+    assert_eq!(
+        report.major_types[&3],
+        SizeTotals {
+            count: 3,
+            header: 4,
+            payload: 3,
+        }
+    );
+    assert_eq!(report.major_types[&4].header, 2);
+}
+
+#[test]
+fn non_preferred_widths() {
+    let item = parse_hex("9a00000001 1b0000000000000001").unwrap();
+    let report = item.size_report();
+    assert_eq!(report.subtrees, [subtree(&[], 14, 0), subtree(&[0], 9, 0)]);
+}
+
+#[test]
+fn largest() {
+    let item = parse_diag(r#"[1, "abc", [2, 3], h'01020304']"#).unwrap();
+    let report = item.size_report();
+    let largest: Vec<(Vec<usize>, usize)> = report
+        .largest(4)
+        .into_iter()
+        .map(|subtree| (subtree.path.clone(), subtree.total()))
+        .collect();
+    assert_eq!(
+        largest,
+        [(vec![], 14), (vec![3], 5), (vec![1], 4), (vec![2], 3),]
+    );
+    assert_eq!(report.largest(100).len(), report.subtrees.len());
+}
+
+#[test]
+fn by_major_type_and_tag() {
+    let item = parse_diag(r#"[1(1), 1(2.5_1), 32("http://a"), -1, true]"#).unwrap();
+    let report = item.size_report();
+    let counts: Vec<(u8, usize)> = report
+        .major_types
+        .iter()
+        .map(|(&major, totals)| (major, totals.count))
+        .collect();
+    assert_eq!(counts, [(0, 1), (1, 1), (3, 1), (4, 1), (6, 3), (7, 2)]);
+    assert_eq!(
+        report.tags[&1],
+        SizeTotals {
+            count: 2,
+            header: 6,
+            payload: 0,
+        }
+    );
+    assert_eq!(report.tags[&32].total(), 11);
+    let own: usize = report.major_types.values().map(SizeTotals::total).sum();
+    assert_eq!(own, report.total());
+}
+
+#[test]
+fn encoded_len() {
+    for diag in &["[]", "1.5", "1.5_1", "(_ h'01')", r#"{_ "a": 4294967296}"#] {
+        let item = parse_diag(diag).unwrap();
+        assert_eq!(
+            item.encoded_len().unwrap(),
+            item.to_bytes().len(),
+            "{}",
+            diag
+        );
+    }
+    let item = DataItem::AppLiteral {
+        prefix: "xy".into(),
+        content: "z".into(),
+    };
+    assert_eq!(
+        item.encoded_len().unwrap_err().to_string(),
+        "TODO cbor-diag::Error: application literal xy'z' has no binary encoding"
+    );
+    assert_eq!(item.size_report().total(), 0);
+}
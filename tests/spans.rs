@@ -0,0 +1,76 @@
+use cbor_diag::{parse_bytes_with_spans, Error, Span};
+use pretty_assertions::assert_eq;
+
+fn span(header: std::ops::Range<usize>, content: std::ops::Range<usize>) -> Span {
+    Span { header, content }
+}
+
+// {"a": 1(2), "b": (_ h'0102', h'03')}
+const NESTED: &[u8] = b"\xa2\x61a\xc1\x02\x61b\x5f\x42\x01\x02\x41\x03\xff";
+
+#[test]
+fn nested_ranges() {
+    let (item, spans) = parse_bytes_with_spans(NESTED).unwrap();
+    assert_eq!(item.to_diag(), r#"{"a":1(2),"b":(_h'0102',h'03')}"#);
+
+    for (path, expected) in &[
+        (&[][..], span(0..1, 1..14)),
+        (&[0, 0], span(1..2, 2..3)),
+        (&[0, 1], span(3..4, 4..5)),
+        (&[0, 1, 0], span(4..5, 5..5)),
+        (&[1, 0], span(5..6, 6..7)),
+        (&[1, 1], span(7..8, 8..14)),
+        (&[1, 1, 0], span(8..9, 9..11)),
+        (&[1, 1, 1], span(11..12, 12..13)),
+    ] {
+        assert_eq!(spans.get(path), Some(expected), "{:?}", path);
+    }
+
+    assert_eq!(spans.get(&[2, 0]), None);
+    assert_eq!(spans.get(&[0]), None);
+    assert_eq!(spans.get(&[0, 2]), None);
+    assert_eq!(spans.get(&[1, 1, 2]), None);
+}
+
+#[test]
+fn item_at() {
+    let (_, spans) = parse_bytes_with_spans(NESTED).unwrap();
+    for &(offset, path) in &[
+        (0, &[][..]),
+        (1, &[0, 0]),
+        (2, &[0, 0]),
+        (3, &[0, 1]),
+        (4, &[0, 1, 0]),
+        (7, &[1, 1]),
+        (9, &[1, 1, 0]),
+        (10, &[1, 1, 0]),
+        (12, &[1, 1, 1]),
+        (13, &[1, 1]),
+    ] {
+        let (found, span) = spans.item_at(offset).unwrap();
+        assert_eq!(found, path, "offset {}", offset);
+        assert_eq!(Some(span), spans.get(path));
+    }
+    assert_eq!(spans.item_at(14), None);
+}
+
+#[test]
+fn scalars() {
+    let (_, spans) = parse_bytes_with_spans(b"\xf9\x3c\x00").unwrap();
+    assert_eq!(spans.span(), &span(0..3, 3..3));
+
+    let (_, spans) = parse_bytes_with_spans(b"\x9f\x19\x01\x00\xf5\xff").unwrap();
+    assert_eq!(spans.span(), &span(0..1, 1..6));
+    assert_eq!(spans.get(&[0]), Some(&span(1..4, 4..4)));
+    assert_eq!(spans.get(&[1]), Some(&span(4..5, 5..5)));
+    assert_eq!(spans.item_at(5).unwrap().0, Vec::<usize>::new());
+}
+
+#[test]
+fn errors() {
+    match parse_bytes_with_spans(b"\x01\x02") {
+        Err(Error::TrailingData { offset, .. }) => assert_eq!(offset, 1),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(parse_bytes_with_spans(b"\x82\x01").is_err());
+}
@@ -0,0 +1,115 @@
+use cbor_diag::{parse_bytes_partial_with, parse_bytes_with, DataItem, Error, ParseOptions};
+use pretty_assertions::assert_eq;
+
+const STOP: ParseOptions = ParseOptions {
+    allow_invalid_utf8: false,
+    app_literal_handler: None,
+    allow_unknown_app_literals: false,
+    max_allocation: None,
+    max_chunks: 1_000_000,
+    max_items: 1_000_000,
+    allow_hex_arrays: false,
+    allow_lossy_float_narrowing: false,
+    strict_tag_content: false,
+    stop_at_stray_break: true,
+};
+
+/// Read a sequence the way a framed protocol would, stopping at a break
+fn sequence(bytes: &[u8], options: &ParseOptions) -> Result<(Vec<String>, usize), Error> {
+    let mut items = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if options.stop_at_stray_break && bytes[offset] == 0xff {
+            return Ok((items, offset + 1));
+        }
+        match parse_bytes_partial_with(&bytes[offset..], options)? {
+            Some((item, len)) => {
+                items.push(item.to_diag());
+                offset += len;
+            }
+            None => break,
+        }
+    }
+    Ok((items, offset))
+}
+
+#[test]
+fn single_item() {
+    let item = parse_bytes_with(b"\x01\xff", &STOP).unwrap();
+    assert_eq!(item.to_diag(), "1");
+
+    let item = parse_bytes_with(b"\x9f\x01\xff\xff", &STOP).unwrap();
+    assert_eq!(item.to_diag(), "[_1]");
+
+    match parse_bytes_with(b"\x01\xff\x02", &STOP) {
+        Err(Error::TrailingData {
+            parsed: DataItem::Integer { value: 1, .. },
+            offset: 2,
+            remaining: 1,
+        }) => {}
+        other => panic!("expected trailing data, got {:?}", other),
+    }
+
+    // Only a single break is consumed
+    match parse_bytes_with(b"\x01\xff\xff", &STOP) {
+        Err(Error::TrailingData {
+            offset: 2,
+            remaining: 1,
+            ..
+        }) => {}
+        other => panic!("expected trailing data, got {:?}", other),
+    }
+}
+
+#[test]
+fn single_item_without_option() {
+    match parse_bytes_with(b"\x01\xff", &ParseOptions::default()) {
+        Err(Error::TrailingData {
+            offset: 1,
+            remaining: 1,
+            ..
+        }) => {}
+        other => panic!("expected trailing data, got {:?}", other),
+    }
+}
+
+#[test]
+fn required_item() {
+    for bytes in &[
+        &b"\xff"[..],
+        b"\x82\x01\xff",
+        b"\xa1\x01\xff",
+        b"\xc1\xff",
+        b"\xbf\x01\xff\xff",
+    ] {
+        assert!(
+            parse_bytes_with(bytes, &STOP).is_err(),
+            "{}",
+            hex::encode(bytes)
+        );
+        assert!(
+            parse_bytes_partial_with(bytes, &STOP).is_err(),
+            "{}",
+            hex::encode(bytes)
+        );
+    }
+}
+
+#[test]
+fn sequences() {
+    let bytes = b"\x01\x82\x02\x03\x9f\xff\xff\x04";
+    assert_eq!(
+        sequence(bytes, &STOP).unwrap(),
+        (
+            vec!["1".to_owned(), "[2,3]".to_owned(), "[_]".to_owned()],
+            7
+        )
+    );
+    assert!(sequence(bytes, &ParseOptions::default()).is_err());
+
+    assert_eq!(
+        sequence(b"\x01\x02", &STOP).unwrap(),
+        (vec!["1".to_owned(), "2".to_owned()], 2)
+    );
+    assert_eq!(sequence(b"\xff", &STOP).unwrap(), (vec![], 1));
+}
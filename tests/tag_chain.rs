@@ -0,0 +1,144 @@
+use cbor_diag::{parse_bytes, parse_diag, DataItem, IntegerWidth, Tag};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn three_levels() {
+    // 55799(24(37(h'01')))
+    let item = parse_bytes(hex::decode("d9d9f7d818d8254101").unwrap()).unwrap();
+    let tags = [
+        (Tag::SELF_DESCRIBED, IntegerWidth::Sixteen),
+        (Tag::ENCODED_CBOR, IntegerWidth::Eight),
+        (Tag::UUID, IntegerWidth::Eight),
+    ];
+
+    let (chain, value) = item.tag_chain();
+    assert_eq!(chain, tags);
+    assert_eq!(value, &parse_bytes([0x41, 0x01]).unwrap());
+
+    let rebuilt = DataItem::retag(&tags, value.clone());
+    assert_eq!(rebuilt, item);
+    assert_eq!(hex::encode(rebuilt.to_bytes()), "d9d9f7d818d8254101");
+}
+
+#[test]
+fn untagged() {
+    let item = parse_diag("[1(2)]").unwrap();
+    let (chain, value) = item.tag_chain();
+    assert_eq!(chain, []);
+    assert_eq!(value, &item);
+    assert_eq!(DataItem::retag(&[], item.clone()), item);
+}
+
+#[test]
+fn only_outer_tags() {
+    let item = parse_diag("1(2([3(4)]))").unwrap();
+    let (chain, value) = item.tag_chain();
+    assert_eq!(
+        chain,
+        [(Tag(1), IntegerWidth::Zero), (Tag(2), IntegerWidth::Zero)]
+    );
+    assert_eq!(value, &parse_diag("[3(4)]").unwrap());
+}
+
+#[test]
+fn nested_tag_layout() {
+    let value = parse_diag(r#"["abcdefghij", "klmnopqrst", "uvwxyz"]"#).unwrap();
+    assert_eq!(
+        value.to_diag_pretty(),
+        r#"["abcdefghij", "klmnopqrst", "uvwxyz"]"#
+    );
+
+    // The tags share the line, so push it past the limit
+    let tagged = DataItem::retag(
+        &[
+            (Tag::SELF_DESCRIBED, IntegerWidth::Sixteen),
+            (Tag(1_000_000), IntegerWidth::ThirtyTwo),
+            (Tag(1_000_001), IntegerWidth::ThirtyTwo),
+        ],
+        value,
+    );
+    assert_eq!(
+        tagged.to_diag_pretty(),
+        indoc!(
+            r#"
+            55799_1(1000000_2(1000001_2([
+                "abcdefghij",
+                "klmnopqrst",
+                "uvwxyz",
+            ])))"#
+        )
+    );
+
+    // But don't affect the layout of later siblings
+    let tagged = parse_diag(
+        r#"[1000000(22(1000002([1]))), ["abcdefghijkl", "mnopqrstuvwx", "yzabcdefgh"]]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        tagged.to_diag_pretty(),
+        indoc!(
+            r#"
+            [
+                1000000(22(1000002([1]))),
+                ["abcdefghijkl", "mnopqrstuvwx", "yzabcdefgh"],
+            ]"#
+        )
+    );
+}
+
+#[test]
+fn tagged_large_containers() {
+    // The closing parens follow the container's closing delimiter, with its
+    // content indented one level from the line the tags start on
+    let numbers: Vec<String> = (1..=30).map(|n| n.to_string()).collect();
+    let value = parse_diag(format!(
+        r#"55799(18([h'a10126', {{4: h'{}', 1: [1, 2]}}, h'01', 1({{"a": [1, 2, {{"b": 3}}]}}), [{}]]))"#,
+        "31".repeat(32),
+        numbers.join(", ")
+    ))
+    .unwrap();
+    assert_eq!(
+        value.to_diag_pretty(),
+        indoc!(
+            r#"
+            55799(18([
+                h'a10126',
+                {
+                    4: h'3131313131313131313131313131313131313131313131313131313131313131',
+                    1: [1, 2],
+                },
+                h'01',
+                1({"a": [1, 2, {"b": 3}]}),
+                [
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+                    26, 27, 28, 29, 30,
+                ],
+            ]))"#
+        )
+    );
+
+    // Nested within other containers the alignment is the same at each level
+    let value = parse_diag(format!(
+        r#"{{"key": 55799(18([{}])), 24(1({{_ "x": [_ 1, 2]}})): 1000(1001([{}]))}}"#,
+        numbers.join(", "),
+        numbers.join(", ")
+    ))
+    .unwrap();
+    assert_eq!(
+        value.to_diag_pretty(),
+        indoc!(
+            r#"
+            {
+                "key": 55799(18([
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+                    26, 27, 28, 29, 30,
+                ])),
+                24(1({_ "x": [_ 1, 2]})): 1000(1001([
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+                    26, 27, 28, 29, 30,
+                ])),
+            }"#
+        )
+    );
+}
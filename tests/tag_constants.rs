@@ -0,0 +1,80 @@
+use cbor_diag::{parse_diag, DataItem, Tag};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn iana_registry_values() {
+    // https://www.iana.org/assignments/cbor-tags/cbor-tags.xhtml
+    let registry = [
+        (Tag::DATETIME, 0),
+        (Tag::DATETIME_STRING, 0),
+        (Tag::EPOCH_DATETIME, 1),
+        (Tag::POSITIVE_BIGNUM, 2),
+        (Tag::NEGATIVE_BIGNUM, 3),
+        (Tag::DECIMAL_FRACTION, 4),
+        (Tag::BIGFLOAT, 5),
+        (Tag::PACKED_REFERENCE, 6),
+        (Tag::COSE_ENCRYPT0, 16),
+        (Tag::COSE_MAC0, 17),
+        (Tag::COSE_SIGN1, 18),
+        (Tag::ENCODED_BASE64URL, 21),
+        (Tag::ENCODED_BASE64, 22),
+        (Tag::ENCODED_BASE16, 23),
+        (Tag::ENCODED_CBOR, 24),
+        (Tag::STRING_REFERENCE, 25),
+        (Tag::SHAREABLE, 28),
+        (Tag::SHARED_REFERENCE, 29),
+        (Tag::RATIONAL, 30),
+        (Tag::URI, 32),
+        (Tag::BASE64URL, 33),
+        (Tag::BASE64, 34),
+        (Tag::REGEX, 35),
+        (Tag::MIME, 36),
+        (Tag::UUID, 37),
+        (Tag::LANGUAGE_TAGGED_STRING, 38),
+        (Tag::IPV4, 52),
+        (Tag::IPV6, 54),
+        (Tag::CWT, 61),
+        (Tag::COSE_ENCRYPT, 96),
+        (Tag::COSE_MAC, 97),
+        (Tag::COSE_SIGN, 98),
+        (Tag::EPOCH_DATE, 100),
+        (Tag::PACKED_TABLE_SETUP, 113),
+        (Tag::STRING_REFERENCE_NAMESPACE, 256),
+        (Tag::SET, 258),
+        (Tag::NETWORK_ADDRESS, 260),
+        (Tag::EXTENDED_TIME, 1001),
+        (Tag::DURATION, 1002),
+        (Tag::PERIOD, 1003),
+        (Tag::DATE, 1004),
+        (Tag::PACKED_SPLIT_TABLE_SETUP, 1113),
+        (Tag::SELF_DESCRIBE_CBOR, 55799),
+        (Tag::SELF_DESCRIBED, 55799),
+        (Tag::SELF_DESCRIBE_CBOR_SEQUENCE, 55800),
+    ];
+
+    for &(tag, value) in &registry {
+        assert_eq!(tag, Tag(value));
+        assert_eq!(u64::from(tag), value);
+    }
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(Tag::from(24), Tag::ENCODED_CBOR);
+    assert_eq!(u64::from(Tag::URI), 32);
+
+    let tag: Tag = 55799.into();
+    assert_eq!(tag, Tag::SELF_DESCRIBED);
+}
+
+#[test]
+fn compare_with_u64() {
+    assert!(Tag::UUID == 37);
+    assert!(37 == Tag::UUID);
+    assert!(Tag::UUID != 38);
+
+    match parse_diag("1(0)").unwrap() {
+        DataItem::Tag { tag, .. } if tag == 1 => {}
+        item => panic!("unexpected item {:?}", item),
+    }
+}
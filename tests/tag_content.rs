@@ -0,0 +1,174 @@
+use cbor_diag::{
+    parse_bytes_partial_with, parse_bytes_report, parse_bytes_with, parse_diag_report,
+    parse_diag_with, ParseOptions, ParseWarning, WarningKind,
+};
+
+const STRICT: ParseOptions = ParseOptions {
+    allow_invalid_utf8: false,
+    app_literal_handler: None,
+    allow_unknown_app_literals: false,
+    max_allocation: None,
+    max_chunks: 1_000_000,
+    max_items: 1_000_000,
+    allow_hex_arrays: false,
+    allow_lossy_float_narrowing: false,
+    strict_tag_content: true,
+    stop_at_stray_break: false,
+};
+
+fn warnings(text: &str) -> Vec<(usize, String)> {
+    let report = parse_diag_report(text, &ParseOptions::default()).unwrap();
+    let warnings = report
+        .warnings
+        .iter()
+        .map(
+            |ParseWarning {
+                 offset, message, ..
+             }| (*offset, message.clone()),
+        )
+        .collect();
+    assert!(parse_diag_with(text, &STRICT).is_err(), "{}", text);
+    warnings
+}
+
+#[test]
+fn valid() {
+    for text in &[
+        r#"0("2013-03-21T20:04:00Z")"#,
+        r#"0((_ "2013-03-21", "T20:04:00Z"))"#,
+        "1(1363896240)",
+        "1(-1)",
+        "1(1363896240.5)",
+        "2(h'010000000000000000')",
+        "3(h'010000000000000000')",
+        "4([-2, 27315])",
+        "4([-2, 2(h'010000000000000000')])",
+        "5([-1, 3])",
+        "21(h'00')",
+        "22([1, 2])",
+        "23({1: 2})",
+        "24(h'6449455446')",
+        r#"32("http://www.example.com")"#,
+        r#"33("aGk")"#,
+        r#"34("aGk=")"#,
+        r#"35("^a+$")"#,
+        r#"36("MIME-Version: 1.0\r\n")"#,
+        "1000(h'00')",
+    ] {
+        let report = parse_diag_report(text, &ParseOptions::default()).unwrap();
+        assert_eq!(report.warnings, [], "{}", text);
+        parse_diag_with(text, &STRICT).unwrap();
+    }
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(
+        warnings("0(5)"),
+        [(
+            0,
+            "tag 0 (date/time) expects a text string, found an unsigned integer".to_owned()
+        )],
+    );
+    assert_eq!(
+        warnings(r#"1("2013-03-21T20:04:00Z")"#),
+        [(
+            0,
+            "tag 1 (epoch date/time) expects an integer or float, found a text string".to_owned()
+        )],
+    );
+    assert_eq!(
+        warnings("3(-1)"),
+        [(
+            0,
+            "tag 3 (negative bignum) expects a byte string, found a negative integer".to_owned()
+        )],
+    );
+    assert_eq!(
+        warnings("4([1.5, 2])"),
+        [(
+            0,
+            "tag 4 (decimal fraction) expects an array of an integer exponent and an integer or \
+             bignum mantissa, found an array"
+                .to_owned()
+        )],
+    );
+    assert_eq!(
+        warnings("24([])"),
+        [(
+            0,
+            "tag 24 (encoded CBOR data item) expects a byte string, found an array".to_owned()
+        )],
+    );
+    assert_eq!(
+        warnings("36(true)"),
+        [(
+            0,
+            "tag 36 (MIME message) expects a text string, found a simple value".to_owned()
+        )],
+    );
+}
+
+#[test]
+fn positions() {
+    assert_eq!(
+        warnings(r#"{ "a": [1, /0(1)/ 33(2)], 0(3): 'x', [1, 2]: 35(h'') }"#),
+        [
+            (
+                18,
+                "tag 33 (base64url) expects a text string, found an unsigned integer".to_owned()
+            ),
+            (
+                26,
+                "tag 0 (date/time) expects a text string, found an unsigned integer".to_owned()
+            ),
+            (
+                45,
+                "tag 35 (regular expression) expects a text string, found a byte string".to_owned()
+            ),
+        ],
+    );
+    // Nested tags are checked separately
+    assert_eq!(
+        warnings("32(0(1))"),
+        [
+            (
+                0,
+                "tag 32 (URI) expects a text string, found a tag".to_owned()
+            ),
+            (
+                3,
+                "tag 0 (date/time) expects a text string, found an unsigned integer".to_owned()
+            ),
+        ],
+    );
+}
+
+#[test]
+fn bytes() {
+    // [1, {2: 32(3)}]
+    let bytes = b"\x82\x01\xa1\x02\xd8\x20\x03";
+    let report = parse_bytes_report(bytes, &ParseOptions::default()).unwrap();
+    assert_eq!(report.item.to_diag(), "[1,{2:32_0(3)}]");
+    assert_eq!(
+        report.warnings,
+        [ParseWarning {
+            kind: WarningKind::TagContent,
+            offset: 4,
+            path: vec![1, 0, 1],
+            message: "tag 32 (URI) expects a text string, found an unsigned integer".into(),
+        }],
+    );
+    parse_bytes_with(bytes, &ParseOptions::default()).unwrap();
+    assert!(parse_bytes_with(bytes, &STRICT)
+        .unwrap_err()
+        .to_string()
+        .ends_with(
+            "Invalid tag content at offset 4: tag 32 (URI) expects a text string, found an \
+             unsigned integer"
+        ));
+    assert!(parse_bytes_partial_with(bytes, &STRICT).is_err());
+    assert!(parse_bytes_partial_with(&bytes[..6], &STRICT)
+        .unwrap()
+        .is_none());
+}
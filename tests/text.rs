@@ -167,11 +167,47 @@ testcases! {
                 bitwidth: IntegerWidth::Eight,
             }),
             indoc!("
-                78 19                         # text(25)
-                   30313233343536373839616263 #   \"0123456789abc\"
-                   f09f87b3f09f87bf31323334   #   \"\u{1f1f3}\u{1f1ff}1234\"
+                78 19                                 # text(25)
+                   30313233343536373839616263f09f87b3 #   \"0123456789abc\u{1f1f3}\"
+                   f09f87bf31323334                   #   \"\u{1f1ff}1234\"
             ")
         }
+
+        emoji(hex2value, value2hex) {
+            DataItem::TextString(TextString {
+                data: "\u{1f600}\u{1f601}\u{1f602}\u{1f603}\u{1f604}\u{1f605}\u{1f606}".into(),
+                bitwidth: IntegerWidth::Eight,
+            }),
+            indoc!("
+                78 1c                               # text(28)
+                   f09f9880f09f9881f09f9882f09f9883 #   \"\u{1f600}\u{1f601}\u{1f602}\u{1f603}\"
+                   f09f9884f09f9885f09f9886         #   \"\u{1f604}\u{1f605}\u{1f606}\"
+            ")
+        }
+
+        three_byte_across_break(hex2value, value2hex) {
+            DataItem::TextString(TextString {
+                data: "\u{20ac}".repeat(9),
+                bitwidth: IntegerWidth::Eight,
+            }),
+            indoc!("
+                78 1b                                   # text(27)
+                   e282ace282ace282ace282ace282ace282ac #   \"\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{20ac}\"
+                   e282ace282ace282ac                   #   \"\u{20ac}\u{20ac}\u{20ac}\"
+            ")
+        }
+
+        control_and_quotes(hex2value, value2hex) {
+            DataItem::TextString(TextString {
+                data: "tab\there \"quoted\"\nline\u{7f}end".into(),
+                bitwidth: IntegerWidth::Eight,
+            }),
+            indoc!(r#"
+                78 1a                               # text(26)
+                   7461620968657265202271756f746564 #   "tab\there \"quoted"
+                   220a6c696e657f656e64             #   "\"\nline\u{7f}end"
+            "#)
+        }
     }
 
     mod u16 {
@@ -284,11 +320,11 @@ testcases! {
 
     mod indefinite {
         mod diag {
-            empty(value2diag) {
+            empty(diag2value, value2diag) {
                 DataItem::IndefiniteTextString(vec![]),
                 {
-                    "(_)",
-                    "(_ )",
+                    r#"""_"#,
+                    r#"""_"#,
                 }
             }
 
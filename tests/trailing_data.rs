@@ -0,0 +1,48 @@
+use cbor_diag::{parse_bytes, parse_hex, DataItem, Error, IntegerWidth};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn trailing_bytes() {
+    match parse_bytes(b"\x82\x01\x02\x1a\x00") {
+        Err(Error::TrailingData {
+            parsed,
+            offset,
+            remaining,
+        }) => {
+            assert_eq!(parsed.to_diag(), "[1,2]");
+            assert_eq!(offset, 3);
+            assert_eq!(remaining, 2);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn trailing_hex() {
+    match parse_hex("01 02") {
+        Err(Error::TrailingData {
+            parsed,
+            offset,
+            remaining,
+        }) => {
+            assert_eq!(
+                parsed,
+                DataItem::Integer {
+                    value: 1,
+                    bitwidth: IntegerWidth::Zero
+                }
+            );
+            assert_eq!(offset, 1);
+            assert_eq!(remaining, 1);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(
+        parse_bytes(b"\x01\x02\x03").unwrap_err().to_string(),
+        "parsed 1 item (1 bytes), 2 trailing bytes"
+    );
+}
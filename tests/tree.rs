@@ -0,0 +1,100 @@
+use cbor_diag::{parse_diag, parse_hex, DataItem, TreeOptions};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn scalars() {
+    let cases = [
+        ("0", "unsigned 0, 1 byte"),
+        ("1000", "unsigned 1000, 3 bytes"),
+        ("-1_3", "negative -1_3, 9 bytes"),
+        ("1.5_1", "float 1.5_1, 3 bytes"),
+        ("true", "simple true, 1 byte"),
+        ("simple(99)", "simple simple(99), 2 bytes"),
+        (r#""hello""#, r#"text(5) "hello", 6 bytes"#),
+        ("h''", "bytes(0) h'', 1 byte"),
+    ];
+    for &(diag, tree) in &cases {
+        assert_eq!(parse_diag(diag).unwrap().to_tree(), tree, "{}", diag);
+    }
+
+    let item = DataItem::AppLiteral {
+        prefix: "xy".into(),
+        content: "z".into(),
+    };
+    assert_eq!(
+        item.to_tree(),
+        "application literal xy'z', no binary encoding"
+    );
+}
+
+#[test]
+fn nested() {
+    let item = parse_diag(r#"{_ 1: [2, 55799([])], "b": (_ h'01', h'0203')}"#).unwrap();
+    assert_eq!(
+        item.to_tree(),
+        indoc!(
+            r#"
+            map(*), 18 bytes
+            ├── key: unsigned 1, 1 byte
+            ├── value: array(2), 6 bytes
+            │   ├── unsigned 2, 1 byte
+            │   └── tag(55799) self describe cbor, 4 bytes
+            │       └── array(0), 1 byte
+            ├── key: text(1) "b", 2 bytes
+            └── value: bytes(*), 7 bytes
+                ├── bytes(1) h'01', 2 bytes
+                └── bytes(2) h'0203', 3 bytes"#
+        )
+    );
+}
+
+#[test]
+fn sizes_follow_encoding() {
+    let item = parse_hex("9a00000001 1b0000000000000001").unwrap();
+    assert_eq!(
+        item.to_tree(),
+        indoc!(
+            "
+            array(1), 14 bytes
+            └── unsigned 1_3, 9 bytes"
+        )
+    );
+}
+
+#[test]
+fn truncation() {
+    let item = parse_diag(r#"["ab€defghij", h'00010203']"#).unwrap();
+    let options = TreeOptions { max_value_width: 5 };
+    assert_eq!(
+        item.to_tree_with(&options),
+        indoc!(
+            r#"
+            array(2), 19 bytes
+            ├── text(12) "ab€d…, 13 bytes
+            └── bytes(4) h'000…, 5 bytes"#
+        )
+    );
+    let options = TreeOptions { max_value_width: 0 };
+    assert!(item.to_tree_with(&options).contains(r#""ab€defghij""#));
+    assert!(item.to_tree().contains(r#""ab€defghij""#));
+}
+
+#[test]
+fn dot() {
+    let item = parse_diag(r#"{"a\"": 1}"#).unwrap();
+    assert_eq!(
+        item.to_dot(),
+        indoc!(
+            r#"
+            digraph cbor {
+                node [shape=box];
+                n0 [label="map(1)\n5 bytes"];
+                n1 [label="text(2) \"a\\\"\"\n3 bytes"];
+                n0 -> n1 [label="key"];
+                n2 [label="unsigned 1\n1 byte"];
+                n0 -> n2 [label="value"];
+            }"#
+        )
+    );
+}
@@ -125,6 +125,15 @@ macro_rules! testcases {
             ::pretty_assertions::assert_eq!($crate::utils::DisplayDebug(hex), $crate::utils::DisplayDebug($hex));
         }
 
+        #[test]
+        fn stream2hex() {
+            let input = ::std::io::Cursor::new($value.to_bytes());
+            let mut hex = Vec::new();
+            cbor_diag::annotate_stream(input, &mut hex, &Default::default()).unwrap();
+            let hex = String::from_utf8(hex).unwrap();
+            ::pretty_assertions::assert_eq!($crate::utils::DisplayDebug(hex), $crate::utils::DisplayDebug($hex));
+        }
+
         testcases! {
             @testcase $name($($rest),*) { $value, { $compact $(, $pretty)? }, { $hex } }
         }
@@ -0,0 +1,197 @@
+use cbor_diag::{
+    ByteString, DataItem, EncodeOptions, EncodingError, IntegerWidth, Simple, Tag, TextString,
+};
+use pretty_assertions::assert_eq;
+
+const WIDEN: EncodeOptions = EncodeOptions {
+    widen_on_overflow: true,
+    force_indefinite_containers: false,
+    chunk_strings: None,
+};
+
+fn integer(value: u64, bitwidth: IntegerWidth) -> DataItem {
+    DataItem::Integer { value, bitwidth }
+}
+
+fn error(path: &[usize], message: &str) -> EncodingError {
+    EncodingError {
+        path: path.to_vec(),
+        message: message.into(),
+    }
+}
+
+#[test]
+fn integers() {
+    for &(value, bitwidth, message, widened) in &[
+        (24, IntegerWidth::Zero, "the initial byte", "1818"),
+        (256, IntegerWidth::Eight, "8 bits", "190100"),
+        (65536, IntegerWidth::Sixteen, "16 bits", "1a00010000"),
+        (
+            1 << 32,
+            IntegerWidth::ThirtyTwo,
+            "32 bits",
+            "1b0000000100000000",
+        ),
+    ] {
+        let item = integer(value, bitwidth);
+        let message = format!("unsigned integer {} does not fit in {}", value, message);
+        assert_eq!(item.validate_encoding(), Err(vec![error(&[], &message)]));
+        assert_eq!(
+            item.try_to_bytes().unwrap_err().to_string(),
+            format!("TODO cbor-diag::Error: {}", message)
+        );
+        assert_eq!(
+            hex::encode(item.try_to_bytes_with(&WIDEN).unwrap()),
+            widened
+        );
+        assert_eq!(hex::encode(item.to_bytes()), widened);
+    }
+}
+
+#[test]
+fn integers_that_fit() {
+    for &(value, bitwidth, encoded) in &[
+        (23, IntegerWidth::Zero, "17"),
+        (255, IntegerWidth::Eight, "18ff"),
+        (0, IntegerWidth::Sixteen, "190000"),
+        (
+            u64::max_value(),
+            IntegerWidth::SixtyFour,
+            "1bffffffffffffffff",
+        ),
+        (
+            u64::max_value(),
+            IntegerWidth::Unknown,
+            "1bffffffffffffffff",
+        ),
+    ] {
+        let item = integer(value, bitwidth);
+        assert_eq!(item.validate_encoding(), Ok(()));
+        assert_eq!(hex::encode(item.try_to_bytes().unwrap()), encoded);
+    }
+}
+
+#[test]
+fn negative() {
+    let item = DataItem::Negative {
+        value: 300,
+        bitwidth: IntegerWidth::Eight,
+    };
+    assert_eq!(
+        item.validate_encoding(),
+        Err(vec![error(
+            &[],
+            "negative integer -301 does not fit in 8 bits"
+        )])
+    );
+    assert_eq!(
+        hex::encode(item.try_to_bytes_with(&WIDEN).unwrap()),
+        "39012c"
+    );
+}
+
+#[test]
+fn lengths() {
+    let item = DataItem::Array {
+        data: vec![
+            DataItem::ByteString(ByteString {
+                data: vec![0; 24],
+                bitwidth: IntegerWidth::Zero,
+                encoding: None,
+            }),
+            DataItem::TextString(TextString {
+                data: "a".repeat(256),
+                bitwidth: IntegerWidth::Eight,
+            }),
+            DataItem::IndefiniteTextString(vec![
+                TextString {
+                    data: "ok".into(),
+                    bitwidth: IntegerWidth::Zero,
+                },
+                TextString {
+                    data: "a".repeat(30),
+                    bitwidth: IntegerWidth::Zero,
+                },
+            ]),
+            DataItem::Map {
+                data: vec![(
+                    integer(1, IntegerWidth::Zero),
+                    integer(99, IntegerWidth::Zero),
+                )],
+                bitwidth: Some(IntegerWidth::Zero),
+            },
+            DataItem::Array {
+                data: vec![integer(0, IntegerWidth::Zero); 24],
+                bitwidth: Some(IntegerWidth::Zero),
+            },
+        ],
+        bitwidth: Some(IntegerWidth::Zero),
+    };
+    assert_eq!(
+        item.validate_encoding(),
+        Err(vec![
+            error(&[0], "length 24 does not fit in the initial byte"),
+            error(&[1], "length 256 does not fit in 8 bits"),
+            error(&[2, 1], "length 30 does not fit in the initial byte"),
+            error(
+                &[3, 0, 1],
+                "unsigned integer 99 does not fit in the initial byte"
+            ),
+            error(&[4], "length 24 does not fit in the initial byte"),
+        ])
+    );
+    assert_eq!(
+        item.try_to_bytes().unwrap_err().to_string(),
+        "TODO cbor-diag::Error: at path [0]: length 24 does not fit in the initial byte"
+    );
+
+    let widened = item.try_to_bytes_with(&WIDEN).unwrap();
+    assert_eq!(hex::encode(&widened[..3]), "855818");
+    assert_eq!(
+        cbor_diag::parse_bytes(&widened)
+            .unwrap()
+            .validate_encoding(),
+        Ok(())
+    );
+}
+
+#[test]
+fn tags() {
+    let item = DataItem::Tag {
+        tag: Tag(1000),
+        bitwidth: IntegerWidth::Eight,
+        value: Box::new(DataItem::Simple(Simple(24))),
+    };
+    assert_eq!(
+        item.validate_encoding(),
+        Err(vec![
+            error(&[], "tag 1000 does not fit in 8 bits"),
+            error(&[0], "simple(24) is reserved and cannot be encoded"),
+        ])
+    );
+    // Widening doesn't help reserved simple values
+    assert!(item.try_to_bytes_with(&WIDEN).is_err());
+}
+
+#[test]
+fn infallible() {
+    let item = DataItem::Array {
+        data: vec![
+            integer(300, IntegerWidth::Eight),
+            DataItem::Simple(Simple(24)),
+            DataItem::AppLiteral {
+                prefix: "dt".into(),
+                content: "x".into(),
+            },
+        ],
+        bitwidth: Some(IntegerWidth::Zero),
+    };
+    assert!(item.try_to_bytes().is_err());
+    assert!(item.try_to_bytes_with(&WIDEN).is_err());
+    let encoded = "8319012cf818d903e7826264746178";
+    assert_eq!(hex::encode(item.to_bytes()), encoded);
+    assert_eq!(
+        hex::encode(item.to_bytes_with(&EncodeOptions::default())),
+        encoded
+    );
+}
@@ -0,0 +1,165 @@
+use cbor_diag::{
+    parse_bytes_lenient, parse_bytes_report, parse_diag_report, parse_hex_report, ParseOptions,
+    WarningKind,
+};
+use pretty_assertions::assert_eq;
+
+fn summary(warnings: &[cbor_diag::ParseWarning]) -> Vec<(WarningKind, usize, Vec<usize>, &str)> {
+    warnings
+        .iter()
+        .map(|warning| {
+            (
+                warning.kind,
+                warning.offset,
+                warning.path.clone(),
+                &warning.message[..],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn duplicate_keys() {
+    let text = r#"[{1: 2, "a": 3, 1_1: 4}, 6({h'00': [{}], h'00': 5, h'00': 6})]"#;
+    let report = parse_diag_report(text, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        summary(&report.warnings),
+        [
+            (
+                WarningKind::DuplicateKey,
+                16,
+                vec![0, 2, 0],
+                "duplicate map key 1_1"
+            ),
+            (
+                WarningKind::DuplicateKey,
+                41,
+                vec![1, 0, 1, 0],
+                "duplicate map key h'00'"
+            ),
+            (
+                WarningKind::DuplicateKey,
+                51,
+                vec![1, 0, 2, 0],
+                "duplicate map key h'00'"
+            ),
+        ]
+    );
+
+    let bytes = report.item.to_bytes();
+    let report = parse_bytes_report(&bytes, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        summary(&report.warnings),
+        [
+            (
+                WarningKind::DuplicateKey,
+                7,
+                vec![0, 2, 0],
+                "duplicate map key 1_1"
+            ),
+            (
+                WarningKind::DuplicateKey,
+                17,
+                vec![1, 0, 1, 0],
+                "duplicate map key h'00'"
+            ),
+            (
+                WarningKind::DuplicateKey,
+                20,
+                vec![1, 0, 2, 0],
+                "duplicate map key h'00'"
+            ),
+        ]
+    );
+    assert_eq!(
+        parse_hex_report(hex::encode(&bytes), &ParseOptions::default())
+            .unwrap()
+            .warnings,
+        report.warnings
+    );
+}
+
+#[test]
+fn distinct_keys() {
+    for text in &[
+        "{1: 1, -1: 2, 1.0: 3, h'01': 4, \"1\": 5, 1(1): 6}",
+        "[{1: 1}, {1: 1}]",
+        "{[1]: 1, [1, 1]: 2}",
+    ] {
+        let report = parse_diag_report(text, &ParseOptions::default()).unwrap();
+        assert_eq!(report.warnings, [], "{}", text);
+    }
+}
+
+#[test]
+fn ordered_by_offset() {
+    let options = ParseOptions {
+        allow_lossy_float_narrowing: true,
+        ..ParseOptions::default()
+    };
+    let text = "{1: 0(2), 1: [0.1_1]}";
+    let report = parse_diag_report(text, &options).unwrap();
+    assert_eq!(
+        summary(&report.warnings),
+        [
+            (
+                WarningKind::TagContent,
+                4,
+                vec![0, 1],
+                "tag 0 (date/time) expects a text string, found an unsigned integer"
+            ),
+            (
+                WarningKind::DuplicateKey,
+                10,
+                vec![1, 0],
+                "duplicate map key 1"
+            ),
+            (
+                WarningKind::LossyFloat,
+                14,
+                vec![1, 1, 0],
+                "0.1 is not exactly representable as a 16-bit float; nearest is 0.0999755859375"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn lenient() {
+    // ["a", "\xff", 1, 2, 3] truncated
+    let (_, warnings) = parse_bytes_lenient(b"\x85\x61\x61\x61\xff\x01");
+    assert_eq!(
+        summary(&warnings),
+        [
+            (
+                WarningKind::InvalidUtf8,
+                3,
+                vec![],
+                "text string contains invalid UTF-8"
+            ),
+            (
+                WarningKind::Malformed,
+                6,
+                vec![],
+                "expected a data item, only 0 bytes present"
+            ),
+            (
+                WarningKind::Malformed,
+                6,
+                vec![],
+                "array declared 5 items, only 3 present"
+            ),
+        ]
+    );
+
+    let (_, warnings) = parse_bytes_lenient(b"\x01\x02");
+    assert_eq!(
+        summary(&warnings),
+        [(
+            WarningKind::TrailingData,
+            1,
+            vec![],
+            "1 bytes of trailing data after the data item"
+        )]
+    );
+}
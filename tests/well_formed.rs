@@ -0,0 +1,73 @@
+use cbor_diag::{check_well_formed, check_well_formed_seq, parse_diag, WellFormedness};
+use pretty_assertions::assert_eq;
+
+fn error(result: cbor_diag::Result<WellFormedness>) -> String {
+    result.unwrap_err().to_string()
+}
+
+#[test]
+fn single() {
+    let bytes = parse_diag(r#"{1: [_ h'01', (_ "a", "b")], 2: 3(-1)}"#)
+        .unwrap()
+        .to_bytes();
+    assert_eq!(
+        check_well_formed(&bytes).unwrap(),
+        WellFormedness {
+            items: 1,
+            len: bytes.len()
+        }
+    );
+}
+
+#[test]
+fn not_checked_as_valid() {
+    // Invalid UTF-8 and tag content are still well-formed
+    assert_eq!(
+        check_well_formed(b"\xc0\x62\xc3\x28").unwrap(),
+        WellFormedness { items: 1, len: 4 }
+    );
+}
+
+#[test]
+fn malformed() {
+    assert_eq!(
+        error(check_well_formed(b"")),
+        "TODO cbor-diag::Error: Parsing error at offset 0: expected a data item, only 0 bytes present"
+    );
+    assert_eq!(
+        error(check_well_formed(b"\x82\x01")),
+        "TODO cbor-diag::Error: Parsing error at offset 2: expected a data item, only 0 bytes present"
+    );
+    assert_eq!(
+        error(check_well_formed(b"\x5f\x01\xff")),
+        "TODO cbor-diag::Error: Parsing error at offset 1: expected a definite length byte string chunk or break"
+    );
+    assert_eq!(
+        error(check_well_formed(b"\x1c")),
+        "TODO cbor-diag::Error: Parsing error at offset 0: reserved additional information value 28"
+    );
+    assert_eq!(
+        error(check_well_formed(b"\x82\x01\x02\x03\x04")),
+        "TODO cbor-diag::Error: parsed 1 item (3 bytes), 2 trailing bytes"
+    );
+}
+
+#[test]
+fn sequence() {
+    assert_eq!(
+        check_well_formed_seq(b"\x01\x82\x02\x03\x61a").unwrap(),
+        WellFormedness { items: 3, len: 6 }
+    );
+    assert_eq!(
+        check_well_formed_seq(b"").unwrap(),
+        WellFormedness { items: 0, len: 0 }
+    );
+    assert_eq!(
+        error(check_well_formed_seq(b"\x01\x82\x02")),
+        "TODO cbor-diag::Error: Parsing error at offset 3: expected a data item, only 0 bytes present"
+    );
+    assert_eq!(
+        error(check_well_formed_seq(b"\x01\xff")),
+        "TODO cbor-diag::Error: Parsing error at offset 1: unexpected break"
+    );
+}
@@ -0,0 +1,129 @@
+use cbor_diag::{parse_bytes, DataItem, FloatWidth, IntegerWidth};
+use pretty_assertions::assert_eq;
+
+const INTEGER_WIDTHS: &[IntegerWidth] = &[
+    IntegerWidth::Unknown,
+    IntegerWidth::Zero,
+    IntegerWidth::Eight,
+    IntegerWidth::Sixteen,
+    IntegerWidth::ThirtyTwo,
+    IntegerWidth::SixtyFour,
+];
+
+const FLOAT_WIDTHS: &[FloatWidth] = &[
+    FloatWidth::Unknown,
+    FloatWidth::Sixteen,
+    FloatWidth::ThirtyTwo,
+    FloatWidth::SixtyFour,
+];
+
+#[test]
+fn integer_minimal_for() {
+    let u8_max = u64::from(u8::MAX);
+    let u16_max = u64::from(u16::MAX);
+    let u32_max = u64::from(u32::MAX);
+    for &(value, width) in &[
+        (0, IntegerWidth::Zero),
+        (23, IntegerWidth::Zero),
+        (24, IntegerWidth::Eight),
+        (u8_max - 1, IntegerWidth::Eight),
+        (u8_max, IntegerWidth::Eight),
+        (u8_max + 1, IntegerWidth::Sixteen),
+        (u16_max - 1, IntegerWidth::Sixteen),
+        (u16_max, IntegerWidth::Sixteen),
+        (u16_max + 1, IntegerWidth::ThirtyTwo),
+        (u32_max - 1, IntegerWidth::ThirtyTwo),
+        (u32_max, IntegerWidth::ThirtyTwo),
+        (u32_max + 1, IntegerWidth::SixtyFour),
+        (u64::MAX - 1, IntegerWidth::SixtyFour),
+        (u64::MAX, IntegerWidth::SixtyFour),
+    ] {
+        assert_eq!(IntegerWidth::minimal_for(value), width, "{}", value);
+
+        // Matches the width used for the preferred encoding
+        let item = DataItem::Integer {
+            value,
+            bitwidth: IntegerWidth::Unknown,
+        };
+        let bytes = item.to_bytes();
+        assert_eq!(bytes.len(), 1 + width.byte_len(), "{}", value);
+        match parse_bytes(&bytes).unwrap() {
+            DataItem::Integer { bitwidth, .. } => assert_eq!(bitwidth, width, "{}", value),
+            other => panic!("expected an integer, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn integer_byte_len_and_indicator() {
+    let expected = [
+        (0, None),
+        (0, None),
+        (1, Some(0)),
+        (2, Some(1)),
+        (4, Some(2)),
+        (8, Some(3)),
+    ];
+    for (&width, &(len, indicator)) in INTEGER_WIDTHS.iter().zip(&expected) {
+        assert_eq!(width.byte_len(), len, "{:?}", width);
+        assert_eq!(width.indicator(), indicator, "{:?}", width);
+
+        // The length of a header with this width, and its encoding indicator
+        let item = DataItem::Integer {
+            value: 0,
+            bitwidth: width,
+        };
+        assert_eq!(item.to_bytes().len(), 1 + len, "{:?}", width);
+        let diag = item.to_diag();
+        match indicator {
+            Some(indicator) => assert_eq!(diag, format!("0_{}", indicator)),
+            None => assert_eq!(diag, "0"),
+        }
+    }
+}
+
+#[test]
+fn float_minimal_for() {
+    for &(value, width) in &[
+        (0.0, FloatWidth::Sixteen),
+        (-0.0, FloatWidth::Sixteen),
+        (1.5, FloatWidth::Sixteen),
+        (65504.0, FloatWidth::Sixteen),
+        (65505.0, FloatWidth::ThirtyTwo),
+        (f64::INFINITY, FloatWidth::Sixteen),
+        (f64::NAN, FloatWidth::Sixteen),
+        (f64::from(f32::MAX), FloatWidth::ThirtyTwo),
+        (f64::from(f32::MIN_POSITIVE), FloatWidth::ThirtyTwo),
+        (f64::from(f32::MAX) * 2.0, FloatWidth::SixtyFour),
+        (0.1, FloatWidth::SixtyFour),
+        (f64::MAX, FloatWidth::SixtyFour),
+    ] {
+        assert_eq!(FloatWidth::minimal_for(value), width, "{}", value);
+    }
+
+    // NaN payloads are kept, so only fit where they can be represented
+    let payload = f64::from_bits(0x7ff8_0000_2000_0000);
+    assert_eq!(FloatWidth::minimal_for(payload), FloatWidth::ThirtyTwo);
+    let payload = f64::from_bits(0x7ff8_0000_0000_0001);
+    assert_eq!(FloatWidth::minimal_for(payload), FloatWidth::SixtyFour);
+}
+
+#[test]
+fn float_byte_len_and_indicator() {
+    let expected = [(8, None), (2, Some(1)), (4, Some(2)), (8, Some(3))];
+    for (&width, &(len, indicator)) in FLOAT_WIDTHS.iter().zip(&expected) {
+        assert_eq!(width.byte_len(), len, "{:?}", width);
+        assert_eq!(width.indicator(), indicator, "{:?}", width);
+
+        let item = DataItem::Float {
+            value: 1.0,
+            bitwidth: width,
+        };
+        assert_eq!(item.to_bytes().len(), 1 + len, "{:?}", width);
+        let diag = item.to_diag();
+        match indicator {
+            Some(indicator) => assert_eq!(diag, format!("1.0_{}", indicator)),
+            None => assert_eq!(diag, "1.0"),
+        }
+    }
+}